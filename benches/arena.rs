@@ -0,0 +1,100 @@
+//! Counts the allocations of evaluating a string-heavy expression for many rows, with and without an `EvalArena`.
+//!
+//! Run with `cargo bench --bench arena`.
+
+extern crate evalexpr;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use evalexpr::*;
+
+/// An allocator that counts the allocations made through it.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const EXPRESSION: &str = "label = last + \", \" + first; \
+                          (label + \" <\" + email + \">\", (city, country) + (label, email))";
+
+/// The amount of distinct rows, which are prepared upfront such that filling the contexts does not count.
+const DISTINCT_ROWS: usize = 16;
+
+const ROWS: usize = 1_000_000;
+
+/// Evaluates the expression for each row with the given state, and reports the allocations and time per row.
+fn report(
+    name: &str,
+    tree: &Node,
+    contexts: &mut [HashMapContext],
+    state: &EvalState,
+) -> Vec<Value> {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for row in 0..ROWS {
+        let context = &mut contexts[row % DISTINCT_ROWS];
+        tree.eval_with_context_mut_and_state(context, state)
+            .unwrap();
+    }
+    let duration = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{:<16} {:>6.2} allocations per row ({:.1} ns per row)",
+        name,
+        allocations as f64 / ROWS as f64,
+        duration.as_nanos() as f64 / ROWS as f64
+    );
+    contexts
+        .iter_mut()
+        .map(|context| {
+            tree.eval_with_context_mut_and_state(context, state)
+                .unwrap()
+        })
+        .collect()
+}
+
+fn main() {
+    let tree = build_operator_tree(EXPRESSION).unwrap();
+    let mut contexts: Vec<HashMapContext> = (0..DISTINCT_ROWS)
+        .map(|row| {
+            context_map! {
+                "first" => format!("First{}", row),
+                "last" => format!("Last{}", row),
+                "email" => format!("user{}@example.com", row),
+                "city" => format!("City{}", row % 5),
+                "country" => "Country",
+            }
+            .unwrap()
+        })
+        .collect();
+
+    let without_arena = report("without arena", &tree, &mut contexts, &EvalState::default());
+    let arena = EvalArena::new();
+    let with_arena = report(
+        "with arena",
+        &tree,
+        &mut contexts,
+        &EvalState::new(EvalConfig {
+            arena: Some(arena.clone()),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(without_arena, with_arena);
+    println!("{:?}", arena);
+}
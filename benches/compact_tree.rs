@@ -0,0 +1,107 @@
+//! Compares building and evaluating a batch of operator trees as `Node` and as `CompactTree`.
+//!
+//! Run with `cargo bench --bench compact_tree`.
+
+extern crate evalexpr;
+
+use std::time::{Duration, Instant};
+
+use evalexpr::*;
+
+/// The amount of distinct expressions in the batch.
+/// The batch is large enough that its trees do not fit into the CPU caches.
+const EXPRESSIONS: usize = 5_000;
+
+const ROUNDS: usize = 10;
+
+/// Returns the shortest time of several runs of `run`.
+fn measure<F: FnMut()>(mut run: F) -> Duration {
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..5 {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn report(name: &str, duration: Duration) {
+    let expressions = ROUNDS * EXPRESSIONS;
+    println!(
+        "{:<24} {:>14?} ({:.0} ns per expression)",
+        name,
+        duration,
+        duration.as_nanos() as f64 / expressions as f64
+    );
+}
+
+fn main() {
+    let context = context_map! {
+        "a" => 3,
+        "b" => 4.5,
+        "flag" => true,
+    }
+    .unwrap();
+
+    let expressions: Vec<String> = (0..EXPRESSIONS)
+        .map(|i| {
+            format!(
+                "((a + {0}) * (b - 1) + (a - 1) * (b + {0})) / ((a * a) + (b * b) + 1) \
+                 + ({0} - a) * ({0} + b) - (a * {0}) / (b + {0}) > {0} || flag && a < {0}",
+                i
+            )
+        })
+        .collect();
+    let nodes: Vec<Node> = expressions
+        .iter()
+        .map(|expression| build_operator_tree(expression).unwrap())
+        .collect();
+    let compact_trees: Vec<CompactTree> = nodes.iter().map(Node::compact).collect();
+    for (node, compact_tree) in nodes.iter().zip(&compact_trees) {
+        assert_eq!(
+            node.eval_with_context(&context),
+            compact_tree.eval_with_context(&context)
+        );
+    }
+
+    report(
+        "build Node",
+        measure(|| {
+            for _ in 0..ROUNDS {
+                for expression in &expressions {
+                    build_operator_tree(expression).unwrap();
+                }
+            }
+        }),
+    );
+    report(
+        "build CompactTree",
+        measure(|| {
+            for _ in 0..ROUNDS {
+                for expression in &expressions {
+                    build_compact_tree(expression).unwrap();
+                }
+            }
+        }),
+    );
+    report(
+        "evaluate Node",
+        measure(|| {
+            for _ in 0..ROUNDS {
+                for node in &nodes {
+                    node.eval_with_context(&context).unwrap();
+                }
+            }
+        }),
+    );
+    report(
+        "evaluate CompactTree",
+        measure(|| {
+            for _ in 0..ROUNDS {
+                for compact_tree in &compact_trees {
+                    compact_tree.eval_with_context(&context).unwrap();
+                }
+            }
+        }),
+    );
+}
@@ -0,0 +1,83 @@
+//! Measures the evaluation of expressions that consist mostly of operators, such that the dispatch of the operators dominates the time.
+//!
+//! Run with `cargo bench --bench operators`.
+
+extern crate evalexpr;
+
+use std::time::{Duration, Instant};
+
+use evalexpr::*;
+
+const ROUNDS: usize = 200_000;
+
+/// Returns the shortest time of several runs of `run`.
+fn measure<F: FnMut()>(mut run: F) -> Duration {
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..7 {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn report(name: &str, duration: Duration) {
+    println!(
+        "{:<32} {:>14?} ({:.1} ns per evaluation)",
+        name,
+        duration,
+        duration.as_nanos() as f64 / ROUNDS as f64
+    );
+}
+
+fn main() {
+    let context = context_map! {
+        "a" => 3,
+        "b" => 4.5,
+        "c" => -7,
+        "flag" => true,
+    }
+    .unwrap();
+
+    let expressions = [
+        (
+            "arithmetic",
+            "(a + 1) * (a - 2) + a % 2 - -a * 3 ^ 2 / (b + 1)",
+        ),
+        (
+            "comparisons",
+            "a < 4 && b >= 4.5 || a == c || !(c > 0) && a != 3",
+        ),
+        ("bitwise", "(a << 4 | c & 255) >> 2 bitxor a & 7 | a << 1"),
+        (
+            "conditional",
+            "flag ? (a > 1 ? a * 2 : a) : c; missing ?? a + 1",
+        ),
+    ];
+
+    for (name, expression) in &expressions {
+        let node = build_operator_tree(expression).unwrap();
+        let compact_tree = node.compact();
+        assert_eq!(
+            node.eval_with_context(&context),
+            compact_tree.eval_with_context(&context)
+        );
+
+        report(
+            &format!("{} (Node)", name),
+            measure(|| {
+                for _ in 0..ROUNDS {
+                    node.eval_with_context(&context).unwrap();
+                }
+            }),
+        );
+        report(
+            &format!("{} (CompactTree)", name),
+            measure(|| {
+                for _ in 0..ROUNDS {
+                    compact_tree.eval_with_context(&context).unwrap();
+                }
+            }),
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Counts the allocations of repeatedly overwriting the values of a `HashMapContext`.
+//!
+//! Run with `cargo bench --bench set_value`.
+
+extern crate evalexpr;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use evalexpr::*;
+
+/// An allocator that counts the allocations made through it.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const IDENTIFIERS: [&str; 4] = ["alpha", "beta", "gamma", "delta"];
+
+const ROUNDS: usize = 1_000_000;
+
+/// Runs `overwrite` for each round and identifier, and reports the allocations and time per call.
+fn report<F: FnMut(&mut HashMapContext, &str, IntType)>(name: &str, mut overwrite: F) {
+    let mut context = HashMapContext::new();
+    for identifier in IDENTIFIERS.iter() {
+        context.set_value(*identifier, 0).unwrap();
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for round in 0..ROUNDS {
+        for identifier in IDENTIFIERS.iter() {
+            overwrite(&mut context, identifier, round as IntType);
+        }
+    }
+    let duration = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    let calls = (ROUNDS * IDENTIFIERS.len()) as f64;
+    println!(
+        "{:<28} {:>5.2} allocations per call ({:.1} ns per call)",
+        name,
+        allocations as f64 / calls,
+        duration.as_nanos() as f64 / calls
+    );
+}
+
+fn main() {
+    report("Context::set_value", |context, identifier, value| {
+        Context::set_value(context, identifier.to_string(), Value::from(value)).unwrap();
+    });
+    report("HashMapContext::set_value", |context, identifier, value| {
+        context.set_value(identifier, value).unwrap();
+    });
+}
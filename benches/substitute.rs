@@ -0,0 +1,180 @@
+//! Compares instantiating variants of a large expression by building each variant from source and by substituting variables in clones of one base tree.
+//! Also compares evaluating the base tree with evaluating a variant, which shares most of its nodes with the base tree.
+//!
+//! Run with `cargo bench --bench substitute`.
+
+extern crate evalexpr;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use evalexpr::*;
+
+/// An allocator that counts the bytes allocated through it.
+struct CountingAllocator;
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The amount of variants of the base expression.
+const TENANTS: usize = 2_000;
+
+/// The amount of clauses of the base expression.
+const CLAUSES: usize = 200;
+
+const ROUNDS: usize = 2;
+
+/// Returns the shortest time of several runs of `run`, and the bytes allocated by the last run.
+fn measure<F: FnMut()>(mut run: F) -> (Duration, usize) {
+    let mut best = Duration::from_secs(u64::MAX);
+    let mut bytes = 0;
+    for _ in 0..5 {
+        let start_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+        bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - start_bytes;
+    }
+    (best, bytes)
+}
+
+fn report(name: &str, (duration, bytes): (Duration, usize), count: usize, unit: &str) {
+    println!(
+        "{:<32} {:>14?} ({:.0} ns and {} bytes per {})",
+        name,
+        duration,
+        duration.as_nanos() as f64 / count as f64,
+        bytes / count,
+        unit
+    );
+}
+
+fn main() {
+    let clauses: Vec<String> = (0..CLAUSES)
+        .map(|i| format!("(amount * {0} + fee_{1}) / (rate + {0}) > limit", i, i % 4))
+        .collect();
+    let base_expression = clauses.join(" || ");
+    let base = build_operator_tree(&base_expression).unwrap();
+
+    let tenant_values = |tenant: usize| {
+        vec![
+            ("limit", (tenant % 100).to_string()),
+            ("rate", format!("{}.5", tenant % 7)),
+        ]
+    };
+    let substitutions: Vec<HashMap<String, Node>> = (0..TENANTS)
+        .map(|tenant| {
+            tenant_values(tenant)
+                .into_iter()
+                .map(|(identifier, value)| {
+                    (identifier.to_string(), build_operator_tree(&value).unwrap())
+                })
+                .collect()
+        })
+        .collect();
+    let sources: Vec<String> = (0..TENANTS)
+        .map(|tenant| {
+            let mut source = base_expression.clone();
+            for (identifier, value) in tenant_values(tenant) {
+                source = source.replace(identifier, &format!("({})", value));
+            }
+            source
+        })
+        .collect();
+
+    let mut variants = Vec::with_capacity(TENANTS);
+    report(
+        "build variants from source",
+        measure(|| {
+            variants = sources
+                .iter()
+                .map(|source| build_operator_tree(source).unwrap())
+                .collect();
+        }),
+        TENANTS,
+        "variant",
+    );
+    let built_variants = std::mem::take(&mut variants);
+    report(
+        "substitute into clones",
+        measure(|| {
+            variants = substitutions
+                .iter()
+                .map(|substitutions| {
+                    let mut variant = base.clone();
+                    variant.substitute_variables(substitutions);
+                    variant
+                })
+                .collect();
+        }),
+        TENANTS,
+        "variant",
+    );
+
+    let context = context_map! {
+        "amount" => 3,
+        "fee_0" => 1,
+        "fee_1" => 2,
+        "fee_2" => 3,
+        "fee_3" => 4,
+        "rate" => 1.5,
+        "limit" => 1000,
+    }
+    .unwrap();
+    for (built, substituted) in built_variants.iter().zip(&variants) {
+        assert_eq!(
+            built.eval_with_context(&context),
+            substituted.eval_with_context(&context)
+        );
+    }
+
+    report(
+        "evaluate base tree",
+        measure(|| {
+            for _ in 0..ROUNDS * TENANTS {
+                base.eval_with_context(&context).unwrap();
+            }
+        }),
+        ROUNDS * TENANTS,
+        "evaluation",
+    );
+    report(
+        "evaluate built variants",
+        measure(|| {
+            for _ in 0..ROUNDS {
+                for variant in &built_variants {
+                    variant.eval_with_context(&context).unwrap();
+                }
+            }
+        }),
+        ROUNDS * TENANTS,
+        "evaluation",
+    );
+    report(
+        "evaluate substituted variants",
+        measure(|| {
+            for _ in 0..ROUNDS {
+                for variant in &variants {
+                    variant.eval_with_context(&context).unwrap();
+                }
+            }
+        }),
+        ROUNDS * TENANTS,
+        "evaluation",
+    );
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+use std::ptr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A pool of identifiers that is shared between the operator trees built with it, see `ParseConfig::identifier_interner`.
+///
+/// Each distinct identifier is stored once, and the operator trees only hold shared references to it.
+/// So many trees that use the same few identifiers, like thousands of rules over the same variables, do not store a copy of each identifier per occurrence.
+/// The pool can be shared between threads, and it keeps each identifier until the pool itself is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::sync::Arc;
+///
+/// let interner = Arc::new(IdentifierInterner::new());
+/// let config = ParseConfig {
+///     identifier_interner: Some(interner.clone()),
+///     ..ParseConfig::default()
+/// };
+/// let first = build_operator_tree_with_config("temperature > limit", &config).unwrap(); // Do proper error handling here
+/// let second = build_operator_tree_with_config("temperature < limit * 2", &config).unwrap(); // Do proper error handling here
+///
+/// let stats = interner.stats();
+/// assert_eq!(stats.identifiers, 2);
+/// assert_eq!(stats.requests, 4);
+///
+/// let context = context_map! { "temperature" => 30, "limit" => 20 }.unwrap(); // Do proper error handling here
+/// assert_eq!(first.tree.eval_with_context(&context), Ok(Value::from(true)));
+/// assert_eq!(second.tree.eval_with_context(&context), Ok(Value::from(true)));
+/// ```
+#[derive(Debug, Default)]
+pub struct IdentifierInterner {
+    pool: Mutex<Pool>,
+}
+
+#[derive(Debug, Default)]
+struct Pool {
+    identifiers: HashSet<Arc<str>>,
+    requests: usize,
+    requested_bytes: usize,
+}
+
+/// The amount of identifiers in an `IdentifierInterner`, and of the identifiers that it was asked for.
+///
+/// The difference between `requested_bytes` and `bytes` is about the memory that the operator trees built with the interner save.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InternerStats {
+    /// The amount of distinct identifiers in the pool.
+    pub identifiers: usize,
+    /// The total length in bytes of the distinct identifiers in the pool.
+    pub bytes: usize,
+    /// The amount of identifiers that were interned, counting each occurrence separately.
+    pub requests: usize,
+    /// The total length in bytes of the identifiers that were interned, counting each occurrence separately.
+    /// This is what the operator trees would store without the interner.
+    pub requested_bytes: usize,
+}
+
+impl IdentifierInterner {
+    /// Creates an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of the given identifier, which is added to the pool if it is not in there yet.
+    pub fn intern(&self, identifier: &str) -> Arc<str> {
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.requests += 1;
+        pool.requested_bytes += identifier.len();
+        if let Some(interned) = pool.identifiers.get(identifier) {
+            return interned.clone();
+        }
+
+        let interned: Arc<str> = identifier.into();
+        pool.identifiers.insert(interned.clone());
+        interned
+    }
+
+    /// Returns the current statistics of the pool.
+    pub fn stats(&self) -> InternerStats {
+        let pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        InternerStats {
+            identifiers: pool.identifiers.len(),
+            bytes: pool
+                .identifiers
+                .iter()
+                .map(|identifier| identifier.len())
+                .sum(),
+            requests: pool.requests,
+            requested_bytes: pool.requested_bytes,
+        }
+    }
+}
+
+/// Interners are equal if they are the same pool.
+impl PartialEq for IdentifierInterner {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self, other)
+    }
+}
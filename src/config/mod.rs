@@ -0,0 +1,377 @@
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use error::EvalexprResult;
+use state::EvalArena;
+use value::Value;
+
+mod interner;
+
+pub use self::interner::{IdentifierInterner, InternerStats};
+
+/// Configuration options for the evaluation of an operator tree.
+///
+/// The configuration is stored inside an `EvalState`, that is passed through nested evaluations.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let config = EvalConfig {
+///     max_recursion_depth: 8,
+///     ..Default::default()
+/// };
+/// let state = EvalState::new(config);
+/// let tree = build_operator_tree("1 + 2").unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context_and_state(&EmptyContext, &state), Ok(Value::from(3)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalConfig {
+    /// The maximum amount of evaluations that may be nested into each other.
+    /// Evaluations get nested if a user-defined function evaluates another expression.
+    /// If this amount is exceeded, the evaluation fails with `EvalexprError::RecursionLimitExceeded`.
+    pub max_recursion_depth: usize,
+
+    /// The maximum amount of bytes that strings and tuples produced by an evaluation may occupy in total, or `None` for no limit.
+    /// This includes the elements of nested tuples.
+    /// If this amount is exceeded, the evaluation fails with `EvalexprError::ValueTooLarge` before allocating the memory.
+    pub max_result_bytes: Option<usize>,
+
+    /// The maximum amount of elements of a range expression like `a..b` or `a..=b`.
+    /// If a range has more elements, the evaluation fails with `EvalexprError::RangeTooLong` before allocating them.
+    /// The default is `DEFAULT_MAX_RANGE_LENGTH`.
+    pub max_range_length: usize,
+
+    /// A token that allows to cancel the evaluation from another thread, or `None` if the evaluation cannot be cancelled.
+    /// The token is checked before each operator is applied.
+    /// If it is cancelled, the evaluation fails with `EvalexprError::Cancelled`.
+    pub cancellation_token: Option<CancellationToken>,
+
+    /// What to do if an operator or a builtin function produces NaN from operands that are not NaN, like `0.0 / 0.0`.
+    /// The default is `NanPolicy::Propagate`.
+    pub nan_policy: NanPolicy,
+
+    /// What `Node::eval_with_context_and_report` does if an element of a top-level tuple fails.
+    /// The default is `TupleErrorMode::FailFast`.
+    pub tuple_error_mode: TupleErrorMode,
+
+    /// An arena whose buffers are reused for the strings and tuples of intermediate values, or `None` to allocate them as usual.
+    /// See `EvalArena` for details.
+    pub arena: Option<EvalArena>,
+}
+
+impl EvalConfig {
+    /// The default value of `max_recursion_depth`.
+    pub const DEFAULT_MAX_RECURSION_DEPTH: usize = 64;
+
+    /// The default value of `max_range_length`.
+    pub const DEFAULT_MAX_RANGE_LENGTH: usize = 1_000_000;
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            max_recursion_depth: Self::DEFAULT_MAX_RECURSION_DEPTH,
+            max_result_bytes: None,
+            max_range_length: Self::DEFAULT_MAX_RANGE_LENGTH,
+            cancellation_token: None,
+            nan_policy: NanPolicy::default(),
+            tuple_error_mode: TupleErrorMode::default(),
+            arena: None,
+        }
+    }
+}
+
+/// Determines what happens if an operation produces NaN from operands that are not NaN.
+///
+/// This applies to the arithmetic operators and to the builtin functions.
+/// The operations that produce NaN this way are `inf - inf` and `inf + -inf`, `0 * inf`, `0.0 / 0.0` and `inf / inf`, `x % 0.0` and `inf % x`, and raising a negative number to a power that is not an integer, like `(-8) ^ (1 / 3)`.
+/// Of the builtin functions, `sqrt` and `ln` produce NaN for negative arguments, and `pow` like `^`.
+/// `ln(0)` is negative infinity, and not NaN.
+/// Operations that overflow to infinity, like `1.0 / 0.0`, are not affected.
+/// If an operand already is NaN, or a tuple argument of a builtin function contains NaN, the result is propagated under both policies.
+/// Functions from the context are never checked, as they may return NaN on purpose.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let tree = build_operator_tree("0.0 / 0.0").unwrap(); // Do proper error handling here
+/// assert!(tree.eval_float().unwrap().is_nan());
+///
+/// let state = EvalState::new(EvalConfig {
+///     nan_policy: NanPolicy::Error,
+///     ..Default::default()
+/// });
+/// assert_eq!(
+///     tree.eval_with_context_and_state(&EmptyContext, &state),
+///     Err(EvalexprError::NaNProduced {
+///         operator: "/".to_string(),
+///         operands: vec![Value::from(0.0), Value::from(0.0)],
+///     })
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    /// The NaN is returned like any other float, and propagates through further operations.
+    #[default]
+    Propagate,
+    /// The evaluation fails with `EvalexprError::NaNProduced`.
+    Error,
+}
+
+/// Determines what happens if an element of a tuple fails when an expression like `(cpu, memory, disk)` is evaluated with `Node::eval_with_context_and_report`.
+///
+/// Only the elements of the tuple that forms the whole expression are recovered.
+/// An error anywhere within an element fails the whole element, even if it occurs in a nested tuple.
+/// Errors that abort the whole evaluation, namely `Cancelled`, `RecursionLimitExceeded` and `ValueTooLarge`, are never recovered.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let tree = build_operator_tree("1 + 1, 2 / 0, 3").unwrap(); // Do proper error handling here
+/// let state = EvalState::new(EvalConfig {
+///     tuple_error_mode: TupleErrorMode::CollectPartial,
+///     ..Default::default()
+/// });
+/// let report = tree.eval_with_context_and_report(&EmptyContext, &state).unwrap(); // Do proper error handling here
+/// assert_eq!(report.value, Value::from(vec![Value::from(2), Value::Empty, Value::from(3)]));
+/// assert_eq!(report.errors.len(), 1);
+/// assert_eq!(report.errors[0].index, 1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TupleErrorMode {
+    /// The evaluation fails with the error of the first element that fails.
+    #[default]
+    FailFast,
+    /// Failed elements are replaced by `Value::Empty`, and their errors are collected in the `EvalReport`.
+    CollectPartial,
+}
+
+/// A flag that is shared between threads to cancel running evaluations.
+///
+/// Clones of a token share the same flag, so cancelling one clone cancels all evaluations that were configured with any of its clones.
+/// Once cancelled, a token stays cancelled.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let token = CancellationToken::new();
+/// let state = EvalState::new(EvalConfig {
+///     cancellation_token: Some(token.clone()),
+///     ..Default::default()
+/// });
+/// let tree = build_operator_tree("1 + 2").unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context_and_state(&EmptyContext, &state), Ok(Value::from(3)));
+///
+/// token.cancel();
+/// assert_eq!(tree.eval_with_context_and_state(&EmptyContext, &state), Err(EvalexprError::Cancelled));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all evaluations that use this token or one of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if `cancel` was called on this token or one of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Tokens are equal if they are clones of each other.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+/// Configuration options for building an operator tree from an expression string.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let config = ParseConfig {
+///     integer_overflow_policy: IntegerOverflowPolicy::ConvertToFloat,
+///     ..ParseConfig::default()
+/// };
+/// let parsed = build_operator_tree_with_config("10000000000000000000 + 1", &config).unwrap(); // Do proper error handling here
+/// assert_eq!(parsed.tree.eval(), Ok(Value::from(1e19 + 1.0)));
+/// assert_eq!(parsed.warnings.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseConfig {
+    /// What to do with integer literals that are out of the range of `IntType`.
+    pub integer_overflow_policy: IntegerOverflowPolicy,
+    /// Whether constants parsed from number literals or custom literals remember the text of the literal.
+    ///
+    /// If set, the `Display` implementation of `Node` writes such constants as they were written in the expression, so for example `1.50` stays `1.50` instead of becoming `1.5`.
+    /// This is meant for formatters that must not change the spelling of literals.
+    /// The text is also available through `Node::literal_text`, and is ignored by the evaluation.
+    /// If not set, which is the default, constants do not store any text.
+    pub preserve_literal_text: bool,
+    /// Whether a comparison may have another comparison as operand without parentheses, like in `a == b == c`.
+    ///
+    /// If not set, which is the default, such expressions fail with `EvalexprError::SuspiciousComparisonChain`, as they compare the boolean result of the inner comparison, which is rarely intended.
+    /// If set, they are parsed like `(a == b) == c`.
+    pub allow_comparison_chains: bool,
+    /// Recognizers for literals of a domain-specific syntax, like `#FF00FF` for colors, that are parsed into constants.
+    ///
+    /// They are tried in order wherever an identifier or a number literal may start, and the first one that matches is used.
+    /// See `CustomLiteral` for the precedence over the builtin syntax.
+    pub custom_literals: Vec<CustomLiteral>,
+    /// A pool of identifiers that the identifiers of variables and functions in the operator tree are taken from.
+    ///
+    /// If the same pool is used to build many operator trees, each distinct identifier is stored only once for all of them.
+    /// The trees are evaluated like trees built without a pool, and contexts are queried with the shared identifiers without copying them.
+    /// If not set, which is the default, each tree stores its own copy of each identifier.
+    pub identifier_interner: Option<Arc<IdentifierInterner>>,
+    /// Whether a multiplication may be written without `*` after a number, like in `2x` or `3(y - 1)`, and between parenthesized expressions, like in `(a)(b)`.
+    ///
+    /// If set, a `*` is inserted between a number literal and a following identifier or opening parenthesis, and between a closing and an opening parenthesis.
+    /// Whitespace does not matter, so `2 x` is `2 * x` as well, while an identifier followed by a parenthesis stays a function call, like `f(x)`, and `x2` stays an identifier.
+    /// Literals that are numbers as a whole, like `2e3` or `0x1F`, are not split.
+    /// If not set, which is the default, such expressions fail to parse, and `2x` is an identifier, so that typos like a missing operator are not silently accepted.
+    pub implicit_multiplication: bool,
+}
+
+/// Determines how integer literals that are out of the range of `IntType` are parsed.
+///
+/// An integer literal is a literal that consists of decimal digits only, or a hexadecimal, octal or binary literal like `0xFF`, `0o17` or `0b1010`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IntegerOverflowPolicy {
+    /// Parsing fails with `EvalexprError::IntegerLiteralOutOfRange`.
+    #[default]
+    Error,
+    /// The literal is converted to the nearest `FloatType`, and a `ParseWarning::IntegerLiteralConvertedToFloat` is recorded.
+    ConvertToFloat,
+}
+
+/// The type of the function that converts the text of a custom literal to a value.
+type LiteralConverter = dyn Fn(&str) -> EvalexprResult<Value> + Send + Sync;
+
+/// A recognizer for literals of a domain-specific syntax, registered in `ParseConfig::custom_literals`.
+///
+/// A custom literal is the prefix of the recognizer followed by one or more characters that its body predicate accepts, like `#` followed by hexadecimal digits.
+/// Its whole text, including the prefix, is converted to a value when the expression is parsed, and the operator tree contains this value as a constant.
+/// If the conversion fails, parsing fails with `EvalexprError::InvalidCustomLiteral`, which contains the range of bytes of the literal and the error of the conversion.
+///
+/// Custom literals are only recognized where an identifier or a number literal may start, so never within string literals or comments, and never at an operator, like a `-` or a `.`.
+/// Their precedence over the builtin syntax is as follows:
+///
+///  * A custom literal must not be directly followed by a character that continues an identifier or a number, so `#FF00FFzz` is not a color, but is parsed as if the recognizer was not registered.
+///  * A custom literal that starts with a digit must span the whole literal at its position, and is only recognized if that literal is not a valid integer or float literal.
+///    So a recognizer for versions like `1.2.3` does not change the meaning of `1.5` or `2 + 3`.
+///  * A custom literal takes precedence over identifiers and keywords, so a recognizer without prefix must not accept the characters of identifiers that the expressions use.
+///
+/// If `ParseConfig::preserve_literal_text` is set, the constant remembers the text of the custom literal.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let color = CustomLiteral::new("#", |c| c.is_ascii_hexdigit(), |literal| {
+///     IntType::from_str_radix(&literal[1..], 16)
+///         .map(Value::from)
+///         .map_err(|error| EvalexprError::CustomMessage(error.to_string()))
+/// });
+/// let config = ParseConfig {
+///     custom_literals: vec![color],
+///     ..ParseConfig::default()
+/// };
+///
+/// let parsed = build_operator_tree_with_config("#FF00FF & #00FFFF", &config).unwrap(); // Do proper error handling here
+/// assert_eq!(parsed.tree.eval(), Ok(Value::from(0x0000FF)));
+/// assert!(matches!(
+///     build_operator_tree_with_config("#FFFFFFFFFFFFFFFFFF", &config),
+///     Err(EvalexprError::InvalidCustomLiteral { span, .. }) if span == (0..19)
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct CustomLiteral {
+    prefix: String,
+    is_body: Arc<dyn Fn(char) -> bool + Send + Sync>,
+    converter: Arc<LiteralConverter>,
+}
+
+impl CustomLiteral {
+    /// Creates a recognizer for literals that consist of the given prefix followed by one or more characters that `is_body` accepts.
+    /// The prefix may be empty.
+    /// The text of each literal, including the prefix, is converted to a value by `converter`.
+    pub fn new<B, C>(prefix: &str, is_body: B, converter: C) -> Self
+    where
+        B: Fn(char) -> bool + Send + Sync + 'static,
+        C: Fn(&str) -> EvalexprResult<Value> + Send + Sync + 'static,
+    {
+        Self {
+            prefix: prefix.to_string(),
+            is_body: Arc::new(is_body),
+            converter: Arc::new(converter),
+        }
+    }
+
+    /// Returns the prefix of the literals that this recognizer matches.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the length in bytes of the literal of this recognizer at the start of the given string, if there is one.
+    /// The length does not account for the precedence rules.
+    pub(crate) fn match_length(&self, string: &str) -> Option<usize> {
+        let body = string.strip_prefix(self.prefix.as_str())?;
+        let body_length: usize = body
+            .chars()
+            .take_while(|&c| (self.is_body)(c))
+            .map(char::len_utf8)
+            .sum();
+        if body_length > 0 {
+            Some(self.prefix.len() + body_length)
+        } else {
+            None
+        }
+    }
+
+    /// Converts the given text of a literal of this recognizer to a value.
+    pub(crate) fn convert(&self, literal: &str) -> EvalexprResult<Value> {
+        (self.converter)(literal)
+    }
+}
+
+impl fmt::Debug for CustomLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomLiteral")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Recognizers are equal if they are clones of each other.
+impl PartialEq for CustomLiteral {
+    fn eq(&self, other: &Self) -> bool {
+        self.prefix == other.prefix
+            && Arc::ptr_eq(&self.is_body, &other.is_body)
+            && Arc::ptr_eq(&self.converter, &other.converter)
+    }
+}
@@ -1,6 +1,12 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
 
-use function::Function;
+#[cfg(feature = "async")]
+use feature_async::AsyncFunction;
+use function::{Function, IntoFunction};
+use tree::Node;
 use value::value_type::ValueType;
 use EvalexprError;
 use EvalexprResult;
@@ -13,6 +19,11 @@ use crate::value::Value;
 /// In addition, it also allows the manipulation of values and functions.
 /// This crate implements two basic variants, the `EmptyContext`, that returns `None` for each identifier and cannot be manipulated, and the `HashMapContext`, that stores its mappings in hash maps.
 /// The HashMapContext is type-safe and returns an error if the user tries to assign a value of a different type than before to an identifier.
+///
+/// An assignment evaluates its right-hand side completely before it calls `set_value`.
+/// So if the context cannot be manipulated, all side effects of functions called on the right-hand side have already happened when the assignment fails with `EvalexprError::ContextNotManipulable`.
+/// This is the same for evaluations with an immutable context, and for mutable contexts whose `set_value` fails.
+/// Use `is_manipulable` to check a context before evaluating an expression with side effects.
 pub trait Context {
     /// Returns the value that is linked to the given identifier.
     fn get_value(&self, identifier: &str) -> Option<&Value>;
@@ -20,6 +31,23 @@ pub trait Context {
     /// Returns the function that is linked to the given identifier.
     fn get_function(&self, identifier: &str) -> Option<&Function>;
 
+    /// Returns the named expression that is linked to the given identifier.
+    ///
+    /// If a variable identifier is not linked to a value, but to a named expression, the expression is evaluated in place of the identifier.
+    /// The default implementation returns `None` for each identifier.
+    fn get_expression(&self, _identifier: &str) -> Option<Arc<Node>> {
+        None
+    }
+
+    /// Returns true if values can be linked to identifiers with `set_value`.
+    ///
+    /// This is a hint for users of the context, the evaluation of expressions does not depend on it.
+    /// The default implementation returns false, like the default implementation of `set_value` fails.
+    /// Contexts that implement `set_value` should override this method.
+    fn is_manipulable(&self) -> bool {
+        false
+    }
+
     /// Links the given value to the given identifier.
     fn set_value(&mut self, _identifier: String, _value: Value) -> EvalexprResult<()> {
         Err(EvalexprError::ContextNotManipulable)
@@ -29,6 +57,11 @@ pub trait Context {
     fn set_function(&mut self, _identifier: String, _function: Function) -> EvalexprResult<()> {
         Err(EvalexprError::ContextNotManipulable)
     }
+
+    /// Links the given named expression to the given identifier.
+    fn set_expression(&mut self, _identifier: String, _expression: Node) -> EvalexprResult<()> {
+        Err(EvalexprError::ContextNotManipulable)
+    }
 }
 
 /// A context that returns `None` for each identifier.
@@ -44,17 +77,302 @@ impl Context for EmptyContext {
     }
 }
 
+/// The identifier of the function that exports variables from a script.
+pub(crate) const EXPORT_FUNCTION_IDENTIFIER: &str = "export";
+
+/// A context that wraps the context of a script while the script is evaluated.
+///
+/// It behaves like the wrapped context, except that it links `export` to a function that does nothing.
+/// The arguments of `export` are still evaluated, so exporting an undefined variable fails.
+pub(crate) struct ScriptContext<'a> {
+    context: &'a mut dyn Context,
+    export: Function,
+}
+
+impl<'a> ScriptContext<'a> {
+    pub(crate) fn new(context: &'a mut dyn Context) -> Self {
+        Self {
+            context,
+            export: Function::new(Box::new(|_| Ok(Value::Empty))),
+        }
+    }
+}
+
+impl<'a> Context for ScriptContext<'a> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        self.context.get_value(identifier)
+    }
+
+    fn get_function(&self, identifier: &str) -> Option<&Function> {
+        if identifier == EXPORT_FUNCTION_IDENTIFIER {
+            Some(&self.export)
+        } else {
+            self.context.get_function(identifier)
+        }
+    }
+
+    fn get_expression(&self, identifier: &str) -> Option<Arc<Node>> {
+        self.context.get_expression(identifier)
+    }
+
+    fn is_manipulable(&self) -> bool {
+        self.context.is_manipulable()
+    }
+
+    fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        self.context.set_value(identifier, value)
+    }
+
+    fn set_function(&mut self, identifier: String, function: Function) -> EvalexprResult<()> {
+        self.context.set_function(identifier, function)
+    }
+
+    fn set_expression(&mut self, identifier: String, expression: Node) -> EvalexprResult<()> {
+        self.context.set_expression(identifier, expression)
+    }
+}
+
+/// The identifier of the variable that is linked to the current element within the second argument of a higher-order builtin function like `map`.
+pub(crate) const IT_IDENTIFIER: &str = "it";
+
+/// The context that an `ItContext` wraps, which can be manipulated only if it was borrowed mutably.
+enum ScopedContext<'a> {
+    Shared(&'a dyn Context),
+    Exclusive(&'a mut dyn Context),
+}
+
+/// A context that wraps another context while the second argument of a higher-order builtin function is evaluated.
+///
+/// It behaves like the wrapped context, except that it links `it` to the current element, hiding a value with this identifier in the wrapped context.
+pub(crate) struct ItContext<'a> {
+    context: ScopedContext<'a>,
+    it: Value,
+}
+
+impl<'a> ItContext<'a> {
+    pub(crate) fn shared(context: &'a dyn Context) -> Self {
+        Self {
+            context: ScopedContext::Shared(context),
+            it: Value::Empty,
+        }
+    }
+
+    pub(crate) fn exclusive(context: &'a mut dyn Context) -> Self {
+        Self {
+            context: ScopedContext::Exclusive(context),
+            it: Value::Empty,
+        }
+    }
+
+    pub(crate) fn set_it(&mut self, value: Value) {
+        self.it = value;
+    }
+
+    pub(crate) fn it_mut(&mut self) -> &mut Value {
+        &mut self.it
+    }
+
+    fn context(&self) -> &dyn Context {
+        match &self.context {
+            ScopedContext::Shared(context) => *context,
+            ScopedContext::Exclusive(context) => &**context,
+        }
+    }
+
+    fn context_mut(&mut self) -> EvalexprResult<&mut dyn Context> {
+        match &mut self.context {
+            ScopedContext::Shared(_) => Err(EvalexprError::ContextNotManipulable),
+            ScopedContext::Exclusive(context) => Ok(&mut **context),
+        }
+    }
+}
+
+impl<'a> Context for ItContext<'a> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        if identifier == IT_IDENTIFIER {
+            Some(&self.it)
+        } else {
+            self.context().get_value(identifier)
+        }
+    }
+
+    fn get_function(&self, identifier: &str) -> Option<&Function> {
+        self.context().get_function(identifier)
+    }
+
+    fn get_expression(&self, identifier: &str) -> Option<Arc<Node>> {
+        self.context().get_expression(identifier)
+    }
+
+    fn is_manipulable(&self) -> bool {
+        match &self.context {
+            ScopedContext::Shared(_) => false,
+            ScopedContext::Exclusive(context) => context.is_manipulable(),
+        }
+    }
+
+    fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        self.context_mut()?.set_value(identifier, value)
+    }
+
+    fn set_function(&mut self, identifier: String, function: Function) -> EvalexprResult<()> {
+        self.context_mut()?.set_function(identifier, function)
+    }
+
+    fn set_expression(&mut self, identifier: String, expression: Node) -> EvalexprResult<()> {
+        self.context_mut()?.set_expression(identifier, expression)
+    }
+}
+
+/// A context that wraps another context and records the values of the variables that are read through it.
+///
+/// It behaves like the wrapped context, but cannot be manipulated.
+pub(crate) struct RecordingContext<'a> {
+    context: &'a dyn Context,
+    /// The identifiers of the variables in the order they were first read, each with the value it had, or `None` if it had no value.
+    reads: RefCell<Vec<(String, Option<Value>)>>,
+}
+
+impl<'a> RecordingContext<'a> {
+    pub(crate) fn new(context: &'a dyn Context) -> Self {
+        Self {
+            context,
+            reads: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn into_reads(self) -> Vec<(String, Option<Value>)> {
+        self.reads.into_inner()
+    }
+}
+
+impl<'a> Context for RecordingContext<'a> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        let value = self.context.get_value(identifier);
+        let mut reads = self.reads.borrow_mut();
+        if !reads.iter().any(|(read, _)| read == identifier) {
+            reads.push((identifier.to_string(), value.cloned()));
+        }
+        value
+    }
+
+    fn get_function(&self, identifier: &str) -> Option<&Function> {
+        self.context.get_function(identifier)
+    }
+
+    fn get_expression(&self, identifier: &str) -> Option<Arc<Node>> {
+        self.context.get_expression(identifier)
+    }
+}
+
 /// A context that stores its mappings in hash maps.
 ///
 /// *Value and function mappings are stored independently, meaning that there can be a function and a value with the same identifier.*
+/// Named expressions are stored independently as well, but are only used for identifiers that are not linked to a value.
 ///
 /// This context is type-safe, meaning that an identifier that is assigned a value of some type once cannot be assigned a value of another type.
-#[derive(Debug, Default)]
+///
+/// Changes of values can be observed with a hook that is called after each successful `set_value`, or recorded and taken after the evaluation.
+/// Both only see values that were actually stored, so writes that fail because of the type of the value are not reported.
+///
+/// Two contexts are equal if they link the same identifiers to equal values and named expressions, and have functions for the same identifiers.
+/// Functions cannot be compared, so only their identifiers are, and the change hook as well as the recorded changes are ignored.
+/// The `Debug` output lists the mappings ordered by their identifiers, so it stays the same between runs and can be used in snapshot tests.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let mut a = HashMapContext::new();
+/// let mut b = HashMapContext::new();
+/// eval_with_context_mut("x = 1; y = x + 1", &mut a).unwrap(); // Do proper error handling here
+/// eval_with_context_mut("y = 2; x = y - 1", &mut b).unwrap(); // Do proper error handling here
+/// assert_eq!(a, b);
+/// assert!(format!("{:?}", a).starts_with("HashMapContext { variables: {\"x\": Int(1), \"y\": Int(2)}"));
+/// ```
+#[derive(Default)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct HashMapContext {
     variables: HashMap<String, Value>,
     #[cfg_attr(feature = "serde_support", serde(skip))]
     functions: HashMap<String, Function>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    expressions: HashMap<String, Arc<Node>>,
+    #[cfg(feature = "async")]
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub(crate) async_functions: HashMap<String, AsyncFunction>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    on_change: Option<ChangeHook>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    changes: Option<Vec<(String, Value)>>,
+}
+
+/// A function that is called with the identifier and the new value after a value of a `HashMapContext` was changed.
+pub type ChangeHookFunction = dyn Fn(&str, &Value) + Send + Sync;
+
+/// Wraps the change hook of a `HashMapContext` to implement `Debug` for it.
+struct ChangeHook(Box<ChangeHookFunction>);
+
+impl fmt::Debug for ChangeHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ChangeHook")
+    }
+}
+
+impl PartialEq for HashMapContext {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "async")]
+        let async_functions_eq = self.async_functions.len() == other.async_functions.len()
+            && self
+                .async_functions
+                .keys()
+                .all(|identifier| other.async_functions.contains_key(identifier));
+        #[cfg(not(feature = "async"))]
+        let async_functions_eq = true;
+
+        self.variables == other.variables
+            && self.expressions == other.expressions
+            && self.functions.len() == other.functions.len()
+            && self
+                .functions
+                .keys()
+                .all(|identifier| other.functions.contains_key(identifier))
+            && async_functions_eq
+    }
+}
+
+impl fmt::Debug for HashMapContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("HashMapContext");
+        debug
+            .field(
+                "variables",
+                &self.variables.iter().collect::<BTreeMap<_, _>>(),
+            )
+            .field("functions", &self.functions.keys().collect::<BTreeSet<_>>())
+            .field(
+                "expressions",
+                &self
+                    .expressions
+                    .iter()
+                    .map(|(identifier, expression)| (identifier, expression.to_minified_string()))
+                    .collect::<BTreeMap<_, _>>(),
+            );
+        #[cfg(feature = "async")]
+        debug.field(
+            "async_functions",
+            &self.async_functions.keys().collect::<BTreeSet<_>>(),
+        );
+        // The hook and the recorded changes are only shown while they are set.
+        if self.on_change.is_some() {
+            debug.field("on_change", &self.on_change);
+        }
+        if let Some(changes) = &self.changes {
+            debug.field("changes", changes);
+        }
+        debug.finish()
+    }
 }
 
 impl HashMapContext {
@@ -62,19 +380,162 @@ impl HashMapContext {
     pub fn new() -> Self {
         Default::default()
     }
-}
 
-impl Context for HashMapContext {
-    fn get_value(&self, identifier: &str) -> Option<&Value> {
-        self.variables.get(identifier)
+    /// Links a function created from the given closure with typed parameters to the given identifier, and returns the context.
+    ///
+    /// This is a shortcut for `set_function` with `Function::from_fn`, which describes the supported closures.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let context = HashMapContext::new()
+    ///     .with_fn("double", |x: IntType| Ok(x * 2))
+    ///     .with_fn("greet", |name: String, excited: bool| {
+    ///         Ok(format!("Hello, {}{}", name, if excited { "!" } else { "." }))
+    ///     });
+    ///
+    /// assert_eq!(eval_with_context("double(21)", &context), Ok(Value::from(42)));
+    /// assert_eq!(eval_with_context("greet(\"Ada\", true)", &context), Ok(Value::from("Hello, Ada!")));
+    /// ```
+    pub fn with_fn<K: Into<String>, Parameters, F: IntoFunction<Parameters>>(
+        mut self,
+        identifier: K,
+        function: F,
+    ) -> Self {
+        self.functions
+            .insert(identifier.into(), Function::from_fn(function));
+        self
     }
 
-    fn get_function(&self, identifier: &str) -> Option<&Function> {
-        self.functions.get(identifier)
+    /// Links the given async function to the given identifier.
+    ///
+    /// Async functions are only called by `eval_with_context_async`.
+    /// A function linked with `set_function` to the same identifier takes precedence.
+    #[cfg(feature = "async")]
+    pub fn set_async_function(
+        &mut self,
+        identifier: String,
+        function: AsyncFunction,
+    ) -> EvalexprResult<()> {
+        self.async_functions.insert(identifier, function);
+        Ok(())
     }
 
-    fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
-        if let Some(existing_value) = self.variables.get_mut(&identifier) {
+    /// Sets a hook that is called with the identifier and the new value after each successful `set_value`, replacing any previous hook.
+    ///
+    /// The hook is called for assignments during evaluations as well as for direct calls of `set_value`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let changed = Arc::new(Mutex::new(Vec::new()));
+    /// let hook_changed = changed.clone();
+    /// let mut context = HashMapContext::new();
+    /// context.set_on_change(Box::new(move |identifier, value| {
+    ///     hook_changed.lock().unwrap().push(format!("{} = {}", identifier, value));
+    /// }));
+    ///
+    /// eval_empty_with_context_mut("a = 1; b = a + 1", &mut context).unwrap(); // Do proper error handling here
+    /// assert_eq!(*changed.lock().unwrap(), vec!["a = 1", "b = 2"]);
+    /// ```
+    pub fn set_on_change(&mut self, on_change: Box<ChangeHookFunction>) {
+        self.on_change = Some(ChangeHook(on_change));
+    }
+
+    /// Removes the hook set by `set_on_change`.
+    pub fn clear_on_change(&mut self) {
+        self.on_change = None;
+    }
+
+    /// Starts or stops recording the changes of values made by `set_value`.
+    ///
+    /// While recording, each successful `set_value` appends the identifier and the new value to the recorded changes, which can be retrieved with `take_changes`.
+    /// Stopping the recording discards the changes that were not taken yet.
+    pub fn set_record_changes(&mut self, record_changes: bool) {
+        if !record_changes {
+            self.changes = None;
+        } else if self.changes.is_none() {
+            self.changes = Some(Vec::new());
+        }
+    }
+
+    /// Returns the changes recorded since recording was started or since the last call of this method, in the order in which they were made.
+    /// An identifier that was changed multiple times appears multiple times.
+    ///
+    /// Returns an empty vector if changes are not recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_record_changes(true);
+    ///
+    /// eval_empty_with_context_mut("a = 1; a = a + 1", &mut context).unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     context.take_changes(),
+    ///     vec![("a".to_string(), Value::from(1)), ("a".to_string(), Value::from(2))]
+    /// );
+    /// assert_eq!(context.take_changes(), vec![]);
+    /// ```
+    pub fn take_changes(&mut self) -> Vec<(String, Value)> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Returns the value that is linked to the given identifier.
+    ///
+    /// This is `Context::get_value` for any kind of string as identifier, like `&str`, `String` or `&String`.
+    pub fn get_value<Q: AsRef<str>>(&self, identifier: Q) -> Option<&Value> {
+        self.variables.get(identifier.as_ref())
+    }
+
+    /// Links the given value to the given identifier, if the identifier is not linked to a value of another type.
+    ///
+    /// This is `Context::set_value` for identifiers given as `&str` or `String` and values of any type that converts into a `Value`.
+    /// The identifier is only converted into a `String` if it is not linked to a value yet, so overwriting a value with a `&str` identifier does not allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_value("x", 5).unwrap(); // Do proper error handling here
+    /// context.set_value("x", 6).unwrap(); // Do proper error handling here
+    /// assert_eq!(context.get_value("x"), Some(&Value::from(6)));
+    /// assert!(context.set_value("x", "six").is_err());
+    /// ```
+    pub fn set_value<K, V>(&mut self, identifier: K, value: V) -> EvalexprResult<()>
+    where
+        K: AsRef<str> + Into<String>,
+        V: Into<Value>,
+    {
+        let value = value.into();
+        if self.on_change.is_some() || self.changes.is_some() {
+            let notified_value = value.clone();
+            self.store_value(identifier.as_ref(), value)?;
+            self.notify_change(identifier.as_ref(), &notified_value);
+            Ok(())
+        } else {
+            self.store_value(identifier, value)
+        }
+    }
+
+    /// Links the given value to the given identifier, if the identifier is not linked to a value of another type.
+    fn store_value<K>(&mut self, identifier: K, value: Value) -> EvalexprResult<()>
+    where
+        K: AsRef<str> + Into<String>,
+    {
+        if let Some(existing_value) = self.variables.get_mut(identifier.as_ref()) {
             if ValueType::from(&existing_value) == ValueType::from(&value) {
                 *existing_value = value;
                 return Ok(());
@@ -84,18 +545,58 @@ impl Context for HashMapContext {
         }
 
         // Implicit else, because `self.variables` and `identifier` are not unborrowed in else
-        self.variables.insert(identifier, value);
+        self.variables.insert(identifier.into(), value);
         Ok(())
     }
 
+    /// Reports a successful change of a value to the hook and the recorded changes.
+    fn notify_change(&mut self, identifier: &str, value: &Value) {
+        if let Some(ChangeHook(on_change)) = &self.on_change {
+            on_change(identifier, value);
+        }
+        if let Some(changes) = &mut self.changes {
+            changes.push((identifier.to_string(), value.clone()));
+        }
+    }
+}
+
+impl Context for HashMapContext {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        HashMapContext::get_value(self, identifier)
+    }
+
+    fn get_function(&self, identifier: &str) -> Option<&Function> {
+        self.functions.get(identifier)
+    }
+
+    fn get_expression(&self, identifier: &str) -> Option<Arc<Node>> {
+        self.expressions.get(identifier).cloned()
+    }
+
+    fn is_manipulable(&self) -> bool {
+        true
+    }
+
+    fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        HashMapContext::set_value(self, identifier, value)
+    }
+
     fn set_function(&mut self, identifier: String, function: Function) -> EvalexprResult<()> {
-        self.functions.insert(identifier.into(), function);
+        self.functions.insert(identifier, function);
+        Ok(())
+    }
+
+    fn set_expression(&mut self, identifier: String, expression: Node) -> EvalexprResult<()> {
+        self.expressions.insert(identifier, Arc::new(expression));
         Ok(())
     }
 }
 
 /// This macro provides a convenient syntax for creating a static context.
 ///
+/// Values are given as expressions that convert into a `Value`, and functions either as `Function::new(...)` or as a closure with typed parameters after the keyword `fn`.
+/// Such a closure returns its result directly instead of an `EvalexprResult`, and is converted with `Function::from_fn`, so the arguments of calls are checked like described there.
+///
 /// # Examples
 ///
 /// ```rust
@@ -103,16 +604,24 @@ impl Context for HashMapContext {
 ///
 /// let ctx = evalexpr::context_map! {
 ///     "x" => 8,
-///     "f" => Function::new(Box::new(|_| Ok(42.into()) ))
+///     "f" => Function::new(Box::new(|_| Ok(42.into()) )),
+///     "double" => fn |x: IntType| x * 2,
+///     "hypot" => fn |a: FloatType, b: FloatType| (a * a + b * b).sqrt()
 /// }.unwrap();
 ///
 /// assert_eq!(eval_with_context("x + f()", &ctx), Ok(50.into()));
+/// assert_eq!(eval_with_context("double(x)", &ctx), Ok(16.into()));
+/// assert_eq!(eval_with_context("hypot(3, 4)", &ctx), Ok(5.0.into()));
 /// ```
 #[macro_export]
 macro_rules! context_map {
     // Termination (allow missing comma at the end of the argument list)
     ( ($ctx:expr) $k:expr => Function::new($($v:tt)*) ) =>
         { $crate::context_map!(($ctx) $k => Function::new($($v)*),) };
+    ( ($ctx:expr) $k:expr => fn || $body:expr ) =>
+        { $crate::context_map!(($ctx) $k => fn || $body,) };
+    ( ($ctx:expr) $k:expr => fn |$($p:ident : $t:ty),*| $body:expr ) =>
+        { $crate::context_map!(($ctx) $k => fn |$($p : $t),*| $body,) };
     ( ($ctx:expr) $k:expr => $v:expr ) =>
         { $crate::context_map!(($ctx) $k => $v,)  };
     // Termination
@@ -123,9 +632,22 @@ macro_rules! context_map {
         $ctx.set_function($k.into(), $crate::Function::new($($v)*))
             .and($crate::context_map!(($ctx) $($tt)*))
     }};
+    // A closure after 'fn' is converted with 'Function::from_fn', with its result wrapped in 'Ok'
+    ( ($ctx:expr) $k:expr => fn || $body:expr , $($tt:tt)*) => {{
+        $ctx.set_function($k.into(), $crate::Function::from_fn(
+            move || Ok::<_, $crate::EvalexprError>($body)
+        ))
+            .and($crate::context_map!(($ctx) $($tt)*))
+    }};
+    ( ($ctx:expr) $k:expr => fn |$($p:ident : $t:ty),*| $body:expr , $($tt:tt)*) => {{
+        $ctx.set_function($k.into(), $crate::Function::from_fn(
+            move |$($p : $t),*| Ok::<_, $crate::EvalexprError>($body)
+        ))
+            .and($crate::context_map!(($ctx) $($tt)*))
+    }};
     // add a value, and chain the eventual error with the ones in the next values
     ( ($ctx:expr) $k:expr => $v:expr , $($tt:tt)*) => {{
-        $ctx.set_value($k.into(), $v.into())
+        $ctx.set_value($k, $v)
             .and($crate::context_map!(($ctx) $($tt)*))
     }};
 
@@ -1,10 +1,15 @@
 use std::fmt;
 
+use error::Coordinate;
+use function::builtin::disabled_builtin_feature;
+use typecheck::TypeCheckError;
+use value::{display::CanonicalFloats, IntType, Value};
 use EvalexprError;
 
 impl fmt::Display for EvalexprError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         use EvalexprError::*;
+        let _canonical = CanonicalFloats::new();
         match self {
             WrongOperatorArgumentAmount { expected, actual } => write!(
                 f,
@@ -16,6 +21,16 @@ impl fmt::Display for EvalexprError {
                 "A function expected {} arguments, but got {}.",
                 expected, actual
             ),
+            WrongFunctionArgumentRange {
+                minimum,
+                maximum,
+                actual,
+                optional,
+            } => write!(
+                f,
+                "A function expected {} to {} arguments, but got {}. The optional parameters are {:?}.",
+                minimum, maximum, actual, optional
+            ),
             ExpectedString { actual } => {
                 write!(f, "Expected a Value::String, but got {:?}.", actual)
             },
@@ -41,37 +56,143 @@ impl fmt::Display for EvalexprError {
                 f,
                 "Tried to append a node to another node with higher precedence."
             ),
-            VariableIdentifierNotFound(identifier) => write!(
+            VariableIdentifierNotFound { identifier, span } => {
+                write!(
+                    f,
+                    "Variable identifier is not bound to anything by context: {:?}",
+                    identifier
+                )?;
+                if let Some(span) = span {
+                    write!(f, " at offset {}", span.start)?;
+                }
+                write!(f, ".")
+            },
+            FunctionIdentifierNotFound(identifier) => {
+                write!(
+                    f,
+                    "Function identifier is not bound to anything by context: {:?}.",
+                    identifier
+                )?;
+                if let Some(feature) = disabled_builtin_feature(identifier) {
+                    write!(
+                        f,
+                        " The builtin function with this identifier requires the feature flag {:?}.",
+                        feature
+                    )?;
+                }
+                Ok(())
+            },
+            UnknownNamedArgument { function, argument } => write!(
+                f,
+                "The function {:?} has no parameter named {:?}.",
+                function, argument
+            ),
+            DuplicateArgument {
+                function,
+                parameter,
+            } => write!(
+                f,
+                "The parameter {:?} of the function {:?} is given more than once.",
+                parameter, function
+            ),
+            MissingArgument {
+                function,
+                parameter,
+            } => write!(
                 f,
-                "Variable identifier is not bound to anything by context: {:?}.",
-                identifier
+                "The parameter {:?} of the function {:?} is not given and has no default value.",
+                parameter, function
             ),
-            FunctionIdentifierNotFound(identifier) => write!(
+            NonTrailingDefault(name) => write!(
                 f,
-                "Function identifier is not bound to anything by context: {:?}.",
-                identifier
+                "The parameter {:?} has a default value, but is followed by a parameter without one.",
+                name
             ),
+            DuplicateParameter(name) => {
+                write!(f, "A function has more than one parameter named {:?}.", name)
+            },
             TypeError { expected, actual } => {
                 write!(f, "Expected one of {:?}, but got {:?}.", expected, actual)
             },
-            UnmatchedLBrace => write!(f, "Found an unmatched opening parenthesis '('."),
-            UnmatchedRBrace => write!(f, "Found an unmatched closing parenthesis ')'."),
-            UnmatchedPartialToken { first, second } => {
-                if let Some(second) = second {
-                    write!(
-                        f,
-                        "Found a partial token '{}' that should not be followed by '{}'.",
-                        first, second
-                    )
-                } else {
-                    write!(
-                        f,
-                        "Found a partial token '{}' that should be followed by another partial \
-                         token.",
-                        first
-                    )
-                }
-            },
+            UnmatchedLBrace { span } => write!(
+                f,
+                "Found an unmatched opening parenthesis '(' at offset {}.",
+                span.start
+            ),
+            UnmatchedRBrace { span } => write!(
+                f,
+                "Found an unmatched closing parenthesis ')' at offset {}.",
+                span.start
+            ),
+            UnmatchedQuestionMark { span } => write!(
+                f,
+                "Found a question mark '?' at offset {} without a matching colon ':'.",
+                span.start
+            ),
+            UnmatchedColon { span } => write!(
+                f,
+                "Found a colon ':' at offset {} without a matching question mark '?'.",
+                span.start
+            ),
+            MissingOperator { span } => write!(
+                f,
+                "Found a value at offset {} directly after another value without an operator between them.",
+                span.start
+            ),
+            UnmatchedLCurlyBrace { span } => write!(
+                f,
+                "Found an unmatched opening curly brace '{{' at offset {}.",
+                span.start
+            ),
+            UnmatchedRCurlyBrace { span } => write!(
+                f,
+                "Found an unmatched closing curly brace '}}' at offset {}.",
+                span.start
+            ),
+            InvalidIfExpression { span } => write!(
+                f,
+                "Found a malformed if-expression at offset {}, expected a form like 'if a {{ b }} else {{ c }}'.",
+                span.start
+            ),
+            TrailingComma { span } => write!(
+                f,
+                "Found a trailing comma at offset {} that is not followed by another tuple \
+                 element.",
+                span.start
+            ),
+            LeadingComma { span } => write!(
+                f,
+                "Found a comma at offset {} that does not follow a tuple element.",
+                span.start
+            ),
+            PositionalArgumentAfterNamed { span } => write!(
+                f,
+                "Found a positional argument at offset {} after a named argument.",
+                span.start
+            ),
+            InvalidAssignmentTarget { span } => write!(
+                f,
+                "The left-hand side of the assignment at offset {} is not a single variable \
+                 identifier, like in 'a = 1'.",
+                span.start
+            ),
+            AssignmentToKeyword { keyword, span } => write!(
+                f,
+                "Cannot assign to the keyword '{}' at offset {}, as it is not an identifier.",
+                keyword, span.start
+            ),
+            KeywordAsValue { keyword, span } => write!(
+                f,
+                "The keyword '{}' at offset {} is an operator and cannot be used as a variable.",
+                keyword, span.start
+            ),
+            SuspiciousComparisonChain { expression } => write!(
+                f,
+                "Comparisons are chained without parentheses, which would be evaluated as {}. \
+                 Write the parentheses to compare the boolean result, or use && to require both \
+                 comparisons.",
+                expression
+            ),
             AdditionError { augend, addend } => write!(f, "Error adding {} + {}", augend, addend),
             SubtractionError {
                 minuend,
@@ -88,14 +209,600 @@ impl fmt::Display for EvalexprError {
             ModulationError { dividend, divisor } => {
                 write!(f, "Error modulating {} % {}", dividend, divisor)
             },
+            ExponentiationError { base, exponent } => {
+                write!(f, "Error exponentiating {} ^ {}", base, exponent)
+            },
+            ShiftAmountOutOfRange { value, amount } => write!(
+                f,
+                "Cannot shift {} by {} bits, expected an amount from 0 to {}",
+                value,
+                amount,
+                IntType::BITS - 1
+            ),
+            NaNProduced { operator, operands } => {
+                let operands: Vec<String> = operands.iter().map(Value::to_string).collect();
+                write!(
+                    f,
+                    "Computing {} with the operands {} produced NaN",
+                    operator,
+                    operands.join(", ")
+                )
+            },
+            IntegerOverflow {
+                function,
+                arguments,
+            } => {
+                let arguments: Vec<String> = arguments.iter().map(Value::to_string).collect();
+                write!(
+                    f,
+                    "Calling {} with the arguments {} overflows the integer range",
+                    function,
+                    arguments.join(", ")
+                )
+            },
             InvalidRegex { regex, message } => write!(
                 f,
                 "Regular expression {:?} is invalid: {:?}",
                 regex, message
             ),
+            OperatorNeedsFeature { operator, feature } => write!(
+                f,
+                "The operator '{}' needs the feature '{}' of evalexpr, which is disabled.",
+                operator, feature
+            ),
+            InvalidGlob { pattern, message } => {
+                write!(f, "Wildcard pattern {:?} is invalid: {}", pattern, message)
+            },
+            CoordinateOutOfRange { coordinate, value } => write!(
+                f,
+                "The {} {} is outside of the valid range from -{} to {} degrees",
+                coordinate,
+                value,
+                coordinate.bound(),
+                coordinate.bound()
+            ),
+            UnknownComparisonOperator(operator) => write!(
+                f,
+                "Unknown comparison operator {:?}, expected one of \"==\", \"!=\", \">\", \"<\", \">=\" or \"<=\"",
+                operator
+            ),
+            ArgumentOutOfRange {
+                function,
+                argument,
+                expected,
+            } => write!(
+                f,
+                "The argument {} of {} is out of range, expected {}",
+                argument, function, expected
+            ),
+            RangeStepZero => write!(f, "The step of a range must not be zero"),
+            RangeTooLong { limit, length } => write!(
+                f,
+                "The range has {} elements, but ranges are limited to {} elements",
+                length, limit
+            ),
+            RecursionLimitExceeded { max_depth } => write!(
+                f,
+                "Exceeded the maximum recursion depth of {} nested evaluations",
+                max_depth
+            ),
+            ExpressionCycle { chain } => write!(
+                f,
+                "Named expressions reference each other in a cycle: {}",
+                chain.join(" -> ")
+            ),
+            ValueTooLarge { limit, attempted } => write!(
+                f,
+                "The evaluation needs {} bytes for its values, but is limited to {} bytes",
+                attempted, limit
+            ),
+            Cancelled => write!(f, "The evaluation was cancelled"),
+            InvalidExportArgument(argument) => write!(
+                f,
+                "Only variables can be exported, but got the expression {:?}",
+                argument
+            ),
             ContextNotManipulable => write!(f, "Cannot manipulate context"),
             IllegalEscapeSequence(string) => write!(f, "Illegal escape sequence: {}", string),
+            UnterminatedString(string) => {
+                write!(f, "Unterminated string literal: \"{}", string)
+            },
+            UnterminatedComment(text) => write!(f, "Unterminated block comment: /*{}", text),
+            InFunctionCall {
+                function,
+                argument,
+                source,
+            } => write!(
+                f,
+                "in call to '{}' (argument {}): {}",
+                function, argument, source
+            ),
+            TupleIndexOutOfBounds { index, length } => write!(
+                f,
+                "Tuple index {} is out of bounds for a tuple of length {}",
+                index, length
+            ),
+            InTupleElement { index, source } => {
+                write!(f, "in tuple element {}: {}", index, source)
+            },
+            DestructuringLengthMismatch { expected, actual } => write!(
+                f,
+                "Cannot assign a tuple of {} elements to a tuple of {} variables",
+                actual, expected
+            ),
+            NonFiniteFloatInJson(value) => write!(
+                f,
+                "The float {} cannot be converted to JSON, as JSON has no NaN or infinite numbers",
+                value
+            ),
+            IntegerLiteralOutOfRange(literal) => write!(
+                f,
+                "The integer literal {} is out of range, the allowed range is {} to {}",
+                literal,
+                IntType::MIN,
+                IntType::MAX
+            ),
+            IntOutOfRange { value } => write!(
+                f,
+                "The integer {} is out of range, the allowed range is {} to {}",
+                value,
+                IntType::MIN,
+                IntType::MAX
+            ),
+            InvalidNumberLiteral(literal) => write!(f, "Invalid number literal: {}", literal),
+            InvalidCustomLiteral {
+                literal,
+                span,
+                source,
+            } => write!(
+                f,
+                "Invalid custom literal {} at offset {}: {}",
+                literal, span.start, source
+            ),
+            NoContextSucceeded { errors } => {
+                write!(f, "The evaluation failed with all {} contexts", errors.len())?;
+                for (index, error) in errors.iter().enumerate() {
+                    write!(f, "; context {}: {}", index, error)?;
+                }
+                Ok(())
+            },
+            TypeCheckFailed { errors } => {
+                write!(f, "The expression failed the type check")?;
+                for error in errors {
+                    write!(f, "; {}", error)?;
+                }
+                Ok(())
+            },
             CustomMessage(message) => write!(f, "Error: {}", message),
         }
     }
 }
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Coordinate::Latitude => write!(f, "latitude"),
+            Coordinate::Longitude => write!(f, "longitude"),
+        }
+    }
+}
+
+impl EvalexprError {
+    /// Returns a message that explains this error to end users.
+    ///
+    /// Unlike the `Display` implementation, the message does not contain any values, identifiers or other parts of the expression.
+    /// It only depends on the variant of the error, so it is safe to show to untrusted users and stays the same between evaluations.
+    /// For `InFunctionCall`, it is the message of the innermost error.
+    /// This includes `CustomMessage`, as its message may contain arbitrary details.
+    pub fn user_message(&self) -> String {
+        use EvalexprError::*;
+        match self {
+            WrongOperatorArgumentAmount { .. } => {
+                "An operator in this expression has the wrong number of arguments."
+            },
+            WrongFunctionArgumentAmount { .. } | WrongFunctionArgumentRange { .. } => {
+                "A function in this expression is called with the wrong number of arguments."
+            },
+            ExpectedString { .. } => "This expression expects a string here.",
+            ExpectedInt { .. } => "This expression expects an integer here.",
+            ExpectedFloat { .. } => "This expression expects a floating point number here.",
+            ExpectedNumber { .. } => "This expression expects a number here.",
+            ExpectedNumberOrString { .. } => "This expression expects a number or a string here.",
+            ExpectedBoolean { .. } => "This expression expects true or false here.",
+            ExpectedTuple { .. } => "This expression expects a tuple here.",
+            ExpectedEmpty { .. } => "This expression expects an empty value here.",
+            AppendedToLeafNode | PrecedenceViolation => "This expression could not be parsed.",
+            VariableIdentifierNotFound { .. } => {
+                "This expression uses a variable that is not defined."
+            },
+            FunctionIdentifierNotFound(_) => "This expression uses a function that is not defined.",
+            UnknownNamedArgument { .. } => {
+                "A function in this expression is called with an argument name it does not know."
+            },
+            DuplicateArgument { .. } => {
+                "A function in this expression is given the same argument more than once."
+            },
+            MissingArgument { .. } => "A function in this expression is missing an argument.",
+            DuplicateParameter(_) => "A function has two parameters with the same name.",
+            NonTrailingDefault(_) => {
+                "A function has a parameter with a default value before one without."
+            },
+            TypeError { .. } => "A value in this expression has the wrong type.",
+            UnmatchedLBrace { .. } => "An opening parenthesis in this expression is never closed.",
+            UnmatchedRBrace { .. } => "A closing parenthesis in this expression was never opened.",
+            UnmatchedQuestionMark { .. } => "A conditional in this expression has no ':' branch.",
+            UnmatchedColon { .. } => "A ':' in this expression does not belong to a conditional.",
+            UnmatchedLCurlyBrace { .. } => "A block in this expression is never closed with '}'.",
+            UnmatchedRCurlyBrace { .. } => "A '}' in this expression does not close a block.",
+            MissingOperator { .. } => {
+                "Two values in this expression are missing an operator between them."
+            },
+            InvalidIfExpression { .. } => "An 'if' or 'else' in this expression is malformed.",
+            TrailingComma { .. } => "This expression contains a comma without a following value.",
+            LeadingComma { .. } => "This expression contains a comma without a preceding value.",
+            PositionalArgumentAfterNamed { .. } => {
+                "A function in this expression has an unnamed argument after a named one."
+            },
+            InvalidAssignmentTarget { .. } => {
+                "This expression assigns to something that is not a variable."
+            },
+            AssignmentToKeyword { .. } => "This expression assigns to a reserved keyword.",
+            KeywordAsValue { .. } => "This expression uses a reserved keyword as a variable.",
+            SuspiciousComparisonChain { .. } => {
+                "This expression compares the result of a comparison without parentheses."
+            },
+            AdditionError { .. } => "An addition in this expression failed.",
+            SubtractionError { .. } => "A subtraction in this expression failed.",
+            NegationError { .. } => "A negation in this expression failed.",
+            MultiplicationError { .. } => "A multiplication in this expression failed.",
+            DivisionError { .. } => "A division in this expression failed.",
+            ModulationError { .. } => "A modulo operation in this expression failed.",
+            ExponentiationError { .. } => "An exponentiation in this expression failed.",
+            ShiftAmountOutOfRange { .. } => {
+                "A bit shift in this expression shifts by an invalid amount of bits."
+            },
+            NaNProduced { .. } => "A calculation in this expression has no defined result.",
+            IntegerOverflow { .. } => {
+                "A calculation in this expression produces an integer that is too large."
+            },
+            InvalidRegex { .. } => "This expression contains an invalid regular expression.",
+            OperatorNeedsFeature { .. } => {
+                "This expression uses an operator that is not available in this build."
+            },
+            InvalidGlob { .. } => "This expression contains an invalid wildcard pattern.",
+            CoordinateOutOfRange { .. } => {
+                "A latitude or longitude in this expression is out of range."
+            },
+            UnknownComparisonOperator(_) => "This expression uses an unknown comparison operator.",
+            ArgumentOutOfRange { .. } => {
+                "A function in this expression is called with an argument that is out of range."
+            },
+            RangeStepZero => "This expression uses a range with a step of zero.",
+            RangeTooLong { .. } => "This expression uses a range with too many elements.",
+            RecursionLimitExceeded { .. } => "This expression nests too many evaluations.",
+            ExpressionCycle { .. } => "Named expressions refer to each other in a cycle.",
+            ValueTooLarge { .. } => "This expression produces values that are too large.",
+            Cancelled => "The evaluation of this expression was cancelled.",
+            InvalidExportArgument(_) => "This expression exports something that is not a variable.",
+            ContextNotManipulable => "This expression may not assign variables.",
+            IllegalEscapeSequence(_) => {
+                "A string in this expression contains an illegal escape sequence."
+            },
+            UnterminatedString(_) => "A string in this expression is missing its closing quote.",
+            UnterminatedComment(_) => "A comment in this expression is missing its closing `*/`.",
+            InFunctionCall { source, .. } => return source.user_message(),
+            TupleIndexOutOfBounds { .. } => "A tuple in this expression has too few elements.",
+            InTupleElement { source, .. } => return source.user_message(),
+            DestructuringLengthMismatch { .. } => {
+                "This expression assigns a tuple to a different amount of variables."
+            },
+            NonFiniteFloatInJson(_) => {
+                "This expression produces a number that cannot be represented as JSON."
+            },
+            IntegerLiteralOutOfRange(_) => "An integer in this expression is too large.",
+            IntOutOfRange { .. } => "A number given to this expression is too large.",
+            InvalidNumberLiteral(_) => "A number in this expression is malformed.",
+            InvalidCustomLiteral { .. } => "A literal in this expression is malformed.",
+            NoContextSucceeded { .. } => {
+                "This expression could not be evaluated with any of the given contexts."
+            },
+            TypeCheckFailed { .. } => "This expression produces a value of the wrong type.",
+            CustomMessage(_) => "This expression could not be evaluated.",
+        }
+        .to_string()
+    }
+
+    /// Returns a copy of this error where all embedded strings are shortened to at most `max_chars` characters.
+    ///
+    /// This affects string values, identifiers, messages and the strings within tuples.
+    /// Shortened strings end with `...`, which is not counted towards `max_chars`.
+    /// Use this before displaying or logging an error, to keep long user-provided strings out of the detailed message.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let error = eval("\"a very long secret string\" - 1").unwrap_err();
+    /// assert_eq!(
+    ///     error.truncate_strings(6).to_string(),
+    ///     "Expected a Value::Float or Value::Int, but got String(\"a very...\")."
+    /// );
+    /// ```
+    pub fn truncate_strings(&self, max_chars: usize) -> Self {
+        use EvalexprError::*;
+        let string = |string: &String| truncate_string(string, max_chars);
+        let value = |value: &Value| truncate_value(value, max_chars);
+        match self {
+            ExpectedString { actual } => ExpectedString {
+                actual: value(actual),
+            },
+            ExpectedInt { actual } => ExpectedInt {
+                actual: value(actual),
+            },
+            ExpectedFloat { actual } => ExpectedFloat {
+                actual: value(actual),
+            },
+            ExpectedNumber { actual } => ExpectedNumber {
+                actual: value(actual),
+            },
+            ExpectedNumberOrString { actual } => ExpectedNumberOrString {
+                actual: value(actual),
+            },
+            ExpectedBoolean { actual } => ExpectedBoolean {
+                actual: value(actual),
+            },
+            ExpectedTuple { actual } => ExpectedTuple {
+                actual: value(actual),
+            },
+            ExpectedEmpty { actual } => ExpectedEmpty {
+                actual: value(actual),
+            },
+            VariableIdentifierNotFound { identifier, span } => VariableIdentifierNotFound {
+                identifier: string(identifier),
+                span: span.clone(),
+            },
+            FunctionIdentifierNotFound(identifier) => {
+                FunctionIdentifierNotFound(string(identifier))
+            },
+            UnknownNamedArgument { function, argument } => UnknownNamedArgument {
+                function: string(function),
+                argument: string(argument),
+            },
+            DuplicateArgument {
+                function,
+                parameter,
+            } => DuplicateArgument {
+                function: string(function),
+                parameter: string(parameter),
+            },
+            MissingArgument {
+                function,
+                parameter,
+            } => MissingArgument {
+                function: string(function),
+                parameter: string(parameter),
+            },
+            DuplicateParameter(name) => DuplicateParameter(string(name)),
+            NonTrailingDefault(name) => NonTrailingDefault(string(name)),
+            WrongFunctionArgumentRange {
+                minimum,
+                maximum,
+                actual,
+                optional,
+            } => WrongFunctionArgumentRange {
+                minimum: *minimum,
+                maximum: *maximum,
+                actual: *actual,
+                optional: optional.iter().map(string).collect(),
+            },
+            TypeError { expected, actual } => TypeError {
+                expected: expected.iter().map(value).collect(),
+                actual: value(actual),
+            },
+            SuspiciousComparisonChain { expression } => SuspiciousComparisonChain {
+                expression: string(expression),
+            },
+            AdditionError { augend, addend } => AdditionError {
+                augend: value(augend),
+                addend: value(addend),
+            },
+            SubtractionError {
+                minuend,
+                subtrahend,
+            } => SubtractionError {
+                minuend: value(minuend),
+                subtrahend: value(subtrahend),
+            },
+            NegationError { argument } => NegationError {
+                argument: value(argument),
+            },
+            MultiplicationError {
+                multiplicand,
+                multiplier,
+            } => MultiplicationError {
+                multiplicand: value(multiplicand),
+                multiplier: value(multiplier),
+            },
+            DivisionError { dividend, divisor } => DivisionError {
+                dividend: value(dividend),
+                divisor: value(divisor),
+            },
+            ModulationError { dividend, divisor } => ModulationError {
+                dividend: value(dividend),
+                divisor: value(divisor),
+            },
+            ExponentiationError { base, exponent } => ExponentiationError {
+                base: value(base),
+                exponent: value(exponent),
+            },
+            NaNProduced { operator, operands } => NaNProduced {
+                operator: string(operator),
+                operands: operands.iter().map(value).collect(),
+            },
+            IntegerOverflow {
+                function,
+                arguments,
+            } => IntegerOverflow {
+                function: string(function),
+                arguments: arguments.iter().map(value).collect(),
+            },
+            InvalidRegex { regex, message } => InvalidRegex {
+                regex: string(regex),
+                message: string(message),
+            },
+            InvalidGlob { pattern, message } => InvalidGlob {
+                pattern: string(pattern),
+                message: string(message),
+            },
+            UnknownComparisonOperator(operator) => UnknownComparisonOperator(string(operator)),
+            ArgumentOutOfRange {
+                function,
+                argument,
+                expected,
+            } => ArgumentOutOfRange {
+                function: string(function),
+                argument: value(argument),
+                expected: string(expected),
+            },
+            ExpressionCycle { chain } => ExpressionCycle {
+                chain: chain.iter().map(string).collect(),
+            },
+            InvalidExportArgument(argument) => InvalidExportArgument(string(argument)),
+            IllegalEscapeSequence(sequence) => IllegalEscapeSequence(string(sequence)),
+            UnterminatedString(text) => UnterminatedString(string(text)),
+            UnterminatedComment(text) => UnterminatedComment(string(text)),
+            InFunctionCall {
+                function,
+                argument,
+                source,
+            } => InFunctionCall {
+                function: string(function),
+                argument: value(argument),
+                source: Box::new(source.truncate_strings(max_chars)),
+            },
+            InTupleElement { index, source } => InTupleElement {
+                index: *index,
+                source: Box::new(source.truncate_strings(max_chars)),
+            },
+            NoContextSucceeded { errors } => NoContextSucceeded {
+                errors: errors
+                    .iter()
+                    .map(|error| error.truncate_strings(max_chars))
+                    .collect(),
+            },
+            TypeCheckFailed { errors } => TypeCheckFailed {
+                errors: errors
+                    .iter()
+                    .map(|error| truncate_type_check_error(error, max_chars))
+                    .collect(),
+            },
+            IntegerLiteralOutOfRange(literal) => IntegerLiteralOutOfRange(string(literal)),
+            IntOutOfRange { value } => IntOutOfRange {
+                value: string(value),
+            },
+            InvalidNumberLiteral(literal) => InvalidNumberLiteral(string(literal)),
+            InvalidCustomLiteral {
+                literal,
+                span,
+                source,
+            } => InvalidCustomLiteral {
+                literal: string(literal),
+                span: span.clone(),
+                source: Box::new(source.truncate_strings(max_chars)),
+            },
+            CustomMessage(message) => CustomMessage(string(message)),
+            WrongOperatorArgumentAmount { .. }
+            | WrongFunctionArgumentAmount { .. }
+            | AppendedToLeafNode
+            | PrecedenceViolation
+            | UnmatchedLBrace { .. }
+            | UnmatchedRBrace { .. }
+            | UnmatchedQuestionMark { .. }
+            | UnmatchedColon { .. }
+            | UnmatchedLCurlyBrace { .. }
+            | UnmatchedRCurlyBrace { .. }
+            | MissingOperator { .. }
+            | InvalidIfExpression { .. }
+            | TrailingComma { .. }
+            | LeadingComma { .. }
+            | PositionalArgumentAfterNamed { .. }
+            | InvalidAssignmentTarget { .. }
+            | AssignmentToKeyword { .. }
+            | KeywordAsValue { .. }
+            | OperatorNeedsFeature { .. }
+            | CoordinateOutOfRange { .. }
+            | ShiftAmountOutOfRange { .. }
+            | RangeStepZero
+            | RangeTooLong { .. }
+            | RecursionLimitExceeded { .. }
+            | ValueTooLarge { .. }
+            | Cancelled
+            | ContextNotManipulable
+            | TupleIndexOutOfBounds { .. }
+            | DestructuringLengthMismatch { .. }
+            | NonFiniteFloatInJson(_) => self.clone(),
+        }
+    }
+}
+
+fn truncate_string(string: &str, max_chars: usize) -> String {
+    match string.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &string[..end]),
+        None => string.to_string(),
+    }
+}
+
+fn truncate_type_check_error(error: &TypeCheckError, max_chars: usize) -> TypeCheckError {
+    use typecheck::TypeCheckError::*;
+    match error {
+        FunctionArgumentType {
+            function,
+            position,
+            expected,
+            actual,
+            span,
+        } => FunctionArgumentType {
+            function: truncate_string(function, max_chars),
+            position: *position,
+            expected: *expected,
+            actual: *actual,
+            span: span.clone(),
+        },
+        FunctionArgumentAmount {
+            function,
+            expected,
+            actual,
+            span,
+        } => FunctionArgumentAmount {
+            function: truncate_string(function, max_chars),
+            expected: *expected,
+            actual: *actual,
+            span: span.clone(),
+        },
+        UnknownVariable { identifier, span } => UnknownVariable {
+            identifier: truncate_string(identifier, max_chars),
+            span: span.clone(),
+        },
+        UnknownFunction { function, span } => UnknownFunction {
+            function: truncate_string(function, max_chars),
+            span: span.clone(),
+        },
+        OperatorArgumentTypes { .. } | OperatorArgumentAmount { .. } | ResultType { .. } => {
+            error.clone()
+        },
+    }
+}
+
+fn truncate_value(value: &Value, max_chars: usize) -> Value {
+    match value {
+        Value::String(string) => Value::String(truncate_string(string, max_chars)),
+        Value::Tuple(tuple) => Value::Tuple(
+            tuple
+                .iter()
+                .map(|value| truncate_value(value, max_chars))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
@@ -5,15 +5,18 @@
 //! The module also contains some helper functions starting with `expect_` that check for a condition and return `Err(_)` if the condition is not fulfilled.
 //! They are meant as shortcuts to not write the same error checking code everywhere.
 
-use token::PartialToken;
-use value::{value_type::ValueType, TupleType};
+use std::ops::Range;
+
+use typecheck::TypeCheckError;
+use value::{value_type::ValueType, FloatType, IntType, TupleType};
 
 use crate::value::Value;
 
 mod display;
+mod render;
 
 /// Errors used in this crate.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum EvalexprError {
     /// An operator was called with a wrong amount of arguments.
     WrongOperatorArgumentAmount {
@@ -31,6 +34,18 @@ pub enum EvalexprError {
         actual: usize,
     },
 
+    /// A function whose trailing parameters have default values was called with fewer arguments than it has required parameters, or with more arguments than it has parameters.
+    WrongFunctionArgumentRange {
+        /// The amount of required parameters.
+        minimum: usize,
+        /// The amount of all parameters.
+        maximum: usize,
+        /// The actual amount of arguments.
+        actual: usize,
+        /// The names of the parameters that have default values.
+        optional: Vec<String>,
+    },
+
     /// A string value was expected.
     ExpectedString {
         /// The actual value.
@@ -91,11 +106,49 @@ pub enum EvalexprError {
     PrecedenceViolation,
 
     /// A `VariableIdentifier` operation did not find its value in the context.
-    VariableIdentifierNotFound(String),
+    VariableIdentifierNotFound {
+        /// The identifier of the variable.
+        identifier: String,
+        /// The range of bytes of the expression where the variable is read.
+        /// It is `None` if the variable was not read by a node of an operator tree built from source, like when an `Operator` is evaluated directly.
+        span: Option<Range<usize>>,
+    },
 
     /// A `FunctionIdentifier` operation did not find its value in the context.
     FunctionIdentifierNotFound(String),
 
+    /// A function is called with a named argument that does not name one of its parameters, like `round_to(1.5, digits = 1)`.
+    /// Functions without parameters named by `Function::with_parameters`, like builtin functions, accept no named arguments at all.
+    UnknownNamedArgument {
+        /// The identifier of the function.
+        function: String,
+        /// The name of the argument.
+        argument: String,
+    },
+
+    /// A function is called with two arguments for the same parameter, like `clamp(x, lo = 1, lo = 2)`, or `clamp(1, x = 2)` where `x` is the first parameter.
+    DuplicateArgument {
+        /// The identifier of the function.
+        function: String,
+        /// The name of the parameter.
+        parameter: String,
+    },
+
+    /// A function is called with named arguments, but without an argument for one of its parameters that has no default value.
+    MissingArgument {
+        /// The identifier of the function.
+        function: String,
+        /// The name of the parameter.
+        parameter: String,
+    },
+
+    /// `Function::with_parameters` was given two parameters with the same name.
+    DuplicateParameter(String),
+
+    /// `Function::with_parameters` was given a parameter with a default value that is followed by a parameter without one.
+    /// Only trailing parameters may have default values, such that calls can omit them.
+    NonTrailingDefault(String),
+
     /// A value has the wrong type.
     /// Only use this if there is no other error that describes the expected and provided types in more detail.
     TypeError {
@@ -106,19 +159,102 @@ pub enum EvalexprError {
     },
 
     /// An opening brace without a matching closing brace was found.
-    UnmatchedLBrace,
+    UnmatchedLBrace {
+        /// The range of bytes of the expression where the opening brace is.
+        span: Range<usize>,
+    },
 
     /// A closing brace without a matching opening brace was found.
-    UnmatchedRBrace,
+    UnmatchedRBrace {
+        /// The range of bytes of the expression where the closing brace is.
+        span: Range<usize>,
+    },
+
+    /// A question mark of a conditional, like in `a ? b : c`, without a matching colon was found.
+    UnmatchedQuestionMark {
+        /// The range of bytes of the expression where the question mark is.
+        span: Range<usize>,
+    },
+
+    /// A colon without a matching question mark of a conditional was found, like in `a : b` or `a ? (b : c)`.
+    UnmatchedColon {
+        /// The range of bytes of the expression where the colon is.
+        span: Range<usize>,
+    },
+
+    /// An opening curly brace of a block without a matching closing curly brace was found.
+    UnmatchedLCurlyBrace {
+        /// The range of bytes of the expression where the opening curly brace is.
+        span: Range<usize>,
+    },
+
+    /// A closing curly brace without a matching opening curly brace was found.
+    UnmatchedRCurlyBrace {
+        /// The range of bytes of the expression where the closing curly brace is.
+        span: Range<usize>,
+    },
+
+    /// A value directly follows another value without an operator between them, like in `1 2`, `(a)(b)` or `1 f(2)`.
+    /// Only an identifier takes the value after it as its argument, like in `f(2)` or `f 2`.
+    MissingOperator {
+        /// The range of bytes of the expression where the second value starts.
+        span: Range<usize>,
+    },
+
+    /// An if-expression is malformed, like `if a 1`, `if a { 1 } else 2` or `else { 2 }`.
+    /// Each `if` needs a condition followed by a block in curly braces, and each `else` needs to follow the block of an `if` and to be followed by a block or another `if`.
+    InvalidIfExpression {
+        /// The range of bytes of the expression where the `if`, `else` or block that is out of place is.
+        span: Range<usize>,
+    },
+
+    /// A comma is not followed by another element of the tuple, like in `1, 2,` or `a = 1, 2,; a`.
+    /// A trailing comma directly before a closing brace is allowed, like in `(1,)`.
+    TrailingComma {
+        /// The range of bytes of the expression where the trailing comma is.
+        span: Range<usize>,
+    },
+
+    /// A comma is not preceded by an element of the tuple, like in `(,)`, `(, 1)` or `(a,, b)`.
+    LeadingComma {
+        /// The range of bytes of the expression where the comma is.
+        span: Range<usize>,
+    },
+
+    /// A positional argument of a function call follows a named argument, like in `f(x = 1, 2)`.
+    PositionalArgumentAfterNamed {
+        /// The range of bytes of the expression where the positional argument is.
+        span: Range<usize>,
+    },
+
+    /// The left-hand side of an assignment is not a single variable identifier, like in `1 = 2`, `(a) = 2` or `a + b = 2`.
+    InvalidAssignmentTarget {
+        /// The range of bytes of the expression where the assignment operator is.
+        span: Range<usize>,
+    },
+
+    /// One of the `HARD_KEYWORDS`, like `true`, is the target of an assignment, like in `true = 1`.
+    AssignmentToKeyword {
+        /// The keyword.
+        keyword: String,
+        /// The range of bytes of the expression where the keyword is.
+        span: Range<usize>,
+    },
+
+    /// One of the `HARD_KEYWORDS` that denotes an operator, like `xor`, is used in place of a value, like in `xor + 1` or `f(xor)`.
+    KeywordAsValue {
+        /// The keyword.
+        keyword: String,
+        /// The range of bytes of the expression where the keyword is.
+        span: Range<usize>,
+    },
 
-    /// A `PartialToken` is unmatched, such that it cannot be combined into a full `Token`.
-    /// This happens if for example a single `=` is found, surrounded by whitespace.
-    /// It is not a token, but it is part of the string representation of some tokens.
-    UnmatchedPartialToken {
-        /// The unmatched partial token.
-        first: PartialToken,
-        /// The token that follows the unmatched partial token and that cannot be matched to the partial token, or `None`, if `first` is the last partial token in the stream.
-        second: Option<PartialToken>,
+    /// An operand of a comparison is another comparison without parentheses, like in `a == b == c` or `x < y > z`.
+    /// Such an expression compares the boolean result of the inner comparison, which is rarely intended.
+    /// It can be allowed by parenthesizing the inner comparison, or with `ParseConfig::allow_comparison_chains`.
+    SuspiciousComparisonChain {
+        /// The comparison with the other comparison as operand, written like by `Node::to_minified_string`, which parenthesizes the inner comparison.
+        expression: String,
     },
 
     /// An addition operation performed by Rust failed.
@@ -152,6 +288,7 @@ pub enum EvalexprError {
     },
 
     /// A division operation performed by Rust failed.
+    /// Integer divisions fail if the divisor is zero, or if the quotient is out of range like for `IntType::MIN / -1`.
     DivisionError {
         /// The first argument of the division.
         dividend: Value,
@@ -160,6 +297,7 @@ pub enum EvalexprError {
     },
 
     /// A modulation operation performed by Rust failed.
+    /// Integer modulo operations fail if the divisor is zero, and for `IntType::MIN % -1`.
     ModulationError {
         /// The first argument of the modulation.
         dividend: Value,
@@ -167,6 +305,39 @@ pub enum EvalexprError {
         divisor: Value,
     },
 
+    /// An exponentiation operation performed by Rust failed.
+    /// Integer exponentiation fails if the power is out of the range of `IntType`.
+    ExponentiationError {
+        /// The base of the exponentiation.
+        base: Value,
+        /// The exponent of the exponentiation.
+        exponent: Value,
+    },
+
+    /// A bit shift was given an amount of bits that is negative, or not less than the amount of bits of `IntType`.
+    ShiftAmountOutOfRange {
+        /// The integer that was shifted.
+        value: IntType,
+        /// The amount of bits it was shifted by.
+        amount: IntType,
+    },
+
+    /// An operator or a builtin function produced NaN from operands that are not NaN, while `EvalConfig::nan_policy` is `NanPolicy::Error`.
+    NaNProduced {
+        /// The operator, like `"/"`, or the identifier of the builtin function.
+        operator: String,
+        /// The operands of the operator, or the arguments of the function.
+        operands: Vec<Value>,
+    },
+
+    /// A builtin function computed an integer that is out of the range of `IntType`, like `abs` of `IntType::MIN`.
+    IntegerOverflow {
+        /// The identifier of the builtin function.
+        function: String,
+        /// The arguments of the function.
+        arguments: Vec<Value>,
+    },
+
     /// A regular expression could not be parsed
     InvalidRegex {
         /// The invalid regular expression
@@ -175,17 +346,334 @@ pub enum EvalexprError {
         message: String,
     },
 
+    /// An operator was evaluated, but it needs a feature of this crate that is disabled, like `=~` without the `builtin_regex` feature.
+    OperatorNeedsFeature {
+        /// The operator.
+        operator: String,
+        /// The feature that the operator needs.
+        feature: String,
+    },
+
+    /// A shell-style wildcard pattern could not be parsed.
+    InvalidGlob {
+        /// The invalid pattern.
+        pattern: String,
+        /// A description of what is wrong with the pattern.
+        message: String,
+    },
+
+    /// A latitude or longitude is outside of its valid range.
+    CoordinateOutOfRange {
+        /// Whether the coordinate is a latitude or a longitude.
+        coordinate: Coordinate,
+        /// The invalid value of the coordinate.
+        value: FloatType,
+    },
+
+    /// A function was given a name of a comparison operator that it does not know.
+    UnknownComparisonOperator(String),
+
+    /// A builtin function was called with an argument that has the right type, but a value that the function does not accept.
+    ArgumentOutOfRange {
+        /// The identifier of the function.
+        function: String,
+        /// The argument that is out of range.
+        argument: Value,
+        /// A description of the accepted values.
+        expected: String,
+    },
+
+    /// The `range` function was called with a step of zero.
+    RangeStepZero,
+
+    /// A range expression like `0..n` has more elements than configured by `EvalConfig::max_range_length`.
+    /// The evaluation fails before the elements are allocated.
+    RangeTooLong {
+        /// The maximum amount of elements that was configured for ranges.
+        limit: usize,
+        /// The amount of elements of the range, saturated at `usize::MAX`.
+        length: usize,
+    },
+
+    /// The maximum amount of nested evaluations was exceeded.
+    /// Evaluations get nested if a user-defined function evaluates another expression.
+    RecursionLimitExceeded {
+        /// The maximum recursion depth that was configured for the evaluation.
+        max_depth: usize,
+    },
+
+    /// Named expressions reference each other in a cycle.
+    ExpressionCycle {
+        /// The identifiers of the named expressions in the cycle, starting and ending with the same identifier.
+        chain: Vec<String>,
+    },
+
+    /// The values produced by an evaluation need more memory than configured by `EvalConfig::max_result_bytes`.
+    /// The evaluation is stopped before the memory that exceeds the limit is allocated.
+    ValueTooLarge {
+        /// The maximum amount of bytes that was configured for the evaluation.
+        limit: usize,
+        /// The amount of bytes that the evaluation would have allocated in total.
+        attempted: usize,
+    },
+
+    /// The evaluation was cancelled via the `EvalConfig::cancellation_token`.
+    Cancelled,
+
+    /// A script called `export` with an argument that is not a variable identifier.
+    InvalidExportArgument(String),
+
     /// A modification was attempted on a `Context` that does not allow modifications.
     ContextNotManipulable,
 
     /// An escape sequence within a string literal is illegal.
     IllegalEscapeSequence(String),
 
+    /// A string literal is missing its terminating double quote.
+    /// The string contains the text of the expression after the opening double quote.
+    UnterminatedString(String),
+
+    /// A block comment is missing its terminating `*/`.
+    /// The string contains the text of the expression after the opening `/*`.
+    UnterminatedComment(String),
+
+    /// A function from the context failed.
+    /// The error of the function is the `source`, which may itself be an `InFunctionCall` if the function evaluated another expression that failed within a function.
+    ///
+    /// Errors that abort the whole evaluation, namely `Cancelled`, `RecursionLimitExceeded` and `ValueTooLarge`, are not wrapped.
+    InFunctionCall {
+        /// The identifier of the function that failed.
+        function: String,
+        /// The argument that the function was called with.
+        argument: Value,
+        /// The error returned by the function.
+        source: Box<EvalexprError>,
+    },
+
+    /// An index into a tuple, like the one given to `Value::get_int`, is not smaller than the length of the tuple.
+    TupleIndexOutOfBounds {
+        /// The index.
+        index: usize,
+        /// The length of the tuple.
+        length: usize,
+    },
+
+    /// An element of a tuple, like the one read by `Value::get_int`, has the wrong type.
+    /// The error about the element is the `source`.
+    InTupleElement {
+        /// The index of the element.
+        index: usize,
+        /// The error about the element.
+        source: Box<EvalexprError>,
+    },
+
+    /// A tuple is assigned to a tuple of variables with a different amount of elements, like in `(a, b) = (1, 2, 3)`.
+    DestructuringLengthMismatch {
+        /// The amount of variables, or of nested tuples of variables, that are assigned to.
+        expected: usize,
+        /// The amount of elements of the assigned tuple.
+        actual: usize,
+    },
+
+    /// A float is NaN or infinite, and can therefore not be converted to a JSON number.
+    NonFiniteFloatInJson(FloatType),
+
+    /// An integer literal is out of the range of `IntType`.
+    /// The policy for such literals can be configured with `ParseConfig::integer_overflow_policy`.
+    IntegerLiteralOutOfRange(String),
+
+    /// An integer of a Rust type other than `IntType`, like `u64`, is out of the range of `IntType`, so it cannot be converted into a `Value` with `TryFrom`.
+    IntOutOfRange {
+        /// The exact integer, written in decimal.
+        value: String,
+    },
+
+    /// A literal starts like a number, but is not a valid number, like `1e` that is missing the digits of its exponent.
+    InvalidNumberLiteral(String),
+
+    /// The converter of a `CustomLiteral` from `ParseConfig::custom_literals` failed for a literal of the expression.
+    /// The error of the converter is the `source`.
+    InvalidCustomLiteral {
+        /// The text of the literal.
+        literal: String,
+        /// The range of bytes of the expression where the literal is.
+        span: Range<usize>,
+        /// The error returned by the converter.
+        source: Box<EvalexprError>,
+    },
+
+    /// An expression failed with each of the contexts it was evaluated with by `Node::eval_first_success`.
+    NoContextSucceeded {
+        /// The errors of the evaluations, in the order of the contexts.
+        errors: Vec<EvalexprError>,
+    },
+
+    /// An expression did not pass the type check of `TypedExpression::parse`.
+    TypeCheckFailed {
+        /// The type errors of the expression, or a `TypeCheckError::ResultType` if the expression cannot produce the required type.
+        errors: Vec<TypeCheckError>,
+    },
+
     /// A custom error explained by its message.
     CustomMessage(String),
 }
 
+/// The kind of a coordinate in `EvalexprError::CoordinateOutOfRange`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coordinate {
+    /// A latitude, which is valid within `[-90, 90]` degrees.
+    Latitude,
+    /// A longitude, which is valid within `[-180, 180]` degrees.
+    Longitude,
+}
+
+impl Coordinate {
+    /// Returns the largest absolute value in degrees that a coordinate of this kind may have.
+    pub fn bound(self) -> FloatType {
+        match self {
+            Coordinate::Latitude => 90.0,
+            Coordinate::Longitude => 180.0,
+        }
+    }
+}
+
+/// Defines `EvalexprError::ALL_VARIANT_NAMES` and `EvalexprError::variant_name` from the same list of variants.
+/// The match in `variant_name` is exhaustive, so a variant that is missing from the list fails to compile.
+macro_rules! variant_names {
+    ($($variant:ident),* $(,)*) => {
+        impl EvalexprError {
+            /// The names of all variants of `EvalexprError`, in the order of their declaration.
+            pub const ALL_VARIANT_NAMES: &'static [&'static str] = &[$(stringify!($variant)),*];
+
+            /// Returns the name of the variant of this error, like `"DivisionError"`.
+            ///
+            /// Unlike the `Display` implementation, the name does not depend on the values in the error, so it is suited to group errors, for example to count them in metrics.
+            /// For `InFunctionCall`, this is the name of the wrapper, and the variant of the error returned by the function is found in its `source`.
+            ///
+            /// # Examples
+            ///
+            /// ```rust
+            /// use evalexpr::*;
+            /// use std::collections::HashMap;
+            ///
+            /// let mut error_counts: HashMap<&str, usize> = HashMap::new();
+            /// for expression in ["1 / 0", "2 / 0", "a + 1", "true + 1"] {
+            ///     if let Err(error) = eval(expression) {
+            ///         *error_counts.entry(error.variant_name()).or_default() += 1;
+            ///     }
+            /// }
+            /// assert_eq!(error_counts["DivisionError"], 2);
+            /// assert_eq!(error_counts["VariableIdentifierNotFound"], 1);
+            /// assert_eq!(error_counts["ExpectedNumberOrString"], 1);
+            /// ```
+            pub fn variant_name(&self) -> &'static str {
+                match self {
+                    $(EvalexprError::$variant { .. } => stringify!($variant),)*
+                }
+            }
+        }
+    };
+}
+
+variant_names!(
+    WrongOperatorArgumentAmount,
+    WrongFunctionArgumentAmount,
+    WrongFunctionArgumentRange,
+    ExpectedString,
+    ExpectedInt,
+    ExpectedFloat,
+    ExpectedNumber,
+    ExpectedNumberOrString,
+    ExpectedBoolean,
+    ExpectedTuple,
+    ExpectedEmpty,
+    AppendedToLeafNode,
+    PrecedenceViolation,
+    VariableIdentifierNotFound,
+    FunctionIdentifierNotFound,
+    UnknownNamedArgument,
+    DuplicateArgument,
+    MissingArgument,
+    DuplicateParameter,
+    NonTrailingDefault,
+    TypeError,
+    UnmatchedLBrace,
+    UnmatchedRBrace,
+    UnmatchedQuestionMark,
+    UnmatchedColon,
+    UnmatchedLCurlyBrace,
+    UnmatchedRCurlyBrace,
+    MissingOperator,
+    InvalidIfExpression,
+    TrailingComma,
+    LeadingComma,
+    PositionalArgumentAfterNamed,
+    InvalidAssignmentTarget,
+    AssignmentToKeyword,
+    KeywordAsValue,
+    SuspiciousComparisonChain,
+    AdditionError,
+    SubtractionError,
+    NegationError,
+    MultiplicationError,
+    DivisionError,
+    ModulationError,
+    ExponentiationError,
+    ShiftAmountOutOfRange,
+    NaNProduced,
+    IntegerOverflow,
+    InvalidRegex,
+    OperatorNeedsFeature,
+    InvalidGlob,
+    CoordinateOutOfRange,
+    UnknownComparisonOperator,
+    ArgumentOutOfRange,
+    RangeStepZero,
+    RangeTooLong,
+    RecursionLimitExceeded,
+    ExpressionCycle,
+    ValueTooLarge,
+    Cancelled,
+    InvalidExportArgument,
+    ContextNotManipulable,
+    IllegalEscapeSequence,
+    UnterminatedString,
+    UnterminatedComment,
+    InFunctionCall,
+    TupleIndexOutOfBounds,
+    InTupleElement,
+    DestructuringLengthMismatch,
+    NonFiniteFloatInJson,
+    IntegerLiteralOutOfRange,
+    IntOutOfRange,
+    InvalidNumberLiteral,
+    InvalidCustomLiteral,
+    NoContextSucceeded,
+    TypeCheckFailed,
+    CustomMessage,
+);
+
 impl EvalexprError {
+    /// Returns the name from `EvalexprError::ALL_VARIANT_NAMES` that equals the given name, or `None` if there is no variant with this name.
+    ///
+    /// This checks names that are read from a configuration, for example to filter errors by their variant with `EvalexprError::variant_name`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let ignored = EvalexprError::from_variant_name("DivisionError").unwrap();
+    /// assert_eq!(EvalexprError::from_variant_name("DivideError"), None);
+    /// assert_eq!(eval("1 / 0").unwrap_err().variant_name(), ignored);
+    /// ```
+    pub fn from_variant_name(name: &str) -> Option<&'static str> {
+        Self::ALL_VARIANT_NAMES
+            .iter()
+            .find(|variant_name| **variant_name == name)
+            .copied()
+    }
+
     pub(crate) fn wrong_operator_argument_amount(actual: usize, expected: usize) -> Self {
         EvalexprError::WrongOperatorArgumentAmount { actual, expected }
     }
@@ -251,11 +739,11 @@ impl EvalexprError {
         }
     }
 
-    pub(crate) fn unmatched_partial_token(
-        first: PartialToken,
-        second: Option<PartialToken>,
-    ) -> Self {
-        EvalexprError::UnmatchedPartialToken { first, second }
+    pub(crate) fn variable_identifier_not_found(identifier: String) -> Self {
+        EvalexprError::VariableIdentifierNotFound {
+            identifier,
+            span: None,
+        }
     }
 
     pub(crate) fn addition_error(augend: Value, addend: Value) -> Self {
@@ -288,10 +776,120 @@ impl EvalexprError {
         EvalexprError::ModulationError { dividend, divisor }
     }
 
+    pub(crate) fn exponentiation_error(base: Value, exponent: Value) -> Self {
+        EvalexprError::ExponentiationError { base, exponent }
+    }
+
+    pub(crate) fn shift_amount_out_of_range(value: IntType, amount: IntType) -> Self {
+        EvalexprError::ShiftAmountOutOfRange { value, amount }
+    }
+
+    /// Constructs `EvalexprError::CoordinateOutOfRange{coordinate, value}`.
+    pub fn coordinate_out_of_range(coordinate: Coordinate, value: FloatType) -> Self {
+        EvalexprError::CoordinateOutOfRange { coordinate, value }
+    }
+
+    pub(crate) fn in_function_call(function: &str, argument: &Value, source: Self) -> Self {
+        if source.aborts_evaluation() {
+            source
+        } else {
+            EvalexprError::InFunctionCall {
+                function: function.to_string(),
+                argument: argument.clone(),
+                source: Box::new(source),
+            }
+        }
+    }
+
+    /// Returns true if this error is about the evaluation as a whole rather than a part of the expression, namely `Cancelled`, `RecursionLimitExceeded` and `ValueTooLarge`.
+    pub(crate) fn aborts_evaluation(&self) -> bool {
+        matches!(
+            self,
+            EvalexprError::Cancelled
+                | EvalexprError::RecursionLimitExceeded { .. }
+                | EvalexprError::ValueTooLarge { .. }
+        )
+    }
+
+    /// Returns true if this error is about a variable or function that is not defined, namely `VariableIdentifierNotFound` and `FunctionIdentifierNotFound`.
+    pub(crate) fn is_lookup_error(&self) -> bool {
+        matches!(
+            self,
+            EvalexprError::VariableIdentifierNotFound { .. }
+                | EvalexprError::FunctionIdentifierNotFound(_)
+        )
+    }
+
+    /// Constructs `EvalexprError::NonFiniteFloatInJson(value)`.
+    pub fn non_finite_float_in_json(value: FloatType) -> Self {
+        EvalexprError::NonFiniteFloatInJson(value)
+    }
+
+    pub(crate) fn invalid_export_argument(argument: String) -> Self {
+        EvalexprError::InvalidExportArgument(argument)
+    }
+
+    /// Constructs `EvalexprError::UnknownComparisonOperator(operator)`.
+    pub fn unknown_comparison_operator(operator: String) -> Self {
+        EvalexprError::UnknownComparisonOperator(operator)
+    }
+
+    pub(crate) fn recursion_limit_exceeded(max_depth: usize) -> Self {
+        EvalexprError::RecursionLimitExceeded { max_depth }
+    }
+
+    pub(crate) fn expression_cycle(chain: Vec<String>) -> Self {
+        EvalexprError::ExpressionCycle { chain }
+    }
+
+    pub(crate) fn value_too_large(limit: usize, attempted: usize) -> Self {
+        EvalexprError::ValueTooLarge { limit, attempted }
+    }
+
+    pub(crate) fn range_too_long(limit: usize, length: u128) -> Self {
+        EvalexprError::RangeTooLong {
+            limit,
+            length: length.min(usize::MAX as u128) as usize,
+        }
+    }
+
+    pub(crate) fn nan_produced(operator: String, operands: Vec<Value>) -> Self {
+        EvalexprError::NaNProduced { operator, operands }
+    }
+
+    /// Constructs `EvalexprError::IntegerOverflow{function, arguments}`.
+    pub fn integer_overflow(function: &str, arguments: Vec<Value>) -> Self {
+        EvalexprError::IntegerOverflow {
+            function: function.to_string(),
+            arguments,
+        }
+    }
+
+    /// Constructs `EvalexprError::IntOutOfRange{value}` from the given integer.
+    pub fn int_out_of_range<T: ToString>(value: T) -> Self {
+        EvalexprError::IntOutOfRange {
+            value: value.to_string(),
+        }
+    }
+
+    /// Constructs `EvalexprError::ArgumentOutOfRange{function, argument, expected}`.
+    pub fn argument_out_of_range(function: &str, argument: Value, expected: String) -> Self {
+        EvalexprError::ArgumentOutOfRange {
+            function: function.to_string(),
+            argument,
+            expected,
+        }
+    }
+
     /// Constructs `EvalexprError::InvalidRegex(regex)`
     pub fn invalid_regex(regex: String, message: String) -> Self {
         EvalexprError::InvalidRegex { regex, message }
     }
+
+    /// Constructs `EvalexprError::InvalidGlob{pattern, message}`.
+    pub fn invalid_glob(pattern: String, message: String) -> Self {
+        EvalexprError::InvalidGlob { pattern, message }
+    }
 }
 
 /// Returns `Ok(())` if the actual and expected parameters are equal, and `Err(Error::WrongOperatorArgumentAmount)` otherwise.
@@ -361,7 +959,16 @@ pub fn expect_tuple(actual: &Value) -> EvalexprResult<&TupleType> {
     }
 }
 
-impl std::error::Error for EvalexprError {}
+impl std::error::Error for EvalexprError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EvalexprError::InFunctionCall { source, .. }
+            | EvalexprError::InTupleElement { source, .. }
+            | EvalexprError::InvalidCustomLiteral { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 /// Standard result type used by this crate.
 pub type EvalexprResult<T> = Result<T, EvalexprError>;
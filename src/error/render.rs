@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use EvalexprError;
+
+/// The amount of columns that a tab is rendered as.
+const TAB_WIDTH: usize = 4;
+
+/// The smallest amount of columns that a truncated line is shortened to, such that an ellipsis on each side leaves room for at least one character of the line.
+const MIN_WIDTH: usize = 3;
+
+impl EvalexprError {
+    /// Returns the range of bytes of the expression where this error is located, if it is known.
+    ///
+    /// Errors from building an operator tree, like `UnmatchedLBrace`, always have a span, and `VariableIdentifierNotFound` has one if it was returned by evaluating an operator tree built from source.
+    /// The span of an error that a function returned refers to another expression, so it is not reported for the `InFunctionCall` that wraps it.
+    pub fn span(&self) -> Option<Range<usize>> {
+        use EvalexprError::*;
+        match self {
+            VariableIdentifierNotFound { span, .. } => span.clone(),
+            UnmatchedLBrace { span }
+            | UnmatchedRBrace { span }
+            | UnmatchedQuestionMark { span }
+            | UnmatchedColon { span }
+            | UnmatchedLCurlyBrace { span }
+            | UnmatchedRCurlyBrace { span }
+            | MissingOperator { span }
+            | InvalidIfExpression { span }
+            | TrailingComma { span }
+            | LeadingComma { span }
+            | PositionalArgumentAfterNamed { span }
+            | InvalidAssignmentTarget { span }
+            | AssignmentToKeyword { span, .. }
+            | KeywordAsValue { span, .. }
+            | InvalidCustomLiteral { span, .. } => Some(span.clone()),
+            _ => None,
+        }
+    }
+
+    /// Renders this error for the given source expression in the style of compiler diagnostics.
+    ///
+    /// The rendering starts with the message of the error.
+    /// If the error has a `span` that lies within the source, then the rendering continues with the line and column of the span, the line of the source that contains it, and a line of carets that underlines the span.
+    /// Lines and columns start at 1, and columns count characters.
+    /// Tabs in the source line are rendered as four spaces, and the carets are aligned accordingly.
+    /// If the span continues on following lines, only its part on the first line is underlined.
+    ///
+    /// Errors without a span, or with a span that does not fit the given source, are rendered as their message only.
+    /// Use `render_with_source_and_width` to shorten long lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let source = "price * (1 + tax";
+    /// let error = build_operator_tree(source).unwrap_err();
+    /// assert_eq!(
+    ///     error.render_with_source(source),
+    ///     [
+    ///         "error: Found an unmatched opening parenthesis '(' at offset 8.",
+    ///         " --> 1:9",
+    ///         "  |",
+    ///         "1 | price * (1 + tax",
+    ///         "  |         ^",
+    ///     ]
+    ///     .join("\n")
+    /// );
+    /// ```
+    pub fn render_with_source(&self, source: &str) -> String {
+        self.render(source, None)
+    }
+
+    /// Renders this error for the given source expression like `render_with_source`, but shows at most `width` characters of the source line.
+    ///
+    /// Longer lines are cut around the start of the span, and each cut side is marked with an ellipsis `…` that takes the place of one character.
+    /// Widths below three are treated as three.
+    pub fn render_with_source_and_width(&self, source: &str, width: usize) -> String {
+        self.render(source, Some(width.max(MIN_WIDTH)))
+    }
+
+    fn render(&self, source: &str, width: Option<usize>) -> String {
+        let message = format!("error: {}", self);
+        let span = match self.span() {
+            Some(span)
+                if span.start <= span.end
+                    && span.end <= source.len()
+                    && source.is_char_boundary(span.start)
+                    && source.is_char_boundary(span.end) =>
+            {
+                span
+            },
+            _ => return message,
+        };
+
+        let line_start = source[..span.start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |index| span.start + index);
+        let line = &source[line_start..line_end];
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_number = source[..line_start].matches('\n').count() + 1;
+        let column = source[line_start..span.start].chars().count() + 1;
+
+        // The span may start or end in the stripped carriage return.
+        let relative = |offset: usize| (offset - line_start).min(line.len());
+        let mut text: Vec<char> = line
+            .chars()
+            .flat_map(|c| match c {
+                '\t' => vec![' '; TAB_WIDTH],
+                c => vec![c],
+            })
+            .collect();
+        let mut underline_start = rendered_columns(&line[..relative(span.start)]);
+        let mut underline_end = rendered_columns(&line[..relative(span.end)]);
+
+        if let Some(width) = width {
+            if text.len() > width {
+                let begin = underline_start
+                    .saturating_sub(width / 2)
+                    .min(text.len() - width);
+                let end = begin + width;
+                let cut_end = end < text.len();
+                text.truncate(end);
+                text.drain(..begin);
+                if begin > 0 {
+                    text[0] = '…';
+                }
+                if cut_end {
+                    text[width - 1] = '…';
+                }
+                underline_start -= begin;
+                underline_end = underline_end.min(end) - begin;
+            }
+        }
+        let underline_end = underline_end.max(underline_start + 1);
+
+        let gutter = " ".repeat(line_number.to_string().len());
+        let source_line = format!("{} | {}", line_number, text.into_iter().collect::<String>());
+        format!(
+            "{}\n{}--> {}:{}\n{} |\n{}\n{} | {}{}",
+            message,
+            gutter,
+            line_number,
+            column,
+            gutter,
+            source_line.trim_end(),
+            gutter,
+            " ".repeat(underline_start),
+            "^".repeat(underline_end - underline_start)
+        )
+    }
+}
+
+/// Returns the amount of columns that the given part of a source line is rendered as.
+fn rendered_columns(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+
+use config::EvalConfig;
+use error::{EvalexprError, EvalexprResult};
+use function::higher_order::higher_order_identifier;
+use operator::Operator;
+use state::EvalState;
+use tree::{CompactTree, Node};
+use value::Value;
+use Context;
+use EmptyContext;
+use HashMapContext;
+
+/// The future returned by an `AsyncFunction`.
+pub type BoxedValueFuture = Pin<Box<dyn Future<Output = EvalexprResult<Value>> + Send>>;
+
+type AsyncFunctionType = dyn Fn(&Value) -> BoxedValueFuture + Send + Sync;
+
+/// A user-defined function that returns a future, for example to fetch data over the network without blocking the thread.
+/// Async functions can be used in expressions by storing them in an `AsyncContext` and evaluating the expressions with `eval_with_context_async`.
+///
+/// Cloning an async function is cheap, as the clone shares the boxed function with the original.
+#[derive(Clone)]
+pub struct AsyncFunction {
+    function: Arc<AsyncFunctionType>,
+}
+
+impl AsyncFunction {
+    /// Creates a user-defined async function.
+    ///
+    /// The `function` is a boxed function that takes a `Value` and returns a pinned, boxed future of an `EvalexprResult<Value>`.
+    /// It is called when the evaluation reaches the function call, and the evaluation continues once the returned future is ready.
+    pub fn new(function: Box<AsyncFunctionType>) -> Self {
+        Self {
+            function: function.into(),
+        }
+    }
+
+    pub(crate) fn call(&self, argument: &Value) -> BoxedValueFuture {
+        (self.function)(argument)
+    }
+}
+
+impl fmt::Debug for AsyncFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "AsyncFunction {{ [...] }}")
+    }
+}
+
+/// A context that can additionally link identifiers to async functions.
+///
+/// When an expression is evaluated with `eval_with_context_async`, a function identifier is looked up with `Context::get_function` first, then with `get_async_function` and then among the builtin functions.
+pub trait AsyncContext: Context {
+    /// Returns the async function that is linked to the given identifier.
+    fn get_async_function(&self, identifier: &str) -> Option<&AsyncFunction>;
+}
+
+impl AsyncContext for EmptyContext {
+    fn get_async_function(&self, _identifier: &str) -> Option<&AsyncFunction> {
+        None
+    }
+}
+
+impl AsyncContext for HashMapContext {
+    fn get_async_function(&self, identifier: &str) -> Option<&AsyncFunction> {
+        self.async_functions.get(identifier)
+    }
+}
+
+/// The future of an evaluation that awaits async functions, as returned by `eval_with_context_async`.
+///
+/// The operator tree is evaluated in the same order as by the synchronous evaluation.
+/// When the evaluation reaches a call of an async function, it polls the future of the call until it is ready, and then continues with the result.
+/// So calls of async functions within one expression run one after the other, while the thread is free to make progress on other tasks in the meantime.
+///
+/// The evaluation uses the default `EvalConfig`, unless another one is set with `AsyncEvaluation::with_config`.
+/// Like a synchronous evaluation, it checks the cancellation token, the limits and the NaN policy of the configuration.
+/// The time spent awaiting async functions counts as one evaluation, so the allocated bytes are counted across all calls.
+#[must_use = "futures do nothing unless polled"]
+pub struct AsyncEvaluation<'a, C: AsyncContext> {
+    context: &'a C,
+    state: EvalState,
+    /// The evaluated expression, followed by the named expressions that were reached during the evaluation.
+    trees: Vec<CompactTree>,
+    /// The nodes whose children are being evaluated, from the root to the innermost node.
+    stack: Vec<Frame>,
+    pending_call: Option<PendingCall>,
+    /// The result of the evaluation, if it is known before the first poll.
+    result: Option<EvalexprResult<Value>>,
+}
+
+/// A node of the evaluated expression whose children are being evaluated.
+struct Frame {
+    tree: usize,
+    node: usize,
+    children: Range<usize>,
+    arguments: Vec<Value>,
+    /// The identifiers of the named expressions whose root is this node.
+    /// There are multiple if a named expression consists only of a variable that refers to another named expression.
+    expressions: Vec<String>,
+}
+
+/// A call of an async function whose future is not ready yet.
+struct PendingCall {
+    identifier: String,
+    argument: Value,
+    future: BoxedValueFuture,
+}
+
+impl<'a, C: AsyncContext> AsyncEvaluation<'a, C> {
+    pub(crate) fn new(tree: EvalexprResult<CompactTree>, context: &'a C) -> Self {
+        let mut evaluation = Self {
+            context,
+            state: EvalState::default(),
+            trees: Vec::new(),
+            stack: Vec::new(),
+            pending_call: None,
+            result: None,
+        };
+        match tree {
+            Ok(tree) => {
+                evaluation.trees.push(tree);
+                if let Err(error) = evaluation.enter(0, 0, Vec::new()) {
+                    evaluation.result = Some(Err(error));
+                }
+            },
+            Err(error) => evaluation.result = Some(Err(error)),
+        }
+        evaluation
+    }
+
+    /// Sets the configuration of the evaluation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "async")] {
+    /// extern crate futures;
+    /// use evalexpr::*;
+    ///
+    /// let config = EvalConfig {
+    ///     max_result_bytes: Some(4),
+    ///     ..EvalConfig::default()
+    /// };
+    /// let evaluation = eval_with_context_async("\"abc\" + \"def\"", &EmptyContext).with_config(config);
+    /// assert!(matches!(
+    ///     futures::executor::block_on(evaluation),
+    ///     Err(EvalexprError::ValueTooLarge { .. })
+    /// ));
+    /// # }
+    /// ```
+    pub fn with_config(mut self, config: EvalConfig) -> Self {
+        self.state = EvalState::new(config);
+        self
+    }
+
+    /// Starts the evaluation of the given node, or of the named expression it refers to.
+    fn enter(
+        &mut self,
+        tree: usize,
+        node: usize,
+        mut expressions: Vec<String>,
+    ) -> EvalexprResult<()> {
+        let (mut tree, mut node) = (tree, node);
+        while let Operator::VariableIdentifier { identifier } = self.trees[tree].operator(node) {
+            if self.context.get_value(identifier).is_some() {
+                break;
+            }
+            let named_expression = match self.context.get_expression(identifier) {
+                Some(named_expression) => named_expression,
+                None => break,
+            };
+
+            let active_expressions = self
+                .stack
+                .iter()
+                .flat_map(|frame| &frame.expressions)
+                .chain(&expressions);
+            if let Some(start) = active_expressions
+                .clone()
+                .position(|active| **active == **identifier)
+            {
+                let mut chain: Vec<_> = active_expressions.skip(start).cloned().collect();
+                chain.push(identifier.to_string());
+                return Err(EvalexprError::expression_cycle(chain));
+            }
+
+            expressions.push(identifier.to_string());
+            self.trees.push(named_expression.compact());
+            tree = self.trees.len() - 1;
+            node = 0;
+        }
+
+        // The arguments of higher-order builtin functions are evaluated by the function.
+        let children = if self.is_higher_order_call(tree, node) {
+            0..0
+        } else {
+            self.trees[tree].child_indices(node)
+        };
+        self.stack.push(Frame {
+            tree,
+            node,
+            children,
+            arguments: Vec::new(),
+            expressions,
+        });
+        Ok(())
+    }
+
+    /// Evaluates the operator tree until it is finished or a call of an async function is pending.
+    /// The evaluation runs with the given state, which is the state of this evaluation while it is taken out of it.
+    fn advance(
+        &mut self,
+        task_context: &mut TaskContext,
+        state: &EvalState,
+    ) -> Poll<EvalexprResult<Value>> {
+        loop {
+            if let Some(pending_call) = &mut self.pending_call {
+                let result = match pending_call.future.as_mut().poll(task_context) {
+                    Poll::Ready(result) => result.map_err(|error| {
+                        EvalexprError::in_function_call(
+                            &pending_call.identifier,
+                            &pending_call.argument,
+                            error,
+                        )
+                    }),
+                    Poll::Pending => return Poll::Pending,
+                };
+                self.pending_call = None;
+                let result = Node::allocate(state, None, || result);
+                if let Some(result) = self.complete(result) {
+                    return Poll::Ready(result);
+                }
+                continue;
+            }
+
+            let frame = match self.stack.last_mut() {
+                Some(frame) => frame,
+                None => return Poll::Ready(Ok(Value::Empty)),
+            };
+            if let Some(child) = frame.children.next() {
+                let tree = frame.tree;
+                if self.trees[tree]
+                    .operator(frame.node)
+                    .skip_argument(&mut frame.arguments)
+                {
+                    continue;
+                }
+                if let Err(error) = self.enter(tree, child, Vec::new()) {
+                    return Poll::Ready(Err(error));
+                }
+                continue;
+            }
+
+            let frame = self.stack.pop().unwrap();
+            if self.is_higher_order_call(frame.tree, frame.node) {
+                let result = self.trees[frame.tree].eval_node_with_context_and_state(
+                    frame.node,
+                    self.context,
+                    state,
+                );
+                if let Some(result) = self.complete(result) {
+                    return Poll::Ready(result);
+                }
+                continue;
+            }
+            let operator = self.trees[frame.tree].operator(frame.node);
+            if let Operator::FunctionIdentifier { identifier } = operator {
+                if self.context.get_function(identifier).is_none() {
+                    if let Some(function) = self.context.get_async_function(identifier) {
+                        // Function identifiers always have exactly one argument.
+                        let argument = frame.arguments.into_iter().next().unwrap_or(Value::Empty);
+                        self.pending_call = Some(PendingCall {
+                            identifier: identifier.to_string(),
+                            future: function.call(&argument),
+                            argument,
+                        });
+                        continue;
+                    }
+                }
+            }
+
+            if let Err(error) = state.check_cancelled() {
+                return Poll::Ready(Err(error));
+            }
+            let allocation_hint =
+                Node::allocation_hint(operator, &frame.arguments, self.context, state);
+            let result = Node::allocate(state, allocation_hint, || {
+                let result = operator
+                    .eval(&frame.arguments, self.context, state.config())
+                    .map_err(|error| self.trees[frame.tree].locate_error(frame.node, error));
+                Node::apply_nan_policy(operator, &frame.arguments, self.context, state, result)
+            });
+            if let Some(result) = self.complete(result) {
+                return Poll::Ready(result);
+            }
+        }
+    }
+
+    /// Returns true if the given node is a call of a higher-order builtin function like `map` that is not overridden by the context.
+    /// Such calls are evaluated synchronously, so async functions within their arguments are not awaited.
+    fn is_higher_order_call(&self, tree: usize, node: usize) -> bool {
+        higher_order_identifier(self.trees[tree].operator(node), self.context)
+            .is_some_and(|identifier| self.context.get_async_function(identifier).is_none())
+    }
+
+    /// Passes the result of the innermost finished node to its parent.
+    /// An error is passed on to the ancestors until one of them recovers from it, see `Operator::recover_argument`.
+    /// Returns the result of the evaluation if it is finished.
+    fn complete(&mut self, mut result: EvalexprResult<Value>) -> Option<EvalexprResult<Value>> {
+        loop {
+            let parent = match self.stack.last_mut() {
+                Some(parent) => parent,
+                None => return Some(result),
+            };
+            let operator = self.trees[parent.tree].operator(parent.node);
+            let value = match result {
+                Ok(value) => value,
+                Err(error) => {
+                    // The finished node is the child that was entered last.
+                    let argument =
+                        self.trees[parent.tree].stripped_operator(parent.children.start - 1);
+                    match operator.recover_argument(&parent.arguments, argument, error) {
+                        Ok(argument) => {
+                            parent.arguments.push(argument);
+                            return None;
+                        },
+                        Err(error) => {
+                            self.stack.pop();
+                            result = Err(error);
+                            continue;
+                        },
+                    }
+                },
+            };
+
+            parent.arguments.push(value);
+            match operator.short_circuit(&parent.arguments) {
+                Ok(Some(short_circuit_result)) => {
+                    self.stack.pop();
+                    result = Ok(short_circuit_result);
+                },
+                Ok(None) => return None,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+impl<'a, C: AsyncContext> Future for AsyncEvaluation<'a, C> {
+    type Output = EvalexprResult<Value>;
+
+    fn poll(self: Pin<&mut Self>, task_context: &mut TaskContext) -> Poll<Self::Output> {
+        let evaluation = self.get_mut();
+        if let Some(result) = evaluation.result.take() {
+            return Poll::Ready(result);
+        }
+        // The state is taken out of the evaluation while it advances, as the guard of the state borrows it.
+        let state = mem::take(&mut evaluation.state);
+        let result = match state.resume() {
+            Ok(_guard) => evaluation.advance(task_context, &state),
+            Err(error) => Poll::Ready(Err(error)),
+        };
+        evaluation.state = state;
+        if result.is_ready() {
+            // Release the trees early, and make further polls return `Value::Empty` instead of panicking.
+            evaluation.stack.clear();
+            evaluation.trees.clear();
+        }
+        result
+    }
+}
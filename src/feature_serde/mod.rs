@@ -1,7 +1,14 @@
 use interface::build_operator_tree;
 use serde::{de, Deserialize, Deserializer};
+use serde_json;
+use std::convert::TryFrom;
 use std::fmt;
+use std::marker::PhantomData;
+use EvalexprError;
+use ExpressionType;
 use Node;
+use TypedExpression;
+use Value;
 
 impl<'de> Deserialize<'de> for Node {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -34,3 +41,59 @@ impl<'de> de::Visitor<'de> for NodeVisitor {
         }
     }
 }
+
+impl<'de, T: ExpressionType> Deserialize<'de> for TypedExpression<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(TypedExpressionVisitor(PhantomData))
+    }
+}
+
+struct TypedExpressionVisitor<T>(PhantomData<fn() -> T>);
+
+impl<'de, T: ExpressionType> de::Visitor<'de> for TypedExpressionVisitor<T> {
+    type Value = TypedExpression<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a string in the expression format of the `evalexpr` crate that produces a value of type {:?}",
+            T::static_type()
+        )
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        TypedExpression::parse(v).map_err(E::custom)
+    }
+}
+
+impl TryFrom<Value> for serde_json::Value {
+    type Error = EvalexprError;
+
+    /// Converts a value to JSON.
+    ///
+    /// Strings and booleans are converted to their JSON counterparts, tuples to arrays and the empty value to `null`.
+    /// Integers and floats are converted to JSON numbers without loss of precision, and integers stay integers.
+    /// Fails with `EvalexprError::NonFiniteFloatInJson` for NaN and infinite floats, as JSON cannot represent them.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(string) => Ok(serde_json::Value::String(string)),
+            Value::Float(float) => serde_json::Number::from_f64(float)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| EvalexprError::non_finite_float_in_json(float)),
+            Value::Int(int) => Ok(serde_json::Value::Number(int.into())),
+            Value::Boolean(boolean) => Ok(serde_json::Value::Bool(boolean)),
+            Value::Tuple(tuple) => tuple
+                .into_iter()
+                .map(serde_json::Value::try_from)
+                .collect::<Result<_, _>>()
+                .map(serde_json::Value::Array),
+            Value::Empty => Ok(serde_json::Value::Null),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Instrumentation of evaluations and function calls with `tracing` spans.
+//!
+//! Without the `tracing` feature, the functions of this module only run the given closure, such that they compile to nothing.
+
+use std::collections::hash_map::DefaultHasher;
+#[cfg(feature = "tracing")]
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+#[cfg(feature = "tracing")]
+use tracing::{field, Span};
+
+use error::EvalexprResult;
+#[cfg(feature = "tracing")]
+use value::value_type::ValueType;
+use value::Value;
+
+/// The target of all spans and events of this crate.
+#[cfg(feature = "tracing")]
+const TARGET: &str = "evalexpr";
+
+/// Returns the hash of an expression that identifies it in the spans of its evaluations.
+/// The hash is stable within one build of this crate, but not across versions of Rust.
+pub(crate) fn expression_hash<T: Hash + ?Sized>(expression: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    expression.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Runs an evaluation of an expression within a debug span named `eval`.
+/// The hash of the expression is only computed if the span is enabled by a subscriber.
+#[cfg(feature = "tracing")]
+pub(crate) fn instrument_evaluation<H, E>(hash: H, evaluate: E) -> EvalexprResult<Value>
+where
+    H: FnOnce() -> u64,
+    E: FnOnce() -> EvalexprResult<Value>,
+{
+    let span = tracing::debug_span!(
+        target: TARGET,
+        "eval",
+        expression_hash = field::Empty,
+        duration_us = field::Empty,
+        result_type = field::Empty,
+        error = field::Empty,
+    );
+    if !span.is_disabled() {
+        span.record("expression_hash", hash());
+    }
+    instrument(span, evaluate)
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn instrument_evaluation<H, E>(_hash: H, evaluate: E) -> EvalexprResult<Value>
+where
+    H: FnOnce() -> u64,
+    E: FnOnce() -> EvalexprResult<Value>,
+{
+    evaluate()
+}
+
+/// Runs a call of the function with the given identifier within a debug span named `call`.
+#[cfg(feature = "tracing")]
+pub(crate) fn instrument_call<C>(identifier: &str, builtin: bool, call: C) -> EvalexprResult<Value>
+where
+    C: FnOnce() -> EvalexprResult<Value>,
+{
+    let span = tracing::debug_span!(
+        target: TARGET,
+        "call",
+        function = identifier,
+        builtin,
+        duration_us = field::Empty,
+        result_type = field::Empty,
+        error = field::Empty,
+    );
+    instrument(span, call)
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn instrument_call<C>(
+    _identifier: &str,
+    _builtin: bool,
+    call: C,
+) -> EvalexprResult<Value>
+where
+    C: FnOnce() -> EvalexprResult<Value>,
+{
+    call()
+}
+
+/// Runs the given closure within the given span, records its duration and the type of its result or the name of its error in the span, and emits a debug event with the same fields when it finishes.
+/// If no subscriber is interested in the span, the closure is run without measuring it.
+#[cfg(feature = "tracing")]
+fn instrument<F>(span: Span, run: F) -> EvalexprResult<Value>
+where
+    F: FnOnce() -> EvalexprResult<Value>,
+{
+    if span.is_disabled() {
+        return run();
+    }
+
+    let start = Instant::now();
+    let result = span.in_scope(run);
+    let duration_us = u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX);
+
+    span.record("duration_us", duration_us);
+    let _entered = span.enter();
+    match &result {
+        Ok(value) => {
+            let result_type = field::debug(ValueType::from(value));
+            span.record("result_type", &result_type);
+            tracing::debug!(target: TARGET, duration_us, result_type, "finished");
+        },
+        Err(error) => {
+            let error = error.variant_name();
+            span.record("error", error);
+            tracing::debug!(target: TARGET, duration_us, error, "failed");
+        },
+    }
+    result
+}
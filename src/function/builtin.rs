@@ -1,17 +1,92 @@
-#[cfg(feature = "regex_support")]
-use regex::Regex;
+#[cfg(feature = "unicode_support")]
+use caseless::default_case_fold_str;
+#[cfg(feature = "builtin_tuple")]
+use std::collections::HashSet;
+#[cfg(any(feature = "builtin_math", feature = "builtin_string"))]
+use std::convert::TryFrom;
+#[cfg(feature = "builtin_tuple")]
+use std::mem;
+#[cfg(feature = "builtin_datetime")]
+use std::sync::OnceLock;
+#[cfg(feature = "builtin_datetime")]
+use std::time::Instant;
+#[cfg(feature = "unicode_support")]
+use unicode_width::UnicodeWidthStr;
 
+#[cfg(any(
+    feature = "builtin_math",
+    feature = "builtin_string",
+    feature = "builtin_tuple",
+    feature = "builtin_regex",
+    feature = "builtin_crypto",
+    feature = "geo_support",
+    feature = "unicode_support"
+))]
 use crate::error::*;
-use value::{FloatType, IntType};
+#[cfg(feature = "builtin_crypto")]
+use function::digest::sha256_hex;
+#[cfg(feature = "excel_compat")]
+use function::excel;
+#[cfg(feature = "builtin_string")]
+use function::glob::Glob;
+use function::higher_order::is_higher_order_builtin;
+#[cfg(feature = "builtin_string")]
+use function::humanize;
+#[cfg(feature = "builtin_regex")]
+use function::regex_match::{compile_regex, regex_matches};
+#[cfg(feature = "builtin_tuple")]
+use operator::Operator;
+use typecheck::Signature;
+#[cfg(any(
+    feature = "builtin_math",
+    feature = "builtin_tuple",
+    feature = "geo_support"
+))]
+use value::FloatType;
+#[cfg(any(
+    feature = "builtin_math",
+    feature = "builtin_string",
+    feature = "builtin_tuple",
+    feature = "unicode_support"
+))]
+use value::IntType;
+#[cfg(feature = "builtin_tuple")]
+use value::TupleType;
+#[cfg(feature = "builtin_tuple")]
+use EmptyContext;
+#[cfg(feature = "builtin_tuple")]
+use EvalConfig;
+#[cfg(any(
+    feature = "builtin_math",
+    feature = "builtin_string",
+    feature = "builtin_tuple",
+    feature = "geo_support"
+))]
 use EvalexprError;
 use Function;
+#[cfg(any(
+    feature = "builtin_math",
+    feature = "builtin_string",
+    feature = "builtin_tuple",
+    feature = "builtin_datetime",
+    feature = "builtin_regex",
+    feature = "builtin_crypto",
+    feature = "geo_support",
+    feature = "unicode_support"
+))]
 use Value;
 
 pub fn builtin_function(identifier: &str) -> Option<Function> {
+    #[cfg(feature = "excel_compat")]
+    if let Some(function) = excel::excel_function(identifier) {
+        return Some(function);
+    }
+
     match identifier {
+        #[cfg(feature = "builtin_math")]
         "min" => Some(Function::new(Box::new(|argument| {
             let arguments = expect_tuple(argument)?;
-            let mut min_int = IntType::max_value();
+            let mut min_int = IntType::MAX;
             let mut min_float = 1.0f64 / 0.0f64;
             debug_assert!(min_float.is_infinite());
 
@@ -31,9 +106,10 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
                 Ok(Value::Float(min_float))
             }
         }))),
+        #[cfg(feature = "builtin_math")]
         "max" => Some(Function::new(Box::new(|argument| {
             let arguments = expect_tuple(argument)?;
-            let mut max_int = IntType::min_value();
+            let mut max_int = IntType::MIN;
             let mut max_float = -1.0f64 / 0.0f64;
             debug_assert!(max_float.is_infinite());
 
@@ -53,54 +129,807 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
                 Ok(Value::Float(max_float))
             }
         }))),
+        #[cfg(feature = "builtin_math")]
+        "abs" => Some(Function::new(Box::new(|argument| match argument {
+            Value::Int(int) => int
+                .checked_abs()
+                .map(Value::Int)
+                .ok_or_else(|| EvalexprError::integer_overflow("abs", vec![argument.clone()])),
+            Value::Float(float) => Ok(Value::Float(float.abs())),
+            argument => Err(EvalexprError::expected_number(argument.clone())),
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "signum" => Some(Function::new(Box::new(|argument| match argument {
+            // The sign of an integer is always in range, unlike its absolute value.
+            Value::Int(int) => Ok(Value::Int(int.signum())),
+            Value::Float(float) if *float == 0.0 || float.is_nan() => Ok(Value::Float(*float)),
+            Value::Float(float) => Ok(Value::Float(float.signum())),
+            argument => Err(EvalexprError::expected_number(argument.clone())),
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "pow" => Some(Function::new(Box::new(|argument| {
+            let arguments = expect_tuple(argument)?;
+            expect_function_argument_amount(arguments.len(), 2)?;
+            pow(&arguments[0], &arguments[1])
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "sqrt" => Some(Function::new(Box::new(|argument| {
+            Ok(Value::Float(argument.as_number()?.sqrt()))
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "ln" => Some(Function::new(Box::new(|argument| {
+            Ok(Value::Float(argument.as_number()?.ln()))
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "bit" => Some(Function::new(Box::new(|argument| {
+            let arguments = expect_tuple(argument)?;
+            expect_function_argument_amount(arguments.len(), 2)?;
+            let value = arguments[0].as_int()?;
+            let index = expect_bit_index("bit", &arguments[1], 0)?;
+            Ok(Value::Int(((value as u64 >> index) & 1) as IntType))
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "bits" => Some(Function::new(Box::new(|argument| {
+            let arguments = expect_tuple(argument)?;
+            expect_function_argument_amount(arguments.len(), 3)?;
+            let value = arguments[0].as_int()?;
+            let low = expect_bit_index("bits", &arguments[1], 0)?;
+            let high = expect_bit_index("bits", &arguments[2], low)?;
+            // The bits are extracted unsigned, so the result is only negative for the full width from 0 to 63.
+            let mask = u64::MAX >> (IntType::BITS - 1 - (high - low));
+            Ok(Value::Int(((value as u64 >> low) & mask) as IntType))
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "popcount" => Some(Function::new(Box::new(|argument| {
+            Ok(Value::Int(IntType::from(argument.as_int()?.count_ones())))
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "leading_zeros" => Some(Function::new(Box::new(|argument| {
+            Ok(Value::Int(IntType::from(
+                argument.as_int()?.leading_zeros(),
+            )))
+        }))),
+        #[cfg(feature = "builtin_math")]
+        "trailing_zeros" => Some(Function::new(Box::new(|argument| {
+            Ok(Value::Int(IntType::from(
+                argument.as_int()?.trailing_zeros(),
+            )))
+        }))),
+
+        #[cfg(feature = "builtin_tuple")]
+        "concat" => Some(Function::new(Box::new(|argument| {
+            // A single argument is returned unchanged.
+            // A tuple is read as the list of arguments only if its first element can be concatenated, so `concat((1, 2))` is the tuple `(1, 2)`.
+            let arguments = match argument {
+                Value::String(_) => return Ok(argument.clone()),
+                Value::Tuple(arguments) => arguments,
+                argument => return Err(EvalexprError::expected_tuple(argument.clone())),
+            };
+
+            match arguments.first() {
+                Some(Value::Tuple(_)) => {
+                    let mut result = TupleType::new();
+                    for argument in arguments {
+                        result.extend_from_slice(expect_tuple(argument)?);
+                    }
+                    Ok(Value::Tuple(result))
+                },
+                Some(Value::String(_)) => {
+                    let mut result = String::new();
+                    for argument in arguments {
+                        result.push_str(expect_string(argument)?);
+                    }
+                    Ok(Value::String(result))
+                },
+                _ => Ok(argument.clone()),
+            }
+        }))),
+
+        #[cfg(feature = "builtin_tuple")]
+        "all_of" => Some(Function::new(Box::new(|argument| {
+            let (elements, comparison, rhs) = expect_comparison_arguments(argument)?;
+            for element in elements {
+                if !compare(element, &comparison, rhs)? {
+                    return Ok(Value::Boolean(false));
+                }
+            }
+            Ok(Value::Boolean(true))
+        }))),
+        #[cfg(feature = "builtin_tuple")]
+        "any_of" => Some(Function::new(Box::new(|argument| {
+            let (elements, comparison, rhs) = expect_comparison_arguments(argument)?;
+            for element in elements {
+                if compare(element, &comparison, rhs)? {
+                    return Ok(Value::Boolean(true));
+                }
+            }
+            Ok(Value::Boolean(false))
+        }))),
 
+        #[cfg(feature = "builtin_datetime")]
+        "now_monotonic" => Some(
+            Function::new(Box::new(|argument| {
+                argument.as_empty()?;
+                Ok(Value::Float(monotonic_epoch().elapsed().as_secs_f64()))
+            }))
+            .impure(),
+        ),
+
+        #[cfg(feature = "builtin_string")]
+        "str" => Some(Function::new(Box::new(|argument| match argument {
+            Value::String(string) => Ok(Value::String(string.clone())),
+            argument => Ok(Value::String(argument.to_string())),
+        }))),
+        #[cfg(feature = "builtin_string")]
         "len" => Some(Function::new(Box::new(|argument| {
             let subject = expect_string(argument)?;
             Ok(Value::from(subject.len() as i64))
         }))),
 
+        // tuple functions
+        #[cfg(feature = "builtin_tuple")]
+        "unique" => Some(Function::new(Box::new(|argument| {
+            let elements = expect_tuple(argument)?;
+            Ok(Value::Tuple(distinct_elements(elements, None)))
+        }))),
+        #[cfg(feature = "builtin_tuple")]
+        "intersect" => Some(Function::new(Box::new(|argument| {
+            let (a, b) = expect_two_tuples(argument)?;
+            Ok(Value::Tuple(distinct_elements(a, Some((b, true)))))
+        }))),
+        #[cfg(feature = "builtin_tuple")]
+        "union" => Some(Function::new(Box::new(|argument| {
+            let (a, b) = expect_two_tuples(argument)?;
+            let elements: TupleType = a.iter().chain(b).cloned().collect();
+            Ok(Value::Tuple(distinct_elements(&elements, None)))
+        }))),
+        #[cfg(feature = "builtin_tuple")]
+        "difference" => Some(Function::new(Box::new(|argument| {
+            let (a, b) = expect_two_tuples(argument)?;
+            Ok(Value::Tuple(distinct_elements(a, Some((b, false)))))
+        }))),
+        #[cfg(feature = "builtin_tuple")]
+        "range" => Some(Function::new(Box::new(|argument| {
+            let (start, length, step) = expect_range_arguments(argument)?;
+            let bytes = range_allocated_bytes(length);
+            if bytes > isize::MAX as u128 {
+                return Err(EvalexprError::value_too_large(
+                    isize::MAX as usize,
+                    bytes.min(usize::MAX as u128) as usize,
+                ));
+            }
+            Ok(Value::Tuple(
+                (0..length)
+                    .map(|index| Value::Int((start + index as i128 * step) as IntType))
+                    .collect(),
+            ))
+        }))),
+
+        // geo functions
+        #[cfg(feature = "geo_support")]
+        "haversine_km" => Some(Function::new(Box::new(|argument| {
+            let coordinates = expect_coordinates(argument, 4)?;
+            Ok(Value::Float(haversine_km(&coordinates)))
+        }))),
+        #[cfg(feature = "geo_support")]
+        "haversine_miles" => Some(Function::new(Box::new(|argument| {
+            let coordinates = expect_coordinates(argument, 4)?;
+            Ok(Value::Float(
+                haversine_km(&coordinates) / KILOMETRES_PER_MILE,
+            ))
+        }))),
+        #[cfg(feature = "geo_support")]
+        "in_bbox" => Some(Function::new(Box::new(|argument| {
+            let coordinates = expect_coordinates(argument, 6)?;
+            let (lat, lon) = (coordinates[0], coordinates[1]);
+            let (min_lat, min_lon) = (coordinates[2], coordinates[3]);
+            let (max_lat, max_lon) = (coordinates[4], coordinates[5]);
+
+            let lat_inside = min_lat <= lat && lat <= max_lat;
+            // A box whose western edge lies east of its eastern edge crosses the antimeridian.
+            let lon_inside = if min_lon <= max_lon {
+                min_lon <= lon && lon <= max_lon
+            } else {
+                lon >= min_lon || lon <= max_lon
+            };
+            Ok(Value::Boolean(lat_inside && lon_inside))
+        }))),
+
         // string functions
-        #[cfg(feature = "regex_support")]
+        #[cfg(feature = "builtin_regex")]
         "str::regex_matches" => Some(Function::new(Box::new(|argument| {
             let arguments = expect_tuple(argument)?;
+            expect_function_argument_amount(arguments.len(), 2)?;
 
-            let subject = expect_string(&arguments[0])?;
-            let re_str = expect_string(&arguments[1])?;
-            match Regex::new(re_str) {
-                Ok(re) => Ok(Value::Boolean(re.is_match(subject))),
-                Err(err) => Err(EvalexprError::invalid_regex(
-                    re_str.to_string(),
-                    format!("{}", err),
-                )),
-            }
+            regex_matches(&arguments[0], &arguments[1])
         }))),
-        #[cfg(feature = "regex_support")]
+        #[cfg(feature = "builtin_regex")]
         "str::regex_replace" => Some(Function::new(Box::new(|argument| {
             let arguments = expect_tuple(argument)?;
 
             let subject = expect_string(&arguments[0])?;
             let re_str = expect_string(&arguments[1])?;
             let repl = expect_string(&arguments[2])?;
-            match Regex::new(re_str) {
-                Ok(re) => Ok(Value::String(re.replace_all(subject, repl).to_string())),
-                Err(err) => Err(EvalexprError::invalid_regex(
-                    re_str.to_string(),
-                    format!("{}", err),
-                )),
-            }
+            let re = compile_regex(re_str)?;
+            Ok(Value::String(re.replace_all(subject, repl).to_string()))
         }))),
+        #[cfg(feature = "builtin_string")]
         "str::to_lowercase" => Some(Function::new(Box::new(|argument| {
             let subject = expect_string(argument)?;
             Ok(Value::from(subject.to_lowercase()))
         }))),
+        #[cfg(feature = "builtin_string")]
         "str::to_uppercase" => Some(Function::new(Box::new(|argument| {
             let subject = expect_string(argument)?;
             Ok(Value::from(subject.to_uppercase()))
         }))),
+        #[cfg(feature = "builtin_string")]
         "str::trim" => Some(Function::new(Box::new(|argument| {
             let subject = expect_string(argument)?;
             Ok(Value::from(subject.trim()))
         }))),
+        #[cfg(feature = "builtin_string")]
+        "str::starts_with" => Some(Function::new(Box::new(|argument| {
+            let (subject, prefix) = expect_two_strings(argument)?;
+            Ok(Value::Boolean(subject.starts_with(prefix)))
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "str::ends_with" => Some(Function::new(Box::new(|argument| {
+            let (subject, suffix) = expect_two_strings(argument)?;
+            Ok(Value::Boolean(subject.ends_with(suffix)))
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "str::strip_prefix" => Some(Function::new(Box::new(|argument| {
+            let (subject, prefix) = expect_two_strings(argument)?;
+            Ok(Value::from(subject.strip_prefix(prefix).unwrap_or(subject)))
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "str::strip_suffix" => Some(Function::new(Box::new(|argument| {
+            let (subject, suffix) = expect_two_strings(argument)?;
+            Ok(Value::from(subject.strip_suffix(suffix).unwrap_or(subject)))
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "matches_glob" => Some(Function::new(Box::new(|argument| {
+            let (pattern, text) = expect_two_strings(argument)?;
+            Ok(Value::Boolean(Glob::new(pattern)?.matches(text)))
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "matches_glob_ci" => Some(Function::new(Box::new(|argument| {
+            let (pattern, text) = expect_two_strings(argument)?;
+            let glob = Glob::new(pattern)?.case_insensitive();
+            Ok(Value::Boolean(glob.matches(text)))
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "char_at" => Some(Function::new(Box::new(|argument| {
+            let arguments = expect_tuple(argument)?;
+            expect_function_argument_amount(arguments.len(), 2)?;
+            let subject = expect_string(&arguments[0])?;
+            let index = arguments[1].as_int()?;
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| subject.chars().nth(index))
+                .map(|character| Value::from(character.to_string()))
+                .ok_or_else(|| {
+                    EvalexprError::argument_out_of_range(
+                        "char_at",
+                        Value::Int(index),
+                        format!(
+                            "an index of at least 0 and less than the {} characters of the string",
+                            subject.chars().count()
+                        ),
+                    )
+                })
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "code_point" => Some(Function::new(Box::new(|argument| {
+            let subject = expect_string(argument)?;
+            let mut characters = subject.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None) => Ok(Value::Int(IntType::from(u32::from(character)))),
+                _ => Err(EvalexprError::argument_out_of_range(
+                    "code_point",
+                    argument.clone(),
+                    "a string of exactly one character".to_string(),
+                )),
+            }
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "from_code_point" => Some(Function::new(Box::new(|argument| {
+            let code_point = argument.as_int()?;
+            u32::try_from(code_point)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|character| Value::from(character.to_string()))
+                .ok_or_else(|| {
+                    EvalexprError::argument_out_of_range(
+                        "from_code_point",
+                        Value::Int(code_point),
+                        "a Unicode scalar value of at most 0x10FFFF that is not a surrogate from \
+                         0xD800 to 0xDFFF"
+                            .to_string(),
+                    )
+                })
+        }))),
+        #[cfg(feature = "builtin_string")]
+        "humanize_bytes" => Some(Function::new(Box::new(humanize::humanize_bytes))),
+        #[cfg(feature = "builtin_string")]
+        "humanize_duration" => Some(Function::new(Box::new(humanize::humanize_duration))),
+        #[cfg(feature = "builtin_string")]
+        "parse_bytes" => Some(Function::new(Box::new(humanize::parse_bytes))),
+        #[cfg(feature = "builtin_string")]
+        "parse_duration" => Some(Function::new(Box::new(humanize::parse_duration))),
+        #[cfg(feature = "unicode_support")]
+        "str::casefold" => Some(Function::new(Box::new(|argument| {
+            let subject = expect_string(argument)?;
+            Ok(Value::from(default_case_fold_str(subject)))
+        }))),
+        #[cfg(feature = "unicode_support")]
+        "str::width" => Some(Function::new(Box::new(|argument| {
+            let subject = expect_string(argument)?;
+            Ok(Value::from(subject.width() as IntType))
+        }))),
+        #[cfg(feature = "builtin_crypto")]
+        "str::sha256" => Some(Function::new(Box::new(|argument| {
+            let subject = expect_string(argument)?;
+            Ok(Value::from(sha256_hex(subject.as_bytes())))
+        }))),
         _ => None,
     }
 }
+
+/// Raises `base` to the power of `exponent`.
+/// The result is an integer if both arguments are integers and the exponent is not negative, and a float otherwise.
+#[cfg(feature = "builtin_math")]
+fn pow(base: &Value, exponent: &Value) -> EvalexprResult<Value> {
+    match (base, exponent) {
+        (Value::Int(base_int), Value::Int(exponent_int)) if *exponent_int >= 0 => {
+            let result = match u32::try_from(*exponent_int) {
+                Ok(exponent_u32) => base_int.checked_pow(exponent_u32),
+                // Only the bases 0, 1 and -1 stay in range for such large exponents.
+                Err(_) => match base_int {
+                    0 | 1 => Some(*base_int),
+                    -1 if exponent_int % 2 == 0 => Some(1),
+                    -1 => Some(-1),
+                    _ => None,
+                },
+            };
+            result.map(Value::Int).ok_or_else(|| {
+                EvalexprError::integer_overflow("pow", vec![base.clone(), exponent.clone()])
+            })
+        },
+        _ => Ok(Value::Float(base.as_number()?.powf(exponent.as_number()?))),
+    }
+}
+
+/// Expects an integer argument that is the index of a bit of an `IntType`, counted from the least significant bit, and not smaller than `min`.
+/// Returns `Err(Error::ArgumentOutOfRange)` if the index is not within `min` to 63.
+#[cfg(feature = "builtin_math")]
+fn expect_bit_index(function: &str, argument: &Value, min: u32) -> EvalexprResult<u32> {
+    let index = argument.as_int()?;
+    u32::try_from(index)
+        .ok()
+        .filter(|index| (min..IntType::BITS).contains(index))
+        .ok_or_else(|| {
+            EvalexprError::argument_out_of_range(
+                function,
+                Value::Int(index),
+                format!("a bit index from {} to {}", min, IntType::BITS - 1),
+            )
+        })
+}
+
+/// Expects exactly two string arguments.
+#[cfg(feature = "builtin_string")]
+fn expect_two_strings(argument: &Value) -> EvalexprResult<(&str, &str)> {
+    let arguments = expect_tuple(argument)?;
+    expect_function_argument_amount(arguments.len(), 2)?;
+    Ok((expect_string(&arguments[0])?, expect_string(&arguments[1])?))
+}
+
+/// Splits the arguments of `all_of` and `any_of` into the tuple of elements, the comparison operator and the right-hand side.
+#[cfg(feature = "builtin_tuple")]
+fn expect_comparison_arguments(argument: &Value) -> EvalexprResult<(&TupleType, Operator, &Value)> {
+    let arguments = expect_tuple(argument)?;
+    expect_function_argument_amount(arguments.len(), 3)?;
+
+    let elements = expect_tuple(&arguments[0])?;
+    let comparison = match expect_string(&arguments[1])? {
+        "==" => Operator::Eq,
+        "!=" => Operator::Neq,
+        ">" => Operator::Gt,
+        "<" => Operator::Lt,
+        ">=" => Operator::Geq,
+        "<=" => Operator::Leq,
+        other => {
+            return Err(EvalexprError::unknown_comparison_operator(
+                other.to_string(),
+            ))
+        },
+    };
+    Ok((elements, comparison, &arguments[2]))
+}
+
+/// Compares `element` with `rhs` using the given comparison operator.
+#[cfg(feature = "builtin_tuple")]
+fn compare(element: &Value, comparison: &Operator, rhs: &Value) -> EvalexprResult<bool> {
+    comparison
+        .eval(
+            &[element.clone(), rhs.clone()],
+            &EmptyContext,
+            &EvalConfig::default(),
+        )?
+        .as_boolean()
+}
+
+/// Expects exactly two tuple arguments.
+#[cfg(feature = "builtin_tuple")]
+fn expect_two_tuples(argument: &Value) -> EvalexprResult<(&TupleType, &TupleType)> {
+    let arguments = expect_tuple(argument)?;
+    expect_function_argument_amount(arguments.len(), 2)?;
+    Ok((expect_tuple(&arguments[0])?, expect_tuple(&arguments[1])?))
+}
+
+/// Parses the arguments of `range` into the start, the amount of elements and the step.
+/// The arguments are `end`, `(start, end)` or `(start, end, step)`, where the start defaults to zero and the step to one.
+#[cfg(feature = "builtin_tuple")]
+fn expect_range_arguments(argument: &Value) -> EvalexprResult<(i128, u128, i128)> {
+    let (start, end, step) = match argument {
+        Value::Tuple(arguments) => match arguments.as_slice() {
+            [start, end] => (start.as_int()?, end.as_int()?, 1),
+            [start, end, step] => (start.as_int()?, end.as_int()?, step.as_int()?),
+            _ => {
+                return Err(EvalexprError::wrong_function_argument_amount(
+                    arguments.len(),
+                    arguments.len().clamp(2, 3),
+                ))
+            },
+        },
+        end => (0, end.as_int()?, 1),
+    };
+    let (start, end, step) = (start as i128, end as i128, step as i128);
+
+    // The range ends before the first element that reaches or passes `end`.
+    let length = if step > 0 && start < end {
+        (end - start - 1) / step + 1
+    } else if step < 0 && start > end {
+        (start - end - 1) / -step + 1
+    } else if step == 0 {
+        return Err(EvalexprError::RangeStepZero);
+    } else {
+        0
+    };
+    Ok((start, length as u128, step))
+}
+
+/// Returns the amount of bytes that a tuple of a range with the given amount of elements occupies on the heap.
+#[cfg(feature = "builtin_tuple")]
+fn range_allocated_bytes(length: u128) -> u128 {
+    length * mem::size_of::<Value>() as u128
+}
+
+/// Returns the amount of bytes that calling `range` with the given argument allocates, or `None` if the argument is invalid.
+#[cfg(feature = "builtin_tuple")]
+pub(crate) fn range_allocation_hint(argument: &Value) -> Option<usize> {
+    let (_, length, _) = expect_range_arguments(argument).ok()?;
+    Some(range_allocated_bytes(length).min(usize::MAX as u128) as usize)
+}
+
+/// Returns the first occurrences of the distinct elements, in their original order.
+/// If `filter` is `Some((other, contained))`, only elements that are contained in `other` (if `contained` is true) or not contained in `other` (if `contained` is false) are returned.
+#[cfg(feature = "builtin_tuple")]
+fn distinct_elements(elements: &[Value], filter: Option<(&[Value], bool)>) -> TupleType {
+    let filter = filter.map(|(other, contained)| {
+        let other: HashSet<_> = other.iter().filter_map(SetKey::new).collect();
+        (other, contained)
+    });
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+
+    for element in elements {
+        match SetKey::new(element) {
+            Some(key) => {
+                if let Some((other, contained)) = &filter {
+                    if other.contains(&key) != *contained {
+                        continue;
+                    }
+                }
+                if seen.insert(key) {
+                    result.push(element.clone());
+                }
+            },
+            // A value containing NaN is not equal to any value, not even to itself.
+            None => {
+                if !filter.as_ref().is_some_and(|(_, contained)| *contained) {
+                    result.push(element.clone());
+                }
+            },
+        }
+    }
+    result
+}
+
+/// A hashable representation of a value for the set functions.
+/// Integers and floats with the same numeric value have the same key.
+#[cfg(feature = "builtin_tuple")]
+#[derive(PartialEq, Eq, Hash)]
+enum SetKey {
+    String(String),
+    Float(u64),
+    Int(IntType),
+    Boolean(bool),
+    Tuple(Vec<SetKey>),
+    Empty,
+}
+
+#[cfg(feature = "builtin_tuple")]
+impl SetKey {
+    /// Returns `None` if the value is or contains NaN, as such values are not equal to any value.
+    fn new(value: &Value) -> Option<Self> {
+        Some(match value {
+            Value::String(string) => SetKey::String(string.clone()),
+            Value::Float(float) if float.is_nan() => return None,
+            Value::Float(float) => match float_as_exact_int(*float) {
+                Some(int) => SetKey::Int(int),
+                None => SetKey::Float(float.to_bits()),
+            },
+            Value::Int(int) => SetKey::Int(*int),
+            Value::Boolean(boolean) => SetKey::Boolean(*boolean),
+            Value::Tuple(tuple) => {
+                SetKey::Tuple(tuple.iter().map(SetKey::new).collect::<Option<_>>()?)
+            },
+            Value::Empty => SetKey::Empty,
+        })
+    }
+}
+
+/// Returns the float as integer if it has an integral value that an `IntType` can represent exactly.
+#[cfg(feature = "builtin_tuple")]
+fn float_as_exact_int(float: FloatType) -> Option<IntType> {
+    // -2^63 and 2^63 are exactly representable, unlike IntType::MAX.
+    let bound = -(IntType::MIN as FloatType);
+    if float.fract() == 0.0 && float >= -bound && float < bound {
+        Some(float as IntType)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "geo_support")]
+const EARTH_RADIUS_KM: FloatType = 6371.0;
+#[cfg(feature = "geo_support")]
+const KILOMETRES_PER_MILE: FloatType = 1.609344;
+
+/// Expects a tuple of `amount` numbers that are alternating latitudes and longitudes in degrees.
+/// Returns `Err(Error::CoordinateOutOfRange)` if a latitude is not within `[-90, 90]` or a longitude is not within `[-180, 180]`.
+#[cfg(feature = "geo_support")]
+fn expect_coordinates(argument: &Value, amount: usize) -> EvalexprResult<Vec<FloatType>> {
+    let arguments = expect_tuple(argument)?;
+    expect_function_argument_amount(arguments.len(), amount)?;
+
+    let mut coordinates = Vec::with_capacity(amount);
+    for (index, argument) in arguments.iter().enumerate() {
+        let coordinate = argument.as_number()?;
+        let kind = if index % 2 == 0 {
+            Coordinate::Latitude
+        } else {
+            Coordinate::Longitude
+        };
+        // Written negated so that NaN is rejected as well.
+        if !(-kind.bound()..=kind.bound()).contains(&coordinate) {
+            return Err(EvalexprError::coordinate_out_of_range(kind, coordinate));
+        }
+        coordinates.push(coordinate);
+    }
+    Ok(coordinates)
+}
+
+/// Computes the great-circle distance in kilometres between the points `(lat1, lon1)` and `(lat2, lon2)` given in degrees.
+#[cfg(feature = "geo_support")]
+fn haversine_km(coordinates: &[FloatType]) -> FloatType {
+    let (lat1, lon1) = (coordinates[0].to_radians(), coordinates[1].to_radians());
+    let (lat2, lon2) = (coordinates[2].to_radians(), coordinates[3].to_radians());
+
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Returns the instant that `now_monotonic` measures from, which is the first time this function is called.
+#[cfg(feature = "builtin_datetime")]
+fn monotonic_epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Returns the signature of the builtin function with the given identifier, for static type checking.
+pub(crate) fn builtin_signature(identifier: &str) -> Option<Signature> {
+    #[cfg(any(
+        feature = "builtin_math",
+        feature = "builtin_string",
+        feature = "builtin_tuple",
+        feature = "builtin_datetime",
+        feature = "builtin_regex",
+        feature = "builtin_crypto",
+        feature = "geo_support",
+        feature = "unicode_support"
+    ))]
+    use typecheck::StaticType::*;
+    #[cfg(feature = "excel_compat")]
+    if let Some(signature) = excel::excel_signature(identifier) {
+        return Some(signature);
+    }
+
+    match identifier {
+        #[cfg(feature = "builtin_math")]
+        "min" | "max" => Some(Signature::variadic(Number)),
+        #[cfg(feature = "builtin_math")]
+        "abs" | "signum" => Some(Signature::new(vec![Number], Number)),
+        #[cfg(feature = "builtin_math")]
+        "pow" => Some(Signature::new(vec![Number, Number], Number)),
+        #[cfg(feature = "builtin_math")]
+        "sqrt" | "ln" => Some(Signature::new(vec![Number], Float)),
+        #[cfg(feature = "builtin_math")]
+        "bit" => Some(Signature::new(vec![Int, Int], Int)),
+        #[cfg(feature = "builtin_math")]
+        "bits" => Some(Signature::new(vec![Int, Int, Int], Int)),
+        #[cfg(feature = "builtin_math")]
+        "popcount" | "leading_zeros" | "trailing_zeros" => Some(Signature::new(vec![Int], Int)),
+        #[cfg(feature = "builtin_tuple")]
+        "concat" => Some(Signature::variadic(Any)),
+        #[cfg(feature = "builtin_tuple")]
+        "all_of" | "any_of" => Some(Signature::new(vec![Tuple, String, Any], Boolean)),
+        #[cfg(feature = "builtin_tuple")]
+        "unique" => Some(Signature::new(vec![Tuple], Tuple)),
+        #[cfg(feature = "builtin_tuple")]
+        "intersect" | "union" | "difference" => Some(Signature::new(vec![Tuple, Tuple], Tuple)),
+        #[cfg(feature = "builtin_tuple")]
+        "range" => Some(Signature::variadic(Tuple)),
+        #[cfg(feature = "builtin_tuple")]
+        "map" | "filter" => Some(Signature::new(vec![Tuple, Any], Tuple)),
+        #[cfg(feature = "builtin_datetime")]
+        "now_monotonic" => Some(Signature::new(vec![], Float)),
+        #[cfg(feature = "geo_support")]
+        "haversine_km" | "haversine_miles" => {
+            Some(Signature::new(vec![Number, Number, Number, Number], Float))
+        },
+        #[cfg(feature = "geo_support")]
+        "in_bbox" => Some(Signature::new(vec![Number; 6], Boolean)),
+        #[cfg(feature = "builtin_string")]
+        "str" => Some(Signature::new(vec![Any], String)),
+        #[cfg(feature = "builtin_string")]
+        "len" => Some(Signature::new(vec![String], Int)),
+        #[cfg(feature = "builtin_regex")]
+        "str::regex_matches" => Some(Signature::new(vec![String, String], Boolean)),
+        #[cfg(feature = "builtin_regex")]
+        "str::regex_replace" => Some(Signature::new(vec![String, String, String], String)),
+        #[cfg(feature = "builtin_string")]
+        "str::to_lowercase" | "str::to_uppercase" | "str::trim" => {
+            Some(Signature::new(vec![String], String))
+        },
+        #[cfg(feature = "builtin_string")]
+        "str::starts_with" | "str::ends_with" => {
+            Some(Signature::new(vec![String, String], Boolean))
+        },
+        #[cfg(feature = "builtin_string")]
+        "str::strip_prefix" | "str::strip_suffix" => {
+            Some(Signature::new(vec![String, String], String))
+        },
+        #[cfg(feature = "builtin_string")]
+        "matches_glob" | "matches_glob_ci" => Some(Signature::new(vec![String, String], Boolean)),
+        #[cfg(feature = "builtin_string")]
+        "char_at" => Some(Signature::new(vec![String, Int], String)),
+        #[cfg(feature = "builtin_string")]
+        "code_point" => Some(Signature::new(vec![String], Int)),
+        #[cfg(feature = "builtin_string")]
+        "from_code_point" => Some(Signature::new(vec![Int], String)),
+        #[cfg(feature = "builtin_string")]
+        "humanize_bytes" | "humanize_duration" => Some(Signature::new(vec![Number], String)),
+        #[cfg(feature = "builtin_string")]
+        "parse_bytes" | "parse_duration" => Some(Signature::new(vec![String], Int)),
+        #[cfg(feature = "unicode_support")]
+        "str::casefold" => Some(Signature::new(vec![String], String)),
+        #[cfg(feature = "unicode_support")]
+        "str::width" => Some(Signature::new(vec![String], Int)),
+        #[cfg(feature = "builtin_crypto")]
+        "str::sha256" => Some(Signature::new(vec![String], String)),
+        _ => None,
+    }
+}
+
+/// The identifiers of all builtin functions together with the feature flag that enables them.
+const BUILTIN_FUNCTION_FEATURES: &[(&str, &str)] = &[
+    ("min", "builtin_math"),
+    ("max", "builtin_math"),
+    ("abs", "builtin_math"),
+    ("signum", "builtin_math"),
+    ("pow", "builtin_math"),
+    ("sqrt", "builtin_math"),
+    ("ln", "builtin_math"),
+    ("bit", "builtin_math"),
+    ("bits", "builtin_math"),
+    ("popcount", "builtin_math"),
+    ("leading_zeros", "builtin_math"),
+    ("trailing_zeros", "builtin_math"),
+    ("concat", "builtin_tuple"),
+    ("all_of", "builtin_tuple"),
+    ("any_of", "builtin_tuple"),
+    ("unique", "builtin_tuple"),
+    ("intersect", "builtin_tuple"),
+    ("union", "builtin_tuple"),
+    ("difference", "builtin_tuple"),
+    ("range", "builtin_tuple"),
+    ("map", "builtin_tuple"),
+    ("filter", "builtin_tuple"),
+    ("now_monotonic", "builtin_datetime"),
+    ("haversine_km", "geo_support"),
+    ("haversine_miles", "geo_support"),
+    ("in_bbox", "geo_support"),
+    ("str", "builtin_string"),
+    ("len", "builtin_string"),
+    ("str::regex_matches", "builtin_regex"),
+    ("str::regex_replace", "builtin_regex"),
+    ("str::to_lowercase", "builtin_string"),
+    ("str::to_uppercase", "builtin_string"),
+    ("str::trim", "builtin_string"),
+    ("str::starts_with", "builtin_string"),
+    ("str::ends_with", "builtin_string"),
+    ("str::strip_prefix", "builtin_string"),
+    ("str::strip_suffix", "builtin_string"),
+    ("matches_glob", "builtin_string"),
+    ("matches_glob_ci", "builtin_string"),
+    ("char_at", "builtin_string"),
+    ("code_point", "builtin_string"),
+    ("from_code_point", "builtin_string"),
+    ("humanize_bytes", "builtin_string"),
+    ("humanize_duration", "builtin_string"),
+    ("parse_bytes", "builtin_string"),
+    ("parse_duration", "builtin_string"),
+    ("str::casefold", "unicode_support"),
+    ("str::width", "unicode_support"),
+    ("str::sha256", "builtin_crypto"),
+    ("IF", "excel_compat"),
+    ("AND", "excel_compat"),
+    ("OR", "excel_compat"),
+    ("SUMIF", "excel_compat"),
+    ("ROUND", "excel_compat"),
+    ("CONCATENATE", "excel_compat"),
+    ("LEFT", "excel_compat"),
+    ("RIGHT", "excel_compat"),
+    ("MID", "excel_compat"),
+];
+
+/// Returns the identifiers of all builtin functions that are enabled by the feature flags of this build.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let identifiers = builtin_function_identifiers();
+/// assert_eq!(identifiers.contains(&"min"), cfg!(feature = "builtin_math"));
+/// ```
+pub fn builtin_function_identifiers() -> Vec<&'static str> {
+    BUILTIN_FUNCTION_FEATURES
+        .iter()
+        .map(|(identifier, _)| *identifier)
+        .filter(|identifier| is_builtin_enabled(identifier))
+        .collect()
+}
+
+/// Returns the feature flag that enables the builtin function with the given identifier, if the function exists but is disabled in this build.
+pub(crate) fn disabled_builtin_feature(identifier: &str) -> Option<&'static str> {
+    BUILTIN_FUNCTION_FEATURES
+        .iter()
+        .find(|(builtin, feature)| {
+            // The spreadsheet functions are found in any case.
+            *builtin == identifier
+                || (*feature == "excel_compat" && builtin.eq_ignore_ascii_case(identifier))
+        })
+        .filter(|(identifier, _)| !is_builtin_enabled(identifier))
+        .map(|(_, feature)| *feature)
+}
+
+/// Returns true if the builtin function with the given identifier is enabled in this build.
+fn is_builtin_enabled(identifier: &str) -> bool {
+    builtin_function(identifier).is_some() || is_higher_order_builtin(identifier)
+}
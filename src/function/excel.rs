@@ -0,0 +1,409 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::slice;
+
+use error::{expect_function_argument_amount, EvalexprError, EvalexprResult};
+use function::glob::Glob;
+use typecheck::Signature;
+use value::{FloatType, IntType, Value};
+use Function;
+
+/// The identifiers of the spreadsheet functions, in upper case.
+const EXCEL_FUNCTION_IDENTIFIERS: &[&str] = &[
+    "IF",
+    "AND",
+    "OR",
+    "SUMIF",
+    "ROUND",
+    "CONCATENATE",
+    "LEFT",
+    "RIGHT",
+    "MID",
+];
+
+/// Returns the spreadsheet function with the given identifier, which is matched case-insensitively.
+pub(crate) fn excel_function(identifier: &str) -> Option<Function> {
+    let function: fn(&Value) -> EvalexprResult<Value> = match excel_identifier(identifier)? {
+        "IF" => excel_if,
+        "AND" => |argument| excel_logical(argument, true),
+        "OR" => |argument| excel_logical(argument, false),
+        "SUMIF" => excel_sumif,
+        "ROUND" => excel_round,
+        "CONCATENATE" => excel_concatenate,
+        "LEFT" => |argument| excel_left_right(argument, "LEFT"),
+        "RIGHT" => |argument| excel_left_right(argument, "RIGHT"),
+        "MID" => excel_mid,
+        _ => return None,
+    };
+    Some(Function::new(Box::new(function)))
+}
+
+/// Returns the signature of the spreadsheet function with the given identifier, which is matched case-insensitively.
+pub(crate) fn excel_signature(identifier: &str) -> Option<Signature> {
+    use typecheck::StaticType::*;
+    match excel_identifier(identifier)? {
+        "IF" => Some(Signature::variadic(Any)),
+        "AND" | "OR" => Some(Signature::variadic(Boolean)),
+        "SUMIF" | "ROUND" => Some(Signature::variadic(Number)),
+        "CONCATENATE" | "LEFT" | "RIGHT" => Some(Signature::variadic(String)),
+        "MID" => Some(Signature::new(vec![String, Int, Int], String)),
+        _ => None,
+    }
+}
+
+/// Returns the upper case identifier of the spreadsheet function with the given identifier in any case.
+fn excel_identifier(identifier: &str) -> Option<&'static str> {
+    EXCEL_FUNCTION_IDENTIFIERS
+        .iter()
+        .find(|excel_identifier| excel_identifier.eq_ignore_ascii_case(identifier))
+        .copied()
+}
+
+/// `IF(condition, then, else)` returns `then` if the condition is true, and `else` otherwise.
+/// If `else` is omitted, it is `false`.
+fn excel_if(argument: &Value) -> EvalexprResult<Value> {
+    let arguments = expect_tuple_of(argument, 2, 3)?;
+    if expect_logical(&arguments[0])? {
+        Ok(arguments[1].clone())
+    } else {
+        Ok(arguments.get(2).cloned().unwrap_or(Value::Boolean(false)))
+    }
+}
+
+/// `AND(...)` and `OR(...)` over any amount of logical values, where tuples count as ranges of values.
+fn excel_logical(argument: &Value, and: bool) -> EvalexprResult<Value> {
+    let mut amount = 0;
+    let mut result = and;
+    for argument in arguments(argument) {
+        for value in arguments(argument) {
+            amount += 1;
+            if expect_logical(value)? != and {
+                result = !and;
+            }
+        }
+    }
+    if amount == 0 {
+        return Err(EvalexprError::wrong_function_argument_amount(0, 1));
+    }
+    Ok(Value::Boolean(result))
+}
+
+/// `SUMIF(range, criterion, sum_range)` sums the numbers of `sum_range`, or of `range` if it is omitted, at the positions where `range` matches the criterion.
+fn excel_sumif(argument: &Value) -> EvalexprResult<Value> {
+    let arguments = expect_tuple_of(argument, 2, 3)?;
+    let range = arguments[0].as_tuple()?;
+    let criterion = Criterion::new(&arguments[1])?;
+    let sum_range = match arguments.get(2) {
+        Some(sum_range) => {
+            let sum_range = sum_range.as_tuple()?;
+            if sum_range.len() != range.len() {
+                return Err(EvalexprError::argument_out_of_range(
+                    "SUMIF",
+                    Value::Tuple(sum_range),
+                    format!("a tuple of {} elements like the range", range.len()),
+                ));
+            }
+            sum_range
+        },
+        None => range.clone(),
+    };
+
+    let mut int_sum: IntType = 0;
+    let mut float_sum: Option<FloatType> = None;
+    for (value, summand) in range.iter().zip(&sum_range) {
+        if !criterion.matches(value) {
+            continue;
+        }
+        match (summand, &mut float_sum) {
+            (Value::Int(summand), None) => match int_sum.checked_add(*summand) {
+                Some(sum) => int_sum = sum,
+                None => float_sum = Some(int_sum as FloatType + *summand as FloatType),
+            },
+            (Value::Int(summand), Some(sum)) => *sum += *summand as FloatType,
+            (Value::Float(summand), None) => float_sum = Some(int_sum as FloatType + summand),
+            (Value::Float(summand), Some(sum)) => *sum += summand,
+            // Text and logical values are not summed, like in spreadsheets.
+            _ => {},
+        }
+    }
+    Ok(float_sum.map_or(Value::Int(int_sum), Value::Float))
+}
+
+/// `ROUND(number, digits)` rounds half away from zero to the given amount of decimal digits, which may be negative to round to tens, hundreds and so on.
+fn excel_round(argument: &Value) -> EvalexprResult<Value> {
+    let arguments = expect_tuple_of(argument, 2, 2)?;
+    let digits = arguments[1].as_int()?;
+    match &arguments[0] {
+        Value::Int(int) if digits >= 0 => Ok(Value::Int(*int)),
+        Value::Int(int) => round_int(*int, digits),
+        number => Ok(Value::Float(round_float(number.as_number()?, digits))),
+    }
+}
+
+fn round_int(int: IntType, digits: IntType) -> EvalexprResult<Value> {
+    // Rounding to more digits than an integer has results in zero.
+    let factor = match u32::try_from(digits.unsigned_abs())
+        .ok()
+        .and_then(|digits| (10 as IntType).checked_pow(digits))
+    {
+        Some(factor) => factor,
+        None => return Ok(Value::Int(0)),
+    };
+    let (quotient, remainder) = (int / factor, int % factor);
+    let quotient = if remainder.unsigned_abs() * 2 >= factor.unsigned_abs() {
+        quotient + int.signum()
+    } else {
+        quotient
+    };
+    quotient.checked_mul(factor).map(Value::Int).ok_or_else(|| {
+        EvalexprError::multiplication_error(Value::Int(quotient), Value::Int(factor))
+    })
+}
+
+fn round_float(number: FloatType, digits: IntType) -> FloatType {
+    if !number.is_finite() {
+        return number;
+    }
+    let digits = digits.clamp(-400, 400) as i32;
+    let factor = (10.0 as FloatType).powi(digits.abs());
+    if factor.is_infinite() {
+        return if digits >= 0 { number } else { 0.0 };
+    }
+    let scaled = if digits >= 0 {
+        number * factor
+    } else {
+        number / factor
+    };
+    if !scaled.is_finite() {
+        return number;
+    }
+    // Spreadsheets compute with 15 significant digits, so 2.675 is rounded as written, and not as its binary approximation 2.67499999...
+    let rounded = to_significant_digits(scaled).round();
+    if digits >= 0 {
+        rounded / factor
+    } else {
+        rounded * factor
+    }
+}
+
+/// `CONCATENATE(...)` joins the text of any amount of values.
+fn excel_concatenate(argument: &Value) -> EvalexprResult<Value> {
+    let mut result = String::new();
+    for argument in arguments(argument) {
+        result.push_str(&to_text(argument)?);
+    }
+    Ok(Value::String(result))
+}
+
+/// `LEFT(text, count)` and `RIGHT(text, count)` return the first or last characters of a text, one if the count is omitted.
+fn excel_left_right(argument: &Value, function: &str) -> EvalexprResult<Value> {
+    let (text, count) = match argument {
+        Value::Tuple(arguments) => {
+            expect_function_argument_amount(arguments.len(), 2)?;
+            (to_text(&arguments[0])?, arguments[1].as_int()?)
+        },
+        argument => (to_text(argument)?, 1),
+    };
+    let count = expect_count(function, count)?;
+    let length = text.chars().count();
+    let result: String = if function == "LEFT" {
+        text.chars().take(count).collect()
+    } else {
+        text.chars().skip(length.saturating_sub(count)).collect()
+    };
+    Ok(Value::String(result))
+}
+
+/// `MID(text, start, count)` returns `count` characters of a text, starting at the character with the one-based index `start`.
+fn excel_mid(argument: &Value) -> EvalexprResult<Value> {
+    let arguments = expect_tuple_of(argument, 3, 3)?;
+    let text = to_text(&arguments[0])?;
+    let start = arguments[1].as_int()?;
+    if start < 1 {
+        return Err(EvalexprError::argument_out_of_range(
+            "MID",
+            Value::Int(start),
+            "a start position of at least 1".to_string(),
+        ));
+    }
+    let count = expect_count("MID", arguments[2].as_int()?)?;
+    let start = usize::try_from(start - 1).unwrap_or(usize::MAX);
+    Ok(Value::String(
+        text.chars().skip(start).take(count).collect(),
+    ))
+}
+
+/// A condition of `SUMIF`, like `">5"`, `"<>apple"`, `"a*"` or `3`.
+enum Criterion {
+    Number(Ordering, bool, FloatType),
+    Boolean(bool, bool),
+    Text(Ordering, bool, String),
+    Pattern(bool, Glob),
+}
+
+impl Criterion {
+    fn new(criterion: &Value) -> EvalexprResult<Self> {
+        let criterion = match criterion {
+            Value::String(criterion) => criterion,
+            Value::Boolean(boolean) => return Ok(Criterion::Boolean(*boolean, true)),
+            criterion => {
+                return Ok(Criterion::Number(
+                    Ordering::Equal,
+                    true,
+                    criterion.as_number()?,
+                ))
+            },
+        };
+
+        // The ordering that a value must have compared to the operand, and whether it must have it or must not have it.
+        let (ordering, expected, operand) = [
+            ("<=", Ordering::Greater, false),
+            (">=", Ordering::Less, false),
+            ("<>", Ordering::Equal, false),
+            ("<", Ordering::Less, true),
+            (">", Ordering::Greater, true),
+            ("=", Ordering::Equal, true),
+        ]
+        .iter()
+        .find_map(|(prefix, ordering, expected)| {
+            criterion
+                .strip_prefix(prefix)
+                .map(|operand| (*ordering, *expected, operand))
+        })
+        .unwrap_or((Ordering::Equal, true, criterion.as_str()));
+
+        if let Ok(number) = operand.parse::<FloatType>() {
+            Ok(Criterion::Number(ordering, expected, number))
+        } else if operand.eq_ignore_ascii_case("true") || operand.eq_ignore_ascii_case("false") {
+            Ok(Criterion::Boolean(
+                operand.eq_ignore_ascii_case("true"),
+                expected == (ordering == Ordering::Equal),
+            ))
+        } else if ordering == Ordering::Equal {
+            Ok(Criterion::Pattern(
+                expected,
+                Glob::new(&excel_pattern_to_glob(operand))?.case_insensitive(),
+            ))
+        } else {
+            Ok(Criterion::Text(ordering, expected, operand.to_lowercase()))
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Criterion::Number(ordering, expected, number), Value::Int(_) | Value::Float(_)) => {
+                let value_ordering = value
+                    .as_number()
+                    .ok()
+                    .and_then(|value| value.partial_cmp(number));
+                (value_ordering == Some(*ordering)) == *expected
+            },
+            (Criterion::Boolean(boolean, expected), Value::Boolean(value)) => {
+                (value == boolean) == *expected
+            },
+            (Criterion::Text(ordering, expected, text), Value::String(value)) => {
+                (value.to_lowercase().cmp(text) == *ordering) == *expected
+            },
+            (Criterion::Pattern(expected, glob), Value::String(value)) => {
+                glob.matches(value) == *expected
+            },
+            // A value of another type only matches a criterion that excludes something, like "<>5".
+            (Criterion::Number(Ordering::Equal, expected, _), _)
+            | (Criterion::Boolean(_, expected), _)
+            | (Criterion::Pattern(expected, _), _) => !*expected,
+            _ => false,
+        }
+    }
+}
+
+/// Converts a spreadsheet wildcard pattern, where `~` escapes `*`, `?` and itself, into a `Glob` pattern.
+fn excel_pattern_to_glob(pattern: &str) -> String {
+    let mut glob = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some(escaped) => {
+                    glob.push('\\');
+                    glob.push(escaped);
+                },
+                None => glob.push('~'),
+            },
+            '\\' | '[' | ']' => {
+                glob.push('\\');
+                glob.push(c);
+            },
+            c => glob.push(c),
+        }
+    }
+    glob
+}
+
+/// Returns the arguments of a function call, which are the elements of a tuple, or else the argument itself.
+fn arguments(argument: &Value) -> &[Value] {
+    match argument {
+        Value::Tuple(tuple) => tuple,
+        argument => slice::from_ref(argument),
+    }
+}
+
+/// Expects a tuple with at least `min` and at most `max` arguments.
+fn expect_tuple_of(argument: &Value, min: usize, max: usize) -> EvalexprResult<&[Value]> {
+    let arguments = arguments(argument);
+    if arguments.len() < min {
+        Err(EvalexprError::wrong_function_argument_amount(
+            arguments.len(),
+            min,
+        ))
+    } else if arguments.len() > max {
+        Err(EvalexprError::wrong_function_argument_amount(
+            arguments.len(),
+            max,
+        ))
+    } else {
+        Ok(arguments)
+    }
+}
+
+/// Expects a logical value, which is a boolean, or a number that is true if it is not zero.
+fn expect_logical(value: &Value) -> EvalexprResult<bool> {
+    match value {
+        Value::Boolean(boolean) => Ok(*boolean),
+        Value::Int(int) => Ok(*int != 0),
+        Value::Float(float) => Ok(*float != 0.0),
+        value => Err(EvalexprError::expected_boolean(value.clone())),
+    }
+}
+
+/// Expects a count of characters, which must not be negative.
+fn expect_count(function: &str, count: IntType) -> EvalexprResult<usize> {
+    usize::try_from(count).map_err(|_| {
+        EvalexprError::argument_out_of_range(
+            function,
+            Value::Int(count),
+            "a count of at least 0".to_string(),
+        )
+    })
+}
+
+/// Converts a value to text like spreadsheets do, with numbers rounded to 15 significant digits and logical values in upper case.
+fn to_text(value: &Value) -> EvalexprResult<String> {
+    match value {
+        Value::String(string) => Ok(string.clone()),
+        Value::Int(int) => Ok(int.to_string()),
+        Value::Float(float) => Ok(to_significant_digits(*float).to_string()),
+        Value::Boolean(true) => Ok("TRUE".to_string()),
+        Value::Boolean(false) => Ok("FALSE".to_string()),
+        Value::Empty => Ok(String::new()),
+        value => Err(EvalexprError::expected_string(value.clone())),
+    }
+}
+
+/// Rounds the given number to 15 significant digits, the precision of spreadsheets.
+fn to_significant_digits(number: FloatType) -> FloatType {
+    if number.is_finite() {
+        format!("{:.14e}", number).parse().unwrap_or(number)
+    } else {
+        number
+    }
+}
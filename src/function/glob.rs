@@ -0,0 +1,260 @@
+use error::{EvalexprError, EvalexprResult};
+
+/// A parsed shell-style wildcard pattern.
+///
+/// `*` matches any sequence of characters, `?` matches any single character, and `[...]` matches a single character of a class.
+/// A class consists of characters and ranges like `a-z`, and is negated if it starts with `!` or `^`.
+/// A `]` directly after the opening bracket or the negation is part of the class.
+/// A backslash escapes the following character, so `\*` matches a literal `*`.
+/// The pattern must match the whole text, not only a part of it.
+/// A case-insensitive pattern also matches a character if its lower-case or upper-case form matches.
+#[derive(Debug)]
+pub(crate) struct Glob {
+    tokens: Vec<GlobToken>,
+    case_insensitive: bool,
+}
+
+#[derive(Debug)]
+enum GlobToken {
+    Literal(char),
+    AnyChar,
+    AnyString,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    /// Parses the given pattern.
+    /// Fails with `EvalexprError::InvalidGlob` if a character class is not terminated, contains a range whose start is greater than its end, or if the pattern ends with a single backslash.
+    pub(crate) fn new(pattern: &str) -> EvalexprResult<Self> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut position = 0;
+
+        while position < chars.len() {
+            let token = match chars[position] {
+                '*' => GlobToken::AnyString,
+                '?' => GlobToken::AnyChar,
+                '\\' => {
+                    position += 1;
+                    match chars.get(position) {
+                        Some(escaped) => GlobToken::Literal(*escaped),
+                        None => {
+                            return Err(invalid_glob(
+                                pattern,
+                                format!(
+                                    "pattern ends with an unescaped '\\' at character {}",
+                                    position - 1
+                                ),
+                            ))
+                        },
+                    }
+                },
+                '[' => {
+                    let (token, end) = parse_class(pattern, &chars, position)?;
+                    position = end;
+                    token
+                },
+                literal => GlobToken::Literal(literal),
+            };
+            // Consecutive stars match the same as a single one.
+            if !matches!(
+                (&token, tokens.last()),
+                (GlobToken::AnyString, Some(GlobToken::AnyString))
+            ) {
+                tokens.push(token);
+            }
+            position += 1;
+        }
+
+        Ok(Glob {
+            tokens,
+            case_insensitive: false,
+        })
+    }
+
+    /// Makes this pattern ignore the case of letters.
+    ///
+    /// The pattern is not changed, so a class like `[Z-a]` stays valid and matches both cases of `z` and `a`.
+    pub(crate) fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Returns true if this pattern matches the whole given text.
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let (mut token_index, mut text_index) = (0, 0);
+        // The position of the last star and the text position it was tried to match up to, to backtrack to if the rest of the pattern does not match.
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while text_index < text.len() {
+            match self.tokens.get(token_index) {
+                Some(GlobToken::AnyString) => {
+                    backtrack = Some((token_index, text_index));
+                    token_index += 1;
+                    continue;
+                },
+                Some(token) if token.matches_with_case(text[text_index], self.case_insensitive) => {
+                    token_index += 1;
+                    text_index += 1;
+                    continue;
+                },
+                _ => {},
+            }
+
+            // Let the last star match one more character.
+            match backtrack {
+                Some((star_index, star_text_index)) => {
+                    backtrack = Some((star_index, star_text_index + 1));
+                    token_index = star_index + 1;
+                    text_index = star_text_index + 1;
+                },
+                None => return false,
+            }
+        }
+
+        self.tokens[token_index..]
+            .iter()
+            .all(|token| matches!(token, GlobToken::AnyString))
+    }
+}
+
+impl GlobToken {
+    /// Returns true if this token matches the given character, or, if `case_insensitive` is set, its lower-case or upper-case form.
+    fn matches_with_case(&self, c: char, case_insensitive: bool) -> bool {
+        if !case_insensitive {
+            return self.matches(c);
+        }
+        match self {
+            GlobToken::Literal(literal) => {
+                simple_lowercase(*literal) == simple_lowercase(c)
+                    || simple_uppercase(*literal) == simple_uppercase(c)
+            },
+            GlobToken::Class { negated, ranges } => {
+                [c, simple_lowercase(c), simple_uppercase(c)]
+                    .iter()
+                    .any(|c| {
+                        ranges
+                            .iter()
+                            .any(|(start, end)| (*start..=*end).contains(c))
+                    })
+                    != *negated
+            },
+            _ => self.matches(c),
+        }
+    }
+
+    /// Returns true if this token matches the given character.
+    /// `AnyString` is handled by the matcher itself.
+    fn matches(&self, c: char) -> bool {
+        match self {
+            GlobToken::Literal(literal) => *literal == c,
+            GlobToken::AnyChar => true,
+            GlobToken::AnyString => false,
+            GlobToken::Class { negated, ranges } => {
+                ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&c))
+                    != *negated
+            },
+        }
+    }
+}
+
+/// Parses the character class that starts with the `[` at the given position.
+/// Returns the class and the position of its closing `]`.
+fn parse_class(pattern: &str, chars: &[char], start: usize) -> EvalexprResult<(GlobToken, usize)> {
+    let mut position = start + 1;
+    let negated = matches!(chars.get(position), Some('!') | Some('^'));
+    if negated {
+        position += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let range_start = match chars.get(position) {
+            Some(']') if !first => return Ok((GlobToken::Class { negated, ranges }, position)),
+            Some('\\') => {
+                position += 1;
+                chars.get(position)
+            },
+            c => c,
+        };
+        let range_start = match range_start {
+            Some(range_start) => *range_start,
+            None => {
+                return Err(invalid_glob(
+                    pattern,
+                    format!(
+                        "unterminated character class starting at character {}",
+                        start
+                    ),
+                ))
+            },
+        };
+        first = false;
+        position += 1;
+
+        // A '-' before the closing ']' is a literal.
+        let range_end = match (chars.get(position), chars.get(position + 1)) {
+            (Some('-'), Some(end)) if *end != ']' => {
+                position += 2;
+                if *end == '\\' {
+                    position += 1;
+                    chars.get(position - 1).copied()
+                } else {
+                    Some(*end)
+                }
+            },
+            _ => Some(range_start),
+        };
+        let range_end = match range_end {
+            Some(range_end) => range_end,
+            None => {
+                return Err(invalid_glob(
+                    pattern,
+                    format!(
+                        "unterminated character class starting at character {}",
+                        start
+                    ),
+                ))
+            },
+        };
+        if range_start > range_end {
+            return Err(invalid_glob(
+                pattern,
+                format!(
+                    "invalid range '{}-{}' in character class",
+                    range_start, range_end
+                ),
+            ));
+        }
+        ranges.push((range_start, range_end));
+    }
+}
+
+/// Returns the lower-case form of the character, or the character itself if its lower-case form consists of several characters.
+fn simple_lowercase(c: char) -> char {
+    let mut lowercase = c.to_lowercase();
+    match (lowercase.next(), lowercase.next()) {
+        (Some(lowercase), None) => lowercase,
+        _ => c,
+    }
+}
+
+/// Returns the upper-case form of the character, or the character itself if its upper-case form consists of several characters, like the one of `ß`.
+fn simple_uppercase(c: char) -> char {
+    let mut uppercase = c.to_uppercase();
+    match (uppercase.next(), uppercase.next()) {
+        (Some(uppercase), None) => uppercase,
+        _ => c,
+    }
+}
+
+fn invalid_glob(pattern: &str, message: String) -> EvalexprError {
+    EvalexprError::invalid_glob(pattern.to_string(), message)
+}
@@ -0,0 +1,118 @@
+use std::{mem, slice};
+
+use context::{Context, ItContext, IT_IDENTIFIER};
+use error::{expect_function_argument_amount, EvalexprResult};
+use function::builtin::builtin_function;
+use operator::Operator;
+use state::EvalState;
+use value::Value;
+use Node;
+
+/// Returns true if the given identifier is a builtin function that evaluates its second argument once for each element of its first argument.
+///
+/// Such functions are not available through `builtin_function`, as they need the unevaluated expression of their second argument.
+pub(crate) fn is_higher_order_builtin(identifier: &str) -> bool {
+    cfg!(feature = "builtin_tuple") && matches!(identifier, "map" | "filter")
+}
+
+/// Returns the identifier of the higher-order builtin function that the given operator calls, or `None` if it calls something else or the function is overridden by the context.
+pub(crate) fn higher_order_identifier<'a>(
+    operator: &'a Operator,
+    context: &dyn Context,
+) -> Option<&'a str> {
+    match operator {
+        Operator::FunctionIdentifier { identifier }
+            if is_higher_order_builtin(identifier)
+                && context.get_function(identifier).is_none() =>
+        {
+            Some(identifier)
+        },
+        _ => None,
+    }
+}
+
+/// Returns the amount of arguments of a call of a higher-order builtin function, whose argument without root nodes has the given operator and amount of children.
+pub(crate) fn higher_order_argument_amount(argument: &Operator, child_count: usize) -> usize {
+    match argument {
+        Operator::Tuple => child_count,
+        Operator::RootNode if child_count == 0 => 0,
+        _ => 1,
+    }
+}
+
+/// Checks that the argument of a higher-order builtin function, given by its operator and amount of children, consists of two expressions.
+pub(crate) fn expect_higher_order_arguments(
+    argument: &Operator,
+    child_count: usize,
+) -> EvalexprResult<()> {
+    expect_function_argument_amount(higher_order_argument_amount(argument, child_count), 2)
+}
+
+/// Applies the higher-order builtin function with the given identifier to the elements of the given tuple.
+///
+/// For each element, `eval_body` evaluates the second argument of the call, whose operator is `body`, with `it` linked to the element.
+/// If the second argument is only the identifier of a function, like in `map(xs, double)`, the function is called with each element instead.
+/// Such calls follow the configuration of the given state like calls within the second argument, so they can be cancelled, count towards `EvalConfig::max_result_bytes` and are checked against `EvalConfig::nan_policy`.
+pub(crate) fn eval_higher_order<F>(
+    identifier: &str,
+    elements: Value,
+    body: &Operator,
+    scope: &mut ItContext,
+    state: &EvalState,
+    mut eval_body: F,
+) -> EvalexprResult<Value>
+where
+    F: FnMut(&mut ItContext) -> EvalexprResult<Value>,
+{
+    let elements = elements.as_tuple()?;
+    let function_call = function_call(body, scope);
+
+    let mut results = Vec::new();
+    for element in elements {
+        let (element, result) = match &function_call {
+            Some(function_call) => {
+                state.check_cancelled()?;
+                let arguments = slice::from_ref(&element);
+                let allocation_hint = Node::allocation_hint(function_call, arguments, scope, state);
+                let result = Node::allocate(state, allocation_hint, || {
+                    let result = function_call.eval(arguments, scope, state.config());
+                    Node::apply_nan_policy(function_call, arguments, scope, state, result)
+                });
+                (element, result)
+            },
+            None => {
+                scope.set_it(element);
+                let result = eval_body(scope);
+                (mem::replace(scope.it_mut(), Value::Empty), result)
+            },
+        };
+
+        let result = result?;
+        if identifier == "filter" {
+            if result.as_boolean()? {
+                results.push(element);
+            }
+        } else {
+            results.push(result);
+        }
+    }
+    Ok(Value::Tuple(results))
+}
+
+/// Returns the call of the function that the given operator names, if it is a variable identifier that is not linked to a value or named expression, but to a function.
+fn function_call(body: &Operator, context: &dyn Context) -> Option<Operator> {
+    match body {
+        Operator::VariableIdentifier { identifier }
+            if &**identifier != IT_IDENTIFIER
+                && context.get_value(identifier).is_none()
+                && context.get_expression(identifier).is_none()
+                && (context.get_function(identifier).is_some()
+                    || builtin_function(identifier).is_some()) =>
+        {
+            Some(Operator::FunctionIdentifier {
+                identifier: identifier.clone(),
+            })
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,236 @@
+use std::convert::TryFrom;
+
+use error::{EvalexprError, EvalexprResult};
+use value::{FloatType, IntType, Value};
+
+/// The units of sizes, where each unit is 1024 times the previous one.
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// The units of durations together with their length in seconds, from the largest to the smallest.
+const DURATION_UNITS: [(&str, IntType); 4] = [("d", 86_400), ("h", 3_600), ("m", 60), ("s", 1)];
+
+/// The reason why a size or duration could not be parsed.
+enum ParseError {
+    /// The text is not a size or duration.
+    Malformed,
+    /// The value is out of the range of `IntType`.
+    TooLarge,
+}
+
+/// Formats a number of bytes with the largest binary unit in which it is at least 1, like `1.5 GiB`.
+///
+/// Sizes below 1024 bytes are rounded to whole bytes, like `512 B`, and larger sizes are rounded to one decimal place in the units from `KiB` up to `EiB`.
+/// A size that rounds up to 1024 of a unit is written in the next unit, so 1023.96 bytes are `1.0 KiB`.
+/// Negative sizes are written with a leading minus sign.
+pub(crate) fn humanize_bytes(argument: &Value) -> EvalexprResult<Value> {
+    let bytes = expect_finite_number("humanize_bytes", argument)?;
+    let mut scaled = bytes.abs();
+    let mut unit = 0;
+    while unit + 1 < BYTE_UNITS.len() && round_to_decimals(scaled, unit.min(1)) >= 1024.0 {
+        scaled /= 1024.0;
+        unit += 1;
+    }
+
+    let text = if unit == 0 {
+        format!("{:.0} B", scaled.round())
+    } else {
+        format!("{:.1} {}", round_to_decimals(scaled, 1), BYTE_UNITS[unit])
+    };
+    Ok(Value::from(with_sign(bytes, text, "0 B")))
+}
+
+/// Formats a number of seconds with the two largest units of days, hours, minutes and seconds, like `3d 4h`.
+///
+/// Durations below a minute are rounded to one decimal place, like `1.5s`, where a zero decimal is left out, like in `45s`.
+/// Longer durations are rounded to whole seconds, and written in their largest unit together with the next smaller unit if it is not zero, so `3d 4h 59m` is shortened to `3d 4h`.
+/// Negative durations are written with a leading minus sign.
+pub(crate) fn humanize_duration(argument: &Value) -> EvalexprResult<Value> {
+    let seconds = expect_finite_number("humanize_duration", argument)?;
+    let magnitude = seconds.abs();
+    let text = if round_to_decimals(magnitude, 1) < 60.0 {
+        format!("{}s", round_to_decimals(magnitude, 1))
+    } else {
+        let total = magnitude.round();
+        let amounts: Vec<(&str, FloatType)> = DURATION_UNITS
+            .iter()
+            .enumerate()
+            .map(|(index, (unit, length))| {
+                // The remainder of the next larger unit, which is exact for floats with an integral value.
+                let rest = match index {
+                    0 => total,
+                    _ => total % DURATION_UNITS[index - 1].1 as FloatType,
+                };
+                (*unit, (rest / *length as FloatType).floor())
+            })
+            .collect();
+        let largest = amounts
+            .iter()
+            .position(|(_, amount)| *amount > 0.0)
+            .unwrap_or(amounts.len() - 1);
+        let mut text = format!("{}{}", amounts[largest].1, amounts[largest].0);
+        if let Some((unit, amount)) = amounts.get(largest + 1) {
+            if *amount > 0.0 {
+                text.push_str(&format!(" {}{}", amount, unit));
+            }
+        }
+        text
+    };
+    Ok(Value::from(with_sign(seconds, text, "0s")))
+}
+
+/// Parses a size like `1.5 GiB` or `1.5GiB` into a number of bytes, rounded to a whole byte.
+/// The units are the ones written by `humanize_bytes`, and a number without a unit is a number of bytes.
+pub(crate) fn parse_bytes(argument: &Value) -> EvalexprResult<Value> {
+    let text = argument.as_string()?;
+    parse_with_units(&text, |text| {
+        let (number, rest) = split_number(text).ok_or(ParseError::Malformed)?;
+        let unit = rest.trim_start();
+        let exponent = match unit {
+            "" => 0,
+            unit => BYTE_UNITS
+                .iter()
+                .position(|known| *known == unit)
+                .ok_or(ParseError::Malformed)?,
+        };
+        scale(number, 1 << (10 * exponent))
+    })
+    .map(Value::Int)
+    .map_err(|error| {
+        let expected = match error {
+            ParseError::Malformed => {
+                "a size like \"1.5 GiB\" with one of the units B, KiB, MiB, GiB, TiB, PiB or EiB"
+                    .to_string()
+            },
+            ParseError::TooLarge => format!("a size of at most {} bytes", IntType::MAX),
+        };
+        EvalexprError::argument_out_of_range("parse_bytes", argument.clone(), expected)
+    })
+}
+
+/// Parses a duration like `3d 4h` or `3d4h` into a number of seconds, rounded to a whole second.
+/// The duration consists of amounts with the units `d`, `h`, `m` and `s`, from the largest to the smallest unit, each at most once.
+/// A number without a unit is a number of seconds.
+pub(crate) fn parse_duration(argument: &Value) -> EvalexprResult<Value> {
+    let text = argument.as_string()?;
+    parse_with_units(&text, |text| {
+        if let Some((number, "")) = split_number(text) {
+            return scale(number, 1);
+        }
+
+        let mut rest = text;
+        let mut units = DURATION_UNITS.iter();
+        let mut total: i128 = 0;
+        while !rest.is_empty() {
+            let (number, after_number) = split_number(rest).ok_or(ParseError::Malformed)?;
+            let after_number = after_number.trim_start();
+            // Units must be given from the largest to the smallest, which also forbids repeating a unit.
+            let (unit, length) = units
+                .by_ref()
+                .find(|(unit, _)| after_number.starts_with(unit))
+                .ok_or(ParseError::Malformed)?;
+            total = total
+                .checked_add(scale(number, *length)?)
+                .ok_or(ParseError::TooLarge)?;
+            rest = after_number[unit.len()..].trim_start();
+        }
+        Ok(total)
+    })
+    .map(Value::Int)
+    .map_err(|error| {
+        let expected = match error {
+            ParseError::Malformed => {
+                "a duration like \"3d 4h\" with the units d, h, m and s in this order".to_string()
+            },
+            ParseError::TooLarge => format!("a duration of at most {} seconds", IntType::MAX),
+        };
+        EvalexprError::argument_out_of_range("parse_duration", argument.clone(), expected)
+    })
+}
+
+/// Expects a number that is neither NaN nor infinite.
+fn expect_finite_number(function: &str, argument: &Value) -> EvalexprResult<FloatType> {
+    let number = argument.as_number()?;
+    if number.is_finite() {
+        Ok(number)
+    } else {
+        Err(EvalexprError::argument_out_of_range(
+            function,
+            argument.clone(),
+            "a finite number".to_string(),
+        ))
+    }
+}
+
+/// Rounds the number half away from zero to the given amount of decimal places.
+fn round_to_decimals(number: FloatType, decimals: usize) -> FloatType {
+    let factor = FloatType::powi(10.0, decimals as i32);
+    (number * factor).round() / factor
+}
+
+/// Prefixes the formatted magnitude of the number with a minus sign if the number is negative, unless the magnitude was formatted as zero.
+fn with_sign(number: FloatType, magnitude: String, zero: &str) -> String {
+    if number < 0.0 && magnitude != zero {
+        format!("-{}", magnitude)
+    } else {
+        magnitude
+    }
+}
+
+/// Parses the text with an optional leading minus sign, where `parse_magnitude` parses the rest of the trimmed text.
+fn parse_with_units<F>(text: &str, parse_magnitude: F) -> Result<IntType, ParseError>
+where
+    F: FnOnce(&str) -> Result<i128, ParseError>,
+{
+    let text = text.trim();
+    let (negative, magnitude) = match text.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude.trim_start()),
+        None => (false, text),
+    };
+    if magnitude.is_empty() {
+        return Err(ParseError::Malformed);
+    }
+    let magnitude = parse_magnitude(magnitude)?;
+    let value = if negative { -magnitude } else { magnitude };
+    IntType::try_from(value).map_err(|_| ParseError::TooLarge)
+}
+
+/// Splits a leading unsigned decimal number like `12`, `1.5` or `.5` off the text.
+fn split_number(text: &str) -> Option<(&str, &str)> {
+    let end = text
+        .find(|character: char| !character.is_ascii_digit() && character != '.')
+        .unwrap_or(text.len());
+    let number = &text[..end];
+    if number.matches('.').count() <= 1
+        && number.chars().any(|character| character.is_ascii_digit())
+    {
+        Some((number, &text[end..]))
+    } else {
+        None
+    }
+}
+
+/// Multiplies the decimal number by the factor, and rounds the result half away from zero to an integer.
+/// Integers are multiplied exactly, and numbers with decimal places as floats.
+/// The number must consist of digits and at most one decimal point, as returned by `split_number`.
+fn scale(number: &str, factor: IntType) -> Result<i128, ParseError> {
+    if number.contains('.') {
+        let scaled = (number
+            .parse::<FloatType>()
+            .map_err(|_| ParseError::Malformed)?
+            * factor as FloatType)
+            .round();
+        // Floats from 2^127 on do not fit into an i128, and are far out of the range of `IntType` anyway.
+        if scaled < FloatType::powi(2.0, 127) {
+            Ok(scaled as i128)
+        } else {
+            Err(ParseError::TooLarge)
+        }
+    } else {
+        // The digits only fail to parse if they are too many for an i128.
+        number
+            .parse::<i128>()
+            .ok()
+            .and_then(|number| number.checked_mul(i128::from(factor)))
+            .ok_or(ParseError::TooLarge)
+    }
+}
@@ -1,9 +1,33 @@
 use std::fmt;
+use std::rc::Rc;
 
-use error::EvalexprResult;
-use value::Value;
+use error::{EvalexprError, EvalexprResult};
+use value::{TupleType, Value};
+
+use self::parameters::{
+    apply_defaults, expect_valid_parameters, has_defaults, order_named_arguments,
+};
 
 pub(crate) mod builtin;
+#[cfg(feature = "builtin_crypto")]
+mod digest;
+#[cfg(feature = "excel_compat")]
+mod excel;
+#[cfg(any(feature = "builtin_string", feature = "excel_compat"))]
+pub(crate) mod glob;
+pub(crate) mod higher_order;
+#[cfg(feature = "builtin_string")]
+mod humanize;
+mod parameters;
+#[cfg(feature = "builtin_regex")]
+pub(crate) mod regex_match;
+mod typed;
+
+pub use self::parameters::Parameter;
+pub use self::typed::{FromArgument, IntoFunction};
+
+type FunctionType = dyn Fn(&Value) -> EvalexprResult<Value>;
+type BoxedFunction = Box<FunctionType>;
 
 /// A user-defined function.
 /// Functions can be used in expressions by storing them in a `Context`.
@@ -19,20 +43,259 @@ pub(crate) mod builtin;
 /// }))).unwrap(); // Do proper error handling here
 /// assert_eq!(eval_with_context("id(4)", &context), Ok(Value::from(4)));
 /// ```
+///
+/// Cloning a function is cheap, as the clone shares the boxed function with the original.
+#[derive(Clone)]
 pub struct Function {
-    function: Box<Fn(&Value) -> EvalexprResult<Value>>,
+    function: Rc<FunctionType>,
+    pure: bool,
+    parameters: Option<Rc<[Parameter]>>,
 }
 
 impl Function {
     /// Creates a user-defined function.
     ///
     /// The `function` is a boxed function that takes a `Value` and returns a `EvalexprResult<Value, Error>`.
-    pub fn new(function: Box<Fn(&Value) -> EvalexprResult<Value>>) -> Self {
-        Self { function }
+    pub fn new(function: BoxedFunction) -> Self {
+        Self {
+            function: function.into(),
+            pure: true,
+            parameters: None,
+        }
+    }
+
+    /// Creates a user-defined function from a closure with typed parameters.
+    ///
+    /// The closure can take up to three parameters of the types that implement `FromArgument`, like `IntType`, `FloatType`, `String` or `bool`, and returns an `EvalexprResult` of anything that converts into a `Value`.
+    /// The arguments are checked and converted before the closure is called.
+    /// If the function is called with the wrong amount of arguments, it fails with `EvalexprError::WrongFunctionArgumentAmount`, and if an argument has the wrong type, with the error of the conversion, like `EvalexprError::ExpectedInt`.
+    /// A closure with a single parameter receives the whole argument, so a `TupleType` parameter receives all arguments as a tuple.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("clamp".into(), Function::from_fn(|x: IntType, min: IntType, max: IntType| {
+    ///     Ok(x.max(min).min(max))
+    /// })).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(eval_with_context("clamp(12, 0, 10)", &context), Ok(Value::from(10)));
+    /// assert_eq!(
+    ///     eval_with_context("clamp(12, 0, 1.5)", &context),
+    ///     Err(EvalexprError::InFunctionCall {
+    ///         function: "clamp".into(),
+    ///         argument: Value::from(vec![Value::from(12), Value::from(0), Value::from(1.5)]),
+    ///         source: Box::new(EvalexprError::expected_int(Value::from(1.5))),
+    ///     })
+    /// );
+    /// ```
+    pub fn from_fn<Parameters, F: IntoFunction<Parameters>>(function: F) -> Self {
+        function.into_function()
+    }
+
+    /// Creates a function that calls this function with the given arguments prepended to its own arguments.
+    ///
+    /// If the new function is called with a tuple, the elements of the tuple are appended to the bound arguments.
+    /// Otherwise, the argument is appended as a single element, unless it is `Value::Empty`.
+    /// If this results in exactly one argument, it is passed to this function as is, and otherwise as a tuple.
+    ///
+    /// If this function fails with `EvalexprError::WrongFunctionArgumentAmount` or `EvalexprError::WrongFunctionArgumentRange`, the error is adjusted to not count the bound arguments.
+    /// Like all errors of functions in the context, it is wrapped in an `EvalexprError::InFunctionCall` when the function is called from an expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::{error::expect_function_argument_amount, *};
+    ///
+    /// let sum = Function::new(Box::new(|argument| {
+    ///     let arguments = argument.as_tuple()?;
+    ///     expect_function_argument_amount(arguments.len(), 3)?;
+    ///     let mut sum = 0;
+    ///     for argument in arguments {
+    ///         sum += argument.as_int()?;
+    ///     }
+    ///     Ok(Value::Int(sum))
+    /// }));
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("sum".into(), sum.clone()).unwrap(); // Do proper error handling here
+    /// context.set_function("add_to_ten".into(), sum.bind(vec![Value::Int(10)])).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(eval_with_context("sum(1, 2, 3)", &context), Ok(Value::from(6)));
+    /// assert_eq!(eval_with_context("add_to_ten(2, 3)", &context), Ok(Value::from(15)));
+    /// assert_eq!(
+    ///     eval_with_context("add_to_ten(2, 3, 4)", &context),
+    ///     Err(EvalexprError::InFunctionCall {
+    ///         function: "add_to_ten".into(),
+    ///         argument: Value::from(vec![Value::from(2), Value::from(3), Value::from(4)]),
+    ///         source: Box::new(EvalexprError::WrongFunctionArgumentAmount { expected: 2, actual: 3 }),
+    ///     })
+    /// );
+    /// ```
+    pub fn bind(self, prefix_arguments: Vec<Value>) -> Function {
+        let bound_amount = prefix_arguments.len();
+        let pure = self.pure;
+        let mut function = Function::new(Box::new(move |argument| {
+            let mut arguments: TupleType = prefix_arguments.clone();
+            match argument {
+                Value::Tuple(tuple) => arguments.extend_from_slice(tuple),
+                Value::Empty => {},
+                argument => arguments.push(argument.clone()),
+            }
+
+            let argument = if arguments.len() == 1 {
+                arguments.pop().unwrap()
+            } else if arguments.is_empty() {
+                Value::Empty
+            } else {
+                Value::Tuple(arguments)
+            };
+
+            self.call(&argument).map_err(|error| match error {
+                EvalexprError::WrongFunctionArgumentAmount { expected, actual }
+                    if expected >= bound_amount && actual >= bound_amount =>
+                {
+                    EvalexprError::wrong_function_argument_amount(
+                        actual - bound_amount,
+                        expected - bound_amount,
+                    )
+                },
+                EvalexprError::WrongFunctionArgumentRange {
+                    minimum,
+                    maximum,
+                    actual,
+                    optional,
+                } if minimum >= bound_amount && actual >= bound_amount => {
+                    EvalexprError::WrongFunctionArgumentRange {
+                        minimum: minimum - bound_amount,
+                        maximum: maximum - bound_amount,
+                        actual: actual - bound_amount,
+                        optional,
+                    }
+                },
+                error => error,
+            })
+        }));
+        function.pure = pure;
+        function
+    }
+
+    /// Marks this function as impure, meaning that it may return different results for the same argument, or have side effects.
+    ///
+    /// Functions are pure by default.
+    /// The calls of an impure function are never shared by `Node::share_common_subexpressions`, so each call in the expression is evaluated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(Cell::new(0));
+    /// let next_counter = counter.clone();
+    /// let next = Function::new(Box::new(move |_| {
+    ///     next_counter.set(next_counter.get() + 1);
+    ///     Ok(Value::Int(next_counter.get()))
+    /// }))
+    /// .impure();
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("next".into(), next).unwrap(); // Do proper error handling here
+    ///
+    /// let mut tree = build_operator_tree("next() + next()").unwrap(); // Do proper error handling here
+    /// tree.share_common_subexpressions();
+    /// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(3)));
+    /// assert_eq!(counter.get(), 2);
+    /// ```
+    pub fn impure(mut self) -> Self {
+        self.pure = false;
+        self
+    }
+
+    /// Returns true if this function is pure, meaning that it was not marked with `Function::impure`.
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    /// Names the parameters of this function, such that it can be called with named arguments, like `round_to(x, places = 2)`.
+    ///
+    /// A call with named arguments gives its positional arguments first, followed by the named ones, which may be given in any order.
+    /// The arguments are ordered like the parameters before the function is called, and parameters that the call does not give take their default value.
+    /// So the function receives its arguments like from a call with positional arguments only, as a tuple if it has more than one parameter.
+    ///
+    /// Calls with positional arguments only may omit the trailing parameters that have default values, like `round_to(3.14159)`, and the defaults are appended before the function is called.
+    /// So the function always receives an argument for each parameter.
+    /// If such a call gives fewer arguments than there are required parameters, or more than there are parameters, it fails with `EvalexprError::WrongFunctionArgumentRange`, which names the optional parameters.
+    /// As the argument of a call is a tuple if the call has several arguments, a tuple given as the only argument of a function with several parameters is taken as the arguments themselves.
+    ///
+    /// Fails with `EvalexprError::DuplicateParameter` if two parameters have the same name, and with `EvalexprError::NonTrailingDefault` if a parameter with a default value is followed by one without.
+    /// Calling a function without named parameters, like a builtin function, with a named argument fails with `EvalexprError::UnknownNamedArgument`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let round_to = Function::from_fn(|x: FloatType, places: IntType| {
+    ///     let factor = 10f64.powi(places as i32);
+    ///     Ok((x * factor).round() / factor)
+    /// })
+    /// .with_parameters(vec![
+    ///     Parameter::required("x"),
+    ///     Parameter::optional("places", Value::from(2)),
+    /// ])
+    /// .unwrap(); // Do proper error handling here
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("round_to".into(), round_to).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(eval_with_context("round_to(3.14159, places = 3)", &context), Ok(Value::from(3.142)));
+    /// assert_eq!(eval_with_context("round_to(places = 1, x = 2.75)", &context), Ok(Value::from(2.8)));
+    /// assert_eq!(eval_with_context("round_to(x = 3.14159)", &context), Ok(Value::from(3.14)));
+    /// assert_eq!(eval_with_context("round_to(3.14159)", &context), Ok(Value::from(3.14)));
+    /// assert_eq!(
+    ///     eval_with_context("round_to(1.5, digits = 1)", &context),
+    ///     Err(EvalexprError::UnknownNamedArgument {
+    ///         function: "round_to".into(),
+    ///         argument: "digits".into(),
+    ///     })
+    /// );
+    /// ```
+    pub fn with_parameters(mut self, parameters: Vec<Parameter>) -> EvalexprResult<Self> {
+        expect_valid_parameters(&parameters)?;
+        self.parameters = Some(parameters.into());
+        Ok(self)
+    }
+
+    /// Returns the parameters of this function as named by `Function::with_parameters`, or `None` if they are not named.
+    pub fn parameters(&self) -> Option<&[Parameter]> {
+        self.parameters.as_deref()
+    }
+
+    /// Orders the arguments of a call of this function with named arguments like its parameters, see `Function::with_parameters`.
+    pub(crate) fn order_named_arguments(
+        &self,
+        identifier: &str,
+        arguments: &[Value],
+        names: &[String],
+    ) -> EvalexprResult<Value> {
+        match &self.parameters {
+            Some(parameters) => order_named_arguments(identifier, parameters, arguments, names),
+            None => Err(EvalexprError::UnknownNamedArgument {
+                function: identifier.to_string(),
+                argument: names[0].clone(),
+            }),
+        }
     }
 
     pub(crate) fn call(&self, argument: &Value) -> EvalexprResult<Value> {
-        (self.function)(argument)
+        match &self.parameters {
+            Some(parameters) if has_defaults(parameters) => {
+                (self.function)(&apply_defaults(parameters, argument)?)
+            },
+            _ => (self.function)(argument),
+        }
     }
 }
 
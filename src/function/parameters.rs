@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::slice;
+
+use error::{EvalexprError, EvalexprResult};
+use value::{TupleType, Value};
+
+/// A named parameter of a function, see `Function::with_parameters`.
+///
+/// A parameter with a default value is optional, so calls with named arguments may omit it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    name: String,
+    default: Option<Value>,
+}
+
+impl Parameter {
+    /// Creates a parameter that must be given in each call.
+    pub fn required<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            default: None,
+        }
+    }
+
+    /// Creates a parameter that takes the given default value if a call does not give it.
+    pub fn optional<S: Into<String>>(name: S, default: Value) -> Self {
+        Self {
+            name: name.into(),
+            default: Some(default),
+        }
+    }
+
+    /// Returns the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the default value of the parameter, or `None` if the parameter is required.
+    pub fn default(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+}
+
+/// Returns an error if two of the given parameters have the same name, or if a parameter with a default value is followed by one without.
+pub(crate) fn expect_valid_parameters(parameters: &[Parameter]) -> EvalexprResult<()> {
+    let mut names = HashSet::new();
+    for parameter in parameters {
+        if !names.insert(parameter.name()) {
+            return Err(EvalexprError::DuplicateParameter(parameter.name.clone()));
+        }
+    }
+    if let Some(optional) = parameters
+        .windows(2)
+        .find(|pair| pair[0].default.is_some() && pair[1].default.is_none())
+    {
+        return Err(EvalexprError::NonTrailingDefault(optional[0].name.clone()));
+    }
+    Ok(())
+}
+
+/// Returns the amount of parameters without a default value, which all come before the ones with a default value.
+fn required_amount(parameters: &[Parameter]) -> usize {
+    parameters
+        .iter()
+        .take_while(|parameter| parameter.default.is_none())
+        .count()
+}
+
+/// Returns the error for a call with `actual` positional arguments that does not match the amount of the given parameters.
+/// If some parameters have default values, the error names them.
+fn wrong_argument_amount(parameters: &[Parameter], actual: usize) -> EvalexprError {
+    let required = required_amount(parameters);
+    if required == parameters.len() {
+        EvalexprError::wrong_function_argument_amount(actual, required)
+    } else {
+        EvalexprError::WrongFunctionArgumentRange {
+            minimum: required,
+            maximum: parameters.len(),
+            actual,
+            optional: parameters[required..]
+                .iter()
+                .map(|parameter| parameter.name.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Returns true if some of the given parameters have default values.
+pub(crate) fn has_defaults(parameters: &[Parameter]) -> bool {
+    parameters
+        .last()
+        .is_some_and(|parameter| parameter.default.is_some())
+}
+
+/// Appends the default values of the trailing parameters that a call with positional arguments omits to its argument.
+///
+/// The argument is split into positional arguments like the function receives them, so it is the single argument itself if the function has one parameter, and a tuple otherwise.
+pub(crate) fn apply_defaults(parameters: &[Parameter], argument: &Value) -> EvalexprResult<Value> {
+    let arguments = match (parameters.len(), argument) {
+        (_, Value::Empty) => &[],
+        (1, argument) => slice::from_ref(argument),
+        (_, Value::Tuple(tuple)) => tuple.as_slice(),
+        (_, argument) => slice::from_ref(argument),
+    };
+    if arguments.len() < required_amount(parameters) || arguments.len() > parameters.len() {
+        return Err(wrong_argument_amount(parameters, arguments.len()));
+    }
+    if arguments.len() == parameters.len() {
+        return Ok(argument.clone());
+    }
+
+    let mut arguments = arguments.to_vec();
+    arguments.extend(
+        parameters[arguments.len()..]
+            .iter()
+            .filter_map(|parameter| parameter.default.clone()),
+    );
+    Ok(argument_of(arguments))
+}
+
+/// Returns the argument of a call with the given positional arguments.
+fn argument_of(mut arguments: TupleType) -> Value {
+    match arguments.len() {
+        0 => Value::Empty,
+        1 => arguments.pop().unwrap(),
+        _ => Value::Tuple(arguments),
+    }
+}
+
+/// Orders the arguments of a call with named arguments like the given parameters of the called function.
+///
+/// The `arguments` are the positional arguments followed by the values of the named arguments, whose names are given by `names` in the same order.
+/// Parameters that are given by neither take their default value.
+/// The result is the argument of a call with positional arguments only, so it is the single argument itself if the function has one parameter, and a tuple otherwise.
+pub(crate) fn order_named_arguments(
+    function: &str,
+    parameters: &[Parameter],
+    arguments: &[Value],
+    names: &[String],
+) -> EvalexprResult<Value> {
+    let (positional, named) = arguments.split_at(arguments.len() - names.len());
+    let mut ordered: Vec<Option<Value>> = vec![None; parameters.len()];
+    for (name, value) in names.iter().zip(named) {
+        let index = parameters
+            .iter()
+            .position(|parameter| &parameter.name == name)
+            .ok_or_else(|| EvalexprError::UnknownNamedArgument {
+                function: function.to_string(),
+                argument: name.clone(),
+            })?;
+        if index < positional.len() || ordered[index].is_some() {
+            return Err(EvalexprError::DuplicateArgument {
+                function: function.to_string(),
+                parameter: name.clone(),
+            });
+        }
+        ordered[index] = Some(value.clone());
+    }
+
+    if positional.len() > parameters.len() {
+        return Err(wrong_argument_amount(parameters, positional.len()));
+    }
+    for (slot, value) in ordered.iter_mut().zip(positional) {
+        *slot = Some(value.clone());
+    }
+
+    let ordered = ordered
+        .into_iter()
+        .zip(parameters)
+        .map(|(value, parameter)| {
+            value.or_else(|| parameter.default.clone()).ok_or_else(|| {
+                EvalexprError::MissingArgument {
+                    function: function.to_string(),
+                    parameter: parameter.name.clone(),
+                }
+            })
+        })
+        .collect::<EvalexprResult<TupleType>>()?;
+    Ok(argument_of(ordered))
+}
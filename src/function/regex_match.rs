@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use error::*;
+use Value;
+
+/// The maximum amount of compiled regexes that are kept per thread.
+const CACHE_CAPACITY: usize = 64;
+
+thread_local! {
+    /// The regexes compiled on this thread, by their pattern.
+    static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the compiled regex of the pattern.
+///
+/// Compiled regexes are cached per thread, such that evaluating an operator tree with a constant pattern again does not compile the pattern again.
+/// The cache is cleared when it is full, so patterns that are built at runtime cannot grow it without bound.
+pub(crate) fn compile_regex(pattern: &str) -> EvalexprResult<Regex> {
+    REGEX_CACHE.with(|cache| {
+        if let Some(regex) = cache.borrow().get(pattern) {
+            return Ok(regex.clone());
+        }
+
+        let regex = Regex::new(pattern).map_err(|error| {
+            EvalexprError::invalid_regex(pattern.to_string(), format!("{}", error))
+        })?;
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    })
+}
+
+/// Returns true if the regex `pattern` matches anywhere in `subject`.
+/// This implements both the `=~` operator and the builtin function `str::regex_matches`.
+pub(crate) fn regex_matches(subject: &Value, pattern: &Value) -> EvalexprResult<Value> {
+    let subject = expect_string(subject)?;
+    let regex = compile_regex(expect_string(pattern)?)?;
+    Ok(Value::Boolean(regex.is_match(subject)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compiled_regexes_are_cached() {
+        let pattern = "^cached[0-9]+$";
+        let first = compile_regex(pattern).unwrap();
+        let second = compile_regex(pattern).unwrap();
+        assert_eq!(first.as_str(), second.as_str());
+        REGEX_CACHE.with(|cache| assert!(cache.borrow().contains_key(pattern)));
+
+        for index in 0..CACHE_CAPACITY {
+            compile_regex(&format!("pattern{}", index)).unwrap();
+        }
+        REGEX_CACHE.with(|cache| assert!(cache.borrow().len() <= CACHE_CAPACITY));
+    }
+}
@@ -0,0 +1,144 @@
+use std::slice;
+
+use error::{expect_function_argument_amount, EvalexprResult};
+use function::Function;
+use value::{FloatType, IntType, TupleType, Value};
+
+/// A type that a parameter of a function created by `Function::from_fn` can have.
+///
+/// The conversion fails with the usual errors of the `Value::as_[type]` methods, like `EvalexprError::ExpectedInt`.
+/// A `FloatType` parameter also accepts integers, which are converted like by `Value::as_number`.
+/// A `Value` parameter accepts any value.
+pub trait FromArgument: Sized {
+    /// Converts an argument of a function call into this type.
+    fn from_argument(argument: &Value) -> EvalexprResult<Self>;
+}
+
+impl FromArgument for Value {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        Ok(argument.clone())
+    }
+}
+
+impl FromArgument for String {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        argument.as_string()
+    }
+}
+
+impl FromArgument for IntType {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        argument.as_int()
+    }
+}
+
+impl FromArgument for FloatType {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        argument.as_number()
+    }
+}
+
+impl FromArgument for bool {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        argument.as_boolean()
+    }
+}
+
+impl FromArgument for TupleType {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        argument.as_tuple()
+    }
+}
+
+impl FromArgument for () {
+    fn from_argument(argument: &Value) -> EvalexprResult<Self> {
+        argument.as_empty()
+    }
+}
+
+/// A closure with typed parameters that can be converted into a `Function`.
+///
+/// This is implemented for closures with up to three parameters whose types implement `FromArgument`, and that return an `EvalexprResult` of a type that converts into a `Value`.
+/// The type parameter `Parameters` is the tuple of the parameter types, and only serves to tell the implementations for different amounts of parameters apart.
+pub trait IntoFunction<Parameters> {
+    /// Wraps this closure into a `Function` that converts the arguments before calling it.
+    fn into_function(self) -> Function;
+}
+
+impl<F, R> IntoFunction<()> for F
+where
+    F: Fn() -> EvalexprResult<R> + 'static,
+    R: Into<Value>,
+{
+    fn into_function(self) -> Function {
+        Function::new(Box::new(move |argument| {
+            expect_function_argument_amount(arguments(argument).len(), 0)?;
+            self().map(Into::into)
+        }))
+    }
+}
+
+/// A function with a single parameter receives the argument as is, even if it is a tuple.
+impl<F, A, R> IntoFunction<(A,)> for F
+where
+    F: Fn(A) -> EvalexprResult<R> + 'static,
+    A: FromArgument,
+    R: Into<Value>,
+{
+    fn into_function(self) -> Function {
+        Function::new(Box::new(move |argument| {
+            self(A::from_argument(argument)?).map(Into::into)
+        }))
+    }
+}
+
+impl<F, A, B, R> IntoFunction<(A, B)> for F
+where
+    F: Fn(A, B) -> EvalexprResult<R> + 'static,
+    A: FromArgument,
+    B: FromArgument,
+    R: Into<Value>,
+{
+    fn into_function(self) -> Function {
+        Function::new(Box::new(move |argument| {
+            let arguments = arguments(argument);
+            expect_function_argument_amount(arguments.len(), 2)?;
+            self(
+                A::from_argument(&arguments[0])?,
+                B::from_argument(&arguments[1])?,
+            )
+            .map(Into::into)
+        }))
+    }
+}
+
+impl<F, A, B, C, R> IntoFunction<(A, B, C)> for F
+where
+    F: Fn(A, B, C) -> EvalexprResult<R> + 'static,
+    A: FromArgument,
+    B: FromArgument,
+    C: FromArgument,
+    R: Into<Value>,
+{
+    fn into_function(self) -> Function {
+        Function::new(Box::new(move |argument| {
+            let arguments = arguments(argument);
+            expect_function_argument_amount(arguments.len(), 3)?;
+            self(
+                A::from_argument(&arguments[0])?,
+                B::from_argument(&arguments[1])?,
+                C::from_argument(&arguments[2])?,
+            )
+            .map(Into::into)
+        }))
+    }
+}
+
+/// Returns the arguments of a function call, which are the elements of a tuple, nothing for an empty value, or else the argument itself.
+fn arguments(argument: &Value) -> &[Value] {
+    match argument {
+        Value::Tuple(tuple) => tuple,
+        Value::Empty => &[],
+        argument => slice::from_ref(argument),
+    }
+}
@@ -1,9 +1,17 @@
+use std::collections::HashMap;
+
+use config::ParseConfig;
+#[cfg(feature = "async")]
+use feature_async::{AsyncContext, AsyncEvaluation};
+use feature_tracing;
 use token;
 use tree;
+use tree::{CompactTree, ParsedTree};
 use value::TupleType;
 use Context;
 use EmptyContext;
 use EmptyType;
+use EvalState;
 use EvalexprError;
 use EvalexprResult;
 use FloatType;
@@ -35,15 +43,21 @@ pub fn eval(string: &str) -> EvalexprResult<Value> {
 /// use evalexpr::*;
 ///
 /// let mut context = HashMapContext::new();
-/// context.set_value("one".into(), 1.into()).unwrap(); // Do proper error handling here
-/// context.set_value("two".into(), 2.into()).unwrap(); // Do proper error handling here
-/// context.set_value("three".into(), 3.into()).unwrap(); // Do proper error handling here
+/// context.set_value("one", 1).unwrap(); // Do proper error handling here
+/// context.set_value("two", 2).unwrap(); // Do proper error handling here
+/// context.set_value("three", 3).unwrap(); // Do proper error handling here
 /// assert_eq!(eval_with_context("one + two + three", &context), Ok(Value::from(6)));
 /// ```
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_with_context(string: &str, context: &Context) -> EvalexprResult<Value> {
-    tree::tokens_to_operator_tree(token::tokenize(string)?)?.eval_with_context(context)
+pub fn eval_with_context(string: &str, context: &dyn Context) -> EvalexprResult<Value> {
+    feature_tracing::instrument_evaluation(
+        || feature_tracing::expression_hash(string),
+        || {
+            let tree = tree::tokens_to_operator_tree(token::tokenize(string)?)?;
+            EvalState::with_default(|state| tree.eval_with_context_and_state(context, state))
+        },
+    )
 }
 
 /// Evaluate the given expression string with the given mutable context.
@@ -54,15 +68,76 @@ pub fn eval_with_context(string: &str, context: &Context) -> EvalexprResult<Valu
 /// use evalexpr::*;
 ///
 /// let mut context = HashMapContext::new();
-/// context.set_value("one".into(), 1.into()).unwrap(); // Do proper error handling here
-/// context.set_value("two".into(), 2.into()).unwrap(); // Do proper error handling here
-/// context.set_value("three".into(), 3.into()).unwrap(); // Do proper error handling here
+/// context.set_value("one", 1).unwrap(); // Do proper error handling here
+/// context.set_value("two", 2).unwrap(); // Do proper error handling here
+/// context.set_value("three", 3).unwrap(); // Do proper error handling here
 /// assert_eq!(eval_with_context_mut("one + two + three", &mut context), Ok(Value::from(6)));
 /// ```
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_with_context_mut(string: &str, context: &mut Context) -> EvalexprResult<Value> {
-    tree::tokens_to_operator_tree(token::tokenize(string)?)?.eval_with_context_mut(context)
+pub fn eval_with_context_mut(string: &str, context: &mut dyn Context) -> EvalexprResult<Value> {
+    feature_tracing::instrument_evaluation(
+        || feature_tracing::expression_hash(string),
+        || {
+            let tree = tree::tokens_to_operator_tree(token::tokenize(string)?)?;
+            EvalState::with_default(|state| tree.eval_with_context_mut_and_state(context, state))
+        },
+    )
+}
+
+/// Evaluate the given expression string with each of the given contexts in order, and return the index of the first context it succeeds with together with the value.
+///
+/// Only the errors of variables or functions that are not defined fall through to the next context.
+/// See `Node::eval_first_success` for details, and `Node::eval_first_success_with_fallthrough` to fall through on any error.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let user = context_map! { "theme" => "dark" }.unwrap(); // Do proper error handling here
+/// let group = context_map! { "font_size" => 14 }.unwrap(); // Do proper error handling here
+/// let global = context_map! { "font_size" => 12, "theme" => "light" }.unwrap(); // Do proper error handling here
+/// let contexts = vec![&user as &dyn Context, &group, &global];
+/// assert_eq!(eval_first_success("font_size * 2", contexts.clone()), Ok((1, Value::from(28))));
+/// assert!(matches!(
+///     eval_first_success("line_height", contexts),
+///     Err(EvalexprError::NoContextSucceeded { .. })
+/// ));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn eval_first_success<'a, I>(string: &str, contexts: I) -> EvalexprResult<(usize, Value)>
+where
+    I: IntoIterator<Item = &'a dyn Context>,
+{
+    tree::tokens_to_operator_tree(token::tokenize(string)?)?.eval_first_success(contexts)
+}
+
+/// Evaluate the given script with the given mutable context, and return the variables that the script exports.
+///
+/// Variables are exported by calling `export` with their identifiers, like `export(a, b)`.
+/// All other variables assigned by the script stay in the context, but are not returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let mut context = HashMapContext::new();
+/// let exports = eval_script("a = 1; b = a + 1; c = b * 3; export(a, c)", &mut context).unwrap(); // Do proper error handling here
+/// assert_eq!(exports.len(), 2);
+/// assert_eq!(exports["a"], Value::from(1));
+/// assert_eq!(exports["c"], Value::from(6));
+/// assert_eq!(context.get_value("b"), Some(&Value::from(2)));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn eval_script(
+    string: &str,
+    context: &mut dyn Context,
+) -> EvalexprResult<HashMap<String, Value>> {
+    tree::tokens_to_operator_tree(token::tokenize(string)?)?.eval_script_with_context_mut(context)
 }
 
 /// Build the operator tree for the given expression string.
@@ -78,13 +153,13 @@ pub fn eval_with_context_mut(string: &str, context: &mut Context) -> EvalexprRes
 /// let precomputed = build_operator_tree("one + two + three").unwrap(); // Do proper error handling here
 ///
 /// let mut context = HashMapContext::new();
-/// context.set_value("one".into(), 1.into()).unwrap(); // Do proper error handling here
-/// context.set_value("two".into(), 2.into()).unwrap(); // Do proper error handling here
-/// context.set_value("three".into(), 3.into()).unwrap(); // Do proper error handling here
+/// context.set_value("one", 1).unwrap(); // Do proper error handling here
+/// context.set_value("two", 2).unwrap(); // Do proper error handling here
+/// context.set_value("three", 3).unwrap(); // Do proper error handling here
 ///
 /// assert_eq!(precomputed.eval_with_context(&context), Ok(Value::from(6)));
 ///
-/// context.set_value("three".into(), 5.into()).unwrap(); // Do proper error handling here
+/// context.set_value("three", 5).unwrap(); // Do proper error handling here
 /// assert_eq!(precomputed.eval_with_context(&context), Ok(Value::from(8)));
 /// ```
 ///
@@ -93,6 +168,72 @@ pub fn build_operator_tree(string: &str) -> EvalexprResult<Node> {
     tree::tokens_to_operator_tree(token::tokenize(string)?)
 }
 
+/// Build the operator tree for the given expression string, and store it as a `CompactTree`.
+///
+/// This is the same as `build_operator_tree(string)?.compact()`.
+/// Compact trees take longer to build, but are faster to evaluate in large batches.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let precomputed = build_compact_tree("one + two").unwrap(); // Do proper error handling here
+///
+/// let mut context = HashMapContext::new();
+/// context.set_value("one", 1).unwrap(); // Do proper error handling here
+/// context.set_value("two", 2).unwrap(); // Do proper error handling here
+/// assert_eq!(precomputed.eval_with_context(&context), Ok(Value::from(3)));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn build_compact_tree(string: &str) -> EvalexprResult<CompactTree> {
+    build_operator_tree(string).map(|node| node.compact())
+}
+
+/// Build the operator tree for the given expression string according to the given configuration.
+///
+/// Unlike `build_operator_tree`, this returns the warnings that were recorded while parsing the expression.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let strict = ParseConfig::default();
+/// assert_eq!(
+///     build_operator_tree_with_config("99999999999999999999", &strict),
+///     Err(EvalexprError::IntegerLiteralOutOfRange("99999999999999999999".to_string()))
+/// );
+///
+/// let lenient = ParseConfig {
+///     integer_overflow_policy: IntegerOverflowPolicy::ConvertToFloat,
+///     ..ParseConfig::default()
+/// };
+/// let parsed = build_operator_tree_with_config("99999999999999999999", &lenient).unwrap(); // Do proper error handling here
+/// assert_eq!(parsed.tree.eval(), Ok(Value::from(1e20)));
+/// assert_eq!(
+///     parsed.warnings,
+///     vec![ParseWarning::IntegerLiteralConvertedToFloat {
+///         literal: "99999999999999999999".to_string(),
+///         value: 1e20,
+///     }]
+/// );
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn build_operator_tree_with_config(
+    string: &str,
+    config: &ParseConfig,
+) -> EvalexprResult<ParsedTree> {
+    let mut warnings = Vec::new();
+    let tree = tree::tokens_to_operator_tree_with_config(
+        token::tokenize_with_config(string, config, &mut warnings)?,
+        config,
+    )?;
+    Ok(ParsedTree { tree, warnings })
+}
+
 /// Evaluate the given expression string into a string.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
@@ -146,7 +287,7 @@ pub fn eval_empty(string: &str) -> EvalexprResult<EmptyType> {
 /// Evaluate the given expression string into a string with the given context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_string_with_context(string: &str, context: &Context) -> EvalexprResult<String> {
+pub fn eval_string_with_context(string: &str, context: &dyn Context) -> EvalexprResult<String> {
     match eval_with_context(string, context) {
         Ok(Value::String(string)) => Ok(string),
         Ok(value) => Err(EvalexprError::expected_string(value)),
@@ -157,7 +298,7 @@ pub fn eval_string_with_context(string: &str, context: &Context) -> EvalexprResu
 /// Evaluate the given expression string into an integer with the given context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_int_with_context(string: &str, context: &Context) -> EvalexprResult<IntType> {
+pub fn eval_int_with_context(string: &str, context: &dyn Context) -> EvalexprResult<IntType> {
     match eval_with_context(string, context) {
         Ok(Value::Int(int)) => Ok(int),
         Ok(value) => Err(EvalexprError::expected_int(value)),
@@ -168,7 +309,7 @@ pub fn eval_int_with_context(string: &str, context: &Context) -> EvalexprResult<
 /// Evaluate the given expression string into a float with the given context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_float_with_context(string: &str, context: &Context) -> EvalexprResult<FloatType> {
+pub fn eval_float_with_context(string: &str, context: &dyn Context) -> EvalexprResult<FloatType> {
     match eval_with_context(string, context) {
         Ok(Value::Float(float)) => Ok(float),
         Ok(value) => Err(EvalexprError::expected_float(value)),
@@ -180,7 +321,7 @@ pub fn eval_float_with_context(string: &str, context: &Context) -> EvalexprResul
 /// If the result of the expression is an integer, it is silently converted into a float.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_number_with_context(string: &str, context: &Context) -> EvalexprResult<FloatType> {
+pub fn eval_number_with_context(string: &str, context: &dyn Context) -> EvalexprResult<FloatType> {
     match eval_with_context(string, context) {
         Ok(Value::Float(float)) => Ok(float),
         Ok(Value::Int(int)) => Ok(int as FloatType),
@@ -192,7 +333,7 @@ pub fn eval_number_with_context(string: &str, context: &Context) -> EvalexprResu
 /// Evaluate the given expression string into a boolean with the given context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_boolean_with_context(string: &str, context: &Context) -> EvalexprResult<bool> {
+pub fn eval_boolean_with_context(string: &str, context: &dyn Context) -> EvalexprResult<bool> {
     match eval_with_context(string, context) {
         Ok(Value::Boolean(boolean)) => Ok(boolean),
         Ok(value) => Err(EvalexprError::expected_boolean(value)),
@@ -203,7 +344,7 @@ pub fn eval_boolean_with_context(string: &str, context: &Context) -> EvalexprRes
 /// Evaluate the given expression string into a tuple with the given context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_tuple_with_context(string: &str, context: &Context) -> EvalexprResult<TupleType> {
+pub fn eval_tuple_with_context(string: &str, context: &dyn Context) -> EvalexprResult<TupleType> {
     match eval_with_context(string, context) {
         Ok(Value::Tuple(tuple)) => Ok(tuple),
         Ok(value) => Err(EvalexprError::expected_tuple(value)),
@@ -214,7 +355,7 @@ pub fn eval_tuple_with_context(string: &str, context: &Context) -> EvalexprResul
 /// Evaluate the given expression string into an empty value with the given context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_empty_with_context(string: &str, context: &Context) -> EvalexprResult<EmptyType> {
+pub fn eval_empty_with_context(string: &str, context: &dyn Context) -> EvalexprResult<EmptyType> {
     match eval_with_context(string, context) {
         Ok(Value::Empty) => Ok(EMPTY_VALUE),
         Ok(value) => Err(EvalexprError::expected_empty(value)),
@@ -225,7 +366,10 @@ pub fn eval_empty_with_context(string: &str, context: &Context) -> EvalexprResul
 /// Evaluate the given expression string into a string with the given mutable context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_string_with_context_mut(string: &str, context: &mut Context) -> EvalexprResult<String> {
+pub fn eval_string_with_context_mut(
+    string: &str,
+    context: &mut dyn Context,
+) -> EvalexprResult<String> {
     match eval_with_context_mut(string, context) {
         Ok(Value::String(string)) => Ok(string),
         Ok(value) => Err(EvalexprError::expected_string(value)),
@@ -236,7 +380,10 @@ pub fn eval_string_with_context_mut(string: &str, context: &mut Context) -> Eval
 /// Evaluate the given expression string into an integer with the given mutable context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_int_with_context_mut(string: &str, context: &mut Context) -> EvalexprResult<IntType> {
+pub fn eval_int_with_context_mut(
+    string: &str,
+    context: &mut dyn Context,
+) -> EvalexprResult<IntType> {
     match eval_with_context_mut(string, context) {
         Ok(Value::Int(int)) => Ok(int),
         Ok(value) => Err(EvalexprError::expected_int(value)),
@@ -249,7 +396,7 @@ pub fn eval_int_with_context_mut(string: &str, context: &mut Context) -> Evalexp
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
 pub fn eval_float_with_context_mut(
     string: &str,
-    context: &mut Context,
+    context: &mut dyn Context,
 ) -> EvalexprResult<FloatType> {
     match eval_with_context_mut(string, context) {
         Ok(Value::Float(float)) => Ok(float),
@@ -264,7 +411,7 @@ pub fn eval_float_with_context_mut(
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
 pub fn eval_number_with_context_mut(
     string: &str,
-    context: &mut Context,
+    context: &mut dyn Context,
 ) -> EvalexprResult<FloatType> {
     match eval_with_context_mut(string, context) {
         Ok(Value::Float(float)) => Ok(float),
@@ -277,7 +424,10 @@ pub fn eval_number_with_context_mut(
 /// Evaluate the given expression string into a boolean with the given mutable context.
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
-pub fn eval_boolean_with_context_mut(string: &str, context: &mut Context) -> EvalexprResult<bool> {
+pub fn eval_boolean_with_context_mut(
+    string: &str,
+    context: &mut dyn Context,
+) -> EvalexprResult<bool> {
     match eval_with_context_mut(string, context) {
         Ok(Value::Boolean(boolean)) => Ok(boolean),
         Ok(value) => Err(EvalexprError::expected_boolean(value)),
@@ -290,7 +440,7 @@ pub fn eval_boolean_with_context_mut(string: &str, context: &mut Context) -> Eva
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
 pub fn eval_tuple_with_context_mut(
     string: &str,
-    context: &mut Context,
+    context: &mut dyn Context,
 ) -> EvalexprResult<TupleType> {
     match eval_with_context_mut(string, context) {
         Ok(Value::Tuple(tuple)) => Ok(tuple),
@@ -304,7 +454,7 @@ pub fn eval_tuple_with_context_mut(
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
 pub fn eval_empty_with_context_mut(
     string: &str,
-    context: &mut Context,
+    context: &mut dyn Context,
 ) -> EvalexprResult<EmptyType> {
     match eval_with_context_mut(string, context) {
         Ok(Value::Empty) => Ok(EMPTY_VALUE),
@@ -312,3 +462,76 @@ pub fn eval_empty_with_context_mut(
         Err(error) => Err(error),
     }
 }
+
+/// Evaluate the given expression string with the given context, and convert the result to JSON.
+///
+/// Tuples are converted to arrays and the empty value to `null`.
+/// Integers are converted to JSON integers, keeping all 64 bits, and floats to JSON floats without loss of precision.
+/// Floats stay floats, even if they have no fractional part, so `2.0` is converted to the JSON number `2.0`.
+/// NaN and infinite floats cannot be represented in JSON, so they fail with `EvalexprError::NonFiniteFloatInJson` instead of being replaced by `null`.
+///
+/// This is only available with the `serde_support` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "serde_support")] {
+/// extern crate serde_json;
+/// use evalexpr::*;
+///
+/// let context = context_map! { "a" => 3 }.unwrap(); // Do proper error handling here
+/// assert_eq!(
+///     eval_to_json("(a, a / 2.0, \"text\", ())", &context).map(|json| json.to_string()),
+///     Ok("[3,1.5,\"text\",null]".to_string())
+/// );
+/// assert_eq!(
+///     eval_to_json("1.0 / 0.0", &context),
+///     Err(EvalexprError::NonFiniteFloatInJson(f64::INFINITY))
+/// );
+/// # }
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+#[cfg(feature = "serde_support")]
+pub fn eval_to_json(string: &str, context: &dyn Context) -> EvalexprResult<serde_json::Value> {
+    use std::convert::TryFrom;
+
+    serde_json::Value::try_from(eval_with_context(string, context)?)
+}
+
+/// Evaluate the given expression string with the given context, awaiting the async functions of the context.
+///
+/// The returned future evaluates the expression like `eval_with_context`, but when it reaches a call of an `AsyncFunction`, it awaits the future of the call instead of blocking the thread.
+/// Functions and builtin functions are called synchronously as usual.
+/// See `AsyncEvaluation` for details.
+///
+/// This is only available with the `async` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "async")] {
+/// extern crate futures;
+/// use evalexpr::*;
+///
+/// let mut context = HashMapContext::new();
+/// context.set_async_function("fetch_price".into(), AsyncFunction::new(Box::new(|argument| {
+///     let price = argument.as_int().map(|id| Value::from(id as FloatType * 1.5));
+///     // A real function would return a future that queries a remote service.
+///     Box::pin(futures::future::ready(price))
+/// }))).unwrap(); // Do proper error handling here
+///
+/// let evaluation = eval_with_context_async("fetch_price(2) * 2", &context);
+/// // Inside of an async function, this would be `evaluation.await`.
+/// assert_eq!(futures::executor::block_on(evaluation), Ok(Value::from(6.0)));
+/// # }
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+#[cfg(feature = "async")]
+pub fn eval_with_context_async<'a, C: AsyncContext>(
+    string: &str,
+    context: &'a C,
+) -> AsyncEvaluation<'a, C> {
+    AsyncEvaluation::new(build_compact_tree(string), context)
+}
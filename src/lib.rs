@@ -98,7 +98,7 @@
 //! }.unwrap(); // Do proper error handling here
 //! assert_eq!(precompiled.eval_with_context(&context), Ok(Value::from(true)));
 //!
-//! context.set_value("c".into(), 8.into()).unwrap(); // Do proper error handling here
+//! context.set_value("c", 8).unwrap(); // Do proper error handling here
 //! assert_eq!(precompiled.eval_with_context(&context), Ok(Value::from(false)));
 //! // `Node::eval_with_context` returns a variant of the `Value` enum,
 //! // while `Node::eval_[type]_with_context` returns the respective type directly.
@@ -106,6 +106,13 @@
 //! assert_eq!(precompiled.eval_boolean_with_context(&context), Ok(false));
 //! ```
 //!
+//! To evaluate large batches of precompiled expressions, `build_compact_tree` stores each operator tree in a single vector instead of one allocation per node.
+//! Such a `CompactTree` takes a bit longer to build, but evaluates faster if the trees do not fit into the CPU caches.
+//!
+//! Generated expressions often repeat the same subexpression, like `lookup(user_id)`.
+//! `Node::share_common_subexpressions` rewrites a precompiled operator tree such that each repeated subexpression without side effects is evaluated only once per evaluation.
+//! Functions from the context are assumed to have no side effects, so functions that do, like random number generators, need to be marked with `Function::impure`.
+//!
 //! ## Features
 //!
 //! ### Operators
@@ -123,17 +130,29 @@
 //! | * | 100 | Product |
 //! | / | 100 | Division |
 //! | % | 100 | Modulo |
-//! | + | 95 | Sum or String Concatenation |
+//! | + | 95 | Sum, String Concatenation or Tuple Concatenation |
 //! | - | 95 | Difference |
+//! | << | 90 | Left shift |
+//! | \>\> | 90 | Right shift |
+//! | .., ..= | 85 | Range |
+//! | ?? | 82 | Null-coalescing |
 //! | < | 80 | Lower than |
 //! | \> | 80 | Greater than |
 //! | <= | 80 | Lower than or equal |
 //! | \>= | 80 | Greater than or equal |
 //! | == | 80 | Equal |
 //! | != | 80 | Not equal |
+//! | =~ | 80 | Regex match |
+//! | & | 78 | Bitwise and |
+//! | bitxor | 77 | Bitwise exclusive or |
+//! | &#124; | 76 | Bitwise or |
 //! | && | 75 | Logical and |
 //! | &#124;&#124; | 70 | Logical or |
+//! | xor | 70 | Logical exclusive or |
+//! | implies | 65 | Logical implication |
+//! | ? : | 60 | Conditional |
 //! | = | 50 | Assignment |
+//! | +=, -=, *=, /=, %=, &&=, &#124;&#124;= | 50 | Compound Assignment |
 //! | , | 40 | Aggregation |
 //! | ; | 0 | Expression Chaining |
 //!
@@ -142,8 +161,12 @@
 //! | Operator | Precedence | Description |
 //! |----------|------------|-------------|
 //! | - | 110 | Negation |
+//! | + | 110 | Identity on numbers |
 //! | ! | 110 | Logical not |
 //!
+//! A `+` or `-` is unary if it starts an expression or follows an opening parenthesis or another operator, like in `-x`, `3 * -2` or `3 + +4`, and binary otherwise.
+//! The unary `+` returns its operand unchanged, but fails with `EvalexprError::ExpectedNumber` if the operand is not a number, so `+"foo"` is an error.
+//!
 //! #### The Aggregation Operator
 //!
 //! The aggregation operator aggregates two values into a tuple.
@@ -156,11 +179,302 @@
 //! assert_eq!(eval("1, 2, 3"), Ok(Value::from(vec![Value::from(1), Value::from(2), Value::from(3)])));
 //! ```
 //!
+//! A trailing comma directly before a closing brace ends the tuple, so `(1,)` is a tuple with one element, and `(1, 2,)` is the same as `(1, 2)`.
+//! Note that this makes `f(a,)` pass a tuple with one element to `f`, like `f((a,))`.
+//! Anywhere else, a comma needs to be followed by another element, so a trailing comma like in `1, 2,` fails with `EvalexprError::TrailingComma`.
+//! A comma also needs to follow an element, so `(,)` and `(1,, 2)` fail with `EvalexprError::LeadingComma`.
+//! An empty element can be written as `()`, like in `(1, ())`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("(3,)"), Ok(Value::from(vec![Value::from(3)])));
+//! assert_eq!(eval("(1, 2,)"), eval("(1, 2)"));
+//! assert!(matches!(eval("1, 2,"), Err(EvalexprError::TrailingComma { .. })));
+//! assert!(matches!(eval("(1,, 2)"), Err(EvalexprError::LeadingComma { .. })));
+//! ```
+//!
+//! #### Evaluation Order
+//!
+//! Operands are evaluated from left to right, and each operand is evaluated exactly once.
+//! This also holds for the elements of tuples and for the arguments of functions, even if the resulting value is discarded.
+//! So if elements have side effects, like assignments or calls to functions that record their calls, these side effects happen in the order in which the elements are written.
+//!
+//! The only exceptions are the logical operators `&&`, `||` and `implies`, which are short-circuiting.
+//! If the left operand of `&&` or `implies` is `false` or the left operand of `||` is `true`, then the right operand is not evaluated at all, including all tuples and function calls within it.
+//! Likewise, the conditional operator only evaluates the branch that its condition selects.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! // The right operand would fail with a type error, but it is never evaluated.
+//! assert_eq!(eval("false && 1 + true"), Ok(Value::from(false)));
+//! assert_eq!(eval("true || 1 + true"), Ok(Value::from(true)));
+//! ```
+//!
+//! #### Chained Comparisons
+//!
+//! Comparisons cannot be chained like in mathematics, as `a < b < c` would compare the boolean result of `a < b` with `c`.
+//! So a comparison that has another comparison as operand without parentheses fails to parse with `EvalexprError::SuspiciousComparisonChain`.
+//! Use `&&` to require both comparisons, or parentheses to compare the boolean result on purpose.
+//! To accept such chains and parse them from left to right, build the operator tree with `build_operator_tree_with_config` and `ParseConfig::allow_comparison_chains`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert!(matches!(eval("1 < 2 < 3"), Err(EvalexprError::SuspiciousComparisonChain { .. })));
+//! assert_eq!(eval("1 < 2 && 2 < 3"), Ok(Value::from(true)));
+//! assert_eq!(eval("(1 < 2) == true"), Ok(Value::from(true)));
+//! ```
+//!
+//! #### Implicit Multiplication
+//!
+//! Multiplications need an explicit `*`, so a missing operator like in `3(y - 1)` is an error, and `2x` is the identifier of a variable.
+//! Formulas written like in mathematics can be parsed with `ParseConfig::implicit_multiplication`, which inserts a `*` after a number that is followed by an identifier or an opening parenthesis, and between a closing and an opening parenthesis.
+//! As whitespace does not matter, `2 x` is a multiplication as well.
+//! An identifier followed by a parenthesis stays a function call, and an identifier that ends with digits, like `x2`, stays an identifier.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let config = ParseConfig {
+//!     implicit_multiplication: true,
+//!     ..ParseConfig::default()
+//! };
+//! let context = context_map! { "x" => 3, "y" => 2 }.unwrap(); // Do proper error handling here
+//! let parsed = build_operator_tree_with_config("2x + 3(y - 1) + (x)(y)", &config).unwrap(); // Do proper error handling here
+//! assert_eq!(parsed.tree.eval_with_context(&context), Ok(Value::from(15)));
+//! assert_eq!(parsed.tree.to_minified_string(), "2*x+3*(y-1)+x*y");
+//! ```
+//!
+//! #### The Addition Operator
+//!
+//! The addition operator adds two numbers, concatenates two strings or concatenates two tuples.
+//! Concatenating tuples does not flatten nested tuples.
+//! Adding a tuple and a value that is not a tuple fails with `EvalexprError::AdditionError`, as does adding a string and a number.
+//! To append a single value to a tuple, wrap it into a tuple of its own, like in `(1, 2) + (3,)`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("(1, 2) + (3, 4)"), Ok(Value::from(vec![Value::from(1), Value::from(2), Value::from(3), Value::from(4)])));
+//! assert_eq!(eval("((1, 2), 3) + (4, 5)"), Ok(Value::from(vec![
+//!     Value::from(vec![Value::from(1), Value::from(2)]),
+//!     Value::from(3),
+//!     Value::from(4),
+//!     Value::from(5),
+//! ])));
+//! assert_eq!(eval("(1, 2) + (3,)"), Ok(Value::from(vec![Value::from(1), Value::from(2), Value::from(3)])));
+//! assert_eq!(eval("(1, 2) + 3"), Err(EvalexprError::AdditionError {
+//!     augend: Value::from(vec![Value::from(1), Value::from(2)]),
+//!     addend: Value::from(3),
+//! }));
+//! ```
+//!
+//! #### The Division and Modulo Operators
+//!
+//! The division and modulo operators compute with integers only if both operands are integers, rounding the quotient towards zero.
+//! If one of the operands is a float, even one without a fractional part like `4.0`, then both operands are converted to floats, so `7.0 / 2` is `3.5`.
+//! Integer operations that have no result fail with `EvalexprError::DivisionError` or `EvalexprError::ModulationError`, which contain both operands.
+//! This includes `IntType::MIN / -1`, whose quotient is out of the range of `IntType`.
+//! Float operations never fail, but may produce infinity or NaN, see `EvalConfig::nan_policy`.
+//!
+//! | Expression | Integers | Floats |
+//! |------------|----------|--------|
+//! | `x / 0` | `DivisionError` | infinity with the sign of `x`, or NaN if `x` is zero |
+//! | `x % 0` | `ModulationError` | NaN |
+//! | `0 / x` and `0 % x` | `0` | `0.0` |
+//! | `IntType::MIN / -1` | `DivisionError` | |
+//! | `IntType::MIN % -1` | `ModulationError` | |
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "min" => IntType::MIN }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("min / -1", &context), Err(EvalexprError::DivisionError {
+//!     dividend: Value::from(IntType::MIN),
+//!     divisor: Value::from(-1),
+//! }));
+//! assert_eq!(eval("7 / 2"), Ok(Value::from(3)));
+//! assert_eq!(eval("7.0 / 2"), Ok(Value::from(3.5)));
+//! ```
+//!
+//! #### The Exponentiation Operator
+//!
+//! The exponentiation operator is right associative, so `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`, and it binds stronger than negation, so `-2 ^ 2` is `-(2 ^ 2)`.
+//! If both operands are integers and the exponent is not negative, then the power is an integer, and powers out of the range of `IntType` fail with `EvalexprError::ExponentiationError`.
+//! Otherwise, both operands are converted to floats, so `2 ^ -1` is `0.5`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("2 ^ 3 ^ 2"), Ok(Value::from(512)));
+//! assert_eq!(eval("2 ^ -1"), Ok(Value::from(0.5)));
+//! assert_eq!(eval("2 ^ 64"), Err(EvalexprError::ExponentiationError {
+//!     base: Value::from(2),
+//!     exponent: Value::from(64),
+//! }));
+//! ```
+//!
+//! #### Exclusive Or and Implication
+//!
+//! The logical exclusive or `xor` accepts booleans only, and binds as weak as `||`, so `a && b xor c` means `(a && b) xor c`.
+//! It needs both operands to decide its result, so unlike `&&` and `||` it always evaluates both of them.
+//! The bitwise exclusive or on integers is the keyword `bitxor`, see the bitwise operators below.
+//!
+//! The implication `a implies b` is the same as `!a || b`, binds weaker than `||`, and is right associative.
+//! Like `&&`, it does not evaluate its right operand if the left one is `false`.
+//! The operators `xor`, `bitxor` and `implies` are written as words, which are keywords and cannot be used as variables.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("true xor false"), Ok(Value::from(true)));
+//! assert_eq!(eval("!true xor true"), Ok(Value::from(true)));
+//! assert!(matches!(eval("1 xor 2"), Err(EvalexprError::ExpectedBoolean { .. })));
+//! assert_eq!(eval("1 > 2 implies 1 + true"), Ok(Value::from(true)));
+//! assert!(matches!(eval("xor + 1"), Err(EvalexprError::KeywordAsValue { .. })));
+//! ```
+//!
+//! #### The Conditional Operator
+//!
+//! The conditional `c ? a : b` evaluates to `a` if the condition `c` is `true`, and to `b` if it is `false`.
+//! The condition must be a boolean, otherwise it fails with `EvalexprError::ExpectedBoolean`.
+//! Only the selected branch is evaluated, so the other one may read a missing variable or divide by zero.
+//! The conditional binds weaker than all binary operators except assignment, aggregation and chaining, and is right associative, so `a ? b : c ? d : e` means `a ? b : (c ? d : e)`.
+//! The branch between `?` and `:` is delimited like by parentheses, so it may contain any expression.
+//! A `?` without a matching `:` fails with `EvalexprError::UnmatchedQuestionMark`, and a `:` without a matching `?` fails with `EvalexprError::UnmatchedColon`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "qty" => 150, "price" => 10.0 }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("qty > 100 ? price * 0.9 : price", &context), Ok(Value::from(9.0)));
+//! assert_eq!(eval("false ? 1 / 0 : 2"), Ok(Value::from(2)));
+//! assert_eq!(eval("false ? 1 : true ? 2 : 3"), Ok(Value::from(2)));
+//! assert_eq!(eval("1 ? 2 : 3"), Err(EvalexprError::expected_boolean(Value::from(1))));
+//! ```
+//!
+//! #### If-Expressions
+//!
+//! For longer expressions, a conditional can also be written as `if c { a } else { b }`, which evaluates like `c ? a : b`.
+//! Branches for further conditions can be chained with `else if`, and an `if` without `else` evaluates to `Value::Empty` if its condition is `false`.
+//! Each block may contain any expression, including assignments and chains.
+//! The whole if-expression is an operand like a parenthesized subexpression, so `1 + if c { 2 } else { 3 }` adds the selected branch to one.
+//! The words `if` and `else` are keywords, so they cannot be used as variables, but `if(a, b, c)` is still a call of a function named `if`, like the `IF` of the `excel_compat` feature.
+//! A malformed if-expression, like one without a block, fails with `EvalexprError::InvalidIfExpression`, and a block without its closing curly brace fails with `EvalexprError::UnmatchedLCurlyBrace`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "temperature" => 35 }.unwrap(); // Do proper error handling here
+//! assert_eq!(
+//!     eval_with_context("if temperature > 30 { \"hot\" } else { \"cold\" }", &context),
+//!     Ok(Value::from("hot"))
+//! );
+//! assert_eq!(
+//!     eval_with_context("if temperature < 0 { 1 } else if temperature < 20 { 2 } else { 3 }", &context),
+//!     Ok(Value::from(3))
+//! );
+//! assert_eq!(eval("if false { 1 }"), Ok(Value::Empty));
+//! assert!(matches!(
+//!     eval("if true { 1 } else 2"),
+//!     Err(EvalexprError::InvalidIfExpression { .. })
+//! ));
+//! ```
+//!
+//! #### The Null-Coalescing Operator
+//!
+//! The null-coalescing operator `a ?? b` evaluates to `a`, unless `a` is a variable that is not found, in which case it evaluates to `b`.
+//! Other errors of `a`, like `EvalexprError::DivisionError` or type errors, are not caught.
+//! Neither are errors from within a left-hand side that is not just a variable, so `f(a) ?? 0` and `(a + 1) ?? 0` fail if `a` is not found.
+//! The right-hand side is only evaluated if it is needed, and chains like `a ?? b ?? 0` are right associative, so they try each variable from left to right.
+//! It binds weaker than arithmetic and ranges but stronger than comparisons, so `discount ?? 0 > 5` compares the result with five.
+//! When checking types, the variable of the left-hand side may be missing, and the result has the type of either side.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "discount" => 10 }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("discount ?? 0", &context), Ok(Value::from(10)));
+//! assert_eq!(eval_with_context("bonus ?? 0", &context), Ok(Value::from(0)));
+//! assert_eq!(eval_with_context("bonus ?? penalty ?? discount * 2", &context), Ok(Value::from(20)));
+//! assert!(matches!(eval("1 / 0 ?? 2"), Err(EvalexprError::DivisionError { .. })));
+//! assert!(matches!(eval("(bonus + 1) ?? 2"), Err(EvalexprError::VariableIdentifierNotFound { .. })));
+//! ```
+//!
+//! #### The Regex Match Operator
+//!
+//! The regex match operator `a =~ b` evaluates to true if the regex `b` matches anywhere in the string `a`, like the function `str::regex_matches(a, b)`.
+//! Both operands must be strings, otherwise it fails with `EvalexprError::ExpectedString`, and a pattern that is not a valid regex fails with `EvalexprError::InvalidRegex`.
+//! It is a comparison, so chains like `a =~ b == c` need parentheses.
+//! Compiled regexes are cached per thread, so evaluating a precompiled operator tree with a constant pattern again does not compile the pattern again.
+//! Like the regex functions, it requires the feature flag `builtin_regex`, and fails with `EvalexprError::OperatorNeedsFeature` without it.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! # #[cfg(feature = "builtin_regex")] {
+//! let context = context_map! { "name" => "report-2024.csv" }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("name =~ \"^report-[0-9]+[.]csv$\"", &context), Ok(Value::from(true)));
+//! assert_eq!(eval_with_context("!(name =~ \"[.]txt$\")", &context), Ok(Value::from(true)));
+//! assert!(matches!(eval("\"a\" =~ \"(\""), Err(EvalexprError::InvalidRegex { .. })));
+//! # }
+//! ```
+//!
+//! #### The Bitwise Operators
+//!
+//! The bitwise operators `&`, `bitxor` and `|` accept integers only, and fail with `EvalexprError::ExpectedInt` for other values.
+//! Like in C, they bind weaker than comparisons, so a bitwise result needs parentheses to be compared, like in `(flags & 4) == 4`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "flags" => 0b0110 }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("flags & 0b0011", &context), Ok(Value::from(0b0010)));
+//! assert_eq!(eval_with_context("flags bitxor 0b1111 | 1", &context), Ok(Value::from(0b1001)));
+//! assert_eq!(eval_with_context("(flags & 4) == 4", &context), Ok(Value::from(true)));
+//! ```
+//!
+//! The shift operators `<<` and `>>` accept integers only as well, and bind weaker than arithmetic but stronger than comparisons, so `1 << n + 1` shifts by `n + 1` bits.
+//! The right shift keeps the sign of negative integers, and bits shifted out on either side are dropped.
+//! Shifting by a negative amount of bits, or by at least the amount of bits of `IntType`, fails with `EvalexprError::ShiftAmountOutOfRange`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("0b0101 << 3"), Ok(Value::from(0b0010_1000)));
+//! assert_eq!(eval("-16 >> 2"), Ok(Value::from(-4)));
+//! assert_eq!(eval("(0b0100 >> 2 & 1) == 1"), Ok(Value::from(true)));
+//! assert!(matches!(eval("1 << 64"), Err(EvalexprError::ShiftAmountOutOfRange { .. })));
+//! ```
+//!
+//! #### The Range Operators
+//!
+//! The range `a..b` evaluates to a tuple of the integers from `a` up to, but not including, `b`, and the inclusive range `a..=b` includes `b` as well.
+//! A range whose end is not after its start, like `5..1`, is an empty tuple.
+//! Both ends must be integers, otherwise the range fails with `EvalexprError::ExpectedInt`.
+//! Ranges bind weaker than arithmetic and shifts but stronger than comparisons, so `1..n + 1` ends after `n`.
+//! A range with more elements than `EvalConfig::max_range_length`, which defaults to a million, fails with `EvalexprError::RangeTooLong` before its elements are allocated.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("1..4"), Ok(Value::from(vec![Value::from(1), Value::from(2), Value::from(3)])));
+//! assert_eq!(eval("(1..=3) == (1, 2, 3)"), Ok(Value::from(true)));
+//! assert_eq!(eval("5..1"), Ok(Value::Tuple(vec![])));
+//! assert_eq!(eval("1..2.5"), Err(EvalexprError::expected_int(Value::from(2.5))));
+//! assert!(matches!(eval("0..10000000000"), Err(EvalexprError::RangeTooLong { .. })));
+//! ```
+//!
 //! #### The Assignment Operator
 //!
 //! This crate features the assignment operator, that allows expressions to store their result in a variable in the expression context.
 //! If an expression uses the assignment operator, it must be evaluated with a mutable context.
+//! The right-hand side of an assignment is evaluated before the value is stored, so if the context cannot be manipulated, functions on the right-hand side are called before the assignment fails with `EvalexprError::ContextNotManipulable`.
+//! `Context::is_manipulable` tells if a context accepts assignments.
 //! Note that assignments are type safe, meaning if an identifier is assigned a value of a type once, it cannot be assigned a value of another type.
+//! The left-hand side of an assignment has to be a single variable identifier or a tuple of them, so expressions like `1 = 2`, `(a) = 2` or `a + b = 2` fail to parse with `EvalexprError::InvalidAssignmentTarget`.
 //!
 //! ```rust
 //! use evalexpr::*;
@@ -171,6 +485,41 @@
 //! assert_eq!(eval_empty_with_context_mut("a = 5.0", &mut context), Err(EvalexprError::expected_int(5.0.into())));
 //! assert_eq!(eval_int_with_context("a", &context), Ok(5));
 //! assert_eq!(context.get_value("a"), Some(5.into()).as_ref());
+//! assert_eq!(build_operator_tree("(a) = 5"), Err(EvalexprError::InvalidAssignmentTarget { span: 4..5 }));
+//! ```
+//!
+//! Assigning to a tuple of variables, like `(a, b) = pair`, unpacks a tuple into its elements.
+//! The right-hand side is evaluated once, and needs to be a tuple with one element per variable, or fails with `EvalexprError::DestructuringLengthMismatch` otherwise.
+//! Tuples of variables can be nested, like in `((a, b), c) = ((1, 2), 3)`, and only contain identifiers.
+//! As the assignment has a higher precedence than the aggregation, the right-hand side needs parentheses if it is written as a tuple.
+//! The variables are assigned from left to right, so if the context rejects a value, like one with another type than the variable had before, the variables before it are assigned already.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let mut context = HashMapContext::new();
+//! assert_eq!(eval_with_context_mut("(q, r) = (17 / 5, 17 % 5); ((x, y), z) = ((q, r), q * r); (x, y, z)", &mut context),
+//!            Ok(Value::from(vec![Value::from(3), Value::from(2), Value::from(6)])));
+//! assert_eq!(eval_empty_with_context_mut("(q, r) = (1, 2, 3)", &mut context),
+//!            Err(EvalexprError::DestructuringLengthMismatch { expected: 2, actual: 3 }));
+//! ```
+//!
+//! The compound assignment operators `+=`, `-=`, `*=`, `/=`, `%=`, `&&=` and `||=` apply an operator to the value of a variable and assign the result to it.
+//! They are built into the operator tree as a plain assignment, so `a *= b + 1` is the same as `a = a * (b + 1)`.
+//! Therefore, the variable needs to have a value already, and the operator fails with the same errors as its plain form, like `EvalexprError::DivisionError` for `a /= 0`.
+//! Like `&&` and `||`, `&&=` and `||=` do not evaluate their right-hand side if the value of the variable decides the result.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let mut context = context_map! { "total" => 0, "price" => 12, "qty" => 3 }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_empty_with_context_mut("total += price * qty; total -= 6", &mut context), Ok(EMPTY_VALUE));
+//! assert_eq!(eval_int_with_context("total", &context), Ok(30));
+//! assert_eq!(build_operator_tree("total += 1").unwrap().to_minified_string(), "total=total+1");
+//! assert!(matches!(
+//!     eval_empty_with_context_mut("missing += 1", &mut context),
+//!     Err(EvalexprError::VariableIdentifierNotFound { .. })
+//! ));
 //! ```
 //!
 //! #### The Expression Chaining Operator
@@ -178,6 +527,8 @@
 //! The expression chaining operator works as one would expect from programming languages that use the semicolon to end statements, like `Rust`, `C` or `Java`.
 //! It has the special feature that it returns the value of the last expression in the expression chain.
 //! If the last expression is terminated by a semicolon as well, then `Value::Empty` is returned.
+//! Empty expressions, like between the semicolons of `a = 1;; a`, are skipped.
+//! The expressions are evaluated from left to right, and if one of them fails, the whole chain fails with its error, without evaluating the remaining expressions.
 //! Expression chaining is useful together with assignment to create small scripts.
 //!
 //! ```rust
@@ -189,33 +540,210 @@
 //!
 //! // Initialization of variables via script.
 //! assert_eq!(eval_empty_with_context_mut("hp = 1; max_hp = 5; heal_amount = 3;", &mut context), Ok(EMPTY_VALUE));
+//! # #[cfg(feature = "builtin_math")] {
 //! // Precompile healing script.
 //! let healing_script = build_operator_tree("hp = min(hp + heal_amount, max_hp); hp").unwrap(); // Do proper error handling here
 //! // Execute precompiled healing script.
 //! assert_eq!(healing_script.eval_int_with_context_mut(&mut context), Ok(4));
 //! assert_eq!(healing_script.eval_int_with_context_mut(&mut context), Ok(5));
+//! # }
 //! ```
 //!
+//! If a script computes several results, `eval_script` returns the variables that the script exports with `export(a, b, ...)` as a `HashMap`.
+//! Within scripts evaluated this way, `export` takes variable identifiers only, and the exported values are read after the whole script was evaluated.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let mut context = HashMapContext::new();
+//! let exports = eval_script("area = 3 * 4; perimeter = 2 * (3 + 4); export(area, perimeter)", &mut context).unwrap(); // Do proper error handling here
+//! assert_eq!(exports["area"], Value::from(12));
+//! assert_eq!(exports["perimeter"], Value::from(14));
+//! ```
+//!
+//! #### Comments
+//!
+//! Two slashes `//` start a comment that lasts until the end of the line, so expressions stored in configuration files can explain themselves.
+//! Block comments start with `/*` and end with the next `*/`, so they can annotate parts of a line, like in `(base /* per unit */ + surcharge) * count`.
+//! Block comments are not nested, and a block comment without its `*/` fails with `EvalexprError::UnterminatedComment`.
+//! Comments are ignored like whitespace, so `2/*x*/3` are two separate integers, and slashes within string literals do not start a comment.
+//! A single slash is still the division operator, also if it is followed by another one after whitespace, like in `a / /b`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "price" => 100.0 }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("price * 1.19 // add VAT", &context), Ok(Value::from(119.0)));
+//! assert_eq!(eval_with_context("price /* net */ * 1.19", &context), Ok(Value::from(119.0)));
+//! assert_eq!(eval("\"http://example.com\" // a string with slashes"), Ok(Value::from("http://example.com")));
+//! ```
+//!
+//! Tools that rewrite expressions, like formatters, can keep the comments with `tokenize_lossless`.
+//! It splits an expression into `LosslessToken`s that include the whitespace and comments with their text and position, such that concatenating the texts of all tokens gives the expression back.
+//!
 //! ### Builtin Functions
 //!
 //! This crate offers a set of builtin functions.
 //!
 //! | Identifier | Argument Amount | Argument Types | Description |
 //! |------------|-----------------|----------------|-------------|
+//! | `all_of` | 3 | Tuple, String, Any | Returns true if all elements of the tuple satisfy the comparison named by the second argument with the third argument |
+//! | `any_of` | 3 | Tuple, String, Any | Returns true if any element of the tuple satisfies the comparison named by the second argument with the third argument |
+//! | `concat` | >= 1 | Tuple or String | Concatenates all tuple arguments or all string arguments, like the `+` operator. A single argument is returned unchanged |
 //! | `min` | >= 1 | Numeric | Returns the minimum of the arguments |
 //! | `max` | >= 1 | Numeric | Returns the maximum of the arguments |
+//! | `abs` | 1 | Numeric | Returns the absolute value of the argument |
+//! | `signum` | 1 | Numeric | Returns -1, 0 or 1 depending on the sign of the argument, in the type of the argument |
+//! | `pow` | 2 | Numeric | Returns the first argument raised to the power of the second |
+//! | `sqrt` | 1 | Numeric | Returns the square root of the argument as float |
+//! | `ln` | 1 | Numeric | Returns the natural logarithm of the argument as float |
+//! | `bit` | 2 | Integer | Returns the bit of the first argument at the index in the second argument, counted from the least significant bit from 0 to 63, as 0 or 1 |
+//! | `bits` | 3 | Integer | Returns the bits of the first argument from the index in the second argument up to and including the index in the third argument, shifted down to start at bit 0 |
+//! | `popcount` | 1 | Integer | Returns the amount of one bits of the argument, where negative integers count in two's complement |
+//! | `leading_zeros` | 1 | Integer | Returns the amount of zero bits before the most significant one bit, or 64 for 0 |
+//! | `trailing_zeros` | 1 | Integer | Returns the amount of zero bits after the least significant one bit, or 64 for 0 |
+//! | `now_monotonic` | 0 | | Returns the seconds elapsed since its first call in the process as float |
+//! | `haversine_km` | 4 | Numeric | Returns the great-circle distance in kilometres between the points given as latitude and longitude of the first and of the second point in degrees |
+//! | `haversine_miles` | 4 | Numeric | Like `haversine_km`, but returns the distance in miles |
+//! | `in_bbox` | 6 | Numeric | Returns true if the point given by the first two arguments lies within the bounding box given by minimum latitude, minimum longitude, maximum latitude and maximum longitude |
+//! | `unique` | 1 | Tuple | Returns the distinct elements of the tuple |
+//! | `intersect` | 2 | Tuple, Tuple | Returns the distinct elements of the first tuple that are contained in the second tuple |
+//! | `union` | 2 | Tuple, Tuple | Returns the distinct elements of both tuples |
+//! | `difference` | 2 | Tuple, Tuple | Returns the distinct elements of the first tuple that are not contained in the second tuple |
+//! | `range` | 1 - 3 | Integer | Returns a tuple of the integers from the start (default 0) up to but excluding the end, in steps of the step (default 1) |
+//! | `map` | 2 | Tuple, Any | Returns the values of the second argument for each element of the tuple, where `it` is the element |
+//! | `filter` | 2 | Tuple, Boolean | Returns the elements of the tuple for which the second argument is true, where `it` is the element |
+//! | `str` | 1 | Any | Returns the argument as string, where strings stay unchanged and other values are written like by their `Display` implementation |
 //! | `len` | 1 | String | Returns the character length of a string |
 //! | `str::regex_matches` | 2 | String, String | Returns true if the first argument matches the regex in the second argument |
 //! | `str::regex_replace` | 3 | String, String, String | Returns the first argument with all matches of the regex in the second argument replaced by the third argument |
 //! | `str::to_lowercase` | 1 | String | Returns the lower-case version of the string |
 //! | `str::to_uppercase` | 1 | String | Returns the upper-case version of the string |
 //! | `str::trim` | 1 | String | Strips whitespace from the start and the end of the string |
+//! | `str::starts_with` | 2 | String, String | Returns true if the first argument starts with the second argument |
+//! | `str::ends_with` | 2 | String, String | Returns true if the first argument ends with the second argument |
+//! | `str::strip_prefix` | 2 | String, String | Returns the first argument without the second argument at its start, or the first argument unchanged if it does not start with the second argument |
+//! | `str::strip_suffix` | 2 | String, String | Returns the first argument without the second argument at its end, or the first argument unchanged if it does not end with the second argument |
+//! | `matches_glob` | 2 | String, String | Returns true if the second argument matches the shell-style wildcard pattern in the first argument, see below |
+//! | `matches_glob_ci` | 2 | String, String | Like `matches_glob`, but ignores the case of letters |
+//! | `char_at` | 2 | String, Integer | Returns the character at the zero-based character index of the string |
+//! | `code_point` | 1 | String | Returns the Unicode code point of a string of exactly one character as integer |
+//! | `from_code_point` | 1 | Integer | Returns a string of the character with the given Unicode code point |
+//! | `humanize_bytes` | 1 | Numeric | Returns the number of bytes as size with a binary unit, like `1.5 GiB`, see below |
+//! | `humanize_duration` | 1 | Numeric | Returns the number of seconds as duration in its two largest units, like `3d 4h`, see below |
+//! | `parse_bytes` | 1 | String | Returns the number of bytes of a size like `1.5 GiB` or `1.5GiB`, rounded to an integer |
+//! | `parse_duration` | 1 | String | Returns the number of seconds of a duration like `3d 4h` or `3d4h`, rounded to an integer |
+//! | `str::casefold` | 1 | String | Returns the string with full Unicode case folding applied, for case-insensitive comparisons |
+//! | `str::width` | 1 | String | Returns the display width of the string in terminal columns |
+//! | `str::sha256` | 1 | String | Returns the SHA-256 digest of the UTF-8 bytes of the string as 64 lower-case hexadecimal digits |
+//! | `IF` | 2 - 3 | Any | Returns the second argument if the first is true, and otherwise the third argument, or false if it is omitted |
+//! | `AND` | >= 1 | Boolean or Numeric | Returns true if all arguments are true |
+//! | `OR` | >= 1 | Boolean or Numeric | Returns true if any argument is true |
+//! | `SUMIF` | 2 - 3 | Tuple, Any, Tuple | Sums the numbers of the third tuple, or of the first if it is omitted, where the first tuple matches the criterion in the second argument |
+//! | `ROUND` | 2 | Numeric, Integer | Rounds the number half away from zero to the given amount of decimal digits |
+//! | `CONCATENATE` | >= 1 | String, Numeric or Boolean | Joins the text of the arguments |
+//! | `LEFT` | 1 - 2 | String, Integer | Returns the given amount of characters (default 1) from the start of the string |
+//! | `RIGHT` | 1 - 2 | String, Integer | Returns the given amount of characters (default 1) from the end of the string |
+//! | `MID` | 3 | String, Integer, Integer | Returns the given amount of characters of the string, starting at the one-based position in the second argument |
+//!
+//! The builtin functions are grouped by feature flags, such that unused groups can be left out of the binary.
+//!
+//! | Feature Flag | Functions |
+//! |--------------|-----------|
+//! | `builtin_math` | `min`, `max`, `abs`, `signum`, `pow`, `sqrt`, `ln`, `bit`, `bits`, `popcount`, `leading_zeros`, `trailing_zeros` |
+//! | `builtin_string` | `str`, `len`, `str::to_lowercase`, `str::to_uppercase`, `str::trim`, `str::starts_with`, `str::ends_with`, `str::strip_prefix`, `str::strip_suffix`, `matches_glob`, `matches_glob_ci`, `char_at`, `code_point`, `from_code_point`, `humanize_bytes`, `humanize_duration`, `parse_bytes`, `parse_duration` |
+//! | `builtin_tuple` | `all_of`, `any_of`, `concat`, `unique`, `intersect`, `union`, `difference`, `range`, `map`, `filter` |
+//! | `builtin_datetime` | `now_monotonic` |
+//! | `builtin_regex` | `str::regex_matches`, `str::regex_replace` |
+//! | `builtin_crypto` | `str::sha256` |
+//! | `unicode_support` | `str::casefold`, `str::width` |
+//! | `geo_support` | `haversine_km`, `haversine_miles`, `in_bbox` |
+//! | `excel_compat` | `IF`, `AND`, `OR`, `SUMIF`, `ROUND`, `CONCATENATE`, `LEFT`, `RIGHT`, `MID` |
+//!
+//! The default feature flag `builtin_all` enables all `builtin_*` groups, which are `builtin_math`, `builtin_string`, `builtin_tuple`, `builtin_datetime`, `builtin_regex` and `builtin_crypto`.
+//! `builtin_regex` pulls in the `regex` crate, so builds that do not need regexes may want to select the groups they need instead.
+//! The groups `unicode_support`, `geo_support` and `excel_compat` are not part of `builtin_all` and need to be enabled explicitly, where `unicode_support` pulls in the `caseless` and `unicode-width` crates.
+//! For example, to only include the math functions, depend on evalexpr with `default-features = false, features = ["builtin_math"]`.
+//! Calling a builtin function of a disabled group fails with `EvalexprError::FunctionIdentifierNotFound`, and the message of the error names the missing feature flag.
+//! `builtin_function_identifiers` returns the identifiers of all enabled builtin functions.
 //!
 //! The `min` and `max` functions can deal with a mixture of integer and floating point arguments.
 //! If the maximum or minimum is an integer, then an integer is returned.
 //! Otherwise, a float is returned.
 //!
-//! The regex functions require the feature flag `regex_support`.
+//! The `abs`, `signum` and `pow` functions compute with integers if all their arguments are integers, and with floats otherwise.
+//! If an integer result is out of the range of `IntType`, like `abs` of `IntType::MIN` or `pow(2, 63)`, they fail with `EvalexprError::IntegerOverflow` instead of wrapping around.
+//! `pow` with a negative integer exponent returns a float, so `pow(2, -1)` is `0.5`.
+//! Like in most languages, `pow(0, 0)` is `1`.
+//! `signum` of a float zero is zero, and of NaN is NaN.
+//! `sqrt` and `ln` of a negative number are NaN, while `ln(0)` is negative infinity, see `EvalConfig::nan_policy` for how to catch the NaN.
+//!
+//! The `all_of` and `any_of` functions accept the comparison operators `"=="`, `"!="`, `">"`, `"<"`, `">="` and `"<="`, which behave like the respective operators.
+//! For example, `all_of(scores, ">", 0.5)` is true if every element of `scores` is greater than `0.5`.
+//! Like `&&` and `||`, they stop comparing as soon as the result is known.
+//!
+//! The set functions `unique`, `intersect`, `union` and `difference` return the first occurrence of each distinct element, in the order of the first and then the second argument.
+//! Elements are compared like with `==`, except that integers and floats with the same numeric value are equal, also as elements of nested tuples.
+//! For example, `union((1, 2, 2), (2.0, 3))` is `(1, 2, 3)`.
+//! Like with `==`, values that contain NaN are not equal to any value, so they are kept by `unique` and never found by `intersect`.
+//! They hash the elements, so they take linear time in the length of the tuples.
+//!
+//! The `range` function takes the arguments `end`, `start, end` or `start, end, step`, like the `range` of Python.
+//! For example, `range(4)` is `(0, 1, 2, 3)` and `range(5, 0, -2)` is `(5, 3, 1)`.
+//! A range that never reaches its end, like `range(5, 0)`, is empty, and a step of zero fails with `EvalexprError::RangeStepZero`.
+//! Ranges can get large quickly, so their length counts against `EvalConfig::max_result_bytes` with the size of a `Value` per element, and the evaluation fails with `EvalexprError::ValueTooLarge` before the range is allocated.
+//!
+//! The higher-order functions `map` and `filter` evaluate their second argument once for each element of the tuple in their first argument, with the variable `it` linked to the element.
+//! For example, `map(prices, it * 1.2)` raises all prices by 20%, and `filter(names, len(it) > 3)` keeps the names that are longer than three characters.
+//! If calls are nested, like in `map(map(xs, it + 1), it * 2)`, each `it` refers to the element of the nearest enclosing call whose second argument contains it.
+//! `it` is only special within the second argument, and hides a variable with the same identifier there, while it is a normal variable everywhere else.
+//! If the second argument is only the identifier of a function, like in `map(names, str::to_uppercase)`, then the function is called with each element instead.
+//! `eval_with_context_async` evaluates calls of `map` and `filter` synchronously, so async functions cannot be called within them.
+//!
+//! The `matches_glob` function matches text against shell-style wildcards without a regex engine.
+//! In the pattern, `*` matches any sequence of characters, `?` matches any single character, and `[a-z]` matches a single character of a class, which is negated if it starts with `!` or `^`.
+//! A backslash escapes the following character, so `matches_glob("100\\*", "100*")` is true, as the backslash itself needs to be escaped in the string literal.
+//! The pattern has to match the whole text, so `matches_glob("*.txt", "notes.txt")` is true, but `matches_glob("notes", "notes.txt")` is false.
+//! A malformed pattern, like one with an unterminated character class, fails with `EvalexprError::InvalidGlob`.
+//! `matches_glob_ci` compares each character in its lower-case and upper-case form, so `matches_glob_ci("[A-C]*", "bob")` is true, and ranges like `[Z-a]` keep their meaning.
+//!
+//! The functions `char_at`, `code_point` and `from_code_point` inspect strings character by character, like in `code_point(char_at(s, 0)) == 65`.
+//! They count Unicode scalar values, so `char_at("a😀b", 1)` is `"😀"` regardless of how many bytes the emoji takes.
+//! An index out of range, a string that is not exactly one character long and a code point that is a surrogate or above `0x10FFFF` fail with `EvalexprError::ArgumentOutOfRange`.
+//!
+//! The functions `humanize_bytes` and `humanize_duration` format numbers for display, like `humanize_bytes(disk_free)` as `"1.5 GiB"` and `humanize_duration(uptime)` as `"3d 4h"`.
+//! Sizes below 1024 bytes are written in whole bytes like `"512 B"`, and larger sizes with one decimal place in the largest of the binary units `KiB`, `MiB`, `GiB`, `TiB`, `PiB` and `EiB` in which they are at least 1, where each unit is 1024 times the previous one.
+//! Durations below a minute are written in seconds with one decimal place that is left out if it is zero, like `"1.5s"` and `"45s"`.
+//! Longer durations are rounded to whole seconds and written in their largest unit of `d`, `h`, `m` and `s`, followed by the next smaller unit if it is not zero, so the smaller units are left out.
+//! Negative numbers get a leading minus sign, and zero is `"0 B"` and `"0s"`.
+//! The inverse functions `parse_bytes` and `parse_duration` read these formats, with or without spaces, and return integers, so `parse_duration("3d4h")` is `273600`.
+//! Malformed text and values out of the range of `IntType` fail with `EvalexprError::ArgumentOutOfRange`.
+//!
+//! The `now_monotonic` function reads a monotonic clock, so the difference of two calls is the time that passed between them, like in `t0 = now_monotonic(); ...; elapsed = now_monotonic() - t0`.
+//! It counts from its first call in the process rather than from the start of the process, so the first call returns about zero.
+//! It is not suitable for wall-clock timestamps, as the point in time it counts from depends on when it is first called.
+//! Like all builtin functions, it can be overridden by a function with the same identifier in the context, for example to return fixed values in tests.
+//!
+//! The functions `haversine_km`, `haversine_miles` and `in_bbox` require the feature flag `geo_support`.
+//! They take latitudes and longitudes in degrees, as integers or floats, and return an `EvalexprError::CoordinateOutOfRange` if a latitude is not within `[-90, 90]` or a longitude is not within `[-180, 180]`.
+//! Distances are computed on a sphere with the mean earth radius of 6371 km, which deviates from the true distance by up to about 0.5%.
+//! If the minimum longitude of a bounding box is greater than its maximum longitude, `in_bbox` treats the box as crossing the antimeridian, so `in_bbox(0, 179.5, -1, 179, 1, -179)` is true.
+//!
+//! The spreadsheet functions `IF`, `AND`, `OR`, `SUMIF`, `ROUND`, `CONCATENATE`, `LEFT`, `RIGHT` and `MID` require the feature flag `excel_compat`.
+//! They follow the conventions of Excel, so that formulas can be taken over with few changes, and unlike all other identifiers, their identifiers are matched in any case.
+//! Like all builtin functions, they can be overridden by functions in the context, which are matched with the exact identifier.
+//! `AND` and `OR` take any amount of booleans or numbers, where a number is true if it is not zero, and tuples count as ranges of such values.
+//! `IF` evaluates all of its arguments, as it is a function and not an operator.
+//! `ROUND` rounds half away from zero, to 15 significant digits first like Excel, so `ROUND(2.675, 2)` is `2.68` even though `2.675` is slightly smaller as a float.
+//! `LEFT`, `RIGHT` and `MID` count characters, and `MID` counts positions from one.
+//! The criterion of `SUMIF` is a value that the elements must equal, or a string like `">160000"`, `"<>Fruits"` or `"*es"`, with the operators `=`, `<>`, `<`, `>`, `<=` and `>=`.
+//! Text is compared without regarding case, and may contain the wildcards `*` and `?`, which are escaped with `~`.
+//! Arguments that Excel rejects with `#VALUE!`, like a start position of zero for `MID`, fail with `EvalexprError::ArgumentOutOfRange`.
+//!
+//! The regex functions require the feature flag `builtin_regex`, or its older alias `regex_support`, and share the cache of compiled regexes with the `=~` operator.
+//! The functions `str::casefold` and `str::width` require the feature flag `unicode_support`.
+//! Unlike `str::to_lowercase`, `str::casefold` also folds characters like `ß` into `ss`, so `str::casefold("Straße") == str::casefold("STRASSE")`.
+//! `str::width` counts East Asian wide and full-width characters as two columns and zero-width characters as zero columns.
 //!
 //! ### Values
 //!
@@ -227,19 +755,46 @@
 //! |------------|---------|
 //! | `Value::String` | `"abc"`, `""`, `"a\"b\\c"` |
 //! | `Value::Boolean` | `true`, `false` |
-//! | `Value::Int` | `3`, `-9`, `0`, `135412` |
-//! | `Value::Float` | `3.`, `.35`, `1.00`, `0.5`, `123.554` |
+//! | `Value::Int` | `3`, `-9`, `0`, `135412`, `0xFF`, `0o17`, `0b1010` |
+//! | `Value::Float` | `3.`, `.35`, `1.00`, `0.5`, `123.554`, `1e9`, `2.5E+7`, `1.5e-3` |
 //! | `Value::Tuple` | `(3, 55.0, false, ())`, `(1, 2)` |
 //! | `Value::Empty` | `()` |
 //!
+//! Within strings, a backslash starts an escape sequence.
+//! The escape sequences `\"` and `\\` stand for a double quote and a backslash, and `\n`, `\t` and `\r` for a newline, a tab and a carriage return.
+//! Other escape sequences fail with `EvalexprError::IllegalEscapeSequence`, and a string without a closing double quote fails with `EvalexprError::UnterminatedString`.
+//!
 //! Integers are internally represented as `i64`, and floating point numbers are represented as `f64`.
 //! Tuples are represented as `Vec<Value>` and empty values are not stored, but represented by rust's unit type `()` where necessary.
 //!
+//! Float literals may have an exponent with an optional sign, like `1.5e-3`, which belongs to the literal rather than being a subtraction.
+//! A number with an exponent marker but without exponent digits, like `1e`, is an `EvalexprError::InvalidNumberLiteral`.
+//!
+//! Digits of number literals may be separated by underscores, like in `10_000_000`, `1_000.5_5` or `0xFF_FF`.
+//! Each underscore must stand between two digits, so literals like `1__000` or `1_.5` are an `EvalexprError::InvalidNumberLiteral`, while names like `_foo` or `foo_bar` that do not start with a digit are identifiers.
+//!
+//! Integer literals with the prefix `0x`, `0o` or `0b` are hexadecimal, octal or binary, like `0xff00`, `0o755` or `0b1010`.
+//! The prefixes may be written in upper case as well, and hexadecimal digits in any case.
+//! Like decimal literals, they are always positive and can be negated with a unary minus, like `-0x10`.
+//!
+//! An integer literal that does not fit into an `i64`, like `9223372036854775808` or `0x8000000000000000`, is an `EvalexprError::IntegerLiteralOutOfRange`.
+//! The exception is the smallest `i64`, which is in range directly after a unary minus, like in `-9223372036854775808` or `-0x8000000000000000`, unless it is the base of `^`.
+//! To parse such literals as the nearest floating point number instead, build the operator tree with `build_operator_tree_with_config` and `IntegerOverflowPolicy::ConvertToFloat`.
+//! Each conversion is then reported as a `ParseWarning` together with the operator tree.
+//!
+//! Expressions may contain literals of a domain-specific syntax, like `#FF00FF` for colors or `v1.2.3` for versions, if the operator tree is built with `build_operator_tree_with_config` and a `CustomLiteral` for the syntax in `ParseConfig::custom_literals`.
+//! A custom literal is a prefix followed by characters that the recognizer accepts, and its text is converted to a constant value when the expression is parsed.
+//! If the conversion fails, parsing fails with `EvalexprError::InvalidCustomLiteral`, whose span locates the literal.
+//! Number literals always keep their meaning, so a custom literal that starts with a digit is only recognized if it is not a valid number, like `1.2.3`.
+//!
 //! There exist type aliases for some of the types.
 //! They include `IntType`, `FloatType`, `TupleType` and `EmptyType`.
 //!
 //! Values can be constructed either directly or using the `From` trait.
+//! Integers of the types `usize`, `isize`, `u64`, `i128` and `u128` may not fit into an `IntType`, so they are converted with the `TryFrom` trait instead, which fails with an `EvalexprError::IntOutOfRange` that holds the exact integer if it is out of range.
+//! To saturate at the bounds of `IntType` instead, use `Value::from_usize_lossy` and its variants for the other types.
 //! Values can be decomposed using the `Value::as_[type]` methods.
+//! The elements of tuples can be read with `Value::get` and `Value::len`, or with the `Value::get_[type]` methods that check the type of the element, and fail with an error that names the index of the element.
 //! The type of a value can be checked using the `Value::is_[type]` methods.
 //!
 //! **Examples for constructing a value:**
@@ -248,6 +803,8 @@
 //! |------|--------|
 //! | `Value::from(4)` | `Value::Int(4)` |
 //! | `Value::from(4.4)` | `Value::Float(4.4)` |
+//! | `Value::try_from(4usize)` | `Ok(Value::Int(4))` |
+//! | `Value::from_usize_lossy(usize::MAX)` | `Value::Int(IntType::MAX)` |
 //! | `Value::from(true)` | `Value::Boolean(true)` |
 //! | `Value::from(vec![Value::from(3)])` | `Value::Tuple(vec![Value::Int(3)])` |
 //!
@@ -262,10 +819,44 @@
 //! Operators that take numbers as arguments can either take integers or floating point numbers.
 //! If one of the arguments is a floating point number, all others are converted to floating point numbers as well, and the resulting value is a floating point number as well.
 //! Otherwise, the result is an integer.
-//! An exception to this is the exponentiation operator that always returns a floating point number.
+//! The arithmetic operators `+`, `-`, `*`, `/`, `%` and `^` all follow this rule regardless of the order of their arguments, so `total / 3.0` and `3.0 / total` are both floats if `total` is an integer.
+//! The only exception are integer powers with a negative exponent, like `2 ^ -1`, which are floats as they have no integer result.
+//!
+//! Floating point operations without a defined result, like `0.0 / 0.0` or `(-8) ^ 0.5`, produce NaN, which then makes all comparisons with it false.
+//! To catch such operations where they happen, set `EvalConfig::nan_policy` to `NanPolicy::Error`.
+//! Then an operator or builtin function that produces NaN from operands that are not NaN fails with `EvalexprError::NaNProduced`, which names the operation and its operands.
 //!
 //! Values have a precedence of 200.
 //!
+//! When values are displayed, floats are written like by the `Display` implementation of `FloatType`, which never uses scientific notation.
+//! To write them differently, for example always with six decimal places, install a `FloatFormatter` for the current thread with `set_float_formatter`.
+//! It is used by the `Display` implementation of `Value` and by the builtin function `str`, but not for the messages of errors and the display of operator trees.
+//!
+//! To embed a value into a generated expression, use `Value::to_expression_literal`, which renders the value such that the expression evaluates to it.
+//! For strings, this escapes quotes and backslashes, such that user-provided strings cannot change the meaning of the expression.
+//!
+//! Strings and tuples can grow quickly, for example if a string is repeatedly added to itself.
+//! To limit the memory used by an evaluation, set `EvalConfig::max_result_bytes`.
+//! Then, the bytes of all strings and tuples produced by the evaluation are counted, including the elements of nested tuples, and the evaluation fails with `EvalexprError::ValueTooLarge` before it allocates more bytes than allowed.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let state = EvalState::new(EvalConfig {
+//!     max_result_bytes: Some(64),
+//!     ..Default::default()
+//! });
+//! let tree = build_operator_tree("s = \"abcdefgh\"; s = s + s; s = s + s; s = s + s").unwrap(); // Do proper error handling here
+//! assert!(matches!(
+//!     tree.eval_with_context_mut_and_state(&mut HashMapContext::new(), &state),
+//!     Err(EvalexprError::ValueTooLarge { limit: 64, .. })
+//! ));
+//! ```
+//!
+//! Evaluating the same expression for many rows allocates the strings and tuples of intermediate values anew for each row.
+//! To reuse their buffers instead, set `EvalConfig::arena` to an `EvalArena`, which keeps the buffers of finished intermediate values for later evaluations.
+//! The results are the same as without an arena, and they do not borrow from it.
+//!
 //! ### Variables
 //!
 //! This crate allows to compile parameterizable formulas by using variables.
@@ -274,6 +865,7 @@
 //! This is done with the `Context` trait.
 //! Two structs implementing this trait are predefined.
 //! There is `EmptyContext`, that returns `None` for each request, and `HashMapContext`, that stores mappings from literals to variables in a hash map.
+//! Besides the methods of `Context`, `HashMapContext` has generic `set_value` and `get_value` methods, so values can be set like `context.set_value("x", 5)`.
 //!
 //! Variables do not have fixed types in the expression itself, but are typed by the context.
 //! The `Context` trait contains a function that takes a string literal and returns a `Value` enum.
@@ -281,6 +873,61 @@
 //!
 //! Variables have a precedence of 200.
 //!
+//! The keywords `true`, `false`, `xor`, `bitxor`, `implies`, `if` and `else`, listed in `HARD_KEYWORDS`, are literals, operators or parts of if-expressions and cannot be used as variables.
+//! Assigning to them fails with `EvalexprError::AssignmentToKeyword`, and reading an operator keyword like a variable fails with `EvalexprError::KeywordAsValue`.
+//! The words in `SOFT_RESERVED_WORDS`, like `in`, `as`, `and` and `null`, are reserved for syntax that may be added in later versions.
+//! They can still be used as variables for now, but `build_operator_tree_with_config` reports each such variable as a `ParseWarning::ReservedIdentifier`, such that it can be renamed before the syntax is added.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let parsed = build_operator_tree_with_config("null == 0", &ParseConfig::default()).unwrap(); // Do proper error handling here
+//! assert_eq!(parsed.warnings, vec![ParseWarning::ReservedIdentifier { identifier: "null".into(), span: 0..4 }]);
+//! ```
+//!
+//! Expressions that are shared between multiple formulas can be stored in the context as named expressions with `Context::set_expression`.
+//! If a variable is not linked to a value, but to a named expression, the named expression is evaluated in the current context in place of the variable.
+//! Named expressions may refer to other named expressions, but if they refer to each other in a cycle, the evaluation fails with `EvalexprError::ExpressionCycle`.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let mut context = context_map! {
+//!     "orders" => 12,
+//!     "revenue" => 1500.0
+//! }.unwrap(); // Do proper error handling here
+//! context.set_expression(
+//!     "is_premium".into(),
+//!     build_operator_tree("orders >= 10 && revenue > 1000").unwrap() // Do proper error handling here
+//! ).unwrap(); // Do proper error handling here
+//!
+//! assert_eq!(eval_with_context("is_premium && orders < 20", &context), Ok(Value::from(true)));
+//! ```
+//!
+//! To mirror assignments to some other place, a `HashMapContext` can call a hook after each successful `set_value` with `HashMapContext::set_on_change`.
+//! Alternatively, `HashMapContext::set_record_changes` records the changes, such that they can be retrieved with `HashMapContext::take_changes` after the evaluation.
+//! Assignments that fail, for example because the variable already has a value of another type, are not reported.
+//!
+//! If a variable is neither bound to a value nor to a named expression, the evaluation fails with `EvalexprError::VariableIdentifierNotFound`.
+//! For operator trees built from source, the error contains the range of bytes where the variable is read, just like parse errors such as `EvalexprError::UnmatchedLBrace` contain the range of the offending token.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! { "price" => 10 }.unwrap(); // Do proper error handling here
+//! assert_eq!(
+//!     eval_with_context("price * (1 + rate)", &context),
+//!     Err(EvalexprError::VariableIdentifierNotFound { identifier: "rate".into(), span: Some(13..17) })
+//! );
+//! assert_eq!(
+//!     eval("max(1, (2 + 3)").unwrap_err().to_string(),
+//!     "Found an unmatched opening parenthesis '(' at offset 3."
+//! );
+//! ```
+//!
+//! `EvalexprError::span` returns this range for all errors that have one.
+//! For command line tools, `EvalexprError::render_with_source` renders an error together with the line of the source that contains the range, and underlines the range with carets.
+//!
 //! ### User-Defined Functions
 //!
 //! This crate also allows to define arbitrary functions to be used in parsed expressions.
@@ -290,8 +937,8 @@
 //! The `argument_amount` determines the length of the slice that is passed to `function` if it is `Some(_)`, otherwise the function is defined to take an arbitrary amount of arguments.
 //! It is verified on execution by the crate and does not need to be verified by the `function`.
 //!
-//! Functions with no arguments are not allowed.
-//! Use variables instead.
+//! A function is called with a single argument, which is the value of the expression in the parentheses of the call.
+//! So a call with several arguments like `f(1, 2)` passes a tuple, a call with one argument like `f(1)` passes the value itself, and a call without arguments like `now()` passes `Value::Empty`.
 //!
 //! Be aware that functions need to verify the types of values that are passed to them.
 //! The `error` module contains some shortcuts for verification, and error types for passing a wrong value type.
@@ -299,10 +946,56 @@
 //!
 //! Functions are identified by literals, like variables as well.
 //! A literal identifies a function, if it is followed by an opening brace `(`, another literal, or a value.
+//! Whitespace between the identifier and the opening brace is ignored, so `f (1, 2)` and `f\n(1, 2)` are calls like `f(1, 2)`.
+//! If no function with the identifier is defined, the call fails with `EvalexprError::FunctionIdentifierNotFound`.
 //!
 //! Same as variables, function bindings are provided by the user via a `Context`.
 //! Functions have a precedence of 190.
 //!
+//! If a function from the context fails, its error is wrapped in an `EvalexprError::InFunctionCall` that names the function and its argument, such that the failing call can be found in expressions with many calls.
+//! The error is displayed like `in call to 'normalize' (argument (3, "x")): Error: bad input`, and the error of the function is available via `std::error::Error::source`.
+//! Builtin functions are not wrapped.
+//!
+//! Closures with up to three typed parameters can be turned into functions with `Function::from_fn`, which checks the amount and types of the arguments before calling the closure.
+//! The same works with `HashMapContext::with_fn`, and with closures after the keyword `fn` in `context_map!`:
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! {
+//!     "double" => fn |x: IntType| x * 2,
+//!     "repeat" => fn |text: String, times: IntType| text.repeat(times as usize),
+//! }.unwrap(); // Do proper error handling here
+//! assert_eq!(eval_with_context("repeat(\"ab\", double(2))", &context), Ok(Value::from("abababab")));
+//! ```
+//!
+//! To register specialized variants of a function, `Function::bind` fixes its leading arguments.
+//! For example, `distance.bind(vec![Value::from(lat), Value::from(lon)])` creates a function that only takes the remaining two coordinates.
+//!
+//! Functions whose parameters are named with `Function::with_parameters` can be called with named arguments, like `round_to(x, places = 3)`.
+//! Named arguments follow the positional ones in any order, and parameters created with `Parameter::optional` take their default value if a call omits them.
+//! Only trailing parameters may have default values, so calls with positional arguments only may omit them as well, like `round_to(x)`.
+//! Unknown names fail with `EvalexprError::UnknownNamedArgument`, parameters that are given twice with `EvalexprError::DuplicateArgument`, and omitted required parameters with `EvalexprError::MissingArgument`.
+//! A positional argument after a named one, or a name that is given twice, is an error of parsing.
+//! To pass the result of an assignment as an argument instead, the assignment needs parentheses, like `f((a = 1))`.
+//! The arguments of the higher-order builtins like `map` are never named, so `map(xs, total = total + it)` assigns to `total`.
+//!
+//! Functions may evaluate other expressions themselves.
+//! Such nested evaluations are counted, and if more than `EvalConfig::max_recursion_depth` (64 by default) evaluations are nested into each other, the evaluation fails with `EvalexprError::RecursionLimitExceeded`.
+//! Nested evaluations started with the plain `eval_*` methods share a thread-local `EvalState`.
+//! To use a custom configuration, functions can pass an `EvalState` through to `Node::eval_with_context_and_state`.
+//!
+//! Evaluations that call slow functions can be stopped from another thread with a `CancellationToken` set as `EvalConfig::cancellation_token`.
+//! The token is checked before each operator is applied, so after `CancellationToken::cancel` is called, the evaluation fails with `EvalexprError::Cancelled` as soon as the currently running function returns.
+//!
+//! Expressions that compute several independent results as a tuple, like `(cpu_pct, mem_pct, disk_pct)` for a dashboard, fail as a whole if one of the elements fails.
+//! With `EvalConfig::tuple_error_mode` set to `TupleErrorMode::CollectPartial`, `Node::eval_with_context_and_report` replaces each failed element of such a top-level tuple with `Value::Empty` instead, and returns the errors of the elements together with the value in an `EvalReport`.
+//!
+//! Functions that wait for the network or other slow resources should not block the thread if the expression is evaluated within an async runtime.
+//! With the `async` feature, such functions can be defined as `AsyncFunction`s that return a future, and linked with `HashMapContext::set_async_function` or any other implementation of the `AsyncContext` trait.
+//! The future returned by `eval_with_context_async` evaluates the expression like `eval_with_context`, but awaits the future of each call of an async function before it continues.
+//! Ordinary functions and builtin functions work the same way in both evaluations, but the synchronous evaluation does not call async functions.
+//!
 //! ### Examplary variables and functions in expressions:
 //!
 //! | Expression | Valid? | Explanation |
@@ -315,6 +1008,140 @@
 //! | `true` | no | Expression is interpreted as `Value::Bool` |
 //! | `.34` | no | Expression is interpreted as `Value::Float` |
 //!
+//! ### Type Checking
+//!
+//! `Node::check_types` checks the types of an expression without evaluating it, for example to reject `"abc" * 2` when a rule is saved.
+//! It takes the types of the variables and the signatures of the functions, and returns the `StaticType` of the result or all `TypeCheckError`s in the expression.
+//! Each `TypeCheckError` has the `span` of the node that caused it, like the errors of parsing.
+//! A `StaticType` is either one of the types of `ValueType`, `Number` for integers and floats, or `Any` if the type cannot be determined statically.
+//!
+//! ```rust
+//! use evalexpr::*;
+//! use std::collections::HashMap;
+//!
+//! let mut variable_types = HashMap::new();
+//! variable_types.insert("flag".to_string(), ValueType::Boolean);
+//!
+//! let tree = build_operator_tree("flag + 1").unwrap(); // Do proper error handling here
+//! assert_eq!(
+//!     tree.check_types(&variable_types, &HashMap::new(), false),
+//!     Err(vec![TypeCheckError::OperatorArgumentTypes {
+//!         operator: "+".to_string(),
+//!         actual: vec![StaticType::Boolean, StaticType::Int],
+//!         span: Some(5..6),
+//!     }])
+//! );
+//! ```
+//!
+//! The other way around, `Node::infer_variable_types` infers the types that the variables of an expression may have from how they are used, for example to generate a form for the inputs of the expression.
+//! It returns a `TypeSet` for each variable, which is `TypeSet::CONFLICT` if the variable is used in ways that no single type satisfies.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let tree = build_operator_tree("a + 1 > 0 && b").unwrap(); // Do proper error handling here
+//! let types = tree.infer_variable_types();
+//! assert_eq!(types["a"].value_types(), vec![ValueType::Int, ValueType::Float]);
+//! assert_eq!(types["b"].value_types(), vec![ValueType::Boolean]);
+//! ```
+//!
+//! Expressions that are stored to compute a value of a certain type, like a rule that must produce a boolean, can be parsed as `TypedExpression`.
+//! `TypedExpression::parse` fails if the expression cannot produce a value of the type, and `TypedExpression::eval` returns the value already converted.
+//! Variables and functions can have any type when parsing, so their types are checked when evaluating.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let rule = TypedExpression::<bool>::parse("score > 0.5").unwrap(); // Do proper error handling here
+//! assert_eq!(rule.eval(&context_map! { "score" => 0.75 }.unwrap()), Ok(true));
+//! assert!(TypedExpression::<bool>::parse("score * 2").is_err());
+//! ```
+//!
+//! ### Inspecting Operator Trees
+//!
+//! Tools like formatters, linters or transpilers can walk an operator tree with `Node::children` or `Node::iter` and ask each node for its `Node::kind`.
+//! The returned `OperatorKind` is a stable view of the operator that does not change when the internal representation for evaluation does.
+//! It is marked `#[non_exhaustive]`, so matches on it need a wildcard arm for kinds added by later versions.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! fn to_sql(node: &Node) -> String {
+//!     let children: Vec<String> = node.children().iter().map(to_sql).collect();
+//!     match node.kind() {
+//!         OperatorKind::Root => format!("({})", children.join(" ")),
+//!         OperatorKind::And => format!("{} AND {}", children[0], children[1]),
+//!         OperatorKind::Or => format!("{} OR {}", children[0], children[1]),
+//!         OperatorKind::Eq => format!("{} = {}", children[0], children[1]),
+//!         OperatorKind::Neq => format!("{} <> {}", children[0], children[1]),
+//!         OperatorKind::Gt => format!("{} > {}", children[0], children[1]),
+//!         OperatorKind::VariableRead | OperatorKind::Constant => node.to_string().trim().to_string(),
+//!         kind => panic!("{:?} is not supported", kind),
+//!     }
+//! }
+//!
+//! let tree = build_operator_tree("age > 17 && country == \"NZ\"").unwrap(); // Do proper error handling here
+//! assert_eq!(to_sql(&tree), "(age > 17 AND country = \"NZ\")");
+//! ```
+//!
+//! Constants forget how their literal was spelled, so `1.50` is written as `1.5` when the tree is displayed.
+//! Formatters that must keep literals as they are can set `ParseConfig::preserve_literal_text`, which makes constants parsed from number literals remember their text for `Display` and `Node::literal_text`.
+//!
+//! To embed an expression into a client bundle, `Node::to_minified_string` writes it back with as little whitespace and as few parentheses as possible.
+//! `Node::to_renamed_string` additionally replaces the identifiers of variables with the ones given in a map, which leaves string literals and function identifiers untouched.
+//! Building an operator tree from either string gives a tree that evaluates the same as the original one.
+//! Their variants `Node::to_minified_string_with_source_map` and `Node::to_renamed_string_with_source_map` also return a `SourceMap`, which translates byte offsets and ranges of the written expression back to the source that the tree was built from.
+//! This way, a position found in the written expression, like the one of a failing subexpression, can be shown to the user in the text they wrote.
+//!
+//! For filters that should run inside a database, the `sql` feature provides a complete translation with `sql::to_sql`.
+//! It generates a parameterized `WHERE` condition for Postgres or MySQL from comparisons, boolean logic, arithmetic and string functions with known SQL equivalents, and reports the first node without an equivalent as `sql::UnsupportedNode`, so that the expression can be evaluated in-process instead.
+//!
+//! To find out why a rule matched or not, `Node::explain_with_context` evaluates the expression and returns an `Explanation` with the value of each subexpression.
+//! Subexpressions that were skipped by short-circuiting `&&` and `||` are marked as not evaluated, and `Explanation::to_text` writes the whole breakdown as an indented report.
+//!
+//! Clones of an operator tree share their nodes, so cloning a tree takes the same time regardless of its size.
+//! `Node::substitute_variables` replaces variables of a tree with other trees, and copies only the nodes on the way to the replaced variables.
+//! This way, variants of a large expression, like one per tenant, can be instantiated from a single base tree without copying it each time.
+//!
+//! Applications that keep many parsed expressions over the same variables, like a rule engine, can share their identifiers as well.
+//! If the trees are built with the same `IdentifierInterner` in `ParseConfig::identifier_interner`, each distinct identifier is stored once for all of them, and `IdentifierInterner::stats` reports how many bytes this saves.
+//!
+//! An expression that is evaluated repeatedly with mostly unchanged variables can be wrapped with `Node::memoize`.
+//! The returned `MemoizedNode` remembers the values of the variables that its last evaluation read, and returns the cached result as long as they are unchanged.
+//! Expressions that assign, call impure functions or read named expressions are evaluated each time.
+//!
+//! ### Testing Expressions
+//!
+//! The macros `assert_eval_eq!`, `assert_eval_err!` and `assert_eval_type!` help to test expressions.
+//! If an assertion fails, the panic message shows the expression, its parsed operator tree and the actual result.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let context = context_map! {
+//!     "five" => 5
+//! }.unwrap(); // Do proper error handling here
+//!
+//! assert_eval_eq!("five + 1", 6, &context);
+//! assert_eval_err!("five + true", EvalexprError::ExpectedNumberOrString { .. }, &context);
+//! assert_eval_type!("five / 2", ValueType::Int, &context);
+//! ```
+//!
+//! To make sure that stored expressions keep their results when this crate is upgraded, the `testing` feature provides `testing::run_corpus`.
+//! It evaluates a corpus of expressions, written in text files together with their variables and expected results, and reports each case whose result changed.
+//! The corpus in the directory `tests/corpus` of this crate covers every operator and builtin function, and can serve as an example.
+//!
+//! ### Tracing
+//!
+//! With the `tracing` feature, evaluations are instrumented with [`tracing`](https://docs.rs/tracing) spans at debug level, with the target `evalexpr`.
+//! Each call of `eval_with_context`, `eval_with_context_mut`, `Node::eval_with_context` or `Node::eval_with_context_mut`, and of the functions and methods built on them, runs in a span named `eval`.
+//! Its field `expression_hash` identifies the expression, hashed from its text for the functions and from its operator tree for the methods of `Node`.
+//! Each call of a function runs in a span named `call`, with the identifier of the function in the field `function`, and the field `builtin` telling if it is a builtin function.
+//! When the evaluation or call finishes, its span records the field `duration_us`, and either `result_type` with the `ValueType` of the result or `error` with the `EvalexprError::variant_name` of the error, and a debug event with the same fields is emitted.
+//!
+//! Without a subscriber that is interested in these spans, the expression is not hashed and the evaluation is not timed.
+//! Without the feature, the instrumentation is not compiled at all.
+//!
 //! ### [Serde](https://serde.rs)
 //!
 //! To use this crate with serde, the `serde_support` feature flag has to be set.
@@ -354,6 +1181,11 @@
 //! The crate also implements `Serialize` and `Deserialize` for the `HashMapContext`.
 //! But note that only the variables get serialized, not the functions.
 //!
+//! To return results as JSON, values can be converted into a `serde_json::Value` with `TryFrom`, or evaluated directly into JSON with `eval_to_json`.
+//! Tuples become arrays and the empty value becomes `null`.
+//! Integers stay integers and keep all 64 bits, and floats are converted without loss of precision.
+//! NaN and infinite floats have no JSON representation, so converting them fails with `EvalexprError::NonFiniteFloatInJson`.
+//!
 //! ## License
 //!
 //! This crate is primarily distributed under the terms of the MIT license.
@@ -362,7 +1194,9 @@
 
 #![warn(missing_docs)]
 
-#[cfg(feature = "regex_support")]
+#[cfg(feature = "unicode_support")]
+extern crate caseless;
+#[cfg(feature = "builtin_regex")]
 extern crate regex;
 #[cfg(test)]
 extern crate ron;
@@ -371,25 +1205,58 @@ extern crate serde;
 #[cfg(feature = "serde_support")]
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "serde_support")]
+extern crate serde_json;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "unicode_support")]
+extern crate unicode_width;
 
-pub use context::{Context, EmptyContext, HashMapContext};
-pub use error::{EvalexprError, EvalexprResult};
-pub use function::Function;
+pub use config::{
+    CancellationToken, CustomLiteral, EvalConfig, IdentifierInterner, IntegerOverflowPolicy,
+    InternerStats, NanPolicy, ParseConfig, TupleErrorMode,
+};
+pub use context::{ChangeHookFunction, Context, EmptyContext, HashMapContext};
+pub use error::{Coordinate, EvalexprError, EvalexprResult};
+#[cfg(feature = "async")]
+pub use feature_async::{AsyncContext, AsyncEvaluation, AsyncFunction, BoxedValueFuture};
+pub use function::{
+    builtin::builtin_function_identifiers, FromArgument, Function, IntoFunction, Parameter,
+};
 pub use interface::*;
-pub use tree::Node;
+pub use operator::OperatorKind;
+pub use state::{EvalArena, EvalState};
+pub use token::{
+    tokenize_lossless, LosslessToken, ParseWarning, TokenKind, HARD_KEYWORDS, SOFT_RESERVED_WORDS,
+};
+pub use tree::{
+    CompactTree, EvalReport, Explanation, ExpressionType, MemoizedNode, Node, ParsedTree,
+    SourceMap, TupleElementError, TypedExpression,
+};
+pub use typecheck::{Signature, StaticType, TypeCheckError, TypeSet};
 pub use value::{
-    value_type::ValueType, EmptyType, FloatType, IntType, TupleType, Value, EMPTY_VALUE,
+    escape_string_literal, set_float_formatter, value_type::ValueType, EmptyType, FloatFormatter,
+    FloatType, IntType, TupleType, Value, EMPTY_VALUE,
 };
 
+mod config;
 mod context;
 pub mod error;
+#[cfg(feature = "async")]
+mod feature_async;
 #[cfg(feature = "serde_support")]
 mod feature_serde;
+mod feature_tracing;
 mod function;
 mod interface;
 mod operator;
+#[cfg(feature = "sql")]
+pub mod sql;
+mod state;
+pub mod testing;
 mod token;
 mod tree;
+mod typecheck;
 mod value;
 
 // Exports
@@ -1,38 +1,22 @@
 use std::fmt::{Display, Error, Formatter};
 
 use operator::*;
+use value::display::CanonicalFloats;
 
 impl Display for Operator {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         use crate::operator::Operator::*;
         match self {
-            RootNode => Ok(()),
-            Add => write!(f, "+"),
-            Sub => write!(f, "-"),
-            Neg => write!(f, "-"),
-            Mul => write!(f, "*"),
-            Div => write!(f, "/"),
-            Mod => write!(f, "%"),
-            Exp => write!(f, "^"),
-
-            Eq => write!(f, "=="),
-            Neq => write!(f, "!="),
-            Gt => write!(f, ">"),
-            Lt => write!(f, "<"),
-            Geq => write!(f, ">="),
-            Leq => write!(f, "<="),
-            And => write!(f, "&&"),
-            Or => write!(f, "||"),
-            Not => write!(f, "!"),
-
-            Tuple => write!(f, ", "),
-            Assign => write!(f, " = "),
-
-            Chain => write!(f, "; "),
-
-            Const { value } => write!(f, "{}", value),
+            Shared { .. } => Ok(()),
+            Const { value } => {
+                let _canonical = CanonicalFloats::new();
+                write!(f, "{}", value)
+            },
+            PreservedConst { constant } => write!(f, "{}", constant.text),
             VariableIdentifier { identifier } => write!(f, "{}", identifier),
             FunctionIdentifier { identifier } => write!(f, "{}", identifier),
+            NamedFunctionIdentifier { call } => write!(f, "{}", call.identifier),
+            operator => write!(f, "{}", operator.expect_implementation().symbol()),
         }
     }
 }
@@ -0,0 +1,164 @@
+use operator::Operator;
+
+/// The kind of operator of a node in the operator tree, as returned by `Node::kind`.
+///
+/// This enum is part of the stable public interface and is independent of how the operators are represented for evaluation.
+/// New kinds may be added in minor versions as the expression language grows, so matches on it need a wildcard arm.
+/// Existing kinds are not removed or changed in meaning without a major version.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let tree = build_operator_tree("a == 1 && f(b)").unwrap(); // Do proper error handling here
+/// let comparisons = tree
+///     .iter()
+///     .filter(|node| match node.kind() {
+///         OperatorKind::Eq | OperatorKind::Neq | OperatorKind::Gt | OperatorKind::Lt => true,
+///         OperatorKind::Geq | OperatorKind::Leq => true,
+///         _ => false,
+///     })
+///     .count();
+/// assert_eq!(comparisons, 1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OperatorKind {
+    /// A root node, which groups its children, like the whole expression or a parenthesized subexpression.
+    Root,
+
+    /// The binary `+` operator.
+    Add,
+    /// The binary `-` operator.
+    Sub,
+    /// The unary `-` operator.
+    Neg,
+    /// The unary `+` operator.
+    Pos,
+    /// The `*` operator.
+    Mul,
+    /// The `/` operator.
+    Div,
+    /// The `%` operator.
+    Mod,
+    /// The `^` operator.
+    Exp,
+
+    /// The `==` operator.
+    Eq,
+    /// The `!=` operator.
+    Neq,
+    /// The `>` operator.
+    Gt,
+    /// The `<` operator.
+    Lt,
+    /// The `>=` operator.
+    Geq,
+    /// The `<=` operator.
+    Leq,
+    /// The `=~` operator that matches a string against a regex.
+    RegexMatch,
+    /// The `&&` operator.
+    And,
+    /// The `||` operator.
+    Or,
+    /// The `xor` operator.
+    Xor,
+    /// The `!` operator.
+    Not,
+    /// The `implies` operator.
+    Implies,
+
+    /// The `&` operator.
+    BitAnd,
+    /// The `|` operator.
+    BitOr,
+    /// The `bitxor` operator.
+    BitXor,
+    /// The `<<` operator.
+    Shl,
+    /// The `>>` operator.
+    Shr,
+    /// The `..` operator that creates a tuple of the integers from its start up to, but not including, its end.
+    Range,
+    /// The `..=` operator that creates a tuple of the integers from its start up to and including its end.
+    RangeInclusive,
+
+    /// The `? :` operator that selects one of two branches by a condition.
+    /// Its children are the condition, the branch if it is true and the branch if it is false.
+    Conditional,
+    /// The `??` operator that evaluates to its second child if the variable of its first child is not found.
+    Coalesce,
+
+    /// The `,` operator that aggregates its children into a tuple.
+    Tuple,
+    /// The `=` operator that assigns to a variable.
+    /// Its first child is the identifier of the variable as a string constant.
+    Assign,
+    /// The `;` operator that chains expressions.
+    Chain,
+
+    /// A constant value, like a literal.
+    Constant,
+    /// The read of a variable.
+    VariableRead,
+    /// The call of a function, whose only child is the argument.
+    FunctionCall,
+
+    /// A subexpression that is evaluated only once for all of its occurrences, see `Node::share_common_subexpressions`.
+    /// Its only child is the subexpression.
+    Shared,
+}
+
+impl<'a> From<&'a Operator> for OperatorKind {
+    fn from(operator: &'a Operator) -> Self {
+        match operator {
+            Operator::RootNode => OperatorKind::Root,
+
+            Operator::Add => OperatorKind::Add,
+            Operator::Sub => OperatorKind::Sub,
+            Operator::Neg => OperatorKind::Neg,
+            Operator::Pos => OperatorKind::Pos,
+            Operator::Mul => OperatorKind::Mul,
+            Operator::Div => OperatorKind::Div,
+            Operator::Mod => OperatorKind::Mod,
+            Operator::Exp => OperatorKind::Exp,
+
+            Operator::Eq => OperatorKind::Eq,
+            Operator::Neq => OperatorKind::Neq,
+            Operator::Gt => OperatorKind::Gt,
+            Operator::Lt => OperatorKind::Lt,
+            Operator::Geq => OperatorKind::Geq,
+            Operator::Leq => OperatorKind::Leq,
+            Operator::RegexMatch => OperatorKind::RegexMatch,
+            Operator::And => OperatorKind::And,
+            Operator::Or => OperatorKind::Or,
+            Operator::Xor => OperatorKind::Xor,
+            Operator::Not => OperatorKind::Not,
+            Operator::Implies => OperatorKind::Implies,
+
+            Operator::BitAnd => OperatorKind::BitAnd,
+            Operator::BitOr => OperatorKind::BitOr,
+            Operator::BitXor => OperatorKind::BitXor,
+            Operator::Shl => OperatorKind::Shl,
+            Operator::Shr => OperatorKind::Shr,
+            Operator::Range => OperatorKind::Range,
+            Operator::RangeInclusive => OperatorKind::RangeInclusive,
+
+            Operator::Conditional => OperatorKind::Conditional,
+            Operator::Coalesce => OperatorKind::Coalesce,
+
+            Operator::Tuple => OperatorKind::Tuple,
+            Operator::Assign => OperatorKind::Assign,
+            Operator::Chain => OperatorKind::Chain,
+
+            Operator::Const { .. } | Operator::PreservedConst { .. } => OperatorKind::Constant,
+            Operator::VariableIdentifier { .. } => OperatorKind::VariableRead,
+            Operator::FunctionIdentifier { .. } | Operator::NamedFunctionIdentifier { .. } => {
+                OperatorKind::FunctionCall
+            },
+            Operator::Shared { .. } => OperatorKind::Shared,
+        }
+    }
+}
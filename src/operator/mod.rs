@@ -1,16 +1,30 @@
+use std::sync::Arc;
+
+use config::EvalConfig;
+use feature_tracing;
 use function::builtin::builtin_function;
+#[cfg(feature = "builtin_tuple")]
+use function::builtin::range_allocation_hint;
 
 use crate::{context::Context, error::*, value::Value};
+use Function;
 
 mod display;
+mod kind;
+mod registry;
+
+pub use self::kind::OperatorKind;
+pub(crate) use self::registry::OperatorImpl;
+use self::registry::BUILTIN_OPERATORS;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Operator {
     RootNode,
 
     Add,
     Sub,
     Neg,
+    Pos,
     Mul,
     Div,
     Mod,
@@ -22,18 +36,70 @@ pub enum Operator {
     Lt,
     Geq,
     Leq,
+    RegexMatch,
     And,
     Or,
+    Xor,
     Not,
+    Implies,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+
+    Range,
+    RangeInclusive,
+
+    Conditional,
+    Coalesce,
 
     Tuple,
     Assign,
 
     Chain,
 
-    Const { value: Value },
-    VariableIdentifier { identifier: String },
-    FunctionIdentifier { identifier: String },
+    Const {
+        value: Value,
+    },
+    /// A constant that remembers the text of the literal it was parsed from, see `ParseConfig::preserve_literal_text`.
+    /// The constant is boxed, such that the text does not increase the size of other operators.
+    PreservedConst {
+        constant: Box<PreservedConstant>,
+    },
+    VariableIdentifier {
+        identifier: Arc<str>,
+    },
+    FunctionIdentifier {
+        identifier: Arc<str>,
+    },
+    /// A call of a function with named arguments, like `round_to(x, places = 2)`.
+    /// Its only child evaluates to the tuple of the positional arguments followed by the values of the named arguments.
+    /// The call is boxed, such that the names do not increase the size of other operators.
+    NamedFunctionIdentifier {
+        call: Box<NamedCall>,
+    },
+    /// A subexpression that occurs several times in the operator tree, see `Node::share_common_subexpressions`.
+    /// All occurrences have the same id, and the value of the first one that is evaluated is reused by the others.
+    Shared {
+        id: usize,
+    },
+}
+
+/// The identifier of a function that is called with named arguments, together with their names.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedCall {
+    pub identifier: Arc<str>,
+    /// The names of the trailing arguments of the call, in the order in which they are given.
+    pub argument_names: Vec<String>,
+}
+
+/// A constant together with the text of the literal it was parsed from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreservedConstant {
+    pub value: Value,
+    pub text: String,
 }
 
 impl Operator {
@@ -41,405 +107,247 @@ impl Operator {
         Operator::Const { value }
     }
 
-    pub(crate) fn variable_identifier(identifier: String) -> Self {
-        Operator::VariableIdentifier { identifier }
+    pub(crate) fn preserved_value(value: Value, text: String) -> Self {
+        Operator::PreservedConst {
+            constant: Box::new(PreservedConstant { value, text }),
+        }
     }
 
-    pub(crate) fn function_identifier(identifier: String) -> Self {
-        Operator::FunctionIdentifier { identifier }
+    /// Returns the value of this operator if it is a constant.
+    #[cfg(feature = "sql")]
+    pub(crate) fn constant_value(&self) -> Option<&Value> {
+        match self {
+            Operator::Const { value } => Some(value),
+            Operator::PreservedConst { constant } => Some(&constant.value),
+            _ => None,
+        }
     }
 
-    /// Returns the precedence of the operator.
-    /// A high precedence means that the operator has priority to be deeper in the tree.
-    // Make this a const fn once #57563 is resolved
-    pub(crate) fn precedence(&self) -> i32 {
-        use crate::operator::Operator::*;
-        match self {
-            RootNode => 200,
+    pub(crate) fn variable_identifier<S: Into<Arc<str>>>(identifier: S) -> Self {
+        Operator::VariableIdentifier {
+            identifier: identifier.into(),
+        }
+    }
 
-            Add | Sub => 95,
-            Neg => 110,
-            Mul | Div | Mod => 100,
-            Exp => 120,
+    pub(crate) fn function_identifier<S: Into<Arc<str>>>(identifier: S) -> Self {
+        Operator::FunctionIdentifier {
+            identifier: identifier.into(),
+        }
+    }
 
-            Eq | Neq | Gt | Lt | Geq | Leq => 80,
-            And => 75,
-            Or => 70,
-            Not => 110,
+    /// Returns the implementation of the operator in `BUILTIN_OPERATORS`, or `None` if the operator carries data, like constants, variables, function calls and shared subexpressions.
+    pub(crate) fn implementation(&self) -> Option<&'static dyn OperatorImpl> {
+        let kind = OperatorKind::from(self);
+        let implementation = BUILTIN_OPERATORS.get(kind as usize).copied();
+        debug_assert!(implementation.map_or(true, |implementation| implementation.kind() == kind));
+        implementation
+    }
 
-            Tuple => 40,
-            Assign => 50,
+    /// Returns the implementation of an operator that carries no data.
+    pub(crate) fn expect_implementation(&self) -> &'static dyn OperatorImpl {
+        self.implementation()
+            .unwrap_or_else(|| unreachable!("{:?} carries data", self))
+    }
 
-            Chain => 0,
+    /// Returns the identifier of the function that this operator calls, if it is a function call.
+    pub(crate) fn called_function(&self) -> Option<&str> {
+        match self {
+            Operator::FunctionIdentifier { identifier } => Some(identifier),
+            Operator::NamedFunctionIdentifier { call } => Some(&call.identifier),
+            _ => None,
+        }
+    }
 
-            Const { value: _ } => 200,
-            VariableIdentifier { identifier: _ } => 200,
-            FunctionIdentifier { identifier: _ } => 190,
+    /// Returns the precedence of the operator.
+    /// A high precedence means that the operator has priority to be deeper in the tree.
+    pub(crate) fn precedence(&self) -> i32 {
+        use crate::operator::Operator::*;
+        match self {
+            Const { value: _ } | PreservedConst { constant: _ } => 200,
+            VariableIdentifier { identifier: _ } | Shared { id: _ } => 200,
+            FunctionIdentifier { identifier: _ } | NamedFunctionIdentifier { call: _ } => 190,
+            operator => operator.expect_implementation().precedence(),
         }
     }
 
     /// Returns true if chains of operators with the same precedence as this one should be evaluated left-to-right,
     /// and false if they should be evaluated right-to-left.
     /// Left-to-right chaining has priority if operators with different order but same precedence are chained.
-    // Make this a const fn once #57563 is resolved
     pub(crate) fn is_left_to_right(&self) -> bool {
-        use crate::operator::Operator::*;
         match self {
-            Assign => false,
-            FunctionIdentifier { identifier: _ } => false,
-            _ => true,
+            Operator::FunctionIdentifier { identifier: _ }
+            | Operator::NamedFunctionIdentifier { call: _ } => false,
+            operator => operator
+                .implementation()
+                .map_or(true, |implementation| implementation.is_left_to_right()),
         }
     }
 
     /// Returns true if chains of this operator should be flattened into one operator with many arguments.
-    // Make this a const fn once #57563 is resolved
     pub(crate) fn is_sequence(&self) -> bool {
-        use crate::operator::Operator::*;
-        match self {
-            Tuple | Chain => true,
-            _ => false,
-        }
+        self.max_argument_amount().is_none()
+    }
+
+    /// Returns true if this operator compares its arguments, like `==` or `<`.
+    pub(crate) fn is_comparison(&self) -> bool {
+        self.implementation()
+            .is_some_and(|implementation| implementation.is_comparison())
+    }
+
+    /// Returns false if evaluating this operator has side effects, like assignments.
+    /// Whether variables and functions are pure depends on the context, see `Node::is_pure_in`.
+    pub(crate) fn is_pure(&self) -> bool {
+        self.implementation()
+            .map_or(true, |implementation| implementation.is_pure())
     }
 
     /// True if this operator is a leaf, meaning it accepts no arguments.
-    // Make this a const fn once #57563 is resolved
     pub(crate) fn is_leaf(&self) -> bool {
         self.max_argument_amount() == Some(0)
     }
 
     /// Returns the maximum amount of arguments required by this operator.
-    // Make this a const fn once #57563 is resolved
     pub(crate) fn max_argument_amount(&self) -> Option<usize> {
         use crate::operator::Operator::*;
         match self {
-            Add | Sub | Mul | Div | Mod | Exp | Eq | Neq | Gt | Lt | Geq | Leq | And | Or
-            | Assign => Some(2),
-            Tuple | Chain => None,
-            Not | Neg | RootNode => Some(1),
-            Const { value: _ } => Some(0),
+            Const { value: _ } | PreservedConst { constant: _ } => Some(0),
             VariableIdentifier { identifier: _ } => Some(0),
-            FunctionIdentifier { identifier: _ } => Some(1),
+            FunctionIdentifier { identifier: _ } | Shared { id: _ } => Some(1),
+            NamedFunctionIdentifier { call: _ } => Some(1),
+            operator => operator.expect_implementation().arity(),
         }
     }
 
-    /// Evaluates the operator with the given arguments and context.
-    pub(crate) fn eval(&self, arguments: &[Value], context: &dyn Context) -> EvalexprResult<Value> {
-        use crate::operator::Operator::*;
-        match self {
-            RootNode => {
-                if let Some(first) = arguments.first() {
-                    Ok(first.clone())
-                } else {
-                    Ok(Value::Empty)
-                }
-            },
-            Add => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number_or_string(&arguments[0])?;
-                expect_number_or_string(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string()) {
-                    let mut result = String::with_capacity(a.len() + b.len());
-                    result.push_str(&a);
-                    result.push_str(&b);
-                    Ok(Value::String(result))
-                } else if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    let result = a.checked_add(b);
-                    if let Some(result) = result {
-                        Ok(Value::Int(result))
-                    } else {
-                        Err(EvalexprError::addition_error(
-                            arguments[0].clone(),
-                            arguments[1].clone(),
-                        ))
-                    }
-                } else {
-                    Ok(Value::Float(
-                        arguments[0].as_number().unwrap() + arguments[1].as_number().unwrap(),
-                    ))
-                }
-            },
-            Sub => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    let result = a.checked_sub(b);
-                    if let Some(result) = result {
-                        Ok(Value::Int(result))
-                    } else {
-                        Err(EvalexprError::subtraction_error(
-                            arguments[0].clone(),
-                            arguments[1].clone(),
-                        ))
-                    }
-                } else {
-                    Ok(Value::Float(
-                        arguments[0].as_number().unwrap() - arguments[1].as_number().unwrap(),
-                    ))
-                }
-            },
-            Neg => {
-                expect_operator_argument_amount(arguments.len(), 1)?;
-                expect_number(&arguments[0])?;
-
-                if let Ok(a) = arguments[0].as_int() {
-                    let result = a.checked_neg();
-                    if let Some(result) = result {
-                        Ok(Value::Int(result))
-                    } else {
-                        Err(EvalexprError::negation_error(arguments[0].clone()))
-                    }
-                } else {
-                    Ok(Value::Float(-arguments[0].as_number().unwrap()))
-                }
-            },
-            Mul => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    let result = a.checked_mul(b);
-                    if let Some(result) = result {
-                        Ok(Value::Int(result))
-                    } else {
-                        Err(EvalexprError::multiplication_error(
-                            arguments[0].clone(),
-                            arguments[1].clone(),
-                        ))
-                    }
-                } else {
-                    Ok(Value::Float(
-                        arguments[0].as_number().unwrap() * arguments[1].as_number().unwrap(),
-                    ))
-                }
-            },
-            Div => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    let result = a.checked_div(b);
-                    if let Some(result) = result {
-                        Ok(Value::Int(result))
-                    } else {
-                        Err(EvalexprError::division_error(
-                            arguments[0].clone(),
-                            arguments[1].clone(),
-                        ))
-                    }
-                } else {
-                    Ok(Value::Float(
-                        arguments[0].as_number().unwrap() / arguments[1].as_number().unwrap(),
-                    ))
-                }
-            },
-            Mod => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    let result = a.checked_rem(b);
-                    if let Some(result) = result {
-                        Ok(Value::Int(result))
-                    } else {
-                        Err(EvalexprError::modulation_error(
-                            arguments[0].clone(),
-                            arguments[1].clone(),
-                        ))
-                    }
-                } else {
-                    Ok(Value::Float(
-                        arguments[0].as_number().unwrap() % arguments[1].as_number().unwrap(),
-                    ))
-                }
-            },
-            Exp => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
-
-                Ok(Value::Float(
-                    arguments[0]
-                        .as_number()
-                        .unwrap()
-                        .powf(arguments[1].as_number().unwrap()),
-                ))
-            },
-            Eq => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
+    /// Returns the result of the operator if it is already determined by the given leading arguments.
+    /// In this case, the remaining arguments must not be evaluated.
+    ///
+    /// The logical and, or and implies operators are short-circuiting, i.e. they do not evaluate their second argument if the first one decides the result.
+    /// The coalescing operator does not evaluate its second argument if the first one is evaluated successfully.
+    pub(crate) fn short_circuit(&self, arguments: &[Value]) -> EvalexprResult<Option<Value>> {
+        match self.implementation() {
+            Some(implementation) => implementation.short_circuit(arguments),
+            None => Ok(None),
+        }
+    }
 
-                if arguments[0] == arguments[1] {
-                    Ok(Value::Boolean(true))
-                } else {
-                    Ok(Value::Boolean(false))
-                }
-            },
-            Neq => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
+    /// Returns the value that replaces the next argument of the operator if evaluating it failed with the given error, given the leading arguments that are evaluated already.
+    /// The next argument is given by its operator, after stripping root nodes like parentheses.
+    /// Returns the error if the operator does not recover from it.
+    ///
+    /// The coalescing operator recovers if its first argument is a variable that is not found, and then evaluates its second argument.
+    /// Errors from within other expressions, like `f(a)` or `a + 1`, are not recovered from, even if they failed because a variable is not found.
+    // This is not inlined, such that it does not increase the stack frames of the recursive evaluation.
+    #[inline(never)]
+    pub(crate) fn recover_argument(
+        &self,
+        arguments: &[Value],
+        argument: &Operator,
+        error: EvalexprError,
+    ) -> EvalexprResult<Value> {
+        match self.implementation() {
+            Some(implementation) => implementation.recover_argument(arguments, argument, error),
+            None => Err(error),
+        }
+    }
 
-                if arguments[0] != arguments[1] {
-                    Ok(Value::Boolean(true))
-                } else {
-                    Ok(Value::Boolean(false))
-                }
-            },
-            Gt => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number_or_string(&arguments[0])?;
-                expect_number_or_string(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string()) {
-                    if a > b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    if a > b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else {
-                    if arguments[0].as_number().unwrap() > arguments[1].as_number().unwrap() {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                }
-            },
-            Lt => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number_or_string(&arguments[0])?;
-                expect_number_or_string(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string()) {
-                    if a < b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    if a < b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else {
-                    if arguments[0].as_number().unwrap() < arguments[1].as_number().unwrap() {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                }
-            },
-            Geq => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number_or_string(&arguments[0])?;
-                expect_number_or_string(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string()) {
-                    if a >= b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    if a >= b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else {
-                    if arguments[0].as_number().unwrap() >= arguments[1].as_number().unwrap() {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                }
+    /// Pushes the result of evaluating the next argument of the operator to the arguments, or the value that replaces it if the operator recovers from its error.
+    /// The argument is given by its operator, see `Operator::recover_argument`.
+    /// Returns the result of the operator if it is short-circuited by the pushed argument, see `Operator::short_circuit`.
+    /// An argument that replaces an error does not short-circuit the operator.
+    // This is not inlined, such that it does not increase the stack frames of the recursive evaluation.
+    #[inline(never)]
+    pub(crate) fn push_argument(
+        &self,
+        arguments: &mut Vec<Value>,
+        result: EvalexprResult<Value>,
+        argument: &Operator,
+    ) -> EvalexprResult<Option<Value>> {
+        match result {
+            Ok(argument) => {
+                arguments.push(argument);
+                self.short_circuit(arguments)
             },
-            Leq => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                expect_number_or_string(&arguments[0])?;
-                expect_number_or_string(&arguments[1])?;
-
-                if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string()) {
-                    if a <= b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
-                    if a <= b {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                } else {
-                    if arguments[0].as_number().unwrap() <= arguments[1].as_number().unwrap() {
-                        Ok(Value::Boolean(true))
-                    } else {
-                        Ok(Value::Boolean(false))
-                    }
-                }
+            Err(error) => {
+                let argument = self.recover_argument(arguments, argument, error)?;
+                arguments.push(argument);
+                Ok(None)
             },
-            And => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                let a = expect_boolean(&arguments[0])?;
-                let b = expect_boolean(&arguments[1])?;
-
-                if a && b {
-                    Ok(Value::Boolean(true))
-                } else {
-                    Ok(Value::Boolean(false))
-                }
-            },
-            Or => {
-                expect_operator_argument_amount(arguments.len(), 2)?;
-                let a = expect_boolean(&arguments[0])?;
-                let b = expect_boolean(&arguments[1])?;
+        }
+    }
 
-                if a || b {
-                    Ok(Value::Boolean(true))
-                } else {
-                    Ok(Value::Boolean(false))
-                }
-            },
-            Not => {
-                expect_operator_argument_amount(arguments.len(), 1)?;
-                let a = expect_boolean(&arguments[0])?;
+    /// Returns true if the next argument of the operator must not be evaluated, given the leading arguments that are evaluated already.
+    /// In this case, `Value::Empty` is pushed to the arguments in place of the skipped one.
+    ///
+    /// The conditional operator only evaluates the branch that its condition selects.
+    /// If the condition is not a boolean, both branches are skipped, and the operator fails when it is evaluated.
+    // This is not inlined, such that it does not increase the stack frames of the recursive evaluation.
+    #[inline(never)]
+    pub(crate) fn skip_argument(&self, arguments: &mut Vec<Value>) -> bool {
+        let skip = self
+            .implementation()
+            .is_some_and(|implementation| implementation.skip_argument(arguments));
+        if skip {
+            arguments.push(Value::Empty);
+        }
+        skip
+    }
 
-                if !a {
-                    Ok(Value::Boolean(true))
-                } else {
-                    Ok(Value::Boolean(false))
-                }
+    /// Returns the amount of bytes that evaluating the operator with the given arguments allocates for strings and tuples, if it is known in advance.
+    /// Returns `None` if the amount can only be determined from the result, like for function calls.
+    pub(crate) fn allocation_hint(
+        &self,
+        arguments: &[Value],
+        context: &dyn Context,
+    ) -> Option<usize> {
+        use crate::operator::Operator::*;
+        match (self, arguments) {
+            (Const { value }, _) => Some(value.allocated_bytes()),
+            (PreservedConst { constant }, _) => Some(constant.value.allocated_bytes()),
+            (VariableIdentifier { identifier }, _) => Some(
+                context
+                    .get_value(identifier)
+                    .map(Value::allocated_bytes)
+                    .unwrap_or(0),
+            ),
+            #[cfg(feature = "builtin_tuple")]
+            (FunctionIdentifier { identifier }, [argument])
+                if &**identifier == "range" && context.get_function(identifier).is_none() =>
+            {
+                range_allocation_hint(argument)
             },
-            Tuple => Ok(Value::Tuple(arguments.into())),
-            Assign => Err(EvalexprError::ContextNotManipulable),
-            Chain => {
-                if arguments.is_empty() {
-                    return Err(EvalexprError::wrong_operator_argument_amount(0, 1));
-                }
+            (FunctionIdentifier { .. }, _) | (NamedFunctionIdentifier { .. }, _) => None,
+            (Shared { .. }, _) => Some(0),
+            (operator, arguments) => operator.expect_implementation().allocation_hint(arguments),
+        }
+    }
 
-                Ok(arguments.last().cloned().unwrap_or(Value::Empty))
-            },
+    /// Evaluates the operator with the given arguments and context.
+    /// The configuration limits the length of ranges.
+    pub(crate) fn eval(
+        &self,
+        arguments: &[Value],
+        context: &dyn Context,
+        config: &EvalConfig,
+    ) -> EvalexprResult<Value> {
+        use crate::operator::Operator::*;
+        match self {
+            Shared { .. } => Ok(arguments.first().cloned().unwrap_or(Value::Empty)),
             Const { value } => {
                 expect_operator_argument_amount(arguments.len(), 0)?;
 
                 Ok(value.clone())
             },
+            PreservedConst { constant } => {
+                expect_operator_argument_amount(arguments.len(), 0)?;
+
+                Ok(constant.value.clone())
+            },
             VariableIdentifier { identifier } => {
-                if let Some(value) = context.get_value(&identifier).cloned() {
+                if let Some(value) = context.get_value(identifier).cloned() {
                     Ok(value)
                 } else {
-                    Err(EvalexprError::VariableIdentifierNotFound(
-                        identifier.clone(),
+                    Err(EvalexprError::variable_identifier_not_found(
+                        identifier.to_string(),
                     ))
                 }
             },
@@ -447,16 +355,42 @@ impl Operator {
                 expect_operator_argument_amount(arguments.len(), 1)?;
                 let arguments = &arguments[0];
 
-                if let Some(function) = context.get_function(&identifier) {
-                    function.call(arguments)
-                } else if let Some(builtin_function) = builtin_function(&identifier) {
-                    builtin_function.call(arguments)
+                if let Some(function) = context.get_function(identifier) {
+                    call_function(identifier, function, arguments)
+                } else if let Some(builtin_function) = builtin_function(identifier) {
+                    feature_tracing::instrument_call(identifier, true, || {
+                        builtin_function.call(arguments)
+                    })
+                } else {
+                    Err(EvalexprError::FunctionIdentifierNotFound(
+                        identifier.to_string(),
+                    ))
+                }
+            },
+            NamedFunctionIdentifier { call } => {
+                expect_operator_argument_amount(arguments.len(), 1)?;
+                let identifier = &call.identifier;
+                let arguments = expect_tuple(&arguments[0])?;
+
+                if let Some(function) = context.get_function(identifier) {
+                    let arguments = function.order_named_arguments(
+                        identifier,
+                        arguments,
+                        &call.argument_names,
+                    )?;
+                    call_function(identifier, function, &arguments)
+                } else if builtin_function(identifier).is_some() {
+                    Err(EvalexprError::UnknownNamedArgument {
+                        function: identifier.to_string(),
+                        argument: call.argument_names[0].clone(),
+                    })
                 } else {
                     Err(EvalexprError::FunctionIdentifierNotFound(
-                        identifier.clone(),
+                        identifier.to_string(),
                     ))
                 }
             },
+            operator => operator.expect_implementation().eval(arguments, config),
         }
     }
 
@@ -465,17 +399,71 @@ impl Operator {
         &self,
         arguments: &[Value],
         context: &mut dyn Context,
+        config: &EvalConfig,
     ) -> EvalexprResult<Value> {
         use crate::operator::Operator::*;
         match self {
             Assign => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
-                let target = expect_string(&arguments[0])?;
-                context.set_value(target.into(), arguments[1].clone())?;
+                expect_assignable(&arguments[0], &arguments[1])?;
+                assign(&arguments[0], &arguments[1], context)?;
 
                 Ok(Value::Empty)
             },
-            _ => self.eval(arguments, context),
+            _ => self.eval(arguments, context, config),
         }
     }
 }
+
+/// Calls the given function from the context with the given argument, wrapping its errors in `EvalexprError::InFunctionCall`.
+fn call_function(identifier: &str, function: &Function, argument: &Value) -> EvalexprResult<Value> {
+    feature_tracing::instrument_call(identifier, false, || {
+        function
+            .call(argument)
+            .map_err(|error| EvalexprError::in_function_call(identifier, argument, error))
+    })
+}
+
+/// Returns the identifiers of the variables of the given assignment target, which is the identifier of a variable or a tuple of targets, like the one of `(a, (b, c)) = t`.
+pub(crate) fn assignment_target_identifiers(target: &Value) -> Vec<&str> {
+    match target {
+        Value::String(identifier) => vec![identifier.as_str()],
+        Value::Tuple(targets) => targets
+            .iter()
+            .flat_map(assignment_target_identifiers)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns `Ok(())` if the given value can be assigned to the given assignment target.
+/// A tuple of targets needs a tuple value with the same amount of elements, such that no variable is assigned if any part of the value does not fit.
+fn expect_assignable(target: &Value, value: &Value) -> EvalexprResult<()> {
+    match target {
+        Value::Tuple(targets) => {
+            let values = expect_tuple(value)?;
+            if values.len() != targets.len() {
+                return Err(EvalexprError::DestructuringLengthMismatch {
+                    expected: targets.len(),
+                    actual: values.len(),
+                });
+            }
+            targets
+                .iter()
+                .zip(values)
+                .try_for_each(|(target, value)| expect_assignable(target, value))
+        },
+        target => expect_string(target).map(|_| ()),
+    }
+}
+
+/// Assigns the given value to the variables of the given assignment target in order, after `expect_assignable` accepted it.
+fn assign(target: &Value, value: &Value, context: &mut dyn Context) -> EvalexprResult<()> {
+    match (target, value) {
+        (Value::Tuple(targets), Value::Tuple(values)) => targets
+            .iter()
+            .zip(values)
+            .try_for_each(|(target, value)| assign(target, value, context)),
+        (target, value) => context.set_value(expect_string(target)?.into(), value.clone()),
+    }
+}
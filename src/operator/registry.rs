@@ -0,0 +1,1103 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::mem;
+
+use config::EvalConfig;
+use error::*;
+#[cfg(feature = "builtin_regex")]
+use function::regex_match::regex_matches;
+use operator::{Operator, OperatorKind};
+use typecheck::{
+    TypingRule, ADDITION_RULES, ARITHMETIC_RULES, BITWISE_RULES, EQUALITY_RULES, LOGIC_RULES,
+    NEGATION_RULES, NOT_RULES, ORDERING_RULES, RANGE_RULES, REGEX_MATCH_RULES,
+};
+use value::{tuple_allocated_bytes, IntType, TupleType, Value};
+
+/// The behaviour of an operator that carries no data, like `+` or `&&`.
+///
+/// The parser, the evaluators, `Display`, `Node::kind` and the type inference look up operators in `BUILTIN_OPERATORS` through `Operator::implementation`.
+/// Constants, variables, function calls and shared subexpressions carry data and depend on the context, so `Operator` handles them itself.
+pub(crate) trait OperatorImpl: Sync {
+    /// Returns the kind of the operator, which is its index in `BUILTIN_OPERATORS`.
+    fn kind(&self) -> OperatorKind;
+
+    /// Returns the symbol of the operator as written by `Display`, like `&&` or `, `.
+    fn symbol(&self) -> &'static str;
+
+    /// Returns the precedence of the operator.
+    /// A high precedence means that the operator has priority to be deeper in the tree.
+    fn precedence(&self) -> i32;
+
+    /// Returns the amount of arguments of the operator, or `None` if chains of it are flattened into one operator with any amount of arguments.
+    fn arity(&self) -> Option<usize>;
+
+    /// Returns true if chains of operators with the same precedence as this one should be evaluated left-to-right,
+    /// and false if they should be evaluated right-to-left.
+    fn is_left_to_right(&self) -> bool {
+        true
+    }
+
+    /// Returns true if this operator compares its arguments, like `==` or `<`.
+    fn is_comparison(&self) -> bool {
+        false
+    }
+
+    /// Returns false if evaluating the operator has side effects, such that subexpressions containing it must not be shared or cached.
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    /// Returns the argument types that the operator accepts together with the type of its result, or `None` if the type of its result is derived from the types of its arguments.
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        None
+    }
+
+    /// Returns the amount of bytes that evaluating the operator with the given arguments allocates for strings and tuples.
+    fn allocation_hint(&self, _arguments: &[Value]) -> Option<usize> {
+        Some(0)
+    }
+
+    /// Returns the result of the operator if it is already determined by the given leading arguments, see `Operator::short_circuit`.
+    fn short_circuit(&self, _arguments: &[Value]) -> EvalexprResult<Option<Value>> {
+        Ok(None)
+    }
+
+    /// Returns true if the next argument must not be evaluated, given the leading arguments, see `Operator::skip_argument`.
+    fn skip_argument(&self, _arguments: &[Value]) -> bool {
+        false
+    }
+
+    /// Returns the value that replaces the next argument if evaluating it failed with the given error, see `Operator::recover_argument`.
+    fn recover_argument(
+        &self,
+        _arguments: &[Value],
+        _argument: &Operator,
+        error: EvalexprError,
+    ) -> EvalexprResult<Value> {
+        Err(error)
+    }
+
+    /// Evaluates the operator with the given arguments.
+    /// The configuration limits the length of ranges.
+    fn eval(&self, arguments: &[Value], config: &EvalConfig) -> EvalexprResult<Value>;
+}
+
+/// The operators that carry no data, in the order of their kinds in `OperatorKind`.
+pub(crate) static BUILTIN_OPERATORS: &[&dyn OperatorImpl] = &[
+    &Group,
+    &Addition,
+    &Arithmetic {
+        kind: OperatorKind::Sub,
+        symbol: "-",
+        precedence: 95,
+    },
+    &Negation,
+    &UnaryPlus,
+    &Arithmetic {
+        kind: OperatorKind::Mul,
+        symbol: "*",
+        precedence: 100,
+    },
+    &Arithmetic {
+        kind: OperatorKind::Div,
+        symbol: "/",
+        precedence: 100,
+    },
+    &Arithmetic {
+        kind: OperatorKind::Mod,
+        symbol: "%",
+        precedence: 100,
+    },
+    &Arithmetic {
+        kind: OperatorKind::Exp,
+        symbol: "^",
+        precedence: 120,
+    },
+    &Equality { negated: false },
+    &Equality { negated: true },
+    &Comparison {
+        kind: OperatorKind::Gt,
+        symbol: ">",
+        accepted: &[Ordering::Greater],
+    },
+    &Comparison {
+        kind: OperatorKind::Lt,
+        symbol: "<",
+        accepted: &[Ordering::Less],
+    },
+    &Comparison {
+        kind: OperatorKind::Geq,
+        symbol: ">=",
+        accepted: &[Ordering::Greater, Ordering::Equal],
+    },
+    &Comparison {
+        kind: OperatorKind::Leq,
+        symbol: "<=",
+        accepted: &[Ordering::Less, Ordering::Equal],
+    },
+    &RegexMatch,
+    &Logic {
+        kind: OperatorKind::And,
+        symbol: "&&",
+        precedence: 75,
+        deciding_argument: false,
+        apply: |a, b| a && b,
+    },
+    &Logic {
+        kind: OperatorKind::Or,
+        symbol: "||",
+        precedence: 70,
+        deciding_argument: true,
+        apply: |a, b| a || b,
+    },
+    &ExclusiveOr,
+    &LogicalNot,
+    &Logic {
+        kind: OperatorKind::Implies,
+        symbol: "implies",
+        precedence: 65,
+        deciding_argument: false,
+        apply: |a, b| !a || b,
+    },
+    &Bitwise {
+        kind: OperatorKind::BitAnd,
+        symbol: "&",
+        precedence: 78,
+        apply: |a, b| a & b,
+    },
+    &Bitwise {
+        kind: OperatorKind::BitOr,
+        symbol: "|",
+        precedence: 76,
+        apply: |a, b| a | b,
+    },
+    &Bitwise {
+        kind: OperatorKind::BitXor,
+        symbol: "bitxor",
+        precedence: 77,
+        apply: |a, b| a ^ b,
+    },
+    &Shift {
+        kind: OperatorKind::Shl,
+        symbol: "<<",
+        apply: IntType::checked_shl,
+    },
+    &Shift {
+        kind: OperatorKind::Shr,
+        symbol: ">>",
+        apply: IntType::checked_shr,
+    },
+    &Range { inclusive: false },
+    &Range { inclusive: true },
+    &Conditional,
+    &Coalesce,
+    &Tuple,
+    &Assignment,
+    &Chain,
+];
+
+/// A root node, which groups its child like parentheses.
+struct Group;
+
+impl OperatorImpl for Group {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Root
+    }
+
+    fn symbol(&self) -> &'static str {
+        ""
+    }
+
+    fn precedence(&self) -> i32 {
+        200
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        Ok(arguments.first().cloned().unwrap_or(Value::Empty))
+    }
+}
+
+/// The binary `+`, which adds numbers and concatenates strings and tuples.
+struct Addition;
+
+impl OperatorImpl for Addition {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Add
+    }
+
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn precedence(&self) -> i32 {
+        95
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(ADDITION_RULES)
+    }
+
+    fn allocation_hint(&self, arguments: &[Value]) -> Option<usize> {
+        match arguments {
+            [Value::String(a), Value::String(b)] => Some(a.len().saturating_add(b.len())),
+            [Value::Tuple(a), Value::Tuple(b)] => {
+                Some(tuple_allocated_bytes(a).saturating_add(tuple_allocated_bytes(b)))
+            },
+            _ => Some(0),
+        }
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        if let (Value::Tuple(a), Value::Tuple(b)) = (&arguments[0], &arguments[1]) {
+            let mut result = TupleType::with_capacity(a.len() + b.len());
+            result.extend_from_slice(a);
+            result.extend_from_slice(b);
+            return Ok(Value::Tuple(result));
+        } else if arguments[0].is_tuple() || arguments[1].is_tuple() {
+            return Err(EvalexprError::addition_error(
+                arguments[0].clone(),
+                arguments[1].clone(),
+            ));
+        }
+
+        expect_number_or_string(&arguments[0])?;
+        expect_number_or_string(&arguments[1])?;
+
+        if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string()) {
+            let mut result = String::with_capacity(a.len() + b.len());
+            result.push_str(&a);
+            result.push_str(&b);
+            Ok(Value::String(result))
+        } else if arguments[0].is_string() || arguments[1].is_string() {
+            Err(EvalexprError::addition_error(
+                arguments[0].clone(),
+                arguments[1].clone(),
+            ))
+        } else {
+            eval_arithmetic(OperatorKind::Add, &arguments[0], &arguments[1])
+        }
+    }
+}
+
+/// One of the arithmetic operators `-`, `*`, `/`, `%` and `^` on numbers.
+struct Arithmetic {
+    kind: OperatorKind,
+    symbol: &'static str,
+    precedence: i32,
+}
+
+impl OperatorImpl for Arithmetic {
+    fn kind(&self) -> OperatorKind {
+        self.kind
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn precedence(&self) -> i32 {
+        self.precedence
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn is_left_to_right(&self) -> bool {
+        self.kind != OperatorKind::Exp
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(ARITHMETIC_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        eval_arithmetic(self.kind, &arguments[0], &arguments[1])
+    }
+}
+
+/// The unary `-`.
+struct Negation;
+
+impl OperatorImpl for Negation {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Neg
+    }
+
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn precedence(&self) -> i32 {
+        110
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(NEGATION_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 1)?;
+        expect_number(&arguments[0])?;
+
+        if let Ok(a) = arguments[0].as_int() {
+            let result = a.checked_neg();
+            if let Some(result) = result {
+                Ok(Value::Int(result))
+            } else {
+                Err(EvalexprError::negation_error(arguments[0].clone()))
+            }
+        } else {
+            Ok(Value::Float(-arguments[0].as_number().unwrap()))
+        }
+    }
+}
+
+/// The unary `+`, which returns numbers unchanged.
+struct UnaryPlus;
+
+impl OperatorImpl for UnaryPlus {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Pos
+    }
+
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn precedence(&self) -> i32 {
+        110
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(NEGATION_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 1)?;
+        expect_number(&arguments[0])?;
+
+        Ok(arguments[0].clone())
+    }
+}
+
+/// The operators `==` and `!=`, which compare values of any type.
+struct Equality {
+    negated: bool,
+}
+
+impl OperatorImpl for Equality {
+    fn kind(&self) -> OperatorKind {
+        if self.negated {
+            OperatorKind::Neq
+        } else {
+            OperatorKind::Eq
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        if self.negated {
+            "!="
+        } else {
+            "=="
+        }
+    }
+
+    fn precedence(&self) -> i32 {
+        80
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn is_comparison(&self) -> bool {
+        true
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(EQUALITY_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+
+        Ok(Value::Boolean(
+            (arguments[0] == arguments[1]) != self.negated,
+        ))
+    }
+}
+
+/// One of the operators `>`, `<`, `>=` and `<=`, which order two numbers or two strings.
+struct Comparison {
+    kind: OperatorKind,
+    symbol: &'static str,
+    /// The orderings of the first argument relative to the second one for which the comparison is true.
+    accepted: &'static [Ordering],
+}
+
+impl OperatorImpl for Comparison {
+    fn kind(&self) -> OperatorKind {
+        self.kind
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn precedence(&self) -> i32 {
+        80
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn is_comparison(&self) -> bool {
+        true
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(ORDERING_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        expect_number_or_string(&arguments[0])?;
+        expect_number_or_string(&arguments[1])?;
+        expect_comparable(&arguments[0], &arguments[1])?;
+
+        // Floats are unordered if one of them is NaN, which makes all comparisons false.
+        let ordering = if let (Ok(a), Ok(b)) = (arguments[0].as_string(), arguments[1].as_string())
+        {
+            Some(a.cmp(&b))
+        } else if let (Ok(a), Ok(b)) = (arguments[0].as_int(), arguments[1].as_int()) {
+            Some(a.cmp(&b))
+        } else {
+            arguments[0]
+                .as_number()
+                .unwrap()
+                .partial_cmp(&arguments[1].as_number().unwrap())
+        };
+        Ok(Value::Boolean(
+            ordering.is_some_and(|ordering| self.accepted.contains(&ordering)),
+        ))
+    }
+}
+
+/// The operator `=~`, which matches a string against a regex like the builtin function `str::regex_matches`.
+/// Without the `builtin_regex` feature, it fails with `EvalexprError::OperatorNeedsFeature`.
+struct RegexMatch;
+
+impl OperatorImpl for RegexMatch {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::RegexMatch
+    }
+
+    fn symbol(&self) -> &'static str {
+        "=~"
+    }
+
+    fn precedence(&self) -> i32 {
+        80
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn is_comparison(&self) -> bool {
+        true
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(REGEX_MATCH_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+
+        #[cfg(feature = "builtin_regex")]
+        return regex_matches(&arguments[0], &arguments[1]);
+        #[cfg(not(feature = "builtin_regex"))]
+        return Err(EvalexprError::OperatorNeedsFeature {
+            operator: "=~".to_string(),
+            feature: "builtin_regex".to_string(),
+        });
+    }
+}
+
+/// One of the short-circuiting operators `&&`, `||` and `implies` on booleans.
+struct Logic {
+    kind: OperatorKind,
+    symbol: &'static str,
+    precedence: i32,
+    /// The value of the first argument that decides the result, such that the second argument is not evaluated.
+    deciding_argument: bool,
+    apply: fn(bool, bool) -> bool,
+}
+
+impl OperatorImpl for Logic {
+    fn kind(&self) -> OperatorKind {
+        self.kind
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn precedence(&self) -> i32 {
+        self.precedence
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn is_left_to_right(&self) -> bool {
+        self.kind != OperatorKind::Implies
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(LOGIC_RULES)
+    }
+
+    fn short_circuit(&self, arguments: &[Value]) -> EvalexprResult<Option<Value>> {
+        match arguments {
+            [first] if expect_boolean(first)? == self.deciding_argument => {
+                Ok(Some(Value::Boolean((self.apply)(
+                    self.deciding_argument,
+                    !self.deciding_argument,
+                ))))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        let a = expect_boolean(&arguments[0])?;
+        let b = expect_boolean(&arguments[1])?;
+
+        Ok(Value::Boolean((self.apply)(a, b)))
+    }
+}
+
+/// The operator `!` on booleans.
+struct LogicalNot;
+
+impl OperatorImpl for LogicalNot {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Not
+    }
+
+    fn symbol(&self) -> &'static str {
+        "!"
+    }
+
+    fn precedence(&self) -> i32 {
+        110
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(NOT_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 1)?;
+        let a = expect_boolean(&arguments[0])?;
+
+        Ok(Value::Boolean(!a))
+    }
+}
+
+/// The operator `xor` on booleans.
+/// Unlike `&&` and `||`, it always needs both arguments, so it does not short-circuit.
+struct ExclusiveOr;
+
+impl OperatorImpl for ExclusiveOr {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Xor
+    }
+
+    fn symbol(&self) -> &'static str {
+        "xor"
+    }
+
+    fn precedence(&self) -> i32 {
+        70
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(LOGIC_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        let a = expect_boolean(&arguments[0])?;
+        let b = expect_boolean(&arguments[1])?;
+
+        Ok(Value::Boolean(a != b))
+    }
+}
+
+/// One of the bitwise operators `&`, `|` and `bitxor` on integers.
+struct Bitwise {
+    kind: OperatorKind,
+    symbol: &'static str,
+    precedence: i32,
+    apply: fn(IntType, IntType) -> IntType,
+}
+
+impl OperatorImpl for Bitwise {
+    fn kind(&self) -> OperatorKind {
+        self.kind
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn precedence(&self) -> i32 {
+        self.precedence
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(BITWISE_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        let a = arguments[0].as_int()?;
+        let b = arguments[1].as_int()?;
+
+        Ok(Value::Int((self.apply)(a, b)))
+    }
+}
+
+/// One of the shift operators `<<` and `>>` on integers.
+struct Shift {
+    kind: OperatorKind,
+    symbol: &'static str,
+    /// Shifts the integer by the amount of bits, or returns `None` if the amount is at least the bit width of `IntType`.
+    apply: fn(IntType, u32) -> Option<IntType>,
+}
+
+impl OperatorImpl for Shift {
+    fn kind(&self) -> OperatorKind {
+        self.kind
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn precedence(&self) -> i32 {
+        90
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(BITWISE_RULES)
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        let a = arguments[0].as_int()?;
+        let b = arguments[1].as_int()?;
+
+        u32::try_from(b)
+            .ok()
+            .and_then(|bits| (self.apply)(a, bits))
+            .map(Value::Int)
+            .ok_or(EvalexprError::shift_amount_out_of_range(a, b))
+    }
+}
+
+/// One of the range operators `..` and `..=` on integers.
+struct Range {
+    inclusive: bool,
+}
+
+impl OperatorImpl for Range {
+    fn kind(&self) -> OperatorKind {
+        if self.inclusive {
+            OperatorKind::RangeInclusive
+        } else {
+            OperatorKind::Range
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        if self.inclusive {
+            "..="
+        } else {
+            ".."
+        }
+    }
+
+    fn precedence(&self) -> i32 {
+        85
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn typing_rules(&self) -> Option<&'static [TypingRule]> {
+        Some(RANGE_RULES)
+    }
+
+    fn allocation_hint(&self, arguments: &[Value]) -> Option<usize> {
+        Some(match arguments {
+            [start, end] => match (start.as_int(), end.as_int()) {
+                (Ok(start), Ok(end)) => range_length(start, end, self.inclusive)
+                    .saturating_mul(mem::size_of::<Value>() as u128)
+                    .min(usize::MAX as u128) as usize,
+                _ => 0,
+            },
+            _ => 0,
+        })
+    }
+
+    fn eval(&self, arguments: &[Value], config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        let start = arguments[0].as_int()?;
+        let end = arguments[1].as_int()?;
+
+        let length = range_length(start, end, self.inclusive);
+        if length > config.max_range_length as u128 {
+            return Err(EvalexprError::range_too_long(
+                config.max_range_length,
+                length,
+            ));
+        }
+        Ok(Value::Tuple(
+            (0..length as i128)
+                .map(|index| Value::Int((start as i128 + index) as IntType))
+                .collect(),
+        ))
+    }
+}
+
+/// The conditional operator `c ? a : b`, which only evaluates the branch that its condition selects.
+struct Conditional;
+
+impl OperatorImpl for Conditional {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Conditional
+    }
+
+    fn symbol(&self) -> &'static str {
+        "?:"
+    }
+
+    fn precedence(&self) -> i32 {
+        60
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(3)
+    }
+
+    fn is_left_to_right(&self) -> bool {
+        false
+    }
+
+    fn skip_argument(&self, arguments: &[Value]) -> bool {
+        match arguments {
+            [condition] => condition != &Value::Boolean(true),
+            [condition, _] => condition != &Value::Boolean(false),
+            _ => false,
+        }
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 3)?;
+        if expect_boolean(&arguments[0])? {
+            Ok(arguments[1].clone())
+        } else {
+            Ok(arguments[2].clone())
+        }
+    }
+}
+
+/// The null-coalescing operator `a ?? b`, which only evaluates `b` if `a` is a variable that is not found.
+struct Coalesce;
+
+impl OperatorImpl for Coalesce {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Coalesce
+    }
+
+    fn symbol(&self) -> &'static str {
+        "??"
+    }
+
+    fn precedence(&self) -> i32 {
+        82
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    /// Chains like `a ?? b ?? 0` are right associative, such that each left-hand side is a variable that may be missing.
+    fn is_left_to_right(&self) -> bool {
+        false
+    }
+
+    fn short_circuit(&self, arguments: &[Value]) -> EvalexprResult<Option<Value>> {
+        match arguments {
+            [first] => Ok(Some(first.clone())),
+            _ => Ok(None),
+        }
+    }
+
+    fn recover_argument(
+        &self,
+        arguments: &[Value],
+        argument: &Operator,
+        error: EvalexprError,
+    ) -> EvalexprResult<Value> {
+        match (arguments, argument, &error) {
+            (
+                [],
+                Operator::VariableIdentifier { identifier },
+                EvalexprError::VariableIdentifierNotFound {
+                    identifier: missing,
+                    ..
+                },
+            ) if **identifier == **missing => Ok(Value::Empty),
+            _ => Err(error),
+        }
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        // The first argument is only evaluated up to here if it is a variable that is not found, see `Operator::recover_argument`.
+        expect_operator_argument_amount(arguments.len(), 2)?;
+        Ok(arguments[1].clone())
+    }
+}
+
+/// The operator `,`, which aggregates its arguments into a tuple.
+struct Tuple;
+
+impl OperatorImpl for Tuple {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Tuple
+    }
+
+    fn symbol(&self) -> &'static str {
+        ", "
+    }
+
+    fn precedence(&self) -> i32 {
+        40
+    }
+
+    fn arity(&self) -> Option<usize> {
+        None
+    }
+
+    fn allocation_hint(&self, arguments: &[Value]) -> Option<usize> {
+        Some(tuple_allocated_bytes(arguments))
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        Ok(Value::Tuple(arguments.into()))
+    }
+}
+
+/// The operator `=`, which assigns to variables.
+/// It needs a mutable context, so it is evaluated by `Operator::eval_mut`, and fails without one.
+struct Assignment;
+
+impl OperatorImpl for Assignment {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Assign
+    }
+
+    fn symbol(&self) -> &'static str {
+        " = "
+    }
+
+    fn precedence(&self) -> i32 {
+        50
+    }
+
+    fn arity(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn is_left_to_right(&self) -> bool {
+        false
+    }
+
+    fn is_pure(&self) -> bool {
+        false
+    }
+
+    fn allocation_hint(&self, arguments: &[Value]) -> Option<usize> {
+        match arguments {
+            [_, value] => Some(value.allocated_bytes()),
+            _ => Some(0),
+        }
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        expect_operator_argument_amount(arguments.len(), 2)?;
+
+        Err(EvalexprError::ContextNotManipulable)
+    }
+}
+
+/// The operator `;`, which evaluates to its last argument.
+struct Chain;
+
+impl OperatorImpl for Chain {
+    fn kind(&self) -> OperatorKind {
+        OperatorKind::Chain
+    }
+
+    fn symbol(&self) -> &'static str {
+        "; "
+    }
+
+    fn precedence(&self) -> i32 {
+        0
+    }
+
+    fn arity(&self) -> Option<usize> {
+        None
+    }
+
+    fn eval(&self, arguments: &[Value], _config: &EvalConfig) -> EvalexprResult<Value> {
+        if arguments.is_empty() {
+            return Err(EvalexprError::wrong_operator_argument_amount(0, 1));
+        }
+
+        Ok(arguments.last().cloned().unwrap_or(Value::Empty))
+    }
+}
+
+/// Returns the amount of integers from `start` up to `end`, including `end` if `inclusive` is true.
+/// A range whose end is before its start is empty.
+fn range_length(start: IntType, end: IntType, inclusive: bool) -> u128 {
+    let end = end as i128 + inclusive as i128;
+    (end - start as i128).max(0) as u128
+}
+
+/// Returns `Ok(())` if the given values can be compared with each other, i.e. if they are both strings or both not strings.
+/// Otherwise, returns an error that expects the second value to have the type of the first.
+fn expect_comparable(a: &Value, b: &Value) -> EvalexprResult<()> {
+    match (a, b) {
+        (Value::String(_), Value::String(_)) => Ok(()),
+        (Value::String(_), b) => Err(EvalexprError::expected_string(b.clone())),
+        (_, Value::String(_)) => Err(EvalexprError::expected_number(b.clone())),
+        _ => Ok(()),
+    }
+}
+
+/// Evaluates one of the arithmetic operators `+`, `-`, `*`, `/`, `%` and `^` on two numbers.
+///
+/// This is the only place that chooses between integer and float arithmetic, such that all arithmetic operators promote their operands the same way.
+/// If both operands are integers, the operation is computed with integers, and fails if the result is out of the range of `IntType`.
+/// If any operand is a float, both operands are converted to floats, regardless of their order.
+/// The only exception are integer powers with a negative exponent, which have no integer result, so they are computed with floats as well.
+fn eval_arithmetic(kind: OperatorKind, a: &Value, b: &Value) -> EvalexprResult<Value> {
+    expect_number(a)?;
+    expect_number(b)?;
+
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) if !(kind == OperatorKind::Exp && *y < 0) => {
+            let result = match kind {
+                OperatorKind::Add => x.checked_add(*y),
+                OperatorKind::Sub => x.checked_sub(*y),
+                OperatorKind::Mul => x.checked_mul(*y),
+                OperatorKind::Div => x.checked_div(*y),
+                OperatorKind::Mod => x.checked_rem(*y),
+                OperatorKind::Exp => {
+                    // Larger exponents only have a result for the bases 0, 1 and -1, which depends only on the parity of the exponent.
+                    let exponent = u32::try_from(*y).unwrap_or(u32::MAX - 1 + (*y % 2) as u32);
+                    x.checked_pow(exponent)
+                },
+                kind => unreachable!("{:?} is not an arithmetic operator", kind),
+            };
+            result.map(Value::Int).ok_or_else(|| {
+                let (a, b) = (a.clone(), b.clone());
+                match kind {
+                    OperatorKind::Add => EvalexprError::addition_error(a, b),
+                    OperatorKind::Sub => EvalexprError::subtraction_error(a, b),
+                    OperatorKind::Mul => EvalexprError::multiplication_error(a, b),
+                    OperatorKind::Div => EvalexprError::division_error(a, b),
+                    OperatorKind::Mod => EvalexprError::modulation_error(a, b),
+                    _ => EvalexprError::exponentiation_error(a, b),
+                }
+            })
+        },
+        _ => {
+            let (x, y) = (a.as_number()?, b.as_number()?);
+            Ok(Value::Float(match kind {
+                OperatorKind::Add => x + y,
+                OperatorKind::Sub => x - y,
+                OperatorKind::Mul => x * y,
+                OperatorKind::Div => x / y,
+                OperatorKind::Mod => x % y,
+                OperatorKind::Exp => x.powf(y),
+                kind => unreachable!("{:?} is not an arithmetic operator", kind),
+            }))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_operators_are_indexed_by_kind() {
+        for (index, implementation) in BUILTIN_OPERATORS.iter().enumerate() {
+            assert_eq!(
+                implementation.kind() as usize,
+                index,
+                "{:?}",
+                implementation.kind()
+            );
+        }
+        assert_eq!(BUILTIN_OPERATORS.len(), OperatorKind::Constant as usize);
+    }
+}
@@ -0,0 +1,550 @@
+//! Translation of expressions into SQL `WHERE` clauses, enabled by the `sql` feature flag.
+//!
+//! `to_sql` converts an operator tree into a condition that can be pushed down to a database, with all constants passed as bind parameters.
+//! Only a subset of expressions has an SQL equivalent.
+//! For other expressions, `to_sql` returns an `UnsupportedNode` error, and the expression can be evaluated in-process instead.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use evalexpr::*;
+//! use evalexpr::sql::{to_sql, SqlDialect};
+//!
+//! let tree = build_operator_tree("age >= 18 && str::starts_with(name, \"Jo\")").unwrap(); // Do proper error handling here
+//! let (clause, parameters) = to_sql(&tree, &SqlDialect::Postgres).unwrap(); // Do proper error handling here
+//! assert_eq!(clause, "(\"age\" >= $1) AND (\"name\" LIKE $2)");
+//! assert_eq!(parameters, vec![Value::from(18), Value::from("Jo%")]);
+//! ```
+
+use std::fmt;
+
+use operator::{Operator, OperatorKind};
+use value::Value;
+use Node;
+
+/// The SQL dialect to generate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// PostgreSQL, with numbered placeholders like `$1` and identifiers quoted with `"`.
+    Postgres,
+    /// MySQL, with `?` placeholders and identifiers quoted with `` ` ``.
+    MySql,
+}
+
+impl SqlDialect {
+    /// Returns the placeholder of the bind parameter with the given one-based index.
+    fn placeholder(self, index: usize) -> String {
+        match self {
+            SqlDialect::Postgres => format!("${}", index),
+            SqlDialect::MySql => "?".to_string(),
+        }
+    }
+
+    /// Quotes each dot-separated part of the identifier, so that `user.name` refers to the column `name` of the table `user`.
+    fn quote_identifier(self, identifier: &str) -> String {
+        let quote = match self {
+            SqlDialect::Postgres => "\"",
+            SqlDialect::MySql => "`",
+        };
+        identifier
+            .split('.')
+            .map(|part| {
+                let escaped = part.replace(quote, &format!("{}{}", quote, quote));
+                format!("{}{}{}", quote, escaped, quote)
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// A node of the operator tree that has no SQL equivalent, as returned by `to_sql`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsupportedNode {
+    /// The kind of the node.
+    pub kind: OperatorKind,
+    /// Why the node cannot be translated.
+    pub reason: String,
+}
+
+impl UnsupportedNode {
+    fn new(node: &Node, reason: impl Into<String>) -> Self {
+        Self {
+            kind: node.kind(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for UnsupportedNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Cannot translate {:?} node to SQL: {}",
+            self.kind, self.reason
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedNode {}
+
+/// Translates the given operator tree into the condition of an SQL `WHERE` clause in the given dialect.
+///
+/// Returns the condition together with the values of its bind parameters, in the order of their placeholders.
+/// Constants of the expression are never written into the condition itself, and variables are translated to quoted column names.
+///
+/// The following expressions are supported:
+///
+/// * the comparisons `==`, `!=`, `<`, `<=`, `>` and `>=`, and the boolean operators `&&`, `||`, `xor`, `!` and `implies`, where `xor` is translated to `<>`,
+/// * the conditional `c ? a : b`, which is translated to `CASE WHEN c THEN a ELSE b END`,
+/// * the arithmetic operators `+`, `-`, `*`, `/`, `%` and `^`, where `+` with a string constant as operand is translated to string concatenation,
+/// * `any_of(tuple, "==", x)` and `all_of(tuple, "!=", x)`, which are translated to `x IN (...)` and `x NOT IN (...)`,
+/// * `len`, `min`, `max`, `str::to_lowercase`, `str::to_uppercase` and `str::trim`,
+/// * `str::starts_with`, `str::ends_with`, `matches_glob` and `matches_glob_ci` with a constant prefix, suffix or pattern, which are translated to `LIKE` or, for Postgres, `ILIKE`.
+///   Wildcard patterns with character classes are not supported.
+///
+/// Function calls are translated according to the builtin function of the same name, as the translation does not know the context the expression is meant to be evaluated with.
+/// The semantics of the translated condition follow SQL, so for example `NULL` values and integer division behave as defined by the database.
+///
+/// Fails with an `UnsupportedNode` describing the first node in evaluation order that cannot be translated, like an assignment or a call of a user-defined function.
+pub fn to_sql(node: &Node, dialect: &SqlDialect) -> Result<(String, Vec<Value>), UnsupportedNode> {
+    let mut translator = Translator {
+        dialect: *dialect,
+        parameters: Vec::new(),
+    };
+    let clause = translator.translate(node)?;
+    Ok((clause, translator.parameters))
+}
+
+struct Translator {
+    dialect: SqlDialect,
+    parameters: Vec<Value>,
+}
+
+impl Translator {
+    fn translate(&mut self, node: &Node) -> Result<String, UnsupportedNode> {
+        let children = node.children();
+        if let Some(value) = node.operator().constant_value() {
+            return match value {
+                Value::String(_) | Value::Int(_) | Value::Float(_) | Value::Boolean(_) => {
+                    Ok(self.bind(value.clone()))
+                },
+                Value::Tuple(_) => Err(UnsupportedNode::new(
+                    node,
+                    "tuples are only supported as the first argument of any_of and all_of",
+                )),
+                Value::Empty => Err(UnsupportedNode::new(
+                    node,
+                    "the empty value has no SQL equivalent",
+                )),
+            };
+        }
+
+        match node.operator() {
+            Operator::RootNode => match children {
+                [child] => self.translate(child),
+                [] => Err(UnsupportedNode::new(
+                    node,
+                    "the empty value has no SQL equivalent",
+                )),
+                _ => Err(UnsupportedNode::new(
+                    node,
+                    "a root node must have a single child",
+                )),
+            },
+            Operator::Shared { .. } => match children {
+                [child] => self.translate(child),
+                _ => Err(unexpected_children(node)),
+            },
+            Operator::VariableIdentifier { identifier } => {
+                Ok(self.dialect.quote_identifier(identifier))
+            },
+            Operator::FunctionIdentifier { identifier } => {
+                self.translate_function(node, identifier)
+            },
+            Operator::NamedFunctionIdentifier { .. } => Err(UnsupportedNode::new(
+                node,
+                "named arguments have no SQL equivalent",
+            )),
+
+            Operator::Add if children.iter().any(is_string_constant) => {
+                let (left, right) = self.translate_binary(node)?;
+                Ok(match self.dialect {
+                    SqlDialect::Postgres => format!("{} || {}", left, right),
+                    SqlDialect::MySql => format!("CONCAT({}, {})", left, right),
+                })
+            },
+            Operator::Exp => {
+                let (left, right) = self.translate_binary(node)?;
+                Ok(match self.dialect {
+                    SqlDialect::Postgres => format!("{} ^ {}", left, right),
+                    SqlDialect::MySql => format!("POWER({}, {})", left, right),
+                })
+            },
+            Operator::Xor => {
+                let (left, right) = self.translate_binary(node)?;
+                Ok(format!("{} <> {}", left, right))
+            },
+            Operator::BitXor => {
+                let (left, right) = self.translate_binary(node)?;
+                Ok(match self.dialect {
+                    SqlDialect::Postgres => format!("{} # {}", left, right),
+                    SqlDialect::MySql => format!("{} ^ {}", left, right),
+                })
+            },
+            Operator::Neg => Ok(format!("-{}", self.translate_unary(node)?)),
+            Operator::Pos => Ok(format!("+{}", self.translate_unary(node)?)),
+            Operator::Not => Ok(format!("NOT {}", self.translate_unary(node)?)),
+            Operator::Implies => {
+                let (left, right) = self.translate_binary(node)?;
+                Ok(format!("NOT {} OR {}", left, right))
+            },
+            Operator::Conditional => match children {
+                [condition, if_true, if_false] => Ok(format!(
+                    "CASE WHEN {} THEN {} ELSE {} END",
+                    self.translate(condition)?,
+                    self.translate(if_true)?,
+                    self.translate(if_false)?
+                )),
+                _ => Err(unexpected_children(node)),
+            },
+            Operator::Coalesce => match children {
+                [value, default] => Ok(format!(
+                    "COALESCE({}, {})",
+                    self.translate(value)?,
+                    self.translate(default)?
+                )),
+                _ => Err(unexpected_children(node)),
+            },
+            operator => {
+                let symbol =
+                    match operator {
+                        Operator::Add => "+",
+                        Operator::Sub => "-",
+                        Operator::Mul => "*",
+                        Operator::Div => "/",
+                        Operator::Mod => "%",
+                        Operator::Eq => "=",
+                        Operator::Neq => "<>",
+                        Operator::Gt => ">",
+                        Operator::Lt => "<",
+                        Operator::Geq => ">=",
+                        Operator::Leq => "<=",
+                        Operator::And => "AND",
+                        Operator::Or => "OR",
+                        Operator::BitAnd => "&",
+                        Operator::BitOr => "|",
+                        Operator::Shl => "<<",
+                        Operator::Shr => ">>",
+                        Operator::RegexMatch => {
+                            return Err(UnsupportedNode::new(
+                                node,
+                                "regex matches differ between SQL dialects",
+                            ))
+                        },
+                        Operator::Range | Operator::RangeInclusive => {
+                            return Err(UnsupportedNode::new(node, "ranges have no SQL equivalent"))
+                        },
+                        Operator::Tuple => return Err(UnsupportedNode::new(
+                            node,
+                            "tuples are only supported as the first argument of any_of and all_of",
+                        )),
+                        operator => {
+                            return Err(UnsupportedNode::new(
+                                node,
+                                format!(
+                                    "the operator `{}` has no SQL equivalent",
+                                    operator.to_string().trim()
+                                ),
+                            ))
+                        },
+                    };
+                let (left, right) = self.translate_binary(node)?;
+                Ok(format!("{} {} {}", left, symbol, right))
+            },
+        }
+    }
+
+    fn translate_unary(&mut self, node: &Node) -> Result<String, UnsupportedNode> {
+        match node.children() {
+            [operand] => self.translate_operand(operand),
+            _ => Err(unexpected_children(node)),
+        }
+    }
+
+    fn translate_binary(&mut self, node: &Node) -> Result<(String, String), UnsupportedNode> {
+        match node.children() {
+            [left, right] => Ok((
+                self.translate_operand(left)?,
+                self.translate_operand(right)?,
+            )),
+            _ => Err(unexpected_children(node)),
+        }
+    }
+
+    /// Translates an operand of an operator, and puts it in parentheses unless it is a single value or an SQL function call.
+    /// This way, the translation does not depend on the operator precedence of the dialect.
+    fn translate_operand(&mut self, node: &Node) -> Result<String, UnsupportedNode> {
+        let translated = self.translate(node)?;
+        match node.strip_root_nodes().operator() {
+            Operator::Const { .. }
+            | Operator::PreservedConst { .. }
+            | Operator::VariableIdentifier { .. } => Ok(translated),
+            Operator::FunctionIdentifier { identifier } if !is_infix_function(identifier) => {
+                Ok(translated)
+            },
+            _ => Ok(format!("({})", translated)),
+        }
+    }
+
+    fn translate_function(
+        &mut self,
+        node: &Node,
+        identifier: &str,
+    ) -> Result<String, UnsupportedNode> {
+        let arguments = function_arguments(node);
+        let sql_function = match (identifier, self.dialect) {
+            ("len", SqlDialect::Postgres) => "LENGTH",
+            ("len", SqlDialect::MySql) => "CHAR_LENGTH",
+            ("min", _) if arguments.len() >= 2 => "LEAST",
+            ("max", _) if arguments.len() >= 2 => "GREATEST",
+            ("str::to_lowercase", _) => "LOWER",
+            ("str::to_uppercase", _) => "UPPER",
+            ("str::trim", _) => "TRIM",
+            ("str::starts_with", _) | ("str::ends_with", _) => {
+                let (subject, affix) = expect_two_arguments(node, identifier, &arguments)?;
+                let affix = expect_string_constant(affix).ok_or_else(|| {
+                    UnsupportedNode::new(
+                        node,
+                        format!(
+                            "the second argument of {} must be a string constant",
+                            identifier
+                        ),
+                    )
+                })?;
+                let subject = self.translate_operand(subject)?;
+                let pattern = if identifier == "str::starts_with" {
+                    format!("{}%", escape_like(affix))
+                } else {
+                    format!("%{}", escape_like(affix))
+                };
+                return Ok(format!("{} LIKE {}", subject, self.bind(pattern.into())));
+            },
+            ("matches_glob", _) | ("matches_glob_ci", _) => {
+                let (pattern, text) = expect_two_arguments(node, identifier, &arguments)?;
+                let pattern = expect_string_constant(pattern)
+                    .ok_or_else(|| {
+                        UnsupportedNode::new(
+                            node,
+                            format!(
+                                "the first argument of {} must be a string constant",
+                                identifier
+                            ),
+                        )
+                    })
+                    .and_then(|pattern| {
+                        glob_to_like(pattern).map_err(|reason| UnsupportedNode::new(node, reason))
+                    })?;
+                let text = self.translate_operand(text)?;
+                return Ok(match (identifier, self.dialect) {
+                    ("matches_glob", _) => format!("{} LIKE {}", text, self.bind(pattern.into())),
+                    (_, SqlDialect::Postgres) => {
+                        format!("{} ILIKE {}", text, self.bind(pattern.into()))
+                    },
+                    (_, SqlDialect::MySql) => {
+                        format!(
+                            "LOWER({}) LIKE {}",
+                            text,
+                            self.bind(pattern.to_lowercase().into())
+                        )
+                    },
+                });
+            },
+            ("any_of", _) | ("all_of", _) => {
+                return self.translate_membership(node, identifier, &arguments)
+            },
+            _ => {
+                return Err(UnsupportedNode::new(
+                    node,
+                    format!("the function {:?} has no SQL equivalent", identifier),
+                ))
+            },
+        };
+
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| self.translate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(format!("{}({})", sql_function, arguments.join(", ")))
+    }
+
+    /// Translates `any_of(tuple, "==", x)` to `x IN (...)` and `all_of(tuple, "!=", x)` to `x NOT IN (...)`.
+    fn translate_membership(
+        &mut self,
+        node: &Node,
+        identifier: &str,
+        arguments: &[&Node],
+    ) -> Result<String, UnsupportedNode> {
+        if arguments.len() != 3 {
+            return Err(UnsupportedNode::new(
+                node,
+                format!(
+                    "{} expects 3 arguments, but got {}",
+                    identifier,
+                    arguments.len()
+                ),
+            ));
+        }
+        let (negated, comparison) = if identifier == "any_of" {
+            (false, "==")
+        } else {
+            (true, "!=")
+        };
+        if expect_string_constant(arguments[1]) != Some(comparison) {
+            return Err(UnsupportedNode::new(
+                node,
+                format!(
+                    "{} is only supported with the comparison {:?}",
+                    identifier, comparison
+                ),
+            ));
+        }
+
+        let elements = arguments[0].strip_root_nodes();
+        let elements: Vec<&Node> = match elements.operator() {
+            Operator::Tuple => elements.children().iter().collect(),
+            Operator::RootNode if elements.children().is_empty() => Vec::new(),
+            _ => {
+                return Err(UnsupportedNode::new(
+                    node,
+                    format!(
+                        "the first argument of {} must be a tuple expression",
+                        identifier
+                    ),
+                ))
+            },
+        };
+
+        let value = self.translate_operand(arguments[2])?;
+        if elements.is_empty() {
+            // `IN ()` is not valid SQL.
+            return Ok(if negated { "TRUE" } else { "FALSE" }.to_string());
+        }
+        let elements = elements
+            .into_iter()
+            .map(|element| self.translate(element))
+            .collect::<Result<Vec<_>, _>>()?;
+        let operator = if negated { "NOT IN" } else { "IN" };
+        Ok(format!("{} {} ({})", value, operator, elements.join(", ")))
+    }
+
+    /// Adds a bind parameter and returns its placeholder.
+    fn bind(&mut self, value: Value) -> String {
+        self.parameters.push(value);
+        self.dialect.placeholder(self.parameters.len())
+    }
+}
+
+/// Returns the arguments of the given function call node.
+/// Returns the error for a node whose number of children does not match its operator, like the multiplication with one operand that `1 * * 2` is parsed into.
+fn unexpected_children(node: &Node) -> UnsupportedNode {
+    UnsupportedNode::new(
+        node,
+        format!(
+            "the operator has {} operands, which is not the number it takes",
+            node.children().len()
+        ),
+    )
+}
+
+fn function_arguments(node: &Node) -> Vec<&Node> {
+    let argument = node.children()[0].strip_root_nodes();
+    match argument.operator() {
+        Operator::Tuple => argument.children().iter().collect(),
+        Operator::RootNode if argument.children().is_empty() => Vec::new(),
+        _ => vec![argument],
+    }
+}
+
+fn expect_two_arguments<'a>(
+    node: &Node,
+    identifier: &str,
+    arguments: &[&'a Node],
+) -> Result<(&'a Node, &'a Node), UnsupportedNode> {
+    match arguments {
+        [first, second] => Ok((first, second)),
+        _ => Err(UnsupportedNode::new(
+            node,
+            format!(
+                "{} expects 2 arguments, but got {}",
+                identifier,
+                arguments.len()
+            ),
+        )),
+    }
+}
+
+/// Returns true if the given builtin function is translated to an operator like `LIKE` instead of an SQL function call.
+fn is_infix_function(identifier: &str) -> bool {
+    matches!(
+        identifier,
+        "str::starts_with"
+            | "str::ends_with"
+            | "matches_glob"
+            | "matches_glob_ci"
+            | "any_of"
+            | "all_of"
+    )
+}
+
+fn is_string_constant(node: &Node) -> bool {
+    expect_string_constant(node).is_some()
+}
+
+fn expect_string_constant(node: &Node) -> Option<&str> {
+    match node.strip_root_nodes().operator() {
+        Operator::Const {
+            value: Value::String(string),
+        } => Some(string),
+        _ => None,
+    }
+}
+
+/// Escapes the wildcards of `LIKE` with the default escape character `\`, which both supported dialects use.
+fn escape_like(string: &str) -> String {
+    let mut result = String::with_capacity(string.len());
+    for c in string.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Converts a wildcard pattern as accepted by `matches_glob` into a `LIKE` pattern.
+fn glob_to_like(pattern: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => result.push('%'),
+            '?' => result.push('_'),
+            '[' => {
+                return Err(
+                    "wildcard patterns with character classes have no LIKE equivalent".to_string(),
+                )
+            },
+            '\\' => match chars.next() {
+                Some(escaped) => result.push_str(&escape_like(&escaped.to_string())),
+                None => {
+                    return Err(format!(
+                        "the wildcard pattern {:?} ends with an unescaped '\\'",
+                        pattern
+                    ))
+                },
+            },
+            c => result.push_str(&escape_like(&c.to_string())),
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use context::Context;
+use operator::Operator;
+use value::Value;
+
+/// The maximum amount of buffers of each kind that a pool keeps.
+/// Evaluations need about as many buffers at once as their operator tree is deep, so the pool does not need to grow beyond that.
+const MAX_POOLED_BUFFERS: usize = 64;
+
+/// The maximum capacity in bytes of a string buffer that is kept in a pool, such that single huge strings do not stay allocated.
+const MAX_POOLED_STRING_CAPACITY: usize = 64 * 1024;
+
+/// A pool of buffers that evaluations reuse for the strings and tuples of intermediate values, instead of allocating new ones.
+///
+/// If an arena is set as `EvalConfig::arena`, then each evaluation takes the vectors that collect the arguments of operators from the arena, and builds the strings and tuples produced by variable reads, constants, string concatenation and tuples in buffers from the arena.
+/// Once an operator is applied, its arguments are not needed anymore, and their buffers are checked back into the arena.
+/// The buffers are owned by the values, so no value ever points into the arena, and the result of an evaluation is returned like any other value.
+/// This reduces the allocations of evaluating the same expression for many rows, especially if the expression handles strings.
+/// The results are identical to the ones of evaluations without arena.
+///
+/// Clones of an arena share the same buffers.
+/// An evaluation keeps the buffers while it runs, so evaluations that run at the same time with clones of the same arena do not block each other, but only one of them reuses the buffers.
+/// The arena keeps a bounded amount of buffers, which are released by `EvalArena::reset` or when the last clone is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let arena = EvalArena::new();
+/// let state = EvalState::new(EvalConfig {
+///     arena: Some(arena.clone()),
+///     ..Default::default()
+/// });
+/// let tree = build_operator_tree("first + \" \" + last").unwrap(); // Do proper error handling here
+///
+/// for (first, last) in [("Ada", "Lovelace"), ("Alan", "Turing")] {
+///     let context = context_map! { "first" => first, "last" => last }.unwrap(); // Do proper error handling here
+///     let name = tree.eval_with_context_and_state(&context, &state).unwrap(); // Do proper error handling here
+///     assert_eq!(name, Value::from(format!("{} {}", first, last)));
+/// }
+/// assert!(arena.reused_buffers() > 0);
+///
+/// arena.reset();
+/// assert_eq!(arena.reused_buffers(), 0);
+/// ```
+#[derive(Clone, Default)]
+pub struct EvalArena {
+    pool: Arc<Mutex<BufferPool>>,
+}
+
+impl EvalArena {
+    /// Creates a new arena without buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns how many buffers evaluations took from this arena instead of allocating them, since it was created or reset.
+    /// Buffers of evaluations that are still running are not counted yet.
+    pub fn reused_buffers(&self) -> usize {
+        self.lock().reused_buffers
+    }
+
+    /// Releases all buffers of this arena and resets the count of reused buffers.
+    /// The arena can be used for further evaluations afterwards, which allocate new buffers as needed.
+    /// Evaluations that are running while the arena is reset keep their buffers and check them back in when they finish.
+    pub fn reset(&self) {
+        *self.lock() = BufferPool::default();
+    }
+
+    /// Takes all buffers out of this arena, for an evaluation to use them without locking the arena.
+    pub(crate) fn take_pool(&self) -> BufferPool {
+        mem::take(&mut *self.lock())
+    }
+
+    /// Checks the buffers of a finished evaluation back into this arena.
+    pub(crate) fn return_pool(&self, pool: BufferPool) {
+        self.lock().merge(pool);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BufferPool> {
+        // The pool stays consistent even if an evaluation panicked while holding the lock.
+        self.pool.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl fmt::Debug for EvalArena {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pool = self.lock();
+        f.debug_struct("EvalArena")
+            .field("strings", &pool.strings.len())
+            .field("tuples", &pool.tuples.len())
+            .field("reused_buffers", &pool.reused_buffers)
+            .finish()
+    }
+}
+
+/// Arenas are equal if they are clones of each other.
+impl PartialEq for EvalArena {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pool, &other.pool)
+    }
+}
+
+/// Empty buffers for strings and tuples.
+#[derive(Debug, Default)]
+pub(crate) struct BufferPool {
+    strings: Vec<String>,
+    tuples: Vec<Vec<Value>>,
+    reused_buffers: usize,
+}
+
+impl BufferPool {
+    /// Returns an empty vector, reusing a buffer if there is one.
+    pub(crate) fn take_values(&mut self) -> Vec<Value> {
+        match self.tuples.pop() {
+            Some(values) => {
+                self.reused_buffers += 1;
+                values
+            },
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns an empty string, reusing a buffer if there is one.
+    fn take_string(&mut self) -> String {
+        match self.strings.pop() {
+            Some(string) => {
+                self.reused_buffers += 1;
+                string
+            },
+            None => String::new(),
+        }
+    }
+
+    /// Checks the buffers of the given values and the vector itself back into this pool.
+    pub(crate) fn recycle_values(&mut self, mut values: Vec<Value>) {
+        for value in values.drain(..) {
+            self.recycle(value);
+        }
+        if self.tuples.len() < MAX_POOLED_BUFFERS {
+            self.tuples.push(values);
+        }
+    }
+
+    /// Checks the buffer of the given value back into this pool, if it has one.
+    fn recycle(&mut self, value: Value) {
+        match value {
+            Value::String(mut string)
+                if self.strings.len() < MAX_POOLED_BUFFERS
+                    && string.capacity() <= MAX_POOLED_STRING_CAPACITY =>
+            {
+                string.clear();
+                self.strings.push(string);
+            },
+            Value::Tuple(tuple) => self.recycle_values(tuple),
+            _ => {},
+        }
+    }
+
+    /// Returns a copy of the given value whose strings and tuples are built in buffers of this pool.
+    fn copy(&mut self, value: &Value) -> Value {
+        match value {
+            Value::String(string) => {
+                let mut result = self.take_string();
+                result.push_str(string);
+                Value::String(result)
+            },
+            Value::Tuple(tuple) => Value::Tuple(self.copy_values(tuple)),
+            value => value.clone(),
+        }
+    }
+
+    fn copy_values(&mut self, values: &[Value]) -> Vec<Value> {
+        let mut result = self.take_values();
+        result.reserve(values.len());
+        for value in values {
+            result.push(self.copy(value));
+        }
+        result
+    }
+
+    /// Applies the given operator with buffers of this pool, if it is one that builds strings or tuples from its arguments.
+    /// Returns `None` for all other operators, and if the operator fails, such that it is applied as usual.
+    pub(crate) fn eval(
+        &mut self,
+        operator: &Operator,
+        arguments: &[Value],
+        context: &dyn Context,
+    ) -> Option<Value> {
+        match (operator, arguments) {
+            (Operator::RootNode, [value]) | (Operator::Chain, [.., value]) => {
+                Some(self.copy(value))
+            },
+            (Operator::Const { value }, []) => Some(self.copy(value)),
+            (Operator::PreservedConst { constant }, []) => Some(self.copy(&constant.value)),
+            (Operator::VariableIdentifier { identifier }, []) => {
+                context.get_value(identifier).map(|value| self.copy(value))
+            },
+            (Operator::Add, [Value::String(a), Value::String(b)]) => {
+                let mut result = self.take_string();
+                result.reserve(a.len() + b.len());
+                result.push_str(a);
+                result.push_str(b);
+                Some(Value::String(result))
+            },
+            (Operator::Add, [Value::Tuple(a), Value::Tuple(b)]) => {
+                let mut result = self.copy_values(a);
+                for value in b {
+                    result.push(self.copy(value));
+                }
+                Some(Value::Tuple(result))
+            },
+            (Operator::Tuple, arguments) => Some(Value::Tuple(self.copy_values(arguments))),
+            _ => None,
+        }
+    }
+
+    /// Moves the buffers of the given pool into this one, as far as this pool has room for them.
+    fn merge(&mut self, mut other: BufferPool) {
+        // Keep the larger vectors, such that returning the pool of an evaluation to an empty arena does not allocate.
+        if self.strings.len() < other.strings.len() {
+            mem::swap(&mut self.strings, &mut other.strings);
+        }
+        if self.tuples.len() < other.tuples.len() {
+            mem::swap(&mut self.tuples, &mut other.tuples);
+        }
+        let strings = MAX_POOLED_BUFFERS.saturating_sub(self.strings.len());
+        self.strings.extend(other.strings.into_iter().take(strings));
+        let tuples = MAX_POOLED_BUFFERS.saturating_sub(self.tuples.len());
+        self.tuples.extend(other.tuples.into_iter().take(tuples));
+        self.reused_buffers += other.reused_buffers;
+    }
+}
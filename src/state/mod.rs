@@ -0,0 +1,244 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use config::EvalConfig;
+use context::Context;
+use error::{EvalexprError, EvalexprResult};
+use operator::Operator;
+use value::Value;
+
+use self::arena::BufferPool;
+
+mod arena;
+
+pub use self::arena::EvalArena;
+
+/// The mutable state of an evaluation.
+///
+/// The state counts how deeply evaluations are nested into each other, and how many bytes the values produced by the evaluations occupy.
+/// Evaluations get nested if a user-defined function evaluates another expression while being called from an expression.
+/// To share the counters with such nested evaluations, the function needs access to the state of the outer evaluation, for example via an `Rc<EvalState>`.
+///
+/// Evaluations that do not receive an explicit state use a thread-local default state.
+/// This way, functions that evaluate further expressions with the plain `eval_*` methods are protected against endless recursion as well.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::rc::Rc;
+///
+/// let state = Rc::new(EvalState::default());
+/// let inner = build_operator_tree("2 * 3").unwrap(); // Do proper error handling here
+///
+/// let mut context = HashMapContext::new();
+/// let function_state = state.clone();
+/// context.set_function("six".into(), Function::new(Box::new(move |_| {
+///     assert_eq!(function_state.recursion_depth(), 1);
+///     inner.eval_with_context_and_state(&EmptyContext, &function_state)
+/// }))).unwrap(); // Do proper error handling here
+///
+/// let outer = build_operator_tree("six() + 1").unwrap(); // Do proper error handling here
+/// assert_eq!(outer.eval_with_context_and_state(&context, &state), Ok(Value::from(7)));
+/// assert_eq!(state.recursion_depth(), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct EvalState {
+    config: EvalConfig,
+    recursion_depth: Cell<usize>,
+    allocated_bytes: Cell<usize>,
+    expression_stack: RefCell<Vec<String>>,
+    /// The values of shared subexpressions, by the recursion depth of the evaluation that computed them and the id of the subexpression.
+    shared_values: RefCell<HashMap<(usize, usize), Value>>,
+    /// The buffers of the `EvalConfig::arena` while an evaluation runs with this state.
+    buffers: RefCell<Option<BufferPool>>,
+}
+
+thread_local! {
+    static DEFAULT_STATE: EvalState = EvalState::default();
+}
+
+impl EvalState {
+    /// Creates a new state with the given configuration.
+    pub fn new(config: EvalConfig) -> Self {
+        Self {
+            config,
+            recursion_depth: Cell::new(0),
+            allocated_bytes: Cell::new(0),
+            expression_stack: RefCell::new(Vec::new()),
+            shared_values: RefCell::new(HashMap::new()),
+            buffers: RefCell::new(None),
+        }
+    }
+
+    /// Returns the configuration of this state.
+    pub fn config(&self) -> &EvalConfig {
+        &self.config
+    }
+
+    /// Returns the amount of evaluations that are currently running with this state.
+    pub fn recursion_depth(&self) -> usize {
+        self.recursion_depth.get()
+    }
+
+    /// Returns the amount of bytes that the strings and tuples produced by the current or last evaluation occupy in total.
+    /// The bytes are only counted if `EvalConfig::max_result_bytes` is set.
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated_bytes.get()
+    }
+
+    /// Registers the start of an evaluation.
+    /// The evaluation is registered as finished when the returned guard is dropped.
+    ///
+    /// If the evaluation is not nested into another one, the count of allocated bytes is reset, and the buffers of the configured arena are taken until the evaluation finishes.
+    pub(crate) fn enter(&self) -> EvalexprResult<RecursionGuard<'_>> {
+        if self.recursion_depth.get() == 0 {
+            self.allocated_bytes.set(0);
+        }
+        self.resume()
+    }
+
+    /// Registers the continuation of an evaluation that was suspended, like an async evaluation that awaited a function.
+    /// Unlike `enter`, this keeps the count of allocated bytes, such that it covers the whole evaluation.
+    pub(crate) fn resume(&self) -> EvalexprResult<RecursionGuard<'_>> {
+        let depth = self.recursion_depth.get();
+        if depth >= self.config.max_recursion_depth {
+            Err(EvalexprError::recursion_limit_exceeded(
+                self.config.max_recursion_depth,
+            ))
+        } else {
+            if depth == 0 {
+                if let Some(arena) = &self.config.arena {
+                    *self.buffers.borrow_mut() = Some(arena.take_pool());
+                }
+            }
+            self.recursion_depth.set(depth + 1);
+            Ok(RecursionGuard { state: self })
+        }
+    }
+
+    /// Registers the start of the evaluation of the named expression with the given identifier.
+    /// The evaluation is registered as finished when the returned guard is dropped.
+    ///
+    /// Fails if the named expression is already being evaluated, i.e. if named expressions reference each other in a cycle.
+    pub(crate) fn enter_expression(&self, identifier: &str) -> EvalexprResult<ExpressionGuard<'_>> {
+        let mut expression_stack = self.expression_stack.borrow_mut();
+        if let Some(start) = expression_stack
+            .iter()
+            .position(|active| active == identifier)
+        {
+            let mut chain = expression_stack[start..].to_vec();
+            chain.push(identifier.to_string());
+            return Err(EvalexprError::expression_cycle(chain));
+        }
+
+        expression_stack.push(identifier.to_string());
+        Ok(ExpressionGuard { state: self })
+    }
+
+    /// Registers that the evaluation is about to allocate the given amount of bytes for a value.
+    /// Fails if this exceeds the configured `max_result_bytes`.
+    pub(crate) fn allocate(&self, bytes: usize) -> EvalexprResult<()> {
+        if let Some(limit) = self.config.max_result_bytes {
+            let attempted = self.allocated_bytes.get().saturating_add(bytes);
+            if attempted > limit {
+                return Err(EvalexprError::value_too_large(limit, attempted));
+            }
+            self.allocated_bytes.set(attempted);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the shared subexpression with the given id, if the current evaluation already computed it.
+    pub(crate) fn shared_value(&self, id: usize) -> Option<Value> {
+        self.shared_values
+            .borrow()
+            .get(&(self.recursion_depth(), id))
+            .cloned()
+    }
+
+    /// Stores the value of the shared subexpression with the given id until the current evaluation finishes.
+    pub(crate) fn set_shared_value(&self, id: usize, value: Value) {
+        self.shared_values
+            .borrow_mut()
+            .insert((self.recursion_depth(), id), value);
+    }
+
+    /// Returns an empty vector for the arguments of an operator, reusing a buffer of the configured arena if there is one.
+    pub(crate) fn take_arguments(&self) -> Vec<Value> {
+        match self.buffers.borrow_mut().as_mut() {
+            Some(buffers) => buffers.take_values(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Checks the buffers of the given arguments of an operator back into the configured arena, or drops them if there is no arena.
+    pub(crate) fn recycle_arguments(&self, arguments: Vec<Value>) {
+        if let Some(buffers) = self.buffers.borrow_mut().as_mut() {
+            buffers.recycle_values(arguments);
+        }
+    }
+
+    /// Applies the given operator with buffers of the configured arena, if there is an arena and the operator builds strings or tuples from its arguments.
+    /// Returns `None` otherwise, such that the operator is applied as usual.
+    pub(crate) fn eval_in_arena(
+        &self,
+        operator: &Operator,
+        arguments: &[Value],
+        context: &dyn Context,
+    ) -> Option<Value> {
+        self.buffers
+            .borrow_mut()
+            .as_mut()
+            .and_then(|buffers| buffers.eval(operator, arguments, context))
+    }
+
+    /// Fails with `EvalexprError::Cancelled` if the configured cancellation token was cancelled.
+    pub(crate) fn check_cancelled(&self) -> EvalexprResult<()> {
+        match &self.config.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(EvalexprError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Calls the given closure with the thread-local default state.
+    pub(crate) fn with_default<T, F: FnOnce(&EvalState) -> T>(f: F) -> T {
+        DEFAULT_STATE.with(f)
+    }
+}
+
+/// Decrements the recursion depth of an `EvalState` when dropped.
+pub(crate) struct RecursionGuard<'a> {
+    state: &'a EvalState,
+}
+
+impl<'a> Drop for RecursionGuard<'a> {
+    fn drop(&mut self) {
+        let depth = self.state.recursion_depth.get();
+        let mut shared_values = self.state.shared_values.borrow_mut();
+        if !shared_values.is_empty() {
+            shared_values.retain(|&(shared_depth, _), _| shared_depth < depth);
+        }
+        self.state.recursion_depth.set(depth - 1);
+        if depth == 1 {
+            if let (Some(arena), Some(buffers)) = (
+                &self.state.config.arena,
+                self.state.buffers.borrow_mut().take(),
+            ) {
+                arena.return_pool(buffers);
+            }
+        }
+    }
+}
+
+/// Removes the innermost named expression from the expression stack of an `EvalState` when dropped.
+pub(crate) struct ExpressionGuard<'a> {
+    state: &'a EvalState,
+}
+
+impl<'a> Drop for ExpressionGuard<'a> {
+    fn drop(&mut self) {
+        self.state.expression_stack.borrow_mut().pop();
+    }
+}
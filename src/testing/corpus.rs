@@ -0,0 +1,352 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use eval;
+use eval_with_context_mut;
+use EvalexprError;
+use EvalexprResult;
+use HashMapContext;
+use Value;
+
+/// The extension of the files that `run_corpus` reads from a directory.
+const CORPUS_EXTENSION: &str = "corpus";
+
+/// The keys of the fields of a case.
+const KEYS: &[&str] = &["expr", "context", "requires", "expect", "display", "error"];
+
+/// The result of running a corpus with `run_corpus`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CorpusReport {
+    passed: usize,
+    skipped: usize,
+    failures: Vec<CorpusFailure>,
+}
+
+impl CorpusReport {
+    /// Returns the amount of cases whose result was as expected.
+    pub fn passed(&self) -> usize {
+        self.passed
+    }
+
+    /// Returns the amount of cases that were not run, because they require a feature flag that is not enabled.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Returns the cases whose result was not as expected, and the cases that are malformed, in the order of the corpus.
+    pub fn failures(&self) -> &[CorpusFailure] {
+        &self.failures
+    }
+
+    /// Returns true if no case failed.
+    /// This is also true for an empty corpus, so tests may want to check `CorpusReport::passed` as well.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for CorpusReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.passed,
+            self.failures.len(),
+            self.skipped
+        )?;
+        for failure in &self.failures {
+            write!(f, "\n{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// A case of a corpus whose result was not as expected, or that is malformed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorpusFailure {
+    /// The file that contains the case.
+    pub file: PathBuf,
+    /// The line of the file where the case starts, counted from one.
+    pub line: usize,
+    /// The expression of the case, or an empty string if the case has none.
+    pub expression: String,
+    /// The difference between the expected and the actual result, or what is wrong with the case.
+    pub message: String,
+}
+
+impl fmt::Display for CorpusFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {:?}: {}",
+            self.file.display(),
+            self.line,
+            self.expression,
+            self.message
+        )
+    }
+}
+
+/// A case of a corpus as written in the corpus file.
+#[derive(Debug, Default)]
+struct CorpusCase {
+    line: usize,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl CorpusCase {
+    fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field_key, _)| *field_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the feature flags that the case requires and that are not enabled, or an error message if one of them is unknown.
+    fn missing_features(&self) -> Result<Vec<&str>, String> {
+        let mut missing = Vec::new();
+        for feature in self.field("requires").into_iter().flat_map(|features| {
+            features
+                .split(',')
+                .map(str::trim)
+                .filter(|feature| !feature.is_empty())
+        }) {
+            match is_feature_enabled(feature) {
+                Some(true) => {},
+                Some(false) => missing.push(feature),
+                None => return Err(format!("unknown feature flag {:?}", feature)),
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Evaluates the case and returns a message if its result is not as expected.
+    fn run(&self) -> Result<(), String> {
+        let expression = self
+            .field("expr")
+            .ok_or_else(|| "the case has no expression".to_string())?;
+        let expected = self.field("expect");
+        let display = self.field("display");
+        let error = self.field("error");
+        if let Some(error) = error {
+            if expected.is_some() || display.is_some() {
+                return Err("an error cannot be expected together with a value".to_string());
+            }
+            if EvalexprError::from_variant_name(error).is_none() {
+                return Err(format!("unknown error variant {:?}", error));
+            }
+        } else if expected.is_none() && display.is_none() {
+            return Err("the case expects no result".to_string());
+        }
+        let expected = match expected {
+            Some(expected) => Some(
+                eval(expected).map_err(|error| format!("the expected value fails: {}", error))?,
+            ),
+            None => None,
+        };
+
+        let mut context = HashMapContext::new();
+        if let Some(setup) = self.field("context") {
+            eval_with_context_mut(setup, &mut context)
+                .map_err(|error| format!("the context fails: {}", error))?;
+        }
+        let actual = eval_with_context_mut(expression, &mut context);
+
+        match (error, actual) {
+            (Some(error), Err(actual)) if actual.variant_name() == error => Ok(()),
+            (Some(error), actual) => Err(format!(
+                "expected the error {}, but got {}",
+                error,
+                describe(&actual)
+            )),
+            (None, Err(actual)) => Err(format!(
+                "expected a value, but got {}",
+                describe(&Err(actual))
+            )),
+            (None, Ok(actual)) => {
+                if let Some(expected) = expected {
+                    if actual != expected {
+                        return Err(format!(
+                            "expected {}, but got {}",
+                            expected.to_expression_literal(),
+                            describe(&Ok(actual))
+                        ));
+                    }
+                }
+                match display {
+                    Some(display) if actual.to_string() != display => Err(format!(
+                        "expected the display {:?}, but got {:?}",
+                        display,
+                        actual.to_string()
+                    )),
+                    _ => Ok(()),
+                }
+            },
+        }
+    }
+}
+
+/// Describes the result of an evaluation for a failure message.
+fn describe(result: &EvalexprResult<Value>) -> String {
+    match result {
+        Ok(value) => value.to_expression_literal(),
+        Err(error) => format!("the error {} ({})", error.variant_name(), error),
+    }
+}
+
+/// Returns whether the given feature flag of this crate is enabled, or `None` if it is not one of the feature flags that change the builtin functions.
+fn is_feature_enabled(feature: &str) -> Option<bool> {
+    match feature {
+        "builtin_math" => Some(cfg!(feature = "builtin_math")),
+        "builtin_string" => Some(cfg!(feature = "builtin_string")),
+        "builtin_tuple" => Some(cfg!(feature = "builtin_tuple")),
+        "builtin_datetime" => Some(cfg!(feature = "builtin_datetime")),
+        "builtin_regex" => Some(cfg!(feature = "builtin_regex")),
+        "unicode_support" => Some(cfg!(feature = "unicode_support")),
+        "geo_support" => Some(cfg!(feature = "geo_support")),
+        "excel_compat" => Some(cfg!(feature = "excel_compat")),
+        _ => None,
+    }
+}
+
+/// Splits the text of a corpus file into its cases.
+/// Lines that do not belong to a field are returned as malformed cases with their message.
+fn parse_cases(text: &str) -> Vec<Result<CorpusCase, (usize, String)>> {
+    let mut result = Vec::new();
+    let mut case: Option<CorpusCase> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            result.extend(case.take().map(Ok));
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let case = case.get_or_insert_with(|| CorpusCase {
+            line: line_number,
+            ..CorpusCase::default()
+        });
+        let field = line.split_once(':').and_then(|(key, value)| {
+            KEYS.iter()
+                .find(|known| **known == key.trim())
+                .map(|key| (*key, value.trim().to_string()))
+        });
+        match field {
+            Some((key, _)) if case.field(key).is_some() => {
+                result.push(Err((line_number, format!("duplicate field {:?}", key))));
+            },
+            Some(field) => case.fields.push(field),
+            None => result.push(Err((
+                line_number,
+                format!("expected a field like \"expr: 1 + 2\", but got {:?}", line),
+            ))),
+        }
+    }
+    result.extend(case.map(Ok));
+    result
+}
+
+/// Runs the cases of the given corpus file or directory and reports the cases whose result is not as expected.
+///
+/// A corpus pins the results of expressions, such that changes of the semantics of evaluation, like of type coercion, precedence or the formatting of floats, are noticed when upgrading this crate.
+/// Applications that store expressions can keep a corpus of them, and run it against the version of this crate they embed.
+/// If the path is a directory, all files with the extension `.corpus` directly within it are run in the order of their names.
+///
+/// A corpus file consists of cases that are separated by empty lines.
+/// Each line of a case is a field, written as a key, a colon and a value, and lines that start with `#` are comments.
+///
+/// | Key | Value |
+/// |-----|-------|
+/// | `expr` | The expression to evaluate |
+/// | `context` | An expression that is evaluated before, with the same `HashMapContext`, to assign the variables that `expr` reads |
+/// | `requires` | A comma-separated list of feature flags of this crate, like `builtin_math, excel_compat`, without which the case is skipped |
+/// | `expect` | An expression for the expected value, usually a literal as written by `Value::to_expression_literal` |
+/// | `display` | The expected value as written by its `Display` implementation, like `0.30000000000000004` or `NaN` |
+/// | `error` | The name of the variant of the expected error, like `DivisionError`, as returned by `EvalexprError::variant_name` |
+///
+/// Each case needs an `expr`, and either an `error`, or an `expect`, a `display` or both.
+/// The expected value and the actual value must be equal, so an integer is not equal to a float with the same numeric value.
+/// A case that is malformed, like one with an unknown key or an unknown feature flag, is reported as failure.
+///
+/// Fails with the errors of reading the file or directory.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::testing::run_corpus;
+///
+/// let report = run_corpus("tests/corpus").unwrap(); // Do proper error handling here
+/// assert!(report.is_success(), "{}", report);
+/// assert!(report.passed() > 0);
+/// ```
+///
+/// A corpus file may look like this:
+///
+/// ```text
+/// # The sum of an integer and a float is a float.
+/// expr: a + 0.5
+/// context: a = 1
+/// expect: 1.5
+///
+/// expr: 1 / 0
+/// error: DivisionError
+/// ```
+pub fn run_corpus<P: AsRef<Path>>(path: P) -> io::Result<CorpusReport> {
+    let path = path.as_ref();
+    let files = if path.is_dir() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.is_file()
+                && file
+                    .extension()
+                    .is_some_and(|extension| extension == CORPUS_EXTENSION)
+            {
+                files.push(file);
+            }
+        }
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut report = CorpusReport::default();
+    for file in files {
+        let text = fs::read_to_string(&file)?;
+        for case in parse_cases(&text) {
+            let failure = |line, expression: &str, message| CorpusFailure {
+                file: file.clone(),
+                line,
+                expression: expression.to_string(),
+                message,
+            };
+            let case = match case {
+                Ok(case) => case,
+                Err((line, message)) => {
+                    report.failures.push(failure(line, "", message));
+                    continue;
+                },
+            };
+            let expression = case.field("expr").unwrap_or_default();
+            match case.missing_features() {
+                Ok(missing) if !missing.is_empty() => report.skipped += 1,
+                Ok(_) => match case.run() {
+                    Ok(()) => report.passed += 1,
+                    Err(message) => report
+                        .failures
+                        .push(failure(case.line, expression, message)),
+                },
+                Err(message) => report
+                    .failures
+                    .push(failure(case.line, expression, message)),
+            }
+        }
+    }
+    Ok(report)
+}
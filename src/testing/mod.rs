@@ -0,0 +1,224 @@
+//! The `testing` module contains helpers for testing expressions.
+//!
+//! The assertion macros `assert_eval_eq!`, `assert_eval_err!` and `assert_eval_type!` evaluate an expression and panic with a detailed message if the result is not as expected.
+//! The message contains the expression, the parsed operator tree and the actual result.
+//! Each macro takes an optional third argument, which is a reference to the context to evaluate the expression with.
+//! If it is omitted, the expression is evaluated with an `EmptyContext`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate evalexpr;
+//!
+//! use evalexpr::*;
+//!
+//! # fn main() {
+//! let context = context_map! {
+//!     "five" => 5
+//! }.unwrap(); // Do proper error handling here
+//!
+//! assert_eval_eq!("1 + 2", 3);
+//! assert_eval_eq!("five * 2", 10, &context);
+//! assert_eval_err!("five + true", EvalexprError::ExpectedNumberOrString { .. }, &context);
+//! assert_eval_type!("five / 2.0", ValueType::Float, &context);
+//! # }
+//! ```
+//!
+//! With the `testing` feature, `run_corpus` runs a corpus of expressions with their expected results from text files, such that applications can check that their stored expressions keep their meaning across versions of this crate.
+
+use std::fmt::Write;
+
+use build_operator_tree;
+use Context;
+use EvalexprResult;
+use Node;
+use Value;
+
+#[cfg(feature = "testing")]
+mod corpus;
+
+#[cfg(feature = "testing")]
+pub use self::corpus::{run_corpus, CorpusFailure, CorpusReport};
+
+/// The result of evaluating an expression for an assertion.
+///
+/// This type is used by the assertion macros of this crate to create their failure messages.
+pub struct EvalAssertion {
+    expression: String,
+    tree: Option<Node>,
+    result: EvalexprResult<Value>,
+}
+
+impl EvalAssertion {
+    /// Parses and evaluates the given expression with the given context.
+    pub fn new(expression: &str, context: &dyn Context) -> Self {
+        let (tree, result) = match build_operator_tree(expression) {
+            Ok(tree) => {
+                let result = tree.eval_with_context(context);
+                (Some(tree), result)
+            },
+            Err(error) => (None, Err(error)),
+        };
+
+        Self {
+            expression: expression.to_string(),
+            tree,
+            result,
+        }
+    }
+
+    /// Returns the result of the evaluation.
+    pub fn result(&self) -> &EvalexprResult<Value> {
+        &self.result
+    }
+
+    /// Returns a message that explains that the result differs from the given expectation.
+    pub fn failure_message(&self, expected: &str) -> String {
+        let mut message = String::new();
+        // Writing to a string cannot fail.
+        writeln!(
+            message,
+            "assertion failed for expression {:?}",
+            self.expression
+        )
+        .unwrap();
+        match &self.tree {
+            Some(tree) => {
+                writeln!(message, "  parsed tree:").unwrap();
+                for line in tree.to_debug_tree().lines() {
+                    writeln!(message, "    {}", line).unwrap();
+                }
+            },
+            None => writeln!(message, "  parsed tree: <failed to parse>").unwrap(),
+        }
+        writeln!(message, "     expected: {}", expected).unwrap();
+        match &self.result {
+            Ok(value) => write!(message, "       actual: Ok({:?})", value),
+            Err(error) => write!(message, "       actual: Err({:?}) ({})", error, error),
+        }
+        .unwrap();
+        message
+    }
+}
+
+/// Asserts that an expression evaluates to the expected value.
+///
+/// The expected value can be anything that converts into a `Value`.
+/// An optional third argument gives a reference to the context to evaluate the expression with.
+///
+/// On failure, the panic message contains the expression, the parsed operator tree and the actual result.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate evalexpr;
+///
+/// use evalexpr::*;
+///
+/// # fn main() {
+/// assert_eval_eq!("1 + 2", 3);
+/// assert_eval_eq!("\"a\" + \"b\"", "ab");
+///
+/// let context = context_map! { "a" => 2.5 }.unwrap(); // Do proper error handling here
+/// assert_eval_eq!("a * 2", 5.0, &context);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_eval_eq {
+    ($expression:expr, $expected:expr) => {
+        $crate::assert_eval_eq!($expression, $expected, &$crate::EmptyContext)
+    };
+    ($expression:expr, $expected:expr, $context:expr) => {{
+        let assertion = $crate::testing::EvalAssertion::new($expression, $context);
+        let expected = $crate::Value::from($expected);
+        match assertion.result() {
+            Ok(actual) if actual == &expected => {},
+            _ => panic!(
+                "{}",
+                assertion.failure_message(&format!("Ok({:?})", expected))
+            ),
+        }
+    }};
+}
+
+/// Asserts that the evaluation of an expression fails with an error matching the given pattern.
+///
+/// The pattern may be followed by a match guard.
+/// An optional third argument gives a reference to the context to evaluate the expression with.
+///
+/// On failure, the panic message contains the expression, the parsed operator tree and the actual result.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate evalexpr;
+///
+/// use evalexpr::*;
+///
+/// # fn main() {
+/// assert_eval_err!("1 + true", EvalexprError::ExpectedNumberOrString { .. });
+/// assert_eval_err!(
+///     "a",
+///     EvalexprError::VariableIdentifierNotFound { identifier, .. } if identifier == "a"
+/// );
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_eval_err {
+    ($expression:expr, $pattern:pat $(if $guard:expr)?) => {
+        $crate::assert_eval_err!($expression, $pattern $(if $guard)?, &$crate::EmptyContext)
+    };
+    ($expression:expr, $pattern:pat $(if $guard:expr)?, $context:expr) => {{
+        let assertion = $crate::testing::EvalAssertion::new($expression, $context);
+        match assertion.result() {
+            Err($pattern) $(if $guard)? => {},
+            _ => panic!(
+                "{}",
+                assertion.failure_message(&format!(
+                    "Err({})",
+                    stringify!($pattern $(if $guard)?)
+                ))
+            ),
+        }
+    }};
+}
+
+/// Asserts that an expression evaluates to a value of the given `ValueType`.
+///
+/// An optional third argument gives a reference to the context to evaluate the expression with.
+///
+/// On failure, the panic message contains the expression, the parsed operator tree and the actual result.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate evalexpr;
+///
+/// use evalexpr::*;
+///
+/// # fn main() {
+/// assert_eval_type!("1 + 2", ValueType::Int);
+/// assert_eval_type!("1 + 2.0", ValueType::Float);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_eval_type {
+    ($expression:expr, $expected:expr) => {
+        $crate::assert_eval_type!($expression, $expected, &$crate::EmptyContext)
+    };
+    ($expression:expr, $expected:expr, $context:expr) => {{
+        let assertion = $crate::testing::EvalAssertion::new($expression, $context);
+        let expected: $crate::ValueType = $expected;
+        match assertion.result() {
+            Ok(actual) if $crate::ValueType::from(actual) == expected => {},
+            _ => panic!(
+                "{}",
+                assertion.failure_message(&format!("a value of type {:?}", expected))
+            ),
+        }
+    }};
+}
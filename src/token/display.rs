@@ -20,25 +20,59 @@ impl fmt::Display for Token {
             Lt => write!(f, "<"),
             Geq => write!(f, ">="),
             Leq => write!(f, "<="),
+            RegexMatch => write!(f, "=~"),
             And => write!(f, "&&"),
             Or => write!(f, "||"),
+            Xor => write!(f, "xor"),
             Not => write!(f, "!"),
+            Implies => write!(f, "implies"),
+
+            // Bitwise
+            Ampersand => write!(f, "&"),
+            VerticalBar => write!(f, "|"),
+            BitXor => write!(f, "bitxor"),
+            Shl => write!(f, "<<"),
+            Shr => write!(f, ">>"),
+
+            // Ranges
+            Range => write!(f, ".."),
+            RangeInclusive => write!(f, "..="),
 
             // Precedence
             LBrace => write!(f, "("),
             RBrace => write!(f, ")"),
 
+            // Conditional
+            Question => write!(f, "?"),
+            Colon => write!(f, ":"),
+            Coalesce => write!(f, "??"),
+            If => write!(f, "if"),
+            Else => write!(f, "else"),
+            LCurlyBrace => write!(f, "{{"),
+            RCurlyBrace => write!(f, "}}"),
+
             // Special
             Comma => write!(f, ","),
             Assign => write!(f, "="),
             Semicolon => write!(f, ";"),
 
+            // Compound assignments
+            PlusAssign => write!(f, "+="),
+            MinusAssign => write!(f, "-="),
+            StarAssign => write!(f, "*="),
+            SlashAssign => write!(f, "/="),
+            PercentAssign => write!(f, "%="),
+            AndAssign => write!(f, "&&="),
+            OrAssign => write!(f, "||="),
+
             // Values => write!(f, ""), Variables and Functions
             Identifier(identifier) => identifier.fmt(f),
             Float(float) => float.fmt(f),
             Int(int) => int.fmt(f),
             Boolean(boolean) => boolean.fmt(f),
             String(string) => fmt::Debug::fmt(string, f),
+            CustomLiteral(value) => value.fmt(f),
+            PreservedLiteral { text, .. } => text.fmt(f),
         }
     }
 }
@@ -0,0 +1,134 @@
+use std::ops::Range;
+
+use error::EvalexprResult;
+use token::{tokenize, Token};
+
+/// The kind of a `LosslessToken`.
+///
+/// New kinds may be added in minor versions as the expression language grows, so matches on it need a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// A run of whitespace characters.
+    Whitespace,
+    /// A comment from `//` up to, but not including, the end of the line.
+    LineComment,
+    /// A comment from `/*` up to and including the next `*/`.
+    BlockComment,
+    /// An operator symbol, like `+`, `==` or `+=`.
+    Operator,
+    /// An opening or closing parenthesis, or a curly brace of a block.
+    Parenthesis,
+    /// The `,` that separates the elements of a tuple.
+    Comma,
+    /// The `;` that chains expressions.
+    Semicolon,
+    /// The identifier of a variable or function.
+    Identifier,
+    /// An integer or float literal.
+    Number,
+    /// The literal `true` or `false`.
+    Boolean,
+    /// A string literal, including its double quotes.
+    String,
+    /// A literal recognized by one of the `ParseConfig::custom_literals`.
+    CustomLiteral,
+}
+
+/// A token of an expression, as returned by `tokenize_lossless`, together with the text it was read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LosslessToken<'a> {
+    /// The kind of the token.
+    pub kind: TokenKind,
+    /// The text of the token, exactly as it appears in the expression.
+    pub text: &'a str,
+    /// The range of bytes of the expression where the token is.
+    pub span: Range<usize>,
+}
+
+impl Token {
+    /// Returns the kind of a lossless token for this token.
+    fn lossless_kind(&self) -> TokenKind {
+        match self {
+            Token::LBrace | Token::RBrace | Token::LCurlyBrace | Token::RCurlyBrace => {
+                TokenKind::Parenthesis
+            },
+            Token::Comma => TokenKind::Comma,
+            Token::Semicolon => TokenKind::Semicolon,
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::Float(_) | Token::Int(_) => TokenKind::Number,
+            Token::Boolean(_) => TokenKind::Boolean,
+            Token::CustomLiteral(_) => TokenKind::CustomLiteral,
+            Token::PreservedLiteral { token, .. } => token.lossless_kind(),
+            Token::String(_) => TokenKind::String,
+            _ => TokenKind::Operator,
+        }
+    }
+}
+
+/// Splits the given expression into tokens without dropping anything, including whitespace and comments.
+///
+/// Every byte of the expression belongs to exactly one token, and the tokens are ordered by their position, so concatenating their texts gives the expression back.
+/// This is meant for tools like formatters that need to keep the comments and the layout of an expression.
+/// The tokens that are not whitespace or comments are the ones that the expression is parsed from, so each `+=` or `>=` is one token, and a string literal is one token with its quotes and escape sequences.
+/// Consecutive whitespace characters form one token, while a comment does not include the line break that ends it.
+///
+/// Fails with the same errors as `build_operator_tree` for expressions that cannot be tokenized, like `EvalexprError::UnterminatedString`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let expression = "a  +=1 // increment\n";
+/// let tokens = tokenize_lossless(expression).unwrap(); // Do proper error handling here
+/// let texts: Vec<_> = tokens.iter().map(|token| token.text).collect();
+/// assert_eq!(texts, ["a", "  ", "+=", "1", " ", "// increment", "\n"]);
+/// assert_eq!(tokens[5].kind, TokenKind::LineComment);
+/// assert_eq!(tokens[5].span, 7..19);
+/// assert_eq!(texts.concat(), expression);
+/// ```
+pub fn tokenize_lossless(string: &str) -> EvalexprResult<Vec<LosslessToken<'_>>> {
+    let mut result = Vec::new();
+    let mut position = 0;
+    for (token, span) in tokenize(string)? {
+        push_trivia(string, position..span.start, &mut result);
+        position = span.end;
+        result.push(LosslessToken {
+            kind: token.lossless_kind(),
+            text: &string[span.clone()],
+            span,
+        });
+    }
+    push_trivia(string, position..string.len(), &mut result);
+    Ok(result)
+}
+
+/// Appends the whitespace and comments between two tokens of `string` as tokens to `result`.
+/// The given range contains only whitespace and complete comments, as the tokenizer skips nothing else.
+fn push_trivia<'a>(string: &'a str, range: Range<usize>, result: &mut Vec<LosslessToken<'a>>) {
+    let mut position = range.start;
+    while position < range.end {
+        let rest = &string[position..range.end];
+        let (kind, length) = if rest.starts_with("//") {
+            (
+                TokenKind::LineComment,
+                rest.find('\n').unwrap_or(rest.len()),
+            )
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let length = comment.find("*/").map_or(rest.len(), |length| length + 4);
+            (TokenKind::BlockComment, length)
+        } else {
+            let length = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (TokenKind::Whitespace, length)
+        };
+        result.push(LosslessToken {
+            kind,
+            text: &rest[..length],
+            span: position..position + length,
+        });
+        position += length;
+    }
+}
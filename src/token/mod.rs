@@ -1,7 +1,13 @@
+use config::{IntegerOverflowPolicy, ParseConfig};
 use error::{EvalexprError, EvalexprResult};
-use value::{FloatType, IntType};
+use std::borrow::Cow;
+use std::ops::Range;
+use value::{FloatType, IntType, Value};
 
 mod display;
+mod lossless;
+
+pub use self::lossless::{tokenize_lossless, LosslessToken, TokenKind};
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Token {
@@ -20,25 +26,64 @@ pub enum Token {
     Lt,
     Geq,
     Leq,
+    RegexMatch,
     And,
     Or,
+    Xor,
     Not,
+    Implies,
+
+    // Bitwise
+    Ampersand,
+    VerticalBar,
+    BitXor,
+    Shl,
+    Shr,
+
+    // Ranges
+    Range,
+    RangeInclusive,
 
     // Precedence
     LBrace,
     RBrace,
 
+    // Conditional
+    Question,
+    Colon,
+    Coalesce,
+    If,
+    Else,
+    LCurlyBrace,
+    RCurlyBrace,
+
     // Special
     Comma,
     Assign,
     Semicolon,
 
+    // Compound assignments
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
+    AndAssign,
+    OrAssign,
+
     // Values, Variables and Functions
     Identifier(String),
     Float(FloatType),
     Int(IntType),
     Boolean(bool),
     String(String),
+    /// The value of a literal recognized by one of the `ParseConfig::custom_literals`.
+    CustomLiteral(Value),
+    /// A number or custom literal token together with the text of its literal, created if `ParseConfig::preserve_literal_text` is set.
+    PreservedLiteral {
+        token: Box<Token>,
+        text: String,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -54,36 +99,78 @@ pub enum PartialToken {
     VerticalBar,
 }
 
-// Make this a const fn as soon as match gets stable (issue #57563)
-fn char_to_partial_token(c: char) -> PartialToken {
-    match c {
-        '+' => PartialToken::Token(Token::Plus),
-        '-' => PartialToken::Token(Token::Minus),
-        '*' => PartialToken::Token(Token::Star),
-        '/' => PartialToken::Token(Token::Slash),
-        '%' => PartialToken::Token(Token::Percent),
-        '^' => PartialToken::Token(Token::Hat),
-
-        '(' => PartialToken::Token(Token::LBrace),
-        ')' => PartialToken::Token(Token::RBrace),
-
-        ',' => PartialToken::Token(Token::Comma),
-        ';' => PartialToken::Token(Token::Semicolon),
-
-        '=' => PartialToken::Eq,
-        '!' => PartialToken::ExclamationMark,
-        '>' => PartialToken::Gt,
-        '<' => PartialToken::Lt,
-        '&' => PartialToken::Ampersand,
-        '|' => PartialToken::VerticalBar,
-
-        c => {
-            if c.is_whitespace() {
-                PartialToken::Whitespace
-            } else {
-                PartialToken::Literal(c.to_string())
-            }
-        },
+/// The class of a byte of an expression, as seen by the tokenizer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ByteClass {
+    /// A character that forms a token or a part of a token on its own, like `+` or `=`.
+    Operator,
+    /// ASCII whitespace.
+    Whitespace,
+    /// The double quote that starts a string literal.
+    Quote,
+    /// Any other ASCII character, which becomes part of a literal.
+    Literal,
+    /// A byte of a multi-byte UTF-8 sequence.
+    /// The character it belongs to is classified by the slow path `classify_non_ascii`.
+    NonAscii,
+}
+
+/// The class of each possible byte.
+static BYTE_CLASSES: [ByteClass; 256] = byte_classes();
+
+const fn byte_classes() -> [ByteClass; 256] {
+    let mut classes = [ByteClass::NonAscii; 256];
+    let mut byte = 0;
+    while byte < 128 {
+        classes[byte] = match byte as u8 {
+            b'+' | b'-' | b'*' | b'/' | b'%' | b'^' | b'(' | b')' | b',' | b';' | b'=' | b'!'
+            | b'>' | b'<' | b'&' | b'|' | b'?' | b':' | b'{' | b'}' => ByteClass::Operator,
+            // The ASCII characters for which `char::is_whitespace` is true.
+            b'\t' | b'\n' | 0x0B | 0x0C | b'\r' | b' ' => ByteClass::Whitespace,
+            b'"' => ByteClass::Quote,
+            _ => ByteClass::Literal,
+        };
+        byte += 1;
+    }
+    classes
+}
+
+/// Returns true if the non-ASCII character at the start of `string` is whitespace, and the length of the character in bytes.
+fn classify_non_ascii(string: &str) -> (bool, usize) {
+    // Non-ASCII bytes are only classified at character boundaries, so the string is never empty.
+    let c = string.chars().next().unwrap();
+    (c.is_whitespace(), c.len_utf8())
+}
+
+/// Converts a byte of class `ByteClass::Operator` to a partial token.
+fn operator_to_partial_token(byte: u8) -> PartialToken {
+    match byte {
+        b'+' => PartialToken::Token(Token::Plus),
+        b'-' => PartialToken::Token(Token::Minus),
+        b'*' => PartialToken::Token(Token::Star),
+        b'/' => PartialToken::Token(Token::Slash),
+        b'%' => PartialToken::Token(Token::Percent),
+        b'^' => PartialToken::Token(Token::Hat),
+
+        b'(' => PartialToken::Token(Token::LBrace),
+        b')' => PartialToken::Token(Token::RBrace),
+
+        b'?' => PartialToken::Token(Token::Question),
+        b':' => PartialToken::Token(Token::Colon),
+        b'{' => PartialToken::Token(Token::LCurlyBrace),
+        b'}' => PartialToken::Token(Token::RCurlyBrace),
+
+        b',' => PartialToken::Token(Token::Comma),
+        b';' => PartialToken::Token(Token::Semicolon),
+
+        b'=' => PartialToken::Eq,
+        b'!' => PartialToken::ExclamationMark,
+        b'>' => PartialToken::Gt,
+        b'<' => PartialToken::Lt,
+        b'&' => PartialToken::Ampersand,
+        b'|' => PartialToken::VerticalBar,
+
+        byte => unreachable!("byte {:?} is not an operator", byte as char),
     }
 }
 
@@ -104,22 +191,52 @@ impl Token {
             Token::Lt => false,
             Token::Geq => false,
             Token::Leq => false,
+            Token::RegexMatch => false,
             Token::And => false,
             Token::Or => false,
+            Token::Xor => false,
             Token::Not => false,
+            Token::Implies => false,
+
+            Token::Ampersand => false,
+            Token::VerticalBar => false,
+            Token::BitXor => false,
+            Token::Shl => false,
+            Token::Shr => false,
+
+            Token::Range => false,
+            Token::RangeInclusive => false,
 
             Token::LBrace => true,
             Token::RBrace => false,
 
+            Token::Question => false,
+            Token::Colon => false,
+            Token::Coalesce => false,
+            Token::If => false,
+            Token::Else => false,
+            Token::LCurlyBrace => false,
+            Token::RCurlyBrace => false,
+
             Token::Comma => false,
             Token::Assign => false,
             Token::Semicolon => false,
 
+            Token::PlusAssign => false,
+            Token::MinusAssign => false,
+            Token::StarAssign => false,
+            Token::SlashAssign => false,
+            Token::PercentAssign => false,
+            Token::AndAssign => false,
+            Token::OrAssign => false,
+
             Token::Identifier(_) => true,
             Token::Float(_) => true,
             Token::Int(_) => true,
             Token::Boolean(_) => true,
             Token::String(_) => true,
+            Token::CustomLiteral(_) => true,
+            Token::PreservedLiteral { .. } => true,
         }
     }
 
@@ -139,162 +256,747 @@ impl Token {
             Token::Lt => false,
             Token::Geq => false,
             Token::Leq => false,
+            Token::RegexMatch => false,
             Token::And => false,
             Token::Or => false,
+            Token::Xor => false,
             Token::Not => false,
+            Token::Implies => false,
+
+            Token::Ampersand => false,
+            Token::VerticalBar => false,
+            Token::BitXor => false,
+            Token::Shl => false,
+            Token::Shr => false,
+
+            Token::Range => false,
+            Token::RangeInclusive => false,
 
             Token::LBrace => false,
             Token::RBrace => true,
 
+            Token::Question => false,
+            Token::Colon => false,
+            Token::Coalesce => false,
+            Token::If => false,
+            Token::Else => false,
+            Token::LCurlyBrace => false,
+            Token::RCurlyBrace => true,
+
             Token::Comma => false,
             Token::Assign => false,
             Token::Semicolon => false,
 
+            Token::PlusAssign => false,
+            Token::MinusAssign => false,
+            Token::StarAssign => false,
+            Token::SlashAssign => false,
+            Token::PercentAssign => false,
+            Token::AndAssign => false,
+            Token::OrAssign => false,
+
             Token::Identifier(_) => true,
             Token::Float(_) => true,
             Token::Int(_) => true,
             Token::Boolean(_) => true,
             Token::String(_) => true,
+            Token::CustomLiteral(_) => true,
+            Token::PreservedLiteral { .. } => true,
         }
     }
+
+    /// Returns true if a leftsided value directly after this token is an operand of it.
+    /// This is the case for all tokens that are not rightsided values and for identifiers, which take the value after them as their argument.
+    /// A closing brace is a rightsided value such that a binary operator may follow it, but it takes no argument, so `(a)(b)` is not a call.
+    pub(crate) fn accepts_value_after(&self) -> bool {
+        !self.is_rightsided_value() || matches!(self, Token::Identifier(_))
+    }
+
+    /// Returns true if this token assigns to the variable before it, like `=` or `+=`.
+    pub(crate) fn is_assignment(&self) -> bool {
+        matches!(
+            self,
+            Token::Assign
+                | Token::PlusAssign
+                | Token::MinusAssign
+                | Token::StarAssign
+                | Token::SlashAssign
+                | Token::PercentAssign
+                | Token::AndAssign
+                | Token::OrAssign
+        )
+    }
 }
 
-/// Parses an escape sequence within a string literal.
-fn parse_escape_sequence<Iter: Iterator<Item = char>>(iter: &mut Iter) -> EvalexprResult<char> {
-    match iter.next() {
-        Some('"') => Ok('"'),
-        Some('\\') => Ok('\\'),
-        Some(c) => Err(EvalexprError::IllegalEscapeSequence(format!("\\{}", c))),
-        None => Err(EvalexprError::IllegalEscapeSequence(format!("\\"))),
+/// Returns the compound assignment token that the given arithmetic token forms if it is directly followed by `=`, like `+=`.
+fn compound_assignment(token: &Token) -> Option<Token> {
+    match token {
+        Token::Plus => Some(Token::PlusAssign),
+        Token::Minus => Some(Token::MinusAssign),
+        Token::Star => Some(Token::StarAssign),
+        Token::Slash => Some(Token::SlashAssign),
+        Token::Percent => Some(Token::PercentAssign),
+        _ => None,
     }
 }
 
-/// Parses a string value from the given character iterator.
+/// Parses a string value from the start of the given string.
 ///
-/// The first character from the iterator is interpreted as first character of the string.
-/// The string is terminated by a double quote `"`.
-/// Occurrences of `"` within the string can be escaped with `\`.
+/// The first character of the string is interpreted as first character of the string value.
+/// The string value is terminated by a double quote `"`.
+/// Occurrences of `"` within the string value can be escaped with `\`.
 /// The backslash needs to be escaped with another backslash `\`.
-fn parse_string_literal<Iter: Iterator<Item = char>>(
-    mut iter: &mut Iter,
-) -> EvalexprResult<PartialToken> {
+/// Newlines, tabs and carriage returns can be written as `\n`, `\t` and `\r`.
+///
+/// Returns the string value and the amount of bytes that were consumed, including the terminating double quote.
+/// Fails with `EvalexprError::UnterminatedString` if there is no terminating double quote.
+fn parse_string_literal(string: &str) -> EvalexprResult<(PartialToken, usize)> {
+    let bytes = string.as_bytes();
     let mut result = String::new();
+    let mut position = 0;
+
+    // Copy everything up to the next double quote or backslash at once.
+    while let Some(offset) = bytes[position..]
+        .iter()
+        .position(|&byte| byte == b'"' || byte == b'\\')
+    {
+        result.push_str(&string[position..position + offset]);
+        position += offset;
+        if bytes[position] == b'"' {
+            return Ok((PartialToken::Token(Token::String(result)), position + 1));
+        }
 
-    while let Some(c) = iter.next() {
-        match c {
-            '"' => break,
-            '\\' => result.push(parse_escape_sequence(&mut iter)?),
-            c => result.push(c),
+        // Parse an escape sequence.
+        match string[position + 1..].chars().next() {
+            Some(c @ '"') | Some(c @ '\\') => result.push(c),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(c) => return Err(EvalexprError::IllegalEscapeSequence(format!("\\{}", c))),
+            None => return Err(EvalexprError::IllegalEscapeSequence("\\".to_string())),
         }
+        position += 2;
     }
 
-    Ok(PartialToken::Token(Token::String(result)))
+    Err(EvalexprError::UnterminatedString(string.to_string()))
 }
 
-/// Converts a string to a vector of partial tokens.
-fn str_to_partial_tokens(string: &str) -> EvalexprResult<Vec<PartialToken>> {
-    let mut result = Vec::new();
-    let mut iter = string.chars().peekable();
+/// Returns true if the given literal is a decimal number followed by the marker of an exponent, like `1.5e` or `2E`.
+/// The number may contain digit separators, like `1_000e`.
+fn is_exponent_prefix(literal: &str) -> bool {
+    match literal.strip_suffix(['e', 'E']) {
+        Some(mantissa) => {
+            mantissa.bytes().any(|byte| byte.is_ascii_digit())
+                && !mantissa.starts_with('_')
+                && mantissa.bytes().filter(|&byte| byte == b'.').count() <= 1
+                && mantissa
+                    .bytes()
+                    .all(|byte| byte.is_ascii_digit() || byte == b'.' || byte == b'_')
+        },
+        None => false,
+    }
+}
+
+/// Returns true if the bytes at the given position are a path separator `::`, like in `str::trim`.
+/// The separator is part of a literal, while a single colon is an operator.
+fn is_path_separator(bytes: &[u8], position: usize) -> bool {
+    bytes[position] == b':' && bytes.get(position + 1) == Some(&b':')
+}
+
+/// Returns true if the bytes at the given position start a range operator `..` or `..=`, like in `1..5`.
+fn is_range_operator(bytes: &[u8], position: usize) -> bool {
+    bytes[position] == b'.' && bytes.get(position + 1) == Some(&b'.')
+}
+
+/// Returns the length in bytes of the literal at the start of the given string.
+///
+/// A literal ends before the first operator, range operator, whitespace or double quote.
+/// The sign of an exponent, like in `1.5e-3`, is part of the literal if it is followed by a digit.
+fn literal_length(string: &str) -> usize {
+    let bytes = string.as_bytes();
+    let mut position = 0;
+
+    while position < bytes.len() {
+        match BYTE_CLASSES[bytes[position] as usize] {
+            // Two dots are a range operator, so `1..5` is not a single literal.
+            ByteClass::Literal if is_range_operator(bytes, position) => break,
+            ByteClass::Literal => position += 1,
+            ByteClass::Operator if is_path_separator(bytes, position) => position += 2,
+            ByteClass::Operator
+                if matches!(bytes[position], b'+' | b'-')
+                    && bytes.get(position + 1).is_some_and(u8::is_ascii_digit)
+                    && is_exponent_prefix(&string[..position]) =>
+            {
+                position += 1
+            },
+            ByteClass::NonAscii => {
+                let (is_whitespace, length) = classify_non_ascii(&string[position..]);
+                if is_whitespace {
+                    break;
+                }
+                position += length;
+            },
+            ByteClass::Operator | ByteClass::Whitespace | ByteClass::Quote => break,
+        }
+    }
+
+    position
+}
+
+/// Returns true if the given literal is a valid integer or float literal, including integers that are out of range.
+fn is_number_literal(literal: &str) -> bool {
+    let token = literal_to_token(
+        literal.to_string(),
+        &ParseConfig::default(),
+        &mut Vec::new(),
+    );
+    matches!(
+        token,
+        Ok(Token::Int(_) | Token::Float(_)) | Err(EvalexprError::IntegerLiteralOutOfRange(_))
+    )
+}
 
-    while let Some(c) = iter.next() {
-        if c == '"' {
-            result.push(parse_string_literal(&mut iter)?);
+/// Returns the token of the first custom literal of the configuration that is recognized at the start of the given string, and its length in bytes.
+/// The string starts at the given offset of the expression.
+///
+/// A match is discarded if it is directly followed by further characters of a literal, or if it starts with a digit but does not span the whole literal at the start of the string, or the literal is a number.
+fn custom_literal(
+    string: &str,
+    offset: usize,
+    config: &ParseConfig,
+) -> EvalexprResult<Option<(PartialToken, usize)>> {
+    let starts_with_digit = string.starts_with(|c: char| c.is_ascii_digit());
+    for custom_literal in &config.custom_literals {
+        let length = match custom_literal.match_length(string) {
+            Some(length) => length,
+            None => continue,
+        };
+        if literal_length(&string[length..]) > 0 {
+            continue;
+        }
+        let literal = &string[..length];
+        if starts_with_digit && (literal_length(string) != length || is_number_literal(literal)) {
+            continue;
+        }
+
+        let value = custom_literal.convert(literal).map_err(|error| {
+            EvalexprError::InvalidCustomLiteral {
+                literal: literal.to_string(),
+                span: offset..offset + length,
+                source: Box::new(error),
+            }
+        })?;
+        let token = if config.preserve_literal_text {
+            Token::PreservedLiteral {
+                token: Box::new(Token::CustomLiteral(value)),
+                text: literal.to_string(),
+            }
         } else {
-            let partial_token = char_to_partial_token(c);
+            Token::CustomLiteral(value)
+        };
+        return Ok(Some((PartialToken::Token(token), length)));
+    }
+    Ok(None)
+}
 
-            let if_let_successful =
-                if let (Some(PartialToken::Literal(last)), PartialToken::Literal(literal)) =
-                    (result.last_mut(), &partial_token)
-                {
-                    last.push_str(literal);
-                    true
+/// Converts a string to a vector of partial tokens.
+/// The byte offset of each partial token within the string is appended to `offsets`, followed by the length of the string.
+fn str_to_partial_tokens(
+    string: &str,
+    config: &ParseConfig,
+    offsets: &mut Vec<usize>,
+) -> EvalexprResult<Vec<PartialToken>> {
+    let bytes = string.as_bytes();
+    let mut result = Vec::new();
+    let mut position = 0;
+
+    while position < bytes.len() {
+        offsets.push(position);
+        let byte = bytes[position];
+        match BYTE_CLASSES[byte as usize] {
+            // A line comment separates tokens like whitespace, and ends before the next newline.
+            ByteClass::Operator if byte == b'/' && bytes.get(position + 1) == Some(&b'/') => {
+                result.push(PartialToken::Whitespace);
+                position += bytes[position..]
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .unwrap_or(bytes.len() - position);
+            },
+            // A block comment separates tokens like whitespace as well, and ends with the first `*/`.
+            ByteClass::Operator if byte == b'/' && bytes.get(position + 1) == Some(&b'*') => {
+                let text = &string[position + 2..];
+                match text.find("*/") {
+                    Some(length) => {
+                        result.push(PartialToken::Whitespace);
+                        position += 2 + length + 2;
+                    },
+                    None => return Err(EvalexprError::UnterminatedComment(text.to_string())),
+                }
+            },
+            ByteClass::Literal if is_range_operator(bytes, position) => {
+                if bytes.get(position + 2) == Some(&b'=') {
+                    result.push(PartialToken::Token(Token::RangeInclusive));
+                    position += 3;
                 } else {
-                    false
-                };
-
-            if !if_let_successful {
+                    result.push(PartialToken::Token(Token::Range));
+                    position += 2;
+                }
+            },
+            ByteClass::Operator if byte == b'=' && bytes.get(position + 1) == Some(&b'~') => {
+                result.push(PartialToken::Token(Token::RegexMatch));
+                position += 2;
+            },
+            ByteClass::Operator if byte == b'?' && bytes.get(position + 1) == Some(&b'?') => {
+                result.push(PartialToken::Token(Token::Coalesce));
+                position += 2;
+            },
+            ByteClass::Operator if !is_path_separator(bytes, position) => {
+                result.push(operator_to_partial_token(byte));
+                position += 1;
+            },
+            ByteClass::Whitespace => {
+                result.push(PartialToken::Whitespace);
+                position += 1;
+            },
+            ByteClass::Quote => {
+                let (partial_token, length) = parse_string_literal(&string[position + 1..])?;
                 result.push(partial_token);
-            }
+                position += 1 + length;
+            },
+            ByteClass::NonAscii if classify_non_ascii(&string[position..]).0 => {
+                result.push(PartialToken::Whitespace);
+                position += classify_non_ascii(&string[position..]).1;
+            },
+            ByteClass::Literal | ByteClass::NonAscii | ByteClass::Operator => {
+                if let Some((partial_token, length)) =
+                    custom_literal(&string[position..], position, config)?
+                {
+                    result.push(partial_token);
+                    position += length;
+                    continue;
+                }
+                let length = literal_length(&string[position..]);
+                result.push(PartialToken::Literal(
+                    string[position..position + length].to_string(),
+                ));
+                position += length;
+            },
         }
     }
+    offsets.push(bytes.len());
     Ok(result)
 }
 
+/// A problem in an expression that does not prevent parsing it, but may change its meaning.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseWarning {
+    /// An integer literal is out of the range of `IntType`, and was converted to the nearest `FloatType`.
+    /// This happens only if `ParseConfig::integer_overflow_policy` is `IntegerOverflowPolicy::ConvertToFloat`.
+    IntegerLiteralConvertedToFloat {
+        /// The integer literal as written in the expression.
+        literal: String,
+        /// The float that the literal was converted to.
+        value: FloatType,
+    },
+    /// A variable has an identifier that is reserved for syntax that may be added in a later version, like `in` or `null`.
+    /// The expression is parsed as usual for now, but the variable should be renamed, as the expression may fail to parse or change its meaning once the syntax is added.
+    /// See `SOFT_RESERVED_WORDS` for the reserved identifiers.
+    ReservedIdentifier {
+        /// The identifier of the variable.
+        identifier: String,
+        /// The range of bytes of the expression where the variable is.
+        span: Range<usize>,
+    },
+}
+
+/// Words that have a meaning in the syntax of expressions, and therefore cannot be used as identifiers.
+/// Assigning to them fails with `EvalexprError::AssignmentToKeyword`.
+pub const HARD_KEYWORDS: &[&str] = &["true", "false", "xor", "bitxor", "implies", "if", "else"];
+
+/// The operators and other tokens that are written as words, with the word that denotes each of them.
+/// Each of these words must also be listed in `HARD_KEYWORDS`.
+const KEYWORD_TOKENS: &[(&str, Token)] = &[
+    ("xor", Token::Xor),
+    ("bitxor", Token::BitXor),
+    ("implies", Token::Implies),
+    ("if", Token::If),
+    ("else", Token::Else),
+];
+
+/// Words that are reserved for syntax that may be added in a later version.
+/// They can still be used as identifiers of variables, but each such use is reported as a `ParseWarning::ReservedIdentifier`.
+pub const SOFT_RESERVED_WORDS: &[&str] = &["and", "as", "in", "not", "null", "or"];
+
+/// Returns the radix and the digits of a hexadecimal, octal or binary integer literal like `0xFF`, `0o17` or `0b1010`.
+/// The prefix may be written in upper or lower case, and hexadecimal digits in any case.
+fn radix_literal(literal: &str) -> Option<(u32, &str)> {
+    let radix = match literal.as_bytes() {
+        [b'0', b'x', _, ..] | [b'0', b'X', _, ..] => 16,
+        [b'0', b'o', _, ..] | [b'0', b'O', _, ..] => 8,
+        [b'0', b'b', _, ..] | [b'0', b'B', _, ..] => 2,
+        _ => return None,
+    };
+    let digits = &literal[2..];
+    if digits.chars().all(|c| c.is_digit(radix)) {
+        Some((radix, digits))
+    } else {
+        None
+    }
+}
+
+/// Returns the given literal without the underscores that separate its digits, like in `10_000` or `0xFF_FF`.
+///
+/// Only literals that start with a digit contain digit separators, so identifiers like `_foo` or `foo_bar` are returned as they are.
+/// Each underscore must stand between two digits, so literals like `1__000`, `1_` or `1_.5` fail with `EvalexprError::InvalidNumberLiteral`.
+fn strip_digit_separators(literal: &str) -> EvalexprResult<Cow<'_, str>> {
+    if !literal.starts_with(|c: char| c.is_ascii_digit()) || !literal.contains('_') {
+        return Ok(Cow::Borrowed(literal));
+    }
+
+    let radix = match literal.as_bytes() {
+        [b'0', b'x' | b'X', ..] => 16,
+        [b'0', b'o' | b'O', ..] => 8,
+        [b'0', b'b' | b'B', ..] => 2,
+        _ => 10,
+    };
+    let is_digit = |byte: Option<&u8>| byte.is_some_and(|&byte| (byte as char).is_digit(radix));
+    let bytes = literal.as_bytes();
+    let is_separator_valid = bytes.iter().enumerate().all(|(index, &byte)| {
+        byte != b'_'
+            || (index > 0 && is_digit(bytes.get(index - 1)) && is_digit(bytes.get(index + 1)))
+    });
+
+    if is_separator_valid {
+        Ok(Cow::Owned(literal.replace('_', "")))
+    } else {
+        Err(EvalexprError::InvalidNumberLiteral(literal.to_string()))
+    }
+}
+
+/// Converts an integer literal that is too large for `IntType` according to the integer overflow policy.
+/// The value is the nearest float to the literal.
+fn out_of_range_integer_literal(
+    literal: String,
+    value: FloatType,
+    config: &ParseConfig,
+    warnings: &mut Vec<ParseWarning>,
+) -> EvalexprResult<Token> {
+    match config.integer_overflow_policy {
+        IntegerOverflowPolicy::Error => Err(EvalexprError::IntegerLiteralOutOfRange(literal)),
+        IntegerOverflowPolicy::ConvertToFloat => {
+            warnings.push(ParseWarning::IntegerLiteralConvertedToFloat { literal, value });
+            Ok(Token::Float(value))
+        },
+    }
+}
+
+/// Returns the start of the unary minus before the given literal if both together denote `IntType::MIN`, like in `-9223372036854775808` or `-0x8000000000000000`.
+/// The literal alone is out of range, as `IntType::MIN` has no positive counterpart.
+/// Returns `None` if the literal is the base of an exponentiation like in `-9223372036854775808 ^ 2`, as the unary minus binds looser than `^`.
+fn negated_min_literal_start(
+    literal: &str,
+    preceding: &[(Token, Range<usize>)],
+    following: &[PartialToken],
+) -> Option<usize> {
+    let number = strip_digit_separators(literal).ok()?;
+    let magnitude = match radix_literal(&number) {
+        Some((radix, digits)) => u128::from_str_radix(digits, radix).ok()?,
+        None if number.bytes().all(|byte| byte.is_ascii_digit()) => number.parse::<u128>().ok()?,
+        None => return None,
+    };
+    let is_exponentiated = following
+        .iter()
+        .find(|token| **token != PartialToken::Whitespace)
+        == Some(&PartialToken::Token(Token::Hat));
+    if magnitude != u128::from(IntType::MIN.unsigned_abs()) || is_exponentiated {
+        return None;
+    }
+
+    match preceding {
+        [.., (previous, _), (Token::Minus, span)] if !previous.is_rightsided_value() => {
+            Some(span.start)
+        },
+        [(Token::Minus, span)] => Some(span.start),
+        _ => None,
+    }
+}
+
+/// Converts a literal to a number, boolean or identifier token.
+fn literal_to_token(
+    literal: String,
+    config: &ParseConfig,
+    warnings: &mut Vec<ParseWarning>,
+) -> EvalexprResult<Token> {
+    let number = strip_digit_separators(&literal)?;
+    if let Some((radix, digits)) = radix_literal(&number) {
+        match IntType::from_str_radix(digits, radix) {
+            Ok(number) => Ok(Token::Int(number)),
+            Err(_) => {
+                // Literals beyond `u128` are larger than any float that is not infinite anyway.
+                let value = u128::from_str_radix(digits, radix)
+                    .map_or(FloatType::INFINITY, |number| number as FloatType);
+                out_of_range_integer_literal(literal, value, config, warnings)
+            },
+        }
+    } else if let Ok(number) = number.parse::<IntType>() {
+        Ok(Token::Int(number))
+    } else if number.bytes().all(|byte| byte.is_ascii_digit()) {
+        // The literal is an integer, but too large for `IntType`.
+        // Parsing a string of digits as float cannot fail.
+        let value = number.parse::<FloatType>().unwrap_or(FloatType::INFINITY);
+        out_of_range_integer_literal(literal, value, config, warnings)
+    } else if let Ok(number) = number.parse::<FloatType>() {
+        Ok(Token::Float(number))
+    } else if let Ok(boolean) = literal.parse::<bool>() {
+        Ok(Token::Boolean(boolean))
+    } else if let Some((_, token)) = KEYWORD_TOKENS
+        .iter()
+        .find(|(keyword, _)| *keyword == literal)
+    {
+        Ok(token.clone())
+    } else if is_exponent_prefix(&number) {
+        // The exponent has no digits.
+        Err(EvalexprError::InvalidNumberLiteral(literal))
+    } else {
+        Ok(Token::Identifier(literal))
+    }
+}
+
+/// Wraps a number token into a `Token::PreservedLiteral` with the given text of its literal if the configuration asks for it.
+fn preserve_literal_text(token: Token, literal: &str, config: &ParseConfig) -> Token {
+    match token {
+        Token::Int(_) | Token::Float(_) if config.preserve_literal_text => {
+            Token::PreservedLiteral {
+                token: Box::new(token),
+                text: literal.to_string(),
+            }
+        },
+        token => token,
+    }
+}
+
+/// Splits a literal like `2x` or `1.5y` into a decimal number and the identifier that follows it, if the configuration enables implicit multiplication.
+/// Returns `None` if the literal is a number as a whole, like `2e3` or `0x1F`, or if it does not consist of a decimal number followed by an identifier that starts with a letter.
+fn split_coefficient<'a>(literal: &'a str, config: &ParseConfig) -> Option<(&'a str, &'a str)> {
+    if !config.implicit_multiplication || is_number_literal(literal) {
+        return None;
+    }
+
+    let digits = |string: &str| string.bytes().take_while(u8::is_ascii_digit).count();
+    let mut length = digits(literal);
+    if length > 0 && literal[length..].starts_with('.') {
+        let decimals = digits(&literal[length + 1..]);
+        if decimals > 0 {
+            length += 1 + decimals;
+        }
+    }
+    let (coefficient, factor) = literal.split_at(length);
+    let is_identifier = matches!(
+        literal_to_token(factor.to_string(), config, &mut Vec::new()),
+        Ok(Token::Identifier(_))
+    );
+    if length > 0 && factor.starts_with(char::is_alphabetic) && is_identifier {
+        Some((coefficient, factor))
+    } else {
+        None
+    }
+}
+
+/// Inserts a `Token::Star` between a number and a following identifier or opening brace, and between a closing and an opening brace, for `ParseConfig::implicit_multiplication`.
+/// The inserted tokens have an empty range at the end of the token that they follow.
+fn insert_implicit_multiplications(
+    tokens: Vec<(Token, Range<usize>)>,
+) -> Vec<(Token, Range<usize>)> {
+    let mut result: Vec<(Token, Range<usize>)> = Vec::with_capacity(tokens.len());
+    for (token, span) in tokens {
+        if let Some((previous, previous_span)) = result.last() {
+            let is_number = match previous {
+                Token::PreservedLiteral { token, .. } => is_number_token(token),
+                previous => is_number_token(previous),
+            };
+            let multiplies = match token {
+                Token::Identifier(_) | Token::LBrace => is_number,
+                _ => false,
+            } || (*previous == Token::RBrace && token == Token::LBrace);
+            if multiplies {
+                let end = previous_span.end;
+                result.push((Token::Star, end..end));
+            }
+        }
+        result.push((token, span));
+    }
+    result
+}
+
+/// Returns true if the token is an integer or float.
+fn is_number_token(token: &Token) -> bool {
+    matches!(token, Token::Int(_) | Token::Float(_))
+}
+
+/// Returns true if an identifier followed by the given partial tokens is the identifier of a function, like in `f(x)` or `f x`.
+/// This mirrors the distinction between variable and function identifiers when building the operator tree.
+fn is_function_identifier(following: &[PartialToken]) -> bool {
+    match following
+        .iter()
+        .find(|token| **token != PartialToken::Whitespace)
+    {
+        Some(PartialToken::Token(token)) => token.is_leftsided_value(),
+        Some(PartialToken::Literal(_)) => true,
+        _ => false,
+    }
+}
+
 /// Resolves all partial tokens by converting them to complex tokens.
-fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> EvalexprResult<Vec<Token>> {
+/// Each token is paired with the range of bytes of the string that it was read from, given the byte offsets of the partial tokens like returned by `str_to_partial_tokens`.
+fn partial_tokens_to_tokens(
+    mut tokens: &[PartialToken],
+    offsets: &[usize],
+    config: &ParseConfig,
+    warnings: &mut Vec<ParseWarning>,
+) -> EvalexprResult<Vec<(Token, Range<usize>)>> {
     let mut result = Vec::new();
-    while tokens.len() > 0 {
+    let mut index = 0;
+    while !tokens.is_empty() {
         let first = tokens[0].clone();
         let second = tokens.get(1).cloned();
         let mut cutoff = 2;
+        let span = |cutoff: usize| offsets[index]..offsets[index + cutoff];
 
-        result.extend(
-            match first {
-                PartialToken::Token(token) => {
+        let token = match first {
+            PartialToken::Token(token) => match (compound_assignment(&token), second) {
+                (Some(compound_assignment), Some(PartialToken::Eq)) => Some(compound_assignment),
+                _ => {
                     cutoff = 1;
                     Some(token)
                 },
-                PartialToken::Literal(literal) => {
-                    cutoff = 1;
-                    if let Ok(number) = literal.parse::<IntType>() {
-                        Some(Token::Int(number))
-                    } else if let Ok(number) = literal.parse::<FloatType>() {
-                        Some(Token::Float(number))
-                    } else if let Ok(boolean) = literal.parse::<bool>() {
-                        Some(Token::Boolean(boolean))
-                    } else {
-                        Some(Token::Identifier(literal.to_string()))
+            },
+            PartialToken::Literal(literal) => {
+                cutoff = 1;
+                if let Some((coefficient, factor)) = split_coefficient(&literal, config) {
+                    let boundary = offsets[index] + coefficient.len();
+                    let token = literal_to_token(coefficient.to_string(), config, warnings)?;
+                    result.push((
+                        preserve_literal_text(token, coefficient, config),
+                        offsets[index]..boundary,
+                    ));
+                    result.push((
+                        Token::Identifier(factor.to_string()),
+                        boundary..offsets[index + 1],
+                    ));
+                    tokens = &tokens[1..];
+                    index += 1;
+                    continue;
+                }
+
+                if let Some(start) = negated_min_literal_start(&literal, &result, &tokens[1..]) {
+                    result.pop();
+                    let text = format!("-{}", literal);
+                    result.push((
+                        preserve_literal_text(Token::Int(IntType::MIN), &text, config),
+                        start..offsets[index + 1],
+                    ));
+                    tokens = &tokens[1..];
+                    index += 1;
+                    continue;
+                }
+
+                let token = literal_to_token(literal.to_string(), config, warnings)?;
+                if let Token::Identifier(identifier) = &token {
+                    if SOFT_RESERVED_WORDS.contains(&identifier.as_str())
+                        && !is_function_identifier(&tokens[1..])
+                    {
+                        warnings.push(ParseWarning::ReservedIdentifier {
+                            identifier: identifier.clone(),
+                            span: span(1),
+                        });
                     }
+                }
+                Some(preserve_literal_text(token, &literal, config))
+            },
+            PartialToken::Whitespace => {
+                cutoff = 1;
+                None
+            },
+            PartialToken::Eq => match second {
+                Some(PartialToken::Eq) => Some(Token::Eq),
+                _ => {
+                    cutoff = 1;
+                    Some(Token::Assign)
                 },
-                PartialToken::Whitespace => {
+            },
+            PartialToken::ExclamationMark => match second {
+                Some(PartialToken::Eq) => Some(Token::Neq),
+                _ => {
                     cutoff = 1;
-                    None
+                    Some(Token::Not)
                 },
-                PartialToken::Eq => match second {
-                    Some(PartialToken::Eq) => Some(Token::Eq),
-                    _ => {
-                        cutoff = 1;
-                        Some(Token::Assign)
-                    },
+            },
+            PartialToken::Gt => match second {
+                Some(PartialToken::Eq) => Some(Token::Geq),
+                Some(PartialToken::Gt) => Some(Token::Shr),
+                _ => {
+                    cutoff = 1;
+                    Some(Token::Gt)
                 },
-                PartialToken::ExclamationMark => match second {
-                    Some(PartialToken::Eq) => Some(Token::Eq),
-                    _ => {
-                        cutoff = 1;
-                        Some(Token::Not)
-                    },
+            },
+            PartialToken::Lt => match second {
+                Some(PartialToken::Eq) => Some(Token::Leq),
+                Some(PartialToken::Lt) => Some(Token::Shl),
+                _ => {
+                    cutoff = 1;
+                    Some(Token::Lt)
                 },
-                PartialToken::Gt => match second {
-                    Some(PartialToken::Eq) => Some(Token::Geq),
-                    _ => {
-                        cutoff = 1;
-                        Some(Token::Gt)
-                    },
+            },
+            PartialToken::Ampersand => match second {
+                Some(PartialToken::Ampersand) if tokens.get(2) == Some(&PartialToken::Eq) => {
+                    cutoff = 3;
+                    Some(Token::AndAssign)
                 },
-                PartialToken::Lt => match second {
-                    Some(PartialToken::Eq) => Some(Token::Leq),
-                    _ => {
-                        cutoff = 1;
-                        Some(Token::Lt)
-                    },
+                Some(PartialToken::Ampersand) => Some(Token::And),
+                _ => {
+                    cutoff = 1;
+                    Some(Token::Ampersand)
                 },
-                PartialToken::Ampersand => match second {
-                    Some(PartialToken::Ampersand) => Some(Token::And),
-                    _ => return Err(EvalexprError::unmatched_partial_token(first, second)),
+            },
+            PartialToken::VerticalBar => match second {
+                Some(PartialToken::VerticalBar) if tokens.get(2) == Some(&PartialToken::Eq) => {
+                    cutoff = 3;
+                    Some(Token::OrAssign)
                 },
-                PartialToken::VerticalBar => match second {
-                    Some(PartialToken::VerticalBar) => Some(Token::Or),
-                    _ => return Err(EvalexprError::unmatched_partial_token(first, second)),
+                Some(PartialToken::VerticalBar) => Some(Token::Or),
+                _ => {
+                    cutoff = 1;
+                    Some(Token::VerticalBar)
                 },
-            }
-            .into_iter(),
-        );
+            },
+        };
 
+        if let Some(token) = token {
+            result.push((token, span(cutoff)));
+        }
         tokens = &tokens[cutoff..];
+        index += cutoff;
     }
     Ok(result)
 }
 
-pub(crate) fn tokenize(string: &str) -> EvalexprResult<Vec<Token>> {
-    partial_tokens_to_tokens(&str_to_partial_tokens(string)?)
+/// Converts a string to tokens, each paired with the range of bytes of the string that it was read from.
+pub(crate) fn tokenize(string: &str) -> EvalexprResult<Vec<(Token, Range<usize>)>> {
+    tokenize_with_config(string, &ParseConfig::default(), &mut Vec::new())
+}
+
+/// Converts a string to tokens like `tokenize`, but according to the given configuration, and appends warnings about the string to `warnings`.
+pub(crate) fn tokenize_with_config(
+    string: &str,
+    config: &ParseConfig,
+    warnings: &mut Vec<ParseWarning>,
+) -> EvalexprResult<Vec<(Token, Range<usize>)>> {
+    let mut offsets = Vec::new();
+    let partial_tokens = str_to_partial_tokens(string, config, &mut offsets)?;
+    let tokens = partial_tokens_to_tokens(&partial_tokens, &offsets, config, warnings)?;
+    if config.implicit_multiplication {
+        Ok(insert_implicit_multiplications(tokens))
+    } else {
+        Ok(tokens)
+    }
 }
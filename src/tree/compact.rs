@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::ptr;
+
+use context::{Context, ItContext};
+use error::{EvalexprError, EvalexprResult};
+use function::higher_order::{
+    eval_higher_order, expect_higher_order_arguments, higher_order_identifier,
+};
+use operator::Operator;
+use state::EvalState;
+use value::Value;
+use EmptyContext;
+use Node;
+
+/// An operator tree that stores all of its nodes in a single vector.
+///
+/// A `Node` owns a separate vector for the children of each node, so its nodes are scattered over the heap.
+/// A `CompactTree` stores the nodes in breadth-first order instead, such that the children of each node are adjacent and referenced by their index.
+/// This needs a single allocation for the whole tree, which makes evaluating it more cache-friendly.
+/// The benefit shows when evaluating many different trees, as in `benches/compact_tree.rs`, while building a `CompactTree` takes an additional pass over the `Node`.
+///
+/// A `CompactTree` is created from a `Node` with `Node::compact`, or from an expression string with the crate-level `build_compact_tree` method.
+/// It evaluates exactly like the `Node` it was created from.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let tree = build_compact_tree("a * 2 + 1").unwrap(); // Do proper error handling here
+/// let context = context_map! { "a" => 4 }.unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(9)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompactTree {
+    nodes: Vec<CompactNode>,
+    /// The indices of the nodes that read variables, in ascending order, paired with the range of bytes of the source that they were parsed from.
+    /// They are kept apart from the nodes, as they are only needed to report variables that are not found.
+    spans: Vec<(u32, Range<usize>)>,
+}
+
+/// A node of a `CompactTree`.
+/// Its children are the nodes at the indices `first_child..first_child + child_count` of the tree.
+#[derive(Clone, Debug, PartialEq)]
+struct CompactNode {
+    operator: Operator,
+    first_child: u32,
+    child_count: u32,
+}
+
+impl CompactTree {
+    /// Stores the operator tree rooted at the given node in breadth-first order.
+    ///
+    /// Panics if the tree has more than `u32::MAX` nodes.
+    pub(crate) fn new(root: &Node) -> Self {
+        let mut nodes = Vec::new();
+        let mut spans = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+        // The children of the nodes in the queue are stored after all nodes that are already queued.
+        let mut next_index = 1;
+
+        while let Some(node) = queue.pop_front() {
+            if let (Operator::VariableIdentifier { .. }, Some(span)) = (node.operator(), &node.span)
+            {
+                spans.push((to_index(nodes.len()), span.clone()));
+            }
+            let children = node.children();
+            nodes.push(CompactNode {
+                operator: node.operator().clone(),
+                first_child: to_index(next_index),
+                child_count: to_index(children.len()),
+            });
+            next_index += children.len();
+            queue.extend(children);
+        }
+
+        CompactTree { nodes, spans }
+    }
+
+    /// Returns the amount of nodes in this tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if this tree has no nodes.
+    /// This is never the case for trees that were created from a `Node`, as they contain at least a root node.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Evaluates this tree with the given context.
+    ///
+    /// Fails, if one of the operators in the expression tree fails.
+    pub fn eval_with_context(&self, context: &dyn Context) -> EvalexprResult<Value> {
+        EvalState::with_default(|state| self.eval_with_context_and_state(context, state))
+    }
+
+    /// Evaluates this tree with the given mutable context.
+    ///
+    /// Fails, if one of the operators in the expression tree fails.
+    pub fn eval_with_context_mut(&self, context: &mut dyn Context) -> EvalexprResult<Value> {
+        EvalState::with_default(|state| self.eval_with_context_mut_and_state(context, state))
+    }
+
+    /// Evaluates this tree with the given context and evaluation state.
+    ///
+    /// The evaluation counts as nested into all other evaluations that currently run with the same state.
+    ///
+    /// Fails, if one of the operators in the expression tree fails, or if the maximum recursion depth of the state is exceeded.
+    pub fn eval_with_context_and_state(
+        &self,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let _guard = state.enter()?;
+        self.eval_recursively(&self.nodes[0], context, state)
+    }
+
+    /// Evaluates this tree with the given mutable context and evaluation state.
+    ///
+    /// The evaluation counts as nested into all other evaluations that currently run with the same state.
+    ///
+    /// Fails, if one of the operators in the expression tree fails, or if the maximum recursion depth of the state is exceeded.
+    pub fn eval_with_context_mut_and_state(
+        &self,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let _guard = state.enter()?;
+        self.eval_recursively_mut(&self.nodes[0], context, state)
+    }
+
+    /// Evaluates this tree with an empty context.
+    ///
+    /// Fails, if one of the operators in the expression tree fails.
+    pub fn eval(&self) -> EvalexprResult<Value> {
+        self.eval_with_context(&EmptyContext)
+    }
+
+    /// Returns the operator of the node at the given index.
+    #[cfg(feature = "async")]
+    pub(crate) fn operator(&self, index: usize) -> &Operator {
+        &self.nodes[index].operator
+    }
+
+    /// Returns the operator of the node at the given index, after stripping root nodes like `Node::strip_root_nodes`.
+    #[cfg(feature = "async")]
+    pub(crate) fn stripped_operator(&self, index: usize) -> &Operator {
+        &self.strip_root_nodes(&self.nodes[index]).operator
+    }
+
+    /// Returns the indices of the children of the node at the given index.
+    #[cfg(feature = "async")]
+    pub(crate) fn child_indices(&self, index: usize) -> Range<usize> {
+        let node = &self.nodes[index];
+        let first_child = node.first_child as usize;
+        first_child..first_child + node.child_count as usize
+    }
+
+    /// Evaluates the node at the given index with the given context as part of an evaluation that runs with the given state.
+    /// Calls of async functions within the node are not awaited, so they fail like in a synchronous evaluation.
+    #[cfg(feature = "async")]
+    pub(crate) fn eval_node_with_context_and_state(
+        &self,
+        index: usize,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        self.eval_recursively(&self.nodes[index], context, state)
+    }
+
+    /// Adds the span of the node at the given index to an error about a variable that the node reads, but that is not found.
+    /// This mirrors `Node::locate_error`.
+    pub(crate) fn locate_error(&self, index: usize, error: EvalexprError) -> EvalexprError {
+        match error {
+            EvalexprError::VariableIdentifierNotFound {
+                identifier,
+                span: None,
+            } if matches!(
+                self.nodes[index].operator,
+                Operator::VariableIdentifier { .. }
+            ) =>
+            {
+                let span = self
+                    .spans
+                    .binary_search_by_key(&to_index(index), |(index, _)| *index)
+                    .ok()
+                    .map(|position| self.spans[position].1.clone());
+                EvalexprError::VariableIdentifierNotFound { identifier, span }
+            },
+            error => error,
+        }
+    }
+
+    /// Returns the index of the given node, which must be one of the nodes of this tree.
+    fn index_of(&self, node: &CompactNode) -> usize {
+        self.nodes
+            .iter()
+            .position(|candidate| ptr::eq(candidate, node))
+            .expect("the node belongs to this tree")
+    }
+
+    /// Returns the children of the given node.
+    fn children(&self, node: &CompactNode) -> &[CompactNode] {
+        let first_child = node.first_child as usize;
+        &self.nodes[first_child..first_child + node.child_count as usize]
+    }
+
+    /// Evaluates the children of the given node from left to right, and then the node itself.
+    /// This mirrors `Node::eval_recursively`.
+    fn eval_recursively(
+        &self,
+        node: &CompactNode,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let operator = &node.operator;
+        if let Some((identifier, expression)) = Node::named_expression(operator, context) {
+            let _guard = state.enter_expression(identifier)?;
+            return expression.eval_recursively(context, state);
+        }
+        if let Some(identifier) = higher_order_identifier(operator, context) {
+            return self.eval_higher_order(node, identifier, context, state);
+        }
+
+        let mut arguments = state.take_arguments();
+        for child in self.children(node) {
+            if operator.skip_argument(&mut arguments) {
+                continue;
+            }
+            if let Some(result) = operator.push_argument(
+                &mut arguments,
+                self.eval_recursively(child, context, state),
+                &self.strip_root_nodes(child).operator,
+            )? {
+                state.recycle_arguments(arguments);
+                return Ok(result);
+            }
+        }
+        state.check_cancelled()?;
+        let allocation_hint = Node::allocation_hint(operator, &arguments, context, state);
+        let result = Node::allocate(state, allocation_hint, || {
+            let result = match state.eval_in_arena(operator, &arguments, context) {
+                Some(value) => Ok(value),
+                None => operator
+                    .eval(&arguments, context, state.config())
+                    .map_err(|error| self.locate_error(self.index_of(node), error)),
+            };
+            Node::apply_nan_policy(operator, &arguments, context, state, result)
+        });
+        state.recycle_arguments(arguments);
+        result
+    }
+
+    /// Evaluates the children of the given node from left to right, and then the node itself.
+    /// This mirrors `Node::eval_recursively_mut`.
+    fn eval_recursively_mut(
+        &self,
+        node: &CompactNode,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let operator = &node.operator;
+        if let Some((identifier, expression)) = Node::named_expression(operator, context) {
+            let _guard = state.enter_expression(identifier)?;
+            return expression.eval_recursively_mut(context, state);
+        }
+        if let Some(identifier) = higher_order_identifier(operator, context) {
+            return self.eval_higher_order_mut(node, identifier, context, state);
+        }
+
+        let mut arguments = state.take_arguments();
+        for child in self.children(node) {
+            if operator.skip_argument(&mut arguments) {
+                continue;
+            }
+            if let Some(result) = operator.push_argument(
+                &mut arguments,
+                self.eval_recursively_mut(child, context, state),
+                &self.strip_root_nodes(child).operator,
+            )? {
+                state.recycle_arguments(arguments);
+                return Ok(result);
+            }
+        }
+        state.check_cancelled()?;
+        let allocation_hint = Node::allocation_hint(operator, &arguments, context, state);
+        let result = Node::allocate(state, allocation_hint, || {
+            let result = match state.eval_in_arena(operator, &arguments, &*context) {
+                Some(value) => Ok(value),
+                None => operator
+                    .eval_mut(&arguments, context, state.config())
+                    .map_err(|error| self.locate_error(self.index_of(node), error)),
+            };
+            Node::apply_nan_policy(operator, &arguments, context, state, result)
+        });
+        state.recycle_arguments(arguments);
+        result
+    }
+
+    /// Evaluates the given node, which is a call of the higher-order builtin function with the given identifier, like `map`.
+    /// This mirrors `Node::eval_higher_order`.
+    fn eval_higher_order(
+        &self,
+        node: &CompactNode,
+        identifier: &str,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let (elements, body) = self.higher_order_arguments(node)?;
+        let elements = self.eval_recursively(elements, context, state)?;
+        let mut scope = ItContext::shared(context);
+        let body_operator = &self.strip_root_nodes(body).operator;
+        let result = eval_higher_order(
+            identifier,
+            elements,
+            body_operator,
+            &mut scope,
+            state,
+            |scope| self.eval_recursively(body, scope, state),
+        );
+        Node::allocate(state, None, || result)
+    }
+
+    /// Evaluates the given node, which is a call of the higher-order builtin function with the given identifier, with a mutable context.
+    /// This mirrors `Node::eval_higher_order_mut`.
+    fn eval_higher_order_mut(
+        &self,
+        node: &CompactNode,
+        identifier: &str,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let (elements, body) = self.higher_order_arguments(node)?;
+        let elements = self.eval_recursively_mut(elements, context, state)?;
+        let mut scope = ItContext::exclusive(context);
+        let body_operator = &self.strip_root_nodes(body).operator;
+        let result = eval_higher_order(
+            identifier,
+            elements,
+            body_operator,
+            &mut scope,
+            state,
+            |scope| self.eval_recursively_mut(body, scope, state),
+        );
+        Node::allocate(state, None, || result)
+    }
+
+    /// Returns the two arguments of a call of a higher-order builtin function.
+    fn higher_order_arguments(
+        &self,
+        node: &CompactNode,
+    ) -> EvalexprResult<(&CompactNode, &CompactNode)> {
+        let argument = self.strip_root_nodes(&self.children(node)[0]);
+        let children = self.children(argument);
+        expect_higher_order_arguments(&argument.operator, children.len())?;
+        Ok((&children[0], &children[1]))
+    }
+
+    /// Returns the first descendant of the given node that is not a root node with a single child.
+    /// This mirrors `Node::strip_root_nodes`.
+    fn strip_root_nodes<'a>(&'a self, mut node: &'a CompactNode) -> &'a CompactNode {
+        while matches!(node.operator, Operator::RootNode | Operator::Shared { .. })
+            && node.child_count == 1
+        {
+            node = &self.children(node)[0];
+        }
+        node
+    }
+}
+
+/// Compact trees are equal if their nodes are, like `Node` does not compare spans.
+impl PartialEq for CompactTree {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+    }
+}
+
+/// Converts a node index or amount to the type used to store it.
+fn to_index(value: usize) -> u32 {
+    assert!(
+        value <= u32::MAX as usize,
+        "a compact tree cannot have more than {} nodes",
+        u32::MAX
+    );
+    value as u32
+}
@@ -1,8 +1,19 @@
-use std::fmt::{Display, Error, Formatter};
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter, Write};
+
+use function::higher_order::is_higher_order_builtin;
+use operator::Operator;
+use value::display::CanonicalFloats;
+use value::value_type::ValueType;
+use value::Value;
 use Node;
 
 impl Display for Node {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        // Shared subexpressions are written as the subexpression itself.
+        if let Operator::Shared { .. } = self.operator {
+            return self.children()[0].fmt(f);
+        }
         self.operator.fmt(f)?;
         for child in self.children() {
             write!(f, " {}", child)?;
@@ -10,3 +21,388 @@ impl Display for Node {
         Ok(())
     }
 }
+
+impl Node {
+    /// Returns a multi-line dump of the operator tree rooted at this node.
+    ///
+    /// Each node is printed on its own line, indented by two spaces per level of depth.
+    /// Operators are printed by name, constants with their type and value, and identifiers as `Var(...)` and `Fn(...)`.
+    /// This is meant for snapshot tests and bug reports.
+    /// The format is kept stable on a best-effort basis, but may change if the structure of the operator tree changes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("a > 3 || !b").unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     tree.to_debug_tree(),
+    ///     "Root\n  Or\n    Gt\n      Var(a)\n      Const(Int 3)\n    Not\n      Var(b)"
+    /// );
+    /// ```
+    pub fn to_debug_tree(&self) -> String {
+        let mut result = String::new();
+        self.write_debug_tree(&mut result, 0);
+        result
+    }
+
+    fn write_debug_tree(&self, result: &mut String, depth: usize) {
+        if depth > 0 {
+            result.push('\n');
+        }
+        for _ in 0..depth {
+            result.push_str("  ");
+        }
+        // Writing to a string cannot fail.
+        write_debug_label(self.operator(), result).unwrap();
+
+        for child in self.children() {
+            child.write_debug_tree(result, depth + 1);
+        }
+    }
+}
+
+fn write_debug_label(operator: &Operator, result: &mut String) -> Result<(), Error> {
+    use operator::Operator::*;
+    let _canonical = CanonicalFloats::new();
+    match operator {
+        RootNode => write!(result, "Root"),
+        Const { value } => write!(result, "Const({:?} {})", ValueType::from(value), value),
+        PreservedConst { constant } => write!(
+            result,
+            "Const({:?} {})",
+            ValueType::from(&constant.value),
+            constant.value
+        ),
+        VariableIdentifier { identifier } => write!(result, "Var({})", identifier),
+        FunctionIdentifier { identifier } => write!(result, "Fn({})", identifier),
+        NamedFunctionIdentifier { call } => write!(
+            result,
+            "Fn({}; {})",
+            call.identifier,
+            call.argument_names.join(", ")
+        ),
+        Shared { id } => write!(result, "Shared({})", id),
+        operator => write!(result, "{:?}", operator),
+    }
+}
+
+impl Node {
+    /// Returns the expression of this operator tree with as little whitespace and as few parentheses as possible.
+    ///
+    /// Parentheses are only kept where the precedence or associativity of the operators requires them, and float literals are written in their shortest form, like `.5` or `1e10`.
+    /// Building an operator tree from the returned string gives a tree that evaluates the same as this one, and that differs only in redundant root nodes.
+    /// The text kept by `ParseConfig::preserve_literal_text` is not used, as it is rarely the shortest form.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("(a + (b * 2)) - (c - 0.5)").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.to_minified_string(), "a+b*2-(c-.5)");
+    /// ```
+    pub fn to_minified_string(&self) -> String {
+        self.to_renamed_string(&HashMap::new())
+    }
+
+    /// Returns the expression of this operator tree like `Node::to_minified_string`, with identifiers of variables replaced.
+    ///
+    /// Each variable whose identifier is a key of `renames` is written with the mapped identifier instead, both where it is read and where it is assigned to.
+    /// Variables without an entry keep their identifier.
+    /// Function identifiers and string literals are never changed, even if they contain the same text as a renamed variable.
+    /// The new identifiers are written as given, so they should be valid identifiers that do not collide with other variables of the expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let tree = build_operator_tree("total = price * quantity; total + len(\"price\")").unwrap(); // Do proper error handling here
+    /// let mut renames = HashMap::new();
+    /// renames.insert("total".to_string(), "a".to_string());
+    /// renames.insert("price".to_string(), "b".to_string());
+    /// renames.insert("quantity".to_string(), "c".to_string());
+    /// assert_eq!(tree.to_renamed_string(&renames), "a=b*c;a+len(\"price\")");
+    /// ```
+    pub fn to_renamed_string(&self, renames: &HashMap<String, String>) -> String {
+        let mut result = String::new();
+        let minifier = Minifier { renames };
+        // An empty expression is written as nothing rather than as `()`.
+        if !self.strip_root_nodes().is_empty_root() {
+            minifier.write(self, &mut result);
+        }
+        result
+    }
+
+    fn is_empty_root(&self) -> bool {
+        self.operator() == &Operator::RootNode && self.children().is_empty()
+    }
+}
+
+/// Writes operator trees as expressions with minimal whitespace and parentheses.
+struct Minifier<'a> {
+    renames: &'a HashMap<String, String>,
+}
+
+impl<'a> Minifier<'a> {
+    /// Writes the given node without parentheses around it.
+    fn write(&self, node: &Node, result: &mut String) {
+        use operator::Operator::*;
+
+        let node = node.strip_root_nodes();
+        let children = node.children();
+        match (node.operator(), children) {
+            // A root node with one child was stripped above, and more children are not produced by the parser.
+            (RootNode, _) => result.push_str("()"),
+            (Const { value }, _) => result.push_str(&minified_literal(value)),
+            (PreservedConst { constant }, _) => result.push_str(&minified_literal(&constant.value)),
+            (VariableIdentifier { identifier }, _) => result.push_str(self.rename(identifier)),
+            (FunctionIdentifier { identifier }, [argument]) => {
+                result.push_str(identifier);
+                // The assignments in the arguments of higher-order builtins are not read as named arguments.
+                let parenthesize_assignments = !is_higher_order_builtin(identifier);
+                self.write_arguments(argument, &[], parenthesize_assignments, result);
+            },
+            (NamedFunctionIdentifier { call }, [argument]) => {
+                result.push_str(&call.identifier);
+                self.write_arguments(argument, &call.argument_names, true, result);
+            },
+            (Neg, [operand]) | (Pos, [operand]) | (Not, [operand]) => {
+                result.push_str(match node.operator() {
+                    Neg => "-",
+                    Pos => "+",
+                    _ => "!",
+                });
+                self.write_operand(
+                    operand,
+                    precedence(operand) < node.operator().precedence(),
+                    result,
+                );
+            },
+            // A tuple with one element needs a trailing comma, and braces around it, as a trailing comma is only allowed before a closing brace.
+            (Tuple, [element]) => {
+                result.push('(');
+                self.write_operand(element, precedence(element) <= Tuple.precedence(), result);
+                result.push_str(",)");
+            },
+            (Tuple, _) | (Chain, _) => {
+                let separator = if node.operator() == &Tuple { "," } else { ";" };
+                for (index, child) in children.iter().enumerate() {
+                    if index > 0 {
+                        result.push_str(separator);
+                    }
+                    // An empty element, like after a trailing `;`, is written as nothing.
+                    // Empty elements of tuples are written as `()`, as a comma needs a value on both sides.
+                    if child.is_empty_root() && node.operator() == &Tuple {
+                        result.push_str("()");
+                    } else if !child.is_empty_root() {
+                        self.write_operand(
+                            child,
+                            precedence(child) <= node.operator().precedence(),
+                            result,
+                        );
+                    }
+                }
+            },
+            (Assign, [target, value]) => {
+                match target.operator() {
+                    Const { value: target } => self.write_assignment_target(target, result),
+                    _ => self.write(target, result),
+                }
+                result.push('=');
+                let start = result.len();
+                self.write_operand(value, precedence(value) < Assign.precedence(), result);
+                // An identifier starting with a tilde after an equals sign would be read as the regex match operator `=~`.
+                if result[start..].starts_with('~') {
+                    result.insert(start, ' ');
+                }
+            },
+            // The branch for a true condition is delimited by `?` and `:` like by parentheses.
+            (Conditional, [condition, if_true, if_false]) => {
+                self.write_operand(
+                    condition,
+                    precedence(condition) <= Conditional.precedence(),
+                    result,
+                );
+                result.push('?');
+                self.write(if_true, result);
+                result.push(':');
+                self.write_operand(
+                    if_false,
+                    precedence(if_false) < Conditional.precedence(),
+                    result,
+                );
+            },
+            (operator, [left, right]) => {
+                let operator_precedence = operator.precedence();
+                let left_to_right = operator.is_left_to_right();
+                let left_precedence = precedence(left);
+                let right_precedence = precedence(right);
+                // Comparisons of comparisons need parentheses to be accepted by the parser.
+                let is_comparison_chain =
+                    operator.is_comparison() && left.strip_root_nodes().operator().is_comparison();
+                self.write_operand(
+                    left,
+                    left_precedence < operator_precedence
+                        || (left_precedence == operator_precedence && !left_to_right)
+                        || is_comparison_chain,
+                    result,
+                );
+                // The dot at the end of a float literal like `1.` would be read as part of the range operator.
+                if matches!(operator, Range | RangeInclusive) && result.ends_with('.') {
+                    result.push(' ');
+                }
+                write_binary_operator_symbol(operator, result);
+                let start = result.len();
+                self.write_operand(
+                    right,
+                    right_precedence < operator_precedence
+                        || (right_precedence == operator_precedence && left_to_right),
+                    result,
+                );
+                if result[..start].ends_with('=') && result[start..].starts_with('~') {
+                    result.insert(start, ' ');
+                }
+            },
+            // Nodes with an unexpected number of children, like the binary operator with one operand that `1 * * 2` is parsed into, are written in prefix notation.
+            _ => write!(result, "{}", node).unwrap(),
+        }
+    }
+
+    /// Writes the identifier of a variable that is assigned to, or a tuple of such targets like `(a,(b,c))`.
+    fn write_assignment_target(&self, target: &Value, result: &mut String) {
+        match target {
+            Value::Tuple(targets) => {
+                result.push('(');
+                for (index, target) in targets.iter().enumerate() {
+                    if index > 0 {
+                        result.push(',');
+                    }
+                    self.write_assignment_target(target, result);
+                }
+                result.push(')');
+            },
+            Value::String(identifier) => result.push_str(self.rename(identifier)),
+            value => result.push_str(&minified_literal(value)),
+        }
+    }
+
+    /// Writes the parenthesized arguments of a function call, whose trailing arguments are named by `names`.
+    /// If `parenthesize_assignments` is set, assignments are parenthesized, as they would be read as named arguments otherwise.
+    fn write_arguments(
+        &self,
+        argument: &Node,
+        names: &[String],
+        parenthesize_assignments: bool,
+        result: &mut String,
+    ) {
+        use operator::Operator::*;
+
+        result.push('(');
+        let argument = argument.strip_root_nodes();
+        if argument.operator() == &Tuple {
+            let elements = argument.children();
+            let first_named = elements.len() - names.len();
+            for (index, element) in elements.iter().enumerate() {
+                if index > 0 {
+                    result.push(',');
+                }
+                if let Some(name) = index.checked_sub(first_named).map(|index| &names[index]) {
+                    result.push_str(name);
+                    result.push('=');
+                }
+                let start = result.len();
+                self.write_operand(
+                    element,
+                    precedence(element) <= Tuple.precedence()
+                        || (parenthesize_assignments
+                            && element.strip_root_nodes().operator() == &Assign),
+                    result,
+                );
+                if result[..start].ends_with('=') && result[start..].starts_with('~') {
+                    result.insert(start, ' ');
+                }
+            }
+            if elements.len() == 1 && names.is_empty() {
+                result.push(',');
+            }
+        } else if !argument.is_empty_root() {
+            self.write_operand(
+                argument,
+                parenthesize_assignments && argument.operator() == &Assign,
+                result,
+            );
+        }
+        result.push(')');
+    }
+
+    fn write_operand(&self, node: &Node, parenthesize: bool, result: &mut String) {
+        if parenthesize {
+            result.push('(');
+            self.write(node, result);
+            result.push(')');
+        } else {
+            self.write(node, result);
+        }
+    }
+
+    fn rename<'b>(&'b self, identifier: &'b str) -> &'b str {
+        self.renames
+            .get(identifier)
+            .map(String::as_str)
+            .unwrap_or(identifier)
+    }
+}
+
+/// Returns the precedence of the given node as it is written by the minifier.
+/// Nodes that are written with their own parentheses bind tightest.
+fn precedence(node: &Node) -> i32 {
+    use operator::Operator::*;
+
+    let node = node.strip_root_nodes();
+    match node.operator() {
+        Const { value } if minified_literal(value).starts_with('(') => RootNode.precedence(),
+        PreservedConst { constant } if minified_literal(&constant.value).starts_with('(') => {
+            RootNode.precedence()
+        },
+        Tuple if node.children().len() == 1 => RootNode.precedence(),
+        operator => operator.precedence(),
+    }
+}
+
+/// Writes the symbol of a binary operator for a minified expression.
+fn write_binary_operator_symbol(operator: &Operator, result: &mut String) {
+    let symbol = operator.expect_implementation().symbol();
+    // The keywords need to be separated from identifiers.
+    if symbol.starts_with(char::is_alphabetic) {
+        result.push(' ');
+        result.push_str(symbol);
+        result.push(' ');
+    } else {
+        result.push_str(symbol);
+    }
+}
+
+/// Returns the shortest literal that evaluates to the given value.
+fn minified_literal(value: &Value) -> String {
+    match value {
+        Value::Float(float) if float.is_finite() && !float.is_sign_negative() => {
+            let mut literal = float.to_string();
+            if let Some(fraction) = literal.strip_prefix("0.") {
+                literal = format!(".{}", fraction);
+            } else if !literal.contains('.') {
+                literal.push('.');
+            }
+            let exponent = format!("{:e}", float);
+            if exponent.len() < literal.len() {
+                exponent
+            } else {
+                literal
+            }
+        },
+        value => value.to_expression_literal(),
+    }
+}
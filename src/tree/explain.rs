@@ -0,0 +1,189 @@
+use std::fmt::Write;
+
+use context::Context;
+use error::EvalexprResult;
+use function::higher_order::higher_order_identifier;
+use state::EvalState;
+use value::Value;
+use Node;
+
+/// A breakdown of the evaluation of an expression, created by `Node::explain_with_context`.
+///
+/// The explanation mirrors the operator tree of the expression.
+/// Each subexpression is listed with the value it evaluated to, or with the error it failed with.
+/// Root nodes that only group a single subexpression, like parentheses, are left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    /// The subexpression, written like by `Node::to_minified_string`.
+    pub expression: String,
+    /// The result of the subexpression, or `None` if it was not evaluated.
+    /// A subexpression is not evaluated if a preceding operand of the same operator short-circuited it, like the right operand of `false && b`, or failed.
+    pub result: Option<EvalexprResult<Value>>,
+    /// The explanations of the operands of the subexpression, in order.
+    /// Operands of function calls are explained like one tuple, and named expressions, calls of higher-order functions like `map` and constants have no operands.
+    pub children: Vec<Explanation>,
+}
+
+impl Explanation {
+    /// Returns a report of the explanation with one subexpression per line, indented by two spaces per level of depth.
+    ///
+    /// Each line has the form `expression => value`.
+    /// Failed subexpressions are written as `expression => error: message`, and subexpressions that were not evaluated as `expression => not evaluated`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let context = context_map! { "age" => 17, "country" => "NZ" }.unwrap(); // Do proper error handling here
+    /// let rule = build_operator_tree("age >= 18 && country == \"DE\"").unwrap(); // Do proper error handling here
+    /// let explanation = rule.explain_with_context(&context).unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     explanation.to_text(),
+    ///     "\
+    /// age>=18&&country==\"DE\" => false
+    ///   age>=18 => false
+    ///     age => 17
+    ///     18 => 18
+    ///   country==\"DE\" => not evaluated
+    ///     country => not evaluated
+    ///     \"DE\" => not evaluated"
+    /// );
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+        self.write_text(&mut result, 0);
+        result
+    }
+
+    fn write_text(&self, result: &mut String, depth: usize) {
+        if depth > 0 {
+            result.push('\n');
+        }
+        for _ in 0..depth {
+            result.push_str("  ");
+        }
+        // Writing to a string cannot fail.
+        match &self.result {
+            Some(Ok(value)) => write!(result, "{} => {}", self.expression, value),
+            Some(Err(error)) => write!(result, "{} => error: {}", self.expression, error),
+            None => write!(result, "{} => not evaluated", self.expression),
+        }
+        .unwrap();
+
+        for child in &self.children {
+            child.write_text(result, depth + 1);
+        }
+    }
+
+    /// Returns the explanation of a subexpression that was not evaluated.
+    fn not_evaluated(node: &Node) -> Self {
+        let node = node.strip_root_nodes();
+        Self {
+            expression: node.to_minified_string(),
+            result: None,
+            children: node.children().iter().map(Self::not_evaluated).collect(),
+        }
+    }
+}
+
+impl Node {
+    /// Evaluates the operator tree rooted at this node with the given context, and returns the value of each subexpression as an `Explanation`.
+    ///
+    /// The evaluation is the same as with `Node::eval_with_context`, such that functions are called once for each call in the expression, in the same order.
+    /// The result of the whole expression is the result of the returned explanation, so an evaluation that fails still gets explained.
+    /// A shared subexpression, see `Node::share_common_subexpressions`, is evaluated for each of its occurrences.
+    ///
+    /// Fails only with the errors that abort the whole evaluation, like `EvalexprError::Cancelled` or `EvalexprError::RecursionLimitExceeded`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let context = context_map! { "plan" => "pro", "seats" => 12 }.unwrap(); // Do proper error handling here
+    /// let rule = build_operator_tree("plan == \"pro\" || seats > 50").unwrap(); // Do proper error handling here
+    /// let explanation = rule.explain_with_context(&context).unwrap(); // Do proper error handling here
+    /// assert_eq!(explanation.result, Some(Ok(Value::from(true))));
+    /// assert_eq!(explanation.children[1].expression, "seats>50");
+    /// assert_eq!(explanation.children[1].result, None);
+    /// ```
+    pub fn explain_with_context(&self, context: &dyn Context) -> EvalexprResult<Explanation> {
+        EvalState::with_default(|state| {
+            let _guard = state.enter()?;
+            let explanation = self.explain_recursively(context, state);
+            match explanation.result {
+                Some(Err(error)) if error.aborts_evaluation() => Err(error),
+                _ => Ok(explanation),
+            }
+        })
+    }
+
+    /// Evaluates this node like `Node::eval_recursively`, and explains the results of its children.
+    fn explain_recursively(&self, context: &dyn Context, state: &EvalState) -> Explanation {
+        let node = self.strip_root_nodes();
+        let expression = node.to_minified_string();
+        if node.children().is_empty()
+            || Self::named_expression(node.operator(), context).is_some()
+            || higher_order_identifier(node.operator(), context).is_some()
+        {
+            return Explanation {
+                expression,
+                result: Some(node.eval_recursively(context, state)),
+                children: Vec::new(),
+            };
+        }
+
+        let mut children = Vec::new();
+        let mut arguments = Vec::new();
+        let mut result = None;
+        for child in node.children() {
+            if result.is_some() || node.operator().skip_argument(&mut arguments) {
+                children.push(Explanation::not_evaluated(child));
+                continue;
+            }
+
+            let explanation = child.explain_recursively(context, state);
+            match &explanation.result {
+                Some(Ok(value)) => {
+                    arguments.push(value.clone());
+                    result = node.operator().short_circuit(&arguments).transpose();
+                },
+                Some(Err(error)) => {
+                    let argument = child.strip_root_nodes().operator();
+                    match node
+                        .operator()
+                        .recover_argument(&arguments, argument, error.clone())
+                    {
+                        Ok(argument) => arguments.push(argument),
+                        Err(error) => result = Some(Err(error)),
+                    }
+                },
+                None => unreachable!("an evaluated subexpression has a result"),
+            }
+            children.push(explanation);
+        }
+
+        let result = result.unwrap_or_else(|| node.apply_operator(&arguments, context, state));
+        Explanation {
+            expression,
+            result: Some(result),
+            children,
+        }
+    }
+
+    /// Applies the operator of this node to the given evaluated arguments, like the last step of `Node::eval_recursively`.
+    fn apply_operator(
+        &self,
+        arguments: &[Value],
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        state.check_cancelled()?;
+        let allocation_hint = Self::allocation_hint(self.operator(), arguments, context, state);
+        Self::allocate(state, allocation_hint, || {
+            let result = self.operator().eval(arguments, context, state.config());
+            Self::apply_nan_policy(self.operator(), arguments, context, state, result)
+        })
+    }
+}
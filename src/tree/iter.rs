@@ -9,7 +9,7 @@ pub struct NodeIter<'a> {
 impl<'a> NodeIter<'a> {
     fn new(node: &'a Node) -> Self {
         NodeIter {
-            stack: vec![node.children.iter()],
+            stack: vec![node.children().iter()],
         }
     }
 }
@@ -34,11 +34,11 @@ impl<'a> Iterator for NodeIter<'a> {
 
             if pop_stack {
                 // Can not fail because we borrowed last before.
-                self.stack.pop().unwrap();
+                let _ = self.stack.pop().unwrap();
             }
 
             if let Some(result) = result {
-                self.stack.push(result.children.iter());
+                self.stack.push(result.children().iter());
                 return Some(result);
             }
         }
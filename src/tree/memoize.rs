@@ -0,0 +1,133 @@
+use std::iter;
+use std::sync::{Mutex, MutexGuard};
+
+use context::{Context, RecordingContext};
+use error::EvalexprResult;
+use value::Value;
+use EmptyContext;
+use Node;
+
+/// An operator tree that caches the result of its last evaluation, created by `Node::memoize`.
+///
+/// Each evaluation records the variables that it reads, together with their values.
+/// If the next evaluation is with a context that has equal values for all of these variables, the cached result is returned without evaluating the tree again.
+/// Variables that were not read, like the ones in the right operand of a short-circuiting `&&`, do not invalidate the cache.
+/// This is meant for expensive expressions, like ones that match regexes or build large tuples, whose inputs rarely change between evaluations.
+///
+/// Only pure expressions are cached, as checked with the same rules as for `Node::share_common_subexpressions`.
+/// An expression that contains an assignment is never cached.
+/// Other expressions are evaluated without the cache if they call a function marked with `Function::impure` or a builtin function like `now_monotonic`, or read a variable that is a named expression or not found in the context.
+/// This is checked before each evaluation, so it walks the operator tree once even if the cached result is returned.
+///
+/// Functions are expected to stay the same between evaluations, so if a function of the context is replaced, the cache should be cleared with `MemoizedNode::clear_cache`.
+/// Errors that abort the whole evaluation, like `EvalexprError::Cancelled`, are not cached.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+///
+/// let calls = Rc::new(Cell::new(0));
+/// let expensive_calls = calls.clone();
+/// let mut context = HashMapContext::new();
+/// context.set_value("threshold", 10).unwrap(); // Do proper error handling here
+/// context.set_function("expensive".into(), Function::new(Box::new(move |argument| {
+///     expensive_calls.set(expensive_calls.get() + 1);
+///     Ok(Value::Int(argument.as_int()? * 2))
+/// }))).unwrap(); // Do proper error handling here
+///
+/// let tree = build_operator_tree("expensive(threshold) > 15").unwrap().memoize(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+/// assert_eq!(calls.get(), 1);
+///
+/// context.set_value("threshold", 5).unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(false)));
+/// assert_eq!(calls.get(), 2);
+/// ```
+#[derive(Debug)]
+pub struct MemoizedNode {
+    node: Node,
+    /// Whether the tree contains an assignment, which prevents caching its result.
+    has_assignment: bool,
+    cache: Mutex<Option<CachedResult>>,
+}
+
+/// The result of an evaluation, together with the variables that the evaluation read and their values.
+#[derive(Debug)]
+struct CachedResult {
+    reads: Vec<(String, Option<Value>)>,
+    result: EvalexprResult<Value>,
+}
+
+impl MemoizedNode {
+    fn new(node: Node) -> Self {
+        let has_assignment = iter::once(&node)
+            .chain(node.iter())
+            .any(|node| !node.operator().is_pure());
+        Self {
+            node,
+            has_assignment,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Evaluates the operator tree with the given context, or returns the cached result if the variables that the last evaluation read still have the same values.
+    pub fn eval_with_context(&self, context: &dyn Context) -> EvalexprResult<Value> {
+        if self.has_assignment || !self.node.is_pure_in(context) {
+            return self.node.eval_with_context(context);
+        }
+
+        if let Some(cached) = &*self.lock_cache() {
+            let is_valid = cached
+                .reads
+                .iter()
+                .all(|(identifier, value)| context.get_value(identifier) == value.as_ref());
+            if is_valid {
+                return cached.result.clone();
+            }
+        }
+
+        // The cache is not locked during the evaluation, so evaluations on other threads are not blocked by it.
+        let recording_context = RecordingContext::new(context);
+        let result = self.node.eval_with_context(&recording_context);
+        *self.lock_cache() = match &result {
+            Err(error) if error.aborts_evaluation() => None,
+            result => Some(CachedResult {
+                reads: recording_context.into_reads(),
+                result: result.clone(),
+            }),
+        };
+        result
+    }
+
+    /// Evaluates the operator tree with an empty context, or returns the cached result.
+    pub fn eval(&self) -> EvalexprResult<Value> {
+        self.eval_with_context(&EmptyContext)
+    }
+
+    /// Removes the cached result, such that the next evaluation evaluates the operator tree.
+    pub fn clear_cache(&self) {
+        *self.lock_cache() = None;
+    }
+
+    /// Returns the operator tree whose results are cached.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Locks the cache, which stays usable even if an evaluation panicked on another thread, as it is only replaced as a whole.
+    fn lock_cache(&self) -> MutexGuard<'_, Option<CachedResult>> {
+        self.cache.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Node {
+    /// Returns a copy of the operator tree rooted at this node that caches the result of its last evaluation.
+    /// See `MemoizedNode` for details.
+    pub fn memoize(&self) -> MemoizedNode {
+        MemoizedNode::new(self.clone())
+    }
+}
@@ -1,4 +1,15 @@
-use token::Token;
+use config::{IdentifierInterner, NanPolicy, ParseConfig, TupleErrorMode};
+use feature_tracing;
+use function::higher_order::{
+    eval_higher_order, expect_higher_order_arguments, higher_order_identifier,
+    is_higher_order_builtin,
+};
+use state::EvalState;
+use std::collections::HashMap;
+use std::sync::Arc;
+use token::{ParseWarning, Token};
+use typecheck::{self, Signature, StaticType, TypeCheckError, TypeSet};
+use value::value_type::ValueType;
 use value::{TupleType, EMPTY_VALUE};
 use EmptyContext;
 use EmptyType;
@@ -6,15 +17,29 @@ use FloatType;
 use IntType;
 
 use crate::{
-    context::Context,
+    context::{Context, ItContext, ScriptContext, EXPORT_FUNCTION_IDENTIFIER},
     error::{EvalexprError, EvalexprResult},
     operator::*,
     value::Value,
 };
-use std::mem;
+use std::ops::Range;
+use std::{fmt, mem, slice};
 
+mod compact;
 mod display;
+mod explain;
 mod iter;
+mod memoize;
+mod share;
+mod source_map;
+mod substitute;
+mod typed;
+
+pub use self::compact::CompactTree;
+pub use self::explain::Explanation;
+pub use self::memoize::MemoizedNode;
+pub use self::source_map::SourceMap;
+pub use self::typed::{ExpressionType, TypedExpression};
 
 /// A node in the operator tree.
 /// The operator tree is created by the crate-level `build_operator_tree` method.
@@ -28,22 +53,87 @@ mod iter;
 /// use evalexpr::*;
 ///
 /// let mut context = HashMapContext::new();
-/// context.set_value("alpha".into(), 2.into()).unwrap(); // Do proper error handling here
+/// context.set_value("alpha", 2).unwrap(); // Do proper error handling here
 /// let node = build_operator_tree("1 + alpha").unwrap(); // Do proper error handling here
 /// assert_eq!(node.eval_with_context(&context), Ok(Value::from(3)));
 /// ```
 ///
-#[derive(Debug, PartialEq)]
+#[derive(Clone)]
 pub struct Node {
     operator: Operator,
-    children: Vec<Node>,
+    /// The children are shared between clones of this node, and copied on write by `Node::children_mut`.
+    /// Leaves have no children allocated.
+    children: Option<Arc<Vec<Node>>>,
+    /// The range of bytes of the source that this node was parsed from, or `None` if it was not parsed from a token, like the root node of the whole expression.
+    /// Nodes of parenthesized expressions span the parentheses.
+    /// The span is not compared by `PartialEq`, such that expressions that only differ in whitespace have equal operator trees.
+    span: Option<Range<usize>>,
+}
+
+/// An operator tree together with the warnings that were recorded while building it.
+/// It is created by the crate-level `build_operator_tree_with_config` method.
+#[derive(Debug, PartialEq)]
+pub struct ParsedTree {
+    /// The operator tree of the expression.
+    pub tree: Node,
+    /// Problems in the expression that did not prevent building the operator tree, in the order they appear in the expression.
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Drops the descendants of a node with a worklist instead of recursion.
+/// Operator trees can be nested deeper than the stack allows to recurse, like the left operands of a long sum `1 + 1 + ... + 1`.
+/// Children that are shared with a clone of the node are left to the clone.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut worklist = Vec::new();
+        self.take_unshared_children(&mut worklist);
+        while let Some(mut node) = worklist.pop() {
+            // The node is dropped without children at the end of the iteration.
+            node.take_unshared_children(&mut worklist);
+        }
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.operator == other.operator && self.children() == other.children()
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("operator", &self.operator)
+            .field("children", &self.children())
+            .finish()
+    }
+}
+
+/// The result of an evaluation together with the errors that were recovered from.
+/// It is created by `Node::eval_with_context_and_report`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalReport {
+    /// The value of the expression, where the elements of a top-level tuple that failed are `Value::Empty`.
+    pub value: Value,
+    /// The errors of the failed tuple elements, in the order of the elements.
+    pub errors: Vec<TupleElementError>,
+}
+
+/// The error of an element of a top-level tuple that was replaced by `Value::Empty`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TupleElementError {
+    /// The zero-based index of the element in the tuple.
+    pub index: usize,
+    /// The error that the evaluation of the element failed with.
+    pub error: EvalexprError,
 }
 
 impl Node {
     fn new(operator: Operator) -> Self {
         Self {
-            children: Vec::new(),
+            children: None,
             operator,
+            span: None,
         }
     }
 
@@ -69,9 +159,8 @@ impl Node {
     /// ```
     pub fn iter_identifiers(&self) -> impl Iterator<Item = &str> {
         self.iter().filter_map(|node| match node.operator() {
-            Operator::VariableIdentifier { identifier }
-            | Operator::FunctionIdentifier { identifier } => Some(identifier.as_str()),
-            _ => None,
+            Operator::VariableIdentifier { identifier } => Some(identifier.as_ref()),
+            operator => operator.called_function(),
         })
     }
 
@@ -92,7 +181,7 @@ impl Node {
     /// ```
     pub fn iter_variable_identifiers(&self) -> impl Iterator<Item = &str> {
         self.iter().filter_map(|node| match node.operator() {
-            Operator::VariableIdentifier { identifier } => Some(identifier.as_str()),
+            Operator::VariableIdentifier { identifier } => Some(identifier.as_ref()),
             _ => None,
         })
     }
@@ -111,32 +200,631 @@ impl Node {
     /// assert_eq!(iter.next(), None);
     /// ```
     pub fn iter_function_identifiers(&self) -> impl Iterator<Item = &str> {
-        self.iter().filter_map(|node| match node.operator() {
-            Operator::FunctionIdentifier { identifier } => Some(identifier.as_str()),
-            _ => None,
-        })
+        self.iter()
+            .filter_map(|node| node.operator().called_function())
     }
 
     /// Evaluates the operator tree rooted at this node with the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_with_context(&self, context: &Context) -> EvalexprResult<Value> {
-        let mut arguments = Vec::new();
-        for child in self.children() {
-            arguments.push(child.eval_with_context(context)?);
-        }
-        self.operator().eval(&arguments, context)
+    pub fn eval_with_context(&self, context: &dyn Context) -> EvalexprResult<Value> {
+        feature_tracing::instrument_evaluation(
+            || feature_tracing::expression_hash(&self.to_string()),
+            || EvalState::with_default(|state| self.eval_with_context_and_state(context, state)),
+        )
     }
 
     /// Evaluates the operator tree rooted at this node with the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_with_context_mut(&self, context: &mut Context) -> EvalexprResult<Value> {
-        let mut arguments = Vec::new();
+    pub fn eval_with_context_mut(&self, context: &mut dyn Context) -> EvalexprResult<Value> {
+        feature_tracing::instrument_evaluation(
+            || feature_tracing::expression_hash(&self.to_string()),
+            || {
+                EvalState::with_default(|state| {
+                    self.eval_with_context_mut_and_state(context, state)
+                })
+            },
+        )
+    }
+
+    /// Evaluates the operator tree rooted at this node with each of the given contexts in order, and returns the index of the first context it succeeds with together with the value.
+    ///
+    /// This is meant for chains of fallbacks, like user overrides, group defaults and global defaults, where earlier contexts may not define all variables.
+    /// Only the errors of variables or functions that are not defined, namely `EvalexprError::VariableIdentifierNotFound` and `FunctionIdentifierNotFound`, fall through to the next context.
+    /// See `Node::eval_first_success_with_fallthrough` to fall through on any error.
+    ///
+    /// Fails with the first error that does not fall through, or with `EvalexprError::NoContextSucceeded` containing the errors of all contexts if none succeeds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let user = context_map! { "discount" => 0.2 }.unwrap(); // Do proper error handling here
+    /// let global = context_map! { "discount" => 0.0, "price" => 10.0 }.unwrap(); // Do proper error handling here
+    /// let tree = build_operator_tree("price * (1 - discount)").unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     tree.eval_first_success(vec![&user as &dyn Context, &global]),
+    ///     Ok((1, Value::from(10.0)))
+    /// );
+    /// ```
+    pub fn eval_first_success<'a, I>(&self, contexts: I) -> EvalexprResult<(usize, Value)>
+    where
+        I: IntoIterator<Item = &'a dyn Context>,
+    {
+        self.eval_first_success_with_fallthrough(contexts, false)
+    }
+
+    /// Evaluates the operator tree rooted at this node with each of the given contexts in order like `Node::eval_first_success`.
+    ///
+    /// If `fall_through_on_any_error` is true, then every error falls through to the next context, except for errors that abort the whole evaluation, namely `EvalexprError::Cancelled`, `RecursionLimitExceeded` and `ValueTooLarge`.
+    /// Otherwise, only the errors of variables or functions that are not defined fall through.
+    pub fn eval_first_success_with_fallthrough<'a, I>(
+        &self,
+        contexts: I,
+        fall_through_on_any_error: bool,
+    ) -> EvalexprResult<(usize, Value)>
+    where
+        I: IntoIterator<Item = &'a dyn Context>,
+    {
+        let mut errors = Vec::new();
+        for (index, context) in contexts.into_iter().enumerate() {
+            match self.eval_with_context(context) {
+                Ok(value) => return Ok((index, value)),
+                Err(error)
+                    if error.aborts_evaluation()
+                        || !(fall_through_on_any_error || error.is_lookup_error()) =>
+                {
+                    return Err(error)
+                },
+                Err(error) => errors.push(error),
+            }
+        }
+        Err(EvalexprError::NoContextSucceeded { errors })
+    }
+
+    /// Evaluates the operator tree rooted at this node with the given context and evaluation state.
+    ///
+    /// The evaluation counts as nested into all other evaluations that currently run with the same state.
+    ///
+    /// Fails, if one of the operators in the expression tree fails, or if the maximum recursion depth of the state is exceeded.
+    pub fn eval_with_context_and_state(
+        &self,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let _guard = state.enter()?;
+        self.eval_recursively(context, state)
+    }
+
+    /// Evaluates the operator tree rooted at this node with the given mutable context and evaluation state.
+    ///
+    /// The evaluation counts as nested into all other evaluations that currently run with the same state.
+    ///
+    /// Fails, if one of the operators in the expression tree fails, or if the maximum recursion depth of the state is exceeded.
+    pub fn eval_with_context_mut_and_state(
+        &self,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let _guard = state.enter()?;
+        self.eval_recursively_mut(context, state)
+    }
+
+    /// Evaluates the operator tree rooted at this node with the given context and evaluation state, and reports the errors that were recovered from.
+    ///
+    /// If the `EvalConfig::tuple_error_mode` of the state is `TupleErrorMode::CollectPartial` and the expression is a tuple, like `(cpu, memory, disk)`, then each element that fails is replaced by `Value::Empty` and its error is added to the report.
+    /// Otherwise, the expression is evaluated like with `Node::eval_with_context_and_state`, and the report contains no errors.
+    ///
+    /// Fails, if an operator outside of the elements of the tuple fails, or with the first error that aborts the whole evaluation, like `EvalexprError::Cancelled`.
+    pub fn eval_with_context_and_report(
+        &self,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<EvalReport> {
+        let tuple = self.strip_root_nodes();
+        if state.config().tuple_error_mode == TupleErrorMode::FailFast
+            || *tuple.operator() != Operator::Tuple
+        {
+            return self
+                .eval_with_context_and_state(context, state)
+                .map(|value| EvalReport {
+                    value,
+                    errors: Vec::new(),
+                });
+        }
+
+        let _guard = state.enter()?;
+        let mut elements = Vec::new();
+        let mut errors = Vec::new();
+        for (index, child) in tuple.children().iter().enumerate() {
+            match child.eval_recursively(context, state) {
+                Ok(value) => elements.push(value),
+                Err(error) if error.aborts_evaluation() => return Err(error),
+                Err(error) => {
+                    elements.push(Value::Empty);
+                    errors.push(TupleElementError { index, error });
+                },
+            }
+        }
+        state.check_cancelled()?;
+        let allocation_hint = Self::allocation_hint(tuple.operator(), &elements, context, state);
+        let value = Self::allocate(state, allocation_hint, || {
+            tuple.operator().eval(&elements, context, state.config())
+        })?;
+        Ok(EvalReport { value, errors })
+    }
+
+    /// Evaluates the operator tree rooted at this node as a script with the given mutable context, and returns the exported variables.
+    ///
+    /// Variables are exported by calling `export` with their identifiers, like `export(a, b)`.
+    /// The exported values are read from the context after the whole script was evaluated.
+    /// Variables that are not exported stay in the context, but are not returned.
+    ///
+    /// Fails, if one of the operators in the expression tree fails, if `export` is called with something else than variable identifiers, or if an exported variable is not defined.
+    pub fn eval_script_with_context_mut(
+        &self,
+        context: &mut dyn Context,
+    ) -> EvalexprResult<HashMap<String, Value>> {
+        let exported_identifiers = self.exported_identifiers()?;
+        self.eval_with_context_mut(&mut ScriptContext::new(context))?;
+
+        let mut exports = HashMap::new();
+        for identifier in exported_identifiers {
+            match context.get_value(&identifier) {
+                Some(value) => exports.insert(identifier, value.clone()),
+                None => return Err(EvalexprError::variable_identifier_not_found(identifier)),
+            };
+        }
+        Ok(exports)
+    }
+
+    /// Returns the identifiers of all variables that are exported by calls to `export` in this expression.
+    fn exported_identifiers(&self) -> EvalexprResult<Vec<String>> {
+        let mut identifiers = Vec::new();
+        let export_calls = self.iter().filter(|node| {
+            matches!(node.operator(), Operator::FunctionIdentifier { identifier } if &**identifier == EXPORT_FUNCTION_IDENTIFIER)
+        });
+
+        for export_call in export_calls {
+            let argument = export_call.children()[0].strip_root_nodes();
+            let arguments = match argument.operator() {
+                Operator::Tuple => argument.children(),
+                Operator::RootNode if argument.children().is_empty() => &[],
+                _ => slice::from_ref(argument),
+            };
+
+            for argument in arguments {
+                match argument.strip_root_nodes().operator() {
+                    Operator::VariableIdentifier { identifier } => {
+                        if !identifiers.iter().any(|exported| exported == &**identifier) {
+                            identifiers.push(identifier.to_string());
+                        }
+                    },
+                    _ => return Err(EvalexprError::invalid_export_argument(argument.to_string())),
+                }
+            }
+        }
+        Ok(identifiers)
+    }
+
+    /// Evaluates the children of this node from left to right, and then this node itself.
+    /// If the operator of this node short-circuits, the remaining children are not evaluated, and children that the operator skips are not evaluated either.
+    fn eval_recursively(&self, context: &dyn Context, state: &EvalState) -> EvalexprResult<Value> {
+        if let Some((identifier, expression)) = Self::named_expression(self.operator(), context) {
+            let _guard = state.enter_expression(identifier)?;
+            return expression.eval_recursively(context, state);
+        }
+        if let Some(identifier) = higher_order_identifier(self.operator(), context) {
+            return self.eval_higher_order(identifier, context, state);
+        }
+        if let Operator::Shared { id } = self.operator() {
+            return self.eval_shared(*id, context, state);
+        }
+
+        let mut arguments = state.take_arguments();
         for child in self.children() {
-            arguments.push(child.eval_with_context_mut(context)?);
+            if self.operator().skip_argument(&mut arguments) {
+                continue;
+            }
+            if let Some(result) = self.push_argument(
+                &mut arguments,
+                child.eval_recursively(context, state),
+                child,
+            )? {
+                state.recycle_arguments(arguments);
+                return Ok(result);
+            }
         }
-        self.operator().eval_mut(&arguments, context)
+        state.check_cancelled()?;
+        let allocation_hint = Self::allocation_hint(self.operator(), &arguments, context, state);
+        let result = Self::allocate(state, allocation_hint, || {
+            let result = match state.eval_in_arena(self.operator(), &arguments, context) {
+                Some(value) => Ok(value),
+                None => self
+                    .operator()
+                    .eval(&arguments, context, state.config())
+                    .map_err(|error| self.locate_error(error)),
+            };
+            Self::apply_nan_policy(self.operator(), &arguments, context, state, result)
+        });
+        state.recycle_arguments(arguments);
+        result
+    }
+
+    /// Evaluates the children of this node from left to right, and then this node itself.
+    /// If the operator of this node short-circuits, the remaining children are not evaluated, and children that the operator skips are not evaluated either.
+    fn eval_recursively_mut(
+        &self,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        if let Some((identifier, expression)) = Self::named_expression(self.operator(), context) {
+            let _guard = state.enter_expression(identifier)?;
+            return expression.eval_recursively_mut(context, state);
+        }
+        if let Some(identifier) = higher_order_identifier(self.operator(), context) {
+            return self.eval_higher_order_mut(identifier, context, state);
+        }
+        if let Operator::Shared { id } = self.operator() {
+            return self.eval_shared_mut(*id, context, state);
+        }
+
+        let mut arguments = state.take_arguments();
+        for child in self.children() {
+            if self.operator().skip_argument(&mut arguments) {
+                continue;
+            }
+            if let Some(result) = self.push_argument(
+                &mut arguments,
+                child.eval_recursively_mut(context, state),
+                child,
+            )? {
+                state.recycle_arguments(arguments);
+                return Ok(result);
+            }
+        }
+        state.check_cancelled()?;
+        let allocation_hint = Self::allocation_hint(self.operator(), &arguments, context, state);
+        let result = Self::allocate(state, allocation_hint, || {
+            let result = match state.eval_in_arena(self.operator(), &arguments, &*context) {
+                Some(value) => Ok(value),
+                None => self
+                    .operator()
+                    .eval_mut(&arguments, context, state.config())
+                    .map_err(|error| self.locate_error(error)),
+            };
+            Self::apply_nan_policy(self.operator(), &arguments, context, state, result)
+        });
+        state.recycle_arguments(arguments);
+        result
+    }
+
+    /// Pushes the result of evaluating the given child to the arguments of this node, see `Operator::push_argument`.
+    // This is not inlined, such that it does not increase the stack frames of the recursive evaluation.
+    #[inline(never)]
+    fn push_argument(
+        &self,
+        arguments: &mut Vec<Value>,
+        result: EvalexprResult<Value>,
+        child: &Node,
+    ) -> EvalexprResult<Option<Value>> {
+        self.operator()
+            .push_argument(arguments, result, child.strip_root_nodes().operator())
+    }
+
+    /// Adds the span of this node to an error about a variable that this node reads, but that is not found.
+    fn locate_error(&self, error: EvalexprError) -> EvalexprError {
+        match error {
+            EvalexprError::VariableIdentifierNotFound {
+                identifier,
+                span: None,
+            } if matches!(self.operator(), Operator::VariableIdentifier { .. }) => {
+                EvalexprError::VariableIdentifierNotFound {
+                    identifier,
+                    span: self.span.clone(),
+                }
+            },
+            error => error,
+        }
+    }
+
+    /// Returns the named expression that a node with the given operator refers to, if the operator is a variable identifier that is not linked to a value, but to a named expression.
+    fn named_expression<'a>(
+        operator: &'a Operator,
+        context: &dyn Context,
+    ) -> Option<(&'a str, Arc<Node>)> {
+        if let Operator::VariableIdentifier { identifier } = operator {
+            if context.get_value(identifier).is_none() {
+                return context
+                    .get_expression(identifier)
+                    .map(|expression| (identifier.as_ref(), expression));
+            }
+        }
+        None
+    }
+
+    /// Evaluates this node, which is a call of the higher-order builtin function with the given identifier, like `map`.
+    /// The second argument of the call is evaluated once for each element of the first.
+    fn eval_higher_order(
+        &self,
+        identifier: &str,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let (elements, body) = self.higher_order_arguments()?;
+        let elements = elements.eval_recursively(context, state)?;
+        let mut scope = ItContext::shared(context);
+        let result = eval_higher_order(
+            identifier,
+            elements,
+            body.strip_root_nodes().operator(),
+            &mut scope,
+            state,
+            |scope| body.eval_recursively(scope, state),
+        );
+        Self::allocate(state, None, || result)
+    }
+
+    /// Evaluates this node, which is a call of the higher-order builtin function with the given identifier, with a mutable context.
+    fn eval_higher_order_mut(
+        &self,
+        identifier: &str,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        let (elements, body) = self.higher_order_arguments()?;
+        let elements = elements.eval_recursively_mut(context, state)?;
+        let mut scope = ItContext::exclusive(context);
+        let result = eval_higher_order(
+            identifier,
+            elements,
+            body.strip_root_nodes().operator(),
+            &mut scope,
+            state,
+            |scope| body.eval_recursively_mut(scope, state),
+        );
+        Self::allocate(state, None, || result)
+    }
+
+    /// Evaluates this node, which is an occurrence of the shared subexpression with the given id.
+    /// If another occurrence was evaluated before within the same evaluation, its value is reused.
+    fn eval_shared(
+        &self,
+        id: usize,
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        if let Some(value) = Self::reuse_shared_value(id, state)? {
+            return Ok(value);
+        }
+        let subexpression = &self.children()[0];
+        let value = subexpression.eval_recursively(context, state)?;
+        if subexpression.is_pure_in(context) {
+            state.set_shared_value(id, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// Evaluates this node, which is an occurrence of the shared subexpression with the given id, with a mutable context.
+    fn eval_shared_mut(
+        &self,
+        id: usize,
+        context: &mut dyn Context,
+        state: &EvalState,
+    ) -> EvalexprResult<Value> {
+        if let Some(value) = Self::reuse_shared_value(id, state)? {
+            return Ok(value);
+        }
+        let subexpression = &self.children()[0];
+        let value = subexpression.eval_recursively_mut(context, state)?;
+        if subexpression.is_pure_in(context) {
+            state.set_shared_value(id, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// Returns the value of the shared subexpression with the given id if it was already computed in the current evaluation.
+    /// The bytes of the value are registered with the state again, as if the subexpression was evaluated another time.
+    fn reuse_shared_value(id: usize, state: &EvalState) -> EvalexprResult<Option<Value>> {
+        match state.shared_value(id) {
+            Some(value) => {
+                if state.config().max_result_bytes.is_some() {
+                    state.allocate(value.allocated_bytes())?;
+                }
+                Ok(Some(value))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the two arguments of a call of a higher-order builtin function.
+    fn higher_order_arguments(&self) -> EvalexprResult<(&Node, &Node)> {
+        let argument = self.children()[0].strip_root_nodes();
+        expect_higher_order_arguments(argument.operator(), argument.children().len())?;
+        Ok((&argument.children()[0], &argument.children()[1]))
+    }
+
+    /// Returns the amount of bytes that evaluating the given operator allocates, if it is known in advance.
+    /// Returns `Some(0)` without computing the amount if the state does not limit the allocated bytes.
+    pub(crate) fn allocation_hint(
+        operator: &Operator,
+        arguments: &[Value],
+        context: &dyn Context,
+        state: &EvalState,
+    ) -> Option<usize> {
+        if state.config().max_result_bytes.is_some() {
+            operator.allocation_hint(arguments, context)
+        } else {
+            Some(0)
+        }
+    }
+
+    /// Registers the allocation of the given amount of bytes with the state before calling `eval`.
+    /// If the amount is not known in advance, the bytes of the result of `eval` are registered afterwards.
+    pub(crate) fn allocate<F: FnOnce() -> EvalexprResult<Value>>(
+        state: &EvalState,
+        allocation_hint: Option<usize>,
+        eval: F,
+    ) -> EvalexprResult<Value> {
+        if let Some(bytes) = allocation_hint {
+            state.allocate(bytes)?;
+            eval()
+        } else {
+            let result = eval()?;
+            state.allocate(result.allocated_bytes())?;
+            Ok(result)
+        }
+    }
+
+    /// Replaces a NaN that the given operator produced from operands that are not NaN with an error, if the `EvalConfig::nan_policy` of the state says so.
+    /// Functions from the context are not checked, and neither are leaf operators, which only pass on existing values.
+    pub(crate) fn apply_nan_policy(
+        operator: &Operator,
+        arguments: &[Value],
+        context: &dyn Context,
+        state: &EvalState,
+        result: EvalexprResult<Value>,
+    ) -> EvalexprResult<Value> {
+        match (&result, state.config().nan_policy) {
+            (Ok(Value::Float(float)), NanPolicy::Error)
+                if float.is_nan() && !operator.is_leaf() => {},
+            _ => return result,
+        }
+
+        let operands = match (operator, arguments) {
+            // Functions called with named arguments are always from the context.
+            (Operator::NamedFunctionIdentifier { .. }, _) => return result,
+            (Operator::FunctionIdentifier { identifier }, [argument]) => {
+                if context.get_function(identifier).is_some() {
+                    return result;
+                }
+                match argument {
+                    Value::Tuple(tuple) => tuple.clone(),
+                    argument => vec![argument.clone()],
+                }
+            },
+            _ => arguments.to_vec(),
+        };
+        if operands
+            .iter()
+            .any(|operand| matches!(operand, Value::Float(float) if float.is_nan()))
+        {
+            result
+        } else {
+            Err(EvalexprError::nan_produced(operator.to_string(), operands))
+        }
+    }
+
+    /// Checks the types of this expression without evaluating it.
+    ///
+    /// The types of variables are given by `variable_types`, and the signatures of functions by `function_signatures`.
+    /// Builtin functions have predefined signatures that can be overridden by `function_signatures`.
+    /// Variables assigned within the expression get the type of the assigned value.
+    /// If `unknown_identifiers_are_any` is true, variables and functions without a known type can have any type and return any type.
+    /// Otherwise, they are reported as errors.
+    ///
+    /// Returns the static type of the result of the expression, or all type errors found in the expression.
+    /// The span of a type error is the one of the operator, function call, argument or variable that caused it.
+    /// An operator is only reported if it certainly fails for the types of its arguments.
+    /// If for example one of the arguments of `+` can have any type, then no error is reported for the `+`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut variable_types = HashMap::new();
+    /// variable_types.insert("flag".to_string(), ValueType::Boolean);
+    /// variable_types.insert("score".to_string(), ValueType::Float);
+    /// let mut function_signatures = HashMap::new();
+    /// function_signatures.insert(
+    ///     "scale".to_string(),
+    ///     Signature::new(vec![StaticType::Number, StaticType::Int], StaticType::Float),
+    /// );
+    ///
+    /// let tree = build_operator_tree("scale(score, 2) > 0.5 && flag").unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     tree.check_types(&variable_types, &function_signatures, false),
+    ///     Ok(StaticType::Boolean)
+    /// );
+    ///
+    /// let tree = build_operator_tree("\"abc\" * 2 + (flag + 1)").unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     tree.check_types(&variable_types, &function_signatures, false),
+    ///     Err(vec![
+    ///         TypeCheckError::OperatorArgumentTypes {
+    ///             operator: "*".to_string(),
+    ///             actual: vec![StaticType::String, StaticType::Int],
+    ///             span: Some(6..7),
+    ///         },
+    ///         TypeCheckError::OperatorArgumentTypes {
+    ///             operator: "+".to_string(),
+    ///             actual: vec![StaticType::Boolean, StaticType::Int],
+    ///             span: Some(18..19),
+    ///         },
+    ///     ])
+    /// );
+    /// ```
+    pub fn check_types(
+        &self,
+        variable_types: &HashMap<String, ValueType>,
+        function_signatures: &HashMap<String, Signature>,
+        unknown_identifiers_are_any: bool,
+    ) -> Result<StaticType, Vec<TypeCheckError>> {
+        typecheck::check_types(
+            self,
+            variable_types,
+            function_signatures,
+            unknown_identifiers_are_any,
+        )
+    }
+
+    /// Infers the types that the variables of this expression must have, from the operators and builtin functions that use them.
+    ///
+    /// Returns the variables that are read before they are assigned within the expression, like the inputs of a form, together with their possible types.
+    /// A variable starts out with `TypeSet::ANY`, and each use of the variable as a direct argument of an operator or builtin function narrows the set to the types that the use accepts.
+    /// For example, `a + 1` narrows `a` to integers and floats, and `a && b` narrows both `a` and `b` to booleans.
+    /// A variable that is used in ways that no single type satisfies, like in `a + 1 == 2 && a`, gets `TypeSet::CONFLICT`.
+    /// A use does not narrow the variable if the operator fails for the other arguments regardless of the type of the variable, like in `a + true`.
+    ///
+    /// The inference is conservative: it only narrows variables that are used directly, not through nested operators, and it does not constrain the arguments of functions that are not builtin.
+    /// So the actual types may be narrower than the inferred ones, but a type that is not in an inferred set certainly makes the evaluation fail.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("price * amount > limit && name > \"\"").unwrap(); // Do proper error handling here
+    /// let types = tree.infer_variable_types();
+    /// assert_eq!(types["price"].value_types(), vec![ValueType::Int, ValueType::Float]);
+    /// assert_eq!(types["limit"].value_types(), vec![ValueType::Int, ValueType::Float]);
+    /// assert_eq!(types["name"].value_types(), vec![ValueType::String]);
+    ///
+    /// let tree = build_operator_tree("a + 1 == 2 && a").unwrap(); // Do proper error handling here
+    /// assert!(tree.infer_variable_types()["a"].is_conflict());
+    /// ```
+    pub fn infer_variable_types(&self) -> HashMap<String, TypeSet> {
+        typecheck::infer_variable_types(self)
+    }
+
+    /// Returns a copy of the operator tree rooted at this node that stores all nodes in a single vector.
+    /// See `CompactTree` for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let node = build_operator_tree("1 + 2 * 3").unwrap(); // Do proper error handling here
+    /// let compact = node.compact();
+    /// assert_eq!(compact.eval(), node.eval());
+    /// ```
+    pub fn compact(&self) -> CompactTree {
+        CompactTree::new(self)
     }
 
     /// Evaluates the operator tree rooted at this node with an empty context.
@@ -149,7 +837,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a string with an the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_string_with_context(&self, context: &Context) -> EvalexprResult<String> {
+    pub fn eval_string_with_context(&self, context: &dyn Context) -> EvalexprResult<String> {
         match self.eval_with_context(context) {
             Ok(Value::String(string)) => Ok(string),
             Ok(value) => Err(EvalexprError::expected_string(value)),
@@ -160,7 +848,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a float with an the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_float_with_context(&self, context: &Context) -> EvalexprResult<FloatType> {
+    pub fn eval_float_with_context(&self, context: &dyn Context) -> EvalexprResult<FloatType> {
         match self.eval_with_context(context) {
             Ok(Value::Float(float)) => Ok(float),
             Ok(value) => Err(EvalexprError::expected_float(value)),
@@ -171,7 +859,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into an integer with an the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_int_with_context(&self, context: &Context) -> EvalexprResult<IntType> {
+    pub fn eval_int_with_context(&self, context: &dyn Context) -> EvalexprResult<IntType> {
         match self.eval_with_context(context) {
             Ok(Value::Int(int)) => Ok(int),
             Ok(value) => Err(EvalexprError::expected_int(value)),
@@ -183,7 +871,7 @@ impl Node {
     /// If the result of the expression is an integer, it is silently converted into a float.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_number_with_context(&self, context: &Context) -> EvalexprResult<FloatType> {
+    pub fn eval_number_with_context(&self, context: &dyn Context) -> EvalexprResult<FloatType> {
         match self.eval_with_context(context) {
             Ok(Value::Int(int)) => Ok(int as FloatType),
             Ok(Value::Float(float)) => Ok(float),
@@ -195,7 +883,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a boolean with an the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_boolean_with_context(&self, context: &Context) -> EvalexprResult<bool> {
+    pub fn eval_boolean_with_context(&self, context: &dyn Context) -> EvalexprResult<bool> {
         match self.eval_with_context(context) {
             Ok(Value::Boolean(boolean)) => Ok(boolean),
             Ok(value) => Err(EvalexprError::expected_boolean(value)),
@@ -206,7 +894,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a tuple with an the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_tuple_with_context(&self, context: &Context) -> EvalexprResult<TupleType> {
+    pub fn eval_tuple_with_context(&self, context: &dyn Context) -> EvalexprResult<TupleType> {
         match self.eval_with_context(context) {
             Ok(Value::Tuple(tuple)) => Ok(tuple),
             Ok(value) => Err(EvalexprError::expected_tuple(value)),
@@ -217,7 +905,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into an empty value with an the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_empty_with_context(&self, context: &Context) -> EvalexprResult<EmptyType> {
+    pub fn eval_empty_with_context(&self, context: &dyn Context) -> EvalexprResult<EmptyType> {
         match self.eval_with_context(context) {
             Ok(Value::Empty) => Ok(EMPTY_VALUE),
             Ok(value) => Err(EvalexprError::expected_empty(value)),
@@ -228,7 +916,10 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a string with an the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_string_with_context_mut(&self, context: &mut Context) -> EvalexprResult<String> {
+    pub fn eval_string_with_context_mut(
+        &self,
+        context: &mut dyn Context,
+    ) -> EvalexprResult<String> {
         match self.eval_with_context_mut(context) {
             Ok(Value::String(string)) => Ok(string),
             Ok(value) => Err(EvalexprError::expected_string(value)),
@@ -239,7 +930,10 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a float with an the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_float_with_context_mut(&self, context: &mut Context) -> EvalexprResult<FloatType> {
+    pub fn eval_float_with_context_mut(
+        &self,
+        context: &mut dyn Context,
+    ) -> EvalexprResult<FloatType> {
         match self.eval_with_context_mut(context) {
             Ok(Value::Float(float)) => Ok(float),
             Ok(value) => Err(EvalexprError::expected_float(value)),
@@ -250,7 +944,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into an integer with an the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_int_with_context_mut(&self, context: &mut Context) -> EvalexprResult<IntType> {
+    pub fn eval_int_with_context_mut(&self, context: &mut dyn Context) -> EvalexprResult<IntType> {
         match self.eval_with_context_mut(context) {
             Ok(Value::Int(int)) => Ok(int),
             Ok(value) => Err(EvalexprError::expected_int(value)),
@@ -262,7 +956,10 @@ impl Node {
     /// If the result of the expression is an integer, it is silently converted into a float.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_number_with_context_mut(&self, context: &mut Context) -> EvalexprResult<FloatType> {
+    pub fn eval_number_with_context_mut(
+        &self,
+        context: &mut dyn Context,
+    ) -> EvalexprResult<FloatType> {
         match self.eval_with_context_mut(context) {
             Ok(Value::Int(int)) => Ok(int as FloatType),
             Ok(Value::Float(float)) => Ok(float),
@@ -274,7 +971,7 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a boolean with an the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_boolean_with_context_mut(&self, context: &mut Context) -> EvalexprResult<bool> {
+    pub fn eval_boolean_with_context_mut(&self, context: &mut dyn Context) -> EvalexprResult<bool> {
         match self.eval_with_context_mut(context) {
             Ok(Value::Boolean(boolean)) => Ok(boolean),
             Ok(value) => Err(EvalexprError::expected_boolean(value)),
@@ -285,7 +982,10 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into a tuple with an the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_tuple_with_context_mut(&self, context: &mut Context) -> EvalexprResult<TupleType> {
+    pub fn eval_tuple_with_context_mut(
+        &self,
+        context: &mut dyn Context,
+    ) -> EvalexprResult<TupleType> {
         match self.eval_with_context_mut(context) {
             Ok(Value::Tuple(tuple)) => Ok(tuple),
             Ok(value) => Err(EvalexprError::expected_tuple(value)),
@@ -296,7 +996,10 @@ impl Node {
     /// Evaluates the operator tree rooted at this node into an empty value with an the given mutable context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
-    pub fn eval_empty_with_context_mut(&self, context: &mut Context) -> EvalexprResult<EmptyType> {
+    pub fn eval_empty_with_context_mut(
+        &self,
+        context: &mut dyn Context,
+    ) -> EvalexprResult<EmptyType> {
         match self.eval_with_context_mut(context) {
             Ok(Value::Empty) => Ok(EMPTY_VALUE),
             Ok(value) => Err(EvalexprError::expected_empty(value)),
@@ -354,18 +1057,100 @@ impl Node {
         self.eval_empty_with_context(&EmptyContext)
     }
 
-    fn children(&self) -> &[Node] {
-        &self.children
+    /// Returns the kind of the operator of this node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("a + 1").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.kind(), OperatorKind::Root);
+    /// let sum = &tree.children()[0];
+    /// assert_eq!(sum.kind(), OperatorKind::Add);
+    /// let kinds: Vec<_> = sum.children().iter().map(Node::kind).collect();
+    /// assert_eq!(kinds, [OperatorKind::VariableRead, OperatorKind::Constant]);
+    /// ```
+    pub fn kind(&self) -> OperatorKind {
+        self.operator().into()
+    }
+
+    /// Returns the text of the literal this node was parsed from, if it is a constant that was parsed with `ParseConfig::preserve_literal_text`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let config = ParseConfig {
+    ///     preserve_literal_text: true,
+    ///     ..ParseConfig::default()
+    /// };
+    /// let tree = build_operator_tree_with_config("1.50", &config).unwrap().tree; // Do proper error handling here
+    /// assert_eq!(tree.children()[0].literal_text(), Some("1.50"));
+    /// assert_eq!(tree.eval(), Ok(Value::from(1.5)));
+    /// ```
+    pub fn literal_text(&self) -> Option<&str> {
+        match self.operator() {
+            Operator::PreservedConst { constant } => Some(&constant.text),
+            _ => None,
+        }
+    }
+
+    /// Returns the children of this node, which are the arguments of its operator, in order.
+    pub fn children(&self) -> &[Node] {
+        match &self.children {
+            Some(children) => children,
+            None => &[],
+        }
+    }
+
+    /// Returns the children of this node for modification.
+    /// If the children are shared with a clone of this node, they are copied first, which clones each child without copying its own children.
+    fn children_mut(&mut self) -> &mut Vec<Node> {
+        Arc::make_mut(self.children.get_or_insert_with(Default::default))
     }
 
-    fn operator(&self) -> &Operator {
+    /// Moves the children of this node to the given vector, unless they are shared with a clone of this node.
+    fn take_unshared_children(&mut self, nodes: &mut Vec<Node>) {
+        if let Some(children) = self.children.as_mut().and_then(Arc::get_mut) {
+            nodes.append(children);
+        }
+    }
+
+    /// Returns the first descendant of this node that is not a root node with a single child, or this node itself if it is not such a root node.
+    /// Shared subexpressions are stripped like root nodes.
+    pub(crate) fn strip_root_nodes(&self) -> &Node {
+        let mut node = self;
+        while matches!(
+            node.operator(),
+            Operator::RootNode | Operator::Shared { .. }
+        ) && node.children().len() == 1
+        {
+            node = &node.children()[0];
+        }
+        node
+    }
+
+    pub(crate) fn operator(&self) -> &Operator {
         &self.operator
     }
 
+    pub(crate) fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+
     fn has_enough_children(&self) -> bool {
         Some(self.children().len()) == self.operator().max_argument_amount()
     }
 
+    /// Returns true if this node or a node on the path along its last children misses an operand.
+    /// Sequences are never missing an operand, as they accept any amount of them.
+    fn has_open_operand(&self) -> bool {
+        matches!(self.operator().max_argument_amount(), Some(amount) if self.children().len() < amount)
+            || self.children().last().is_some_and(Node::has_open_operand)
+    }
+
     fn insert_back_prioritized(&mut self, node: Node, is_root_node: bool) -> EvalexprResult<()> {
         // println!("Inserting {:?} into {:?}", node.operator, self.operator());
         if self.operator().precedence() < node.operator().precedence() || is_root_node
@@ -375,14 +1160,26 @@ impl Node {
             if self.operator().is_leaf() {
                 Err(EvalexprError::AppendedToLeafNode)
             } else if self.has_enough_children() {
-                if self.children.last().unwrap().operator().precedence()
+                let last_child = self.children().last().unwrap();
+                // An operator that misses its right operand takes a following unary operator as operand regardless of precedence, like in `2 ^ -1`.
+                if last_child.has_open_operand()
+                    && matches!(
+                        node.operator(),
+                        Operator::Neg | Operator::Pos | Operator::Not
+                    )
+                {
+                    self.children_mut()
+                        .last_mut()
+                        .unwrap()
+                        .insert_back_prioritized(node, true)
+                } else if self.children().last().unwrap().operator().precedence()
                     < node.operator().precedence()
                     // Right-to-left chaining
-                    || (self.children.last().unwrap().operator().precedence()
-                    == node.operator().precedence() && !self.children.last().unwrap().operator().is_left_to_right() && !node.operator().is_left_to_right())
+                    || (self.children().last().unwrap().operator().precedence()
+                    == node.operator().precedence() && !self.children().last().unwrap().operator().is_left_to_right() && !node.operator().is_left_to_right())
                 {
-                    // println!("Recursing into {:?}", self.children.last().unwrap().operator());
-                    self.children
+                    // println!("Recursing into {:?}", self.children().last().unwrap().operator());
+                    self.children_mut()
                         .last_mut()
                         .unwrap()
                         .insert_back_prioritized(node, false)
@@ -392,16 +1189,16 @@ impl Node {
                         return Err(EvalexprError::AppendedToLeafNode);
                     }
 
-                    let last_child = self.children.pop().unwrap();
-                    self.children.push(node);
-                    let node = self.children.last_mut().unwrap();
+                    let last_child = self.children_mut().pop().unwrap();
+                    self.children_mut().push(node);
+                    let node = self.children_mut().last_mut().unwrap();
 
-                    node.children.push(last_child);
+                    node.children_mut().push(last_child);
                     Ok(())
                 }
             } else {
                 // println!("Inserting as specified");
-                self.children.push(node);
+                self.children_mut().push(node);
                 Ok(())
             }
         } else {
@@ -410,17 +1207,20 @@ impl Node {
     }
 }
 
+/// Collapses the sequences of the root stack with a higher precedence than `collapse_goal` into `root`.
+/// The span is the one of the token that is parsed, and is reported if the topmost root node is missing.
 fn collapse_root_stack_to(
     root_stack: &mut Vec<Node>,
     mut root: Node,
     collapse_goal: &Node,
+    span: &Range<usize>,
 ) -> EvalexprResult<Node> {
     loop {
         if let Some(mut potential_higher_root) = root_stack.pop() {
             // TODO I'm not sure about this >, as I have no example for different sequence operators with the same precedence
             if potential_higher_root.operator().precedence() > collapse_goal.operator().precedence()
             {
-                potential_higher_root.children.push(root);
+                potential_higher_root.children_mut().push(root);
                 root = potential_higher_root;
             } else {
                 root_stack.push(potential_higher_root);
@@ -428,20 +1228,22 @@ fn collapse_root_stack_to(
             }
         } else {
             // This is the only way the topmost root node could have been removed
-            return Err(EvalexprError::UnmatchedRBrace);
+            return Err(EvalexprError::UnmatchedRBrace { span: span.clone() });
         }
     }
 
     Ok(root)
 }
 
-fn collapse_all_sequences(root_stack: &mut Vec<Node>) -> EvalexprResult<()> {
+/// Collapses all sequences on top of the root stack into their parents.
+/// The span is the one of the token that is parsed, and is reported if the topmost root node is missing.
+fn collapse_all_sequences(root_stack: &mut Vec<Node>, span: &Range<usize>) -> EvalexprResult<()> {
     // println!("Collapsing all sequences");
     // println!("Initial root stack is: {:?}", root_stack);
     let mut root = if let Some(root) = root_stack.pop() {
         root
     } else {
-        return Err(EvalexprError::UnmatchedRBrace);
+        return Err(EvalexprError::UnmatchedRBrace { span: span.clone() });
     };
 
     loop {
@@ -453,7 +1255,7 @@ fn collapse_all_sequences(root_stack: &mut Vec<Node>) -> EvalexprResult<()> {
 
         if let Some(mut potential_higher_root) = root_stack.pop() {
             if root.operator().is_sequence() {
-                potential_higher_root.children.push(root);
+                potential_higher_root.children_mut().push(root);
                 root = potential_higher_root;
             } else {
                 root_stack.push(potential_higher_root);
@@ -462,7 +1264,7 @@ fn collapse_all_sequences(root_stack: &mut Vec<Node>) -> EvalexprResult<()> {
             }
         } else {
             // This is the only way the topmost root node could have been removed
-            return Err(EvalexprError::UnmatchedRBrace);
+            return Err(EvalexprError::UnmatchedRBrace { span: span.clone() });
         }
     }
 
@@ -470,16 +1272,378 @@ fn collapse_all_sequences(root_stack: &mut Vec<Node>) -> EvalexprResult<()> {
     Ok(())
 }
 
-pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node> {
+pub(crate) fn tokens_to_operator_tree(tokens: Vec<(Token, Range<usize>)>) -> EvalexprResult<Node> {
+    tokens_to_operator_tree_with_config(tokens, &ParseConfig::default())
+}
+
+/// Builds the operator tree from the given tokens, each paired with the range of bytes of the source that it was read from, and checks the tree according to the given configuration.
+pub(crate) fn tokens_to_operator_tree_with_config(
+    tokens: Vec<(Token, Range<usize>)>,
+    config: &ParseConfig,
+) -> EvalexprResult<Node> {
+    let tree = parse_tokens(tokens, config.identifier_interner.as_deref())?;
+    if !config.allow_comparison_chains {
+        check_comparison_chains(&tree)?;
+    }
+    Ok(tree)
+}
+
+/// Fails with `EvalexprError::SuspiciousComparisonChain` if a comparison in the given tree has another comparison as direct operand.
+/// A parenthesized comparison is wrapped in a root node, so it is not a direct operand.
+/// Comparisons that lack an operand, like the second one in `a > > b`, are not reported, as they fail when evaluated anyway.
+fn check_comparison_chains(tree: &Node) -> EvalexprResult<()> {
+    let is_comparison = |node: &Node| node.operator().is_comparison() && node.children().len() == 2;
+    match tree
+        .iter()
+        .find(|node| is_comparison(node) && node.children().iter().any(is_comparison))
+    {
+        Some(node) => Err(EvalexprError::SuspiciousComparisonChain {
+            expression: node.to_minified_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the binary operator that the given compound assignment token applies, like `Operator::Add` for `+=`.
+fn compound_assignment_operator(token: &Token) -> Option<Operator> {
+    match token {
+        Token::PlusAssign => Some(Operator::Add),
+        Token::MinusAssign => Some(Operator::Sub),
+        Token::StarAssign => Some(Operator::Mul),
+        Token::SlashAssign => Some(Operator::Div),
+        Token::PercentAssign => Some(Operator::Mod),
+        Token::AndAssign => Some(Operator::And),
+        Token::OrAssign => Some(Operator::Or),
+        _ => None,
+    }
+}
+
+/// Returns the name and the value of the given argument of a function call if it is a named argument like `precision = 2`.
+/// Parenthesized assignments, compound assignments and assignments to tuples of variables are not named arguments.
+fn named_argument<'a>(
+    argument: &'a Node,
+    desugared_assignments: &HashMap<usize, Option<Operator>>,
+) -> Option<(&'a str, &'a Node)> {
+    if argument.operator() != &Operator::RootNode || argument.children().len() != 1 {
+        return None;
+    }
+    let assignment = &argument.children()[0];
+    match (assignment.operator(), &assignment.span) {
+        (Operator::Assign, Some(span)) if !desugared_assignments.contains_key(&span.start) => {},
+        _ => return None,
+    }
+    match assignment.children()[0].operator() {
+        Operator::Const {
+            value: Value::String(name),
+        } => Some((name, &assignment.children()[1])),
+        _ => None,
+    }
+}
+
+/// Returns the span of the leftmost token of the given node.
+fn leftmost_span(node: &Node) -> Option<Range<usize>> {
+    let mut span = node.span.clone();
+    let mut node = node;
+    while let Some(child) = node.children().first() {
+        node = child;
+        span = node.span.clone().or(span);
+    }
+    span
+}
+
+/// Rewrites each function call whose trailing arguments are assignments to variables, like `f(x, precision = 2)`, to a call with named arguments.
+///
+/// The calls of higher-order builtins are not rewritten, such that `map(xs, total = total + it)` still assigns to `total`.
+fn name_arguments(
+    node: &mut Node,
+    desugared_assignments: &HashMap<usize, Option<Operator>>,
+) -> EvalexprResult<()> {
+    if node.children.is_none() {
+        return Ok(());
+    }
+    for child in node.children_mut() {
+        name_arguments(child, desugared_assignments)?;
+    }
+
+    let identifier = match node.operator() {
+        Operator::FunctionIdentifier { identifier } if !is_higher_order_builtin(identifier) => {
+            identifier.clone()
+        },
+        _ => return Ok(()),
+    };
+    let argument = &node.children()[0];
+    let (elements, tuple_span) = match argument.children() {
+        [tuple] if tuple.operator() == &Operator::Tuple => (tuple.children(), tuple.span.clone()),
+        _ => (slice::from_ref(argument), None),
+    };
+    let first_named = match elements
+        .iter()
+        .position(|element| named_argument(element, desugared_assignments).is_some())
+    {
+        Some(first_named) => first_named,
+        None => return Ok(()),
+    };
+
+    let mut argument_names: Vec<String> = Vec::new();
+    let mut arguments = elements[..first_named].to_vec();
+    for element in &elements[first_named..] {
+        let (name, value) = named_argument(element, desugared_assignments).ok_or_else(|| {
+            EvalexprError::PositionalArgumentAfterNamed {
+                span: leftmost_span(element).unwrap_or_default(),
+            }
+        })?;
+        // Names that are given twice are reported before unknown ones, which need the called function.
+        if argument_names.iter().any(|previous| previous == name) {
+            return Err(EvalexprError::DuplicateArgument {
+                function: identifier.to_string(),
+                parameter: name.to_string(),
+            });
+        }
+        argument_names.push(name.to_string());
+        let mut value_root = Node::root_node();
+        value_root.children_mut().push(value.clone());
+        value_root.span = element.span.clone();
+        arguments.push(value_root);
+    }
+
+    let mut tuple = Node::new(Operator::Tuple);
+    tuple.children = Some(Arc::new(arguments));
+    tuple.span = tuple_span;
+    let mut argument = Node::root_node();
+    argument.children_mut().push(tuple);
+    argument.span = node.children()[0].span.clone();
+    node.operator = Operator::NamedFunctionIdentifier {
+        call: Box::new(NamedCall {
+            identifier,
+            argument_names,
+        }),
+    };
+    node.children_mut()[0] = argument;
+    Ok(())
+}
+
+/// Returns the assignment target of a tuple of variables, like `(a, (b, c))`, as a tuple of their identifiers.
+fn tuple_assignment_target(node: &Node) -> Value {
+    let node = node.strip_root_nodes();
+    match node.operator() {
+        Operator::VariableIdentifier { identifier } => Value::String(identifier.to_string()),
+        Operator::Tuple => Value::Tuple(
+            node.children()
+                .iter()
+                .map(tuple_assignment_target)
+                .collect(),
+        ),
+        operator => unreachable!("{:?} is not part of an assignment target", operator),
+    }
+}
+
+/// Rewrites each assignment whose span starts at a key of `assignments`.
+///
+/// The target of a tuple of variables, like `(a, b) = t`, is replaced with a constant tuple of their identifiers, such that the variables are not read.
+/// A compound assignment, which is mapped to its operator `op`, is rewritten from `x = e` to `x = x op e`.
+/// This way, a compound assignment like `x += e` reads the variable, applies the operator and assigns the result like the plain assignment `x = x + (e)`.
+fn desugar_assignments(
+    node: &mut Node,
+    assignments: &HashMap<usize, Option<Operator>>,
+    interner: Option<&IdentifierInterner>,
+) {
+    if node.children.is_none() {
+        return;
+    }
+    for child in node.children_mut() {
+        desugar_assignments(child, assignments, interner);
+    }
+
+    let operator = match (&node.operator, &node.span) {
+        (Operator::Assign, Some(span)) => match assignments.get(&span.start) {
+            Some(operator) => operator.clone(),
+            None => return,
+        },
+        _ => return,
+    };
+    let span = node.span.clone();
+    let children = node.children_mut();
+    if !matches!(children[0].operator(), Operator::Const { .. }) {
+        let mut target = Node::new(Operator::value(tuple_assignment_target(&children[0])));
+        target.span = children[0].span.clone();
+        children[0] = target;
+    }
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return,
+    };
+    let identifier = match children[0].operator() {
+        Operator::Const {
+            value: Value::String(identifier),
+        } => intern(identifier, interner),
+        operator => unreachable!("{:?} is not the target of an assignment", operator),
+    };
+    let mut read = Node::new(Operator::variable_identifier(identifier));
+    read.span = children[0].span.clone();
+    let mut operation = Node::new(operator);
+    operation.span = span;
+    operation.children_mut().push(read);
+    operation.children_mut().push(children.pop().unwrap());
+    children.push(operation);
+}
+
+/// Returns true if the given tokens that precede an assignment end with a target that can be assigned to.
+/// This is a single identifier that is not an operand of another operator with a higher precedence than the assignment, like in `a = 1`, `(a = 1)`, `b = a = 1`, `c ? a = 1 : 2` or `if c { a = 1 }`.
+/// If `allow_tuples` is true, the target may also be a tuple of targets, like in `(a, (b, c)) = t`.
+fn is_assignment_target(preceding: &[(Token, Range<usize>)], allow_tuples: bool) -> bool {
+    let start = match preceding.last() {
+        Some((Token::Identifier(_), _)) => preceding.len() - 1,
+        Some((Token::RBrace, _)) if allow_tuples => {
+            match tuple_assignment_target_start(preceding) {
+                Some(start) => start,
+                None => return false,
+            }
+        },
+        _ => return false,
+    };
+    match start.checked_sub(1) {
+        Some(before) => {
+            let before = &preceding[before].0;
+            matches!(
+                before,
+                Token::LBrace
+                    | Token::Question
+                    | Token::LCurlyBrace
+                    | Token::Comma
+                    | Token::Semicolon
+            ) || before.is_assignment()
+        },
+        None => true,
+    }
+}
+
+/// Returns the index of the opening parenthesis of the tuple of assignment targets that the given tokens end with, or `None` if they do not end with such a tuple.
+/// Each element of the tuple is an identifier or a nested tuple of targets, and each tuple has at least two elements, so `(a) = 1` is not accepted.
+fn tuple_assignment_target_start(tokens: &[(Token, Range<usize>)]) -> Option<usize> {
+    // The index of the token after the current element, which is the closing parenthesis or a comma.
+    let mut end = tokens.len().checked_sub(1)?;
+    let mut elements = 0;
+    loop {
+        let element_end = end.checked_sub(1)?;
+        let element_start = match tokens[element_end].0 {
+            Token::Identifier(_) => element_end,
+            Token::RBrace => tuple_assignment_target_start(&tokens[..=element_end])?,
+            _ => return None,
+        };
+        elements += 1;
+        let separator = element_start.checked_sub(1)?;
+        match tokens[separator].0 {
+            Token::Comma => end = separator,
+            Token::LBrace if elements > 1 => return Some(separator),
+            _ => return None,
+        }
+    }
+}
+
+/// Returns true if the given tokens that follow an `if` start the arguments of a function call like `if(a, b, c)`, rather than the condition of an if-expression.
+/// This is the case if the `if` is followed by a parenthesized subexpression that is not followed by a block, so `if (a) { b }` is still an if-expression.
+fn is_if_function_call(following: &[(Token, Range<usize>)]) -> bool {
+    if following.first().map(|(token, _)| token) != Some(&Token::LBrace) {
+        return false;
+    }
+    let mut depth = 0;
+    for (index, (token, _)) in following.iter().enumerate() {
+        match token {
+            Token::LBrace => depth += 1,
+            Token::RBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return following.get(index + 1).map(|(token, _)| token)
+                        != Some(&Token::LCurlyBrace);
+                }
+            },
+            _ => {},
+        }
+    }
+    // The parenthesis is never closed, which is reported when parsing the call.
+    true
+}
+
+/// Closes the innermost if-expression after the block of its last branch, and returns it within a root node, such that it is inserted like a parenthesized subexpression.
+/// If the if-expression directly follows an `else`, it is the branch for a false condition of the enclosing if-expression, which is then closed as well.
+fn close_if_expression(
+    if_expressions: &mut Vec<Node>,
+    open_groups: &mut Vec<(Token, Range<usize>, usize)>,
+    end: usize,
+) -> Node {
+    loop {
+        let mut conditional = if_expressions
+            .pop()
+            .unwrap_or_else(|| unreachable!("a block is closed outside of an if-expression"));
+        let span = conditional
+            .span
+            .as_ref()
+            .map_or(end..end, |span| span.start..end);
+        conditional.span = Some(span.clone());
+        let mut root = Node::root_node();
+        root.span = Some(span);
+        root.children_mut().push(conditional);
+
+        match (open_groups.last(), if_expressions.last_mut()) {
+            (Some((Token::Else, _, _)), Some(enclosing)) => {
+                open_groups.pop();
+                enclosing.children_mut().push(root);
+            },
+            _ => return root,
+        }
+    }
+}
+
+/// Returns the given identifier as shared string, which is taken from the interner if there is one.
+fn intern(identifier: &str, interner: Option<&IdentifierInterner>) -> Arc<str> {
+    match interner {
+        Some(interner) => interner.intern(identifier),
+        None => identifier.into(),
+    }
+}
+
+fn parse_tokens(
+    tokens: Vec<(Token, Range<usize>)>,
+    interner: Option<&IdentifierInterner>,
+) -> EvalexprResult<Node> {
     let mut root_stack = vec![Node::root_node()];
+    // The opening braces, question marks of conditionals, conditions of if-expressions and blocks that are not closed yet, with their spans and indices.
+    // Each of them has its own root node on the root stack.
+    // An `else` that is followed by another `if` is open as well until that if-expression is closed, but has no root node.
+    let mut open_groups: Vec<(Token, Range<usize>, usize)> = Vec::new();
+    // The conditionals of the if-expressions that are not closed yet, with the branches that are parsed so far.
+    let mut if_expressions: Vec<Node> = Vec::new();
+    // Set after the block of an `if` if it is directly followed by an `else`.
+    let mut else_expected = false;
+    let end = tokens.last().map_or(0, |(_, span)| span.end);
+    let end = end..end;
     let mut last_token_is_rightsided_value = false;
-    let mut token_iter = tokens.iter().peekable();
+    let mut token_iter = tokens.iter().enumerate().peekable();
+    // The assignments that are rewritten after parsing, keyed by the offset of their token, together with the operators of compound assignments.
+    let mut desugared_assignments = HashMap::new();
+    // Set if an assignment is parsed that may be a named argument of a function call.
+    let mut plain_assignments = false;
 
-    while let Some(token) = token_iter.next().cloned() {
-        let next = token_iter.peek().cloned();
+    while let Some((index, (token, span))) = token_iter.next() {
+        let (token, span) = (token.clone(), span.clone());
+        let next = token_iter.peek().map(|(_, (token, _))| token);
+
+        // Only identifiers take a directly following value as their argument, so a value after any other value, like in `(a)(b)` or `1 f(2)`, is not an operand of anything.
+        if last_token_is_rightsided_value
+            && token.is_leftsided_value()
+            && !tokens[index - 1].0.accepts_value_after()
+        {
+            return Err(EvalexprError::MissingOperator { span });
+        }
 
         let node = match token.clone() {
-            Token::Plus => Some(Node::new(Operator::Add)),
+            Token::Plus => {
+                if last_token_is_rightsided_value {
+                    Some(Node::new(Operator::Add))
+                } else {
+                    Some(Node::new(Operator::Pos))
+                }
+            },
             Token::Minus => {
                 if last_token_is_rightsided_value {
                     Some(Node::new(Operator::Sub))
@@ -498,59 +1662,301 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
             Token::Lt => Some(Node::new(Operator::Lt)),
             Token::Geq => Some(Node::new(Operator::Geq)),
             Token::Leq => Some(Node::new(Operator::Leq)),
+            Token::RegexMatch => Some(Node::new(Operator::RegexMatch)),
             Token::And => Some(Node::new(Operator::And)),
             Token::Or => Some(Node::new(Operator::Or)),
             Token::Not => Some(Node::new(Operator::Not)),
 
+            Token::Ampersand => Some(Node::new(Operator::BitAnd)),
+            Token::VerticalBar => Some(Node::new(Operator::BitOr)),
+            Token::Xor | Token::BitXor | Token::Implies => {
+                if !last_token_is_rightsided_value {
+                    return Err(if next.is_some_and(Token::is_assignment) {
+                        EvalexprError::AssignmentToKeyword {
+                            keyword: token.to_string(),
+                            span,
+                        }
+                    } else {
+                        EvalexprError::KeywordAsValue {
+                            keyword: token.to_string(),
+                            span,
+                        }
+                    });
+                }
+                Some(Node::new(match token {
+                    Token::Xor => Operator::Xor,
+                    Token::BitXor => Operator::BitXor,
+                    _ => Operator::Implies,
+                }))
+            },
+            Token::Shl => Some(Node::new(Operator::Shl)),
+            Token::Shr => Some(Node::new(Operator::Shr)),
+
+            Token::Range => Some(Node::new(Operator::Range)),
+            Token::RangeInclusive => Some(Node::new(Operator::RangeInclusive)),
+
+            Token::Coalesce => Some(Node::new(Operator::Coalesce)),
+
             Token::LBrace => {
                 root_stack.push(Node::root_node());
+                open_groups.push((token.clone(), span.clone(), index));
                 None
             },
             Token::RBrace => {
                 if root_stack.len() <= 1 {
-                    return Err(EvalexprError::UnmatchedRBrace);
+                    return Err(EvalexprError::UnmatchedRBrace { span });
+                } else {
+                    let (opening, lbrace_span, _) = open_groups
+                        .pop()
+                        .unwrap_or_else(|| (Token::LBrace, span.clone(), index));
+                    match opening {
+                        Token::Question => {
+                            return Err(EvalexprError::UnmatchedQuestionMark { span: lbrace_span })
+                        },
+                        Token::LCurlyBrace => {
+                            return Err(EvalexprError::UnmatchedLCurlyBrace { span: lbrace_span })
+                        },
+                        Token::If | Token::Else => {
+                            return Err(EvalexprError::InvalidIfExpression { span: lbrace_span })
+                        },
+                        _ => {},
+                    }
+                    collapse_all_sequences(&mut root_stack, &span)?;
+                    root_stack.pop().map(|mut root| {
+                        root.span = Some(lbrace_span.start..span.end);
+                        root
+                    })
+                }
+            },
+
+            // The conditional takes the condition as left operand like a binary operator.
+            // Its branch for a true condition is parsed like a parenthesized subexpression that ends with the colon, see below.
+            Token::Question => Some(Node::new(Operator::Conditional)),
+            Token::Colon => match open_groups.pop() {
+                Some((Token::Question, question_span, question_index)) => {
+                    collapse_all_sequences(&mut root_stack, &span)?;
+                    let branch_span = if question_index + 1 < index {
+                        tokens[question_index + 1].1.start..tokens[index - 1].1.end
+                    } else {
+                        question_span.end..span.start
+                    };
+                    root_stack.pop().map(|mut root| {
+                        root.span = Some(branch_span);
+                        root
+                    })
+                },
+                _ => return Err(EvalexprError::UnmatchedColon { span }),
+            },
+
+            // An if-expression is parsed into a conditional with a root node for the condition and for each block.
+            // The conditional is inserted like a parenthesized subexpression when its last block is closed, see `close_if_expression`.
+            Token::If => {
+                if next.is_some_and(Token::is_assignment) {
+                    return Err(EvalexprError::AssignmentToKeyword {
+                        keyword: token.to_string(),
+                        span,
+                    });
+                }
+                // Like a value, an if-expression or a call of `if` is not an operand of a preceding value.
+                if last_token_is_rightsided_value {
+                    return Err(EvalexprError::MissingOperator { span });
+                }
+                if is_if_function_call(&tokens[index + 1..]) {
+                    Some(Node::new(Operator::function_identifier(intern(
+                        &token.to_string(),
+                        interner,
+                    ))))
                 } else {
-                    collapse_all_sequences(&mut root_stack)?;
-                    root_stack.pop()
+                    let mut conditional = Node::new(Operator::Conditional);
+                    conditional.span = Some(span.clone());
+                    if_expressions.push(conditional);
+                    root_stack.push(Node::root_node());
+                    open_groups.push((token.clone(), span.clone(), index));
+                    None
+                }
+            },
+            Token::Else => {
+                if next.is_some_and(Token::is_assignment) {
+                    return Err(EvalexprError::AssignmentToKeyword {
+                        keyword: token.to_string(),
+                        span,
+                    });
+                }
+                if !else_expected {
+                    return Err(EvalexprError::InvalidIfExpression { span });
+                }
+                else_expected = false;
+                match next {
+                    Some(Token::LCurlyBrace) => {},
+                    Some(Token::If) if !is_if_function_call(&tokens[index + 2..]) => {
+                        open_groups.push((token.clone(), span.clone(), index));
+                    },
+                    _ => return Err(EvalexprError::InvalidIfExpression { span }),
+                }
+                None
+            },
+            Token::LCurlyBrace => {
+                match open_groups.last().cloned() {
+                    // The block of the branch for a false condition.
+                    _ if tokens[index.saturating_sub(1)].0 == Token::Else => {},
+                    Some((Token::If, if_span, if_index)) => {
+                        open_groups.pop();
+                        collapse_all_sequences(&mut root_stack, &span)?;
+                        let mut condition =
+                            root_stack
+                                .pop()
+                                .ok_or_else(|| EvalexprError::InvalidIfExpression {
+                                    span: if_span.clone(),
+                                })?;
+                        if condition.children().is_empty() {
+                            return Err(EvalexprError::InvalidIfExpression { span: if_span });
+                        }
+                        condition.span =
+                            Some(tokens[if_index + 1].1.start..tokens[index - 1].1.end);
+                        if let Some(conditional) = if_expressions.last_mut() {
+                            conditional.children_mut().push(condition);
+                        }
+                    },
+                    _ => return Err(EvalexprError::InvalidIfExpression { span }),
+                }
+                root_stack.push(Node::root_node());
+                open_groups.push((token.clone(), span.clone(), index));
+                None
+            },
+            Token::RCurlyBrace => {
+                match open_groups.pop() {
+                    Some((Token::LCurlyBrace, lcurly_span, _)) => {
+                        collapse_all_sequences(&mut root_stack, &span)?;
+                        let mut block = root_stack.pop().ok_or_else(|| {
+                            EvalexprError::UnmatchedRCurlyBrace { span: span.clone() }
+                        })?;
+                        block.span = Some(lcurly_span.start..span.end);
+                        let branches = if_expressions.last_mut().map_or(0, |conditional| {
+                            conditional.children_mut().push(block);
+                            conditional.children().len()
+                        });
+                        if branches == 2 && next == Some(&Token::Else) {
+                            else_expected = true;
+                            None
+                        } else {
+                            if branches == 2 {
+                                // An `if` without `else` evaluates to an empty value if its condition is false.
+                                if let Some(conditional) = if_expressions.last_mut() {
+                                    conditional.children_mut().push(Node::root_node());
+                                }
+                            }
+                            Some(close_if_expression(
+                                &mut if_expressions,
+                                &mut open_groups,
+                                span.end,
+                            ))
+                        }
+                    },
+                    Some((Token::LBrace, span, _)) => {
+                        return Err(EvalexprError::UnmatchedLBrace { span })
+                    },
+                    Some((Token::Question, span, _)) => {
+                        return Err(EvalexprError::UnmatchedQuestionMark { span })
+                    },
+                    Some((_, span, _)) => return Err(EvalexprError::InvalidIfExpression { span }),
+                    None => return Err(EvalexprError::UnmatchedRCurlyBrace { span }),
                 }
             },
 
-            Token::Comma => Some(Node::new(Operator::Tuple)),
-            Token::Assign => Some(Node::new(Operator::Assign)),
+            Token::Comma => {
+                // Without these checks, the missing element would be an empty root node that evaluates to `Value::Empty`.
+                if !last_token_is_rightsided_value {
+                    return Err(EvalexprError::LeadingComma { span });
+                }
+                if matches!(
+                    next,
+                    None | Some(Token::Semicolon) | Some(Token::Colon) | Some(Token::RCurlyBrace)
+                ) {
+                    return Err(EvalexprError::TrailingComma { span });
+                }
+                Some(Node::new(Operator::Tuple))
+            },
+            Token::Assign
+            | Token::PlusAssign
+            | Token::MinusAssign
+            | Token::StarAssign
+            | Token::SlashAssign
+            | Token::PercentAssign
+            | Token::AndAssign
+            | Token::OrAssign => {
+                let operator = compound_assignment_operator(&token);
+                if !is_assignment_target(&tokens[..index], operator.is_none()) {
+                    return Err(EvalexprError::InvalidAssignmentTarget { span });
+                }
+                if operator.is_some() || tokens[index - 1].0 == Token::RBrace {
+                    desugared_assignments.insert(span.start, operator);
+                } else {
+                    plain_assignments = true;
+                }
+                Some(Node::new(Operator::Assign))
+            },
             Token::Semicolon => Some(Node::new(Operator::Chain)),
 
             Token::Identifier(identifier) => {
-                let mut result = Some(Node::new(Operator::variable_identifier(identifier.clone())));
-                if let Some(next) = next {
-                    if next == &Token::Assign {
-                        result = Some(Node::new(Operator::value(identifier.clone().into())));
-                    } else if next.is_leftsided_value() {
-                        result = Some(Node::new(Operator::function_identifier(identifier)));
-                    }
-                }
-                result
+                let node = match next {
+                    Some(next) if next.is_assignment() => Operator::value(identifier.into()),
+                    Some(next) if next.is_leftsided_value() => {
+                        Operator::function_identifier(intern(&identifier, interner))
+                    },
+                    _ => Operator::variable_identifier(intern(&identifier, interner)),
+                };
+                Some(Node::new(node))
             },
             Token::Float(float) => Some(Node::new(Operator::value(Value::Float(float)))),
             Token::Int(int) => Some(Node::new(Operator::value(Value::Int(int)))),
-            Token::Boolean(boolean) => Some(Node::new(Operator::value(Value::Boolean(boolean)))),
+            Token::Boolean(boolean) => {
+                if next.is_some_and(Token::is_assignment) {
+                    return Err(EvalexprError::AssignmentToKeyword {
+                        keyword: boolean.to_string(),
+                        span,
+                    });
+                }
+                Some(Node::new(Operator::value(Value::Boolean(boolean))))
+            },
             Token::String(string) => Some(Node::new(Operator::value(Value::String(string)))),
+            Token::CustomLiteral(value) => Some(Node::new(Operator::value(value))),
+            Token::PreservedLiteral { token, text } => {
+                let value = match *token {
+                    Token::Float(float) => Value::Float(float),
+                    Token::Int(int) => Value::Int(int),
+                    Token::CustomLiteral(value) => value,
+                    token => unreachable!("token {:?} is not a number or custom literal", token),
+                };
+                Some(Node::new(Operator::preserved_value(value, text)))
+            },
         };
 
         if let Some(mut node) = node {
+            if node.span.is_none() {
+                node.span = Some(span.clone());
+            }
             // Need to pop and then repush here, because Rust 1.33.0 cannot release the mutable borrow of root_stack before the end of this complete if-statement
             if let Some(mut root) = root_stack.pop() {
                 if node.operator().is_sequence() {
+                    // A comma directly before a closing brace ends the tuple, like in `(1,)`, so no further element follows it.
+                    let next_element = if token == Token::Comma
+                        && matches!(tokens.get(index + 1), Some((Token::RBrace, _)))
+                    {
+                        None
+                    } else {
+                        Some(Node::root_node())
+                    };
                     // println!("Found a sequence operator");
                     // println!("Stack before sequence operation: {:?}, {:?}", root_stack, root);
                     // If root.operator() and node.operator() are of the same variant, ...
                     if mem::discriminant(root.operator()) == mem::discriminant(node.operator()) {
                         // ... we create a new root node for the next expression in the sequence
-                        root.children.push(Node::root_node());
+                        root.children_mut().extend(next_element);
                         root_stack.push(root);
                     } else if root.operator() == &Operator::RootNode {
                         // If the current root is an actual root node, we start a new sequence
-                        node.children.push(root);
-                        node.children.push(Node::root_node());
+                        node.children_mut().push(root);
+                        node.children_mut().extend(next_element);
                         root_stack.push(Node::root_node());
                         root_stack.push(node);
                     } else {
@@ -558,9 +1964,9 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                         // TODO I'm not sure about this <, as I have no example for different sequence operators with the same precedence
                         if root.operator().precedence() < node.operator().precedence() {
                             // If the new sequence has a higher precedence, it is part of the last element of the current root sequence
-                            if let Some(last_root_child) = root.children.pop() {
-                                node.children.push(last_root_child);
-                                node.children.push(Node::root_node());
+                            if let Some(last_root_child) = root.children_mut().pop() {
+                                node.children_mut().push(last_root_child);
+                                node.children_mut().extend(next_element);
                                 root_stack.push(root);
                                 root_stack.push(node);
                             } else {
@@ -569,16 +1975,16 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                             }
                         } else {
                             // If the new sequence doesn't have a higher precedence, then all sequences with a higher precedence are collapsed below this one
-                            root = collapse_root_stack_to(&mut root_stack, root, &node)?;
-                            node.children.push(root);
+                            root = collapse_root_stack_to(&mut root_stack, root, &node, &span)?;
+                            node.children_mut().push(root);
                             root_stack.push(node);
                         }
                     }
                 // println!("Stack after sequence operation: {:?}", root_stack);
                 } else if root.operator().is_sequence() {
-                    if let Some(mut last_root_child) = root.children.pop() {
+                    if let Some(mut last_root_child) = root.children_mut().pop() {
                         last_root_child.insert_back_prioritized(node, true)?;
-                        root.children.push(last_root_child);
+                        root.children_mut().push(last_root_child);
                         root_stack.push(root);
                     } else {
                         // Once a sequence has been pushed on top of the stack, it also gets a child
@@ -589,21 +1995,40 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                     root_stack.push(root);
                 }
             } else {
-                return Err(EvalexprError::UnmatchedRBrace);
+                return Err(EvalexprError::UnmatchedRBrace { span });
             }
         }
+        // The branch of a conditional for a true condition gets its own root node after the conditional is inserted.
+        if token == Token::Question {
+            root_stack.push(Node::root_node());
+            open_groups.push((token.clone(), span, index));
+        }
 
         last_token_is_rightsided_value = token.is_rightsided_value();
     }
 
     // In the end, all sequences are implicitly terminated
-    collapse_all_sequences(&mut root_stack)?;
+    collapse_all_sequences(&mut root_stack, &end)?;
 
     if root_stack.len() > 1 {
-        Err(EvalexprError::UnmatchedLBrace)
-    } else if let Some(root) = root_stack.pop() {
+        Err(match open_groups.pop() {
+            Some((Token::Question, span, _)) => EvalexprError::UnmatchedQuestionMark { span },
+            Some((Token::LCurlyBrace, span, _)) => EvalexprError::UnmatchedLCurlyBrace { span },
+            Some((Token::If, span, _)) | Some((Token::Else, span, _)) => {
+                EvalexprError::InvalidIfExpression { span }
+            },
+            Some((_, span, _)) => EvalexprError::UnmatchedLBrace { span },
+            None => EvalexprError::UnmatchedLBrace { span: end },
+        })
+    } else if let Some(mut root) = root_stack.pop() {
+        if !desugared_assignments.is_empty() {
+            desugar_assignments(&mut root, &desugared_assignments, interner);
+        }
+        if plain_assignments {
+            name_arguments(&mut root, &desugared_assignments)?;
+        }
         Ok(root)
     } else {
-        Err(EvalexprError::UnmatchedRBrace)
+        Err(EvalexprError::UnmatchedRBrace { span: end })
     }
 }
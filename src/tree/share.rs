@@ -0,0 +1,234 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context::{Context, IT_IDENTIFIER};
+use function::builtin::builtin_function;
+use operator::{assignment_target_identifiers, Operator};
+use value::Value;
+use Node;
+
+/// The id of the next shared subexpression.
+/// Ids are unique within the program, such that shared subexpressions of different operator trees never get mixed up when one tree is evaluated within the other, like a named expression.
+static NEXT_SHARED_ID: AtomicUsize = AtomicUsize::new(0);
+
+impl Node {
+    /// Rewrites this operator tree such that subexpressions that occur several times are evaluated only once per evaluation.
+    ///
+    /// Two subexpressions are shared if they are structurally identical, like the two calls in `lookup(user_id) > 0 && lookup(user_id) < 10`.
+    /// The first occurrence that is evaluated computes the value, and the other occurrences reuse it for the rest of the evaluation.
+    /// An occurrence that is not evaluated, like the right operand of a short-circuiting `&&`, does not compute anything, so the order of evaluation and the errors stay the same.
+    /// The values are kept by the `EvalState` of the evaluation, not in the context, so they are invisible to expressions and functions.
+    ///
+    /// Only pure subexpressions are shared, which always have the same value within an evaluation.
+    /// A subexpression is not shared if it contains an assignment, reads a variable that is assigned anywhere in the expression, reads `it`, or calls the builtin function `now_monotonic`.
+    /// Whether the functions and variables of the context are pure is checked when the subexpression is evaluated:
+    /// if it calls a function marked with `Function::impure` or reads a named expression, then each occurrence is evaluated on its own.
+    /// Functions are pure unless marked otherwise, so functions that count their calls or depend on other state should be marked.
+    ///
+    /// Values are only reused when the tree is evaluated with the `eval_*` methods of `Node`.
+    /// A `CompactTree` or an asynchronous evaluation of the rewritten tree evaluates each occurrence on its own, with the same results.
+    /// The rewritten tree displays like the original one, while `Node::kind` returns `OperatorKind::Shared` for the nodes that mark the shared subexpressions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let calls = Rc::new(Cell::new(0));
+    /// let lookup_calls = calls.clone();
+    /// let mut context = HashMapContext::new();
+    /// context.set_value("user_id", 7).unwrap(); // Do proper error handling here
+    /// context.set_function("lookup".into(), Function::new(Box::new(move |argument| {
+    ///     lookup_calls.set(lookup_calls.get() + 1);
+    ///     Ok(Value::Int(argument.as_int()? * 2))
+    /// }))).unwrap(); // Do proper error handling here
+    ///
+    /// let mut tree = build_operator_tree("lookup(user_id) > 0 && lookup(user_id) < 20").unwrap(); // Do proper error handling here
+    /// tree.share_common_subexpressions();
+    /// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+    /// assert_eq!(calls.get(), 1);
+    /// ```
+    pub fn share_common_subexpressions(&mut self) {
+        let assigned_variables = self.assigned_variables();
+        let ids = {
+            let mut nodes = Vec::new();
+            collect_nodes(self, &assigned_variables, false, &mut nodes);
+            shared_ids(&nodes)
+        };
+        insert_shared_nodes(self, &mut ids.into_iter());
+    }
+
+    /// Returns true if the value of this subexpression can be reused within an evaluation with the given context.
+    /// This is the case if all functions it calls are pure, and all variables it reads are values of the context.
+    pub(crate) fn is_pure_in(&self, context: &dyn Context) -> bool {
+        iter::once(self)
+            .chain(self.iter())
+            .all(|node| match node.operator() {
+                Operator::VariableIdentifier { identifier } => {
+                    context.get_value(identifier).is_some()
+                },
+                operator => match operator.called_function() {
+                    Some(identifier) => match context.get_function(identifier) {
+                        Some(function) => function.is_pure(),
+                        None => is_pure_builtin(identifier),
+                    },
+                    None => true,
+                },
+            })
+    }
+
+    /// Returns the identifiers of all variables that are assigned in this expression.
+    fn assigned_variables(&self) -> HashSet<&str> {
+        iter::once(self)
+            .chain(self.iter())
+            .filter(|node| *node.operator() == Operator::Assign)
+            .flat_map(|node| match node.children().first().map(Node::operator) {
+                Some(Operator::Const { value: target }) => assignment_target_identifiers(target),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+/// Returns false if the given identifier belongs to a builtin function that is impure, like `now_monotonic`.
+fn is_pure_builtin(identifier: &str) -> bool {
+    builtin_function(identifier).map_or(true, |function| function.is_pure())
+}
+
+/// Appends the given node and its descendants in pre-order to `nodes`.
+/// Each node is paired with its structural hash if it may be shared, and with `None` otherwise.
+///
+/// Returns the structural hash of the node, and whether its subexpression is pure as far as can be told without a context.
+fn collect_nodes<'a>(
+    node: &'a Node,
+    assigned_variables: &HashSet<&str>,
+    parent_is_shared: bool,
+    nodes: &mut Vec<(&'a Node, Option<u64>)>,
+) -> (u64, bool) {
+    let index = nodes.len();
+    nodes.push((node, None));
+
+    let operator = node.operator();
+    let is_shared = matches!(operator, Operator::Shared { .. });
+    let mut hasher = DefaultHasher::new();
+    mem::discriminant(operator).hash(&mut hasher);
+    let mut is_pure = match operator {
+        Operator::Const { value } => {
+            hash_value(value, &mut hasher);
+            true
+        },
+        Operator::PreservedConst { constant } => {
+            hash_value(&constant.value, &mut hasher);
+            true
+        },
+        Operator::VariableIdentifier { identifier } => {
+            identifier.hash(&mut hasher);
+            &**identifier != IT_IDENTIFIER && !assigned_variables.contains(&**identifier)
+        },
+        Operator::FunctionIdentifier { identifier } => {
+            identifier.hash(&mut hasher);
+            is_pure_builtin(identifier)
+        },
+        Operator::NamedFunctionIdentifier { call } => {
+            call.identifier.hash(&mut hasher);
+            call.argument_names.hash(&mut hasher);
+            is_pure_builtin(&call.identifier)
+        },
+        Operator::Shared { id } => {
+            id.hash(&mut hasher);
+            true
+        },
+        operator => operator.is_pure(),
+    };
+
+    for child in node.children() {
+        let (child_hash, child_is_pure) =
+            collect_nodes(child, assigned_variables, is_shared, nodes);
+        child_hash.hash(&mut hasher);
+        is_pure &= child_is_pure;
+    }
+
+    // Sharing leaves, groups and tuples does not save any work, and an already shared subexpression is not shared again.
+    let hash = hasher.finish();
+    if is_pure
+        && !parent_is_shared
+        && !operator.is_leaf()
+        && !matches!(
+            operator,
+            Operator::RootNode | Operator::Tuple | Operator::Shared { .. }
+        )
+    {
+        nodes[index].1 = Some(hash);
+    }
+    (hash, is_pure)
+}
+
+fn hash_value<H: Hasher>(value: &Value, hasher: &mut H) {
+    mem::discriminant(value).hash(hasher);
+    match value {
+        Value::String(string) => string.hash(hasher),
+        Value::Float(float) => float.to_bits().hash(hasher),
+        Value::Int(int) => int.hash(hasher),
+        Value::Boolean(boolean) => boolean.hash(hasher),
+        Value::Tuple(tuple) => {
+            tuple.len().hash(hasher);
+            for element in tuple {
+                hash_value(element, hasher);
+            }
+        },
+        Value::Empty => {},
+    }
+}
+
+/// Returns the id of the shared subexpression for each of the given nodes, or `None` if the node occurs only once or may not be shared.
+/// Nodes with the same structural hash are compared to make sure that they are really identical.
+fn shared_ids(nodes: &[(&Node, Option<u64>)]) -> Vec<Option<usize>> {
+    let mut classes: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut class_of = vec![None; nodes.len()];
+    let mut occurrences = vec![0; nodes.len()];
+    for (index, (node, hash)) in nodes.iter().enumerate() {
+        if let Some(hash) = hash {
+            let class = classes.entry(*hash).or_default();
+            let first = match class.iter().find(|&&first| nodes[first].0 == *node) {
+                Some(&first) => first,
+                None => {
+                    class.push(index);
+                    index
+                },
+            };
+            class_of[index] = Some(first);
+            occurrences[first] += 1;
+        }
+    }
+
+    let mut ids = HashMap::new();
+    class_of
+        .into_iter()
+        .map(|first| {
+            let first = first.filter(|&first| occurrences[first] > 1)?;
+            Some(
+                *ids.entry(first)
+                    .or_insert_with(|| NEXT_SHARED_ID.fetch_add(1, Ordering::Relaxed)),
+            )
+        })
+        .collect()
+}
+
+/// Wraps each node of the tree that has a shared id into a node with the operator `Operator::Shared`.
+/// The ids are given in the pre-order of the nodes, like returned by `shared_ids`.
+fn insert_shared_nodes<I: Iterator<Item = Option<usize>>>(node: &mut Node, ids: &mut I) {
+    let id = ids.next().flatten();
+    for child in node.children_mut() {
+        insert_shared_nodes(child, ids);
+    }
+    if let Some(id) = id {
+        let subexpression = mem::replace(node, Node::new(Operator::Shared { id }));
+        node.children_mut().push(subexpression);
+    }
+}
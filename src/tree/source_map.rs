@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+
+use error::EvalexprResult;
+use token::{tokenize, Token};
+use Node;
+
+/// Relates the byte ranges of an expression written by `Node::to_minified_string_with_source_map` to the byte ranges of the source it was parsed from.
+///
+/// Each token of the written expression, like a literal, an identifier or an operator symbol, is mapped to the token of the source that it was written for.
+/// Parentheses are mapped if they were kept from the source, but not if the minifier inserted them, and whitespace is never mapped.
+/// A compound assignment like `x += 1` is written as `x=x+1`, whose `=`, second `x` and `+` are all mapped to the `+=` of the source.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    mappings: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl SourceMap {
+    /// Returns the pairs of byte ranges of the written expression and of the source, ordered by their position in the written expression.
+    pub fn mappings(&self) -> &[(Range<usize>, Range<usize>)] {
+        &self.mappings
+    }
+
+    /// Returns the byte offset in the source that corresponds to the given byte offset in the written expression.
+    ///
+    /// Offsets within a token that was written the same way as in the source map to the same character of the source token.
+    /// If the token was written differently, like a renamed variable or a literal like `1.50` that is written as `1.5`, offsets beyond the end of the source token map to its last byte.
+    /// Returns `None` if the offset does not belong to a mapped token, like for a parenthesis inserted by the minifier.
+    pub fn map_offset(&self, offset: usize) -> Option<usize> {
+        let (output, input) = self.mapping_at(offset)?;
+        Some(input.start + (offset - output.start).min(input.len() - 1))
+    }
+
+    /// Returns the smallest range of the source that contains all tokens that overlap the given range of the written expression.
+    ///
+    /// This translates the range of a subexpression, like the failing subexpression of an `Explanation`, back to the clause that the user wrote.
+    /// Returns `None` if the range does not overlap any mapped token.
+    pub fn map_range(&self, range: Range<usize>) -> Option<Range<usize>> {
+        self.mappings
+            .iter()
+            .filter(|(output, _)| output.start < range.end && range.start < output.end)
+            .map(|(_, input)| input.clone())
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+    }
+
+    fn mapping_at(&self, offset: usize) -> Option<&(Range<usize>, Range<usize>)> {
+        // The mappings are sorted and do not overlap.
+        let index = self
+            .mappings
+            .partition_point(|(output, _)| output.end <= offset);
+        self.mappings
+            .get(index)
+            .filter(|(output, _)| output.contains(&offset))
+    }
+
+    /// Pairs the tokens of the written expression with the tokens of the source in order.
+    /// Tokens of the source that were not written, like removed parentheses or a trailing `;`, are skipped.
+    /// A parenthesis of the written expression is only paired with a parenthesis that directly follows the previously paired token of the source, as it was inserted otherwise.
+    fn new(source: &str, output: &str) -> EvalexprResult<Self> {
+        let source_tokens = tokenize(source)?;
+        let mut source_tokens = source_tokens.iter().peekable();
+        let mut mappings = Vec::new();
+        // The range of the last compound assignment of the source, and the amount of following tokens of the written expression that were written for it.
+        let mut compound_assignment: Option<(&Range<usize>, usize)> = None;
+        for (token, output_range) in tokenize(output)? {
+            if let Some((input_range, remaining)) = compound_assignment.take() {
+                mappings.push((output_range, input_range.clone()));
+                if remaining > 1 {
+                    compound_assignment = Some((input_range, remaining - 1));
+                }
+                continue;
+            }
+
+            let input_range = if matches!(token, Token::LBrace | Token::RBrace) {
+                source_tokens.next_if(|(source_token, _)| *source_token == token)
+            } else {
+                source_tokens.find(|(source_token, _)| {
+                    mem::discriminant(source_token) == mem::discriminant(&token)
+                        || (token == Token::Assign && source_token.is_assignment())
+                })
+            };
+            if let Some((source_token, input_range)) = input_range {
+                if *source_token != Token::Assign && source_token.is_assignment() {
+                    // The variable and the operator follow the `=`.
+                    compound_assignment = Some((input_range, 2));
+                }
+                mappings.push((output_range, input_range.clone()));
+            }
+        }
+        Ok(Self { mappings })
+    }
+}
+
+impl Node {
+    /// Returns the expression of this operator tree like `Node::to_minified_string`, together with a `SourceMap` that relates it to the given source.
+    ///
+    /// The tokens of the written expression are paired with the tokens of the source, so the source needs to be the expression that this tree was built from.
+    /// Errors from tokenizing the source are returned as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let source = "(price  *  quantity) / divisor";
+    /// let tree = build_operator_tree(source).unwrap(); // Do proper error handling here
+    /// let (minified, source_map) = tree.to_minified_string_with_source_map(source).unwrap(); // Do proper error handling here
+    /// assert_eq!(minified, "price*quantity/divisor");
+    ///
+    /// let offset = minified.find("divisor").unwrap();
+    /// assert_eq!(source_map.map_offset(offset), source.find("divisor"));
+    /// assert_eq!(source_map.map_range(0..14), Some(1..19));
+    /// ```
+    pub fn to_minified_string_with_source_map(
+        &self,
+        source: &str,
+    ) -> EvalexprResult<(String, SourceMap)> {
+        self.to_renamed_string_with_source_map(source, &HashMap::new())
+    }
+
+    /// Returns the expression of this operator tree like `Node::to_renamed_string`, together with a `SourceMap` that relates it to the given source.
+    ///
+    /// Renamed variables are mapped to the identifiers they replace.
+    /// See `Node::to_minified_string_with_source_map` for the requirements on the source.
+    pub fn to_renamed_string_with_source_map(
+        &self,
+        source: &str,
+        renames: &HashMap<String, String>,
+    ) -> EvalexprResult<(String, SourceMap)> {
+        let output = self.to_renamed_string(renames);
+        let source_map = SourceMap::new(source, &output)?;
+        Ok((output, source_map))
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use operator::Operator;
+use Node;
+
+impl Node {
+    /// Replaces each read of a variable whose identifier is a key of `substitutions` with a clone of the mapped operator tree.
+    ///
+    /// Clones of operator trees share their children, so cloning a tree is cheap regardless of its size.
+    /// The substitution copies only the nodes on the paths from the root to the replaced variables, while all other subtrees stay shared with the clones of this tree.
+    /// This allows to instantiate many variants of a large expression, like one per tenant, without copying it each time.
+    ///
+    /// Assignments to a variable, function identifiers and string literals are not changed.
+    /// A substituted tree that is not a leaf is evaluated like it was written in parentheses.
+    /// Trees that are rewritten by `Node::share_common_subexpressions` should be substituted before the rewrite, as variants of the same rewritten tree use the same ids for their shared subexpressions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let base = build_operator_tree("price * (1 + tax_rate) - discount").unwrap(); // Do proper error handling here
+    /// let mut substitutions = HashMap::new();
+    /// substitutions.insert("tax_rate".to_string(), build_operator_tree("0.25").unwrap()); // Do proper error handling here
+    /// substitutions.insert("discount".to_string(), build_operator_tree("price / 10").unwrap()); // Do proper error handling here
+    ///
+    /// let mut tenant = base.clone();
+    /// tenant.substitute_variables(&substitutions);
+    /// assert_eq!(tenant.to_minified_string(), "price*(1+.25)-price/10");
+    ///
+    /// let context = context_map! { "price" => 100.0 }.unwrap(); // Do proper error handling here
+    /// assert_eq!(tenant.eval_with_context(&context), Ok(Value::from(115.0)));
+    /// ```
+    pub fn substitute_variables(&mut self, substitutions: &HashMap<String, Node>) {
+        if let Some(node) = self.substituted(substitutions) {
+            *self = node;
+        }
+    }
+
+    /// Returns a copy of this node with the variables substituted, or `None` if it does not read any of them.
+    /// The copy shares all children that were not changed.
+    fn substituted(&self, substitutions: &HashMap<String, Node>) -> Option<Node> {
+        if let Operator::VariableIdentifier { identifier } = self.operator() {
+            return substitutions.get(&**identifier).cloned();
+        }
+
+        let mut result: Option<Node> = None;
+        for (index, child) in self.children().iter().enumerate() {
+            if let Some(child) = child.substituted(substitutions) {
+                result.get_or_insert_with(|| self.clone()).children_mut()[index] = child;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use build_operator_tree;
+    use Node;
+
+    fn shared_children(node: &Node) -> &Arc<Vec<Node>> {
+        node.children.as_ref().unwrap()
+    }
+
+    #[test]
+    fn test_substitution_shares_unchanged_subtrees() {
+        let base = build_operator_tree("(a + b * c) * (d - e) + f").unwrap();
+        let mut substitutions = HashMap::new();
+        substitutions.insert("f".to_string(), build_operator_tree("2").unwrap());
+
+        let mut variant = base.clone();
+        // A clone shares all children with the original.
+        assert_eq!(Arc::strong_count(shared_children(&base)), 2);
+        variant.substitute_variables(&substitutions);
+
+        // The spine to `f` was copied, while the product is still shared.
+        assert!(!Arc::ptr_eq(
+            shared_children(&base),
+            shared_children(&variant)
+        ));
+        let base_sum = &base.children()[0];
+        let variant_sum = &variant.children()[0];
+        assert!(!Arc::ptr_eq(
+            shared_children(base_sum),
+            shared_children(variant_sum)
+        ));
+        assert_eq!(Arc::strong_count(shared_children(&base)), 1);
+        assert_eq!(
+            Arc::strong_count(shared_children(&base_sum.children()[0])),
+            2
+        );
+        assert_eq!(variant_sum.children()[1], build_operator_tree("2").unwrap());
+
+        // Trees without the variables are not copied at all.
+        let mut unchanged = base.clone();
+        unchanged.substitute_variables(&HashMap::new());
+        assert!(Arc::ptr_eq(
+            shared_children(&base),
+            shared_children(&unchanged)
+        ));
+    }
+
+    #[test]
+    fn test_modifying_a_clone_copies_on_write() {
+        let mut tree = build_operator_tree("a + b").unwrap();
+        let clone = tree.clone();
+        tree.children_mut().push(Node::root_node());
+        assert_eq!(Arc::strong_count(shared_children(&clone)), 1);
+        assert_eq!(clone.children().len(), 1);
+        assert_eq!(tree.children().len(), 2);
+    }
+}
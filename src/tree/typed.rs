@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use context::Context;
+use error::{EvalexprError, EvalexprResult};
+use function::FromArgument;
+use interface::build_operator_tree;
+use typecheck::{StaticType, TypeCheckError};
+use value::{FloatType, IntType};
+use Node;
+
+/// A type that the result of a `TypedExpression` can have.
+///
+/// This is implemented for `bool`, `IntType`, `FloatType`, `String` and `()`.
+/// The result of an evaluation is converted like an argument of a function created by `Function::from_fn`, so a `FloatType` expression also accepts integers.
+pub trait ExpressionType: FromArgument {
+    /// Returns the static type that an expression must be able to produce to be parsed as an expression of this type.
+    fn static_type() -> StaticType;
+}
+
+impl ExpressionType for bool {
+    fn static_type() -> StaticType {
+        StaticType::Boolean
+    }
+}
+
+impl ExpressionType for IntType {
+    fn static_type() -> StaticType {
+        StaticType::Int
+    }
+}
+
+impl ExpressionType for FloatType {
+    fn static_type() -> StaticType {
+        StaticType::Number
+    }
+}
+
+impl ExpressionType for String {
+    fn static_type() -> StaticType {
+        StaticType::String
+    }
+}
+
+impl ExpressionType for () {
+    fn static_type() -> StaticType {
+        StaticType::Empty
+    }
+}
+
+/// An operator tree whose result has the type `T`, checked when it is parsed.
+///
+/// `TypedExpression::parse` checks the types of the expression like `Node::check_types`, and fails if the expression has type errors or certainly produces a value of another type.
+/// Variables and functions are not known when parsing, so they can have any type and return any type.
+/// An expression like `a && b` is therefore accepted as `TypedExpression<bool>`, and `a + 1` as `TypedExpression<IntType>`, while `a > 1` is rejected as `TypedExpression<IntType>`.
+/// The type of the result is checked again when evaluating, such that `eval` fails with the usual errors like `EvalexprError::ExpectedBoolean` if the variables have other types than expected.
+///
+/// With the `serde_support` feature, a typed expression can be deserialized from a string, which fails if the expression does not pass the type check.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let rule = TypedExpression::<bool>::parse("age >= 18 && member").unwrap(); // Do proper error handling here
+/// let context = context_map! { "age" => 21, "member" => true }.unwrap(); // Do proper error handling here
+/// assert_eq!(rule.eval(&context), Ok(true));
+///
+/// assert!(matches!(
+///     TypedExpression::<bool>::parse("age + 1"),
+///     Err(EvalexprError::TypeCheckFailed { .. })
+/// ));
+/// ```
+pub struct TypedExpression<T> {
+    node: Node,
+    result_type: PhantomData<fn() -> T>,
+}
+
+impl<T: ExpressionType> TypedExpression<T> {
+    /// Parses the given expression and checks that it can produce a value of type `T`.
+    ///
+    /// Fails with the errors of `build_operator_tree`, or with `EvalexprError::TypeCheckFailed` if the expression does not pass the type check.
+    pub fn parse(expression: &str) -> EvalexprResult<Self> {
+        Self::from_node(build_operator_tree(expression)?)
+    }
+
+    /// Checks that the given operator tree can produce a value of type `T`.
+    ///
+    /// Fails with `EvalexprError::TypeCheckFailed` if the tree does not pass the type check.
+    pub fn from_node(node: Node) -> EvalexprResult<Self> {
+        let expected = T::static_type();
+        match node.check_types(&HashMap::new(), &HashMap::new(), true) {
+            Ok(actual) if actual.intersects(expected) => Ok(TypedExpression {
+                node,
+                result_type: PhantomData,
+            }),
+            Ok(actual) => Err(EvalexprError::TypeCheckFailed {
+                errors: vec![TypeCheckError::ResultType {
+                    expected,
+                    actual,
+                    span: node.strip_root_nodes().span(),
+                }],
+            }),
+            Err(errors) => Err(EvalexprError::TypeCheckFailed { errors }),
+        }
+    }
+
+    /// Evaluates the expression with the given context and converts the result to `T`.
+    ///
+    /// Fails with the errors of the evaluation, or like `Value::as_boolean` and the other conversions if the result has another type than `T`.
+    pub fn eval(&self, context: &dyn Context) -> EvalexprResult<T> {
+        T::from_argument(&self.node.eval_with_context(context)?)
+    }
+
+    /// Evaluates the expression with the given mutable context and converts the result to `T`.
+    ///
+    /// Fails like `TypedExpression::eval`.
+    pub fn eval_mut(&self, context: &mut dyn Context) -> EvalexprResult<T> {
+        T::from_argument(&self.node.eval_with_context_mut(context)?)
+    }
+}
+
+impl<T> TypedExpression<T> {
+    /// Returns the operator tree of the expression.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Returns the operator tree of the expression, dropping the type of its result.
+    pub fn into_node(self) -> Node {
+        self.node
+    }
+}
+
+impl<T> Clone for TypedExpression<T> {
+    fn clone(&self) -> Self {
+        TypedExpression {
+            node: self.node.clone(),
+            result_type: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for TypedExpression<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T> fmt::Debug for TypedExpression<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TypedExpression").field(&self.node).finish()
+    }
+}
+
+impl<T> fmt::Display for TypedExpression<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.node.fmt(f)
+    }
+}
@@ -0,0 +1,1010 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use context::IT_IDENTIFIER;
+use function::builtin::builtin_signature;
+use function::higher_order::{higher_order_argument_amount, is_higher_order_builtin};
+use operator::{assignment_target_identifiers, Operator};
+use tree::Node;
+use value::value_type::ValueType;
+use Value;
+
+/// The type of a value as far as it can be determined without evaluating an expression.
+///
+/// Additionally to the types of `ValueType`, a static type can be `Number`, meaning either `Int` or `Float`, or `Any`, meaning that the type is unknown.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StaticType {
+    /// The type of `Value::String`.
+    String,
+    /// The type of `Value::Int`.
+    Int,
+    /// The type of `Value::Float`.
+    Float,
+    /// Either the type of `Value::Int` or `Value::Float`.
+    Number,
+    /// The type of `Value::Boolean`.
+    Boolean,
+    /// The type of `Value::Tuple`.
+    Tuple,
+    /// The type of `Value::Empty`.
+    Empty,
+    /// Any type.
+    Any,
+}
+
+impl StaticType {
+    /// Returns true if a value can have both `self` and `other` as type.
+    pub fn intersects(self, other: StaticType) -> bool {
+        use self::StaticType::*;
+        match (self, other) {
+            (Any, _) | (_, Any) => true,
+            (Number, Int) | (Number, Float) | (Int, Number) | (Float, Number) => true,
+            (a, b) => a == b,
+        }
+    }
+
+    /// Returns the most specific type that covers both `self` and `other`.
+    fn join(self, other: StaticType) -> StaticType {
+        if self == other {
+            self
+        } else if self.is_numeric() && other.is_numeric() {
+            StaticType::Number
+        } else {
+            StaticType::Any
+        }
+    }
+
+    /// Returns true if `self` is `Int`, `Float` or `Number`.
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            StaticType::Int | StaticType::Float | StaticType::Number
+        )
+    }
+
+    /// Returns the type that `self` must have if it is used as a number.
+    fn as_numeric(self) -> StaticType {
+        if self == StaticType::Any {
+            StaticType::Number
+        } else {
+            self
+        }
+    }
+}
+
+impl From<ValueType> for StaticType {
+    fn from(value_type: ValueType) -> Self {
+        match value_type {
+            ValueType::String => StaticType::String,
+            ValueType::Int => StaticType::Int,
+            ValueType::Float => StaticType::Float,
+            ValueType::Boolean => StaticType::Boolean,
+            ValueType::Tuple => StaticType::Tuple,
+            ValueType::Empty => StaticType::Empty,
+        }
+    }
+}
+
+/// A set of value types, as inferred by `Node::infer_variable_types`.
+///
+/// The set of all types, `TypeSet::ANY`, means that nothing is known about a type.
+/// The set of no types, `TypeSet::CONFLICT`, means that a variable is used in ways that no single type satisfies.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct TypeSet {
+    bits: u8,
+}
+
+/// All value types, in the order in which they are listed by `TypeSet::value_types`.
+const VALUE_TYPES: [ValueType; 6] = [
+    ValueType::String,
+    ValueType::Int,
+    ValueType::Float,
+    ValueType::Boolean,
+    ValueType::Tuple,
+    ValueType::Empty,
+];
+
+impl TypeSet {
+    /// The set of all value types.
+    pub const ANY: TypeSet = TypeSet { bits: 0b11_1111 };
+
+    /// The set of no value types.
+    pub const CONFLICT: TypeSet = TypeSet { bits: 0 };
+
+    const NUMBER: TypeSet = TypeSet {
+        bits: Self::bit(ValueType::Int) | Self::bit(ValueType::Float),
+    };
+    const INT: TypeSet = TypeSet::single(ValueType::Int);
+    const STRING: TypeSet = TypeSet::single(ValueType::String);
+    const FLOAT: TypeSet = TypeSet::single(ValueType::Float);
+    const BOOLEAN: TypeSet = TypeSet::single(ValueType::Boolean);
+    const TUPLE: TypeSet = TypeSet::single(ValueType::Tuple);
+    const EMPTY: TypeSet = TypeSet::single(ValueType::Empty);
+
+    const fn bit(value_type: ValueType) -> u8 {
+        1 << value_type as u8
+    }
+
+    const fn single(value_type: ValueType) -> TypeSet {
+        TypeSet {
+            bits: Self::bit(value_type),
+        }
+    }
+
+    /// Returns true if the given type is in this set.
+    pub fn contains(self, value_type: ValueType) -> bool {
+        self.bits & Self::bit(value_type) != 0
+    }
+
+    /// Returns true if this set contains all value types.
+    pub fn is_any(self) -> bool {
+        self == TypeSet::ANY
+    }
+
+    /// Returns true if this set contains no value types.
+    pub fn is_conflict(self) -> bool {
+        self == TypeSet::CONFLICT
+    }
+
+    /// Returns the types in this set.
+    pub fn value_types(self) -> Vec<ValueType> {
+        VALUE_TYPES
+            .iter()
+            .copied()
+            .filter(|value_type| self.contains(*value_type))
+            .collect()
+    }
+
+    /// Returns the set of types that are in `self` or in `other`.
+    pub fn union(self, other: TypeSet) -> TypeSet {
+        TypeSet {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns the set of types that are in both `self` and `other`.
+    pub fn intersection(self, other: TypeSet) -> TypeSet {
+        TypeSet {
+            bits: self.bits & other.bits,
+        }
+    }
+}
+
+impl From<ValueType> for TypeSet {
+    fn from(value_type: ValueType) -> Self {
+        TypeSet::single(value_type)
+    }
+}
+
+impl From<StaticType> for TypeSet {
+    fn from(static_type: StaticType) -> Self {
+        match static_type {
+            StaticType::String => TypeSet::STRING,
+            StaticType::Int => TypeSet::single(ValueType::Int),
+            StaticType::Float => TypeSet::FLOAT,
+            StaticType::Number => TypeSet::NUMBER,
+            StaticType::Boolean => TypeSet::BOOLEAN,
+            StaticType::Tuple => TypeSet::TUPLE,
+            StaticType::Empty => TypeSet::EMPTY,
+            StaticType::Any => TypeSet::ANY,
+        }
+    }
+}
+
+impl fmt::Debug for TypeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.value_types()).finish()
+    }
+}
+
+/// The static types of the arguments and the result of a function.
+///
+/// A function that takes multiple arguments takes them as a tuple.
+/// If a signature has no argument types, the arguments of the function are not checked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    arguments: Option<Vec<StaticType>>,
+    result: StaticType,
+}
+
+impl Signature {
+    /// Creates a signature for a function that takes arguments of the given types.
+    pub fn new(arguments: Vec<StaticType>, result: StaticType) -> Self {
+        Self {
+            arguments: Some(arguments),
+            result,
+        }
+    }
+
+    /// Creates a signature for a function whose arguments are not checked.
+    pub fn variadic(result: StaticType) -> Self {
+        Self {
+            arguments: None,
+            result,
+        }
+    }
+
+    /// Returns the types of the arguments, or `None` if the arguments are not checked.
+    pub fn arguments(&self) -> Option<&[StaticType]> {
+        self.arguments.as_deref()
+    }
+
+    /// Returns the type of the result.
+    pub fn result(&self) -> StaticType {
+        self.result
+    }
+}
+
+/// A type error found by `Node::check_types`.
+///
+/// Operators and functions are identified by their symbol or identifier, and the `span` of each error locates the offending node in the source of the operator tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeCheckError {
+    /// An operator cannot be applied to arguments of the given types.
+    OperatorArgumentTypes {
+        /// The symbol of the operator.
+        operator: String,
+        /// The static types of the arguments.
+        actual: Vec<StaticType>,
+        /// The range of bytes of the expression where the operator with its arguments is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+
+    /// An operator has the wrong amount of arguments.
+    OperatorArgumentAmount {
+        /// The symbol of the operator.
+        operator: String,
+        /// The expected amount of arguments.
+        expected: usize,
+        /// The actual amount of arguments.
+        actual: usize,
+        /// The range of bytes of the expression where the operator with its arguments is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+
+    /// An argument of a function does not have the type required by the signature of the function.
+    FunctionArgumentType {
+        /// The identifier of the function.
+        function: String,
+        /// The position of the argument, starting at zero.
+        position: usize,
+        /// The type required by the signature of the function.
+        expected: StaticType,
+        /// The static type of the argument.
+        actual: StaticType,
+        /// The range of bytes of the expression where the argument is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+
+    /// A function is called with a different amount of arguments than required by its signature.
+    FunctionArgumentAmount {
+        /// The identifier of the function.
+        function: String,
+        /// The amount of arguments required by the signature of the function.
+        expected: usize,
+        /// The actual amount of arguments.
+        actual: usize,
+        /// The range of bytes of the expression where the function call is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+
+    /// A variable identifier has no known type.
+    UnknownVariable {
+        /// The identifier of the variable.
+        identifier: String,
+        /// The range of bytes of the expression where the variable is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+
+    /// A function identifier has no known signature.
+    UnknownFunction {
+        /// The identifier of the function.
+        function: String,
+        /// The range of bytes of the expression where the function call is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+
+    /// The result of an expression cannot have the type required by a `TypedExpression`.
+    ResultType {
+        /// The static type required by the `TypedExpression`.
+        expected: StaticType,
+        /// The static type of the result of the expression.
+        actual: StaticType,
+        /// The range of bytes of the expression where the expression is.
+        /// It is `None` if the operator tree was not built from source.
+        span: Option<Range<usize>>,
+    },
+}
+
+impl fmt::Display for TypeCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TypeCheckError::*;
+        match self {
+            OperatorArgumentTypes {
+                operator, actual, ..
+            } => write!(
+                f,
+                "Operator {:?} cannot be applied to arguments of types {:?}",
+                operator, actual
+            ),
+            OperatorArgumentAmount {
+                operator,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "Operator {:?} expected {} arguments, but got {}",
+                operator, expected, actual
+            ),
+            FunctionArgumentType {
+                function,
+                position,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "Argument {} of function {:?} is expected to be of type {:?}, but is of type {:?}",
+                position, function, expected, actual
+            ),
+            FunctionArgumentAmount {
+                function,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "Function {:?} expected {} arguments, but got {}",
+                function, expected, actual
+            ),
+            UnknownVariable { identifier, .. } => {
+                write!(f, "The type of variable {:?} is unknown", identifier)
+            },
+            UnknownFunction { function, .. } => {
+                write!(f, "The signature of function {:?} is unknown", function)
+            },
+            ResultType {
+                expected, actual, ..
+            } => write!(
+                f,
+                "The expression is expected to be of type {:?}, but is of type {:?}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+pub(crate) fn check_types(
+    node: &Node,
+    variable_types: &HashMap<String, ValueType>,
+    function_signatures: &HashMap<String, Signature>,
+    unknown_identifiers_are_any: bool,
+) -> Result<StaticType, Vec<TypeCheckError>> {
+    let mut checker = TypeChecker {
+        variable_types: variable_types
+            .iter()
+            .map(|(identifier, value_type)| (identifier.clone(), (*value_type).into()))
+            .collect(),
+        function_signatures,
+        unknown_identifiers_are_any,
+        variables_may_be_missing: false,
+        errors: Vec::new(),
+    };
+
+    let result = checker.check(node);
+    if checker.errors.is_empty() {
+        Ok(result)
+    } else {
+        Err(checker.errors)
+    }
+}
+
+struct TypeChecker<'a> {
+    variable_types: HashMap<String, StaticType>,
+    function_signatures: &'a HashMap<String, Signature>,
+    unknown_identifiers_are_any: bool,
+    /// True while checking the first argument of a `??` operator, which recovers from variables that are not found.
+    variables_may_be_missing: bool,
+    errors: Vec<TypeCheckError>,
+}
+
+impl<'a> TypeChecker<'a> {
+    /// Returns the static type of the given node, and records all type errors within it.
+    /// If the node itself has a type error, its type is `Any`, such that the error does not cause further errors.
+    fn check(&mut self, node: &Node) -> StaticType {
+        use operator::Operator::*;
+
+        if let FunctionIdentifier { identifier } = node.operator() {
+            return self.check_function(identifier, node);
+        }
+
+        let arguments: Vec<_> = node
+            .children()
+            .iter()
+            .enumerate()
+            .map(|(index, child)| {
+                if *node.operator() == Coalesce
+                    && index == 0
+                    && matches!(
+                        child.strip_root_nodes().operator(),
+                        VariableIdentifier { .. }
+                    )
+                {
+                    let outer = self.variables_may_be_missing;
+                    self.variables_may_be_missing = true;
+                    let result = self.check(child);
+                    self.variables_may_be_missing = outer;
+                    result
+                } else {
+                    self.check(child)
+                }
+            })
+            .collect();
+        if let Some(expected) = node.operator().max_argument_amount() {
+            let actual = arguments.len();
+            let is_empty_root = *node.operator() == RootNode && actual == 0;
+            if actual != expected && !is_empty_root {
+                return self.error(TypeCheckError::OperatorArgumentAmount {
+                    operator: operator_symbol(node.operator()),
+                    expected,
+                    actual,
+                    span: node.span(),
+                });
+            }
+        }
+
+        let result = match node.operator() {
+            RootNode | Shared { .. } | Chain => {
+                Some(arguments.last().cloned().unwrap_or(StaticType::Empty))
+            },
+            Add => {
+                let (a, b) = (arguments[0], arguments[1]);
+                let mut result: Option<StaticType> = None;
+                let mut join = |static_type: StaticType| {
+                    result = Some(result.map_or(static_type, |result| result.join(static_type)))
+                };
+                if a.intersects(StaticType::Number) && b.intersects(StaticType::Number) {
+                    join(arithmetic_result(a, b));
+                }
+                for static_type in &[StaticType::String, StaticType::Tuple] {
+                    if a.intersects(*static_type) && b.intersects(*static_type) {
+                        join(*static_type);
+                    }
+                }
+                result
+            },
+            Sub | Mul | Div | Mod => {
+                if arguments.iter().all(|a| a.intersects(StaticType::Number)) {
+                    Some(arithmetic_result(arguments[0], arguments[1]))
+                } else {
+                    None
+                }
+            },
+            Neg | Pos => {
+                if arguments[0].intersects(StaticType::Number) {
+                    Some(arguments[0].as_numeric())
+                } else {
+                    None
+                }
+            },
+            Exp => {
+                if arguments.iter().all(|a| a.intersects(StaticType::Number)) {
+                    // Integers with a negative exponent are exponentiated as floats.
+                    Some(match arithmetic_result(arguments[0], arguments[1]) {
+                        StaticType::Float => StaticType::Float,
+                        _ => StaticType::Number,
+                    })
+                } else {
+                    None
+                }
+            },
+            Eq | Neq => Some(StaticType::Boolean),
+            Gt | Lt | Geq | Leq => {
+                let (a, b) = (arguments[0], arguments[1]);
+                if (a.intersects(StaticType::Number) && b.intersects(StaticType::Number))
+                    || (a.intersects(StaticType::String) && b.intersects(StaticType::String))
+                {
+                    Some(StaticType::Boolean)
+                } else {
+                    None
+                }
+            },
+            RegexMatch => {
+                if arguments.iter().all(|a| a.intersects(StaticType::String)) {
+                    Some(StaticType::Boolean)
+                } else {
+                    None
+                }
+            },
+            And | Or | Xor | Not | Implies => {
+                if arguments.iter().all(|a| a.intersects(StaticType::Boolean)) {
+                    Some(StaticType::Boolean)
+                } else {
+                    None
+                }
+            },
+            BitAnd | BitOr | BitXor | Shl | Shr => {
+                if arguments.iter().all(|a| a.intersects(StaticType::Int)) {
+                    Some(StaticType::Int)
+                } else {
+                    None
+                }
+            },
+            Range | RangeInclusive => {
+                if arguments.iter().all(|a| a.intersects(StaticType::Int)) {
+                    Some(StaticType::Tuple)
+                } else {
+                    None
+                }
+            },
+            Conditional => {
+                if arguments[0].intersects(StaticType::Boolean) {
+                    Some(arguments[1].join(arguments[2]))
+                } else {
+                    None
+                }
+            },
+            Coalesce => Some(arguments[0].join(arguments[1])),
+            Tuple => Some(StaticType::Tuple),
+            Assign => match node.children()[0].operator() {
+                Const {
+                    value: Value::String(identifier),
+                } => {
+                    self.variable_types.insert(identifier.clone(), arguments[1]);
+                    Some(StaticType::Empty)
+                },
+                Const { value: target } => {
+                    // The types of the elements of tuples are not known.
+                    for identifier in assignment_target_identifiers(target) {
+                        self.variable_types
+                            .insert(identifier.to_string(), StaticType::Any);
+                    }
+                    if arguments[1].intersects(StaticType::Tuple) {
+                        Some(StaticType::Empty)
+                    } else {
+                        None
+                    }
+                },
+                _ => Some(StaticType::Empty),
+            },
+            Const { value } => Some(ValueType::from(value).into()),
+            PreservedConst { constant } => Some(ValueType::from(&constant.value).into()),
+            VariableIdentifier { identifier } => {
+                if let Some(static_type) = self.variable_types.get(&**identifier) {
+                    Some(*static_type)
+                } else if self.unknown_identifiers_are_any || self.variables_may_be_missing {
+                    Some(StaticType::Any)
+                } else {
+                    return self.error(TypeCheckError::UnknownVariable {
+                        identifier: identifier.to_string(),
+                        span: node.span(),
+                    });
+                }
+            },
+            // The types of the arguments are not checked, as signatures do not name the parameters.
+            NamedFunctionIdentifier { call } => {
+                let identifier = &call.identifier;
+                match self.function_signatures.get(&**identifier) {
+                    Some(signature) => Some(signature.result()),
+                    None => match builtin_signature(identifier) {
+                        Some(signature) => Some(signature.result()),
+                        None if self.unknown_identifiers_are_any => Some(StaticType::Any),
+                        None => {
+                            return self.error(TypeCheckError::UnknownFunction {
+                                function: identifier.to_string(),
+                                span: node.span(),
+                            })
+                        },
+                    },
+                }
+            },
+            FunctionIdentifier { .. } => unreachable!(),
+        };
+
+        match result {
+            Some(result) => result,
+            None => self.error(TypeCheckError::OperatorArgumentTypes {
+                operator: operator_symbol(node.operator()),
+                actual: arguments,
+                span: node.span(),
+            }),
+        }
+    }
+
+    /// Returns the result type of the given function call, and records all type errors within it.
+    fn check_function(&mut self, identifier: &str, node: &Node) -> StaticType {
+        if is_higher_order_builtin(identifier) && !self.function_signatures.contains_key(identifier)
+        {
+            return self.check_higher_order(identifier, node);
+        }
+        let signature = if let Some(signature) = self.function_signatures.get(identifier) {
+            Some(signature.clone())
+        } else {
+            builtin_signature(identifier)
+        };
+        let argument = node.children()[0].strip_root_nodes();
+
+        let signature = match signature {
+            Some(signature) => signature,
+            None => {
+                self.check(argument);
+                if self.unknown_identifiers_are_any {
+                    return StaticType::Any;
+                } else {
+                    return self.error(TypeCheckError::UnknownFunction {
+                        function: identifier.to_string(),
+                        span: node.span(),
+                    });
+                }
+            },
+        };
+        let expected = match signature.arguments() {
+            Some(expected) => expected,
+            None => {
+                self.check(argument);
+                return signature.result();
+            },
+        };
+
+        // The types of the arguments, together with the nodes they are computed from.
+        let actual: Vec<_> = if expected.len() >= 2 && *argument.operator() == Operator::Tuple {
+            argument
+                .children()
+                .iter()
+                .map(|child| (self.check(child), child))
+                .collect()
+        } else {
+            match self.check(argument) {
+                StaticType::Empty => Vec::new(),
+                StaticType::Tuple | StaticType::Any if expected.len() >= 2 => {
+                    // The elements of a tuple that is not constructed in place cannot be checked.
+                    return signature.result();
+                },
+                static_type => vec![(static_type, argument)],
+            }
+        };
+
+        if actual.len() != expected.len() {
+            return self.error(TypeCheckError::FunctionArgumentAmount {
+                function: identifier.to_string(),
+                expected: expected.len(),
+                actual: actual.len(),
+                span: node.span(),
+            });
+        }
+        let mut result = signature.result();
+        for (position, (expected, (actual, argument))) in expected.iter().zip(actual).enumerate() {
+            if !expected.intersects(actual) {
+                result = self.error(TypeCheckError::FunctionArgumentType {
+                    function: identifier.to_string(),
+                    position,
+                    expected: *expected,
+                    actual,
+                    span: argument.strip_root_nodes().span(),
+                });
+            }
+        }
+        result
+    }
+
+    /// Returns the result type of a call of a higher-order builtin function like `map`, and records all type errors within it.
+    /// The second argument is checked with `it` as a variable of any type, unless it only names a function.
+    fn check_higher_order(&mut self, identifier: &str, node: &Node) -> StaticType {
+        let argument = node.children()[0].strip_root_nodes();
+        let actual = higher_order_argument_amount(argument.operator(), argument.children().len());
+        if actual != 2 {
+            self.check(argument);
+            return self.error(TypeCheckError::FunctionArgumentAmount {
+                function: identifier.to_string(),
+                expected: 2,
+                actual,
+                span: node.span(),
+            });
+        }
+
+        let (elements, body) = (&argument.children()[0], &argument.children()[1]);
+        let elements_type = self.check(elements);
+        let body_type = match body.strip_root_nodes().operator() {
+            Operator::VariableIdentifier { identifier }
+                if !self.variable_types.contains_key(&**identifier)
+                    && (self.function_signatures.contains_key(&**identifier)
+                        || builtin_signature(identifier).is_some()) =>
+            {
+                StaticType::Any
+            },
+            _ => {
+                let outer_type = self
+                    .variable_types
+                    .insert(IT_IDENTIFIER.to_string(), StaticType::Any);
+                let body_type = self.check(body);
+                match outer_type {
+                    Some(outer_type) => self
+                        .variable_types
+                        .insert(IT_IDENTIFIER.to_string(), outer_type),
+                    None => self.variable_types.remove(IT_IDENTIFIER),
+                };
+                body_type
+            },
+        };
+
+        let mut result = StaticType::Tuple;
+        if !StaticType::Tuple.intersects(elements_type) {
+            result = self.error(TypeCheckError::FunctionArgumentType {
+                function: identifier.to_string(),
+                position: 0,
+                expected: StaticType::Tuple,
+                actual: elements_type,
+                span: elements.strip_root_nodes().span(),
+            });
+        }
+        if identifier == "filter" && !StaticType::Boolean.intersects(body_type) {
+            result = self.error(TypeCheckError::FunctionArgumentType {
+                function: identifier.to_string(),
+                position: 1,
+                expected: StaticType::Boolean,
+                actual: body_type,
+                span: body.strip_root_nodes().span(),
+            });
+        }
+        result
+    }
+
+    fn error(&mut self, error: TypeCheckError) -> StaticType {
+        self.errors.push(error);
+        StaticType::Any
+    }
+}
+
+pub(crate) fn infer_variable_types(node: &Node) -> HashMap<String, TypeSet> {
+    let mut inference = TypeInference::default();
+    inference.infer(node);
+    inference.variable_types
+}
+
+/// The argument types that an operator accepts, together with the type of its result for these arguments.
+pub(crate) type TypingRule = (&'static [TypeSet], TypeSet);
+
+pub(crate) const ARITHMETIC_RULES: &[TypingRule] =
+    &[(&[TypeSet::NUMBER, TypeSet::NUMBER], TypeSet::NUMBER)];
+pub(crate) const ADDITION_RULES: &[TypingRule] = &[
+    (&[TypeSet::NUMBER, TypeSet::NUMBER], TypeSet::NUMBER),
+    (&[TypeSet::STRING, TypeSet::STRING], TypeSet::STRING),
+    (&[TypeSet::TUPLE, TypeSet::TUPLE], TypeSet::TUPLE),
+];
+pub(crate) const NEGATION_RULES: &[TypingRule] = &[(&[TypeSet::NUMBER], TypeSet::NUMBER)];
+pub(crate) const ORDERING_RULES: &[TypingRule] = &[
+    (&[TypeSet::NUMBER, TypeSet::NUMBER], TypeSet::BOOLEAN),
+    (&[TypeSet::STRING, TypeSet::STRING], TypeSet::BOOLEAN),
+];
+pub(crate) const REGEX_MATCH_RULES: &[TypingRule] =
+    &[(&[TypeSet::STRING, TypeSet::STRING], TypeSet::BOOLEAN)];
+pub(crate) const LOGIC_RULES: &[TypingRule] =
+    &[(&[TypeSet::BOOLEAN, TypeSet::BOOLEAN], TypeSet::BOOLEAN)];
+pub(crate) const NOT_RULES: &[TypingRule] = &[(&[TypeSet::BOOLEAN], TypeSet::BOOLEAN)];
+pub(crate) const BITWISE_RULES: &[TypingRule] = &[(&[TypeSet::INT, TypeSet::INT], TypeSet::INT)];
+pub(crate) const RANGE_RULES: &[TypingRule] = &[(&[TypeSet::INT, TypeSet::INT], TypeSet::TUPLE)];
+pub(crate) const EQUALITY_RULES: &[TypingRule] =
+    &[(&[TypeSet::ANY, TypeSet::ANY], TypeSet::BOOLEAN)];
+
+/// Returns the rules whose parameters intersect the types of all arguments, except for the argument at the given position.
+fn matching_rules<'a>(
+    rules: &'a [TypingRule],
+    arguments: &'a [TypeSet],
+    skipped_position: Option<usize>,
+) -> impl Iterator<Item = &'a TypingRule> {
+    rules.iter().filter(move |(parameters, _)| {
+        parameters
+            .iter()
+            .zip(arguments)
+            .enumerate()
+            .all(|(position, (parameter, argument))| {
+                Some(position) == skipped_position
+                    || !parameter.intersection(*argument).is_conflict()
+            })
+    })
+}
+
+#[derive(Default)]
+struct TypeInference {
+    /// The types of the variables that are read before they are assigned.
+    variable_types: HashMap<String, TypeSet>,
+    /// The types of the variables that are assigned within the expression.
+    assigned_types: HashMap<String, TypeSet>,
+}
+
+impl TypeInference {
+    /// Returns the types that the given node may evaluate to, and narrows the types of the variables that are used within it.
+    fn infer(&mut self, node: &Node) -> TypeSet {
+        use operator::Operator::*;
+
+        if let FunctionIdentifier { identifier } = node.operator() {
+            return self.infer_function(identifier, node.children());
+        }
+
+        let arguments: Vec<_> = node
+            .children()
+            .iter()
+            .map(|child| self.infer(child))
+            .collect();
+        let rules = match node.operator() {
+            RootNode | Shared { .. } | Chain => {
+                return arguments.last().copied().unwrap_or(TypeSet::EMPTY)
+            },
+            Conditional => {
+                // The condition must be a boolean, and the result is one of the branches.
+                if let Some(condition) = node.children().first() {
+                    self.constrain(condition, TypeSet::BOOLEAN);
+                }
+                return arguments
+                    .iter()
+                    .skip(1)
+                    .fold(TypeSet::CONFLICT, |result, argument| {
+                        result.union(*argument)
+                    });
+            },
+            // The result is one of the arguments, and a variable of the first argument may be missing, so it is not narrowed.
+            Coalesce => {
+                return arguments
+                    .iter()
+                    .fold(TypeSet::CONFLICT, |result, argument| {
+                        result.union(*argument)
+                    })
+            },
+            Tuple => return TypeSet::TUPLE,
+            Assign => {
+                match node.children()[0].operator() {
+                    Const {
+                        value: Value::String(identifier),
+                    } => {
+                        self.assigned_types.insert(identifier.clone(), arguments[1]);
+                    },
+                    Const { value: target } => {
+                        for identifier in assignment_target_identifiers(target) {
+                            self.assigned_types
+                                .insert(identifier.to_string(), TypeSet::ANY);
+                        }
+                    },
+                    _ => {},
+                }
+                return TypeSet::EMPTY;
+            },
+            Const { value } => return ValueType::from(value).into(),
+            PreservedConst { constant } => return ValueType::from(&constant.value).into(),
+            VariableIdentifier { identifier } => {
+                return match self.assigned_types.get(&**identifier) {
+                    Some(assigned_type) => *assigned_type,
+                    None => *self
+                        .variable_types
+                        .entry(identifier.to_string())
+                        .or_insert(TypeSet::ANY),
+                }
+            },
+            NamedFunctionIdentifier { .. } => return TypeSet::ANY,
+            FunctionIdentifier { .. } => unreachable!(),
+            operator => match operator.expect_implementation().typing_rules() {
+                Some(rules) => rules,
+                None => return TypeSet::ANY,
+            },
+        };
+
+        // An argument may have the types that the rules matching the types of all other arguments accept.
+        // If no rule matches the other arguments, the operator fails regardless of the type of this argument, so it is not narrowed.
+        for (position, child) in node.children().iter().enumerate() {
+            let allowed = matching_rules(rules, &arguments, Some(position))
+                .map(|(parameters, _)| parameters.get(position).copied().unwrap_or(TypeSet::ANY))
+                .fold(TypeSet::CONFLICT, TypeSet::union);
+            if !allowed.is_conflict() {
+                self.constrain(child, allowed);
+            }
+        }
+
+        let result = matching_rules(rules, &arguments, None)
+            .map(|(_, result)| *result)
+            .fold(TypeSet::CONFLICT, TypeSet::union);
+        if result.is_conflict() {
+            rules
+                .iter()
+                .map(|(_, result)| *result)
+                .fold(TypeSet::CONFLICT, TypeSet::union)
+        } else {
+            result
+        }
+    }
+
+    /// Returns the types that the given function call may evaluate to, and narrows the types of its arguments according to the signature of the builtin function with the given identifier.
+    fn infer_function(&mut self, identifier: &str, children: &[Node]) -> TypeSet {
+        let argument = children[0].strip_root_nodes();
+        if is_higher_order_builtin(identifier)
+            && higher_order_argument_amount(argument.operator(), argument.children().len()) == 2
+        {
+            // `it` is linked by the function, so it is not a variable of the expression.
+            let (elements, body) = (&argument.children()[0], &argument.children()[1]);
+            self.infer(elements);
+            self.constrain(elements, TypeSet::TUPLE);
+            let outer_type = self
+                .assigned_types
+                .insert(IT_IDENTIFIER.to_string(), TypeSet::ANY);
+            self.infer(body);
+            match outer_type {
+                Some(outer_type) => self
+                    .assigned_types
+                    .insert(IT_IDENTIFIER.to_string(), outer_type),
+                None => self.assigned_types.remove(IT_IDENTIFIER),
+            };
+            return TypeSet::TUPLE;
+        }
+        let signature = match builtin_signature(identifier) {
+            Some(signature) => signature,
+            None => {
+                self.infer(argument);
+                return TypeSet::ANY;
+            },
+        };
+
+        match signature.arguments() {
+            Some(expected)
+                if expected.len() >= 2
+                    && *argument.operator() == Operator::Tuple
+                    && argument.children().len() == expected.len() =>
+            {
+                for (child, expected) in argument.children().iter().zip(expected) {
+                    self.infer(child);
+                    self.constrain(child, (*expected).into());
+                }
+            },
+            Some([expected]) => {
+                self.infer(argument);
+                self.constrain(argument, (*expected).into());
+            },
+            _ => {
+                self.infer(argument);
+            },
+        }
+        signature.result().into()
+    }
+
+    /// Narrows the type of the variable that the given node evaluates to, if it directly evaluates to a variable that is read before it is assigned.
+    fn constrain(&mut self, node: &Node, allowed: TypeSet) {
+        match node.operator() {
+            Operator::VariableIdentifier { identifier }
+                if !self.assigned_types.contains_key(&**identifier) =>
+            {
+                if let Some(variable_type) = self.variable_types.get_mut(&**identifier) {
+                    *variable_type = variable_type.intersection(allowed);
+                }
+            },
+            Operator::RootNode | Operator::Shared { .. } | Operator::Chain => {
+                if let Some(last) = node.children().last() {
+                    self.constrain(last, allowed);
+                }
+            },
+            Operator::Conditional => {
+                for branch in node.children().iter().skip(1) {
+                    self.constrain(branch, allowed);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Returns the type of the result of an arithmetic operator applied to arguments of the given types.
+fn arithmetic_result(a: StaticType, b: StaticType) -> StaticType {
+    match (a.as_numeric(), b.as_numeric()) {
+        (StaticType::Int, StaticType::Int) => StaticType::Int,
+        (StaticType::Float, _) | (_, StaticType::Float) => StaticType::Float,
+        _ => StaticType::Number,
+    }
+}
+
+fn operator_symbol(operator: &Operator) -> String {
+    operator.to_string().trim().to_string()
+}
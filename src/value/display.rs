@@ -1,12 +1,68 @@
+use std::cell::Cell;
 use std::fmt::{Display, Error, Formatter};
 
+use value::FloatType;
 use Value;
 
+/// A function that writes a float in place of the default formatting of `Value::Float`.
+pub type FloatFormatter = fn(FloatType, &mut Formatter) -> Result<(), Error>;
+
+thread_local! {
+    static FLOAT_FORMATTER: Cell<Option<FloatFormatter>> = const { Cell::new(None) };
+}
+
+/// Sets the function that writes floats when values are displayed on the current thread, and returns the previous one.
+///
+/// The formatter is used by the `Display` implementation of `Value`, including floats within tuples, and by the builtin function `str`.
+/// It is not used to display errors and operator trees, so that their messages and expressions stay the same on all threads.
+/// Passing `None` restores the default formatting, which writes floats like the `Display` implementation of `FloatType`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::fmt;
+///
+/// fn fixed(float: FloatType, f: &mut fmt::Formatter) -> fmt::Result {
+///     write!(f, "{:.2}", float)
+/// }
+///
+/// set_float_formatter(Some(fixed));
+/// assert_eq!(Value::from(1.0 / 3.0).to_string(), "0.33");
+/// set_float_formatter(None);
+/// assert_eq!(Value::from(0.5).to_string(), "0.5");
+/// ```
+pub fn set_float_formatter(formatter: Option<FloatFormatter>) -> Option<FloatFormatter> {
+    FLOAT_FORMATTER.with(|current| current.replace(formatter))
+}
+
+/// Disables the float formatter of the current thread until it is dropped.
+pub(crate) struct CanonicalFloats {
+    formatter: Option<FloatFormatter>,
+}
+
+impl CanonicalFloats {
+    pub(crate) fn new() -> Self {
+        Self {
+            formatter: set_float_formatter(None),
+        }
+    }
+}
+
+impl Drop for CanonicalFloats {
+    fn drop(&mut self) {
+        set_float_formatter(self.formatter);
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         match self {
             Value::String(string) => write!(f, "\"{}\"", string),
-            Value::Float(float) => write!(f, "{}", float),
+            Value::Float(float) => match FLOAT_FORMATTER.with(Cell::get) {
+                Some(formatter) => formatter(*float, f),
+                None => write!(f, "{}", float),
+            },
             Value::Int(int) => write!(f, "{}", int),
             Value::Boolean(boolean) => write!(f, "{}", boolean),
             Value::Tuple(tuple) => {
@@ -1,8 +1,12 @@
-use error::{EvalexprError, EvalexprResult};
+use error::{expect_tuple, EvalexprError, EvalexprResult};
+use std::convert::TryFrom;
+use std::mem;
 
-mod display;
+pub(crate) mod display;
 pub mod value_type;
 
+pub use self::display::{set_float_formatter, FloatFormatter};
+
 /// The type used to represent integers in `Value::Int`.
 pub type IntType = i64;
 
@@ -40,57 +44,36 @@ pub enum Value {
 impl Value {
     /// Returns true if `self` is a `Value::String`.
     pub fn is_string(&self) -> bool {
-        match self {
-            Value::String(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::String(_))
     }
     /// Returns true if `self` is a `Value::Int`.
     pub fn is_int(&self) -> bool {
-        match self {
-            Value::Int(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Int(_))
     }
 
     /// Returns true if `self` is a `Value::Float`.
     pub fn is_float(&self) -> bool {
-        match self {
-            Value::Float(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Float(_))
     }
 
     /// Returns true if `self` is a `Value::Int` or `Value::Float`.
     pub fn is_number(&self) -> bool {
-        match self {
-            Value::Int(_) | Value::Float(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Int(_) | Value::Float(_))
     }
 
     /// Returns true if `self` is a `Value::Boolean`.
     pub fn is_boolean(&self) -> bool {
-        match self {
-            Value::Boolean(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Boolean(_))
     }
 
     /// Returns true if `self` is a `Value::Tuple`.
     pub fn is_tuple(&self) -> bool {
-        match self {
-            Value::Tuple(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Tuple(_))
     }
 
     /// Returns true if `self` is a `Value::Empty`.
     pub fn is_empty(&self) -> bool {
-        match self {
-            Value::Empty => true,
-            _ => false,
-        }
+        matches!(self, Value::Empty)
     }
 
     /// Clones the value stored in `self` as `String`, or returns `Err` if `self` is not a `Value::String`.
@@ -150,6 +133,232 @@ impl Value {
             value => Err(EvalexprError::expected_empty(value.clone())),
         }
     }
+
+    /// Returns the element of the tuple at the given index, or `None` if `self` is not a `Value::Tuple` or the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        match self {
+            Value::Tuple(tuple) => tuple.get(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the amount of elements of the tuple, or `None` if `self` is not a `Value::Tuple`.
+    ///
+    /// Note that `Value::is_empty` does not check for an empty tuple, but for `Value::Empty`.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Value::Tuple(tuple) => Some(tuple.len()),
+            _ => None,
+        }
+    }
+
+    /// Clones the element of the tuple at the given index as `String`.
+    ///
+    /// Returns `Err` if `self` is not a `Value::Tuple` or the index is out of bounds, and an `EvalexprError::InTupleElement` with the index if the element is not a `Value::String`.
+    /// The other `get_[type]` methods work the same for their types.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let result = eval("(\"Ada\", 40 + 2, true)").unwrap(); // Do proper error handling here
+    /// let (name, score, active) = (result.get_string(0).unwrap(), result.get_int(1).unwrap(), result.get_boolean(2).unwrap());
+    /// assert_eq!((name.as_str(), score, active), ("Ada", 42, true));
+    ///
+    /// assert_eq!(result.get_float(1), Err(EvalexprError::InTupleElement {
+    ///     index: 1,
+    ///     source: Box::new(EvalexprError::expected_float(Value::from(42))),
+    /// }));
+    /// assert_eq!(result.get_int(3), Err(EvalexprError::TupleIndexOutOfBounds { index: 3, length: 3 }));
+    /// ```
+    pub fn get_string(&self, index: usize) -> EvalexprResult<String> {
+        self.get_as(index, Value::as_string)
+    }
+
+    /// Clones the element of the tuple at the given index as `IntType`, see `Value::get_string`.
+    pub fn get_int(&self, index: usize) -> EvalexprResult<IntType> {
+        self.get_as(index, Value::as_int)
+    }
+
+    /// Clones the element of the tuple at the given index as `FloatType`, see `Value::get_string`.
+    pub fn get_float(&self, index: usize) -> EvalexprResult<FloatType> {
+        self.get_as(index, Value::as_float)
+    }
+
+    /// Clones the element of the tuple at the given index as `FloatType` like `Value::as_number`, see `Value::get_string`.
+    pub fn get_number(&self, index: usize) -> EvalexprResult<FloatType> {
+        self.get_as(index, Value::as_number)
+    }
+
+    /// Clones the element of the tuple at the given index as `bool`, see `Value::get_string`.
+    pub fn get_boolean(&self, index: usize) -> EvalexprResult<bool> {
+        self.get_as(index, Value::as_boolean)
+    }
+
+    /// Clones the element of the tuple at the given index as `TupleType`, see `Value::get_string`.
+    pub fn get_tuple(&self, index: usize) -> EvalexprResult<TupleType> {
+        self.get_as(index, Value::as_tuple)
+    }
+
+    fn get_as<T>(
+        &self,
+        index: usize,
+        as_type: fn(&Value) -> EvalexprResult<T>,
+    ) -> EvalexprResult<T> {
+        let tuple = expect_tuple(self)?;
+        let element = tuple
+            .get(index)
+            .ok_or(EvalexprError::TupleIndexOutOfBounds {
+                index,
+                length: tuple.len(),
+            })?;
+        as_type(element).map_err(|error| EvalexprError::InTupleElement {
+            index,
+            source: Box::new(error),
+        })
+    }
+
+    /// Renders `self` as an expression that evaluates to `self`.
+    ///
+    /// This is meant for generating expressions from data, where embedding a value by simply formatting it could change the meaning of the expression.
+    /// Strings are quoted and escaped with `escape_string_literal`, floats always contain a decimal point, and negative numbers as well as tuples are wrapped in braces.
+    /// Some values have no literal syntax and are rendered as an expression instead:
+    /// `IntType::MIN` becomes a subtraction, infinite and NaN floats become divisions by zero, and the empty tuple becomes the empty range `(0..0)`.
+    /// Tuples with one element are rendered with a trailing comma, like `(1,)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let name = Value::from("\"; drop == true");
+    /// let expression = format!("name == {}", name.to_expression_literal());
+    /// assert_eq!(expression, r#"name == "\"; drop == true""#);
+    ///
+    /// let context = context_map! { "name" => name }.unwrap(); // Do proper error handling here
+    /// assert_eq!(eval_boolean_with_context(&expression, &context), Ok(true));
+    ///
+    /// let tuple = Value::from(vec![Value::from(-1), Value::from(2.0), Value::from(true)]);
+    /// assert_eq!(tuple.to_expression_literal(), "((-1), 2.0, true)");
+    /// assert_eq!(eval(&tuple.to_expression_literal()), Ok(tuple));
+    ///
+    /// let tuples = Value::from(vec![Value::from(vec![]), Value::from(vec![Value::from(1)])]);
+    /// assert_eq!(tuples.to_expression_literal(), "((0..0), (1,))");
+    /// assert_eq!(eval(&tuples.to_expression_literal()), Ok(tuples));
+    /// ```
+    pub fn to_expression_literal(&self) -> String {
+        match self {
+            Value::String(string) => format!("\"{}\"", escape_string_literal(string)),
+            Value::Int(IntType::MIN) => format!("({} - 1)", IntType::MIN + 1),
+            Value::Int(int) if *int < 0 => format!("({})", int),
+            Value::Int(int) => int.to_string(),
+            Value::Float(float) if float.is_nan() => "(0.0 / 0.0)".to_string(),
+            Value::Float(float) if float.is_infinite() && *float > 0.0 => "(1.0 / 0.0)".to_string(),
+            Value::Float(float) if float.is_infinite() => "(-1.0 / 0.0)".to_string(),
+            Value::Float(float) => {
+                let mut literal = float.to_string();
+                if !literal.contains('.') {
+                    literal.push_str(".0");
+                }
+                if float.is_sign_negative() {
+                    format!("({})", literal)
+                } else {
+                    literal
+                }
+            },
+            Value::Boolean(boolean) => boolean.to_string(),
+            Value::Tuple(tuple) if tuple.is_empty() => "(0..0)".to_string(),
+            Value::Tuple(tuple) => {
+                let elements: Vec<_> = tuple.iter().map(Value::to_expression_literal).collect();
+                if elements.len() == 1 {
+                    format!("({},)", elements[0])
+                } else {
+                    format!("({})", elements.join(", "))
+                }
+            },
+            Value::Empty => "()".to_string(),
+        }
+    }
+
+    /// Converts a `usize` into a `Value::Int`, saturating at `IntType::MAX` if it is out of range.
+    ///
+    /// Use `Value::try_from` instead to detect integers that are out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::convert::TryFrom;
+    ///
+    /// assert_eq!(Value::from_usize_lossy(usize::MAX), Value::Int(IntType::MAX));
+    /// assert!(Value::try_from(usize::MAX).is_err());
+    /// ```
+    pub fn from_usize_lossy(int: usize) -> Self {
+        Value::Int(IntType::try_from(int).unwrap_or(IntType::MAX))
+    }
+
+    /// Converts a `u64` into a `Value::Int`, saturating at `IntType::MAX` if it is out of range.
+    pub fn from_u64_lossy(int: u64) -> Self {
+        Value::Int(IntType::try_from(int).unwrap_or(IntType::MAX))
+    }
+
+    /// Converts a `u128` into a `Value::Int`, saturating at `IntType::MAX` if it is out of range.
+    pub fn from_u128_lossy(int: u128) -> Self {
+        Value::Int(IntType::try_from(int).unwrap_or(IntType::MAX))
+    }
+
+    /// Converts an `i128` into a `Value::Int`, saturating at `IntType::MIN` or `IntType::MAX` if it is out of range.
+    pub fn from_i128_lossy(int: i128) -> Self {
+        Value::Int(IntType::try_from(int).unwrap_or(if int < 0 {
+            IntType::MIN
+        } else {
+            IntType::MAX
+        }))
+    }
+
+    /// Returns the amount of bytes that `self` occupies on the heap.
+    /// The bytes of strings count, as well as the elements of tuples, including nested tuples.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        match self {
+            Value::String(string) => string.len(),
+            Value::Tuple(tuple) => tuple_allocated_bytes(tuple),
+            _ => 0,
+        }
+    }
+}
+
+/// Escapes the given string such that it can be placed between double quotes to form a string literal.
+/// The string literal evaluates to the given string.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let string = r#"say "hi" \ bye"#;
+/// let literal = format!("\"{}\"", escape_string_literal(string));
+/// assert_eq!(literal, r#""say \"hi\" \\ bye""#);
+/// assert_eq!(eval_string(&literal), Ok(string.to_string()));
+/// ```
+pub fn escape_string_literal(string: &str) -> String {
+    let mut result = String::with_capacity(string.len());
+    for c in string.chars() {
+        if c == '"' || c == '\\' {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Returns the amount of bytes that a tuple with the given elements occupies on the heap.
+pub(crate) fn tuple_allocated_bytes(tuple: &[Value]) -> usize {
+    tuple.iter().fold(0, |bytes, value| {
+        bytes
+            .saturating_add(mem::size_of::<Value>())
+            .saturating_add(value.allocated_bytes())
+    })
 }
 
 impl From<String> for Value {
@@ -176,6 +385,24 @@ impl From<IntType> for Value {
     }
 }
 
+/// Implements the conversions of integer types that may be out of the range of `IntType`.
+/// Integers out of range fail with `EvalexprError::TypeError`, whose actual value is the integer converted to the nearest float.
+macro_rules! impl_try_from_large_int {
+    ($($int:ty),*) => {$(
+        impl TryFrom<$int> for Value {
+            type Error = EvalexprError;
+
+            fn try_from(int: $int) -> Result<Self, Self::Error> {
+                IntType::try_from(int)
+                    .map(Value::Int)
+                    .map_err(|_| EvalexprError::int_out_of_range(int))
+            }
+        }
+    )*};
+}
+
+impl_try_from_large_int!(isize, usize, u64, i128, u128);
+
 impl From<bool> for Value {
     fn from(boolean: bool) -> Self {
         Value::Boolean(boolean)
@@ -202,7 +429,9 @@ impl From<()> for Value {
 
 #[cfg(test)]
 mod tests {
-    use value::{TupleType, Value};
+    use error::EvalexprError;
+    use std::convert::TryFrom;
+    use value::{IntType, TupleType, Value};
 
     #[test]
     fn test_value_conversions() {
@@ -219,6 +448,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tuple_accessors() {
+        let tuple = Value::from(vec![
+            Value::from("Ada"),
+            Value::from(42),
+            Value::from(true),
+            Value::from(1.5),
+            Value::from(vec![Value::from(1)]),
+        ]);
+        assert_eq!(tuple.len(), Some(5));
+        assert_eq!(tuple.get(1), Some(&Value::from(42)));
+        assert_eq!(tuple.get(5), None);
+        assert_eq!(tuple.get_string(0), Ok("Ada".to_string()));
+        assert_eq!(tuple.get_int(1), Ok(42));
+        assert_eq!(tuple.get_boolean(2), Ok(true));
+        assert_eq!(tuple.get_float(3), Ok(1.5));
+        assert_eq!(tuple.get_number(1), Ok(42.0));
+        assert_eq!(tuple.get_tuple(4), Ok(vec![Value::from(1)]));
+
+        // Values that are not tuples have no elements.
+        assert_eq!(Value::from(1).len(), None);
+        assert_eq!(Value::Empty.len(), None);
+        assert_eq!(Value::from("abc").get(0), None);
+        assert_eq!(
+            Value::from(1).get_int(0),
+            Err(EvalexprError::expected_tuple(Value::from(1)))
+        );
+        assert_eq!(Value::from(TupleType::new()).len(), Some(0));
+
+        // Errors name the index.
+        assert_eq!(
+            tuple.get_int(5),
+            Err(EvalexprError::TupleIndexOutOfBounds {
+                index: 5,
+                length: 5
+            })
+        );
+        assert_eq!(
+            tuple.get_int(3),
+            Err(EvalexprError::InTupleElement {
+                index: 3,
+                source: Box::new(EvalexprError::expected_int(Value::from(1.5))),
+            })
+        );
+        assert_eq!(
+            tuple.get_boolean(0).unwrap_err().to_string(),
+            "in tuple element 0: Expected a Value::Boolean, but got String(\"Ada\")."
+        );
+    }
+
+    #[test]
+    fn test_integer_conversions() {
+        let out_of_range = |value: &str| {
+            Err(EvalexprError::IntOutOfRange {
+                value: value.to_string(),
+            })
+        };
+        let max = IntType::MAX;
+
+        assert_eq!(Value::try_from(max as u64), Ok(Value::Int(max)));
+        assert_eq!(
+            Value::try_from(max as u64 + 1),
+            out_of_range("9223372036854775808")
+        );
+        assert_eq!(
+            Value::try_from(u64::MAX),
+            out_of_range("18446744073709551615")
+        );
+        assert_eq!(Value::try_from(max as usize), Ok(Value::Int(max)));
+        assert_eq!(
+            Value::try_from(max as usize + 1),
+            out_of_range("9223372036854775808")
+        );
+        assert_eq!(Value::try_from(0usize), Ok(Value::Int(0)));
+        assert_eq!(Value::try_from(isize::MIN), Ok(Value::Int(IntType::MIN)));
+        assert_eq!(Value::try_from(isize::MAX), Ok(Value::Int(max)));
+        assert_eq!(Value::try_from(max as u128), Ok(Value::Int(max)));
+        assert_eq!(
+            Value::try_from(max as u128 + 1),
+            out_of_range("9223372036854775808")
+        );
+        assert_eq!(
+            Value::try_from(IntType::MIN as i128),
+            Ok(Value::Int(IntType::MIN))
+        );
+        assert_eq!(Value::try_from(max as i128), Ok(Value::Int(max)));
+        assert_eq!(
+            Value::try_from(IntType::MIN as i128 - 1),
+            out_of_range("-9223372036854775809")
+        );
+        assert_eq!(
+            Value::try_from(max as i128 + 1),
+            out_of_range("9223372036854775808")
+        );
+
+        assert_eq!(Value::from_usize_lossy(max as usize), Value::Int(max));
+        assert_eq!(Value::from_usize_lossy(usize::MAX), Value::Int(max));
+        assert_eq!(Value::from_u64_lossy(max as u64 + 1), Value::Int(max));
+        assert_eq!(Value::from_u128_lossy(u128::MAX), Value::Int(max));
+        assert_eq!(Value::from_i128_lossy(i128::MIN), Value::Int(IntType::MIN));
+        assert_eq!(
+            Value::from_i128_lossy(IntType::MIN as i128),
+            Value::Int(IntType::MIN)
+        );
+        assert_eq!(Value::from_i128_lossy(i128::MAX), Value::Int(max));
+    }
+
     #[test]
     fn test_value_checks() {
         assert!(Value::from("string").is_string());
@@ -1,7 +1,7 @@
 use Value;
 
 /// The type of a `Value`.
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ValueType {
     /// The `Value::String` type.
     String,
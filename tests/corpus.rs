@@ -0,0 +1,88 @@
+extern crate evalexpr;
+
+/// Runs the golden corpus in `tests/corpus`, which pins the results of expressions across versions.
+#[test]
+#[cfg(feature = "testing")]
+fn test_corpus() {
+    use evalexpr::testing::run_corpus;
+
+    let report = run_corpus(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus")).unwrap();
+    assert!(report.is_success(), "{}", report);
+    assert!(report.passed() + report.skipped() >= 200, "{}", report);
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_corpus_failures() {
+    use evalexpr::testing::{run_corpus, CorpusFailure};
+    use std::fs;
+
+    let directory = std::env::temp_dir().join(format!("evalexpr-corpus-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let file = directory.join("cases.corpus");
+    fs::write(
+        &file,
+        [
+            "# Passes.",
+            "expr: a * 2",
+            "context: a = 1.5",
+            "expect: 3.0",
+            "display: 3",
+            "",
+            "expr: 1 + 1",
+            "expect: 3",
+            "",
+            "expr: 1 / 0",
+            "error: DivideError",
+            "",
+            "expr: 1 / 1",
+            "error: DivisionError",
+            "",
+            "expr: 1",
+            "requires: builtin_math, no_such_feature",
+            "expect: 1",
+            "",
+            "expr: 2",
+            "result: 2",
+            "expect: 2",
+            "",
+            "expr: 3",
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    // Files with other extensions are ignored.
+    fs::write(directory.join("notes.txt"), "expr: 1\nexpect: 2\n").unwrap();
+
+    let report = run_corpus(&directory).unwrap();
+    let failure = |line: usize, expression: &str, message: &str| CorpusFailure {
+        file: file.clone(),
+        line,
+        expression: expression.to_string(),
+        message: message.to_string(),
+    };
+    assert_eq!(
+        report.failures(),
+        [
+            failure(7, "1 + 1", "expected 3, but got 2"),
+            failure(10, "1 / 0", "unknown error variant \"DivideError\""),
+            failure(13, "1 / 1", "expected the error DivisionError, but got 1"),
+            failure(16, "1", "unknown feature flag \"no_such_feature\""),
+            failure(
+                21,
+                "",
+                "expected a field like \"expr: 1 + 2\", but got \"result: 2\""
+            ),
+            failure(24, "3", "the case expects no result"),
+        ]
+    );
+    assert_eq!(report.passed(), 2);
+    assert_eq!(report.skipped(), 0);
+    assert!(!report.is_success());
+    assert!(report
+        .to_string()
+        .starts_with("2 passed, 6 failed, 0 skipped\n"));
+    assert!(run_corpus(directory.join("missing.corpus")).is_err());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
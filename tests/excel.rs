@@ -0,0 +1,183 @@
+extern crate evalexpr;
+
+use evalexpr::*;
+
+/// Formulas from the Excel documentation and their results as computed by Excel.
+#[test]
+#[cfg(feature = "excel_compat")]
+fn test_excel_formulas() {
+    let context = context_map! {
+        "property_values" => Value::from(vec![
+            Value::from(100_000),
+            Value::from(200_000),
+            Value::from(300_000),
+            Value::from(400_000),
+        ]),
+        "commissions" => Value::from(vec![
+            Value::from(7_000),
+            Value::from(14_000),
+            Value::from(21_000),
+            Value::from(28_000),
+        ]),
+        "threshold" => 250_000,
+        "categories" => Value::from(vec![
+            Value::from("Vegetables"),
+            Value::from("Vegetables"),
+            Value::from("Fruits"),
+            Value::from(""),
+            Value::from("Vegetables"),
+            Value::from("Fruits"),
+        ]),
+        "foods" => Value::from(vec![
+            Value::from("Tomatoes"),
+            Value::from("Celery"),
+            Value::from("Oranges"),
+            Value::from("Butter"),
+            Value::from("Carrots"),
+            Value::from("Apples"),
+        ]),
+        "sales" => Value::from(vec![
+            Value::from(2_300),
+            Value::from(5_500),
+            Value::from(800),
+            Value::from(400),
+            Value::from(4_200),
+            Value::from(1_200),
+        ]),
+    }
+    .unwrap();
+
+    let formulas: Vec<(&str, Value)> = vec![
+        ("ROUND(2.15, 1)", Value::from(2.2)),
+        ("ROUND(2.149, 1)", Value::from(2.1)),
+        ("ROUND(-1.475, 2)", Value::from(-1.48)),
+        ("ROUND(21.5, -1)", Value::from(20.0)),
+        ("ROUND(626.3, -3)", Value::from(1000.0)),
+        ("ROUND(1.98, -1)", Value::from(0.0)),
+        ("ROUND(-50.55, -2)", Value::from(-100.0)),
+        ("ROUND(2.675, 2)", Value::from(2.68)),
+        ("ROUND(-2.5, 0)", Value::from(-3.0)),
+        ("ROUND(1250, -2)", Value::from(1300)),
+        ("MID(\"Fluid Flow\", 1, 5)", Value::from("Fluid")),
+        ("MID(\"Fluid Flow\", 7, 20)", Value::from("Flow")),
+        ("MID(\"Fluid Flow\", 20, 5)", Value::from("")),
+        ("LEFT(\"Sale Price\", 4)", Value::from("Sale")),
+        ("LEFT(\"Sweden\")", Value::from("S")),
+        ("RIGHT(\"Sale Price\", 5)", Value::from("Price")),
+        ("RIGHT(\"Stock Number\")", Value::from("r")),
+        (
+            "CONCATENATE(\"Stream population for \", \"brook trout\", \" species is \", 32, \"/mile.\")",
+            Value::from("Stream population for brook trout species is 32/mile."),
+        ),
+        ("CONCATENATE(0.1 + 0.2, \" \", true)", Value::from("0.3 TRUE")),
+        ("IF(75 > 50, \"Over Budget\", \"OK\")", Value::from("Over Budget")),
+        ("IF(0, \"Yes\")", Value::from(false)),
+        ("AND(true, 1 > 0, 5)", Value::from(true)),
+        ("AND(true, false, true)", Value::from(false)),
+        ("OR(false, 0, 2 < 1)", Value::from(false)),
+        ("OR((false, false), 1)", Value::from(true)),
+        (
+            "SUMIF(property_values, \">160000\", commissions)",
+            Value::from(63_000),
+        ),
+        ("SUMIF(property_values, \">160000\")", Value::from(900_000)),
+        (
+            "SUMIF(property_values, 300000, commissions)",
+            Value::from(21_000),
+        ),
+        (
+            "SUMIF(property_values, CONCATENATE(\">\", threshold), commissions)",
+            Value::from(49_000),
+        ),
+        ("SUMIF(categories, \"Fruits\", sales)", Value::from(2_000)),
+        ("SUMIF(categories, \"vegetables\", sales)", Value::from(12_000)),
+        ("SUMIF(foods, \"*es\", sales)", Value::from(4_300)),
+        ("SUMIF(categories, \"\", sales)", Value::from(400)),
+        ("SUMIF(categories, \"<>Vegetables\", sales)", Value::from(2_400)),
+        ("SUMIF(sales, \"<=800\")", Value::from(1_200)),
+    ];
+
+    for (formula, expected) in formulas {
+        assert_eq!(
+            eval_with_context(formula, &context),
+            Ok(expected),
+            "{}",
+            formula
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "excel_compat")]
+fn test_excel_aliases() {
+    // The spreadsheet functions are found in any case.
+    assert_eq!(eval("round(2.5, 0)"), Ok(Value::from(3.0)));
+    assert_eq!(eval("Left(\"abc\", 2)"), Ok(Value::from("ab")));
+    assert_eq!(eval("if(1 == 1, 1, 2)"), Ok(Value::from(1)));
+    assert!(builtin_function_identifiers().contains(&"SUMIF"));
+
+    // Functions from the context take precedence.
+    let context = HashMapContext::new().with_fn("round", |x: FloatType| Ok(x.floor()));
+    assert_eq!(
+        eval_with_context("round(2.5)", &context),
+        Ok(Value::from(2.0))
+    );
+    assert_eq!(
+        eval_with_context("ROUND(2.5, 0)", &context),
+        Ok(Value::from(3.0))
+    );
+
+    // Invalid arguments fail like in spreadsheets.
+    assert_eq!(
+        eval("MID(\"abc\", 0, 1)"),
+        Err(EvalexprError::ArgumentOutOfRange {
+            function: "MID".to_string(),
+            argument: Value::from(0),
+            expected: "a start position of at least 1".to_string(),
+        })
+    );
+    assert_eq!(
+        eval("LEFT(\"abc\", -1)"),
+        Err(EvalexprError::ArgumentOutOfRange {
+            function: "LEFT".to_string(),
+            argument: Value::from(-1),
+            expected: "a count of at least 0".to_string(),
+        })
+    );
+    assert_eq!(
+        eval("SUMIF((1, 2), 1, (1, 2, 3))"),
+        Err(EvalexprError::ArgumentOutOfRange {
+            function: "SUMIF".to_string(),
+            argument: Value::from(vec![Value::from(1), Value::from(2), Value::from(3)]),
+            expected: "a tuple of 2 elements like the range".to_string(),
+        })
+    );
+    assert_eq!(
+        eval("AND(\"yes\")"),
+        Err(EvalexprError::expected_boolean(Value::from("yes")))
+    );
+    assert_eq!(
+        eval("IF(true)"),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        eval("ROUND(1.5)"),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+}
+
+#[test]
+#[cfg(not(feature = "excel_compat"))]
+fn test_excel_disabled() {
+    assert_eq!(
+        eval("round(1.5, 0)").unwrap_err().to_string(),
+        "Function identifier is not bound to anything by context: \"round\". The builtin function with this identifier requires the feature flag \"excel_compat\"."
+    );
+    assert!(!builtin_function_identifiers().contains(&"SUMIF"));
+}
@@ -1,22 +1,151 @@
 extern crate evalexpr;
+#[cfg(feature = "async")]
+extern crate futures;
+#[cfg(feature = "async")]
+extern crate tokio;
 
 use evalexpr::{error::*, *};
 
 #[test]
 fn test_unary_examples() {
-    assert_eq!(eval("3"), Ok(Value::Int(3)));
-    assert_eq!(eval("3.3"), Ok(Value::Float(3.3)));
-    assert_eq!(eval("true"), Ok(Value::Boolean(true)));
-    assert_eq!(eval("false"), Ok(Value::Boolean(false)));
-    assert_eq!(
-        eval("blub"),
-        Err(EvalexprError::VariableIdentifierNotFound(
-            "blub".to_string()
-        ))
+    assert_eval_eq!("3", 3);
+    assert_eval_eq!("3.3", 3.3);
+    assert_eval_eq!("true", true);
+    assert_eval_eq!("false", false);
+    assert_eval_err!(
+        "blub",
+        EvalexprError::VariableIdentifierNotFound { identifier, .. } if identifier == "blub"
+    );
+    assert_eval_eq!("-3", -3);
+    assert_eval_eq!("-3.6", -3.6);
+    assert_eval_eq!("----3", 3);
+}
+
+#[test]
+fn test_unary_minus() {
+    let context = context_map! {
+        "x" => 5,
+        "y" => 2.5,
+        "min" => IntType::MIN,
+        "flag" => true,
+        "name" => "a"
+    }
+    .unwrap();
+    let eval = |expression: &str| eval_with_context(expression, &context);
+
+    // A minus at the start, after an opening parenthesis or after another operator negates its operand.
+    assert_eq!(eval("-3"), Ok(Value::from(-3)));
+    assert_eq!(eval("-(1+2)"), Ok(Value::from(-3)));
+    assert_eq!(eval("(-(1+2))"), Ok(Value::from(-3)));
+    assert_eq!(eval("2--3"), Ok(Value::from(5)));
+    assert_eq!(eval("4 - -4"), Ok(Value::from(8)));
+    assert_eq!(eval("3 * -2"), Ok(Value::from(-6)));
+    assert_eq!(eval("-x"), Ok(Value::from(-5)));
+    assert_eq!(eval("-y"), Ok(Value::from(-2.5)));
+    assert_eq!(eval("x - -x"), Ok(Value::from(10)));
+    assert_eq!(
+        eval("(1, -x)"),
+        Ok(Value::from(vec![Value::from(1), Value::from(-5)]))
+    );
+
+    // Negating the smallest integer overflows.
+    assert_eq!(
+        eval("-min"),
+        Err(EvalexprError::NegationError {
+            argument: Value::from(IntType::MIN)
+        })
+    );
+    assert_eq!(eval("-(min + 1)"), Ok(Value::from(IntType::MAX)));
+
+    // Only numbers can be negated.
+    assert_eq!(
+        eval("-flag"),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from(true)
+        })
+    );
+    assert_eq!(
+        eval("-name"),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from("a")
+        })
+    );
+    assert_eq!(
+        eval("-\"b\""),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from("b")
+        })
+    );
+}
+
+#[test]
+fn test_unary_plus() {
+    let context = context_map! {
+        "x" => 5,
+        "y" => 2.5,
+        "name" => "foo"
+    }
+    .unwrap();
+    let eval = |expression: &str| eval_with_context(expression, &context);
+
+    assert_eq!(eval("+3"), Ok(Value::from(3)));
+    assert_eq!(eval("+3.5"), Ok(Value::from(3.5)));
+    assert_eq!(eval("+x"), Ok(Value::from(5)));
+    assert_eq!(eval("+y"), Ok(Value::from(2.5)));
+    assert_eq!(eval("++x"), Ok(Value::from(5)));
+    assert_eq!(eval("+-x"), Ok(Value::from(-5)));
+    assert_eq!(eval("-+x"), Ok(Value::from(-5)));
+    assert_eq!(eval("+(1 + 2) * 3"), Ok(Value::from(9)));
+    assert_eq!(eval("(+3)"), Ok(Value::from(3)));
+    assert_eq!(
+        eval("1, +2"),
+        Ok(Value::from(vec![Value::from(1), Value::from(2)]))
+    );
+    assert_eq!(eval("2 ^ +2"), Ok(Value::from(4)));
+
+    // A `+` after a value is an addition.
+    assert_eq!(eval("3 + +4"), Ok(Value::from(7)));
+    assert_eq!(eval("3++4"), Ok(Value::from(7)));
+    assert_eq!(eval("3 - +4"), Ok(Value::from(-1)));
+    assert_eq!(eval("x + + + x"), Ok(Value::from(10)));
+    assert_eq!(eval("name + \"bar\""), Ok(Value::from("foobar")));
+    let tree = build_operator_tree("3 + +4").unwrap();
+    assert_eq!(
+        tree.to_debug_tree(),
+        "\
+Root
+  Add
+    Const(Int 3)
+    Pos
+      Const(Int 4)"
+    );
+    assert_eq!(tree.to_minified_string(), "3++4");
+
+    // Only numbers pass.
+    assert_eq!(
+        eval("+\"foo\""),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from("foo")
+        })
+    );
+    assert_eq!(
+        eval("+name"),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from("foo")
+        })
+    );
+    assert_eq!(
+        eval("+true"),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from(true)
+        })
+    );
+    assert_eq!(
+        eval("+(1, 2)"),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from(vec![Value::from(1), Value::from(2)])
+        })
     );
-    assert_eq!(eval("-3"), Ok(Value::Int(-3)));
-    assert_eq!(eval("-3.6"), Ok(Value::Float(-3.6)));
-    assert_eq!(eval("----3"), Ok(Value::Int(3)));
 }
 
 #[test]
@@ -58,6 +187,111 @@ fn test_arithmetic_precedence_examples() {
     assert_eq!(eval("-15.0/7/-2"), Ok(Value::Float(-15.0 / 7.0 / -2.0)));
 }
 
+#[test]
+fn test_arithmetic_promotion() {
+    // The results of `7 <op> 2` with integers and with floats.
+    let operators = [
+        ("+", Value::from(9), Value::from(9.0)),
+        ("-", Value::from(5), Value::from(5.0)),
+        ("*", Value::from(14), Value::from(14.0)),
+        ("/", Value::from(3), Value::from(3.5)),
+        ("%", Value::from(1), Value::from(1.0)),
+        ("^", Value::from(49), Value::from(49.0)),
+    ];
+    for (operator, int_result, float_result) in operators.iter() {
+        for &(a, b) in [("7", "2"), ("7", "2.0"), ("7.0", "2"), ("7.0", "2.0")].iter() {
+            let expected = if a == "7" && b == "2" {
+                int_result
+            } else {
+                float_result
+            };
+            let expression = format!("{} {} {}", a, operator, b);
+            assert_eq!(eval(&expression).as_ref(), Ok(expected), "{}", expression);
+
+            // Variables are promoted like literals.
+            let context = context_map! {
+                "a" => eval(a).unwrap(),
+                "b" => eval(b).unwrap()
+            }
+            .unwrap();
+            let expression = format!("a {} b", operator);
+            assert_eq!(
+                eval_with_context(&expression, &context).as_ref(),
+                Ok(expected),
+                "{} with a = {} and b = {}",
+                expression,
+                a,
+                b
+            );
+        }
+    }
+
+    let context = context_map! { "total" => 7 }.unwrap();
+    assert_eq!(
+        eval_with_context("total / 3.0", &context),
+        Ok(Value::from(7.0 / 3.0))
+    );
+    assert_eq!(
+        eval_with_context("3.0 / total", &context),
+        Ok(Value::from(3.0 / 7.0))
+    );
+    assert_eq!(
+        eval_with_context("total % 2.5", &context),
+        Ok(Value::from(2.0))
+    );
+
+    // Only integer arithmetic can overflow or divide by zero.
+    assert_eq!(
+        eval("9223372036854775807 + 1"),
+        Err(EvalexprError::AdditionError {
+            augend: Value::from(IntType::MAX),
+            addend: Value::from(1)
+        })
+    );
+    assert_eq!(
+        eval("9223372036854775807 + 1.0"),
+        Ok(Value::from(IntType::MAX as FloatType + 1.0))
+    );
+    assert!(matches!(
+        eval("1 / 0"),
+        Err(EvalexprError::DivisionError { .. })
+    ));
+    assert_eq!(eval("1 / 0.0"), Ok(Value::from(FloatType::INFINITY)));
+    assert!(matches!(
+        eval("1 % 0"),
+        Err(EvalexprError::ModulationError { .. })
+    ));
+    assert!(eval_float("1.0 % 0").unwrap().is_nan());
+
+    // Integer powers with a negative exponent have no integer result.
+    assert_eq!(eval("2 ^ -1"), Ok(Value::from(0.5)));
+    assert_eq!(eval("2 ^ -1.0"), Ok(Value::from(0.5)));
+
+    // Non-numbers are rejected by all operators, and strings are only added to strings.
+    for operator in ["-", "*", "/", "%", "^"].iter() {
+        assert_eq!(
+            eval(&format!("1 {} \"a\"", operator)),
+            Err(EvalexprError::ExpectedNumber {
+                actual: Value::from("a")
+            }),
+            "{}",
+            operator
+        );
+        assert_eq!(
+            eval(&format!("true {} 1.0", operator)),
+            Err(EvalexprError::ExpectedNumber {
+                actual: Value::from(true)
+            }),
+            "{}",
+            operator
+        );
+    }
+    assert!(matches!(
+        eval("1.0 + \"a\""),
+        Err(EvalexprError::AdditionError { .. })
+    ));
+}
+
 #[test]
 fn test_braced_examples() {
     assert_eq!(eval("(1)"), Ok(Value::Int(1)));
@@ -73,578 +307,10348 @@ fn test_braced_examples() {
 
 #[test]
 fn test_mod_examples() {
-    assert_eq!(eval("1 % 4"), Ok(Value::Int(1)));
-    assert_eq!(eval("6 % 4"), Ok(Value::Int(2)));
-    assert_eq!(eval("1 % 4 + 2"), Ok(Value::Int(3)));
+    assert_eval_eq!("1 % 4", 1);
+    assert_eval_eq!("6 % 4", 2);
+    assert_eval_eq!("1 % 4 + 2", 3);
 }
 
 #[test]
-fn test_pow_examples() {
-    assert_eq!(eval("1 ^ 4"), Ok(Value::Float(1.0)));
-    assert_eq!(eval("6 ^ 4"), Ok(Value::Float(6.0f64.powf(4.0))));
-    assert_eq!(eval("1 ^ 4 + 2"), Ok(Value::Float(3.0)));
-    assert_eq!(eval("2 ^ (4 + 2)"), Ok(Value::Float(64.0)));
-}
+fn test_division_edge_cases() {
+    let context = context_map! {
+        "min" => IntType::MIN,
+        "max" => IntType::MAX
+    }
+    .unwrap();
+    let division_error =
+        |dividend: Value, divisor: Value| Err(EvalexprError::DivisionError { dividend, divisor });
+    let modulation_error =
+        |dividend: Value, divisor: Value| Err(EvalexprError::ModulationError { dividend, divisor });
 
-#[test]
-fn test_boolean_examples() {
-    assert_eq!(eval("true && false"), Ok(Value::Boolean(false)));
+    // The quotient of the smallest integer and -1 is out of range.
     assert_eq!(
-        eval("true && false || true && true"),
-        Ok(Value::Boolean(true))
+        eval_with_context("min / -1", &context),
+        division_error(Value::from(IntType::MIN), Value::from(-1))
     );
-    assert_eq!(eval("5 > 4 && 1 <= 1"), Ok(Value::Boolean(true)));
-    assert_eq!(eval("5.0 <= 4.9 || !(4 > 3.5)"), Ok(Value::Boolean(false)));
-}
-
-#[test]
-fn test_with_context() {
-    let mut context = HashMapContext::new();
-    context
-        .set_value("tr".into(), Value::Boolean(true))
-        .unwrap();
-    context
-        .set_value("fa".into(), Value::Boolean(false))
-        .unwrap();
-    context.set_value("five".into(), Value::Int(5)).unwrap();
-    context.set_value("six".into(), Value::Int(6)).unwrap();
-    context.set_value("half".into(), Value::Float(0.5)).unwrap();
-    context.set_value("zero".into(), Value::Int(0)).unwrap();
+    assert_eq!(
+        eval_with_context("min % -1", &context),
+        modulation_error(Value::from(IntType::MIN), Value::from(-1))
+    );
+    assert_eq!(
+        eval_with_context("min / 1", &context),
+        Ok(Value::from(IntType::MIN))
+    );
+    assert_eq!(
+        eval_with_context("max / -1", &context),
+        Ok(Value::from(-IntType::MAX))
+    );
+    assert_eq!(eval_with_context("max % -1", &context), Ok(Value::from(0)));
 
-    assert_eq!(eval_with_context("tr", &context), Ok(Value::Boolean(true)));
-    assert_eq!(eval_with_context("fa", &context), Ok(Value::Boolean(false)));
+    // Dividing by zero fails for integers only.
     assert_eq!(
-        eval_with_context("tr && false", &context),
-        Ok(Value::Boolean(false))
+        eval("5 / 0"),
+        division_error(Value::from(5), Value::from(0))
     );
     assert_eq!(
-        eval_with_context("five + six", &context),
-        Ok(Value::Int(11))
+        eval("0 / 0"),
+        division_error(Value::from(0), Value::from(0))
     );
     assert_eq!(
-        eval_with_context("five * half", &context),
-        Ok(Value::Float(2.5))
+        eval("5 % 0"),
+        modulation_error(Value::from(5), Value::from(0))
     );
+    assert_eq!(eval("5.0 / 0"), Ok(Value::from(FloatType::INFINITY)));
+    assert_eq!(eval("-5 / 0.0"), Ok(Value::from(FloatType::NEG_INFINITY)));
+    assert!(eval_float("0.0 / 0").unwrap().is_nan());
+    assert!(eval_float("5 % 0.0").unwrap().is_nan());
+
+    // Zero divided by anything else is zero.
+    assert_eq!(eval("0 / -7"), Ok(Value::from(0)));
+    assert_eq!(eval("0 % -7"), Ok(Value::from(0)));
+    assert_eq!(eval_with_context("0 / min", &context), Ok(Value::from(0)));
+    assert_eq!(eval("0.0 / 7"), Ok(Value::from(0.0)));
+
+    // Integers round towards zero, and the remainder has the sign of the dividend.
+    assert_eq!(eval("-7 / 2"), Ok(Value::from(-3)));
+    assert_eq!(eval("-7 % 2"), Ok(Value::from(-1)));
+    assert_eq!(eval("7 % -2"), Ok(Value::from(1)));
+
+    // Floats without a fractional part are not divided like integers.
+    assert_eq!(eval("7.0 / 2"), Ok(Value::from(3.5)));
+    assert_eq!(eval("7 / 2.0"), Ok(Value::from(3.5)));
+    assert_eq!(eval("4.0 / 2.0"), Ok(Value::from(2.0)));
+    assert_eq!(eval("7.0 % 2"), Ok(Value::from(1.0)));
     assert_eq!(
-        eval_with_context("five < six && true", &context),
-        Ok(Value::Boolean(true))
+        eval_with_context("min / -1.0", &context),
+        Ok(Value::from(-(IntType::MIN as FloatType)))
     );
 }
 
 #[test]
-fn test_functions() {
-    let mut context = HashMapContext::new();
-    context
-        .set_function(
-            "sub2".to_string(),
-            Function::new(Box::new(|argument| {
-                if let Value::Int(int) = argument {
-                    Ok(Value::Int(int - 2))
-                } else if let Value::Float(float) = argument {
-                    Ok(Value::Float(float - 2.0))
-                } else {
-                    Err(EvalexprError::expected_number(argument.clone()))
-                }
-            })),
-        )
-        .unwrap();
-    context
-        .set_value("five".to_string(), Value::Int(5))
-        .unwrap();
+fn test_pow_examples() {
+    assert_eval_eq!("1 ^ 4", 1);
+    assert_eval_eq!("6 ^ 4", 1296);
+    assert_eval_eq!("1 ^ 4 + 2", 3);
+    assert_eval_eq!("2 ^ (4 + 2)", 64);
+    assert_eval_eq!("6.0 ^ 4", 6.0f64.powf(4.0));
+    assert_eval_eq!("4 ^ 0.5", 2.0);
+
+    // Exponentiation is right associative and binds stronger than negation.
+    assert_eval_eq!("2 ^ 3 ^ 2", 512);
+    assert_eval_eq!("(2 ^ 3) ^ 2", 64);
+    assert_eval_eq!("2 * 3 ^ 2", 18);
+    assert_eval_eq!("-2 ^ 2", -4);
+    assert_eval_eq!("(-2) ^ 3", -8);
+
+    // Negative integer exponents are computed with floats.
+    assert_eval_eq!("2 ^ -1", 0.5);
+    assert_eval_eq!("-2 ^ -2", -0.25);
+    assert_eval_eq!("2 ^ -2 ^ 2", 0.0625);
 
-    assert_eq!(eval_with_context("sub2 5", &context), Ok(Value::Int(3)));
-    assert_eq!(eval_with_context("sub2(5)", &context), Ok(Value::Int(3)));
-    assert_eq!(eval_with_context("sub2 five", &context), Ok(Value::Int(3)));
-    assert_eq!(eval_with_context("sub2(five)", &context), Ok(Value::Int(3)));
     assert_eq!(
-        eval_with_context("sub2(3) + five", &context),
-        Ok(Value::Int(6))
+        eval(&format!("2 ^ {}", IntType::BITS - 1)),
+        Err(EvalexprError::ExponentiationError {
+            base: Value::from(2),
+            exponent: Value::from(IntType::BITS as IntType - 1),
+        })
+    );
+    assert_eq!(
+        eval("2 ^ 10000000000"),
+        Err(EvalexprError::ExponentiationError {
+            base: Value::from(2),
+            exponent: Value::from(10000000000),
+        })
     );
+    assert_eval_eq!("1 ^ 10000000000", 1);
+    assert_eval_eq!("(-1) ^ 10000000001", -1);
+    assert_eval_eq!("0 ^ 0", 1);
 }
 
 #[test]
-fn test_n_ary_functions() {
-    let mut context = HashMapContext::new();
-    context
-        .set_function(
-            "sub2".into(),
-            Function::new(Box::new(|argument| {
-                if let Value::Int(int) = argument {
-                    Ok(Value::Int(int - 2))
-                } else if let Value::Float(float) = argument {
-                    Ok(Value::Float(float - 2.0))
-                } else {
-                    Err(EvalexprError::expected_number(argument.clone()))
-                }
-            })),
-        )
-        .unwrap();
-    context
-        .set_function(
-            "avg".into(),
-            Function::new(Box::new(|argument| {
-                let arguments = expect_tuple(argument)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
+fn test_bitwise_operators() {
+    let mut context = context_map! {
+        "flags" => 0b0101_1010,
+        "f" => 1.5
+    }
+    .unwrap();
 
-                if let (Value::Int(a), Value::Int(b)) = (&arguments[0], &arguments[1]) {
-                    Ok(Value::Int((a + b) / 2))
-                } else {
-                    Ok(Value::Float(
-                        (arguments[0].as_float()? + arguments[1].as_float()?) / 2.0,
-                    ))
-                }
-            })),
-        )
-        .unwrap();
-    context
-        .set_function(
-            "muladd".into(),
-            Function::new(Box::new(|argument| {
-                let arguments = expect_tuple(argument)?;
-                expect_number(&arguments[0])?;
-                expect_number(&arguments[1])?;
-                expect_number(&arguments[2])?;
+    // Masking, setting, clearing and toggling bits.
+    assert_eval_eq!("flags & 0x0F", 0b1010, &context);
+    assert_eval_eq!("flags | 0b1000_0001", 0b1101_1011, &context);
+    assert_eval_eq!("flags & -1 - 2", 0b0101_1000, &context);
+    assert_eval_eq!("flags bitxor 0xFF", 0b1010_0101, &context);
+    assert_eval_eq!("flags bitxor flags", 0, &context);
+    assert_eval_eq!("-1 bitxor 0", -1);
 
-                if let (Value::Int(a), Value::Int(b), Value::Int(c)) =
-                    (&arguments[0], &arguments[1], &arguments[2])
-                {
-                    Ok(Value::Int(a * b + c))
-                } else {
-                    Ok(Value::Float(
-                        arguments[0].as_float()? * arguments[1].as_float()?
-                            + arguments[2].as_float()?,
-                    ))
-                }
-            })),
-        )
-        .unwrap();
-    context
-        .set_function(
-            "count".into(),
-            Function::new(Box::new(|arguments| match arguments {
-                Value::Tuple(tuple) => Ok(Value::from(tuple.len() as IntType)),
-                Value::Empty => Ok(Value::from(0)),
-                _ => Ok(Value::from(1)),
-            })),
-        )
-        .unwrap();
-    context
-        .set_value("five".to_string(), Value::Int(5))
-        .unwrap();
-    context
-        .set_function(
-            "function_four".into(),
-            Function::new(Box::new(|_| Ok(Value::Int(4)))),
-        )
-        .unwrap();
+    // `&` binds stronger than `bitxor`, which binds stronger than `|`, like in C.
+    assert_eval_eq!("1 | 2 bitxor 3 & 6", 1);
+    assert_eval_eq!("(1 | 2) bitxor 3 & 6", 1);
+    assert_eval_eq!("1 | 2 bitxor 3", 1);
+    assert_eval_eq!("(1 | 2) bitxor 3", 0);
+    assert_eval_eq!("1 + 2 & 3 * 4", 0);
+
+    // Comparisons bind stronger, so bitwise results need parentheses to be compared, while logic operators bind weaker.
+    assert_eval_eq!("(flags & 0x0F) == 0x0A", true, &context);
+    assert_eval_eq!("(flags & 2) != 0 && (flags & 1) == 0", true, &context);
+    assert_eval_err!(
+        "flags & 0x0F == 0x0A",
+        EvalexprError::ExpectedInt { .. },
+        &context
+    );
 
-    assert_eq!(eval_with_context("avg(7, 5)", &context), Ok(Value::Int(6)));
     assert_eq!(
-        eval_with_context("avg(sub2 5, 5)", &context),
-        Ok(Value::Int(4))
+        eval_with_context_mut("flags = flags | 4; flags", &mut context),
+        Ok(Value::from(0b0101_1110))
     );
+
+    // Only integers are accepted.
+    assert_eval_err!("f & 1", EvalexprError::ExpectedInt { .. }, &context);
     assert_eq!(
-        eval_with_context("sub2(avg(3, 6))", &context),
-        Ok(Value::Int(2))
+        eval("true | false"),
+        Err(EvalexprError::expected_int(Value::from(true)))
     );
     assert_eq!(
-        eval_with_context("sub2 avg(3, 6)", &context),
-        Ok(Value::Int(2))
+        eval("1 bitxor \"1\""),
+        Err(EvalexprError::expected_int(Value::from("1")))
     );
     assert_eq!(
-        eval_with_context("muladd(3, 6, -4)", &context),
-        Ok(Value::Int(14))
+        eval("true bitxor false"),
+        Err(EvalexprError::expected_int(Value::from(true)))
     );
-    assert_eq!(eval_with_context("count()", &context), Ok(Value::Int(0)));
+    // On booleans, `xor` is the logical exclusive or.
+    assert_eq!(eval("true xor false"), Ok(Value::Boolean(true)));
+
+    // `bitxor` is a keyword.
     assert_eq!(
-        eval_with_context("count((1, 2, 3))", &context),
-        Ok(Value::Int(3))
+        build_operator_tree("bitxor = 1"),
+        Err(EvalexprError::AssignmentToKeyword {
+            keyword: "bitxor".to_string(),
+            span: 0..6,
+        })
     );
     assert_eq!(
-        eval_with_context("count(3, 5.5, 2)", &context),
-        Ok(Value::Int(3))
+        build_operator_tree("xor2 bitxor 1")
+            .unwrap()
+            .to_minified_string(),
+        "xor2 bitxor 1"
     );
-    assert_eq!(eval_with_context("count 5", &context), Ok(Value::Int(1)));
     assert_eq!(
-        eval_with_context("function_four()", &context),
-        Ok(Value::Int(4))
+        build_operator_tree("a bitxor b & c")
+            .unwrap()
+            .to_minified_string(),
+        "a bitxor b&c"
+    );
+    assert_eq!(
+        build_operator_tree("(a | b) & c")
+            .unwrap()
+            .to_minified_string(),
+        "(a|b)&c"
     );
 }
 
 #[test]
-fn test_builtin_functions() {
-    assert_eq!(eval("min(4.0, 3)"), Ok(Value::Int(3)));
-    assert_eq!(eval("max(4.0, 3)"), Ok(Value::Float(4.0)));
-    assert_eq!(eval("len(\"foobar\")"), Ok(Value::Int(6)));
+fn test_exclusive_or_and_implication() {
+    let context = context_map! {
+        "a" => true,
+        "b" => false,
+        "n" => 1
+    }
+    .unwrap();
+
+    // `xor` is the exclusive or of booleans, and binds as weak as `||`.
+    assert_eval_eq!("true xor false", true);
+    assert_eval_eq!("true xor true", false);
+    assert_eval_eq!("false xor false", false);
+    assert_eval_eq!("!a xor b", false, &context);
+    assert_eval_eq!("!(a xor b)", false, &context);
+    assert_eval_eq!("a xor !b", false, &context);
+    assert_eval_eq!("a && b xor a", true, &context);
+    assert_eval_eq!("a || b xor a", false, &context);
+    assert_eval_eq!("n == 1 xor n > 0", false, &context);
     assert_eq!(
-        eval("str::to_lowercase(\"FOOBAR\")"),
-        Ok(Value::from("foobar"))
+        eval("true xor 1"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
     );
     assert_eq!(
-        eval("str::to_uppercase(\"foobar\")"),
-        Ok(Value::from("FOOBAR"))
+        eval("1 xor 3"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
     );
     assert_eq!(
-        eval("str::trim(\"  foo  bar \")"),
-        Ok(Value::from("foo  bar"))
+        eval("\"a\" xor false"),
+        Err(EvalexprError::expected_boolean(Value::from("a")))
     );
-}
 
-#[test]
-#[cfg(feature = "regex_support")]
-fn test_regex_functions() {
+    // It does not clash with exponentiation or the bitwise `bitxor`.
+    assert_eval_eq!("2 ^ 2 == 4 xor (1 | 2 bitxor 3) == 1", false);
+    assert_eval_eq!("2^-1", 0.5);
+    assert_eval_err!("2 ^ ^ 2", EvalexprError::WrongOperatorArgumentAmount { .. });
+
+    // `implies` binds weaker than `||` and is right associative.
+    assert_eval_eq!("true implies false", false);
+    assert_eval_eq!("true implies true", true);
+    assert_eval_eq!("false implies false", true);
+    assert_eval_eq!("b implies a implies b", true, &context);
+    assert_eval_eq!("(b implies a) implies b", false, &context);
+    assert_eval_eq!("a || b implies b", false, &context);
+    assert_eval_eq!("n > 0 implies n == 1", true, &context);
     assert_eq!(
-        eval("str::regex_matches(\"foobar\", \"[ob]{3}\")"),
-        Ok(Value::Boolean(true))
+        eval("1 implies true"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
     );
     assert_eq!(
-        eval("str::regex_matches(\"gazonk\", \"[ob]{3}\")"),
-        Ok(Value::Boolean(false))
+        eval("true implies 1"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
     );
-    match eval("str::regex_matches(\"foo\", \"[\")") {
-        Err(EvalexprError::InvalidRegex { regex, message }) => {
-            assert_eq!(regex, "[");
+
+    // The right operand of `implies` is not evaluated if the left one is false.
+    assert_eval_eq!("false implies unknown", true);
+    assert_eval_eq!("false implies 1 + true", true);
+    assert_eval_err!(
+        "true implies unknown",
+        EvalexprError::VariableIdentifierNotFound { .. }
+    );
+
+    // The keywords cannot be used as variables.
+    assert_eq!(
+        build_operator_tree("xor"),
+        Err(EvalexprError::KeywordAsValue {
+            keyword: "xor".to_string(),
+            span: 0..3,
+        })
+    );
+    assert_eq!(
+        build_operator_tree("a = implies + 1"),
+        Err(EvalexprError::KeywordAsValue {
+            keyword: "implies".to_string(),
+            span: 4..11,
+        })
+    );
+    assert_eq!(
+        build_operator_tree("f(xor)").unwrap_err().to_string(),
+        "The keyword 'xor' at offset 2 is an operator and cannot be used as a variable."
+    );
+    assert_eq!(
+        build_operator_tree("implies = true"),
+        Err(EvalexprError::AssignmentToKeyword {
+            keyword: "implies".to_string(),
+            span: 0..7,
+        })
+    );
+    assert_eq!(
+        eval_with_context_mut(
+            "implied = true; implied implies false",
+            &mut HashMapContext::new()
+        ),
+        Ok(Value::from(false))
+    );
+
+    assert_eq!(
+        build_operator_tree("a implies (b implies c) && d xor e")
+            .unwrap()
+            .to_minified_string(),
+        "a implies (b implies c)&&d xor e"
+    );
+    assert_eq!(
+        build_operator_tree("(a implies b) implies c")
+            .unwrap()
+            .to_minified_string(),
+        "(a implies b) implies c"
+    );
+}
+
+#[test]
+fn test_conditional_operator() {
+    let context = context_map! {
+        "qty" => 150,
+        "price" => 10.0,
+        "a" => true,
+        "b" => false
+    }
+    .unwrap();
+
+    assert_eval_eq!("qty > 100 ? price * 0.9 : price", 9.0, &context);
+    assert_eval_eq!("qty > 200 ? price * 0.9 : price", 10.0, &context);
+    assert_eval_eq!("a ? 1 : \"one\"", 1, &context);
+    assert_eval_eq!("b ? 1 : \"one\"", "one", &context);
+    assert_eval_eq!("1 + (a ? 2 : 3) * 4", 9, &context);
+    assert_eval_eq!("a || b ? 1 : 2", 1, &context);
+    assert_eval_eq!(
+        "a ? 1, 2 : 3",
+        vec![Value::from(1), Value::from(2)],
+        &context
+    );
+    assert_eval_eq!("a ? -1 : -2", -1, &context);
+
+    // Only the selected branch is evaluated.
+    assert_eval_eq!("true ? 1 : missing", 1);
+    assert_eval_eq!("false ? missing : 2", 2);
+    assert_eval_eq!("qty > 0 ? qty : qty / 0", 150, &context);
+    assert_eval_eq!("qty == 0 ? 0 : 300 / qty", 2, &context);
+    assert_eval_err!(
+        "false ? 1 : missing",
+        EvalexprError::VariableIdentifierNotFound { .. }
+    );
+    let mut assignments = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut("x = 1; true ? y = 2 : (x = 3); (x, y)", &mut assignments),
+        Ok(Value::from(vec![Value::from(1), Value::from(2)]))
+    );
+
+    // The condition must be a boolean.
+    assert_eq!(
+        eval("1 ? 2 : 3"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
+    );
+    assert_eq!(
+        eval("\"yes\" ? missing : missing"),
+        Err(EvalexprError::expected_boolean(Value::from("yes")))
+    );
+
+    // Conditionals are right associative.
+    assert_eval_eq!("true ? 1 : false ? 2 : 3", 1);
+    assert_eval_eq!("false ? 1 : true ? 2 : 3", 2);
+    assert_eval_eq!("false ? 1 : false ? 2 : 3", 3);
+    assert_eval_eq!("true ? false ? 1 : 2 : 3", 2);
+    assert_eval_eq!("(false ? true : false) ? 1 : 2", 2);
+    assert_eq!(
+        build_operator_tree("a ? b : c ? d : e")
+            .unwrap()
+            .to_debug_tree(),
+        "Root\n  Conditional\n    Var(a)\n    Root\n      Var(b)\n    Conditional\n      Var(c)\n      Root\n        Var(d)\n      Var(e)"
+    );
+
+    // The question mark and the colon must match.
+    assert_eq!(
+        build_operator_tree("a ? b"),
+        Err(EvalexprError::UnmatchedQuestionMark { span: 2..3 })
+    );
+    assert_eq!(
+        build_operator_tree("a : b"),
+        Err(EvalexprError::UnmatchedColon { span: 2..3 })
+    );
+    assert_eq!(
+        build_operator_tree("(a ? b) : c"),
+        Err(EvalexprError::UnmatchedQuestionMark { span: 3..4 })
+    );
+    assert_eq!(
+        build_operator_tree("a ? (b : c)"),
+        Err(EvalexprError::UnmatchedColon { span: 7..8 })
+    );
+    assert_eq!(
+        build_operator_tree("a ? b : c : d"),
+        Err(EvalexprError::UnmatchedColon { span: 10..11 })
+    );
+    assert_eq!(
+        build_operator_tree("a ? 1, : 2"),
+        Err(EvalexprError::TrailingComma { span: 5..6 })
+    );
+    // Paths of builtin functions are not affected.
+    #[cfg(feature = "builtin_string")]
+    assert_eval_eq!("a ? str::to_uppercase(\"x\") : \"y\"", "X", &context);
+
+    assert_eq!(
+        build_operator_tree("(a ? b : c) ? d : (e ? f : g)")
+            .unwrap()
+            .to_minified_string(),
+        "(a?b:c)?d:e?f:g"
+    );
+    assert_eq!(
+        build_operator_tree("a ? (b ? c : d) : (e, f)")
+            .unwrap()
+            .to_minified_string(),
+        "a?b?c:d:(e,f)"
+    );
+    assert_eq!(
+        build_operator_tree("x = (a ? 1 : 2) + 3")
+            .unwrap()
+            .to_minified_string(),
+        "x=(a?1:2)+3"
+    );
+}
+
+#[test]
+fn test_coalescing_operator() {
+    let context = context_map! {
+        "discount" => 10,
+        "zero" => 0,
+        "name" => "x",
+        "fail" => Function::new(Box::new(|_| Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "inner".into(),
+            span: None,
+        })))
+    }
+    .unwrap();
+
+    assert_eval_eq!("discount ?? 0", 10, &context);
+    assert_eval_eq!("bonus ?? 0", 0, &context);
+    assert_eval_eq!("bonus ?? \"none\"", "none", &context);
+    assert_eval_eq!("discount ?? 0 > 5", true, &context);
+    assert_eval_eq!("bonus ?? 1 + 2", 3, &context);
+    assert_eval_eq!("(bonus) ?? discount * 2", 20, &context);
+
+    // Chains are right associative, so they try each variable from left to right.
+    assert_eval_eq!("bonus ?? penalty ?? 3", 3, &context);
+    assert_eval_eq!("bonus ?? discount ?? 3", 10, &context);
+    assert_eval_eq!("discount ?? bonus ?? 3", 10, &context);
+    assert_eval_err!(
+        "bonus ?? penalty",
+        EvalexprError::VariableIdentifierNotFound { .. },
+        &context
+    );
+    assert_eq!(
+        build_operator_tree("a ?? b ?? c").unwrap().to_debug_tree(),
+        "Root\n  Coalesce\n    Var(a)\n    Coalesce\n      Var(b)\n      Var(c)"
+    );
+    assert_eval_err!(
+        "(bonus ?? penalty) ?? 3",
+        EvalexprError::VariableIdentifierNotFound { .. },
+        &context
+    );
+
+    // The right-hand side is only evaluated if it is needed.
+    let mut assignments = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut("a = 1; a ?? (b = 2); b ?? 0", &mut assignments),
+        Ok(Value::from(0))
+    );
+    assert_eq!(
+        eval_with_context_mut("c ?? (b = 2); b", &mut assignments),
+        Ok(Value::from(2))
+    );
+    assert_eval_eq!("discount ?? 1 / zero", 10, &context);
+
+    // Other errors are propagated unchanged.
+    assert_eq!(
+        eval_with_context("discount / zero ?? 0", &context),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(10),
+            divisor: Value::from(0),
+        })
+    );
+    assert_eval_err!(
+        "name + 1 ?? 0",
+        EvalexprError::AdditionError { .. },
+        &context
+    );
+    assert_eval_err!(
+        "bonus ?? 1 / zero",
+        EvalexprError::DivisionError { .. },
+        &context
+    );
+    // Errors from within the left-hand side are not caught, even if a variable is not found there.
+    assert_eval_err!(
+        "fail() ?? 0",
+        EvalexprError::InFunctionCall { .. },
+        &context
+    );
+    assert_eval_err!(
+        "bonus() ?? 0",
+        EvalexprError::FunctionIdentifierNotFound(_),
+        &context
+    );
+    let missing_bonus = |span| {
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "bonus".to_string(),
+            span: Some(span),
+        })
+    };
+    assert_eq!(
+        eval_with_context("len(bonus) ?? 0", &context),
+        missing_bonus(4..9)
+    );
+    assert_eq!(
+        eval_with_context("(bonus + 1) ?? discount * 2", &context),
+        missing_bonus(1..6)
+    );
+    assert_eq!(
+        eval_with_context("(discount, bonus) ?? ()", &context),
+        missing_bonus(11..16)
+    );
+
+    // Compacted trees and explanations recover in the same way.
+    for expression in &["(bonus) ?? discount", "(bonus * 2) ?? discount"] {
+        let tree = build_operator_tree(expression).unwrap();
+        let expected = tree.eval_with_context(&context);
+        assert_eq!(tree.compact().eval_with_context(&context), expected);
+        let explanation = tree.explain_with_context(&context).unwrap();
+        assert_eq!(explanation.result, Some(expected));
+    }
+    assert_eval_eq!("(bonus) ?? discount", 10, &context);
+
+    assert_eq!(
+        build_operator_tree("(a ?? b) ?? (c ?? d) + 1")
+            .unwrap()
+            .to_minified_string(),
+        "(a??b)??(c??d)+1"
+    );
+    assert_eq!(
+        build_operator_tree("a ?? (b ?? c)")
+            .unwrap()
+            .to_minified_string(),
+        "a??b??c"
+    );
+    assert_eval_eq!("bonus??discount", 10, &context);
+    // Two separate question marks are not a coalescing operator.
+    assert_eq!(
+        build_operator_tree("a ? ? b"),
+        Err(EvalexprError::UnmatchedQuestionMark { span: 4..5 })
+    );
+}
+
+#[test]
+fn test_if_expressions() {
+    let context = context_map! {
+        "temperature" => 35,
+        "a" => true,
+        "b" => false
+    }
+    .unwrap();
+
+    assert_eval_eq!(
+        "if temperature > 30 { \"hot\" } else { \"cold\" }",
+        "hot",
+        &context
+    );
+    assert_eval_eq!(
+        "if temperature > 40 { \"hot\" } else { \"cold\" }",
+        "cold",
+        &context
+    );
+    assert_eval_eq!("if (a) { 1 } else { 2 }", 1, &context);
+    assert_eval_eq!("1 + if b { 2 } else { 3 } * 2", 7, &context);
+    assert_eval_eq!(
+        "(if a { 1 } else { 2 }, 3)",
+        vec![Value::from(1), Value::from(3)],
+        &context
+    );
+
+    // An `if` without `else` evaluates to an empty value if its condition is false.
+    assert_eval_eq!("if a { 1 }", 1, &context);
+    assert_eq!(eval_with_context("if b { 1 }", &context), Ok(Value::Empty));
+    assert_eq!(eval("if true {}"), Ok(Value::Empty));
+
+    // Branches can be chained with `else if`.
+    let grade = "if temperature < 0 { \"freezing\" } else if temperature < 20 { \"mild\" } else if temperature < 30 { \"warm\" } else { \"hot\" }";
+    assert_eval_eq!(grade, "hot", &context);
+    for (temperature, expected) in &[(-5, "freezing"), (10, "mild"), (25, "warm")] {
+        assert_eval_eq!(
+            grade,
+            *expected,
+            &context_map! { "temperature" => *temperature }.unwrap()
+        );
+    }
+    assert_eq!(
+        eval_with_context("if b { 1 } else if b { 2 }", &context),
+        Ok(Value::Empty)
+    );
+
+    // Blocks can be nested and contain any expression.
+    assert_eval_eq!(
+        "if a { if b { 1 } else { 2 } } else { if b { 3 } else { 4 } }",
+        2,
+        &context
+    );
+    let mut assignments = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut(
+            "x = 1; y = if x > 0 { x += 1; if x > 1 { x * 10 } } else { 0 }; (x, y)",
+            &mut assignments
+        ),
+        Ok(Value::from(vec![Value::from(2), Value::from(20)]))
+    );
+
+    // Only the taken branch is evaluated.
+    assert_eval_eq!("if true { 1 } else { missing }", 1);
+    assert_eval_eq!("if false { 1 / 0 } else if true { 2 } else { missing }", 2);
+    assert_eq!(
+        eval("if 1 { 2 }"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
+    );
+
+    // `if` and `else` are keywords, so they cannot be used as identifiers anymore.
+    assert_eq!(
+        build_operator_tree("if"),
+        Err(EvalexprError::InvalidIfExpression { span: 0..2 })
+    );
+    assert_eq!(
+        build_operator_tree("if + 1"),
+        Err(EvalexprError::InvalidIfExpression { span: 0..2 })
+    );
+    assert_eq!(
+        build_operator_tree("else"),
+        Err(EvalexprError::InvalidIfExpression { span: 0..4 })
+    );
+    assert_eq!(
+        build_operator_tree("if = 1"),
+        Err(EvalexprError::AssignmentToKeyword {
+            keyword: "if".to_string(),
+            span: 0..2,
+        })
+    );
+    // Calls of a function named `if` are still possible.
+    let functions = HashMapContext::new().with_fn("if", |condition: bool, value: IntType| {
+        Ok(if condition { value } else { 0 })
+    });
+    assert_eval_eq!("if(true, 3)", 3, &functions);
+    assert_eval_eq!("if(false, 3) + if (true) { 1 }", 1, &functions);
+
+    // Malformed if-expressions are errors of parsing.
+    assert_eq!(
+        build_operator_tree("if { 1 }"),
+        Err(EvalexprError::InvalidIfExpression { span: 0..2 })
+    );
+    assert_eq!(
+        build_operator_tree("if a { 1 } else 2"),
+        Err(EvalexprError::InvalidIfExpression { span: 11..15 })
+    );
+    assert_eq!(
+        build_operator_tree("if a { 1 } else { 2 } else { 3 }"),
+        Err(EvalexprError::InvalidIfExpression { span: 22..26 })
+    );
+    assert_eq!(
+        build_operator_tree("1 + { 2 }"),
+        Err(EvalexprError::InvalidIfExpression { span: 4..5 })
+    );
+    assert_eq!(
+        build_operator_tree("if a { 1"),
+        Err(EvalexprError::UnmatchedLCurlyBrace { span: 5..6 })
+    );
+    assert_eq!(
+        build_operator_tree("if a { (1 }"),
+        Err(EvalexprError::UnmatchedLBrace { span: 7..8 })
+    );
+    assert_eq!(
+        build_operator_tree("(if a { 1 )"),
+        Err(EvalexprError::UnmatchedLCurlyBrace { span: 6..7 })
+    );
+    assert_eq!(
+        build_operator_tree("1 }"),
+        Err(EvalexprError::UnmatchedRCurlyBrace { span: 2..3 })
+    );
+
+    assert_eq!(
+        build_operator_tree("if a { 1 } else if b { 2 } else { c = 3 }")
+            .unwrap()
+            .to_minified_string(),
+        "a?1:b?2:(c=3)"
+    );
+    assert_eq!(
+        build_operator_tree("if a { 1 } + 2")
+            .unwrap()
+            .to_minified_string(),
+        "(a?1:())+2"
+    );
+}
+
+#[test]
+fn test_shift_operators() {
+    let context = context_map! {
+        "flags" => 0b0101,
+        "f" => 1.5
+    }
+    .unwrap();
+
+    assert_eval_eq!("flags << 3", 0b0010_1000, &context);
+    assert_eval_eq!("flags >> 1", 0b0010, &context);
+    assert_eval_eq!("flags>>2", 1, &context);
+    assert_eval_eq!("1 << 0", 1);
+    assert_eval_eq!("1 << 62", 1 << 62);
+    assert_eval_eq!("1 << 63", IntType::MIN);
+    assert_eval_eq!("3 << 63", IntType::MIN);
+    // The right shift keeps the sign.
+    assert_eval_eq!("-16 >> 2", -4);
+    assert_eval_eq!("-1 >> 63", -1);
+
+    // Shifts bind weaker than arithmetic and stronger than comparisons and bitwise operators.
+    assert_eval_eq!("1 << 2 + 1", 8);
+    assert_eval_eq!("2 * 3 << 1", 12);
+    assert_eval_eq!("1 << 4 >> 2", 4);
+    assert_eval_eq!("1 << 3 > 7", true);
+    assert_eval_eq!("flags >> 2 == 1", true, &context);
+    assert_eval_eq!("1 << 2 | 1 << 0", 0b0101);
+    assert_eval_eq!("flags & 1 << 2", 0b0100, &context);
+
+    // The amount must be less than the amount of bits of an integer.
+    assert_eq!(
+        eval("1 << 64"),
+        Err(EvalexprError::ShiftAmountOutOfRange {
+            value: 1,
+            amount: 64,
+        })
+    );
+    assert_eq!(
+        eval("1 >> -1"),
+        Err(EvalexprError::ShiftAmountOutOfRange {
+            value: 1,
+            amount: -1,
+        })
+    );
+    assert_eq!(
+        eval("-1 >> 10000000000"),
+        Err(EvalexprError::ShiftAmountOutOfRange {
+            value: -1,
+            amount: 10000000000,
+        })
+    );
+    assert_eq!(
+        eval("1 << -1").unwrap_err().to_string(),
+        "Cannot shift 1 by -1 bits, expected an amount from 0 to 63"
+    );
+
+    // Only integers are accepted.
+    assert_eq!(
+        eval_with_context("f << 1", &context),
+        Err(EvalexprError::expected_int(Value::from(1.5)))
+    );
+    assert_eq!(
+        eval("1 >> 1.0"),
+        Err(EvalexprError::expected_int(Value::from(1.0)))
+    );
+    assert_eq!(
+        eval("\"a\" << 1"),
+        Err(EvalexprError::expected_int(Value::from("a")))
+    );
+
+    // Two angle brackets only form a shift if they are adjacent.
+    let texts = |expression| -> Vec<String> {
+        tokenize_lossless(expression)
+            .unwrap()
+            .iter()
+            .map(|token| token.text.to_string())
+            .collect()
+    };
+    assert_eq!(texts("a>>b"), ["a", ">>", "b"]);
+    assert_eq!(texts("a<<=b"), ["a", "<<", "=", "b"]);
+    assert_eq!(texts("a>>=b"), ["a", ">>", "=", "b"]);
+    assert_eq!(texts("a> >b"), ["a", ">", " ", ">", "b"]);
+    assert_eq!(texts("a>=b"), ["a", ">=", "b"]);
+    assert!(eval("1 > > 2").is_err());
+    assert!(eval("1 < < 2").is_err());
+    assert_eq!(
+        build_operator_tree("a  <<  (b >> 1) < c")
+            .unwrap()
+            .to_minified_string(),
+        "a<<(b>>1)<c"
+    );
+}
+
+#[test]
+fn test_range_operators() {
+    let ints = |ints: &[IntType]| {
+        Value::from(ints.iter().map(|&int| Value::from(int)).collect::<Vec<_>>())
+    };
+    let context = context_map! { "n" => 3, "f" => 1.5 }.unwrap();
+
+    assert_eval_eq!("1..5", ints(&[1, 2, 3, 4]));
+    assert_eval_eq!("1..=5", ints(&[1, 2, 3, 4, 5]));
+    assert_eval_eq!("-2..1", ints(&[-2, -1, 0]));
+    assert_eval_eq!("0 .. n", ints(&[0, 1, 2]), &context);
+    assert_eval_eq!("1..n + 1", ints(&[1, 2, 3]), &context);
+    assert_eval_eq!("(1..=n) == (1, 2, 3)", true, &context);
+    assert_eval_eq!("(0..2) + (5..=6)", ints(&[0, 1, 5, 6]));
+    assert_eval_eq!("0..1 << 2", ints(&[0, 1, 2, 3]));
+
+    // Reversed and empty ranges are empty tuples.
+    assert_eval_eq!("5..1", ints(&[]));
+    assert_eval_eq!("3..3", ints(&[]));
+    assert_eval_eq!("3..=3", ints(&[3]));
+    assert_eval_eq!("3..=2", ints(&[]));
+    assert_eval_eq!(
+        "9223372036854775806..=9223372036854775807",
+        ints(&[IntType::MAX - 1, IntType::MAX])
+    );
+
+    // Only integers are accepted as ends.
+    assert_eq!(
+        eval("1..2.5"),
+        Err(EvalexprError::expected_int(Value::from(2.5)))
+    );
+    assert_eq!(
+        eval_with_context("f..=n", &context),
+        Err(EvalexprError::expected_int(Value::from(1.5)))
+    );
+    assert_eq!(
+        eval("\"a\"..3"),
+        Err(EvalexprError::expected_int(Value::from("a")))
+    );
+    assert_eq!(
+        eval("1..2..3"),
+        Err(EvalexprError::expected_int(ints(&[1])))
+    );
+
+    // The length of ranges is limited before their elements are allocated.
+    assert_eq!(
+        eval("0..10000000000"),
+        Err(EvalexprError::RangeTooLong {
+            limit: EvalConfig::DEFAULT_MAX_RANGE_LENGTH,
+            length: 10000000000,
+        })
+    );
+    assert_eq!(
+        eval("0..1000000").map(|range| range.as_tuple().unwrap().len()),
+        Ok(1000000)
+    );
+    let state = EvalState::new(EvalConfig {
+        max_range_length: 3,
+        ..Default::default()
+    });
+    let tree = build_operator_tree("0..n").unwrap();
+    assert_eq!(
+        tree.eval_with_context_and_state(&context, &state),
+        Ok(ints(&[0, 1, 2]))
+    );
+    let tree = build_operator_tree("0..=n").unwrap();
+    assert_eq!(
+        tree.eval_with_context_and_state(&context, &state),
+        Err(EvalexprError::RangeTooLong {
+            limit: 3,
+            length: 4,
+        })
+    );
+    // Ranges also count against the maximum amount of bytes.
+    let state = EvalState::new(EvalConfig {
+        max_result_bytes: Some(64),
+        ..Default::default()
+    });
+    assert!(matches!(
+        tree.eval_with_context_and_state(&context, &state),
+        Err(EvalexprError::ValueTooLarge { .. })
+    ));
+
+    // Two dots end a literal, so they also split numbers and identifiers.
+    let texts = |expression| -> Vec<String> {
+        tokenize_lossless(expression)
+            .unwrap()
+            .iter()
+            .map(|token| token.text.to_string())
+            .collect()
+    };
+    assert_eq!(texts("1..5"), ["1", "..", "5"]);
+    assert_eq!(texts("a..=b"), ["a", "..=", "b"]);
+    assert_eq!(texts("1.5..2"), ["1.5", "..", "2"]);
+    assert_eq!(texts("1...5"), ["1", "..", ".5"]);
+
+    assert_eq!(
+        build_operator_tree("(a .. b) ..= (c + 1..d)")
+            .unwrap()
+            .to_minified_string(),
+        "a..b..=(c+1..d)"
+    );
+    assert_eq!(
+        build_operator_tree("1. ..=2").unwrap().to_minified_string(),
+        "1. ..=2"
+    );
+}
+
+#[test]
+fn test_boolean_examples() {
+    assert_eval_eq!("true && false", false);
+    assert_eval_eq!("true && false || true && true", true);
+    assert_eval_eq!("5 > 4 && 1 <= 1", true);
+    assert_eval_eq!("5.0 <= 4.9 || !(4 > 3.5)", false);
+}
+
+#[test]
+fn test_not_equal() {
+    assert_eval_eq!("1 != 2", true);
+    assert_eval_eq!("1 != 1", false);
+    assert_eval_eq!("1.5 != 1.5", false);
+    assert_eval_eq!("\"a\" != \"b\"", true);
+    assert_eval_eq!("\"a\" != \"a\"", false);
+    assert_eval_eq!("true != false", true);
+    assert_eval_eq!("(1, \"a\") != (1, \"a\")", false);
+    assert_eval_eq!("(1, \"a\") != (1, \"b\")", true);
+    assert_eval_eq!("() != ()", false);
+    // Values of different types are never equal, like with `==`.
+    assert_eval_eq!("1 != 1.0", true);
+    assert_eval_eq!("1 == 1.0", false);
+    assert_eval_eq!("1 != \"1\"", true);
+    assert_eval_eq!("true != 1", true);
+
+    // `!=` is not confused with `!` followed by `=`.
+    let context = context_map! { "a" => 1, "b" => 2 }.unwrap();
+    assert_eval_eq!("!(a != b)", false, &context);
+    assert_eval_eq!("!(a != a)", true, &context);
+    assert_eval_eq!("!true != false", false, &context);
+    assert_eval_eq!("a != b && !(b != 2)", true, &context);
+    assert_eval_eq!("a!=b", true, &context);
+}
+
+#[test]
+fn test_suspicious_comparison_chain() {
+    let context =
+        context_map! { "a" => 1, "b" => 1, "c" => true, "x" => 1, "y" => 2, "z" => 0 }.unwrap();
+
+    // A comparison of a comparison is rejected when parsing.
+    assert_eq!(
+        build_operator_tree("a == b == c"),
+        Err(EvalexprError::SuspiciousComparisonChain {
+            expression: "(a==b)==c".to_string()
+        })
+    );
+    assert_eq!(
+        eval_with_context("x < y > z", &context),
+        Err(EvalexprError::SuspiciousComparisonChain {
+            expression: "(x<y)>z".to_string()
+        })
+    );
+    assert_eq!(
+        eval_with_context("1 + (x < y != true)", &context),
+        Err(EvalexprError::SuspiciousComparisonChain {
+            expression: "(x<y)!=true".to_string()
+        })
+    );
+
+    // Explicit parentheses and logical operators are fine.
+    assert_eq!(
+        eval_with_context("(a == b) == c", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("a == (b == c)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval_with_context("x < y && y > z", &context),
+        Ok(Value::from(true))
+    );
+
+    // The check can be turned off, which parses chains from left to right.
+    let config = ParseConfig {
+        allow_comparison_chains: true,
+        ..ParseConfig::default()
+    };
+    let tree = build_operator_tree_with_config("a == b == c", &config)
+        .unwrap()
+        .tree;
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+    assert_eq!(tree.to_minified_string(), "(a==b)==c");
+}
+
+#[test]
+fn test_with_context() {
+    let mut context = HashMapContext::new();
+    context.set_value("tr", Value::Boolean(true)).unwrap();
+    context.set_value("fa", Value::Boolean(false)).unwrap();
+    context.set_value("five", Value::Int(5)).unwrap();
+    context.set_value("six", Value::Int(6)).unwrap();
+    context.set_value("half", Value::Float(0.5)).unwrap();
+    context.set_value("zero", Value::Int(0)).unwrap();
+
+    assert_eq!(eval_with_context("tr", &context), Ok(Value::Boolean(true)));
+    assert_eq!(eval_with_context("fa", &context), Ok(Value::Boolean(false)));
+    assert_eq!(
+        eval_with_context("tr && false", &context),
+        Ok(Value::Boolean(false))
+    );
+    assert_eq!(
+        eval_with_context("five + six", &context),
+        Ok(Value::Int(11))
+    );
+    assert_eq!(
+        eval_with_context("five * half", &context),
+        Ok(Value::Float(2.5))
+    );
+    assert_eq!(
+        eval_with_context("five < six && true", &context),
+        Ok(Value::Boolean(true))
+    );
+}
+
+#[test]
+fn test_functions() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "sub2".to_string(),
+            Function::new(Box::new(|argument| {
+                if let Value::Int(int) = argument {
+                    Ok(Value::Int(int - 2))
+                } else if let Value::Float(float) = argument {
+                    Ok(Value::Float(float - 2.0))
+                } else {
+                    Err(EvalexprError::expected_number(argument.clone()))
+                }
+            })),
+        )
+        .unwrap();
+    context
+        .set_value("five".to_string(), Value::Int(5))
+        .unwrap();
+
+    assert_eq!(eval_with_context("sub2 5", &context), Ok(Value::Int(3)));
+    assert_eq!(eval_with_context("sub2(5)", &context), Ok(Value::Int(3)));
+    assert_eq!(eval_with_context("sub2 five", &context), Ok(Value::Int(3)));
+    assert_eq!(eval_with_context("sub2(five)", &context), Ok(Value::Int(3)));
+    assert_eq!(
+        eval_with_context("sub2(3) + five", &context),
+        Ok(Value::Int(6))
+    );
+}
+
+#[test]
+fn test_function_call_syntax() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "identity".into(),
+            Function::new(Box::new(|argument| Ok(argument.clone()))),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "now".into(),
+            Function::new(Box::new(|argument| {
+                argument.as_empty()?;
+                Ok(Value::from(42))
+            })),
+        )
+        .unwrap();
+    let pair = Value::from(vec![Value::from(1), Value::from(2)]);
+
+    // The argument is a tuple for several arguments, the value itself for one argument, and empty for none.
+    assert_eq!(
+        eval_with_context("identity(1, 2)", &context),
+        Ok(pair.clone())
+    );
+    assert_eq!(
+        eval_with_context("identity(1)", &context),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        eval_with_context("identity((1, 2))", &context),
+        Ok(pair.clone())
+    );
+    assert_eq!(eval_with_context("identity()", &context), Ok(Value::Empty));
+    assert_eq!(eval_with_context("now()", &context), Ok(Value::from(42)));
+    assert_eq!(
+        eval_with_context("now( ) + 1", &context),
+        Ok(Value::from(43))
+    );
+
+    // Whitespace between the identifier and the opening brace is ignored.
+    for expression in &["identity (1, 2)", "identity\n(1, 2)", "identity\t( 1 , 2 )"] {
+        assert_eq!(eval_with_context(expression, &context), Ok(pair.clone()));
+        assert_eq!(
+            build_operator_tree(expression).unwrap(),
+            build_operator_tree("identity(1, 2)").unwrap(),
+            "{:?}",
+            expression
+        );
+    }
+    assert_eq!(
+        eval_with_context("now () * 2", &context),
+        Ok(Value::from(84))
+    );
+    let tree = build_operator_tree("now ()").unwrap();
+    assert_eq!(tree.to_debug_tree(), "Root\n  Fn(now)\n    Root");
+    assert_eq!(tree.to_minified_string(), "now()");
+
+    assert_eq!(
+        eval_with_context("unknown(1, 2)", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "unknown".to_string()
+        ))
+    );
+    assert_eq!(
+        eval_with_context("unknown ()", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "unknown".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_n_ary_functions() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "sub2".into(),
+            Function::new(Box::new(|argument| {
+                if let Value::Int(int) = argument {
+                    Ok(Value::Int(int - 2))
+                } else if let Value::Float(float) = argument {
+                    Ok(Value::Float(float - 2.0))
+                } else {
+                    Err(EvalexprError::expected_number(argument.clone()))
+                }
+            })),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "avg".into(),
+            Function::new(Box::new(|argument| {
+                let arguments = expect_tuple(argument)?;
+                expect_number(&arguments[0])?;
+                expect_number(&arguments[1])?;
+
+                if let (Value::Int(a), Value::Int(b)) = (&arguments[0], &arguments[1]) {
+                    Ok(Value::Int((a + b) / 2))
+                } else {
+                    Ok(Value::Float(
+                        (arguments[0].as_float()? + arguments[1].as_float()?) / 2.0,
+                    ))
+                }
+            })),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "muladd".into(),
+            Function::new(Box::new(|argument| {
+                let arguments = expect_tuple(argument)?;
+                expect_number(&arguments[0])?;
+                expect_number(&arguments[1])?;
+                expect_number(&arguments[2])?;
+
+                if let (Value::Int(a), Value::Int(b), Value::Int(c)) =
+                    (&arguments[0], &arguments[1], &arguments[2])
+                {
+                    Ok(Value::Int(a * b + c))
+                } else {
+                    Ok(Value::Float(
+                        arguments[0].as_float()? * arguments[1].as_float()?
+                            + arguments[2].as_float()?,
+                    ))
+                }
+            })),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "count".into(),
+            Function::new(Box::new(|arguments| match arguments {
+                Value::Tuple(tuple) => Ok(Value::from(tuple.len() as IntType)),
+                Value::Empty => Ok(Value::from(0)),
+                _ => Ok(Value::from(1)),
+            })),
+        )
+        .unwrap();
+    context
+        .set_value("five".to_string(), Value::Int(5))
+        .unwrap();
+    context
+        .set_function(
+            "function_four".into(),
+            Function::new(Box::new(|_| Ok(Value::Int(4)))),
+        )
+        .unwrap();
+
+    assert_eq!(eval_with_context("avg(7, 5)", &context), Ok(Value::Int(6)));
+    assert_eq!(
+        eval_with_context("avg(sub2 5, 5)", &context),
+        Ok(Value::Int(4))
+    );
+    assert_eq!(
+        eval_with_context("sub2(avg(3, 6))", &context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(
+        eval_with_context("sub2 avg(3, 6)", &context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(
+        eval_with_context("muladd(3, 6, -4)", &context),
+        Ok(Value::Int(14))
+    );
+    assert_eq!(eval_with_context("count()", &context), Ok(Value::Int(0)));
+    assert_eq!(
+        eval_with_context("count((1, 2, 3))", &context),
+        Ok(Value::Int(3))
+    );
+    assert_eq!(
+        eval_with_context("count(3, 5.5, 2)", &context),
+        Ok(Value::Int(3))
+    );
+    assert_eq!(eval_with_context("count 5", &context), Ok(Value::Int(1)));
+    assert_eq!(
+        eval_with_context("function_four()", &context),
+        Ok(Value::Int(4))
+    );
+}
+
+#[test]
+fn test_bound_functions() {
+    let distance = Function::new(Box::new(|argument| {
+        let arguments = expect_tuple(argument)?;
+        expect_function_argument_amount(arguments.len(), 4)?;
+        let dx = arguments[2].as_number()? - arguments[0].as_number()?;
+        let dy = arguments[3].as_number()? - arguments[1].as_number()?;
+        Ok(Value::Float((dx * dx + dy * dy).sqrt()))
+    }));
+
+    let mut context = HashMapContext::new();
+    context
+        .set_function("distance".into(), distance.clone())
+        .unwrap();
+    context
+        .set_function(
+            "distance_from_x".into(),
+            distance.clone().bind(vec![Value::Int(3)]),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "distance_from_hq".into(),
+            distance.bind(vec![Value::Int(3), Value::Int(4)]),
+        )
+        .unwrap();
+
+    assert_eval_eq!("distance(0, 0, 3, 4)", 5.0, &context);
+    assert_eval_eq!("distance_from_x(0, 6, 4)", 5.0, &context);
+    assert_eval_eq!("distance_from_hq(0, 0)", 5.0, &context);
+    assert_eval_eq!("distance_from_hq(6, 8)", 5.0, &context);
+
+    assert_eval_err!(
+        "distance_from_hq(1, 2, 3)",
+        EvalexprError::InFunctionCall { function, source, .. }
+            if function == "distance_from_hq"
+                && **source
+                    == EvalexprError::WrongFunctionArgumentAmount {
+                        expected: 2,
+                        actual: 3
+                    },
+        &context
+    );
+    assert_eval_err!(
+        "distance_from_x(1, 2)",
+        EvalexprError::InFunctionCall { function, source, .. }
+            if function == "distance_from_x"
+                && **source
+                    == EvalexprError::WrongFunctionArgumentAmount {
+                        expected: 3,
+                        actual: 2
+                    },
+        &context
+    );
+    // Binding does not affect the original function.
+    assert_eval_err!(
+        "distance(1, 2)",
+        EvalexprError::InFunctionCall { function, source, .. }
+            if function == "distance"
+                && **source
+                    == EvalexprError::WrongFunctionArgumentAmount {
+                        expected: 4,
+                        actual: 2
+                    },
+        &context
+    );
+
+    // A single remaining argument is passed as is.
+    let negate = Function::new(Box::new(|argument| Ok(Value::Int(-argument.as_int()?))));
+    context
+        .set_function("minus_five".into(), negate.bind(vec![Value::Int(5)]))
+        .unwrap();
+    assert_eval_eq!("minus_five()", -5, &context);
+}
+
+#[test]
+fn test_typed_functions() {
+    let context = context_map! {
+        "offset" => 10,
+        "answer" => fn || 42,
+        "double" => fn |x: IntType| x * 2,
+        "repeat" => fn |text: String, times: IntType| text.repeat(times as usize),
+        "between" => fn |x: FloatType, min: FloatType, max: FloatType| min <= x && x <= max,
+        "count" => fn |elements: TupleType| elements.len() as IntType
+    }
+    .unwrap();
+
+    assert_eq!(eval_with_context("answer()", &context), Ok(Value::from(42)));
+    assert_eq!(
+        eval_with_context("double(offset) + 1", &context),
+        Ok(Value::from(21))
+    );
+    assert_eq!(
+        eval_with_context("repeat(\"ab\", 3)", &context),
+        Ok(Value::from("ababab"))
+    );
+    assert_eq!(
+        eval_with_context("between(2, 1.5, offset)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("count(1, 2, 3)", &context),
+        Ok(Value::from(3))
+    );
+
+    // Wrong argument types and amounts fail with the usual errors.
+    let function_error = |function: &str, argument: Value, source: EvalexprError| {
+        Err(EvalexprError::InFunctionCall {
+            function: function.to_string(),
+            argument,
+            source: Box::new(source),
+        })
+    };
+    assert_eq!(
+        eval_with_context("double(1.5)", &context),
+        function_error(
+            "double",
+            Value::from(1.5),
+            EvalexprError::expected_int(Value::from(1.5))
+        )
+    );
+    assert_eq!(
+        eval_with_context("repeat(3, \"ab\")", &context),
+        function_error(
+            "repeat",
+            Value::from(vec![Value::from(3), Value::from("ab")]),
+            EvalexprError::expected_string(Value::from(3))
+        )
+    );
+    assert_eq!(
+        eval_with_context("between(true, 1, 2)", &context),
+        function_error(
+            "between",
+            Value::from(vec![Value::from(true), Value::from(1), Value::from(2)]),
+            EvalexprError::expected_number(Value::from(true))
+        )
+    );
+    assert_eq!(
+        eval_with_context("repeat(\"ab\")", &context),
+        function_error(
+            "repeat",
+            Value::from("ab"),
+            EvalexprError::WrongFunctionArgumentAmount {
+                expected: 2,
+                actual: 1
+            }
+        )
+    );
+    assert_eq!(
+        eval_with_context("answer(1)", &context),
+        function_error(
+            "answer",
+            Value::from(1),
+            EvalexprError::WrongFunctionArgumentAmount {
+                expected: 0,
+                actual: 1
+            }
+        )
+    );
+
+    // The builder accepts closures that may fail.
+    let context = HashMapContext::new()
+        .with_fn("half", |x: IntType| {
+            if x % 2 == 0 {
+                Ok(x / 2)
+            } else {
+                Err(EvalexprError::CustomMessage(format!("{} is odd", x)))
+            }
+        })
+        .with_fn("join", |a: String, b: String| Ok(a + &b))
+        .with_fn("select", |condition: bool, a: Value, b: Value| {
+            Ok(if condition { a } else { b })
+        });
+    assert_eq!(eval_with_context("half(8)", &context), Ok(Value::from(4)));
+    assert_eq!(
+        eval_with_context("half(7)", &context),
+        function_error(
+            "half",
+            Value::from(7),
+            EvalexprError::CustomMessage("7 is odd".to_string())
+        )
+    );
+    assert_eq!(
+        eval_with_context("join(\"a\", \"b\")", &context),
+        Ok(Value::from("ab"))
+    );
+    assert_eq!(
+        eval_with_context("select(false, 1, (2, 3))", &context),
+        Ok(Value::from(vec![Value::from(2), Value::from(3)]))
+    );
+}
+
+#[test]
+fn test_named_arguments() {
+    let round_to = Function::from_fn(|x: FloatType, places: IntType| {
+        let factor = 10f64.powi(places as i32);
+        Ok((x * factor).round() / factor)
+    })
+    .with_parameters(vec![
+        Parameter::required("x"),
+        Parameter::optional("places", Value::from(2)),
+    ])
+    .unwrap();
+    assert_eq!(
+        round_to.parameters().map(|parameters| parameters.len()),
+        Some(2)
+    );
+    let mut context = HashMapContext::new();
+    context.set_function("round_to".into(), round_to).unwrap();
+    context
+        .set_function(
+            "identity".into(),
+            Function::new(Box::new(|argument| Ok(argument.clone()))),
+        )
+        .unwrap();
+    context
+        .set_value("xs", Value::from(vec![Value::from(1), Value::from(2)]))
+        .unwrap();
+
+    // Named arguments may be given in any order after the positional ones, and omitted parameters take their default.
+    assert_eq!(
+        eval_with_context("round_to(1.23456, places = 3)", &context),
+        Ok(Value::from(1.235))
+    );
+    assert_eq!(
+        eval_with_context("round_to(places = 1, x = 2.75)", &context),
+        Ok(Value::from(2.8))
+    );
+    assert_eq!(
+        eval_with_context("round_to(x = 1.23456)", &context),
+        Ok(Value::from(1.23))
+    );
+    assert_eq!(
+        eval_with_context("round_to(1.23456, 1)", &context),
+        Ok(Value::from(1.2))
+    );
+
+    let error = |expression: &str| eval_with_context(expression, &context).unwrap_err();
+    assert_eq!(
+        error("round_to(1.5, digits = 1)"),
+        EvalexprError::UnknownNamedArgument {
+            function: "round_to".to_string(),
+            argument: "digits".to_string(),
+        }
+    );
+    assert_eq!(
+        error("round_to(1.5, x = 2.5)"),
+        EvalexprError::DuplicateArgument {
+            function: "round_to".to_string(),
+            parameter: "x".to_string(),
+        }
+    );
+    assert_eq!(
+        error("round_to(1.5, places = 1, places = 2)"),
+        EvalexprError::DuplicateArgument {
+            function: "round_to".to_string(),
+            parameter: "places".to_string(),
+        }
+    );
+    // Names that are given twice are reported even if the function has no parameter of that name.
+    assert_eq!(
+        error("round_to(1.5, digits = 1, digits = 2)"),
+        EvalexprError::DuplicateArgument {
+            function: "round_to".to_string(),
+            parameter: "digits".to_string(),
+        }
+    );
+    assert_eq!(
+        error("round_to(places = 1)"),
+        EvalexprError::MissingArgument {
+            function: "round_to".to_string(),
+            parameter: "x".to_string(),
+        }
+    );
+    assert_eq!(
+        error("round_to(1.5, 1, places = 2)"),
+        EvalexprError::DuplicateArgument {
+            function: "round_to".to_string(),
+            parameter: "places".to_string(),
+        }
+    );
+    assert_eq!(
+        build_operator_tree("round_to(places = 1, 1.5)"),
+        Err(EvalexprError::PositionalArgumentAfterNamed { span: 21..24 })
+    );
+
+    // Functions without parameters, including builtin functions, have no names for their arguments.
+    assert_eq!(
+        error("identity(a = 1)"),
+        EvalexprError::UnknownNamedArgument {
+            function: "identity".to_string(),
+            argument: "a".to_string(),
+        }
+    );
+    #[cfg(feature = "builtin_math")]
+    assert_eq!(
+        error("min(a = 1)"),
+        EvalexprError::UnknownNamedArgument {
+            function: "min".to_string(),
+            argument: "a".to_string(),
+        }
+    );
+    assert_eq!(
+        eval_with_context("unknown(a = 1)", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "unknown".to_string()
+        ))
+    );
+
+    // Parenthesized and compound assignments are passed as values, and higher-order builtins keep assigning.
+    assert_eq!(
+        eval_with_context_mut("identity((a = 1)); a", &mut context),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        eval_with_context_mut("identity(a += 1); a", &mut context),
+        Ok(Value::from(2))
+    );
+    #[cfg(feature = "builtin_tuple")]
+    assert_eq!(
+        eval_with_context_mut(
+            "total = 0; map(xs, total = total + it); total",
+            &mut context
+        ),
+        Ok(Value::from(3))
+    );
+
+    assert_eq!(
+        Function::from_fn(|x: IntType, y: IntType| Ok(x + y))
+            .with_parameters(vec![Parameter::required("x"), Parameter::required("x")])
+            .err(),
+        Some(EvalexprError::DuplicateParameter("x".to_string()))
+    );
+}
+
+#[test]
+fn test_default_parameters() {
+    let format = Function::from_fn(|x: FloatType, places: IntType, unit: String| {
+        Ok(format!("{:.*}{}", places as usize, x, unit))
+    })
+    .with_parameters(vec![
+        Parameter::required("x"),
+        Parameter::optional("places", Value::from(2)),
+        Parameter::optional("unit", Value::from("")),
+    ])
+    .unwrap();
+    let mut context = HashMapContext::new();
+    context
+        .set_function("format".into(), format.clone())
+        .unwrap();
+    context
+        .set_function("format_kb".into(), format.bind(vec![Value::from(1.5)]))
+        .unwrap();
+
+    // The closure always receives all three arguments, with the defaults of the omitted ones.
+    assert_eq!(
+        eval_with_context("format(1.5, 3, \" kB\")", &context),
+        Ok(Value::from("1.500 kB"))
+    );
+    assert_eq!(
+        eval_with_context("format(1.5, 3)", &context),
+        Ok(Value::from("1.500"))
+    );
+    assert_eq!(
+        eval_with_context("format(1.5)", &context),
+        Ok(Value::from("1.50"))
+    );
+    assert_eq!(
+        eval_with_context("format(1.5, unit = \"%\")", &context),
+        Ok(Value::from("1.50%"))
+    );
+    assert_eq!(
+        eval_with_context("format_kb(1)", &context),
+        Ok(Value::from("1.5"))
+    );
+
+    // Arity errors name the optional parameters.
+    let wrong_amount = |argument: Value, minimum, maximum, actual| {
+        Err(EvalexprError::InFunctionCall {
+            function: "format".to_string(),
+            argument,
+            source: Box::new(EvalexprError::WrongFunctionArgumentRange {
+                minimum,
+                maximum,
+                actual,
+                optional: vec!["places".to_string(), "unit".to_string()],
+            }),
+        })
+    };
+    assert_eq!(
+        eval_with_context("format()", &context),
+        wrong_amount(Value::Empty, 1, 3, 0)
+    );
+    assert_eq!(
+        eval_with_context("format(1.5, 3, \"\", 4)", &context),
+        wrong_amount(
+            Value::from(vec![
+                Value::from(1.5),
+                Value::from(3),
+                Value::from(""),
+                Value::from(4)
+            ]),
+            1,
+            3,
+            4
+        )
+    );
+    assert_eq!(
+        eval_with_context("format_kb(1, \"\", 4)", &context),
+        Err(EvalexprError::InFunctionCall {
+            function: "format_kb".to_string(),
+            argument: Value::from(vec![Value::from(1), Value::from(""), Value::from(4)]),
+            source: Box::new(EvalexprError::WrongFunctionArgumentRange {
+                minimum: 0,
+                maximum: 2,
+                actual: 3,
+                optional: vec!["places".to_string(), "unit".to_string()],
+            }),
+        })
+    );
+    assert_eq!(
+        EvalexprError::WrongFunctionArgumentRange {
+            minimum: 1,
+            maximum: 3,
+            actual: 0,
+            optional: vec!["places".to_string(), "unit".to_string()],
+        }
+        .to_string(),
+        "A function expected 1 to 3 arguments, but got 0. The optional parameters are [\"places\", \"unit\"]."
+    );
+
+    // A function with a single optional parameter is called with its default if the call has no arguments.
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "greet".into(),
+            Function::from_fn(|name: String| Ok(format!("Hello, {}!", name)))
+                .with_parameters(vec![Parameter::optional("name", Value::from("world"))])
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context("greet()", &context),
+        Ok(Value::from("Hello, world!"))
+    );
+    assert_eq!(
+        eval_with_context("greet(\"you\")", &context),
+        Ok(Value::from("Hello, you!"))
+    );
+
+    // Only trailing parameters may have default values.
+    assert_eq!(
+        Function::from_fn(|x: IntType, y: IntType| Ok(x + y))
+            .with_parameters(vec![
+                Parameter::optional("x", Value::from(0)),
+                Parameter::required("y"),
+            ])
+            .err(),
+        Some(EvalexprError::NonTrailingDefault("x".to_string()))
+    );
+}
+
+#[test]
+fn test_builtin_function_identifiers() {
+    let identifiers = builtin_function_identifiers();
+    assert_eq!(identifiers.contains(&"min"), cfg!(feature = "builtin_math"));
+    assert_eq!(
+        identifiers.contains(&"len"),
+        cfg!(feature = "builtin_string")
+    );
+    assert_eq!(
+        identifiers.contains(&"unique"),
+        cfg!(feature = "builtin_tuple")
+    );
+    assert_eq!(
+        identifiers.contains(&"now_monotonic"),
+        cfg!(feature = "builtin_datetime")
+    );
+    assert_eq!(
+        identifiers.contains(&"str::regex_matches"),
+        cfg!(feature = "builtin_regex")
+    );
+    assert_eq!(
+        identifiers.contains(&"in_bbox"),
+        cfg!(feature = "geo_support")
+    );
+    assert_eq!(
+        identifiers.contains(&"str::sha256"),
+        cfg!(feature = "builtin_crypto")
+    );
+
+    for identifier in identifiers {
+        assert!(
+            !matches!(
+                eval(&format!("{}()", identifier)),
+                Err(EvalexprError::FunctionIdentifierNotFound(_))
+            ),
+            "{} is listed, but not found",
+            identifier
+        );
+    }
+}
+
+#[test]
+#[cfg(not(feature = "builtin_math"))]
+fn test_builtin_math_disabled() {
+    match eval("min(1, 2)") {
+        Err(error @ EvalexprError::FunctionIdentifierNotFound(_)) => assert_eq!(
+            error.to_string(),
+            "Function identifier is not bound to anything by context: \"min\". \
+             The builtin function with this identifier requires the feature flag \"builtin_math\"."
+        ),
+        result => panic!("Expected FunctionIdentifierNotFound, got {:?}", result),
+    }
+
+    // Functions in the context are not affected.
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "min".into(),
+            Function::new(Box::new(|_| Ok(Value::from(0)))),
+        )
+        .unwrap();
+    assert_eq!(eval_with_context("min(1, 2)", &context), Ok(Value::from(0)));
+    assert_eq!(
+        EvalexprError::FunctionIdentifierNotFound("unknown".to_string()).to_string(),
+        "Function identifier is not bound to anything by context: \"unknown\"."
+    );
+}
+
+#[test]
+#[cfg(all(feature = "builtin_math", feature = "builtin_string"))]
+fn test_builtin_functions() {
+    assert_eq!(eval("min(4.0, 3)"), Ok(Value::Int(3)));
+    assert_eq!(eval("max(4.0, 3)"), Ok(Value::Float(4.0)));
+    assert_eq!(eval("len(\"foobar\")"), Ok(Value::Int(6)));
+    assert_eq!(
+        eval("str::to_lowercase(\"FOOBAR\")"),
+        Ok(Value::from("foobar"))
+    );
+    assert_eq!(
+        eval("str::to_uppercase(\"foobar\")"),
+        Ok(Value::from("FOOBAR"))
+    );
+    assert_eq!(
+        eval("str::trim(\"  foo  bar \")"),
+        Ok(Value::from("foo  bar"))
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_math")]
+fn test_integer_math_functions() {
+    let context = context_map! {
+        "min" => IntType::MIN,
+        "max" => IntType::MAX,
+    }
+    .unwrap();
+    let overflow = |function: &str, arguments: Vec<Value>| {
+        Err(EvalexprError::IntegerOverflow {
+            function: function.to_string(),
+            arguments,
+        })
+    };
+
+    assert_eq!(eval("abs(-3)"), Ok(Value::from(3)));
+    assert_eq!(eval("abs(-2.5)"), Ok(Value::from(2.5)));
+    assert_eq!(
+        eval_with_context("abs(max)", &context),
+        Ok(Value::from(IntType::MAX))
+    );
+    assert_eq!(
+        eval_with_context("abs(min + 1)", &context),
+        Ok(Value::from(IntType::MAX))
+    );
+    assert_eq!(
+        eval_with_context("abs(min)", &context),
+        overflow("abs", vec![Value::from(IntType::MIN)])
+    );
+    assert_eq!(
+        eval("abs(\"a\")"),
+        Err(EvalexprError::expected_number(Value::from("a")))
+    );
+
+    assert_eq!(
+        eval_with_context("signum(min)", &context),
+        Ok(Value::from(-1))
+    );
+    assert_eq!(
+        eval_with_context("signum(max)", &context),
+        Ok(Value::from(1))
+    );
+    assert_eq!(eval("signum(0)"), Ok(Value::from(0)));
+    assert_eq!(eval("signum(-0.5)"), Ok(Value::from(-1.0)));
+    assert_eq!(eval("signum(0.0)"), Ok(Value::from(0.0)));
+    assert!(eval_float("signum(0.0 / 0.0)").unwrap().is_nan());
+
+    assert_eq!(eval("pow(2, 10)"), Ok(Value::from(1024)));
+    assert_eq!(eval("pow(2, 62)"), Ok(Value::from(1 << 62)));
+    assert_eq!(eval("pow(-2, 63)"), Ok(Value::from(IntType::MIN)));
+    assert_eq!(
+        eval("pow(2, 63)"),
+        overflow("pow", vec![Value::from(2), Value::from(63)])
+    );
+    assert_eq!(
+        eval_with_context("pow(max, 2)", &context),
+        overflow("pow", vec![Value::from(IntType::MAX), Value::from(2)])
+    );
+    assert_eq!(
+        eval_with_context("pow(max, 1)", &context),
+        Ok(Value::from(IntType::MAX))
+    );
+    assert_eq!(
+        eval_with_context("pow(min, 1)", &context),
+        Ok(Value::from(IntType::MIN))
+    );
+    assert_eq!(
+        eval_with_context("pow(min, 0)", &context),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        eval_with_context("pow(1, max)", &context),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        eval_with_context("pow(-1, max)", &context),
+        Ok(Value::from(-1))
+    );
+    assert_eq!(
+        eval_with_context("pow(-1, max - 1)", &context),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        eval_with_context("pow(0, max)", &context),
+        Ok(Value::from(0))
+    );
+    assert_eq!(
+        eval_with_context("pow(2, max)", &context),
+        overflow("pow", vec![Value::from(2), Value::from(IntType::MAX)])
+    );
+    assert_eq!(eval("pow(0, 0)"), Ok(Value::from(1)));
+
+    // Negative exponents and floats compute with floats.
+    assert_eq!(eval("pow(2, -1)"), Ok(Value::from(0.5)));
+    assert_eq!(eval("pow(0, -1)"), Ok(Value::from(FloatType::INFINITY)));
+    assert_eq!(
+        eval_with_context("pow(2, min)", &context),
+        Ok(Value::from(0.0))
+    );
+    assert_eq!(eval("pow(2.0, 63)"), Ok(Value::from(9.223372036854776e18)));
+    assert_eq!(eval("pow(4, 0.5)"), Ok(Value::from(2.0)));
+
+    assert_eq!(eval("sqrt(16)"), Ok(Value::from(4.0)));
+    assert_eq!(eval("sqrt(2.25)"), Ok(Value::from(1.5)));
+    assert_eq!(eval("sqrt(0)"), Ok(Value::from(0.0)));
+    assert!(eval_float("sqrt(-1)").unwrap().is_nan());
+    assert_eq!(eval("ln(1)"), Ok(Value::from(0.0)));
+    assert_eq!(eval("ln(2.718281828459045)"), Ok(Value::from(1.0)));
+    assert_eq!(eval("ln(0)"), Ok(Value::from(FloatType::NEG_INFINITY)));
+    assert!(eval_float("ln(-1)").unwrap().is_nan());
+    assert_eq!(
+        eval("sqrt(\"a\")"),
+        Err(EvalexprError::expected_number(Value::from("a")))
+    );
+    assert_eq!(
+        eval("pow(2, 3, 4)"),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 3
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_math")]
+fn test_bit_functions() {
+    let context = context_map! {
+        "status" => 0b1010_1000,
+        "min" => IntType::MIN,
+    }
+    .unwrap();
+    let out_of_range = |function: &str, index: IntType, expected: &str| {
+        Err(EvalexprError::ArgumentOutOfRange {
+            function: function.to_string(),
+            argument: Value::from(index),
+            expected: expected.to_string(),
+        })
+    };
+
+    assert_eq!(
+        eval_boolean_with_context("bit(status, 3) == 1 && bits(status, 4, 7) == 10", &context),
+        Ok(true)
+    );
+    assert_eq!(eval_int_with_context("bit(status, 0)", &context), Ok(0));
+    assert_eq!(eval_int("bits(0x1234, 4, 11)"), Ok(0x23));
+    assert_eq!(eval_int("bits(5, 1, 1)"), Ok(0));
+
+    // The sign bit is bit 63, and fields including it are extracted unsigned.
+    assert_eq!(eval_int_with_context("bit(min, 63)", &context), Ok(1));
+    assert_eq!(eval_int_with_context("bit(min, 62)", &context), Ok(0));
+    assert_eq!(eval_int("bit(-2, 0)"), Ok(0));
+    assert_eq!(eval_int("bit(-2, 63)"), Ok(1));
+    assert_eq!(eval_int("bits(-1, 32, 63)"), Ok(0xFFFF_FFFF));
+    assert_eq!(eval_int("bits(-1, 1, 63)"), Ok(IntType::MAX));
+    // Only the full width keeps the sign.
+    assert_eq!(eval_int("bits(-1, 0, 63)"), Ok(-1));
+    assert_eq!(
+        eval_int_with_context("bits(min, 0, 63)", &context),
+        Ok(IntType::MIN)
+    );
+    assert_eq!(eval_int("bits(-8, 0, 63)"), Ok(-8));
+
+    assert_eq!(eval_int("popcount(0)"), Ok(0));
+    assert_eq!(eval_int("popcount(0xFF)"), Ok(8));
+    assert_eq!(eval_int("popcount(-1)"), Ok(64));
+    assert_eq!(eval_int_with_context("popcount(min)", &context), Ok(1));
+    assert_eq!(eval_int("leading_zeros(1)"), Ok(63));
+    assert_eq!(eval_int("leading_zeros(0)"), Ok(64));
+    assert_eq!(eval_int("leading_zeros(-1)"), Ok(0));
+    assert_eq!(eval_int("trailing_zeros(8)"), Ok(3));
+    assert_eq!(eval_int("trailing_zeros(0)"), Ok(64));
+    assert_eq!(
+        eval_int_with_context("trailing_zeros(min)", &context),
+        Ok(63)
+    );
+
+    // Bit indices are validated.
+    assert_eq!(
+        eval("bit(1, 64)"),
+        out_of_range("bit", 64, "a bit index from 0 to 63")
+    );
+    assert_eq!(
+        eval("bit(1, -1)"),
+        out_of_range("bit", -1, "a bit index from 0 to 63")
+    );
+    assert_eq!(
+        eval("bits(1, 0, 64)"),
+        out_of_range("bits", 64, "a bit index from 0 to 63")
+    );
+    assert_eq!(
+        eval("bits(1, 64, 64)"),
+        out_of_range("bits", 64, "a bit index from 0 to 63")
+    );
+    // The high index must not be below the low index.
+    assert_eq!(
+        eval("bits(1, 4, 3)"),
+        out_of_range("bits", 3, "a bit index from 4 to 63")
+    );
+
+    // Only integers are accepted.
+    assert_eq!(
+        eval("popcount(1.0)"),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::from(1.0)
+        })
+    );
+    assert_eq!(
+        eval("bit(true, 0)"),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::from(true)
+        })
+    );
+    assert_eq!(
+        eval("bits(1, 0, 2.0)"),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::from(2.0)
+        })
+    );
+    assert_eq!(
+        eval("leading_zeros(\"1\")"),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::from("1")
+        })
+    );
+    assert_eq!(
+        eval("bit(1, 2, 3)"),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 3
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_string")]
+fn test_prefix_suffix_functions() {
+    let context = context_map! {
+        "url" => "https://example.com/api/v1/users",
+        "path" => "/var/log/app.log"
+    }
+    .unwrap();
+
+    assert_eval_eq!("str::starts_with(url, \"https://\")", true, &context);
+    assert_eval_eq!("str::starts_with(url, \"http://\")", false, &context);
+    assert_eval_eq!("str::ends_with(path, \".log\")", true, &context);
+    assert_eval_eq!("str::ends_with(path, \".txt\")", false, &context);
+    assert_eval_eq!(
+        "str::strip_prefix(url, \"https://example.com\")",
+        "/api/v1/users",
+        &context
+    );
+    assert_eval_eq!(
+        "str::strip_suffix(path, \".log\")",
+        "/var/log/app",
+        &context
+    );
+
+    // Strings without the prefix or suffix are returned unchanged.
+    assert_eval_eq!(
+        "str::strip_prefix(path, \"/usr\")",
+        "/var/log/app.log",
+        &context
+    );
+    assert_eval_eq!(
+        "str::strip_suffix(path, \".txt\")",
+        "/var/log/app.log",
+        &context
+    );
+    // Only a single occurrence is stripped.
+    assert_eval_eq!("str::strip_prefix(\"aaa\", \"a\")", "aa");
+    assert_eval_eq!("str::strip_suffix(\"aaa\", \"a\")", "aa");
+
+    // Matching is exact, without regex or case folding.
+    assert_eval_eq!("str::starts_with(\"a.b\", \"a?\")", false);
+    assert_eval_eq!("str::starts_with(\"ABC\", \"abc\")", false);
+    assert_eval_eq!("str::strip_prefix(\"ü-x\", \"ü-\")", "x");
+
+    // The empty string is a prefix and suffix of every string.
+    assert_eval_eq!("str::starts_with(\"abc\", \"\")", true);
+    assert_eval_eq!("str::ends_with(\"\", \"\")", true);
+    assert_eval_eq!("str::strip_prefix(\"abc\", \"\")", "abc");
+    assert_eval_eq!("str::strip_suffix(\"\", \"\")", "");
+    assert_eval_eq!("str::starts_with(\"\", \"a\")", false);
+    assert_eval_eq!("str::strip_suffix(\"\", \"a\")", "");
+
+    // A prefix that is longer than the string does not match.
+    assert_eval_eq!("str::ends_with(\"log\", \".log\")", false);
+    assert_eval_eq!("str::strip_prefix(\"ab\", \"abc\")", "ab");
+
+    for function in &[
+        "str::starts_with",
+        "str::ends_with",
+        "str::strip_prefix",
+        "str::strip_suffix",
+    ] {
+        assert_eq!(
+            eval(&format!("{}(\"abc\", 1)", function)),
+            Err(EvalexprError::expected_string(Value::from(1)))
+        );
+        assert_eq!(
+            eval(&format!("{}(true, \"a\")", function)),
+            Err(EvalexprError::expected_string(Value::from(true)))
+        );
+        assert_eq!(
+            eval(&format!("{}(\"abc\")", function)),
+            Err(EvalexprError::expected_tuple(Value::from("abc")))
+        );
+        assert_eq!(
+            eval(&format!("{}(\"abc\", \"a\", \"b\")", function)),
+            Err(EvalexprError::WrongFunctionArgumentAmount {
+                expected: 2,
+                actual: 3
+            })
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "builtin_string")]
+fn test_glob_functions() {
+    // The pattern has to match the whole text, not only a substring.
+    assert_eval_eq!("matches_glob(\"*.txt\", \"notes.txt\")", true);
+    assert_eval_eq!("matches_glob(\"*.txt\", \"notes.txt.bak\")", false);
+    assert_eval_eq!("matches_glob(\"notes\", \"notes.txt\")", false);
+    assert_eval_eq!("matches_glob(\"txt\", \"notes.txt\")", false);
+    assert_eval_eq!("matches_glob(\"*\", \"\")", true);
+    assert_eval_eq!("matches_glob(\"\", \"\")", true);
+    assert_eval_eq!("matches_glob(\"\", \"a\")", false);
+
+    // `?` matches exactly one character, and stars backtrack.
+    assert_eval_eq!("matches_glob(\"file?.log\", \"file1.log\")", true);
+    assert_eval_eq!("matches_glob(\"file?.log\", \"file.log\")", false);
+    assert_eval_eq!("matches_glob(\"file?.log\", \"file12.log\")", false);
+    assert_eval_eq!("matches_glob(\"a*b*c\", \"aXbYbZc\")", true);
+    assert_eval_eq!("matches_glob(\"a*b*c\", \"aXbYbZ\")", false);
+    assert_eval_eq!("matches_glob(\"**a**\", \"bab\")", true);
+
+    // Character classes with ranges and negation.
+    assert_eval_eq!("matches_glob(\"[a-c]x\", \"bx\")", true);
+    assert_eval_eq!("matches_glob(\"[a-c]x\", \"dx\")", false);
+    assert_eval_eq!("matches_glob(\"[!a-c]x\", \"dx\")", true);
+    assert_eval_eq!("matches_glob(\"[^a-c]x\", \"ax\")", false);
+    assert_eval_eq!("matches_glob(\"v[0-9][0-9]\", \"v42\")", true);
+    // A leading `]` and a trailing `-` are part of the class.
+    assert_eval_eq!("matches_glob(\"[]]\", \"]\")", true);
+    assert_eval_eq!("matches_glob(\"[a-]\", \"-\")", true);
+
+    // Text and patterns are matched by unicode characters, not bytes.
+    assert_eval_eq!("matches_glob(\"gr?n\", \"grün\")", true);
+    assert_eval_eq!("matches_glob(\"*ß\", \"Fuß\")", true);
+    assert_eval_eq!("matches_glob(\"[α-ω]*\", \"λambda\")", true);
+    assert_eval_eq!("matches_glob(\"??\", \"日本\")", true);
+
+    // A backslash escapes wildcards, and needs to be escaped itself in string literals.
+    let context = context_map! {
+        "escaped_star" => "100\\*",
+        "escaped_class" => "\\[a]",
+        "escaped_backslash" => "a\\\\b"
+    }
+    .unwrap();
+    assert_eval_eq!("matches_glob(escaped_star, \"100*\")", true, &context);
+    assert_eval_eq!("matches_glob(escaped_star, \"1000\")", false, &context);
+    assert_eval_eq!("matches_glob(\"100\\\\*\", \"100*\")", true);
+    assert_eval_eq!("matches_glob(escaped_class, \"[a]\")", true, &context);
+    assert_eval_eq!("matches_glob(escaped_class, \"a\")", false, &context);
+    assert_eval_eq!(
+        "matches_glob(escaped_backslash, \"a\\\\b\")",
+        true,
+        &context
+    );
+    assert_eval_eq!("matches_glob(\"[\\\\]]\", \"]\")", true);
+
+    // Matching is case-sensitive, unless the `_ci` variant is used.
+    assert_eval_eq!("matches_glob(\"*.TXT\", \"notes.txt\")", false);
+    assert_eval_eq!("matches_glob_ci(\"*.TXT\", \"notes.txt\")", true);
+    assert_eval_eq!("matches_glob_ci(\"[A-C]*\", \"bob\")", true);
+    assert_eval_eq!("matches_glob_ci(\"ÜBER*\", \"über-all\")", true);
+    assert_eval_eq!("matches_glob_ci(\"*.txt\", \"notes.md\")", false);
+    // The pattern is not lower-cased, so `[Z-a]` stays a valid range from `Z` to `a`, which contains `_`.
+    assert_eval_eq!("matches_glob(\"[Z-a]\", \"_\")", true);
+    assert_eval_eq!("matches_glob(\"[Z-a]\", \"z\")", false);
+    assert_eval_eq!("matches_glob_ci(\"[Z-a]\", \"_\")", true);
+    assert_eval_eq!("matches_glob_ci(\"[Z-a]\", \"z\")", true);
+    assert_eval_eq!("matches_glob_ci(\"[Z-a]\", \"A\")", true);
+    assert_eval_eq!("matches_glob_ci(\"[Z-a]\", \"b\")", false);
+    assert_eval_eq!("matches_glob_ci(\"[!a-c]\", \"B\")", false);
+    assert_eval_eq!("matches_glob_ci(\"STRASSE\", \"straße\")", false);
+
+    // Malformed patterns fail with a descriptive error.
+    assert_eq!(
+        eval("matches_glob(\"file[0-9\", \"file1\")"),
+        Err(EvalexprError::invalid_glob(
+            "file[0-9".to_string(),
+            "unterminated character class starting at character 4".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("matches_glob(\"[]\", \"]\")"),
+        Err(EvalexprError::invalid_glob(
+            "[]".to_string(),
+            "unterminated character class starting at character 0".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("matches_glob(\"[z-a]\", \"b\")"),
+        Err(EvalexprError::invalid_glob(
+            "[z-a]".to_string(),
+            "invalid range 'z-a' in character class".to_string()
+        ))
+    );
+    // Errors of the case-insensitive variant quote the pattern as written.
+    assert_eq!(
+        eval("matches_glob_ci(\"[Z-A]\", \"b\")"),
+        Err(EvalexprError::invalid_glob(
+            "[Z-A]".to_string(),
+            "invalid range 'Z-A' in character class".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("matches_glob_ci(\"a\\\\\", \"a\")"),
+        Err(EvalexprError::invalid_glob(
+            "a\\".to_string(),
+            "pattern ends with an unescaped '\\' at character 1".to_string()
+        ))
+    );
+    assert_eval_err!(
+        "matches_glob(\"*\", \"a\", \"b\")",
+        EvalexprError::WrongFunctionArgumentAmount { .. }
+    );
+    assert_eval_err!(
+        "matches_glob(1, \"a\")",
+        EvalexprError::ExpectedString { .. }
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_string")]
+fn test_code_point_functions() {
+    assert_eval_eq!("code_point(char_at(\"ABC\", 0)) == 65", true);
+    assert_eval_eq!("char_at(\"ABC\", 2)", "C");
+    assert_eval_eq!("from_code_point(97) + from_code_point(98)", "ab");
+
+    // Indices count characters, not bytes, also beyond the basic multilingual plane.
+    assert_eval_eq!("char_at(\"ü😀x\", 1)", "😀");
+    assert_eval_eq!("char_at(\"ü😀x\", 2)", "x");
+    assert_eval_eq!("code_point(\"😀\")", 0x1F600);
+    assert_eval_eq!("from_code_point(128512)", "😀");
+    assert_eval_eq!("code_point(from_code_point(66376))", 66376);
+
+    // Boundary code points.
+    assert_eval_eq!("code_point(from_code_point(0))", 0);
+    assert_eval_eq!("code_point(from_code_point(55295))", 0xD7FF);
+    assert_eval_eq!("code_point(from_code_point(57344))", 0xE000);
+    assert_eval_eq!("code_point(from_code_point(1114111))", 0x10FFFF);
+    for code_point in &[-1, 0xD800, 0xDFFF, 0x110000, IntType::MAX] {
+        assert_eq!(
+            eval(&format!("from_code_point({})", code_point)),
+            Err(EvalexprError::argument_out_of_range(
+                "from_code_point",
+                Value::Int(*code_point),
+                "a Unicode scalar value of at most 0x10FFFF that is not a surrogate from 0xD800 to 0xDFFF".to_string()
+            ))
+        );
+    }
+
+    // Indices out of range.
+    for index in &[3, -1, IntType::MAX] {
+        assert_eq!(
+            eval(&format!("char_at(\"ü😀x\", {})", index)),
+            Err(EvalexprError::argument_out_of_range(
+                "char_at",
+                Value::Int(*index),
+                "an index of at least 0 and less than the 3 characters of the string".to_string()
+            ))
+        );
+    }
+    assert_eval_err!("char_at(\"\", 0)", EvalexprError::ArgumentOutOfRange { .. });
+
+    // Only strings of exactly one character have a code point.
+    for subject in &["", "ab", "e\u{301}"] {
+        assert_eq!(
+            eval(&format!("code_point(\"{}\")", subject)),
+            Err(EvalexprError::argument_out_of_range(
+                "code_point",
+                Value::from(*subject),
+                "a string of exactly one character".to_string()
+            ))
+        );
+    }
+
+    assert_eval_err!("char_at(\"a\")", EvalexprError::ExpectedTuple { .. });
+    assert_eval_err!("char_at(\"a\", 0.0)", EvalexprError::ExpectedInt { .. });
+    assert_eval_err!("code_point(65)", EvalexprError::ExpectedString { .. });
+    assert_eval_err!("from_code_point(\"A\")", EvalexprError::ExpectedInt { .. });
+
+    let mut variable_types = std::collections::HashMap::new();
+    variable_types.insert("s".to_string(), ValueType::String);
+    variable_types.insert("i".to_string(), ValueType::Int);
+    assert_eq!(
+        build_operator_tree("code_point(char_at(s, i))")
+            .unwrap()
+            .check_types(&variable_types, &std::collections::HashMap::new(), false),
+        Ok(StaticType::Int)
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_string")]
+fn test_humanize_functions() {
+    // Sizes use binary units, with whole bytes below 1 KiB and one decimal place from there on.
+    let sizes: &[(&str, &str)] = &[
+        ("0", "0 B"),
+        ("1", "1 B"),
+        ("1023", "1023 B"),
+        ("1023.4", "1023 B"),
+        ("1023.6", "1.0 KiB"),
+        ("1024", "1.0 KiB"),
+        ("1536", "1.5 KiB"),
+        ("1048524", "1023.9 KiB"),
+        ("1048525", "1.0 MiB"),
+        ("1048576", "1.0 MiB"),
+        ("1610612736", "1.5 GiB"),
+        ("1099511627776", "1.0 TiB"),
+        ("1125899906842624", "1.0 PiB"),
+        ("1152921504606846976", "1.0 EiB"),
+        ("9223372036854775807", "8.0 EiB"),
+        ("1.0e22", "8673.6 EiB"),
+        ("-1536", "-1.5 KiB"),
+        ("-1", "-1 B"),
+        ("-0.4", "0 B"),
+        ("0.5", "1 B"),
+    ];
+    for (bytes, expected) in sizes {
+        assert_eq!(
+            eval_string(&format!("humanize_bytes({})", bytes)),
+            Ok(expected.to_string()),
+            "{}",
+            bytes
+        );
+    }
+
+    // Durations use the two largest units, and seconds below a minute with one decimal place.
+    let durations: &[(&str, &str)] = &[
+        ("0", "0s"),
+        ("0.04", "0s"),
+        ("0.05", "0.1s"),
+        ("1.5", "1.5s"),
+        ("45", "45s"),
+        ("59.9", "59.9s"),
+        ("59.96", "1m"),
+        ("60", "1m"),
+        ("90", "1m 30s"),
+        ("3599", "59m 59s"),
+        ("3600", "1h"),
+        ("3661", "1h 1m"),
+        ("86399", "23h 59m"),
+        ("86400", "1d"),
+        ("86401", "1d"),
+        ("273600", "3d 4h"),
+        ("273599", "3d 3h"),
+        ("273600.4", "3d 4h"),
+        ("-90", "-1m 30s"),
+        ("-1.5", "-1.5s"),
+        ("-0.01", "0s"),
+    ];
+    for (seconds, expected) in durations {
+        assert_eq!(
+            eval_string(&format!("humanize_duration({})", seconds)),
+            Ok(expected.to_string()),
+            "{}",
+            seconds
+        );
+    }
+
+    assert_eq!(eval_int("parse_bytes(\"1.5GiB\")"), Ok(1610612736));
+    assert_eq!(eval_int("parse_bytes(\" 1.5 GiB \")"), Ok(1610612736));
+    assert_eq!(eval_int("parse_bytes(\"512\")"), Ok(512));
+    assert_eq!(eval_int("parse_bytes(\"0.5 B\")"), Ok(1));
+    assert_eq!(eval_int("parse_bytes(\".5 KiB\")"), Ok(512));
+    assert_eq!(eval_int("parse_bytes(\"-2 KiB\")"), Ok(-2048));
+    assert_eq!(eval_int("parse_bytes(\"-8 EiB\")"), Ok(IntType::MIN));
+    assert_eq!(eval_int("parse_duration(\"3d4h\")"), Ok(273600));
+    assert_eq!(eval_int("parse_duration(\"3d 4h 5m 6s\")"), Ok(273906));
+    assert_eq!(eval_int("parse_duration(\"1m30s\")"), Ok(90));
+    assert_eq!(eval_int("parse_duration(\"90\")"), Ok(90));
+    assert_eq!(eval_int("parse_duration(\"1.5s\")"), Ok(2));
+    assert_eq!(eval_int("parse_duration(\"0.5m\")"), Ok(30));
+    assert_eq!(eval_int("parse_duration(\"1.5 d\")"), Ok(129600));
+    assert_eq!(eval_int("parse_duration(\"-1h 1m\")"), Ok(-3660));
+    assert_eq!(eval_int("parse_duration(\"0s\")"), Ok(0));
+
+    // The parsers read what the formatters write, up to the rounding of the formatters.
+    for bytes in &[0, 1, 1023, 1536, -1536, 1610612736, 3 << 40] {
+        let text = eval_string(&format!("humanize_bytes({})", bytes)).unwrap();
+        assert_eq!(
+            eval_int(&format!("parse_bytes(\"{}\")", text)),
+            Ok(*bytes),
+            "{}",
+            text
+        );
+    }
+    // 976.5625 KiB are written as 976.6 KiB.
+    assert_eq!(
+        eval_int("parse_bytes(humanize_bytes(1000000))"),
+        Ok(1000038)
+    );
+    for seconds in &[0, 1, 59, 60, 90, 3600, 3660, 86400, 273600, -273600] {
+        let text = eval_string(&format!("humanize_duration({})", seconds)).unwrap();
+        assert_eq!(
+            eval_int(&format!("parse_duration(\"{}\")", text)),
+            Ok(*seconds),
+            "{}",
+            text
+        );
+    }
+    assert_eq!(eval_int("parse_duration(humanize_duration(1.5))"), Ok(2));
+    assert_eq!(
+        eval_int("parse_duration(humanize_duration(3661))"),
+        Ok(3660)
+    );
+
+    let malformed_size = |text: &str| {
+        Err(EvalexprError::argument_out_of_range(
+            "parse_bytes",
+            Value::from(text),
+            "a size like \"1.5 GiB\" with one of the units B, KiB, MiB, GiB, TiB, PiB or EiB"
+                .to_string(),
+        ))
+    };
+    for text in &[
+        "", "-", "GiB", "1 GB", "1 gib", "1.5.0 B", "1 B B", "- -1 B",
+    ] {
+        assert_eq!(
+            eval(&format!("parse_bytes(\"{}\")", text)),
+            malformed_size(text),
+            "{}",
+            text
+        );
+    }
+    assert_eq!(
+        eval("parse_bytes(\"8 EiB\")"),
+        Err(EvalexprError::argument_out_of_range(
+            "parse_bytes",
+            Value::from("8 EiB"),
+            format!("a size of at most {} bytes", IntType::MAX)
+        ))
+    );
+    assert_eval_err!(
+        "parse_bytes(\"99999999999999999999999999999999999999999 B\")",
+        EvalexprError::ArgumentOutOfRange { .. }
+    );
+
+    let malformed_duration = |text: &str| {
+        Err(EvalexprError::argument_out_of_range(
+            "parse_duration",
+            Value::from(text),
+            "a duration like \"3d 4h\" with the units d, h, m and s in this order".to_string(),
+        ))
+    };
+    for text in &["", "d", "4h 3d", "1h 1h", "1x", "1d 2", "3 days"] {
+        assert_eq!(
+            eval(&format!("parse_duration(\"{}\")", text)),
+            malformed_duration(text),
+            "{}",
+            text
+        );
+    }
+    assert_eq!(
+        eval("parse_duration(\"106751991167301d\")"),
+        Err(EvalexprError::argument_out_of_range(
+            "parse_duration",
+            Value::from("106751991167301d"),
+            format!("a duration of at most {} seconds", IntType::MAX)
+        ))
+    );
+
+    // Formatting needs finite numbers, and parsing needs strings.
+    assert_eq!(
+        eval("humanize_bytes(1.0 / 0.0)"),
+        Err(EvalexprError::argument_out_of_range(
+            "humanize_bytes",
+            Value::from(FloatType::INFINITY),
+            "a finite number".to_string()
+        ))
+    );
+    assert_eval_err!(
+        "humanize_duration(0.0 / 0.0)",
+        EvalexprError::ArgumentOutOfRange { .. }
+    );
+    assert_eval_err!(
+        "humanize_bytes(\"1\")",
+        EvalexprError::ExpectedNumber { .. }
+    );
+    assert_eval_err!(
+        "humanize_duration(true)",
+        EvalexprError::ExpectedNumber { .. }
+    );
+    assert_eval_err!("parse_bytes(1)", EvalexprError::ExpectedString { .. });
+    assert_eval_err!("parse_duration(())", EvalexprError::ExpectedString { .. });
+}
+
+#[test]
+#[cfg(feature = "builtin_tuple")]
+fn test_range() {
+    fn ints(ints: &[IntType]) -> Value {
+        Value::Tuple(ints.iter().map(|int| Value::Int(*int)).collect())
+    }
+
+    assert_eq!(eval("range(4)"), Ok(ints(&[0, 1, 2, 3])));
+    assert_eq!(eval("range(2, 5)"), Ok(ints(&[2, 3, 4])));
+    assert_eq!(eval("range(0, 10, 3)"), Ok(ints(&[0, 3, 6, 9])));
+    assert_eq!(eval("range(-2, 2)"), Ok(ints(&[-2, -1, 0, 1])));
+    assert_eq!(eval("range(5, 0, -2)"), Ok(ints(&[5, 3, 1])));
+    assert_eq!(eval("range(0, -3, -1)"), Ok(ints(&[0, -1, -2])));
+
+    // Ranges that end before they start are empty.
+    assert_eq!(eval("range(0)"), Ok(ints(&[])));
+    assert_eq!(eval("range(-3)"), Ok(ints(&[])));
+    assert_eq!(eval("range(3, 3)"), Ok(ints(&[])));
+    assert_eq!(eval("range(5, 0)"), Ok(ints(&[])));
+    assert_eq!(eval("range(0, 5, -1)"), Ok(ints(&[])));
+
+    // The extreme integers do not overflow.
+    let extremes = context_map! {
+        "min" => IntType::MIN,
+        "max" => IntType::MAX
+    }
+    .unwrap();
+    assert_eq!(
+        eval_with_context("range(max - 1, max, max)", &extremes),
+        Ok(ints(&[IntType::MAX - 1]))
+    );
+    assert_eq!(
+        eval_with_context("range(max, min, min)", &extremes),
+        Ok(ints(&[IntType::MAX, -1]))
+    );
+
+    assert_eq!(eval("range(0, 5, 0)"), Err(EvalexprError::RangeStepZero));
+    assert_eq!(eval("range(5, 5, 0)"), Err(EvalexprError::RangeStepZero));
+    assert_eq!(
+        eval("range(1.5)"),
+        Err(EvalexprError::expected_int(Value::from(1.5)))
+    );
+    assert_eq!(
+        eval("range(0, \"5\")"),
+        Err(EvalexprError::expected_int(Value::from("5")))
+    );
+    assert_eq!(
+        eval("range()"),
+        Err(EvalexprError::expected_int(Value::Empty))
+    );
+    assert_eq!(
+        eval("range(0, 5, 1, 1)"),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 3,
+            actual: 4
+        })
+    );
+
+    // The length of a range is limited by the value-size budget before the range is allocated.
+    let state = EvalState::new(EvalConfig {
+        max_result_bytes: Some(100 * std::mem::size_of::<Value>()),
+        ..Default::default()
+    });
+    let tree = build_operator_tree("range(100)").unwrap();
+    assert_eq!(
+        tree.eval_with_context_and_state(&EmptyContext, &state),
+        eval("range(100)")
+    );
+    for expression in &[
+        "range(101)",
+        "range(0, 101)",
+        "range(0, 4611686018427387904)",
+        "range(100, -1, -1)",
+    ] {
+        let tree = build_operator_tree(expression).unwrap();
+        assert!(matches!(
+            tree.eval_with_context_and_state(&EmptyContext, &state),
+            Err(EvalexprError::ValueTooLarge { .. })
+        ));
+        // Only the tuple of arguments was allocated before the evaluation failed.
+        assert!(state.allocated_bytes() <= 3 * std::mem::size_of::<Value>());
+    }
+
+    // Without a budget, ranges that cannot be allocated at all fail as well.
+    assert!(matches!(
+        eval_with_context("range(min, max)", &extremes),
+        Err(EvalexprError::ValueTooLarge { .. })
+    ));
+}
+
+#[test]
+#[cfg(feature = "builtin_tuple")]
+fn test_implicit_it() {
+    let mut context = context_map! {
+        "xs" => Value::from(vec![Value::from(1), Value::from(2), Value::from(3)]),
+        "it" => "outer",
+        "offset" => 10,
+        "double" => fn |x: IntType| x * 2,
+    }
+    .unwrap();
+    let tuple = |values: &[IntType]| {
+        Value::from(values.iter().map(|&v| Value::from(v)).collect::<Vec<_>>())
+    };
+
+    assert_eq!(
+        eval_with_context("map(xs, it * 2)", &context),
+        Ok(tuple(&[2, 4, 6]))
+    );
+    assert_eq!(
+        eval_with_context("map(xs, it + offset)", &context),
+        Ok(tuple(&[11, 12, 13]))
+    );
+    assert_eq!(
+        eval_with_context("filter(xs, it % 2 == 1)", &context),
+        Ok(tuple(&[1, 3]))
+    );
+    assert_eq!(
+        eval_with_context("map(range(0), it)", &context),
+        Ok(tuple(&[]))
+    );
+
+    // Each `it` refers to the element of the nearest enclosing call.
+    assert_eq!(
+        eval_with_context("map(map(xs, it + 1), it * 2)", &context),
+        Ok(tuple(&[4, 6, 8]))
+    );
+    assert_eq!(
+        eval_with_context("map(xs, map(range(it), it * 10))", &context),
+        Ok(Value::from(vec![
+            tuple(&[0]),
+            tuple(&[0, 10]),
+            tuple(&[0, 10, 20]),
+        ]))
+    );
+
+    // Outside of the second argument, `it` is a normal variable.
+    assert_eq!(eval_with_context("it", &context), Ok(Value::from("outer")));
+    assert_eq!(
+        eval_with_context("map(xs, it), it", &context),
+        Ok(Value::from(vec![tuple(&[1, 2, 3]), Value::from("outer")]))
+    );
+    assert_eq!(
+        eval("it"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "it".to_string(),
+            span: Some(0..2),
+        })
+    );
+
+    // The identifier of a function is called with each element.
+    assert_eq!(
+        eval_with_context("map(xs, double)", &context),
+        Ok(tuple(&[2, 4, 6]))
+    );
+    #[cfg(feature = "builtin_string")]
+    assert_eq!(
+        eval("map((\"a\", \"b\"), str::to_uppercase)"),
+        Ok(Value::from(vec![Value::from("A"), Value::from("B")]))
+    );
+
+    // Such calls follow the configuration of the evaluation, like calls within the second argument.
+    #[cfg(feature = "builtin_math")]
+    {
+        let state = EvalState::new(EvalConfig {
+            nan_policy: NanPolicy::Error,
+            ..Default::default()
+        });
+        let nan_produced = Err(EvalexprError::NaNProduced {
+            operator: "sqrt".to_string(),
+            operands: vec![Value::from(-1)],
+        });
+        assert_eq!(
+            build_operator_tree("map((4, -1), sqrt)")
+                .unwrap()
+                .eval_with_context_and_state(&context, &state),
+            nan_produced
+        );
+        assert_eq!(
+            build_compact_tree("map((4, -1), sqrt)")
+                .unwrap()
+                .eval_with_context_and_state(&context, &state),
+            nan_produced
+        );
+        assert_eq!(
+            build_operator_tree("map((4, -1), sqrt(it))")
+                .unwrap()
+                .eval_with_context_and_state(&context, &state),
+            nan_produced
+        );
+    }
+    let state = EvalState::new(EvalConfig {
+        max_result_bytes: Some(1_000),
+        ..Default::default()
+    });
+    // The range is rejected before it is built, so the attempted size is the same as for a call within the second argument.
+    let result = build_operator_tree("map((1, 1000), range)")
+        .unwrap()
+        .eval_with_context_and_state(&context, &state);
+    assert!(
+        matches!(
+            result,
+            Err(EvalexprError::ValueTooLarge { limit: 1_000, .. })
+        ),
+        "{:?}",
+        result
+    );
+    assert_eq!(
+        result,
+        build_operator_tree("map((1, 1000), range(it))")
+            .unwrap()
+            .eval_with_context_and_state(&context, &state)
+    );
+
+    // Compact trees, mutable contexts and functions from the context behave the same.
+    assert_eq!(
+        build_compact_tree("map(map(xs, it + 1), it * 2)")
+            .unwrap()
+            .eval_with_context(&context),
+        Ok(tuple(&[4, 6, 8]))
+    );
+    assert_eq!(
+        eval_with_context_mut(
+            "total = 0; map(xs, total = total + it); total",
+            &mut context
+        ),
+        Ok(Value::from(6))
+    );
+    assert_eq!(
+        eval_with_context("map(xs, total = it)", &context),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+
+    assert_eq!(
+        eval_with_context("map(xs)", &context),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        eval("filter((1, 2), it)"),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
+    );
+    assert_eq!(
+        eval("map(1, it)"),
+        Err(EvalexprError::expected_tuple(Value::from(1)))
+    );
+
+    // `it` is neither an unknown nor an inferred variable within the second argument.
+    let node = build_operator_tree("map(xs, it * 2)").unwrap();
+    let mut variable_types = std::collections::HashMap::new();
+    variable_types.insert("xs".to_string(), ValueType::Tuple);
+    assert_eq!(
+        node.check_types(&variable_types, &std::collections::HashMap::new(), false),
+        Ok(StaticType::Tuple)
+    );
+    assert_eq!(node.infer_variable_types().len(), 1);
+    assert!(build_operator_tree("filter(xs, it * 2)")
+        .unwrap()
+        .check_types(&variable_types, &std::collections::HashMap::new(), false)
+        .is_err());
+
+    // Functions from the context take precedence.
+    context
+        .set_function(
+            "map".into(),
+            Function::new(Box::new(|_| Ok(Value::from("custom")))),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context("map(xs, 1)", &context),
+        Ok(Value::from("custom"))
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_tuple")]
+fn test_all_of_any_of() {
+    let context = context_map! {
+        "scores" => Value::from(vec![Value::from(0.6), Value::from(0.9), Value::from(1)]),
+        "names" => Value::from(vec![Value::from("ann"), Value::from("bob")]),
+        "nothing" => Value::from(TupleType::new())
+    }
+    .unwrap();
+
+    assert_eval_eq!("all_of(scores, \">\", 0.5)", true, &context);
+    assert_eval_eq!("all_of(scores, \">\", 0.7)", false, &context);
+    assert_eval_eq!("any_of(scores, \">\", 0.7)", true, &context);
+    assert_eval_eq!("any_of(scores, \">=\", 2)", false, &context);
+    assert_eval_eq!("any_of(scores, \"==\", 1)", true, &context);
+    assert_eval_eq!("all_of(scores, \"!=\", 0.9)", false, &context);
+    assert_eval_eq!("all_of(scores, \"<=\", 1)", true, &context);
+    assert_eval_eq!("any_of(names, \"<\", \"b\")", true, &context);
+    assert_eval_eq!("all_of(nothing, \"<\", 0)", true, &context);
+    assert_eval_eq!("any_of(nothing, \"<\", 0)", false, &context);
+
+    assert_eval_err!(
+        "all_of(scores, \"=>\", 0.5)",
+        EvalexprError::UnknownComparisonOperator(operator) if operator == "=>",
+        &context
+    );
+    assert_eval_err!(
+        "any_of(0.6, \">\", 0.5)",
+        EvalexprError::ExpectedTuple {
+            actual: Value::Float(_)
+        }
+    );
+    assert_eval_err!(
+        "all_of(names, \">\", 0.5)",
+        EvalexprError::ExpectedString {
+            actual: Value::Float(_)
+        },
+        &context
+    );
+    assert_eval_err!(
+        "any_of(scores, \">\", \"a\")",
+        EvalexprError::ExpectedNumber { .. },
+        &context
+    );
+    assert_eval_err!(
+        "any_of((true, false), \">\", 0)",
+        EvalexprError::ExpectedNumberOrString { .. }
+    );
+    assert_eval_err!(
+        "all_of(scores, \">\")",
+        EvalexprError::WrongFunctionArgumentAmount {
+            expected: 3,
+            actual: 2
+        },
+        &context
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_tuple")]
+fn test_set_functions() {
+    fn tuple(values: Vec<Value>) -> Value {
+        Value::from(values)
+    }
+
+    let context = context_map! {
+        "segment_a" => tuple(vec![Value::from(4), Value::from(8), Value::from(15), Value::from(8)]),
+        "segment_b" => tuple(vec![Value::from(16), Value::from(8.0), Value::from(4)]),
+        "nothing" => tuple(Vec::new())
+    }
+    .unwrap();
+
+    assert_eval_eq!(
+        "unique(segment_a)",
+        tuple(vec![Value::from(4), Value::from(8), Value::from(15)]),
+        &context
+    );
+    assert_eval_eq!(
+        "unique((1, 1.0, \"1\", true, 1, ()))",
+        tuple(vec![
+            Value::from(1),
+            Value::from("1"),
+            Value::from(true),
+            Value::Empty
+        ])
+    );
+    assert_eval_eq!("unique(nothing)", tuple(Vec::new()), &context);
+    assert_eval_eq!(
+        "intersect(segment_a, segment_b)",
+        tuple(vec![Value::from(4), Value::from(8)]),
+        &context
+    );
+    assert_eval_eq!("intersect(segment_a, nothing)", tuple(Vec::new()), &context);
+    assert_eval_eq!(
+        "union(segment_a, segment_b)",
+        tuple(vec![
+            Value::from(4),
+            Value::from(8),
+            Value::from(15),
+            Value::from(16)
+        ]),
+        &context
+    );
+    assert_eval_eq!(
+        "union((1, 2, 2), (2.0, 3))",
+        tuple(vec![Value::from(1), Value::from(2), Value::from(3)])
+    );
+    assert_eval_eq!(
+        "difference(segment_a, segment_b)",
+        tuple(vec![Value::from(15)]),
+        &context
+    );
+    assert_eval_eq!(
+        "difference(segment_b, segment_a)",
+        tuple(vec![Value::from(16)]),
+        &context
+    );
+    assert_eval_eq!(
+        "difference(nothing, segment_a)",
+        tuple(Vec::new()),
+        &context
+    );
+
+    // Nested tuples are elements as well, and compared element-wise.
+    let pair = |a: Value, b: Value| tuple(vec![a, b]);
+    assert_eval_eq!(
+        "unique(((1, 2), (1.0, 2), (2, 1), (1, 2)))",
+        tuple(vec![
+            pair(Value::from(1), Value::from(2)),
+            pair(Value::from(2), Value::from(1))
+        ])
+    );
+    assert_eval_eq!(
+        "intersect(((1, \"a\"), (2, \"b\")), ((2.0, \"b\"), 3))",
+        tuple(vec![pair(Value::from(2), Value::from("b"))])
+    );
+
+    // Integers and floats are only equal if they have exactly the same value.
+    assert_eval_eq!(
+        "unique((1, 1.5, 1.5))",
+        tuple(vec![Value::from(1), Value::from(1.5)])
+    );
+    assert_eval_eq!(
+        "unique((9007199254740993, 9007199254740992.0))",
+        tuple(vec![
+            Value::from(9007199254740993),
+            Value::from(9007199254740992.0)
+        ])
+    );
+    // NaN is not equal to any value, not even to itself.
+    match eval_tuple("unique((0.0 / 0, 0.0 / 0))") {
+        Ok(elements) => assert_eq!(elements.len(), 2),
+        result => panic!("Expected a tuple, got {:?}", result),
+    }
+    assert_eval_eq!("intersect((0.0 / 0, 1), (0.0 / 0, 2))", tuple(Vec::new()));
+    match eval_tuple("difference((0.0 / 0, 1), (0.0 / 0, 2))") {
+        Ok(elements) => assert_eq!(elements.len(), 2),
+        result => panic!("Expected a tuple, got {:?}", result),
+    }
+
+    assert_eval_err!("unique(1)", EvalexprError::ExpectedTuple { .. });
+    assert_eval_err!(
+        "union((1, 2), 3)",
+        EvalexprError::ExpectedTuple {
+            actual: Value::Int(3)
+        }
+    );
+    assert_eval_err!(
+        "intersect(segment_a)",
+        EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 4
+        },
+        &context
+    );
+}
+
+#[test]
+#[cfg(feature = "geo_support")]
+fn test_geo_functions() {
+    fn assert_distance(expression: &str, expected: FloatType, tolerance: FloatType) {
+        match eval_float(expression) {
+            Ok(distance) => assert!(
+                (distance - expected).abs() <= tolerance,
+                "{} evaluated to {}, expected {}",
+                expression,
+                distance,
+                expected
+            ),
+            Err(error) => panic!("{} failed: {}", expression, error),
+        }
+    }
+
+    // London to Paris and New York to Los Angeles.
+    assert_distance(
+        "haversine_km(51.5074, -0.1278, 48.8566, 2.3522)",
+        343.5,
+        1.0,
+    );
+    assert_distance(
+        "haversine_km(40.7128, -74.0060, 34.0522, -118.2437)",
+        3935.7,
+        2.0,
+    );
+    assert_distance(
+        "haversine_miles(40.7128, -74.0060, 34.0522, -118.2437)",
+        2445.6,
+        1.5,
+    );
+    // The shortest path crosses the antimeridian.
+    assert_distance("haversine_km(0, 179, 0, -179)", 222.4, 0.5);
+    assert_distance("haversine_km(90, 0, -90, 0)", 20015.1, 0.5);
+    assert_eq!(eval("haversine_km(10, 20, 10, 20)"), Ok(Value::Float(0.0)));
+
+    assert_eval_err!(
+        "haversine_km(91, 0, 0, 0)",
+        EvalexprError::CoordinateOutOfRange { coordinate, value }
+            if *coordinate == Coordinate::Latitude && *value == 91.0
+    );
+    assert_eval_err!(
+        "haversine_km(0, 0, 0, -180.5)",
+        EvalexprError::CoordinateOutOfRange { coordinate, value }
+            if *coordinate == Coordinate::Longitude && *value == -180.5
+    );
+    assert_eval_err!(
+        "haversine_km(0, 0, 0)",
+        EvalexprError::WrongFunctionArgumentAmount {
+            expected: 4,
+            actual: 3
+        }
+    );
+    assert_eval_err!(
+        "haversine_km(0, 0, 0, \"0\")",
+        EvalexprError::ExpectedNumber { .. }
+    );
+
+    assert_eq!(
+        eval("in_bbox(48.8566, 2.3522, 41, -5, 51, 10)"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("in_bbox(51.5074, -0.1278, 41, -5, 51, 10)"),
+        Ok(Value::Boolean(false))
+    );
+    assert_eq!(
+        eval("in_bbox(41, -5, 41, -5, 51, 10)"),
+        Ok(Value::Boolean(true))
+    );
+    // A box from 170 degrees east to 170 degrees west crosses the antimeridian.
+    assert_eq!(
+        eval("in_bbox(0, 175, -10, 170, 10, -170)"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("in_bbox(0, -175, -10, 170, 10, -170)"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("in_bbox(0, 0, -10, 170, 10, -170)"),
+        Ok(Value::Boolean(false))
+    );
+    assert_eq!(
+        eval("in_bbox(20, 175, -10, 170, 10, -170)"),
+        Ok(Value::Boolean(false))
+    );
+    assert_eval_err!(
+        "in_bbox(0, 0, -10, -10, 100, 10)",
+        EvalexprError::CoordinateOutOfRange { coordinate, .. } if *coordinate == Coordinate::Latitude
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_regex")]
+fn test_regex_functions() {
+    assert_eq!(
+        eval("str::regex_matches(\"foobar\", \"[ob]{3}\")"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("str::regex_matches(\"gazonk\", \"[ob]{3}\")"),
+        Ok(Value::Boolean(false))
+    );
+    match eval("str::regex_matches(\"foo\", \"[\")") {
+        Err(EvalexprError::InvalidRegex { regex, message }) => {
+            assert_eq!(regex, "[");
+            assert!(message.contains("unclosed character class"));
+        },
+        v => panic!("{:?}", v),
+    };
+    assert_eq!(
+        eval("str::regex_replace(\"foobar\", \".*?(o+)\", \"b$1\")"),
+        Ok(Value::String("boobar".to_owned()))
+    );
+    assert_eq!(
+        eval("str::regex_replace(\"foobar\", \".*?(i+)\", \"b$1\")"),
+        Ok(Value::String("foobar".to_owned()))
+    );
+}
+
+#[test]
+fn test_regex_match_operator() {
+    let context = context_map! {
+        "name" => "report-2024.csv",
+        "pattern" => "[0-9]+",
+    }
+    .unwrap();
+
+    let tree = build_operator_tree("name =~ pattern").unwrap();
+    assert_eq!(tree.to_string(), " =~ name pattern");
+    assert_eq!(tree.to_minified_string(), "name=~pattern");
+
+    if cfg!(not(feature = "builtin_regex")) {
+        let expected = EvalexprError::OperatorNeedsFeature {
+            operator: "=~".to_string(),
+            feature: "builtin_regex".to_string(),
+        };
+        assert_eq!(tree.eval_with_context(&context), Err(expected.clone()));
+        assert_eq!(
+            tree.compact().eval_with_context(&context),
+            Err(expected.clone())
+        );
+        assert_eq!(
+            expected.to_string(),
+            "The operator '=~' needs the feature 'builtin_regex' of evalexpr, which is disabled."
+        );
+        return;
+    }
+
+    for _ in 0..3 {
+        assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+    }
+    assert_eval_eq!("name =~ \"^report\"", true, &context);
+    assert_eval_eq!("name =~ \"^[0-9]\"", false, &context);
+    assert_eval_eq!(
+        "!(name =~ \"\\\\.txt$\") && name =~ \"csv\"",
+        true,
+        &context
+    );
+    assert_eq!(
+        eval_with_context("name =~ \"csv\" ? 1 : 2", &context),
+        eval_with_context("str::regex_matches(name, \"csv\") ? 1 : 2", &context)
+    );
+    assert_eq!(
+        eval("1 =~ \"a\""),
+        Err(EvalexprError::expected_string(Value::from(1)))
+    );
+    assert_eq!(
+        eval("\"a\" =~ ()"),
+        Err(EvalexprError::expected_string(Value::Empty))
+    );
+    match eval("\"a\" =~ \"[\"") {
+        Err(EvalexprError::InvalidRegex { regex, message }) => {
+            assert_eq!(regex, "[");
             assert!(message.contains("unclosed character class"));
         },
-        v => panic!(v),
+        result => panic!("{:?}", result),
+    }
+
+    // Comparisons of comparisons need parentheses.
+    assert!(matches!(
+        build_operator_tree("name =~ \"a\" == true"),
+        Err(EvalexprError::SuspiciousComparisonChain { .. })
+    ));
+
+    // Identifiers that start with a tilde are separated from an equals sign by the minifier.
+    let tree = build_operator_tree("a = ~b; a == ~b").unwrap();
+    assert_eq!(tree.to_minified_string(), "a= ~b;a== ~b");
+    assert_eq!(
+        build_operator_tree(&tree.to_minified_string()).unwrap(),
+        tree
+    );
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        eval("-true"),
+        Err(EvalexprError::expected_number(Value::Boolean(true)))
+    );
+    assert_eq!(
+        eval("1-true"),
+        Err(EvalexprError::expected_number(Value::Boolean(true)))
+    );
+    assert_eq!(
+        eval("true-"),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            actual: 1,
+            expected: 2
+        })
+    );
+    assert_eq!(
+        eval("!(()true)"),
+        Err(EvalexprError::MissingOperator { span: 4..8 })
+    );
+    assert_eq!(
+        eval("\"a\" > 1"),
+        Err(EvalexprError::expected_string(Value::Int(1)))
+    );
+    assert_eq!(
+        eval("1.5 <= \"b\""),
+        Err(EvalexprError::expected_number(Value::from("b")))
+    );
+}
+
+#[test]
+fn test_adjacent_tokens() {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Accepted {
+        Always,
+        AfterIdentifier,
+        // Rejected after any value, because the value is missing an operator.
+        NeverMissingOperator,
+        Never,
+    }
+
+    // Expressions that end with a value, each followed by each of the continuations below.
+    let values = [
+        "1",
+        "1.5",
+        "\"s\"",
+        "true",
+        "a",
+        "(1)",
+        "((1 + 2) * (3 + 4))",
+        "f(1)",
+        "()",
+        "if a { 1 } else { 2 }",
+    ];
+    // Binary operators may follow any value, but values only follow identifiers, which they are the argument of.
+    // A value that follows any other value fails with `MissingOperator`.
+    let continuations = [
+        ("+ 2", Accepted::Always),
+        ("- 2", Accepted::Always),
+        ("* 2", Accepted::Always),
+        ("== 2", Accepted::Always),
+        ("&& b", Accepted::Always),
+        (", 2", Accepted::Always),
+        ("; 2", Accepted::Always),
+        ("? 1 : 2", Accepted::Always),
+        ("(2)", Accepted::AfterIdentifier),
+        ("()", Accepted::AfterIdentifier),
+        ("2", Accepted::AfterIdentifier),
+        ("b", Accepted::AfterIdentifier),
+        ("\"t\"", Accepted::AfterIdentifier),
+        ("false", Accepted::AfterIdentifier),
+        ("g(2)", Accepted::AfterIdentifier),
+        ("= 2", Accepted::AfterIdentifier),
+        ("!b", Accepted::Never),
+        ("{ 2 }", Accepted::Never),
+        ("if b { 2 } else { 3 }", Accepted::NeverMissingOperator),
+        ("if(b, 2, 3)", Accepted::NeverMissingOperator),
+    ];
+
+    for value in values.iter() {
+        for (continuation, accepted) in continuations.iter() {
+            let expression = format!("{} {}", value, continuation);
+            let expected = match accepted {
+                Accepted::Always => true,
+                Accepted::AfterIdentifier => *value == "a",
+                Accepted::NeverMissingOperator | Accepted::Never => false,
+            };
+            let result = build_operator_tree(&expression);
+            assert_eq!(result.is_ok(), expected, "{:?}", expression);
+
+            let missing_operator = match accepted {
+                Accepted::AfterIdentifier => !expected && *continuation != "= 2",
+                Accepted::NeverMissingOperator => true,
+                Accepted::Always | Accepted::Never => false,
+            };
+            if missing_operator {
+                // The span starts at the value that is missing an operator before it.
+                assert!(
+                    matches!(
+                        &result,
+                        Err(EvalexprError::MissingOperator { span }) if span.start == value.len() + 1
+                    ),
+                    "{:?} {:?}",
+                    expression,
+                    result
+                );
+            }
+        }
+    }
+
+    // A closing parenthesis is not called like an identifier.
+    for (expression, span) in [
+        ("(1)(2)", 3..4),
+        ("f(1)(2)", 4..5),
+        ("((1 + 2) * (3 + 4))(5)", 19..20),
+        ("1 (2)", 2..3),
+        ("1 f(2)", 2..3),
+    ]
+    .iter()
+    {
+        assert_eq!(
+            build_operator_tree(expression),
+            Err(EvalexprError::MissingOperator { span: span.clone() }),
+            "{:?}",
+            expression
+        );
+    }
+    assert_eq!(eval("((1 + 2) * (3 + 4)) - 1"), Ok(Value::from(20)));
+    assert_eq!(eval("(1) -2"), Ok(Value::from(-1)));
+}
+
+#[test]
+fn test_error_spans() {
+    // Parse errors point to the offending token.
+    assert_eq!(
+        build_operator_tree("max(a, (b + 1)"),
+        Err(EvalexprError::UnmatchedLBrace { span: 3..4 })
+    );
+    assert_eq!(
+        build_operator_tree("((a) + (b)"),
+        Err(EvalexprError::UnmatchedLBrace { span: 0..1 })
+    );
+    assert_eq!(
+        build_operator_tree("a + b) * 2"),
+        Err(EvalexprError::UnmatchedRBrace { span: 5..6 })
+    );
+    assert_eq!(
+        build_operator_tree("größe + (1").unwrap_err().to_string(),
+        "Found an unmatched opening parenthesis '(' at offset 10."
+    );
+
+    // Variables that are not found point to where they are read, also through comments and in compact trees.
+    let expected = Err(EvalexprError::VariableIdentifierNotFound {
+        identifier: "rate".to_string(),
+        span: Some(21..25),
+    });
+    let expression = "price * /* tax */ (1+rate)";
+    let context = context_map! { "price" => 10 }.unwrap();
+    assert_eq!(eval_with_context(expression, &context), expected);
+    assert_eq!(
+        build_compact_tree(expression)
+            .unwrap()
+            .eval_with_context(&context),
+        expected
+    );
+    assert_eq!(
+        eval_with_context(expression, &context)
+            .unwrap_err()
+            .to_string(),
+        "Variable identifier is not bound to anything by context: \"rate\" at offset 21."
+    );
+
+    // Spans do not change the equality of operator trees.
+    assert_eq!(
+        build_operator_tree("a+b").unwrap(),
+        build_operator_tree("  a  +  b").unwrap()
+    );
+}
+
+#[test]
+fn test_render_with_source() {
+    fn lines(lines: &[&str]) -> String {
+        lines.join("\n")
+    }
+
+    // Single line
+    let source = "a + b) * 2";
+    let error = build_operator_tree(source).unwrap_err();
+    assert_eq!(error.span(), Some(5..6));
+    assert_eq!(
+        error.render_with_source(source),
+        lines(&[
+            "error: Found an unmatched closing parenthesis ')' at offset 5.",
+            " --> 1:6",
+            "  |",
+            "1 | a + b) * 2",
+            "  |      ^",
+        ])
+    );
+    let source = "total == 1 ||   true = x";
+    assert_eq!(
+        build_operator_tree(source)
+            .unwrap_err()
+            .render_with_source(source),
+        lines(&[
+            "error: Cannot assign to the keyword 'true' at offset 16, as it is not an identifier.",
+            " --> 1:17",
+            "  |",
+            "1 | total == 1 ||   true = x",
+            "  |                 ^^^^",
+        ])
+    );
+
+    // Multiple lines, with tabs and carriage returns
+    let source = "a = 1;\r\n\tb = 2;\r\n\t\tc = a +\tmissing; // sum\r\nc";
+    let error = build_operator_tree(source)
+        .unwrap()
+        .eval_with_context_mut(&mut HashMapContext::new())
+        .unwrap_err();
+    assert_eq!(error.span(), Some(27..34));
+    assert_eq!(
+        error.render_with_source(source),
+        lines(&[
+            "error: Variable identifier is not bound to anything by context: \"missing\" at offset 27.",
+            " --> 3:11",
+            "  |",
+            "3 |         c = a +    missing; // sum",
+            "  |                    ^^^^^^^",
+        ])
+    );
+    let source = format!("{}(x", "\n".repeat(11));
+    assert_eq!(
+        build_operator_tree(&source)
+            .unwrap_err()
+            .render_with_source(&source),
+        lines(&[
+            "error: Found an unmatched opening parenthesis '(' at offset 11.",
+            "  --> 12:1",
+            "   |",
+            "12 | (x",
+            "   | ^",
+        ])
+    );
+
+    // Long lines are cut around the span.
+    let source = format!("{} + (b * c{}", "a".repeat(30), " + d".repeat(10));
+    let error = build_operator_tree(&source).unwrap_err();
+    let rendered = error.render_with_source_and_width(&source, 20);
+    assert_eq!(
+        rendered,
+        lines(&[
+            "error: Found an unmatched opening parenthesis '(' at offset 33.",
+            " --> 1:34",
+            "  |",
+            "1 | …aaaaaa + (b * c + …",
+            "  |           ^",
+        ])
+    );
+    assert_eq!(
+        error.render_with_source_and_width(&source, 200),
+        error.render_with_source(&source)
+    );
+    let source = "(a + b + c + d";
+    let error = build_operator_tree(source).unwrap_err();
+    assert_eq!(
+        error.render_with_source_and_width(source, 8),
+        lines(&[
+            "error: Found an unmatched opening parenthesis '(' at offset 0.",
+            " --> 1:1",
+            "  |",
+            "1 | (a + b …",
+            "  | ^",
+        ])
+    );
+    assert_eq!(
+        error.render_with_source_and_width(source, 0),
+        lines(&[
+            "error: Found an unmatched opening parenthesis '(' at offset 0.",
+            " --> 1:1",
+            "  |",
+            "1 | (a…",
+            "  | ^",
+        ])
+    );
+    let source = "a + b + c + (,d)";
+    assert_eq!(
+        build_operator_tree(source)
+            .unwrap_err()
+            .render_with_source_and_width(source, 6),
+        lines(&[
+            "error: Found a comma at offset 13 that does not follow a tuple element.",
+            " --> 1:14",
+            "  |",
+            "1 | … (,d)",
+            "  |    ^",
+        ])
+    );
+
+    // Errors without a span, or with a span outside of the source, are rendered as their message.
+    let error = eval("1 / 0").unwrap_err();
+    assert_eq!(error.span(), None);
+    assert_eq!(
+        error.render_with_source("1 / 0"),
+        "error: Error dividing 1 / 0"
+    );
+    // The span of an error within a function belongs to another expression.
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "inner".into(),
+            Function::new(Box::new(|_| build_operator_tree("y").unwrap().eval())),
+        )
+        .unwrap();
+    let error = eval_with_context("inner(1)", &context).unwrap_err();
+    assert!(matches!(
+        &error,
+        EvalexprError::InFunctionCall { source, .. } if source.span() == Some(0..1)
+    ));
+    assert_eq!(error.span(), None);
+    assert_eq!(
+        error.render_with_source_and_width("inner(1)", 3),
+        format!("error: {}", error)
+    );
+    assert_eq!(
+        EvalexprError::UnmatchedLBrace { span: 20..21 }.render_with_source("(x"),
+        "error: Found an unmatched opening parenthesis '(' at offset 20."
+    );
+    assert_eq!(
+        EvalexprError::UnmatchedLBrace { span: 1..2 }.render_with_source("ä"),
+        "error: Found an unmatched opening parenthesis '(' at offset 1."
+    );
+}
+
+#[test]
+fn test_error_messages() {
+    let long = "abcdefghij";
+    let string = || Value::from(long);
+    // Each error with its user message and its detailed message with strings truncated to 4 characters.
+    let errors = vec![
+        (
+            EvalexprError::WrongOperatorArgumentAmount {
+                expected: 2,
+                actual: 1,
+            },
+            "An operator in this expression has the wrong number of arguments.",
+            "An operator expected 2 arguments, but got 1.",
+        ),
+        (
+            EvalexprError::WrongFunctionArgumentAmount {
+                expected: 2,
+                actual: 1,
+            },
+            "A function in this expression is called with the wrong number of arguments.",
+            "A function expected 2 arguments, but got 1.",
+        ),
+        (
+            EvalexprError::expected_string(Value::from(1)),
+            "This expression expects a string here.",
+            "Expected a Value::String, but got Int(1).",
+        ),
+        (
+            EvalexprError::expected_int(string()),
+            "This expression expects an integer here.",
+            "Expected a Value::Int, but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::expected_float(string()),
+            "This expression expects a floating point number here.",
+            "Expected a Value::Float, but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::expected_number(string()),
+            "This expression expects a number here.",
+            "Expected a Value::Float or Value::Int, but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::expected_number_or_string(Value::from(true)),
+            "This expression expects a number or a string here.",
+            "Expected a Value::Number or a Value::String, but got Boolean(true).",
+        ),
+        (
+            EvalexprError::expected_boolean(Value::from(vec![string(), Value::from("ab")])),
+            "This expression expects true or false here.",
+            "Expected a Value::Boolean, but got Tuple([String(\"abcd...\"), String(\"ab\")]).",
+        ),
+        (
+            EvalexprError::expected_tuple(string()),
+            "This expression expects a tuple here.",
+            "Expected a Value::Tuple, but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::expected_empty(string()),
+            "This expression expects an empty value here.",
+            "Expected a Value::Empty, but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::AppendedToLeafNode,
+            "This expression could not be parsed.",
+            "Tried to append a node to a leaf node.",
+        ),
+        (
+            EvalexprError::PrecedenceViolation,
+            "This expression could not be parsed.",
+            "Tried to append a node to another node with higher precedence.",
+        ),
+        (
+            EvalexprError::VariableIdentifierNotFound {
+                identifier: long.to_string(),
+                span: None,
+            },
+            "This expression uses a variable that is not defined.",
+            "Variable identifier is not bound to anything by context: \"abcd...\".",
+        ),
+        (
+            eval("1 + abcdefghij").unwrap_err(),
+            "This expression uses a variable that is not defined.",
+            "Variable identifier is not bound to anything by context: \"abcd...\" at offset 4.",
+        ),
+        (
+            EvalexprError::FunctionIdentifierNotFound(long.to_string()),
+            "This expression uses a function that is not defined.",
+            "Function identifier is not bound to anything by context: \"abcd...\".",
+        ),
+        (
+            EvalexprError::UnknownNamedArgument {
+                function: long.to_string(),
+                argument: "x".to_string(),
+            },
+            "A function in this expression is called with an argument name it does not know.",
+            "The function \"abcd...\" has no parameter named \"x\".",
+        ),
+        (
+            EvalexprError::DuplicateArgument {
+                function: "f".to_string(),
+                parameter: long.to_string(),
+            },
+            "A function in this expression is given the same argument more than once.",
+            "The parameter \"abcd...\" of the function \"f\" is given more than once.",
+        ),
+        (
+            EvalexprError::MissingArgument {
+                function: "f".to_string(),
+                parameter: "x".to_string(),
+            },
+            "A function in this expression is missing an argument.",
+            "The parameter \"x\" of the function \"f\" is not given and has no default value.",
+        ),
+        (
+            EvalexprError::DuplicateParameter(long.to_string()),
+            "A function has two parameters with the same name.",
+            "A function has more than one parameter named \"abcd...\".",
+        ),
+        (
+            EvalexprError::NonTrailingDefault(long.to_string()),
+            "A function has a parameter with a default value before one without.",
+            "The parameter \"abcd...\" has a default value, but is followed by a parameter without one.",
+        ),
+        (
+            EvalexprError::WrongFunctionArgumentRange {
+                minimum: 1,
+                maximum: 2,
+                actual: 0,
+                optional: vec![long.to_string()],
+            },
+            "A function in this expression is called with the wrong number of arguments.",
+            "A function expected 1 to 2 arguments, but got 0. The optional parameters are [\"abcd...\"].",
+        ),
+        (
+            EvalexprError::type_error(string(), vec![Value::from(long)]),
+            "A value in this expression has the wrong type.",
+            "Expected one of [String(\"abcd...\")], but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::UnmatchedLBrace { span: 37..38 },
+            "An opening parenthesis in this expression is never closed.",
+            "Found an unmatched opening parenthesis '(' at offset 37.",
+        ),
+        (
+            EvalexprError::UnmatchedRBrace { span: 2..3 },
+            "A closing parenthesis in this expression was never opened.",
+            "Found an unmatched closing parenthesis ')' at offset 2.",
+        ),
+        (
+            EvalexprError::UnmatchedQuestionMark { span: 2..3 },
+            "A conditional in this expression has no ':' branch.",
+            "Found a question mark '?' at offset 2 without a matching colon ':'.",
+        ),
+        (
+            EvalexprError::UnmatchedColon { span: 2..3 },
+            "A ':' in this expression does not belong to a conditional.",
+            "Found a colon ':' at offset 2 without a matching question mark '?'.",
+        ),
+        (
+            EvalexprError::MissingOperator { span: 2..3 },
+            "Two values in this expression are missing an operator between them.",
+            "Found a value at offset 2 directly after another value without an operator between them.",
+        ),
+        (
+            EvalexprError::UnmatchedLCurlyBrace { span: 5..6 },
+            "A block in this expression is never closed with '}'.",
+            "Found an unmatched opening curly brace '{' at offset 5.",
+        ),
+        (
+            EvalexprError::UnmatchedRCurlyBrace { span: 2..3 },
+            "A '}' in this expression does not close a block.",
+            "Found an unmatched closing curly brace '}' at offset 2.",
+        ),
+        (
+            EvalexprError::InvalidIfExpression { span: 0..4 },
+            "An 'if' or 'else' in this expression is malformed.",
+            "Found a malformed if-expression at offset 0, expected a form like 'if a { b } else { c }'.",
+        ),
+        (
+            EvalexprError::TrailingComma { span: 6..7 },
+            "This expression contains a comma without a following value.",
+            "Found a trailing comma at offset 6 that is not followed by another tuple element.",
+        ),
+        (
+            EvalexprError::LeadingComma { span: 1..2 },
+            "This expression contains a comma without a preceding value.",
+            "Found a comma at offset 1 that does not follow a tuple element.",
+        ),
+        (
+            EvalexprError::PositionalArgumentAfterNamed { span: 9..10 },
+            "A function in this expression has an unnamed argument after a named one.",
+            "Found a positional argument at offset 9 after a named argument.",
+        ),
+        (
+            EvalexprError::InvalidAssignmentTarget { span: 4..5 },
+            "This expression assigns to something that is not a variable.",
+            "The left-hand side of the assignment at offset 4 is not a single variable identifier, like in 'a = 1'.",
+        ),
+        (
+            EvalexprError::AssignmentToKeyword {
+                keyword: "true".to_string(),
+                span: 4..8,
+            },
+            "This expression assigns to a reserved keyword.",
+            "Cannot assign to the keyword 'true' at offset 4, as it is not an identifier.",
+        ),
+        (
+            EvalexprError::KeywordAsValue {
+                keyword: "xor".to_string(),
+                span: 2..5,
+            },
+            "This expression uses a reserved keyword as a variable.",
+            "The keyword 'xor' at offset 2 is an operator and cannot be used as a variable.",
+        ),
+        (
+            EvalexprError::SuspiciousComparisonChain {
+                expression: long.to_string(),
+            },
+            "This expression compares the result of a comparison without parentheses.",
+            "Comparisons are chained without parentheses, which would be evaluated as abcd.... Write the parentheses to compare the boolean result, or use && to require both comparisons.",
+        ),
+        (
+            EvalexprError::AdditionError {
+                augend: string(),
+                addend: Value::from(1),
+            },
+            "An addition in this expression failed.",
+            "Error adding \"abcd...\" + 1",
+        ),
+        (
+            EvalexprError::SubtractionError {
+                minuend: Value::from(1),
+                subtrahend: string(),
+            },
+            "A subtraction in this expression failed.",
+            "Error subtracting 1 - \"abcd...\"",
+        ),
+        (
+            EvalexprError::NegationError {
+                argument: string(),
+            },
+            "A negation in this expression failed.",
+            "Error negating -\"abcd...\"",
+        ),
+        (
+            EvalexprError::MultiplicationError {
+                multiplicand: string(),
+                multiplier: Value::from(2),
+            },
+            "A multiplication in this expression failed.",
+            "Error multiplying \"abcd...\" * 2",
+        ),
+        (
+            EvalexprError::DivisionError {
+                dividend: string(),
+                divisor: Value::from(0),
+            },
+            "A division in this expression failed.",
+            "Error dividing \"abcd...\" / 0",
+        ),
+        (
+            EvalexprError::ModulationError {
+                dividend: string(),
+                divisor: Value::from(0),
+            },
+            "A modulo operation in this expression failed.",
+            "Error modulating \"abcd...\" % 0",
+        ),
+        (
+            EvalexprError::ExponentiationError {
+                base: string(),
+                exponent: Value::from(2),
+            },
+            "An exponentiation in this expression failed.",
+            "Error exponentiating \"abcd...\" ^ 2",
+        ),
+        (
+            EvalexprError::ShiftAmountOutOfRange {
+                value: 1,
+                amount: 64,
+            },
+            "A bit shift in this expression shifts by an invalid amount of bits.",
+            "Cannot shift 1 by 64 bits, expected an amount from 0 to 63",
+        ),
+        (
+            EvalexprError::NaNProduced {
+                operator: long.to_string(),
+                operands: vec![string(), Value::from(0.0)],
+            },
+            "A calculation in this expression has no defined result.",
+            "Computing abcd... with the operands \"abcd...\", 0 produced NaN",
+        ),
+        (
+            EvalexprError::IntegerOverflow {
+                function: long.to_string(),
+                arguments: vec![string(), Value::from(IntType::MIN)],
+            },
+            "A calculation in this expression produces an integer that is too large.",
+            "Calling abcd... with the arguments \"abcd...\", -9223372036854775808 overflows the integer range",
+        ),
+        (
+            EvalexprError::invalid_regex(long.to_string(), "unclosed group".to_string()),
+            "This expression contains an invalid regular expression.",
+            "Regular expression \"abcd...\" is invalid: \"uncl...\"",
+        ),
+        (
+            EvalexprError::OperatorNeedsFeature {
+                operator: "=~".to_string(),
+                feature: "builtin_regex".to_string(),
+            },
+            "This expression uses an operator that is not available in this build.",
+            "The operator '=~' needs the feature 'builtin_regex' of evalexpr, which is disabled.",
+        ),
+        (
+            EvalexprError::invalid_glob(long.to_string(), "unterminated character class".to_string()),
+            "This expression contains an invalid wildcard pattern.",
+            "Wildcard pattern \"abcd...\" is invalid: unte...",
+        ),
+        (
+            EvalexprError::ArgumentOutOfRange {
+                function: long.to_string(),
+                argument: string(),
+                expected: long.to_string(),
+            },
+            "A function in this expression is called with an argument that is out of range.",
+            "The argument \"abcd...\" of abcd... is out of range, expected abcd...",
+        ),
+        (
+            EvalexprError::coordinate_out_of_range(Coordinate::Latitude, 91.0),
+            "A latitude or longitude in this expression is out of range.",
+            "The latitude 91 is outside of the valid range from -90 to 90 degrees",
+        ),
+        (
+            EvalexprError::unknown_comparison_operator(long.to_string()),
+            "This expression uses an unknown comparison operator.",
+            "Unknown comparison operator \"abcd...\", expected one of \"==\", \"!=\", \">\", \"<\", \">=\" or \"<=\"",
+        ),
+        (
+            EvalexprError::RangeStepZero,
+            "This expression uses a range with a step of zero.",
+            "The step of a range must not be zero",
+        ),
+        (
+            EvalexprError::RangeTooLong {
+                limit: 10,
+                length: 11,
+            },
+            "This expression uses a range with too many elements.",
+            "The range has 11 elements, but ranges are limited to 10 elements",
+        ),
+        (
+            EvalexprError::RecursionLimitExceeded { max_depth: 1 },
+            "This expression nests too many evaluations.",
+            "Exceeded the maximum recursion depth of 1 nested evaluations",
+        ),
+        (
+            EvalexprError::ExpressionCycle {
+                chain: vec![long.to_string(), "b".to_string(), long.to_string()],
+            },
+            "Named expressions refer to each other in a cycle.",
+            "Named expressions reference each other in a cycle: abcd... -> b -> abcd...",
+        ),
+        (
+            EvalexprError::ValueTooLarge {
+                limit: 1,
+                attempted: 2,
+            },
+            "This expression produces values that are too large.",
+            "The evaluation needs 2 bytes for its values, but is limited to 1 bytes",
+        ),
+        (
+            EvalexprError::Cancelled,
+            "The evaluation of this expression was cancelled.",
+            "The evaluation was cancelled",
+        ),
+        (
+            EvalexprError::InvalidExportArgument(long.to_string()),
+            "This expression exports something that is not a variable.",
+            "Only variables can be exported, but got the expression \"abcd...\"",
+        ),
+        (
+            EvalexprError::ContextNotManipulable,
+            "This expression may not assign variables.",
+            "Cannot manipulate context",
+        ),
+        (
+            EvalexprError::IllegalEscapeSequence("\\abcdefghij".to_string()),
+            "A string in this expression contains an illegal escape sequence.",
+            "Illegal escape sequence: \\abc...",
+        ),
+        (
+            EvalexprError::UnterminatedString(long.to_string()),
+            "A string in this expression is missing its closing quote.",
+            "Unterminated string literal: \"abcd...",
+        ),
+        (
+            EvalexprError::UnterminatedComment(long.to_string()),
+            "A comment in this expression is missing its closing `*/`.",
+            "Unterminated block comment: /*abcd...",
+        ),
+        (
+            EvalexprError::InFunctionCall {
+                function: long.to_string(),
+                argument: Value::from(long),
+                source: Box::new(EvalexprError::CustomMessage(long.to_string())),
+            },
+            "This expression could not be evaluated.",
+            "in call to 'abcd...' (argument \"abcd...\"): Error: abcd...",
+        ),
+        (
+            EvalexprError::TupleIndexOutOfBounds {
+                index: 3,
+                length: 2,
+            },
+            "A tuple in this expression has too few elements.",
+            "Tuple index 3 is out of bounds for a tuple of length 2",
+        ),
+        (
+            EvalexprError::InTupleElement {
+                index: 1,
+                source: Box::new(EvalexprError::expected_int(Value::from(long))),
+            },
+            "This expression expects an integer here.",
+            "in tuple element 1: Expected a Value::Int, but got String(\"abcd...\").",
+        ),
+        (
+            EvalexprError::DestructuringLengthMismatch {
+                expected: 2,
+                actual: 3,
+            },
+            "This expression assigns a tuple to a different amount of variables.",
+            "Cannot assign a tuple of 3 elements to a tuple of 2 variables",
+        ),
+        (
+            EvalexprError::NonFiniteFloatInJson(FloatType::INFINITY),
+            "This expression produces a number that cannot be represented as JSON.",
+            "The float inf cannot be converted to JSON, as JSON has no NaN or infinite numbers",
+        ),
+        (
+            EvalexprError::IntegerLiteralOutOfRange("99999999999999999999".to_string()),
+            "An integer in this expression is too large.",
+            "The integer literal 9999... is out of range, the allowed range is -9223372036854775808 to 9223372036854775807",
+        ),
+        (
+            EvalexprError::int_out_of_range(u64::MAX),
+            "A number given to this expression is too large.",
+            "The integer 1844... is out of range, the allowed range is -9223372036854775808 to 9223372036854775807",
+        ),
+        (
+            EvalexprError::InvalidNumberLiteral(long.to_string()),
+            "A number in this expression is malformed.",
+            "Invalid number literal: abcd...",
+        ),
+        (
+            EvalexprError::InvalidCustomLiteral {
+                literal: long.to_string(),
+                span: 0..10,
+                source: Box::new(EvalexprError::CustomMessage(long.to_string())),
+            },
+            "A literal in this expression is malformed.",
+            "Invalid custom literal abcd... at offset 0: Error: abcd...",
+        ),
+        (
+            EvalexprError::NoContextSucceeded {
+                errors: vec![
+                    EvalexprError::FunctionIdentifierNotFound(long.to_string()),
+                    EvalexprError::CustomMessage(long.to_string()),
+                ],
+            },
+            "This expression could not be evaluated with any of the given contexts.",
+            "The evaluation failed with all 2 contexts; context 0: Function identifier is not bound to anything by context: \"abcd...\".; context 1: Error: abcd...",
+        ),
+        (
+            EvalexprError::TypeCheckFailed {
+                errors: vec![
+                    TypeCheckError::FunctionArgumentAmount {
+                        function: long.to_string(),
+                        expected: 1,
+                        actual: 2,
+                        span: None,
+                    },
+                    TypeCheckError::ResultType {
+                        expected: StaticType::Boolean,
+                        actual: StaticType::Int,
+                        span: None,
+                    },
+                ],
+            },
+            "This expression produces a value of the wrong type.",
+            "The expression failed the type check; Function \"abcd...\" expected 1 arguments, but got 2; The expression is expected to be of type Boolean, but is of type Int",
+        ),
+        (
+            EvalexprError::CustomMessage(long.to_string()),
+            "This expression could not be evaluated.",
+            "Error: abcd...",
+        ),
+    ];
+
+    for (error, user_message, truncated_message) in errors {
+        assert_eq!(error.user_message(), user_message, "{:?}", error);
+        assert_eq!(
+            error.truncate_strings(4).to_string(),
+            truncated_message,
+            "{:?}",
+            error
+        );
+        // Strings that are short enough are kept.
+        assert_eq!(error.truncate_strings(100), error);
+    }
+
+    // Truncation counts characters, not bytes.
+    assert_eq!(
+        EvalexprError::CustomMessage("äöüß".to_string()).truncate_strings(3),
+        EvalexprError::CustomMessage("äöü...".to_string())
+    );
+}
+
+#[test]
+fn test_error_clone() {
+    let errors = vec![
+        EvalexprError::WrongOperatorArgumentAmount {
+            expected: 2,
+            actual: 1,
+        },
+        EvalexprError::WrongFunctionArgumentAmount {
+            expected: 2,
+            actual: 1,
+        },
+        EvalexprError::WrongFunctionArgumentRange {
+            minimum: 1,
+            maximum: 2,
+            actual: 3,
+            optional: vec!["b".to_string()],
+        },
+        EvalexprError::expected_string(Value::from(1)),
+        EvalexprError::expected_int(Value::from("a")),
+        EvalexprError::expected_float(Value::from(1)),
+        EvalexprError::expected_number(Value::from(true)),
+        EvalexprError::expected_number_or_string(Value::Empty),
+        EvalexprError::expected_boolean(Value::from(1.5)),
+        EvalexprError::expected_tuple(Value::from(1)),
+        EvalexprError::expected_empty(Value::from(vec![Value::from(1), Value::from("a")])),
+        EvalexprError::AppendedToLeafNode,
+        EvalexprError::PrecedenceViolation,
+        eval("1 + a").unwrap_err(),
+        EvalexprError::FunctionIdentifierNotFound("f".to_string()),
+        EvalexprError::UnknownNamedArgument {
+            function: "f".to_string(),
+            argument: "a".to_string(),
+        },
+        EvalexprError::DuplicateArgument {
+            function: "f".to_string(),
+            parameter: "a".to_string(),
+        },
+        EvalexprError::MissingArgument {
+            function: "f".to_string(),
+            parameter: "a".to_string(),
+        },
+        EvalexprError::DuplicateParameter("a".to_string()),
+        EvalexprError::NonTrailingDefault("a".to_string()),
+        EvalexprError::type_error(Value::from(1), vec![Value::from("a")]),
+        build_operator_tree("(1").unwrap_err(),
+        build_operator_tree("1)").unwrap_err(),
+        build_operator_tree("a ? b").unwrap_err(),
+        build_operator_tree("a : b").unwrap_err(),
+        build_operator_tree("if a { b").unwrap_err(),
+        build_operator_tree("a }").unwrap_err(),
+        build_operator_tree("1 2").unwrap_err(),
+        build_operator_tree("else { b }").unwrap_err(),
+        build_operator_tree("1, 2,").unwrap_err(),
+        build_operator_tree("(,)").unwrap_err(),
+        build_operator_tree("f(a = 1, 2)").unwrap_err(),
+        build_operator_tree("1 = 2").unwrap_err(),
+        build_operator_tree("false = 1").unwrap_err(),
+        build_operator_tree("f(xor)").unwrap_err(),
+        build_operator_tree("a == b == c").unwrap_err(),
+        EvalexprError::AdditionError {
+            augend: Value::from(1),
+            addend: Value::from("a"),
+        },
+        EvalexprError::SubtractionError {
+            minuend: Value::from(IntType::MIN),
+            subtrahend: Value::from(1),
+        },
+        EvalexprError::NegationError {
+            argument: Value::from(IntType::MIN),
+        },
+        EvalexprError::MultiplicationError {
+            multiplicand: Value::from(IntType::MAX),
+            multiplier: Value::from(2),
+        },
+        EvalexprError::DivisionError {
+            dividend: Value::from(1),
+            divisor: Value::from(0),
+        },
+        EvalexprError::ModulationError {
+            dividend: Value::from(1),
+            divisor: Value::from(0),
+        },
+        EvalexprError::ExponentiationError {
+            base: Value::from(IntType::MAX),
+            exponent: Value::from(2),
+        },
+        eval("1 << 64").unwrap_err(),
+        EvalexprError::NaNProduced {
+            operator: "/".to_string(),
+            operands: vec![Value::from(0.0), Value::from(0.0)],
+        },
+        EvalexprError::IntegerOverflow {
+            function: "abs".to_string(),
+            arguments: vec![Value::from(IntType::MIN)],
+        },
+        EvalexprError::invalid_regex("(".to_string(), "unclosed group".to_string()),
+        EvalexprError::OperatorNeedsFeature {
+            operator: "=~".to_string(),
+            feature: "builtin_regex".to_string(),
+        },
+        EvalexprError::invalid_glob("[".to_string(), "unterminated character class".to_string()),
+        EvalexprError::CoordinateOutOfRange {
+            coordinate: Coordinate::Latitude,
+            value: 91.0,
+        },
+        EvalexprError::UnknownComparisonOperator("=>".to_string()),
+        EvalexprError::ArgumentOutOfRange {
+            function: "MID".to_string(),
+            argument: Value::from(0),
+            expected: "a start position of at least 1".to_string(),
+        },
+        EvalexprError::RangeStepZero,
+        eval("0..=1000000").unwrap_err(),
+        EvalexprError::RecursionLimitExceeded { max_depth: 1 },
+        EvalexprError::ExpressionCycle {
+            chain: vec!["a".to_string(), "a".to_string()],
+        },
+        EvalexprError::ValueTooLarge {
+            limit: 1,
+            attempted: 2,
+        },
+        EvalexprError::Cancelled,
+        EvalexprError::InvalidExportArgument("1".to_string()),
+        EvalexprError::ContextNotManipulable,
+        EvalexprError::IllegalEscapeSequence("\\a".to_string()),
+        EvalexprError::UnterminatedString("a".to_string()),
+        EvalexprError::UnterminatedComment("a".to_string()),
+        EvalexprError::InFunctionCall {
+            function: "f".to_string(),
+            argument: Value::from(1),
+            source: Box::new(EvalexprError::CustomMessage("message".to_string())),
+        },
+        EvalexprError::TupleIndexOutOfBounds {
+            index: 1,
+            length: 0,
+        },
+        EvalexprError::InTupleElement {
+            index: 0,
+            source: Box::new(EvalexprError::expected_int(Value::Empty)),
+        },
+        EvalexprError::DestructuringLengthMismatch {
+            expected: 1,
+            actual: 2,
+        },
+        EvalexprError::NonFiniteFloatInJson(FloatType::INFINITY),
+        EvalexprError::IntegerLiteralOutOfRange("9223372036854775808".to_string()),
+        EvalexprError::int_out_of_range(u128::MAX),
+        EvalexprError::InvalidNumberLiteral("1e".to_string()),
+        EvalexprError::InvalidCustomLiteral {
+            literal: "#G".to_string(),
+            span: 0..2,
+            source: Box::new(EvalexprError::CustomMessage("message".to_string())),
+        },
+        eval_first_success("a", vec![&EmptyContext as &dyn Context]).unwrap_err(),
+        TypedExpression::<bool>::parse("1").unwrap_err(),
+        EvalexprError::CustomMessage("message".to_string()),
+    ];
+
+    for error in &errors {
+        assert_eq!(&error.clone(), error);
+        assert_eq!(error.clone().to_string(), error.to_string());
+    }
+    assert_eq!(errors.clone(), errors);
+
+    // The list contains each variant once, in the order of their declaration.
+    let names: Vec<_> = errors.iter().map(EvalexprError::variant_name).collect();
+    assert_eq!(names, EvalexprError::ALL_VARIANT_NAMES);
+    for error in &errors {
+        let name = error.variant_name();
+        assert!(format!("{:?}", error).starts_with(name), "{}", name);
+        assert_eq!(EvalexprError::from_variant_name(name), Some(name));
+    }
+    assert_eq!(EvalexprError::from_variant_name("divisionerror"), None);
+    assert_eq!(EvalexprError::from_variant_name(""), None);
+}
+
+#[test]
+fn test_no_panic() {
+    assert!(eval(&format!("{} + {}", IntType::MAX, IntType::MAX)).is_err());
+    assert!(eval(&format!("-{} - {}", IntType::MAX, IntType::MAX)).is_err());
+    assert!(eval(&format!("-(-{} - 1)", IntType::MAX)).is_err());
+    assert!(eval(&format!("{} * {}", IntType::MAX, IntType::MAX)).is_err());
+    assert!(eval(&format!("{} / {}", IntType::MAX, 0)).is_err());
+    assert!(eval(&format!("{} % {}", IntType::MAX, 0)).is_err());
+    assert!(eval(&format!("{} ^ {}", IntType::MAX, IntType::MAX)).is_err());
+    assert!(eval(&format!("{}.0 ^ {}", IntType::MAX, IntType::MAX)).is_ok());
+}
+
+#[test]
+fn test_shortcut_functions() {
+    let mut context = HashMapContext::new();
+    context
+        .set_value("string", Value::from("a string"))
+        .unwrap();
+
+    // assert_eq!(eval_string("???"));
+    assert_eq!(
+        eval_string_with_context("string", &context),
+        Ok("a string".to_string())
+    );
+    assert_eq!(eval_float("3.3"), Ok(3.3));
+    assert_eq!(eval_float_with_context("3.3", &context), Ok(3.3));
+    assert_eq!(eval_int("3"), Ok(3));
+    assert_eq!(eval_int_with_context("3", &context), Ok(3));
+    assert_eq!(eval_number("3"), Ok(3.0));
+    assert_eq!(eval_number_with_context("3", &context), Ok(3.0));
+    assert_eq!(eval_boolean("true"), Ok(true));
+    assert_eq!(eval_boolean_with_context("true", &context), Ok(true));
+    assert_eq!(eval_tuple("3,3"), Ok(vec![Value::Int(3), Value::Int(3)]));
+    assert_eq!(
+        eval_tuple_with_context("3,3", &context),
+        Ok(vec![Value::Int(3), Value::Int(3)])
+    );
+    assert_eq!(eval_empty(""), Ok(EMPTY_VALUE));
+    assert_eq!(eval_empty_with_context("", &context), Ok(EMPTY_VALUE));
+
+    // assert_eq!(build_operator_tree("???").unwrap().eval_string());
+    assert_eq!(
+        build_operator_tree("string")
+            .unwrap()
+            .eval_string_with_context(&context),
+        Ok("a string".to_string())
+    );
+    assert_eq!(build_operator_tree("3.3").unwrap().eval_float(), Ok(3.3));
+    assert_eq!(
+        build_operator_tree("3.3")
+            .unwrap()
+            .eval_float_with_context(&context),
+        Ok(3.3)
+    );
+    assert_eq!(build_operator_tree("3").unwrap().eval_int(), Ok(3));
+    assert_eq!(
+        build_operator_tree("3")
+            .unwrap()
+            .eval_int_with_context(&context),
+        Ok(3)
+    );
+    assert_eq!(build_operator_tree("3").unwrap().eval_number(), Ok(3.0));
+    assert_eq!(
+        build_operator_tree("3")
+            .unwrap()
+            .eval_number_with_context(&context),
+        Ok(3.0)
+    );
+    assert_eq!(
+        build_operator_tree("true").unwrap().eval_boolean(),
+        Ok(true)
+    );
+    assert_eq!(
+        build_operator_tree("true")
+            .unwrap()
+            .eval_boolean_with_context(&context),
+        Ok(true)
+    );
+    assert_eq!(
+        build_operator_tree("3,3").unwrap().eval_tuple(),
+        Ok(vec![Value::Int(3), Value::Int(3)])
+    );
+    assert_eq!(
+        build_operator_tree("3,3")
+            .unwrap()
+            .eval_tuple_with_context(&context),
+        Ok(vec![Value::Int(3), Value::Int(3)])
+    );
+    assert_eq!(
+        build_operator_tree("")
+            .unwrap()
+            .eval_empty_with_context(&context),
+        Ok(EMPTY_VALUE)
+    );
+
+    assert_eq!(
+        eval_string_with_context_mut("string", &mut context),
+        Ok("a string".to_string())
+    );
+    assert_eq!(eval_float_with_context_mut("3.3", &mut context), Ok(3.3));
+    assert_eq!(eval_int_with_context_mut("3", &mut context), Ok(3));
+    assert_eq!(eval_number_with_context_mut("3", &mut context), Ok(3.0));
+    assert_eq!(
+        eval_boolean_with_context_mut("true", &mut context),
+        Ok(true)
+    );
+    assert_eq!(
+        eval_tuple_with_context_mut("3,3", &mut context),
+        Ok(vec![Value::Int(3), Value::Int(3)])
+    );
+    assert_eq!(
+        eval_empty_with_context_mut("", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+
+    assert_eq!(
+        build_operator_tree("string")
+            .unwrap()
+            .eval_string_with_context_mut(&mut context),
+        Ok("a string".to_string())
+    );
+    assert_eq!(
+        build_operator_tree("3.3")
+            .unwrap()
+            .eval_float_with_context_mut(&mut context),
+        Ok(3.3)
+    );
+    assert_eq!(
+        build_operator_tree("3")
+            .unwrap()
+            .eval_int_with_context_mut(&mut context),
+        Ok(3)
+    );
+    assert_eq!(
+        build_operator_tree("3")
+            .unwrap()
+            .eval_number_with_context_mut(&mut context),
+        Ok(3.0)
+    );
+    assert_eq!(
+        build_operator_tree("true")
+            .unwrap()
+            .eval_boolean_with_context_mut(&mut context),
+        Ok(true)
+    );
+    assert_eq!(
+        build_operator_tree("3,3")
+            .unwrap()
+            .eval_tuple_with_context_mut(&mut context),
+        Ok(vec![Value::Int(3), Value::Int(3)])
+    );
+    assert_eq!(
+        build_operator_tree("")
+            .unwrap()
+            .eval_empty_with_context_mut(&mut context),
+        Ok(EMPTY_VALUE)
+    );
+}
+
+#[test]
+fn test_whitespace() {
+    assert!(eval_boolean("2 < = 3").is_err());
+
+    // All unicode whitespace separates tokens, including non-ASCII whitespace.
+    assert_eq!(eval("\t1\n+\r2\u{0B}*\u{0C}3 "), Ok(Value::from(7)));
+    assert_eq!(eval("1\u{A0}+\u{2003}2\u{3000}"), Ok(Value::from(3)));
+    assert_eq!(
+        eval_tuple("1,\u{2028}2"),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+
+    // Non-ASCII characters that are not whitespace are part of literals.
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut("größe = 2; ∆x = 3; größe * ∆x", &mut context),
+        Ok(Value::from(6))
+    );
+    assert_eq!(context.get_value("größe"), Some(&Value::from(2)));
+    assert_eq!(
+        eval("日本+1"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "日本".to_string(),
+            span: Some(0..6),
+        })
+    );
+}
+
+#[test]
+fn test_assignment() {
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_empty_with_context_mut("int = 3", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(
+        eval_empty_with_context_mut("float = 2.0", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(
+        eval_empty_with_context_mut("tuple = (1,1)", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(
+        eval_empty_with_context_mut("empty = ()", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(
+        eval_empty_with_context_mut("boolean = false", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+
+    assert_eq!(eval_int_with_context("int", &context), Ok(3));
+    assert_eq!(eval_float_with_context("float", &context), Ok(2.0));
+    assert_eq!(
+        eval_tuple_with_context("tuple", &context),
+        Ok(vec![1.into(), 1.into()])
+    );
+    assert_eq!(eval_empty_with_context("empty", &context), Ok(EMPTY_VALUE));
+    assert_eq!(eval_boolean_with_context("boolean", &context), Ok(false));
+
+    assert_eq!(
+        eval_empty_with_context_mut("b = a = 5", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(eval_empty_with_context("b", &context), Ok(EMPTY_VALUE));
+}
+
+#[test]
+fn test_assignment_targets() {
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_empty_with_context_mut("x = 3 + 4", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(context.get_value("x"), Some(&Value::from(7)));
+
+    // Targets after an opening parenthesis, a comma or a semicolon are plain identifiers as well.
+    assert_eq!(
+        eval_with_context_mut("(a = 1); b = 2; a + b", &mut context),
+        Ok(Value::from(3))
+    );
+    assert_eq!(
+        eval_with_context_mut("e = 4, f = 5", &mut context),
+        Ok(Value::from(vec![Value::Empty, Value::Empty]))
+    );
+
+    // Anything else is rejected before the expression is evaluated.
+    for (expression, offset) in &[
+        ("1 = 2", 2),
+        ("(a) = 2", 4),
+        ("a + b = 2", 6),
+        ("-a = 1", 3),
+        ("!a = true", 3),
+        ("\"a\" = 1", 4),
+        ("f(x) = 2", 5),
+        ("f x = 2", 4),
+        ("() = 1", 3),
+        ("= 1", 0),
+        ("a = = 1", 4),
+    ] {
+        assert_eq!(
+            build_operator_tree(expression),
+            Err(EvalexprError::InvalidAssignmentTarget {
+                span: *offset..*offset + 1
+            }),
+            "{}",
+            expression
+        );
+    }
+    assert_eq!(context.get_value("a"), Some(&Value::from(1)));
+}
+
+#[test]
+fn test_compound_assignment() {
+    let mut context = context_map! {
+        "i" => 7,
+        "f" => 1.5,
+        "s" => "ab",
+        "t" => Value::from(vec![Value::from(1), Value::from(2)]),
+        "flag" => true,
+    }
+    .unwrap();
+    for (expression, variable, expected) in &[
+        ("i += 3", "i", Value::from(10)),
+        ("i -= 4", "i", Value::from(6)),
+        ("i *= 1 + 2", "i", Value::from(18)),
+        ("i /= 4", "i", Value::from(4)),
+        ("i %= 3", "i", Value::from(1)),
+        ("f *= 2", "f", Value::from(3.0)),
+        ("f /= 4", "f", Value::from(0.75)),
+        ("s += \"c\"", "s", Value::from("abc")),
+        (
+            "t += (3, 4)",
+            "t",
+            Value::from(vec![1.into(), 2.into(), 3.into(), 4.into()]),
+        ),
+        ("flag &&= i > 0", "flag", Value::from(true)),
+        ("flag ||= false", "flag", Value::from(true)),
+        ("flag &&= false", "flag", Value::from(false)),
+    ] {
+        assert_eq!(
+            eval_with_context_mut(expression, &mut context),
+            Ok(Value::Empty),
+            "{}",
+            expression
+        );
+        assert_eq!(
+            context.get_value(variable),
+            Some(expected),
+            "{}",
+            expression
+        );
+    }
+
+    // The whitespace-free forms are tokenized the same, and compound assignments chain like plain ones.
+    assert_eq!(
+        eval_with_context_mut("i+=1;i*=5;j=i-=2;i", &mut context),
+        Ok(Value::from(8))
+    );
+    assert_eq!(context.get_value("j"), Some(&Value::Empty));
+
+    // Compound assignments are built as plain assignments.
+    assert_eq!(
+        build_operator_tree("x *= a + b")
+            .unwrap()
+            .to_minified_string(),
+        "x=x*(a+b)"
+    );
+    assert_eq!(
+        build_operator_tree("x ||= y; x")
+            .unwrap()
+            .to_minified_string(),
+        "x=x||y;x"
+    );
+
+    // Like `&&` and `||`, the right-hand side is skipped if the variable decides the result.
+    assert_eq!(
+        eval_with_context_mut(
+            "flag &&= missing; flag ||= true; flag ||= missing; flag",
+            &mut context
+        ),
+        Ok(Value::from(true))
+    );
+
+    // Arithmetic errors carry the value of the variable as operand, and leave the variable unchanged.
+    assert_eq!(
+        eval_with_context_mut("i /= 0", &mut context),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(8),
+            divisor: Value::from(0),
+        })
+    );
+    assert_eq!(
+        eval_with_context_mut("i %= 0", &mut context),
+        Err(EvalexprError::ModulationError {
+            dividend: Value::from(8),
+            divisor: Value::from(0),
+        })
+    );
+    context.set_value("i", Value::from(IntType::MAX)).unwrap();
+    assert_eq!(
+        eval_with_context_mut("i += 1", &mut context),
+        Err(EvalexprError::AdditionError {
+            augend: Value::from(IntType::MAX),
+            addend: Value::from(1),
+        })
+    );
+    assert_eq!(
+        eval_with_context_mut("s -= 1", &mut context),
+        Err(EvalexprError::expected_number(Value::from("abc")))
+    );
+    assert_eq!(context.get_value("i"), Some(&Value::from(IntType::MAX)));
+
+    // The variable needs to have a value already.
+    assert_eq!(
+        eval_with_context_mut("unset += 1", &mut context),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "unset".to_string(),
+            span: Some(0..5),
+        })
+    );
+    assert_eq!(context.get_value("unset"), None);
+
+    // Contexts that cannot be manipulated reject compound assignments like plain ones.
+    assert_eq!(
+        eval_with_context("i -= 1", &context),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(
+        eval_with_context_mut("x += 1", &mut EmptyContext),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "x".to_string(),
+            span: Some(0..1),
+        })
+    );
+
+    // The target of a compound assignment is checked like the one of a plain assignment.
+    assert_eq!(
+        build_operator_tree("(a) += 1"),
+        Err(EvalexprError::InvalidAssignmentTarget { span: 4..6 })
+    );
+    assert_eq!(
+        build_operator_tree("a || b &&= c"),
+        Err(EvalexprError::InvalidAssignmentTarget { span: 7..10 })
+    );
+    assert_eq!(
+        build_operator_tree("true ||= false"),
+        Err(EvalexprError::AssignmentToKeyword {
+            keyword: "true".to_string(),
+            span: 0..4,
+        })
+    );
+    assert!(build_operator_tree("a + = 1").is_err());
+
+    // The tokens written for a compound assignment are mapped to its operator.
+    let source = "total += price";
+    let (minified, source_map) = build_operator_tree(source)
+        .unwrap()
+        .to_minified_string_with_source_map(source)
+        .unwrap();
+    assert_eq!(minified, "total=total+price");
+    assert_eq!(source_map.map_range(5..12), Some(6..8));
+    assert_eq!(source_map.map_offset(12), Some(9));
+}
+
+#[test]
+fn test_destructuring_assignment() {
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "split_pair".into(),
+            Function::new(Box::new(|argument| {
+                let string = argument.as_string()?;
+                let (first, second) = string.split_at(string.len() / 2);
+                Ok(Value::from(vec![Value::from(first), Value::from(second)]))
+            })),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context_mut("(a, b) = split_pair(\"abcd\"); b + a", &mut context),
+        Ok(Value::from("cdab"))
+    );
+    assert_eq!(
+        eval_with_context_mut("(a, b) = (b, a); a", &mut context),
+        Ok(Value::from("cd"))
+    );
+
+    // Nested tuples of variables unpack nested tuples.
+    assert_eq!(
+        eval_empty_with_context_mut("((x, y), (z, w)) = ((1, 2.5), (true, ())) ", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(
+        eval_with_context("(x, y, z, w)", &context),
+        Ok(Value::from(vec![
+            Value::from(1),
+            Value::from(2.5),
+            Value::from(true),
+            Value::Empty
+        ]))
+    );
+    assert_eq!(
+        eval_with_context_mut("(x, t) = (3, (4, 5)); t", &mut context),
+        Ok(Value::from(vec![Value::from(4), Value::from(5)]))
+    );
+
+    // The right-hand side is evaluated once.
+    let calls = Rc::new(Cell::new(0));
+    let counted_calls = calls.clone();
+    context
+        .set_function(
+            "pair".into(),
+            Function::new(Box::new(move |_| {
+                counted_calls.set(counted_calls.get() + 1);
+                Ok(Value::from(vec![Value::from(6), Value::from(7)]))
+            })),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context_mut("(p, q) = pair(); p * q", &mut context),
+        Ok(Value::from(42))
+    );
+    assert_eq!(calls.get(), 1);
+
+    // Values that do not fit the variables are rejected before any variable is assigned.
+    for (expression, error) in [
+        (
+            "(p, q) = (10, 20, 30)",
+            EvalexprError::DestructuringLengthMismatch {
+                expected: 2,
+                actual: 3,
+            },
+        ),
+        (
+            "(p, (q, r)) = (10, (20, 30, 40))",
+            EvalexprError::DestructuringLengthMismatch {
+                expected: 2,
+                actual: 3,
+            },
+        ),
+        (
+            "(p, q, r) = (10, 20)",
+            EvalexprError::DestructuringLengthMismatch {
+                expected: 3,
+                actual: 2,
+            },
+        ),
+        (
+            "(p, q) = 10",
+            EvalexprError::expected_tuple(Value::from(10)),
+        ),
+        (
+            "((p, q), r) = (10, 20)",
+            EvalexprError::expected_tuple(Value::from(10)),
+        ),
+        // Without parentheses, only the first element is assigned to the tuple.
+        (
+            "(p, q) = 10, 20",
+            EvalexprError::expected_tuple(Value::from(10)),
+        ),
+    ] {
+        assert_eq!(
+            eval_with_context_mut(expression, &mut context),
+            Err(error),
+            "{}",
+            expression
+        );
+    }
+    assert_eq!(context.get_value("p"), Some(&Value::from(6)));
+    assert_eq!(context.get_value("q"), Some(&Value::from(7)));
+
+    // Contexts that cannot be manipulated reject the assignment after evaluating the right-hand side.
+    assert_eq!(
+        eval_with_context("(p, q) = pair()", &context),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(calls.get(), 2);
+    assert_eq!(
+        eval_with_context_mut("(x, y) = (1, 2)", &mut EmptyContext),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+
+    // Tuples of variables may only contain identifiers, and at least two elements on each level.
+    for (expression, offset) in &[
+        ("(x, 1) = (1, 2)", 7),
+        ("(x, y + 1) = (1, 2)", 11),
+        ("(x, f(y)) = (1, 2)", 10),
+        ("((x), y) = (1, 2)", 9),
+        ("(x) = 1", 4),
+        ("() = ()", 3),
+        ("f(x, y) = (1, 2)", 8),
+        ("z + (x, y) = (1, 2)", 11),
+    ] {
+        assert_eq!(
+            build_operator_tree(expression),
+            Err(EvalexprError::InvalidAssignmentTarget {
+                span: *offset..*offset + 1
+            }),
+            "{}",
+            expression
+        );
+    }
+    assert_eq!(
+        build_operator_tree("(x, y) += (1, 2)"),
+        Err(EvalexprError::InvalidAssignmentTarget { span: 7..9 })
+    );
+
+    // The variables of the target are not read, and are written back as identifiers.
+    let tree = build_operator_tree("(x, (y, z)) = t; x").unwrap();
+    assert_eq!(
+        tree.iter_variable_identifiers().collect::<Vec<_>>(),
+        ["t", "x"]
+    );
+    assert_eq!(tree.to_minified_string(), "(x,(y,z))=t;x");
+    let mut renames = HashMap::new();
+    renames.insert("y".to_string(), "b".to_string());
+    assert_eq!(tree.to_renamed_string(&renames), "(x,(b,z))=t;x");
+}
+
+#[test]
+fn test_expression_chaining() {
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_int_with_context_mut("a = 5; a = a + 2; a", &mut context),
+        Ok(7)
+    );
+    assert_eq!(
+        eval_int_with_context_mut("a = 5; b = a * 2; a + b", &mut context),
+        Ok(15)
+    );
+
+    // A trailing semicolon makes the chain return the empty value, but the assignments still happen.
+    assert_eq!(
+        eval_with_context_mut("a = 1; b = a + 1;", &mut context),
+        Ok(Value::Empty)
+    );
+    assert_eq!(context.get_value("b"), Some(&Value::from(2)));
+    assert_eq!(eval("1; 2;"), Ok(Value::Empty));
+
+    // Empty segments are skipped.
+    assert_eq!(
+        eval_int_with_context_mut("a = 3;; ; a", &mut context),
+        Ok(3)
+    );
+    assert_eq!(eval(";"), Ok(Value::Empty));
+    assert_eq!(eval(";;"), Ok(Value::Empty));
+    assert_eq!(eval("; 4"), Ok(Value::from(4)));
+
+    // Parenthesized chains return their last value to the surrounding expression.
+    assert_eq!(eval("(1; 2) * 3"), Ok(Value::from(6)));
+
+    // An error aborts the whole chain, so later segments are not evaluated.
+    assert_eq!(
+        eval_with_context_mut("a = 10; undefined; a = 20", &mut context),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "undefined".to_string(),
+            span: Some(8..17),
+        })
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::from(10)));
+    assert_eq!(
+        eval_with_context_mut("a = 30; a = \"text\"; a = 40", &mut context),
+        Err(EvalexprError::expected_int(Value::from("text")))
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::from(30)));
+}
+
+#[test]
+fn test_strings() {
+    let mut context = HashMapContext::new();
+    assert_eq!(eval("\"string\""), Ok(Value::from("string")));
+    assert_eq!(
+        eval_with_context_mut("a = \"a string\"", &mut context),
+        Ok(Value::Empty)
+    );
+    assert_eq!(
+        eval_boolean_with_context("a == \"a string\"", &context),
+        Ok(true)
+    );
+    assert_eq!(eval("\"a\" + \"b\""), Ok(Value::from("ab")));
+    assert_eq!(eval("\"a\" > \"b\""), Ok(Value::from(false)));
+    assert_eq!(eval("\"a\" < \"b\""), Ok(Value::from(true)));
+
+    // Escape sequences and non-ASCII characters within strings.
+    assert_eq!(
+        eval("\"a \\\"quoted\\\" \\\\ ünïcödé\"+\"!\""),
+        Ok(Value::from("a \"quoted\" \\ ünïcödé!"))
+    );
+    assert_eq!(eval("\"\""), Ok(Value::from("")));
+    assert_eq!(eval("\"+-*/ ()\""), Ok(Value::from("+-*/ ()")));
+    assert_eq!(
+        eval("\"\\ä\""),
+        Err(EvalexprError::IllegalEscapeSequence("\\ä".to_string()))
+    );
+    assert_eq!(
+        eval("\"abc\\"),
+        Err(EvalexprError::IllegalEscapeSequence("\\".to_string()))
+    );
+    assert_eq!(
+        eval("\"tab\\tnew\\nline\\r\""),
+        Ok(Value::from("tab\tnew\nline\r"))
+    );
+    let context = context_map! { "name" => "John \"The Hammer\"" }.unwrap();
+    assert_eq!(
+        eval_with_context(r#"name == "John \"The Hammer\"""#, &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval("\"\\0\""),
+        Err(EvalexprError::IllegalEscapeSequence("\\0".to_string()))
+    );
+    // An unterminated string is an error instead of ending with the expression.
+    assert_eq!(
+        eval("\"abc"),
+        Err(EvalexprError::UnterminatedString("abc".to_string()))
+    );
+    assert_eq!(
+        eval("1 + \"a\\\" b"),
+        Err(EvalexprError::UnterminatedString("a\\\" b".to_string()))
+    );
+    // Strings and literals are separate tokens, even if they are not separated by whitespace.
+    assert_eq!(
+        eval("a\"b\""),
+        Err(EvalexprError::FunctionIdentifierNotFound("a".to_string()))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let strings = ["3", "4+4", "21^(2*2)--3>5||!true"];
+
+    for string in &strings {
+        let manual_tree = build_operator_tree(string).unwrap();
+        let serde_tree: Node = ron::de::from_str(&format!("\"{}\"", string)).unwrap();
+        assert_eq!(manual_tree.eval(), serde_tree.eval());
+    }
+}
+
+#[test]
+fn test_tuple_definitions() {
+    assert_eq!(eval_empty("()"), Ok(()));
+    assert_eq!(eval_int("(3)"), Ok(3));
+    assert_eq!(
+        eval_tuple("(3, 4)"),
+        Ok(vec![Value::from(3), Value::from(4)])
+    );
+    assert_eq!(
+        eval_tuple("2, (5, 6)"),
+        Ok(vec![
+            Value::from(2),
+            Value::from(vec![Value::from(5), Value::from(6)])
+        ])
+    );
+    assert_eq!(eval_tuple("1, 2"), Ok(vec![Value::from(1), Value::from(2)]));
+    assert_eq!(
+        eval_tuple("1, 2, 3, 4"),
+        Ok(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3),
+            Value::from(4)
+        ])
+    );
+    assert_eq!(
+        eval_tuple("(1, 2, 3), 5, 6, (true, false, 0)"),
+        Ok(vec![
+            Value::from(vec![Value::from(1), Value::from(2), Value::from(3)]),
+            Value::from(5),
+            Value::from(6),
+            Value::from(vec![Value::from(true), Value::from(false), Value::from(0)])
+        ])
+    );
+    assert_eq!(
+        eval_tuple("1, (2)"),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    assert_eq!(
+        eval_tuple("1, ()"),
+        Ok(vec![Value::from(1), Value::from(())])
+    );
+    assert_eq!(
+        eval_tuple("1, ((2))"),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+}
+
+#[test]
+fn test_trailing_comma() {
+    // A trailing comma before a closing brace ends the tuple.
+    assert_eq!(eval("(3,)"), Ok(Value::Tuple(vec![Value::Int(3)])));
+    assert_eq!(
+        eval("(1, 2,)"),
+        Ok(Value::from(vec![Value::from(1), Value::from(2)]))
+    );
+    assert_eq!(
+        eval("((1, 2),)"),
+        Ok(Value::from(vec![Value::from(vec![
+            Value::from(1),
+            Value::from(2)
+        ])]))
+    );
+    assert_eq!(
+        eval("(1, 2) + (3,)"),
+        Ok(Value::from(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3)
+        ]))
+    );
+    #[cfg(feature = "builtin_math")]
+    assert_eq!(eval("max(1, 2, )"), Ok(Value::from(2)));
+    // The argument of a call is the tuple, so it has one element as well.
+    let context = context_map! {
+        "id" => Function::new(Box::new(|argument| Ok(argument.clone())))
+    }
+    .unwrap();
+    assert_eq!(
+        eval_with_context("id(1,)", &context),
+        Ok(Value::from(vec![Value::from(1)]))
+    );
+
+    // Tuples with one element are minified with their trailing comma.
+    for expression in ["(3,)", "((3,),)", "-(3,)", "(1,) + (2,)", "id(1,)"] {
+        let tree = build_operator_tree(expression).unwrap();
+        assert_eq!(
+            build_operator_tree(&tree.to_minified_string()).unwrap(),
+            tree,
+            "{:?}",
+            expression
+        );
+    }
+
+    // Anywhere else, a comma needs to be followed by another element.
+    assert_eq!(
+        eval("a = 1, 2,; a"),
+        Err(EvalexprError::TrailingComma { span: 8..9 })
+    );
+    assert_eq!(
+        eval("1, /* last */"),
+        Err(EvalexprError::TrailingComma { span: 1..2 })
+    );
+
+    // A comma also needs to follow an element.
+    assert_eq!(eval("(,)"), Err(EvalexprError::LeadingComma { span: 1..2 }));
+    assert_eq!(
+        eval("(, 1)"),
+        Err(EvalexprError::LeadingComma { span: 1..2 })
+    );
+    assert_eq!(
+        eval("(a,, b)"),
+        Err(EvalexprError::LeadingComma { span: 3..4 })
+    );
+    assert_eq!(
+        eval("f(,)"),
+        Err(EvalexprError::LeadingComma { span: 2..3 })
+    );
+    assert_eq!(
+        eval("1 + , 2"),
+        Err(EvalexprError::LeadingComma { span: 4..5 })
+    );
+    // An empty last element needs to be written explicitly.
+    assert_eq!(
+        eval_tuple("(1, ())"),
+        Ok(vec![Value::from(1), Value::from(())])
+    );
+
+    // Trees with an empty last element can still be created by substitution, and are minified to a parsable expression.
+    let mut tree = build_operator_tree("1, x").unwrap();
+    let mut substitutions = std::collections::HashMap::new();
+    substitutions.insert("x".to_string(), build_operator_tree("").unwrap());
+    tree.substitute_variables(&substitutions);
+    assert_eq!(tree.to_minified_string(), "1,()");
+    assert_eq!(eval(&tree.to_minified_string()), tree.eval());
+}
+
+#[test]
+fn test_recursion_limit() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let context = Rc::new(RefCell::new(HashMapContext::new()));
+    let ping = Rc::new(build_operator_tree("pong(x)").unwrap());
+    let pong = Rc::new(build_operator_tree("ping(x)").unwrap());
+
+    for (name, node) in &[("ping", ping.clone()), ("pong", pong.clone())] {
+        let weak_context = Rc::downgrade(&context);
+        let node = node.clone();
+        context
+            .borrow_mut()
+            .set_function(
+                name.to_string(),
+                Function::new(Box::new(move |_| {
+                    let context = weak_context.upgrade().unwrap();
+                    let context = context.borrow();
+                    node.eval_with_context(&*context)
+                })),
+            )
+            .unwrap();
+    }
+    context.borrow_mut().set_value("x", Value::Int(1)).unwrap();
+
+    assert_eq!(
+        ping.eval_with_context(&*context.borrow()),
+        Err(EvalexprError::RecursionLimitExceeded {
+            max_depth: EvalConfig::DEFAULT_MAX_RECURSION_DEPTH
+        })
+    );
+
+    // The default state is left clean after the failed evaluation.
+    assert_eq!(
+        eval_with_context("x + 1", &*context.borrow()),
+        Ok(Value::Int(2))
+    );
+}
+
+#[test]
+fn test_recursion_limit_with_shared_state() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let state = Rc::new(EvalState::new(EvalConfig {
+        max_recursion_depth: 3,
+        ..Default::default()
+    }));
+    let context = Rc::new(RefCell::new(HashMapContext::new()));
+
+    let function_state = state.clone();
+    let weak_context = Rc::downgrade(&context);
+    context
+        .borrow_mut()
+        .set_function(
+            "countdown".into(),
+            Function::new(Box::new(move |argument| {
+                let n = argument.as_int()?;
+                if n <= 0 {
+                    Ok(Value::Int(function_state.recursion_depth() as IntType))
+                } else {
+                    let context = weak_context.upgrade().unwrap();
+                    let context = context.borrow();
+                    build_operator_tree(&format!("countdown({})", n - 1))?
+                        .eval_with_context_and_state(&*context, &function_state)
+                }
+            })),
+        )
+        .unwrap();
+
+    assert_eq!(
+        build_operator_tree("countdown(2)")
+            .unwrap()
+            .eval_with_context_and_state(&*context.borrow(), &state),
+        Ok(Value::Int(3))
+    );
+    assert_eq!(state.recursion_depth(), 0);
+    assert_eq!(
+        build_operator_tree("countdown(3)")
+            .unwrap()
+            .eval_with_context_and_state(&*context.borrow(), &state),
+        Err(EvalexprError::RecursionLimitExceeded { max_depth: 3 })
+    );
+    assert_eq!(state.recursion_depth(), 0);
+}
+
+#[test]
+fn test_drop_deep_tree() {
+    // Each `+` takes the sum before it as its left operand, so the tree is as deep as the sum is long.
+    let terms = 200_000;
+    let expression = format!("1{}", "+1".repeat(terms - 1));
+    let tree = build_operator_tree(&expression).unwrap();
+    // The iterator visits the additions and the constants below the root node.
+    assert_eq!(tree.iter().count(), 2 * terms - 1);
+    drop(tree);
+
+    // Named expressions are dropped with their context.
+    let mut context = HashMapContext::new();
+    context
+        .set_expression("deep".into(), build_operator_tree(&expression).unwrap())
+        .unwrap();
+    drop(context);
+
+    // Clones share their children, and the last clone drops them.
+    let tree = build_operator_tree(&expression).unwrap();
+    let clone = tree.clone();
+    drop(tree);
+    assert_eq!(clone.iter().count(), 2 * terms - 1);
+    drop(clone);
+}
+
+#[test]
+fn test_substitute_variables() {
+    use std::collections::HashMap;
+
+    let base = build_operator_tree(
+        "fee = if(plan == \"pro\", base_fee * 2, base_fee); fee * (1 + tax_rate) - discount",
+    )
+    .unwrap();
+    let context = context_map! { "base_fee" => 10.0 }.unwrap();
+
+    let tenants = [
+        ("pro", "0.2", "1.5"),
+        ("free", "0", "0"),
+        ("pro", "0.1", "base_fee / 2"),
+    ];
+    for (plan, tax_rate, discount) in tenants.iter() {
+        let plan = format!("\"{}\"", plan);
+        let mut substitutions = HashMap::new();
+        substitutions.insert("plan".to_string(), build_operator_tree(&plan).unwrap());
+        substitutions.insert(
+            "tax_rate".to_string(),
+            build_operator_tree(tax_rate).unwrap(),
+        );
+        substitutions.insert(
+            "discount".to_string(),
+            build_operator_tree(discount).unwrap(),
+        );
+
+        let mut variant = base.clone();
+        variant.substitute_variables(&substitutions);
+        let expected = format!(
+            "fee = if({} == \"pro\", base_fee * 2, base_fee); fee * (1 + ({})) - ({})",
+            plan, tax_rate, discount
+        );
+        assert_eq!(
+            variant.eval_with_context(&context),
+            eval_with_context(&expected, &context),
+            "{}",
+            expected
+        );
+        assert_eq!(
+            variant.to_minified_string(),
+            build_operator_tree(&expected).unwrap().to_minified_string()
+        );
+    }
+
+    // The base tree is not changed, and assigned variables are not substituted.
+    let mut substitutions = HashMap::new();
+    substitutions.insert("fee".to_string(), build_operator_tree("1").unwrap());
+    let mut variant = base.clone();
+    variant.substitute_variables(&substitutions);
+    assert_eq!(
+        variant.to_minified_string(),
+        "fee=if(plan==\"pro\",base_fee*2,base_fee);1*(1+tax_rate)-discount"
+    );
+    assert_eq!(
+        base.to_minified_string(),
+        "fee=if(plan==\"pro\",base_fee*2,base_fee);fee*(1+tax_rate)-discount"
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_crypto")]
+fn test_crypto_functions() {
+    assert_eq!(
+        eval("str::sha256(\"\")"),
+        Ok(Value::from(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        ))
+    );
+    assert_eq!(
+        eval("str::sha256(\"abc\")"),
+        Ok(Value::from(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        ))
+    );
+    // Messages of 56 bytes and more need a second block for the padding.
+    assert_eq!(
+        eval("str::sha256(\"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq\")"),
+        Ok(Value::from(
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        ))
+    );
+    // The digest is computed from the UTF-8 bytes of the string.
+    assert_eq!(
+        eval("str::sha256(\"ä\")"),
+        Ok(Value::from(
+            "33e6d73fee82904c8d7afb78de1154d1e8dc2a0edb08120e63df5b9385c2d9cc"
+        ))
+    );
+    assert_eq!(
+        eval("str::sha256(1)"),
+        Err(EvalexprError::expected_string(Value::from(1)))
+    );
+}
+
+#[test]
+#[cfg(not(feature = "builtin_crypto"))]
+fn test_builtin_crypto_disabled() {
+    match eval("str::sha256(\"abc\")") {
+        Err(error @ EvalexprError::FunctionIdentifierNotFound(_)) => assert_eq!(
+            error.to_string(),
+            "Function identifier is not bound to anything by context: \"str::sha256\". \
+             The builtin function with this identifier requires the feature flag \"builtin_crypto\"."
+        ),
+        result => panic!("Expected FunctionIdentifierNotFound, got {:?}", result),
+    }
+}
+
+#[test]
+#[cfg(all(feature = "unicode_support", feature = "builtin_string"))]
+fn test_unicode_functions() {
+    // German sharp s folds to "ss", which `str::to_lowercase` does not do.
+    assert_eq!(
+        eval("str::casefold(\"Straße\")"),
+        Ok(Value::from("strasse"))
+    );
+    assert_eq!(
+        eval("str::casefold(\"Straße\") == str::casefold(\"STRASSE\")"),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval("str::to_lowercase(\"Straße\") == str::to_lowercase(\"STRASSE\")"),
+        Ok(Value::from(false))
+    );
+    // Turkish dotted capital I folds to i with a combining dot above, and dotless i stays as is.
+    assert_eq!(
+        eval("str::casefold(\"İstanbul\")"),
+        Ok(Value::from("i\u{307}stanbul"))
+    );
+    assert_eq!(eval("str::casefold(\"ılık\")"), Ok(Value::from("ılık")));
+    assert_eq!(eval("str::casefold(\"\")"), Ok(Value::from("")));
+
+    assert_eq!(eval("str::width(\"hello\")"), Ok(Value::from(5)));
+    // Full-width latin letters and CJK ideographs take up two columns.
+    assert_eq!(eval("str::width(\"ｈｅｌｌｏ\")"), Ok(Value::from(10)));
+    assert_eq!(eval("str::width(\"日本語\")"), Ok(Value::from(6)));
+    assert_eq!(eval("str::width(\"a日b\")"), Ok(Value::from(4)));
+    assert_eq!(eval("str::width(\"\")"), Ok(Value::from(0)));
+
+    assert_eq!(
+        eval("str::casefold(3)"),
+        Err(EvalexprError::expected_string(Value::from(3)))
+    );
+    assert_eq!(
+        eval("str::width(true)"),
+        Err(EvalexprError::expected_string(Value::from(true)))
+    );
+}
+
+#[test]
+#[cfg(not(feature = "unicode_support"))]
+fn test_unicode_functions_disabled() {
+    assert_eq!(
+        eval("str::casefold(\"Straße\")"),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "str::casefold".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_tuple_concatenation() {
+    let context = context_map! {
+        "empty" => Value::Tuple(TupleType::new()),
+        "pair" => Value::from(vec![Value::from(1), Value::from(2)])
+    }
+    .unwrap();
+
+    assert_eq!(
+        eval_tuple("(1, 2) + (3, 4)"),
+        Ok(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3),
+            Value::from(4)
+        ])
+    );
+    assert_eq!(
+        eval_tuple_with_context("empty + empty", &context),
+        Ok(vec![])
+    );
+    assert_eq!(
+        eval_tuple_with_context("empty + pair", &context),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    assert_eq!(
+        eval_tuple_with_context("pair + empty", &context),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    // Nested tuples are not flattened.
+    assert_eq!(
+        eval_tuple("((1, 2), 3) + (4, (5, 6))"),
+        Ok(vec![
+            Value::from(vec![Value::from(1), Value::from(2)]),
+            Value::from(3),
+            Value::from(4),
+            Value::from(vec![Value::from(5), Value::from(6)])
+        ])
+    );
+    assert_eq!(
+        eval_tuple_with_context("(pair, 3) + (pair, 4)", &context),
+        Ok(vec![
+            Value::from(vec![Value::from(1), Value::from(2)]),
+            Value::from(3),
+            Value::from(vec![Value::from(1), Value::from(2)]),
+            Value::from(4)
+        ])
+    );
+
+    // A single value is appended by wrapping it into a tuple of its own.
+    assert_eq!(
+        eval_tuple_with_context("pair + (3,)", &context),
+        Ok(vec![Value::from(1), Value::from(2), Value::from(3)])
+    );
+    assert_eq!(
+        eval_tuple_with_context("pair + ((3, 4),)", &context),
+        Ok(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(vec![Value::from(3), Value::from(4)])
+        ])
+    );
+
+    assert_eq!(
+        eval_with_context("pair + 3", &context),
+        Err(EvalexprError::AdditionError {
+            augend: Value::from(vec![Value::from(1), Value::from(2)]),
+            addend: Value::from(3)
+        })
+    );
+    assert_eq!(
+        eval_with_context("\"a\" + pair", &context),
+        Err(EvalexprError::AdditionError {
+            augend: Value::from("a"),
+            addend: Value::from(vec![Value::from(1), Value::from(2)])
+        })
+    );
+    assert_eq!(
+        eval_with_context("empty + ()", &context),
+        Err(EvalexprError::AdditionError {
+            augend: Value::Tuple(TupleType::new()),
+            addend: Value::Empty
+        })
+    );
+    assert_eq!(
+        eval("\"a\" + 1"),
+        Err(EvalexprError::AdditionError {
+            augend: Value::from("a"),
+            addend: Value::from(1)
+        })
+    );
+    assert_eq!(
+        eval("true + 1"),
+        Err(EvalexprError::expected_number_or_string(Value::from(true)))
+    );
+}
+
+#[test]
+#[cfg(feature = "builtin_tuple")]
+fn test_concat_function() {
+    let context = context_map! {
+        "empty" => Value::Tuple(TupleType::new())
+    }
+    .unwrap();
+
+    assert_eq!(
+        eval_tuple("concat((1, 2), (3, 4), (5, 6))"),
+        Ok(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3),
+            Value::from(4),
+            Value::from(5),
+            Value::from(6)
+        ])
+    );
+    assert_eq!(
+        eval_tuple_with_context("concat(empty, (1, 2), empty)", &context),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    assert_eq!(
+        eval_tuple_with_context("concat(empty, empty)", &context),
+        Ok(vec![])
+    );
+    assert_eq!(
+        eval_tuple("concat(((1, 2), 3), (4, 5))"),
+        eval_tuple("((1, 2), 3) + (4, 5)")
+    );
+    assert_eq!(
+        eval_string("concat(\"a\", \"b\", \"c\")"),
+        Ok("abc".to_string())
+    );
+    assert_eq!(
+        eval("concat((1, 2), 3)"),
+        Err(EvalexprError::expected_tuple(Value::from(3)))
+    );
+    assert_eq!(
+        eval("concat(\"a\", (1, 2))"),
+        Err(EvalexprError::expected_string(Value::from(vec![
+            Value::from(1),
+            Value::from(2)
+        ])))
+    );
+    assert_eq!(
+        eval_tuple("concat((1, 2), (3,))"),
+        Ok(vec![Value::from(1), Value::from(2), Value::from(3)])
+    );
+
+    // A single argument is returned unchanged.
+    assert_eq!(
+        eval_tuple("concat((1, 2))"),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    assert_eq!(eval_string("concat(\"a\")"), Ok("a".to_string()));
+    assert_eq!(
+        eval_tuple("concat(((1, 2),))"),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    assert_eq!(
+        eval_tuple_with_context("concat(empty)", &context),
+        Ok(vec![])
+    );
+    // As the arguments of a call are a tuple, this is the same as the single argument `(1, 2)`.
+    assert_eq!(
+        eval_tuple("concat(1, 2)"),
+        Ok(vec![Value::from(1), Value::from(2)])
+    );
+    assert_eq!(
+        eval("concat(1)"),
+        Err(EvalexprError::expected_tuple(Value::from(1)))
+    );
+}
+
+/// Creates a context with a function `log` that records its argument and returns it.
+fn recording_context() -> (HashMapContext, std::rc::Rc<std::cell::RefCell<Vec<Value>>>) {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let function_log = log.clone();
+    let context = context_map! {
+        "log" => Function::new(Box::new(move |argument| {
+            function_log.borrow_mut().push(argument.clone());
+            Ok(argument.clone())
+        })),
+        "first" => Function::new(Box::new(|argument| {
+            Ok(expect_tuple(argument)?[0].clone())
+        }))
+    }
+    .unwrap();
+    (context, log)
+}
+
+#[test]
+fn test_tuple_evaluation_order() {
+    let (mut context, log) = recording_context();
+
+    assert_eq!(
+        eval_tuple_with_context("(log(1), log(2), log(3))", &context),
+        Ok(vec![Value::from(1), Value::from(2), Value::from(3)])
+    );
+    assert_eq!(
+        *log.borrow(),
+        vec![Value::from(1), Value::from(2), Value::from(3)]
+    );
+    log.borrow_mut().clear();
+
+    // Duplicates are evaluated once per occurrence.
+    assert_eq!(
+        eval_tuple_with_context("log(\"a\"), log(\"a\"), log(\"b\")", &context),
+        Ok(vec![Value::from("a"), Value::from("a"), Value::from("b")])
+    );
+    assert_eq!(
+        *log.borrow(),
+        vec![Value::from("a"), Value::from("a"), Value::from("b")]
+    );
+    log.borrow_mut().clear();
+
+    // Nested tuples are evaluated depth-first from left to right.
+    assert_eq!(
+        eval_tuple_with_context("(log(1), (log(2), log(3)), log(4))", &context),
+        Ok(vec![
+            Value::from(1),
+            Value::from(vec![Value::from(2), Value::from(3)]),
+            Value::from(4)
+        ])
+    );
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3),
+            Value::from(4)
+        ]
+    );
+    log.borrow_mut().clear();
+
+    // Tuples inside function arguments are evaluated completely, even if the function discards parts of them.
+    assert_eq!(
+        eval_with_context("first((log(1), log(2)), log(3))", &context),
+        Ok(Value::from(vec![Value::from(1), Value::from(2)]))
+    );
+    assert_eq!(
+        *log.borrow(),
+        vec![Value::from(1), Value::from(2), Value::from(3)]
+    );
+    log.borrow_mut().clear();
+
+    // Discarded tuples in expression chains are evaluated as well.
+    assert_eq!(
+        eval_with_context_mut("(log(1), log(2)); a = (log(3), log(4)); 5", &mut context),
+        Ok(Value::from(5))
+    );
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3),
+            Value::from(4)
+        ]
+    );
+}
+
+#[test]
+fn test_short_circuit_evaluation() {
+    let (context, log) = recording_context();
+
+    assert_eq!(
+        eval_with_context("false && (log(1), log(2)) == (1, 2)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval_with_context("true || (log(1), log(2)) == (1, 2)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("log(false) && log(true)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(*log.borrow(), vec![Value::from(false)]);
+    log.borrow_mut().clear();
+
+    // Branches that are not skipped evaluate their tuples in order.
+    assert_eq!(
+        eval_with_context("true && (log(1), log(2)) == (1, 2)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("false || (log(3), log(4)) == (1, 2)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3),
+            Value::from(4)
+        ]
+    );
+    log.borrow_mut().clear();
+
+    // The right operand is skipped even if it would fail.
+    assert_eq!(eval("false && 1 + true"), Ok(Value::from(false)));
+    assert_eq!(eval("true || unknown"), Ok(Value::from(true)));
+    assert_eq!(
+        eval("true && unknown"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "unknown".to_string(),
+            span: Some(8..15),
+        })
+    );
+    // A non-boolean left operand fails before the right operand is evaluated.
+    assert_eq!(
+        eval_with_context("1 && log(1)", &context),
+        Err(EvalexprError::expected_boolean(Value::from(1)))
+    );
+    assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn test_assertion_macros() {
+    let context = context_map! {
+        "a" => 3,
+        "name" => "evalexpr"
+    }
+    .unwrap();
+
+    assert_eval_eq!("a * 2", 6, &context);
+    assert_eval_eq!("name + \"!\"", "evalexpr!", &context);
+    assert_eval_eq!("(1, true)", vec![Value::from(1), Value::from(true)]);
+    assert_eval_err!(
+        "a + true",
+        EvalexprError::ExpectedNumberOrString { .. },
+        &context
+    );
+    assert_eval_err!("1 +", EvalexprError::WrongOperatorArgumentAmount { .. });
+    assert_eval_type!("a / 2", ValueType::Int, &context);
+    assert_eval_type!("a ^ 2", ValueType::Int, &context);
+    assert_eval_type!("()", ValueType::Empty);
+}
+
+#[test]
+#[should_panic(
+    expected = "  parsed tree:\n    Root\n      Add\n        Const(Int 1)\n        Const(Int 2)\n"
+)]
+fn test_assertion_macro_failure_message() {
+    assert_eval_eq!("1 + 2", 4);
+}
+
+#[test]
+fn test_max_result_bytes() {
+    let state = EvalState::new(EvalConfig {
+        max_result_bytes: Some(1024),
+        ..Default::default()
+    });
+
+    // Each step doubles the length of the string.
+    let doubling = format!("s = \"ab\"{}; s", "; s = s + s".repeat(40));
+    let result = build_operator_tree(&doubling)
+        .unwrap()
+        .eval_with_context_mut_and_state(&mut HashMapContext::new(), &state);
+    match result {
+        Err(EvalexprError::ValueTooLarge { limit, attempted }) => {
+            assert_eq!(limit, 1024);
+            assert!(attempted > 1024);
+            assert!(attempted < 4 * 1024);
+        },
+        result => panic!("Expected ValueTooLarge, got {:?}", result),
+    }
+    assert!(state.allocated_bytes() <= 1024);
+
+    // Each step doubles the amount of nested tuple elements.
+    let nesting = format!("t = (1, 2){}; ()", "; t = (t, t)".repeat(40));
+    assert!(matches!(
+        build_operator_tree(&nesting)
+            .unwrap()
+            .eval_with_context_mut_and_state(&mut HashMapContext::new(), &state),
+        Err(EvalexprError::ValueTooLarge { limit: 1024, .. })
+    ));
+
+    // Tuple concatenation is counted as well.
+    let concatenation = format!("t = (1, 2){}; ()", "; t = t + t".repeat(40));
+    assert!(matches!(
+        build_operator_tree(&concatenation)
+            .unwrap()
+            .eval_with_context_mut_and_state(&mut HashMapContext::new(), &state),
+        Err(EvalexprError::ValueTooLarge { limit: 1024, .. })
+    ));
+
+    // Small evaluations are not affected, and the count restarts with each evaluation.
+    let small = format!("s = \"ab\"{}; s", "; s = s + s".repeat(3));
+    for _ in 0..10 {
+        assert_eq!(
+            build_operator_tree(&small)
+                .unwrap()
+                .eval_with_context_mut_and_state(&mut HashMapContext::new(), &state),
+            Ok(Value::from("ab".repeat(8)))
+        );
+    }
+    assert!(state.allocated_bytes() > 0);
+    assert!(state.allocated_bytes() <= 1024);
+
+    // Without a limit, nothing is counted.
+    let state = EvalState::default();
+    assert_eq!(
+        build_operator_tree(&small)
+            .unwrap()
+            .eval_with_context_mut_and_state(&mut HashMapContext::new(), &state),
+        Ok(Value::from("ab".repeat(8)))
+    );
+    assert_eq!(state.allocated_bytes(), 0);
+}
+
+#[test]
+fn test_eval_arena() {
+    fn context() -> HashMapContext {
+        let mut context = context_map! {
+            "first" => "Ada",
+            "last" => "Lovelace",
+            "n" => 3,
+            "pair" => Value::Tuple(vec![Value::from("left"), Value::from(2)]),
+            "flag" => true,
+        }
+        .unwrap();
+        context
+            .set_function(
+                "shout".into(),
+                Function::new(Box::new(|argument| {
+                    Ok(Value::from(argument.as_string()?.to_uppercase()))
+                })),
+            )
+            .unwrap();
+        context
+            .set_expression(
+                "full_name".into(),
+                build_operator_tree("first + \" \" + last").unwrap(),
+            )
+            .unwrap();
+        context
+    }
+
+    let expressions = [
+        "first + \" \" + last",
+        "\"<\" + shout(first) + \">\"",
+        "full_name + \"!\" + full_name",
+        "(first, (last, n), ())",
+        "pair + (first, pair) + ()",
+        "s = first; s = s + s; s = s + s; s",
+        "t = (first, n); t = t + t; (t, t)",
+        "label = last + \", \" + first; (label, len(label))",
+        "flag || first + 1",
+        "!flag && first + 1",
+        "first + n",
+        "first + unknown",
+        "first - last",
+        "pair + first",
+        "(first, last) == (\"Ada\", \"Lovelace\")",
+        "str::to_uppercase(first) + str::substring(last, 0, 4)",
+        "first; last; n",
+    ];
+
+    let arena = EvalArena::new();
+    for max_result_bytes in [None, Some(16)] {
+        let plain = EvalState::new(EvalConfig {
+            max_result_bytes,
+            ..Default::default()
+        });
+        let pooled = EvalState::new(EvalConfig {
+            max_result_bytes,
+            arena: Some(arena.clone()),
+            ..Default::default()
+        });
+        // Repeated evaluations take the buffers that earlier ones left in the arena.
+        for _ in 0..3 {
+            for expression in &expressions {
+                let node = build_operator_tree(expression).unwrap();
+                let compact = node.compact();
+
+                let expected = node.eval_with_context_and_state(&context(), &plain);
+                assert_eq!(
+                    node.eval_with_context_and_state(&context(), &pooled),
+                    expected,
+                    "{:?}",
+                    expression
+                );
+                assert_eq!(
+                    compact.eval_with_context_and_state(&context(), &pooled),
+                    expected,
+                    "{:?}",
+                    expression
+                );
+
+                let mut expected_context = context();
+                let expected = node.eval_with_context_mut_and_state(&mut expected_context, &plain);
+                let mut node_context = context();
+                assert_eq!(
+                    node.eval_with_context_mut_and_state(&mut node_context, &pooled),
+                    expected,
+                    "{:?}",
+                    expression
+                );
+                let mut compact_context = context();
+                assert_eq!(
+                    compact.eval_with_context_mut_and_state(&mut compact_context, &pooled),
+                    expected,
+                    "{:?}",
+                    expression
+                );
+                for identifier in &["s", "t", "label"] {
+                    assert_eq!(
+                        node_context.get_value(identifier),
+                        expected_context.get_value(identifier)
+                    );
+                    assert_eq!(
+                        compact_context.get_value(identifier),
+                        expected_context.get_value(identifier)
+                    );
+                }
+                assert_eq!(pooled.allocated_bytes(), plain.allocated_bytes());
+            }
+        }
+        assert_eq!(pooled.recursion_depth(), 0);
+    }
+
+    // Results do not share buffers with the arena, so they stay intact while it is reused.
+    let state = EvalState::new(EvalConfig {
+        arena: Some(arena.clone()),
+        ..Default::default()
+    });
+    let tree = build_operator_tree("(first + last, (last, first))").unwrap();
+    let results: Vec<Value> = (0..10)
+        .map(|_| {
+            tree.eval_with_context_and_state(&context(), &state)
+                .unwrap()
+        })
+        .collect();
+    for result in &results {
+        assert_eq!(
+            result,
+            &Value::Tuple(vec![
+                Value::from("AdaLovelace"),
+                Value::Tuple(vec![Value::from("Lovelace"), Value::from("Ada")])
+            ])
+        );
+    }
+
+    assert!(arena.reused_buffers() > 0);
+    assert_eq!(arena, arena.clone());
+    assert_ne!(arena, EvalArena::new());
+    arena.reset();
+    assert_eq!(arena.reused_buffers(), 0);
+    assert_eq!(
+        format!("{:?}", arena),
+        "EvalArena { strings: 0, tuples: 0, reused_buffers: 0 }"
+    );
+    assert_eq!(
+        tree.eval_with_context_and_state(&context(), &state),
+        Ok(results[0].clone())
+    );
+    assert_eq!(EvalConfig::default().arena, None);
+}
+
+#[test]
+fn test_cancellation() {
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    let token = CancellationToken::new();
+    let config = EvalConfig {
+        cancellation_token: Some(token.clone()),
+        ..Default::default()
+    };
+
+    // Each term takes a few milliseconds, so the whole chain takes several seconds if it is not cancelled.
+    let chain = vec!["tick()"; 1000].join(" + ");
+    let (started_sender, started_receiver) = mpsc::channel();
+    let thread_config = config.clone();
+    let evaluation = thread::spawn(move || {
+        let mut context = HashMapContext::new();
+        context
+            .set_function(
+                "tick".into(),
+                Function::new(Box::new(move |_| {
+                    thread::sleep(Duration::from_millis(5));
+                    let _ = started_sender.send(());
+                    Ok(Value::from(1))
+                })),
+            )
+            .unwrap();
+        let state = EvalState::new(thread_config);
+        let start = Instant::now();
+        let result = build_operator_tree(&chain)
+            .unwrap()
+            .eval_with_context_and_state(&context, &state);
+        (result, start.elapsed())
+    });
+
+    started_receiver.recv().unwrap();
+    token.cancel();
+    let (result, elapsed) = evaluation.join().unwrap();
+    assert_eq!(result, Err(EvalexprError::Cancelled));
+    assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+
+    // A cancelled token stays cancelled and is shared by all clones.
+    assert!(token.is_cancelled());
+    assert!(config.cancellation_token.as_ref().unwrap().is_cancelled());
+    let state = EvalState::new(config);
+    assert_eq!(
+        build_operator_tree("1")
+            .unwrap()
+            .eval_with_context_and_state(&EmptyContext, &state),
+        Err(EvalexprError::Cancelled)
+    );
+
+    // Evaluations with an uncancelled or without a token are not affected.
+    let state = EvalState::new(EvalConfig {
+        cancellation_token: Some(CancellationToken::new()),
+        ..Default::default()
+    });
+    assert_eq!(
+        build_operator_tree("1 + 2")
+            .unwrap()
+            .eval_with_context_and_state(&EmptyContext, &state),
+        Ok(Value::from(3))
+    );
+    assert_eq!(eval("1 + 2"), Ok(Value::from(3)));
+}
+
+#[test]
+fn test_nan_policy() {
+    assert_eq!(EvalConfig::default().nan_policy, NanPolicy::Propagate);
+
+    let mut context = HashMapContext::new();
+    context.set_value("inf", FloatType::INFINITY).unwrap();
+    context.set_value("nan", FloatType::NAN).unwrap();
+    context
+        .set_function(
+            "make_nan".into(),
+            Function::new(Box::new(|_| Ok(Value::Float(FloatType::NAN)))),
+        )
+        .unwrap();
+    let eval_with_policy = |expression: &str, nan_policy: NanPolicy| {
+        let state = EvalState::new(EvalConfig {
+            nan_policy,
+            ..Default::default()
+        });
+        build_operator_tree(expression)
+            .unwrap()
+            .eval_with_context_and_state(&context, &state)
+    };
+
+    // Operations that produce NaN from operands that are not NaN.
+    let producing = vec![
+        ("inf - inf", "-", vec![Value::from(FloatType::INFINITY); 2]),
+        (
+            "inf + -inf",
+            "+",
+            vec![
+                Value::from(FloatType::INFINITY),
+                Value::from(FloatType::NEG_INFINITY),
+            ],
+        ),
+        (
+            "0.0 * inf",
+            "*",
+            vec![Value::from(0.0), Value::from(FloatType::INFINITY)],
+        ),
+        ("0.0 / 0.0", "/", vec![Value::from(0.0), Value::from(0.0)]),
+        ("0 / 0.0", "/", vec![Value::from(0), Value::from(0.0)]),
+        ("inf / inf", "/", vec![Value::from(FloatType::INFINITY); 2]),
+        ("5 % 0.0", "%", vec![Value::from(5), Value::from(0.0)]),
+        (
+            "inf % 2",
+            "%",
+            vec![Value::from(FloatType::INFINITY), Value::from(2)],
+        ),
+        ("(-8) ^ 0.5", "^", vec![Value::from(-8), Value::from(0.5)]),
+    ];
+    for (expression, operator, operands) in producing {
+        assert!(
+            eval_with_policy(expression, NanPolicy::Propagate)
+                .unwrap()
+                .as_float()
+                .unwrap()
+                .is_nan(),
+            "{}",
+            expression
+        );
+        assert_eq!(
+            eval_with_policy(expression, NanPolicy::Error),
+            Err(EvalexprError::NaNProduced {
+                operator: operator.to_string(),
+                operands,
+            }),
+            "{}",
+            expression
+        );
+    }
+
+    // The error names the innermost operation that produced the NaN, and stops the evaluation.
+    assert_eq!(
+        eval_with_policy("a = 1 + 0.0 / 0.0; a", NanPolicy::Error),
+        Err(EvalexprError::NaNProduced {
+            operator: "/".to_string(),
+            operands: vec![Value::from(0.0), Value::from(0.0)],
+        })
+    );
+    assert_eq!(
+        eval_with_policy("0.0 / 0.0", NanPolicy::Error)
+            .unwrap_err()
+            .to_string(),
+        "Computing / with the operands 0, 0 produced NaN"
+    );
+
+    // Results that are infinite or not NaN, and NaN that is only passed on, are not affected.
+    let not_producing = [
+        ("1.0 / 0.0", Some(FloatType::INFINITY)),
+        ("-1.0 / 0.0", Some(FloatType::NEG_INFINITY)),
+        ("0.0 ^ (-1)", Some(FloatType::INFINITY)),
+        ("1e308 * 10", Some(FloatType::INFINITY)),
+        ("inf * 2 - 1", Some(FloatType::INFINITY)),
+        ("(-8.0) ^ 2", Some(64.0)),
+        ("nan", None),
+        ("nan + 1", None),
+        ("0.0 * nan", None),
+        ("-nan", None),
+        ("(nan, 1)", None),
+        ("make_nan()", None),
+        ("make_nan() / 0.0", None),
+    ];
+    for (expression, expected) in not_producing.iter() {
+        for nan_policy in [NanPolicy::Propagate, NanPolicy::Error].iter() {
+            let result = eval_with_policy(expression, *nan_policy);
+            match (result, expected) {
+                (Ok(Value::Float(float)), Some(expected)) => {
+                    assert_eq!(float, *expected, "{}", expression)
+                },
+                (Ok(Value::Float(float)), None) => assert!(float.is_nan(), "{}", expression),
+                (Ok(Value::Tuple(_)), None) => {},
+                (result, _) => panic!("{} evaluated to {:?}", expression, result),
+            }
+        }
+    }
+
+    // Integer division by zero fails regardless of the policy.
+    assert_eq!(
+        eval_with_policy("0 / 0", NanPolicy::Error),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(0),
+            divisor: Value::from(0),
+        })
+    );
+
+    // Builtin functions are checked as well, but a NaN within the arguments is passed on.
+    #[cfg(feature = "builtin_math")]
+    {
+        let producing = [
+            ("sqrt(-1)", "sqrt", vec![Value::from(-1)]),
+            ("sqrt(-0.5)", "sqrt", vec![Value::from(-0.5)]),
+            ("ln(-1)", "ln", vec![Value::from(-1)]),
+            ("ln(-inf)", "ln", vec![Value::from(FloatType::NEG_INFINITY)]),
+            (
+                "pow(-8, 0.5)",
+                "pow",
+                vec![Value::from(-8), Value::from(0.5)],
+            ),
+        ];
+        for (expression, function, operands) in producing.iter() {
+            assert!(
+                eval_with_policy(expression, NanPolicy::Propagate)
+                    .unwrap()
+                    .as_float()
+                    .unwrap()
+                    .is_nan(),
+                "{}",
+                expression
+            );
+            assert_eq!(
+                eval_with_policy(expression, NanPolicy::Error),
+                Err(EvalexprError::NaNProduced {
+                    operator: function.to_string(),
+                    operands: operands.clone(),
+                }),
+                "{}",
+                expression
+            );
+        }
+
+        // The logarithm of zero is negative infinity, which is not NaN.
+        for nan_policy in [NanPolicy::Propagate, NanPolicy::Error].iter() {
+            assert_eq!(
+                eval_with_policy("ln(0)", *nan_policy),
+                Ok(Value::from(FloatType::NEG_INFINITY))
+            );
+            assert_eq!(
+                eval_with_policy("sqrt(inf)", *nan_policy),
+                Ok(Value::from(FloatType::INFINITY))
+            );
+            assert!(eval_with_policy("sqrt(nan)", *nan_policy)
+                .unwrap()
+                .as_float()
+                .unwrap()
+                .is_nan());
+        }
+        assert!(eval_with_policy("max(nan, 1.0)", NanPolicy::Error).is_ok());
+    }
+
+    // Compact trees follow the policy of the state as well.
+    let state = EvalState::new(EvalConfig {
+        nan_policy: NanPolicy::Error,
+        ..Default::default()
+    });
+    assert_eq!(
+        build_compact_tree("inf - inf")
+            .unwrap()
+            .eval_with_context_and_state(&context, &state),
+        Err(EvalexprError::NaNProduced {
+            operator: "-".to_string(),
+            operands: vec![Value::from(FloatType::INFINITY); 2],
+        })
+    );
+}
+
+#[test]
+fn test_tuple_error_mode() {
+    let context = context_map! {
+        "cpu_pct" => 42.5,
+        "mem_pct" => 80,
+        "disk_used" => 0,
+    }
+    .unwrap();
+    let tree = build_operator_tree("(cpu_pct, mem_pct, 100 / disk_used + disk_free)").unwrap();
+
+    // The default fails with the error of the failing element.
+    let state = EvalState::default();
+    assert_eq!(
+        tree.eval_with_context_and_report(&context, &state),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(100),
+            divisor: Value::from(0),
+        })
+    );
+
+    let partial = EvalState::new(EvalConfig {
+        tuple_error_mode: TupleErrorMode::CollectPartial,
+        ..Default::default()
+    });
+    assert_eq!(
+        tree.eval_with_context_and_report(&context, &partial),
+        Ok(EvalReport {
+            value: Value::from(vec![Value::from(42.5), Value::from(80), Value::Empty]),
+            errors: vec![TupleElementError {
+                index: 2,
+                error: EvalexprError::DivisionError {
+                    dividend: Value::from(100),
+                    divisor: Value::from(0),
+                },
+            }],
+        })
+    );
+    assert_eq!(partial.recursion_depth(), 0);
+
+    // Each failing element is reported.
+    assert_eq!(
+        build_operator_tree("missing, 1, true * 2")
+            .unwrap()
+            .eval_with_context_and_report(&context, &partial),
+        Ok(EvalReport {
+            value: Value::from(vec![Value::Empty, Value::from(1), Value::Empty]),
+            errors: vec![
+                TupleElementError {
+                    index: 0,
+                    error: EvalexprError::VariableIdentifierNotFound {
+                        identifier: "missing".to_string(),
+                        span: Some(0..7),
+                    },
+                },
+                TupleElementError {
+                    index: 2,
+                    error: EvalexprError::expected_number(Value::from(true)),
+                },
+            ],
+        })
+    );
+
+    // A failure in a nested tuple fails the whole element.
+    assert_eq!(
+        build_operator_tree("1, (2, missing)")
+            .unwrap()
+            .eval_with_context_and_report(&context, &partial)
+            .map(|report| report.value),
+        Ok(Value::from(vec![Value::from(1), Value::Empty]))
+    );
+
+    // Expressions that are not tuples fail as usual.
+    assert_eq!(
+        build_operator_tree("1 + missing")
+            .unwrap()
+            .eval_with_context_and_report(&context, &partial),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "missing".to_string(),
+            span: Some(4..11),
+        })
+    );
+    assert_eq!(
+        build_operator_tree("1 + 1")
+            .unwrap()
+            .eval_with_context_and_report(&context, &partial),
+        Ok(EvalReport {
+            value: Value::from(2),
+            errors: Vec::new(),
+        })
+    );
+
+    // Errors about the whole evaluation are not recovered.
+    let token = CancellationToken::new();
+    token.cancel();
+    let cancelled = EvalState::new(EvalConfig {
+        tuple_error_mode: TupleErrorMode::CollectPartial,
+        cancellation_token: Some(token),
+        ..Default::default()
+    });
+    assert_eq!(
+        tree.eval_with_context_and_report(&context, &cancelled),
+        Err(EvalexprError::Cancelled)
+    );
+}
+
+#[test]
+fn test_float_formatter() {
+    fn fixed(float: FloatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.6}", float)
+    }
+
+    assert_eq!(Value::from(1e21).to_string(), "1000000000000000000000");
+    assert!(set_float_formatter(Some(fixed)).is_none());
+
+    assert_eq!(Value::from(2.5).to_string(), "2.500000");
+    assert_eq!(Value::from(1e-7).to_string(), "0.000000");
+    assert_eq!(
+        Value::from(vec![Value::from(1.0), Value::from(2), Value::from("x")]).to_string(),
+        "(1.000000, 2, \"x\")"
+    );
+    #[cfg(feature = "builtin_string")]
+    {
+        assert_eq!(eval("str(1 / 4.0)"), Ok(Value::from("0.250000")));
+        assert_eq!(eval("str((0.5, 1))"), Ok(Value::from("(0.500000, 1)")));
+        assert_eq!(eval("str(\"text\")"), Ok(Value::from("text")));
+    }
+
+    // Errors and operator trees keep the canonical form.
+    assert_eq!(
+        EvalexprError::NaNProduced {
+            operator: "%".to_string(),
+            operands: vec![Value::from(2.5), Value::from(0.0)],
+        }
+        .to_string(),
+        "Computing % with the operands 2.5, 0 produced NaN"
+    );
+    assert!(build_operator_tree("0.5")
+        .unwrap()
+        .to_debug_tree()
+        .contains("Const(Float 0.5)"));
+
+    assert!(set_float_formatter(None).is_some());
+    assert_eq!(Value::from(2.5).to_string(), "2.5");
+    #[cfg(feature = "builtin_string")]
+    assert_eq!(eval("str(0.5)"), Ok(Value::from("0.5")));
+}
+
+#[test]
+fn test_check_types() {
+    use std::collections::HashMap;
+
+    let mut variable_types = HashMap::new();
+    variable_types.insert("i".to_string(), ValueType::Int);
+    variable_types.insert("f".to_string(), ValueType::Float);
+    variable_types.insert("s".to_string(), ValueType::String);
+    variable_types.insert("b".to_string(), ValueType::Boolean);
+    variable_types.insert("t".to_string(), ValueType::Tuple);
+    variable_types.insert("e".to_string(), ValueType::Empty);
+    let mut function_signatures = HashMap::new();
+    function_signatures.insert(
+        "distance".to_string(),
+        Signature::new(
+            vec![
+                StaticType::Number,
+                StaticType::Number,
+                StaticType::Number,
+                StaticType::Number,
+            ],
+            StaticType::Float,
+        ),
+    );
+    function_signatures.insert(
+        "half".to_string(),
+        Signature::new(vec![StaticType::Number], StaticType::Number),
+    );
+    function_signatures.insert("now".to_string(), Signature::new(vec![], StaticType::Int));
+    function_signatures.insert("any".to_string(), Signature::variadic(StaticType::Any));
+
+    let check = |expression: &str| {
+        build_operator_tree(expression).unwrap().check_types(
+            &variable_types,
+            &function_signatures,
+            false,
+        )
+    };
+    let check_lenient = |expression: &str| {
+        build_operator_tree(expression).unwrap().check_types(
+            &variable_types,
+            &function_signatures,
+            true,
+        )
+    };
+    let operator_error =
+        |operator: &str, actual: Vec<StaticType>, span: Option<std::ops::Range<usize>>| {
+            Err(vec![TypeCheckError::OperatorArgumentTypes {
+                operator: operator.to_string(),
+                actual,
+                span,
+            }])
+        };
+
+    // Values and variables
+    assert_eq!(check(""), Ok(StaticType::Empty));
+    assert_eq!(check("1"), Ok(StaticType::Int));
+    assert_eq!(check("1.5"), Ok(StaticType::Float));
+    assert_eq!(check("\"a\""), Ok(StaticType::String));
+    assert_eq!(check("true"), Ok(StaticType::Boolean));
+    assert_eq!(check("(i)"), Ok(StaticType::Int));
+    assert_eq!(check("t"), Ok(StaticType::Tuple));
+    assert_eq!(check("e"), Ok(StaticType::Empty));
+    assert_eq!(
+        check("x"),
+        Err(vec![TypeCheckError::UnknownVariable {
+            identifier: "x".to_string(),
+            span: Some(0..1),
+        }])
+    );
+    assert_eq!(check_lenient("x"), Ok(StaticType::Any));
+
+    // Addition
+    assert_eq!(check("i + 1"), Ok(StaticType::Int));
+    assert_eq!(check("i + f"), Ok(StaticType::Float));
+    assert_eq!(check("s + \"a\""), Ok(StaticType::String));
+    assert_eq!(check("t + (1, 2)"), Ok(StaticType::Tuple));
+    assert_eq!(check_lenient("x + i"), Ok(StaticType::Number));
+    assert_eq!(check_lenient("x + f"), Ok(StaticType::Float));
+    assert_eq!(check_lenient("x + s"), Ok(StaticType::String));
+    assert_eq!(check_lenient("x + y"), Ok(StaticType::Any));
+    assert_eq!(
+        check("s + 1"),
+        operator_error("+", vec![StaticType::String, StaticType::Int], Some(2..3))
+    );
+    assert_eq!(
+        check("b + 1"),
+        operator_error("+", vec![StaticType::Boolean, StaticType::Int], Some(2..3))
+    );
+    assert_eq!(
+        check("t + s"),
+        operator_error("+", vec![StaticType::Tuple, StaticType::String], Some(2..3))
+    );
+
+    // Arithmetic
+    assert_eq!(check("i - 1"), Ok(StaticType::Int));
+    assert_eq!(check("i * f"), Ok(StaticType::Float));
+    assert_eq!(check("i / 2"), Ok(StaticType::Int));
+    assert_eq!(check("f % 2"), Ok(StaticType::Float));
+    assert_eq!(check("i ^ 2"), Ok(StaticType::Number));
+    assert_eq!(check("f ^ 2"), Ok(StaticType::Float));
+    assert_eq!(check("i & 3 | i bitxor 1"), Ok(StaticType::Int));
+    assert_eq!(check("b xor !b"), Ok(StaticType::Boolean));
+    assert_eq!(
+        check("b bitxor true"),
+        operator_error(
+            "bitxor",
+            vec![StaticType::Boolean, StaticType::Boolean],
+            Some(2..8)
+        )
+    );
+    assert_eq!(check("b implies i > 0"), Ok(StaticType::Boolean));
+    assert_eq!(check("b ? i : i * 2"), Ok(StaticType::Int));
+    assert_eq!(check("b ? i : f"), Ok(StaticType::Number));
+    assert_eq!(
+        check("i ? s : t"),
+        operator_error(
+            "?:",
+            vec![StaticType::Int, StaticType::String, StaticType::Tuple],
+            Some(2..3)
+        )
+    );
+    assert_eq!(check("i << 2 >> i"), Ok(StaticType::Int));
+    assert_eq!(check("1..i"), Ok(StaticType::Tuple));
+    assert_eq!(check("i..=2"), Ok(StaticType::Tuple));
+    assert_eq!(check("s =~ \"^a\""), Ok(StaticType::Boolean));
+    assert_eq!(
+        check("i =~ s"),
+        operator_error("=~", vec![StaticType::Int, StaticType::String], Some(2..4))
+    );
+    assert_eq!(check("x ?? i"), Ok(StaticType::Any));
+    assert_eq!(check("(x) ?? f"), Ok(StaticType::Any));
+    assert_eq!(
+        check("(i + x) ?? f"),
+        Err(vec![TypeCheckError::UnknownVariable {
+            identifier: "x".to_string(),
+            span: Some(5..6),
+        }])
+    );
+    assert_eq!(check("i ?? f"), Ok(StaticType::Number));
+    assert_eq!(
+        check("x ?? y"),
+        Err(vec![TypeCheckError::UnknownVariable {
+            identifier: "y".to_string(),
+            span: Some(5..6),
+        }])
+    );
+    assert_eq!(check("-i"), Ok(StaticType::Int));
+    assert_eq!(check("-f"), Ok(StaticType::Float));
+    assert_eq!(check("+i"), Ok(StaticType::Int));
+    assert_eq!(check("+f"), Ok(StaticType::Float));
+    assert_eq!(check_lenient("-x"), Ok(StaticType::Number));
+    assert_eq!(check_lenient("x * 2"), Ok(StaticType::Number));
+    assert_eq!(
+        check("\"abc\" * 2"),
+        operator_error("*", vec![StaticType::String, StaticType::Int], Some(6..7))
+    );
+    assert_eq!(
+        check("i - b"),
+        operator_error("-", vec![StaticType::Int, StaticType::Boolean], Some(2..3))
+    );
+    assert_eq!(
+        check("t / 2"),
+        operator_error("/", vec![StaticType::Tuple, StaticType::Int], Some(2..3))
+    );
+    assert_eq!(
+        check("i % e"),
+        operator_error("%", vec![StaticType::Int, StaticType::Empty], Some(2..3))
+    );
+    assert_eq!(
+        check("s ^ 2"),
+        operator_error("^", vec![StaticType::String, StaticType::Int], Some(2..3))
+    );
+    assert_eq!(
+        check("-s"),
+        operator_error("-", vec![StaticType::String], Some(0..1))
+    );
+    assert_eq!(
+        check("f & 1"),
+        operator_error("&", vec![StaticType::Float, StaticType::Int], Some(2..3))
+    );
+    assert_eq!(
+        check("1 << s"),
+        operator_error("<<", vec![StaticType::Int, StaticType::String], Some(2..4))
+    );
+    assert_eq!(
+        check("1..f"),
+        operator_error("..", vec![StaticType::Int, StaticType::Float], Some(1..3))
+    );
+
+    // Comparisons
+    assert_eq!(check("i == s"), Ok(StaticType::Boolean));
+    assert_eq!(check("t != e"), Ok(StaticType::Boolean));
+    assert_eq!(check("i < f"), Ok(StaticType::Boolean));
+    assert_eq!(check("s >= \"a\""), Ok(StaticType::Boolean));
+    assert_eq!(check_lenient("x > 1"), Ok(StaticType::Boolean));
+    assert_eq!(
+        check("s > 1"),
+        operator_error(">", vec![StaticType::String, StaticType::Int], Some(2..3))
+    );
+    assert_eq!(
+        check("b <= true"),
+        operator_error(
+            "<=",
+            vec![StaticType::Boolean, StaticType::Boolean],
+            Some(2..4)
+        )
+    );
+
+    // Logic
+    assert_eq!(check("b && i > 1 || !b"), Ok(StaticType::Boolean));
+    assert_eq!(
+        check("i && b"),
+        operator_error("&&", vec![StaticType::Int, StaticType::Boolean], Some(2..4))
+    );
+    assert_eq!(
+        check("b || s"),
+        operator_error(
+            "||",
+            vec![StaticType::Boolean, StaticType::String],
+            Some(2..4)
+        )
+    );
+    assert_eq!(
+        check("!i"),
+        operator_error("!", vec![StaticType::Int], Some(0..1))
+    );
+
+    // Tuples, assignments and chains
+    assert_eq!(check("(i, s, b)"), Ok(StaticType::Tuple));
+    assert_eq!(check("a = s"), Ok(StaticType::Empty));
+    assert_eq!(check("a = s; a + \"!\""), Ok(StaticType::String));
+    assert_eq!(
+        check("a = 1; a + \"!\""),
+        operator_error("+", vec![StaticType::Int, StaticType::String], Some(9..10))
+    );
+
+    // Functions
+    assert_eq!(check("distance(i, f, 1, 2)"), Ok(StaticType::Float));
+    assert_eq!(check("half(i)"), Ok(StaticType::Number));
+    assert_eq!(check("half i"), Ok(StaticType::Number));
+    assert_eq!(check("now()"), Ok(StaticType::Int));
+    assert_eq!(check("distance(t)"), Ok(StaticType::Float));
+    assert_eq!(check("any(s, t) + 1"), Ok(StaticType::Number));
+    assert_eq!(check("any(s, t)"), Ok(StaticType::Any));
+    assert_eq!(
+        check("distance(i, s, 1, b)"),
+        Err(vec![
+            TypeCheckError::FunctionArgumentType {
+                function: "distance".to_string(),
+                position: 1,
+                expected: StaticType::Number,
+                actual: StaticType::String,
+                span: Some(12..13),
+            },
+            TypeCheckError::FunctionArgumentType {
+                function: "distance".to_string(),
+                position: 3,
+                expected: StaticType::Number,
+                actual: StaticType::Boolean,
+                span: Some(18..19),
+            }
+        ])
+    );
+    assert_eq!(
+        check("distance(i, 1)"),
+        Err(vec![TypeCheckError::FunctionArgumentAmount {
+            function: "distance".to_string(),
+            expected: 4,
+            actual: 2,
+            span: Some(0..8),
+        }])
+    );
+    assert_eq!(
+        check("now(1)"),
+        Err(vec![TypeCheckError::FunctionArgumentAmount {
+            function: "now".to_string(),
+            expected: 0,
+            actual: 1,
+            span: Some(0..3),
+        }])
+    );
+    assert_eq!(
+        check("unknown(1)"),
+        Err(vec![TypeCheckError::UnknownFunction {
+            function: "unknown".to_string(),
+            span: Some(0..7),
+        }])
+    );
+    assert_eq!(check_lenient("unknown(1) + 1"), Ok(StaticType::Number));
+
+    // Builtin functions
+    #[cfg(all(
+        feature = "builtin_math",
+        feature = "builtin_string",
+        feature = "builtin_tuple"
+    ))]
+    {
+        assert_eq!(check("len(s) + min(i, f)"), Ok(StaticType::Number));
+        assert_eq!(check("str::trim(s) + \"!\""), Ok(StaticType::String));
+        assert_eq!(check("all_of(t, \">\", 1)"), Ok(StaticType::Boolean));
+        assert_eq!(
+            check("len(i)"),
+            Err(vec![TypeCheckError::FunctionArgumentType {
+                function: "len".to_string(),
+                position: 0,
+                expected: StaticType::String,
+                actual: StaticType::Int,
+                span: Some(4..5),
+            }])
+        );
+    }
+
+    // All errors are reported, and errors do not cause further errors.
+    assert_eq!(
+        check("(s * 2) + (i && b) - x"),
+        Err(vec![
+            TypeCheckError::OperatorArgumentTypes {
+                operator: "*".to_string(),
+                actual: vec![StaticType::String, StaticType::Int],
+                span: Some(3..4),
+            },
+            TypeCheckError::OperatorArgumentTypes {
+                operator: "&&".to_string(),
+                actual: vec![StaticType::Int, StaticType::Boolean],
+                span: Some(13..15),
+            },
+            TypeCheckError::UnknownVariable {
+                identifier: "x".to_string(),
+                span: Some(21..22),
+            },
+        ])
+    );
+
+    // The span of an error points into the checked expression.
+    let expression = "distance(i, f, 1, 2) + half(\"a\") * unknown_variable";
+    let spans: Vec<_> = check(expression)
+        .unwrap_err()
+        .iter()
+        .map(|error| match error {
+            TypeCheckError::FunctionArgumentType { span, .. }
+            | TypeCheckError::UnknownVariable { span, .. } => &expression[span.clone().unwrap()],
+            error => panic!("unexpected error {:?}", error),
+        })
+        .collect();
+    assert_eq!(spans, vec!["\"a\"", "unknown_variable"]);
+    assert_eq!(
+        build_operator_tree("1 +").unwrap().check_types(
+            &variable_types,
+            &function_signatures,
+            false
+        ),
+        Err(vec![TypeCheckError::OperatorArgumentAmount {
+            operator: "+".to_string(),
+            expected: 2,
+            actual: 1,
+            span: Some(2..3),
+        }])
+    );
+}
+
+#[test]
+fn test_typed_expression() {
+    let result_type = |expected, actual, span| {
+        Err(EvalexprError::TypeCheckFailed {
+            errors: vec![TypeCheckError::ResultType {
+                expected,
+                actual,
+                span,
+            }],
+        })
+    };
+
+    // Booleans
+    let context = context_map! { "a" => 2, "b" => true }.unwrap();
+    let expression = TypedExpression::<bool>::parse("a > 1 && b").unwrap();
+    assert_eq!(expression.eval(&context), Ok(true));
+    assert_eq!(
+        TypedExpression::<bool>::parse("if a > 1 { b } else { false }")
+            .unwrap()
+            .eval(&context),
+        Ok(true)
+    );
+    assert_eq!(
+        TypedExpression::<bool>::parse("a + 1").map(|_| ()),
+        result_type(StaticType::Boolean, StaticType::Number, Some(2..3))
+    );
+    assert_eq!(
+        TypedExpression::<bool>::parse("1.5").map(|_| ()),
+        result_type(StaticType::Boolean, StaticType::Float, Some(0..3))
+    );
+
+    // Integers
+    assert_eq!(
+        TypedExpression::<IntType>::parse("a * 3 << 1")
+            .unwrap()
+            .eval(&context),
+        Ok(12)
+    );
+    assert_eq!(
+        TypedExpression::<IntType>::parse("a > 1").map(|_| ()),
+        result_type(StaticType::Int, StaticType::Boolean, Some(2..3))
+    );
+    assert_eq!(
+        TypedExpression::<IntType>::parse("\"a\"").map(|_| ()),
+        result_type(StaticType::Int, StaticType::String, Some(0..3))
+    );
+
+    // Floats also accept integers, like the parameters of `Function::from_fn`.
+    assert_eq!(
+        TypedExpression::<FloatType>::parse("a / 4.0")
+            .unwrap()
+            .eval(&context),
+        Ok(0.5)
+    );
+    assert_eq!(
+        TypedExpression::<FloatType>::parse("a + 1")
+            .unwrap()
+            .eval(&context),
+        Ok(3.0)
+    );
+    assert_eq!(
+        TypedExpression::<FloatType>::parse("b || false").map(|_| ()),
+        result_type(StaticType::Number, StaticType::Boolean, Some(2..4))
+    );
+
+    // Strings
+    assert_eq!(
+        TypedExpression::<String>::parse("\"a\" + \"b\"")
+            .unwrap()
+            .eval(&context),
+        Ok("ab".to_string())
+    );
+    assert_eq!(
+        TypedExpression::<String>::parse("(1, 2)").map(|_| ()),
+        result_type(StaticType::String, StaticType::Tuple, Some(2..3))
+    );
+
+    // The empty type
+    let mut mutable_context = HashMapContext::new();
+    let expression = TypedExpression::<()>::parse("c = 4").unwrap();
+    assert_eq!(expression.eval_mut(&mut mutable_context), Ok(()));
+    assert_eq!(mutable_context.get_value("c"), Some(&Value::from(4)));
+    assert_eq!(
+        TypedExpression::<()>::parse("").unwrap().eval(&context),
+        Ok(())
+    );
+    assert_eq!(
+        TypedExpression::<()>::parse("c = 4; c").map(|_| ()),
+        result_type(StaticType::Empty, StaticType::Int, Some(5..6))
+    );
+
+    // Type errors within the expression are reported instead of the type of the result.
+    assert_eq!(
+        TypedExpression::<bool>::parse("\"abc\" * 2 > 1").map(|_| ()),
+        Err(EvalexprError::TypeCheckFailed {
+            errors: vec![TypeCheckError::OperatorArgumentTypes {
+                operator: "*".to_string(),
+                actual: vec![StaticType::String, StaticType::Int],
+                span: Some(6..7),
+            }],
+        })
+    );
+    // Errors of parsing are returned as they are.
+    assert_eq!(
+        TypedExpression::<bool>::parse("(a").map(|_| ()),
+        Err(EvalexprError::UnmatchedLBrace { span: 0..1 })
+    );
+
+    // Variables and functions of unknown type are accepted, and the result is checked when evaluating.
+    let expression = TypedExpression::<bool>::parse("f(a)").unwrap();
+    let context = context_map! {
+        "a" => 1,
+        "f" => Function::new(Box::new(|argument| Ok(argument.clone())))
+    }
+    .unwrap();
+    assert_eq!(
+        expression.eval(&context),
+        Err(EvalexprError::ExpectedBoolean {
+            actual: Value::from(1)
+        })
+    );
+    let expression = TypedExpression::<IntType>::parse("a + 1").unwrap();
+    assert_eq!(
+        expression.eval(&context_map! { "a" => 1.5 }.unwrap()),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::from(2.5)
+        })
+    );
+    assert_eq!(
+        expression
+            .eval(&EmptyContext)
+            .map_err(|error| error.variant_name()),
+        Err("VariableIdentifierNotFound")
+    );
+
+    // The operator tree stays accessible.
+    let expression = TypedExpression::<bool>::parse("a  >  1").unwrap();
+    assert_eq!(expression.to_string(), expression.node().to_string());
+    assert_eq!(expression.clone(), expression);
+    assert_eq!(
+        expression.into_node(),
+        build_operator_tree("a > 1").unwrap()
+    );
+    assert!(TypedExpression::<bool>::from_node(build_operator_tree("true").unwrap()).is_ok());
+}
+
+#[cfg(feature = "serde_support")]
+#[test]
+fn test_typed_expression_serde() {
+    let expression: TypedExpression<bool> = serde_json::from_str("\"a > 1\"").unwrap();
+    assert_eq!(
+        expression.eval(&context_map! { "a" => 2 }.unwrap()),
+        Ok(true)
+    );
+    let expressions: Vec<TypedExpression<IntType>> =
+        serde_json::from_str("[\"1 + 2\", \"a * 2\"]").unwrap();
+    assert_eq!(expressions.len(), 2);
+
+    // Expressions of the wrong type fail to deserialize.
+    let error = serde_json::from_str::<TypedExpression<bool>>("\"1 + 2\"").unwrap_err();
+    assert!(
+        error.to_string().starts_with(
+            "The expression failed the type check; The expression is expected to be of type Boolean, but is of type Int"
+        ),
+        "{}",
+        error
+    );
+    assert!(serde_json::from_str::<TypedExpression<bool>>("1").is_err());
+}
+
+#[test]
+fn test_infer_variable_types() {
+    use evalexpr::ValueType::{Boolean, Empty, Float, Int, Tuple};
+    use std::collections::HashMap;
+
+    fn infer(expression: &str) -> HashMap<String, Vec<ValueType>> {
+        build_operator_tree(expression)
+            .unwrap()
+            .infer_variable_types()
+            .into_iter()
+            .map(|(identifier, types)| (identifier, types.value_types()))
+            .collect()
+    }
+
+    fn infer_one(expression: &str, identifier: &str) -> Vec<ValueType> {
+        infer(expression).remove(identifier).unwrap()
+    }
+
+    let any = vec![ValueType::String, Int, Float, Boolean, Tuple, Empty];
+    let number = vec![Int, Float];
+
+    // Arithmetic operators
+    for operator in &["-", "*", "/", "%", "^"] {
+        let expression = format!("a {} b", operator);
+        assert_eq!(infer_one(&expression, "a"), number);
+        assert_eq!(infer_one(&expression, "b"), number);
+    }
+    assert_eq!(infer_one("-a", "a"), number);
+
+    // Addition depends on the type of the other argument.
+    assert_eq!(infer_one("a + 1", "a"), number);
+    assert_eq!(infer_one("1.5 + a", "a"), number);
+    assert_eq!(infer_one("a + \"s\"", "a"), vec![ValueType::String]);
+    assert_eq!(infer_one("a + (1, 2)", "a"), vec![Tuple]);
+    assert_eq!(
+        infer_one("a + b", "a"),
+        vec![ValueType::String, Int, Float, Tuple]
+    );
+
+    // Comparisons
+    assert_eq!(infer_one("a < 1", "a"), number);
+    assert_eq!(infer_one("\"x\" >= a", "a"), vec![ValueType::String]);
+    assert_eq!(infer_one("a > b", "a"), vec![ValueType::String, Int, Float]);
+    assert_eq!(infer_one("a == 1", "a"), any);
+    assert_eq!(infer_one("a != b", "b"), any);
+
+    // Logic operators
+    assert_eq!(infer_one("a && b", "a"), vec![Boolean]);
+    assert_eq!(infer_one("a || b", "b"), vec![Boolean]);
+    assert_eq!(infer_one("!a", "a"), vec![Boolean]);
+    assert_eq!(infer_one("a implies b", "b"), vec![Boolean]);
+    assert_eq!(infer_one("a ? 1 : 2", "a"), vec![Boolean]);
+    assert_eq!(infer_one("c ? a : b", "a"), any);
+    assert_eq!(infer_one("(c ? a : b) && true", "b"), vec![Boolean]);
+    assert_eq!(infer_one("a xor b", "a"), vec![Boolean]);
+
+    // Bitwise operators
+    for operator in &["&", "|", "bitxor", "<<", ">>"] {
+        let expression = format!("a {} b", operator);
+        assert_eq!(infer_one(&expression, "a"), vec![Int]);
+        assert_eq!(infer_one(&expression, "b"), vec![Int]);
+    }
+
+    // Tuples, parentheses and chains
+    assert_eq!(infer_one("(a, b)", "a"), any);
+    assert_eq!(infer_one("(a) + 1", "a"), number);
+    assert_eq!(infer_one("((a)) && true", "a"), vec![Boolean]);
+    assert_eq!(infer_one("(b; a) * 2", "a"), number);
+    assert_eq!(infer_one("(b; a) * 2", "b"), any);
+
+    // Builtin functions narrow their arguments by their signature, other functions do not.
+    #[cfg(all(feature = "builtin_string", feature = "builtin_tuple"))]
+    {
+        assert_eq!(infer_one("len(a)", "a"), vec![ValueType::String]);
+        assert_eq!(
+            infer_one("str::starts_with(a, b)", "b"),
+            vec![ValueType::String]
+        );
+        assert_eq!(infer_one("unique(a)", "a"), vec![Tuple]);
+        assert_eq!(infer_one("len(a) > b", "b"), number);
+        assert_eq!(infer_one("concat(a, b)", "a"), any);
+        assert!(infer_one("len(a) + a", "a").is_empty());
+    }
+    assert_eq!(infer_one("f(a)", "a"), any);
+    assert_eq!(infer_one("f(a) + 1", "a"), any);
+
+    // Nested operators do not narrow the variables within them.
+    assert_eq!(
+        infer_one("(a + b == c) && d", "a"),
+        vec![ValueType::String, Int, Float, Tuple]
+    );
+    assert_eq!(infer_one("(a + b == c) && d", "d"), vec![Boolean]);
+
+    // Uses narrow the type further.
+    assert_eq!(
+        infer_one("a > \"x\" || a + b == \"xy\"", "a"),
+        vec![ValueType::String]
+    );
+    assert_eq!(infer_one("a + 1; a % 2", "a"), number);
+
+    // Variables that are assigned before they are read are not inputs.
+    let types = infer("b = a * 2; c = b + 1; c > limit");
+    assert_eq!(types.keys().count(), 2);
+    assert_eq!(types["a"], number);
+    assert_eq!(types["limit"], number);
+    assert_eq!(infer_one("a = a + 1", "a"), number);
+    assert_eq!(infer_one("a = true; b = a && x", "x"), vec![Boolean]);
+    assert!(!infer("a = true; a && true").contains_key("a"));
+
+    // Unused variables and literals
+    assert_eq!(infer_one("a", "a"), any);
+    assert!(infer("1 + 2").is_empty());
+
+    // Conflicting uses are reported explicitly.
+    let types = build_operator_tree("a + 1 > 0 && a")
+        .unwrap()
+        .infer_variable_types();
+    assert!(types["a"].is_conflict());
+    assert_eq!(types["a"], TypeSet::CONFLICT);
+    assert!(types["a"].value_types().is_empty());
+    assert!(infer_one("a && b; a * 2", "a").is_empty());
+    assert!(infer_one("a < \"x\" || a < 1", "a").is_empty());
+    // Operators that fail for any type of the variable do not narrow it.
+    assert_eq!(infer_one("a + true", "a"), any);
+    assert_eq!(infer_one("(1 + \"x\") && a", "a"), any);
+
+    let types = build_operator_tree("a || a")
+        .unwrap()
+        .infer_variable_types();
+    assert!(!types["a"].is_conflict());
+    assert!(!types["a"].is_any());
+    assert!(types["a"].contains(Boolean));
+    assert!(!types["a"].contains(Int));
+    assert!(build_operator_tree("a").unwrap().infer_variable_types()["a"].is_any());
+    assert_eq!(
+        TypeSet::from(StaticType::Number).union(TypeSet::from(ValueType::String)),
+        TypeSet::from(StaticType::Float)
+            .union(TypeSet::from(Int))
+            .union(TypeSet::from(ValueType::String))
+    );
+    assert_eq!(
+        TypeSet::from(StaticType::Number).intersection(TypeSet::from(Float)),
+        TypeSet::from(Float)
+    );
+    assert_eq!(
+        format!("{:?}", TypeSet::from(StaticType::Number)),
+        "{Int, Float}"
+    );
+}
+
+#[test]
+fn test_debug_tree() {
+    let debug_tree = |expression: &str| build_operator_tree(expression).unwrap().to_debug_tree();
+
+    assert_eq!(debug_tree(""), "Root");
+    assert_eq!(
+        debug_tree("1 + 2 * 3 - -4 / 5 % 6 ^ 7.5"),
+        "\
+Root
+  Sub
+    Add
+      Const(Int 1)
+      Mul
+        Const(Int 2)
+        Const(Int 3)
+    Mod
+      Div
+        Neg
+          Const(Int 4)
+        Const(Int 5)
+      Exp
+        Const(Int 6)
+        Const(Float 7.5)"
+    );
+    assert_eq!(
+        debug_tree("a == \"x\" && !(c >= 1) || d <= 2 && e < f || g > h"),
+        "\
+Root
+  Or
+    Or
+      And
+        Eq
+          Var(a)
+          Const(String \"x\")
+        Not
+          Root
+            Geq
+              Var(c)
+              Const(Int 1)
+      And
+        Leq
+          Var(d)
+          Const(Int 2)
+        Lt
+          Var(e)
+          Var(f)
+    Gt
+      Var(g)
+      Var(h)"
+    );
+    assert_eq!(
+        debug_tree("!(a != b)"),
+        "\
+Root
+  Not
+    Root
+      Neq
+        Var(a)
+        Var(b)"
+    );
+    assert_eq!(
+        debug_tree("x = (1, \"a\", ()); f(x); g true"),
+        "\
+Root
+  Chain
+    Root
+      Assign
+        Const(String \"x\")
+        Root
+          Tuple
+            Root
+              Const(Int 1)
+            Root
+              Const(String \"a\")
+            Root
+              Root
+    Root
+      Fn(f)
+        Root
+          Var(x)
+    Root
+      Fn(g)
+        Const(Boolean true)"
+    );
+}
+
+#[test]
+fn test_operator_kind() {
+    // Returns the kind of the single node below the root node of the expression.
+    let kind = |expression: &str| {
+        let tree = build_operator_tree(expression).unwrap();
+        assert_eq!(tree.kind(), OperatorKind::Root);
+        assert_eq!(tree.children().len(), 1, "{}", expression);
+        tree.children()[0].kind()
+    };
+
+    assert_eq!(kind("(1)"), OperatorKind::Root);
+    assert_eq!(kind("1 + 2"), OperatorKind::Add);
+    assert_eq!(kind("1 - 2"), OperatorKind::Sub);
+    assert_eq!(kind("-1"), OperatorKind::Neg);
+    assert_eq!(kind("+1"), OperatorKind::Pos);
+    assert_eq!(kind("1 * 2"), OperatorKind::Mul);
+    assert_eq!(kind("1 / 2"), OperatorKind::Div);
+    assert_eq!(kind("1 % 2"), OperatorKind::Mod);
+    assert_eq!(kind("1 ^ 2"), OperatorKind::Exp);
+    assert_eq!(kind("1 == 2"), OperatorKind::Eq);
+    assert_eq!(kind("1 != 2"), OperatorKind::Neq);
+    assert_eq!(kind("1 > 2"), OperatorKind::Gt);
+    assert_eq!(kind("1 < 2"), OperatorKind::Lt);
+    assert_eq!(kind("1 >= 2"), OperatorKind::Geq);
+    assert_eq!(kind("1 <= 2"), OperatorKind::Leq);
+    assert_eq!(kind("\"a\" =~ \"b\""), OperatorKind::RegexMatch);
+    assert_eq!(kind("true && false"), OperatorKind::And);
+    assert_eq!(kind("true || false"), OperatorKind::Or);
+    assert_eq!(kind("true xor false"), OperatorKind::Xor);
+    assert_eq!(kind("!true"), OperatorKind::Not);
+    assert_eq!(kind("true implies false"), OperatorKind::Implies);
+    assert_eq!(kind("true ? 1 : 2"), OperatorKind::Conditional);
+    assert_eq!(kind("a ?? 2"), OperatorKind::Coalesce);
+    assert_eq!(kind("1 & 2"), OperatorKind::BitAnd);
+    assert_eq!(kind("1 | 2"), OperatorKind::BitOr);
+    assert_eq!(kind("1 bitxor 2"), OperatorKind::BitXor);
+    assert_eq!(kind("1 << 2"), OperatorKind::Shl);
+    assert_eq!(kind("1 >> 2"), OperatorKind::Shr);
+    assert_eq!(kind("1..2"), OperatorKind::Range);
+    assert_eq!(kind("1..=2"), OperatorKind::RangeInclusive);
+    assert_eq!(kind("1, 2"), OperatorKind::Tuple);
+    assert_eq!(kind("a = 1"), OperatorKind::Assign);
+    assert_eq!(kind("1; 2"), OperatorKind::Chain);
+    assert_eq!(kind("1"), OperatorKind::Constant);
+    assert_eq!(kind("1.5"), OperatorKind::Constant);
+    assert_eq!(kind("\"a\""), OperatorKind::Constant);
+    assert_eq!(kind("true"), OperatorKind::Constant);
+    assert_eq!(kind("()"), OperatorKind::Root);
+    assert_eq!(kind("a"), OperatorKind::VariableRead);
+    assert_eq!(kind("f(1)"), OperatorKind::FunctionCall);
+    assert_eq!(kind("f 1"), OperatorKind::FunctionCall);
+    assert_eq!(kind("f(1, a = 2)"), OperatorKind::FunctionCall);
+
+    // The identifier of an assignment is stored as a string constant.
+    let tree = build_operator_tree("a = b").unwrap();
+    let kinds: Vec<_> = tree.iter().map(Node::kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            OperatorKind::Assign,
+            OperatorKind::Constant,
+            OperatorKind::VariableRead
+        ]
+    );
+
+    // The argument of a function call is its only child.
+    assert_eq!(
+        build_operator_tree("max(a, 2)").unwrap().to_debug_tree(),
+        "\
+Root
+  Fn(max)
+    Root
+      Tuple
+        Root
+          Var(a)
+        Root
+          Const(Int 2)"
+    );
+}
+
+/// Writes the operator tree with one node per line like `Node::to_debug_tree`, but skips root nodes with a single child.
+fn normalized_tree(node: &Node) -> String {
+    fn write(node: &Node, result: &mut String, depth: usize) {
+        if node.kind() == OperatorKind::Root && node.children().len() == 1 {
+            return write(&node.children()[0], result, depth);
+        }
+        result.push_str(&"  ".repeat(depth));
+        result.push_str(node.to_debug_tree().lines().next().unwrap());
+        result.push('\n');
+        for child in node.children() {
+            write(child, result, depth + 1);
+        }
+    }
+
+    let mut result = String::new();
+    write(node, &mut result, 0);
+    result
+}
+
+#[test]
+fn test_minified_string() {
+    let corpus = [
+        "",
+        "()",
+        "1 + 2 * 3",
+        "(1 + 2) * 3",
+        "1 - (2 - 3)",
+        "(1 - 2) - 3",
+        "2 ^ (3 ^ 2)",
+        "(2 ^ 3) ^ 2",
+        "a / (b * c) % d",
+        "-(a + b)",
+        "- -a",
+        "a - -b",
+        "-a ^ 2",
+        "(-a) ^ 2",
+        "!(a && b) || !c",
+        "a == !b",
+        "(a < b) == (c >= d)",
+        "a && (b || c)",
+        "a = -1",
+        "a = b = 3",
+        "a = (b, c)",
+        "(a = 1), b",
+        "a = 1; b = a + 1; b",
+        "a = 1;",
+        "(1, (2, 3)), ()",
+        "((1; 2), 3)",
+        "f()",
+        "f(1, 2)",
+        "f x + 1",
+        "max(1, (2, 3), f(g(4)))",
+        "f(x, precision = 2)",
+        "f(a = 1, b = (2, 3), c = (d = 4))",
+        "f((a = 1))",
+        "map(xs, total = total + it)",
+        "\"a\\\"b\\\\c\" + \"\"",
+        "0.5 + 1.0 + 1e10 + 1.25 + 0.001 + 100.0",
+        "(0.0) * (10000000000.0)",
+        "  a   +   b  ",
+        "true && !false",
+        "(1, ()), 1e300",
+        "(1,), ((2,),), -(3,)",
+    ];
+
+    for expression in corpus.iter() {
+        let tree = build_operator_tree(expression).unwrap();
+        let minified = tree.to_minified_string();
+        let reparsed = build_operator_tree(&minified).unwrap_or_else(|error| {
+            panic!("{:?} minified to {:?}: {}", expression, minified, error)
+        });
+        assert_eq!(
+            normalized_tree(&reparsed),
+            normalized_tree(&tree),
+            "{:?} minified to {:?}",
+            expression,
+            minified
+        );
+        assert_eq!(reparsed.to_minified_string(), minified);
+        assert!(
+            minified.len() <= expression.len(),
+            "{:?} minified to {:?}",
+            expression,
+            minified
+        );
+    }
+
+    let minify = |expression: &str| {
+        build_operator_tree(expression)
+            .unwrap()
+            .to_minified_string()
+    };
+    assert_eq!(minify(""), "");
+    assert_eq!(minify("()"), "");
+    assert_eq!(minify("((1 + 2)) * 3"), "(1+2)*3");
+    assert_eq!(minify("1 - (2 - 3)"), "1-(2-3)");
+    assert_eq!(minify("(1 - 2) - 3"), "1-2-3");
+    assert_eq!(minify("2 ^ (3 ^ 2)"), "2^3^2");
+    assert_eq!(minify("(2 ^ 3) ^ 2"), "(2^3)^2");
+    assert_eq!(minify("2 ^ -1"), "2^(-1)");
+    assert_eq!(minify("a = (b = 3)"), "a=b=3");
+    assert_eq!(minify("a - -b"), "a--b");
+    assert_eq!(minify("(1, (2, 3)), ()"), "(1,(2,3)),()");
+    assert_eq!(minify("a = 1;"), "a=1;");
+    assert_eq!(minify("f x + 1"), "f(x)+1");
+    assert_eq!(minify("f(x, precision = 2)"), "f(x,precision=2)");
+    assert_eq!(minify("f(a += 1)"), "f((a=a+1))");
+    assert_eq!(minify("0.5 + 1.0 + 1e10 + 0.001"), ".5+1.+1e10+.001");
+
+    // Operators that lack an operand, like in `1 * * 2`, are written in prefix notation.
+    assert_eq!(minify("1 * * 2"), "* 1*2");
+    assert_eq!(minify("1 & & 2"), "& 1&2");
+    assert_eq!(minify("1 == == 2"), "(== 1)==2");
+    assert_eq!(minify("1 ?? ?? 2"), "1???? 2");
+    assert_eq!(minify("-"), "-");
+    assert_eq!(minify("\"a\\\"b\""), "\"a\\\"b\"");
+
+    let mut context = HashMapContext::new();
+    for expression in [
+        "1 - (2 - 3) * 4 ^ 2",
+        "-(2 ^ 2) + 2.5 % 2",
+        "(1, 2), (3, 4)",
+    ]
+    .iter()
+    {
+        let reparsed = build_operator_tree(&minify(expression)).unwrap();
+        assert_eq!(
+            reparsed.eval_with_context_mut(&mut context),
+            eval(expression)
+        );
+    }
+}
+
+#[test]
+fn test_renamed_string() {
+    let mut renames = std::collections::HashMap::new();
+    renames.insert("count".to_string(), "a".to_string());
+    renames.insert("total".to_string(), "b".to_string());
+    renames.insert("f".to_string(), "c".to_string());
+
+    let tree = build_operator_tree(
+        "total = count * 2; total + len(\"count\") + f(count, \"total\") + other",
+    )
+    .unwrap();
+    let renamed = tree.to_renamed_string(&renames);
+    assert_eq!(renamed, "b=a*2;b+len(\"count\")+f(a,\"total\")+other");
+
+    // The renamed expression has the same structure with the new identifiers.
+    let reparsed = build_operator_tree(&renamed).unwrap();
+    assert_eq!(
+        reparsed.iter_variable_identifiers().collect::<Vec<_>>(),
+        vec!["a", "b", "a", "other"]
+    );
+    assert_eq!(
+        normalized_tree(&reparsed),
+        normalized_tree(&tree)
+            .replace("Var(count)", "Var(a)")
+            .replace("Var(total)", "Var(b)")
+            .replace(
+                "Assign\n    Const(String \"total\")",
+                "Assign\n    Const(String \"b\")"
+            )
+    );
+
+    let mut context = HashMapContext::new();
+    context.set_value("a", 3).unwrap();
+    context.set_value("other", 1).unwrap();
+    context
+        .set_function(
+            "f".into(),
+            Function::new(Box::new(|argument| {
+                Ok(Value::Int(argument.as_tuple()?.len() as IntType))
+            })),
+        )
+        .unwrap();
+    // Also available without the `builtin_string` feature.
+    context
+        .set_function(
+            "len".into(),
+            Function::new(Box::new(|argument| {
+                Ok(Value::Int(argument.as_string()?.len() as IntType))
+            })),
+        )
+        .unwrap();
+    assert_eq!(
+        reparsed.eval_int_with_context_mut(&mut context),
+        Ok(6 + 5 + 2 + 1)
+    );
+    assert_eq!(
+        tree.to_renamed_string(&Default::default()),
+        tree.to_minified_string()
+    );
+}
+
+#[test]
+fn test_source_map() {
+    let source = "(price * quantity) > 10 &&\n  total / (items - returned) > 1";
+    let tree = build_operator_tree(source).unwrap();
+    let (minified, source_map) = tree.to_minified_string_with_source_map(source).unwrap();
+    assert_eq!(minified, "price*quantity>10&&total/(items-returned)>1");
+    assert_eq!(minified, tree.to_minified_string());
+
+    // A runtime error is found in the minified expression and translated back to the clause that was written.
+    let context = context_map! {
+        "price" => 5,
+        "quantity" => 3,
+        "total" => 15,
+        "items" => 2,
+        "returned" => 2
+    }
+    .unwrap();
+    assert_eq!(
+        tree.eval_with_context(&context),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(15),
+            divisor: Value::from(0),
+        })
+    );
+    let mut failing = tree.explain_with_context(&context).unwrap();
+    while let Some(child) = failing
+        .children
+        .iter()
+        .find(|child| matches!(child.result, Some(Err(_))))
+    {
+        failing = child.clone();
+    }
+    assert_eq!(failing.expression, "total/(items-returned)");
+    let start = minified.find(&failing.expression).unwrap();
+    let range = source_map
+        .map_range(start..start + failing.expression.len())
+        .unwrap();
+    assert_eq!(&source[range], "total / (items - returned)");
+
+    // Offsets within tokens map to the same character, and offsets of dropped or inserted characters map to nothing.
+    let offset = minified.find("quantity").unwrap() + 3;
+    assert_eq!(
+        source_map.map_offset(offset),
+        Some(source.find("quantity").unwrap() + 3)
+    );
+    assert_eq!(
+        source_map.map_offset(minified.find('(').unwrap()),
+        Some(source.find("(items").unwrap())
+    );
+    assert_eq!(source_map.map_offset(minified.len()), None);
+    let (minified, source_map) = build_operator_tree("a - (b - c)")
+        .unwrap()
+        .to_minified_string_with_source_map("a - (b - c)")
+        .unwrap();
+    assert_eq!(minified, "a-(b-c)");
+    assert_eq!(source_map.mappings().len(), 7);
+    let (minified, source_map) = build_operator_tree("-x ^ 2")
+        .unwrap()
+        .to_minified_string_with_source_map("-x ^ 2")
+        .unwrap();
+    assert_eq!(minified, "-x^2");
+    assert_eq!(
+        source_map.mappings(),
+        &[(0..1, 0..1), (1..2, 1..2), (2..3, 3..4), (3..4, 5..6)]
+    );
+
+    // Renamed identifiers and respelled literals map to their whole token.
+    let source = "total_price * 1.50";
+    let mut renames = std::collections::HashMap::new();
+    renames.insert("total_price".to_string(), "a".to_string());
+    let (renamed, source_map) = build_operator_tree(source)
+        .unwrap()
+        .to_renamed_string_with_source_map(source, &renames)
+        .unwrap();
+    assert_eq!(renamed, "a*1.5");
+    assert_eq!(
+        source_map.mappings(),
+        &[(0..1, 0..11), (1..2, 12..13), (2..5, 14..18)]
+    );
+    assert_eq!(source_map.map_offset(0), Some(0));
+    assert_eq!(source_map.map_offset(4), Some(16));
+    assert_eq!(source_map.map_range(0..5), Some(0..18));
+    assert_eq!(source_map.map_range(5..5), None);
+
+    // The source needs to be a valid expression.
+    assert_eq!(
+        build_operator_tree("a")
+            .unwrap()
+            .to_minified_string_with_source_map("\"a")
+            .err(),
+        Some(EvalexprError::UnterminatedString("a".to_string()))
+    );
+}
+
+#[test]
+fn test_expression_literal_round_trip() {
+    let strings = vec![
+        "",
+        "plain",
+        "\"",
+        "\\",
+        "\\\"",
+        "\"; drop == true",
+        "\" + \"",
+        "a\nb\r\nc\td",
+        "trailing backslash \\",
+        "ünïcödé ✓ 日本語 🦀",
+        "(1, 2)",
+        "x = 5; y",
+        "   padded   ",
+    ];
+    let mut values: Vec<Value> = strings.into_iter().map(Value::from).collect();
+    values.extend(vec![
+        Value::from(0),
+        Value::from(42),
+        Value::from(-42),
+        Value::from(IntType::MAX),
+        Value::from(IntType::MIN),
+        Value::from(0.0),
+        Value::from(-0.0),
+        Value::from(1.5),
+        Value::from(-1.5),
+        Value::from(1e300),
+        Value::from(-1e-300),
+        Value::from(0.1 + 0.2),
+        Value::from(FloatType::MAX),
+        Value::from(FloatType::MIN_POSITIVE),
+        Value::from(FloatType::INFINITY),
+        Value::from(FloatType::NEG_INFINITY),
+        Value::from(true),
+        Value::from(false),
+        Value::Empty,
+        Value::Tuple(TupleType::new()),
+        Value::from(vec![Value::from(1)]),
+        Value::from(vec![Value::Tuple(TupleType::new())]),
+        Value::from(vec![Value::from(1), Value::from("a\"b")]),
+        Value::from(vec![
+            Value::from(vec![Value::from(-1), Value::from(2.5)]),
+            Value::from(vec![
+                Value::from(true),
+                Value::from(vec![Value::from("\\"), Value::Empty]),
+            ]),
+        ]),
+    ]);
+
+    for value in values {
+        let literal = value.to_expression_literal();
+        assert_eq!(eval(&literal), Ok(value.clone()), "literal: {}", literal);
+        if let Value::Float(float) = value {
+            assert_eq!(
+                eval_float(&literal).map(FloatType::is_sign_negative),
+                Ok(float.is_sign_negative()),
+                "literal: {}",
+                literal
+            );
+        }
+
+        // Literals stay intact when embedded into a larger expression.
+        let embedded = format!("x = {}; x", literal);
+        assert_eq!(
+            eval_with_context_mut(&embedded, &mut HashMapContext::new()),
+            Ok(value),
+            "expression: {}",
+            embedded
+        );
+    }
+
+    let nan = Value::from(FloatType::NAN).to_expression_literal();
+    assert!(eval_float(&nan).unwrap().is_nan());
+    assert_eq!(
+        eval(&format!(
+            "{} == {}",
+            Value::from(2).to_expression_literal(),
+            Value::from(-2).to_expression_literal()
+        )),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval(&format!("2 ^ {}", Value::from(-1).to_expression_literal())),
+        Ok(Value::from(0.5))
+    );
+}
+
+/// Returns a value of any variant, with tuples nested up to the given depth, from the given state of a xorshift generator.
+fn arbitrary_value(state: &mut u64, depth: usize) -> Value {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    let random = *state;
+    let choice = (random >> 8) as usize;
+    match random % 6 {
+        0 => Value::from(["", "a\"b", "\\", "line\nbreak", "ü🦀", "x = 1; y"][choice % 6]),
+        1 if choice % 8 == 0 => Value::from(IntType::MIN),
+        1 => Value::from(random as IntType),
+        2 => {
+            let float = FloatType::from_bits(random.rotate_left(7));
+            Value::from(if float.is_nan() { -0.0 } else { float })
+        },
+        3 => Value::from(choice % 2 == 0),
+        4 if depth > 0 => Value::Tuple(
+            (0..choice % 4)
+                .map(|_| arbitrary_value(state, depth - 1))
+                .collect(),
+        ),
+        _ => Value::Empty,
+    }
+}
+
+#[test]
+fn test_expression_literal_of_arbitrary_values() {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    let mut seen = [false; 6];
+    let mut tuple_lengths = [false; 4];
+    for _ in 0..2000 {
+        let value = arbitrary_value(&mut state, 3);
+        let variant = match &value {
+            Value::String(_) => 0,
+            Value::Float(_) => 1,
+            Value::Int(_) => 2,
+            Value::Boolean(_) => 3,
+            Value::Tuple(tuple) => {
+                tuple_lengths[tuple.len()] = true;
+                4
+            },
+            Value::Empty => 5,
+        };
+        seen[variant] = true;
+
+        let literal = value.to_expression_literal();
+        assert_eq!(eval(&literal), Ok(value), "literal: {}", literal);
+    }
+    assert_eq!(seen, [true; 6]);
+    assert_eq!(tuple_lengths, [true; 4]);
+}
+
+#[test]
+#[cfg(feature = "builtin_datetime")]
+fn test_now_monotonic() {
+    use std::cell::Cell;
+
+    let t0 = eval_float("now_monotonic()").unwrap();
+    let t1 = eval_float("now_monotonic()").unwrap();
+    assert!(t0 >= 0.0);
+    assert!(t1 >= t0);
+    assert_eval_err!("now_monotonic(1)", EvalexprError::ExpectedEmpty { .. });
+
+    // The clock can be overridden by the context for deterministic results.
+    let ticks = Cell::new(0.0);
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "now_monotonic".into(),
+            Function::new(Box::new(move |argument| {
+                argument.as_empty()?;
+                ticks.set(ticks.get() + 1.5);
+                Ok(Value::Float(ticks.get()))
+            })),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context_mut(
+            "t0 = now_monotonic(); t1 = now_monotonic(); elapsed = now_monotonic() - t0; (t0, t1, elapsed)",
+            &mut context
+        ),
+        Ok(Value::from(vec![
+            Value::from(1.5),
+            Value::from(3.0),
+            Value::from(3.0)
+        ]))
+    );
+    assert_eq!(context.get_value("elapsed"), Some(&Value::from(3.0)));
+}
+
+#[test]
+fn test_assignment_to_non_manipulable_context() {
+    use std::{cell::Cell, rc::Rc};
+
+    // A context that supports functions, but not assignments.
+    struct ReadOnlyContext(HashMapContext);
+
+    impl Context for ReadOnlyContext {
+        fn get_value(&self, identifier: &str) -> Option<&Value> {
+            self.0.get_value(identifier)
+        }
+
+        fn get_function(&self, identifier: &str) -> Option<&Function> {
+            self.0.get_function(identifier)
+        }
+    }
+
+    let calls = Rc::new(Cell::new(0));
+    let mut context = HashMapContext::new();
+    context.set_value("a", Value::from(0)).unwrap();
+    let record_calls = calls.clone();
+    context
+        .set_function(
+            "record".into(),
+            Function::new(Box::new(move |_| {
+                record_calls.set(record_calls.get() + 1);
+                Ok(Value::from(1))
+            })),
+        )
+        .unwrap();
+    let mut read_only = ReadOnlyContext(context);
+
+    assert!(read_only.0.is_manipulable());
+    assert!(!read_only.is_manipulable());
+    assert!(!EmptyContext.is_manipulable());
+
+    // The right-hand side is evaluated completely, then the assignment fails.
+    assert_eq!(
+        eval_with_context("a = record()", &read_only),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(calls.replace(0), 1);
+    assert_eq!(
+        eval_with_context_mut("a = record()", &mut read_only),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(calls.replace(0), 1);
+    assert_eq!(
+        eval_with_context("a = (record(), record())", &read_only.0),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(calls.replace(0), 2);
+    assert_eq!(
+        eval_script("b = record(); export(b)", &mut read_only),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(calls.replace(0), 1);
+
+    // Expressions after a failed assignment are not evaluated.
+    assert_eq!(
+        eval_with_context_mut("a = record(); record()", &mut read_only),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+    assert_eq!(calls.replace(0), 1);
+    assert_eq!(read_only.get_value("a"), Some(&Value::from(0)));
+
+    // A rejected value behaves the same.
+    assert_eq!(
+        eval_with_context_mut("a = record() + 0.5", &mut read_only.0),
+        Err(EvalexprError::expected_int(Value::from(1.5)))
+    );
+    assert_eq!(calls.replace(0), 1);
+    assert_eq!(
+        eval_with_context_mut("a = record()", &mut read_only.0),
+        Ok(Value::Empty)
+    );
+    assert_eq!(calls.replace(0), 1);
+    assert_eq!(read_only.get_value("a"), Some(&Value::from(1)));
+
+    // Invalid assignments fail the same way with immutable and mutable contexts, before anything is evaluated.
+    assert_eq!(
+        eval_with_context("1 = record()", &read_only),
+        Err(EvalexprError::InvalidAssignmentTarget { span: 2..3 })
+    );
+    assert_eq!(
+        eval_with_context_mut("1 = record()", &mut read_only),
+        Err(EvalexprError::InvalidAssignmentTarget { span: 2..3 })
+    );
+    assert_eq!(calls.replace(0), 0);
+}
+
+#[test]
+fn test_change_hooks() {
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+
+    fn recording_hook(
+        prefix: &'static str,
+        changes: &Arc<Mutex<Vec<String>>>,
+    ) -> Box<ChangeHookFunction> {
+        let changes = changes.clone();
+        Box::new(move |identifier, value| {
+            changes
+                .lock()
+                .unwrap()
+                .push(format!("{}{} = {}", prefix, identifier, value));
+        })
+    }
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let mut context = HashMapContext::new();
+    context.set_on_change(recording_hook("", &changes));
+    context.set_record_changes(true);
+
+    eval_empty_with_context_mut("a = 1; b = a + 1; a = a * 10; c = \"x\"", &mut context).unwrap();
+    assert_eq!(
+        *changes.lock().unwrap(),
+        vec!["a = 1", "b = 2", "a = 10", "c = \"x\""]
+    );
+    assert_eq!(
+        context.take_changes(),
+        vec![
+            ("a".to_string(), Value::from(1)),
+            ("b".to_string(), Value::from(2)),
+            ("a".to_string(), Value::from(10)),
+            ("c".to_string(), Value::from("x")),
+        ]
+    );
+    assert_eq!(context.take_changes(), vec![]);
+    changes.lock().unwrap().clear();
+
+    // Failed writes are not reported, but the writes before them are.
+    assert_eq!(
+        eval_empty_with_context_mut("b = 3; a = 1.5; c = \"y\"", &mut context),
+        Err(EvalexprError::expected_int(Value::from(1.5)))
+    );
+    assert_eq!(*changes.lock().unwrap(), vec!["b = 3"]);
+    assert_eq!(
+        context.take_changes(),
+        vec![("b".to_string(), Value::from(3))]
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::from(10)));
+    changes.lock().unwrap().clear();
+
+    // Direct calls of `set_value` are reported as well.
+    context.set_value("d", Value::from(true)).unwrap();
+    assert!(context.set_value("d", Value::from(1)).is_err());
+    assert_eq!(*changes.lock().unwrap(), vec!["d = true"]);
+    assert_eq!(
+        context.take_changes(),
+        vec![("d".to_string(), Value::from(true))]
+    );
+    changes.lock().unwrap().clear();
+
+    // Writes to another context from within a function are reported by the hook of that context, in the order in which they happen.
+    let store = Rc::new(RefCell::new(HashMapContext::new()));
+    store
+        .borrow_mut()
+        .set_on_change(recording_hook("store.", &changes));
+    let function_store = store.clone();
+    context
+        .set_function(
+            "save".into(),
+            Function::new(Box::new(move |argument| {
+                function_store
+                    .borrow_mut()
+                    .set_value("saved", argument.clone())?;
+                Ok(argument.clone())
+            })),
+        )
+        .unwrap();
+    eval_empty_with_context_mut("e = save(4); f = save(5) + 1", &mut context).unwrap();
+    assert_eq!(
+        *changes.lock().unwrap(),
+        vec!["store.saved = 4", "e = 4", "store.saved = 5", "f = 6"]
+    );
+    assert_eq!(
+        context.take_changes(),
+        vec![
+            ("e".to_string(), Value::from(4)),
+            ("f".to_string(), Value::from(6)),
+        ]
+    );
+    changes.lock().unwrap().clear();
+
+    // A failing write within a function aborts the assignment, so neither write is reported.
+    assert!(eval_empty_with_context_mut("g = save(true)", &mut context).is_err());
+    assert!(changes.lock().unwrap().is_empty());
+    assert_eq!(context.take_changes(), vec![]);
+
+    // Without a hook and without recording, nothing is reported.
+    context.clear_on_change();
+    context.set_record_changes(false);
+    eval_empty_with_context_mut("a = 11", &mut context).unwrap();
+    assert!(changes.lock().unwrap().is_empty());
+    assert_eq!(context.take_changes(), vec![]);
+    context.set_record_changes(true);
+    assert_eq!(context.take_changes(), vec![]);
+}
+
+#[test]
+fn test_hash_map_context_generic_accessors() {
+    let mut context = HashMapContext::new();
+    let key = "b".to_string();
+
+    // Identifiers can be given as `&str` or `String`, and values as anything that converts into a `Value`.
+    context.set_value("a", 1).unwrap();
+    context.set_value(key.clone(), 2.5).unwrap();
+    context.set_value("c", "three").unwrap();
+    context.set_value("d", Value::from(true)).unwrap();
+    context.set_value("e", vec![Value::from(1)]).unwrap();
+
+    assert_eq!(context.get_value("a"), Some(&Value::from(1)));
+    assert_eq!(context.get_value(&key), Some(&Value::from(2.5)));
+    assert_eq!(context.get_value(key.clone()), Some(&Value::from(2.5)));
+    assert_eq!(context.get_value("c"), Some(&Value::from("three")));
+    assert_eq!(context.get_value("missing"), None);
+
+    // Overwriting behaves like before: the type of a variable cannot change.
+    context.set_value("a", 2).unwrap();
+    assert_eq!(context.get_value("a"), Some(&Value::from(2)));
+    assert_eq!(
+        context.set_value("a", "two"),
+        Err(EvalexprError::expected_int(Value::from("two")))
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::from(2)));
+    assert_eq!(eval_with_context("a + b", &context), Ok(Value::from(4.5)));
+
+    // The `Context` methods are the same as the generic methods.
+    {
+        let dynamic: &mut dyn Context = &mut context;
+        dynamic.set_value("a".to_string(), Value::from(3)).unwrap();
+        assert!(dynamic
+            .set_value("a".to_string(), Value::from(true))
+            .is_err());
+        assert_eq!(dynamic.get_value("a"), Some(&Value::from(3)));
+    }
+    assert_eq!(context.get_value("a"), Some(&Value::from(3)));
+
+    // Overwrites through the generic method are observed like any other change.
+    context.set_record_changes(true);
+    context.set_value("a", 4).unwrap();
+    assert!(context.set_value("a", false).is_err());
+    context.set_value(String::from("f"), "new").unwrap();
+    assert_eq!(
+        context.take_changes(),
+        vec![
+            ("a".to_string(), Value::from(4)),
+            ("f".to_string(), Value::from("new"))
+        ]
+    );
+}
+
+#[test]
+fn test_hash_map_context_eq_and_debug() {
+    // Two scripts that compute the same variables in a different order.
+    let mut a = HashMapContext::new();
+    let mut b = HashMapContext::new();
+    eval_with_context_mut("net = 100; tax = net / 5; gross = net + tax", &mut a).unwrap();
+    eval_with_context_mut("tax = 20; gross = 120; net = gross - tax", &mut b).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    #[cfg(not(feature = "async"))]
+    assert_eq!(
+        format!("{:?}", a),
+        "HashMapContext { variables: {\"gross\": Int(120), \"net\": Int(100), \"tax\": Int(20)}, functions: {}, expressions: {} }"
+    );
+
+    // A single different variable is detected.
+    eval_with_context_mut("tax = 21", &mut b).unwrap();
+    assert_ne!(a, b);
+    eval_with_context_mut("tax = 20; discount = 0", &mut b).unwrap();
+    assert_ne!(a, b);
+    let mut b = context_map! { "gross" => 120, "net" => 100, "tax" => 20 }.unwrap();
+    assert_eq!(a, b);
+
+    // Functions are compared by their identifiers only.
+    a.set_function("f".into(), Function::new(Box::new(|_| Ok(Value::from(1)))))
+        .unwrap();
+    assert_ne!(a, b);
+    b.set_function("f".into(), Function::new(Box::new(|_| Ok(Value::from(2)))))
+        .unwrap();
+    assert_eq!(a, b);
+
+    // Named expressions are compared by their operator trees.
+    a.set_expression("double".into(), build_operator_tree("net * 2").unwrap())
+        .unwrap();
+    b.set_expression("double".into(), build_operator_tree("2 * net").unwrap())
+        .unwrap();
+    assert_ne!(a, b);
+    b.set_expression("double".into(), build_operator_tree("net*2").unwrap())
+        .unwrap();
+    assert_eq!(a, b);
+    #[cfg(not(feature = "async"))]
+    assert_eq!(
+        format!("{:?}", b),
+        "HashMapContext { variables: {\"gross\": Int(120), \"net\": Int(100), \"tax\": Int(20)}, functions: {\"f\"}, expressions: {\"double\": \"net*2\"} }"
+    );
+
+    // Recorded changes are shown, but not compared.
+    b.set_record_changes(true);
+    b.set_value("net", 100).unwrap();
+    assert_eq!(a, b);
+    assert!(format!("{:?}", b).ends_with(", changes: [(\"net\", Int(100))] }"));
+}
+
+#[test]
+fn test_eval_script() {
+    let mut context = HashMapContext::new();
+    let exports = eval_script(
+        "a = 1; b = 2.5; c = \"c\"; d = a + 4; e = (a, d); export(b, e)",
+        &mut context,
+    )
+    .unwrap();
+    let mut expected = std::collections::HashMap::new();
+    expected.insert("b".to_string(), Value::from(2.5));
+    expected.insert(
+        "e".to_string(),
+        Value::from(vec![Value::from(1), Value::from(5)]),
+    );
+    assert_eq!(exports, expected);
+    for (identifier, value) in [
+        ("a", Value::from(1)),
+        ("b", Value::from(2.5)),
+        ("c", Value::from("c")),
+        ("d", Value::from(5)),
+        ("e", Value::from(vec![Value::from(1), Value::from(5)])),
+    ] {
+        assert_eq!(context.get_value(identifier), Some(&value));
+    }
+
+    // Exported values are read after the whole script was evaluated, and exports may be split and repeated.
+    let mut context = HashMapContext::new();
+    let exports = eval_script(
+        "a = 1; export(a); b = 2; export b; a = 3; export(a, b)",
+        &mut context,
+    )
+    .unwrap();
+    assert_eq!(exports.len(), 2);
+    assert_eq!(exports["a"], Value::from(3));
+    assert_eq!(exports["b"], Value::from(2));
+
+    // Variables of the context can be exported as well, and scripts without exports export nothing.
+    let mut context = context_map! { "x" => 7 }.unwrap();
+    assert_eq!(
+        eval_script("export(x)", &mut context).unwrap()["x"],
+        Value::from(7)
+    );
+    assert!(eval_script("y = x + 1; export()", &mut context)
+        .unwrap()
+        .is_empty());
+    assert!(eval_script("y = x + 1", &mut context).unwrap().is_empty());
+
+    assert_eq!(
+        eval_script("a = 1; export(a, undefined)", &mut HashMapContext::new()),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "undefined".to_string(),
+            span: Some(17..26),
+        })
+    );
+    assert!(matches!(
+        eval_script("a = 1; export(a, 1 + 2)", &mut HashMapContext::new()),
+        Err(EvalexprError::InvalidExportArgument(_))
+    ));
+    assert!(matches!(
+        eval_script("a = 1; export(\"a\")", &mut HashMapContext::new()),
+        Err(EvalexprError::InvalidExportArgument(_))
+    ));
+    // `export` is only defined in scripts.
+    assert_eq!(
+        eval_with_context_mut("a = 1; export(a)", &mut HashMapContext::new()),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "export".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_eval_first_success() {
+    let user = context_map! { "theme" => "dark" }.unwrap();
+    let group = context_map! {
+        "font_size" => 14,
+        "ratio" => 0,
+        "scale" => fn |x: IntType| x * 2,
+    }
+    .unwrap();
+    let global = context_map! {
+        "font_size" => 12,
+        "theme" => "light",
+        "ratio" => 2,
+        "scale" => fn |x: IntType| x * 3,
+    }
+    .unwrap();
+    let contexts = vec![&user as &dyn Context, &group, &global];
+
+    // The second context is the first one that defines all variables.
+    assert_eq!(
+        eval_first_success("font_size * 2", contexts.clone()),
+        Ok((1, Value::from(28)))
+    );
+    assert_eq!(
+        eval_first_success("theme", contexts.clone()),
+        Ok((0, Value::from("dark")))
+    );
+    assert_eq!(
+        eval_first_success("scale(font_size)", contexts.clone()),
+        Ok((1, Value::from(28)))
+    );
+    let tree = build_operator_tree("(theme, font_size)").unwrap();
+    assert_eq!(
+        tree.eval_first_success(contexts.clone()),
+        Ok((2, Value::from(vec![Value::from("light"), Value::from(12)])))
+    );
+
+    // If all contexts fail, the errors of all of them are returned.
+    assert_eq!(
+        eval_first_success("line_height", contexts.clone()),
+        Err(EvalexprError::NoContextSucceeded {
+            errors: vec![
+                EvalexprError::VariableIdentifierNotFound {
+                    identifier: "line_height".to_string(),
+                    span: Some(0..11),
+                };
+                3
+            ]
+        })
+    );
+    let error = build_operator_tree("font_size + missing(1)")
+        .unwrap()
+        .eval_first_success(contexts.clone())
+        .unwrap_err();
+    assert_eq!(error.variant_name(), "NoContextSucceeded");
+    assert_eq!(
+        error.to_string(),
+        "The evaluation failed with all 3 contexts; \
+         context 0: Variable identifier is not bound to anything by context: \"font_size\" at offset 0.; \
+         context 1: Function identifier is not bound to anything by context: \"missing\".; \
+         context 2: Function identifier is not bound to anything by context: \"missing\"."
+    );
+    assert_eq!(
+        eval_first_success("1", Vec::new()),
+        Err(EvalexprError::NoContextSucceeded { errors: Vec::new() })
+    );
+
+    // Other errors do not fall through, unless requested.
+    assert_eq!(
+        eval_first_success("font_size / ratio", contexts.clone()),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(14),
+            divisor: Value::from(0),
+        })
+    );
+    let tree = build_operator_tree("font_size / ratio").unwrap();
+    assert_eq!(
+        tree.eval_first_success_with_fallthrough(contexts.clone(), true),
+        Ok((2, Value::from(6)))
+    );
+    assert_eq!(
+        tree.eval_first_success_with_fallthrough(contexts.clone(), false),
+        tree.eval_first_success(contexts.clone())
+    );
+    assert_eq!(
+        build_operator_tree("font_size + true")
+            .unwrap()
+            .eval_first_success_with_fallthrough(contexts, true)
+            .map_err(|error| error.user_message()),
+        Err("This expression could not be evaluated with any of the given contexts.".to_string())
+    );
+
+    // Errors of parsing are returned as they are.
+    assert_eq!(
+        eval_first_success("(", vec![&user as &dyn Context]),
+        Err(EvalexprError::UnmatchedLBrace { span: 0..1 })
+    );
+}
+
+#[test]
+fn test_named_expressions() {
+    let mut context = context_map! {
+        "orders" => 12,
+        "revenue" => 1500.0
+    }
+    .unwrap();
+    context
+        .set_expression(
+            "is_frequent".into(),
+            build_operator_tree("orders >= 10").unwrap(),
+        )
+        .unwrap();
+    context
+        .set_expression(
+            "is_premium".into(),
+            build_operator_tree("is_frequent && revenue > 1000").unwrap(),
+        )
+        .unwrap();
+
+    // Two levels of composition, evaluated in the current context
+    assert_eval_eq!("is_premium", true, &context);
+    assert_eval_eq!("is_premium && !is_frequent", false, &context);
+    context.set_value("orders", Value::from(3)).unwrap();
+    assert_eval_eq!("is_premium", false, &context);
+
+    // Named expressions can assign to a mutable context.
+    context
+        .set_expression(
+            "bump".into(),
+            build_operator_tree("orders = orders + 1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context_mut("bump; bump; orders", &mut context),
+        Ok(Value::from(5))
+    );
+
+    // Plain variables shadow named expressions.
+    context.set_value("is_frequent", Value::from(true)).unwrap();
+    assert_eval_eq!("is_premium", true, &context);
+
+    // Cycles are reported with the chain of named expressions.
+    context
+        .set_expression("a".into(), build_operator_tree("b + 1").unwrap())
+        .unwrap();
+    context
+        .set_expression("b".into(), build_operator_tree("2 * c").unwrap())
+        .unwrap();
+    context
+        .set_expression("c".into(), build_operator_tree("a").unwrap())
+        .unwrap();
+    assert_eval_err!(
+        "1 + a",
+        EvalexprError::ExpressionCycle { chain } if chain == &["a", "b", "c", "a"],
+        &context
+    );
+    assert_eval_err!(
+        "c",
+        EvalexprError::ExpressionCycle { chain } if chain == &["c", "a", "b", "c"],
+        &context
+    );
+    assert_eq!(
+        eval_with_context("b", &context).unwrap_err().to_string(),
+        "Named expressions reference each other in a cycle: b -> c -> a -> b"
+    );
+
+    // Using the same named expression twice is not a cycle.
+    context
+        .set_expression(
+            "twice".into(),
+            build_operator_tree("(orders, orders)").unwrap(),
+        )
+        .unwrap();
+    context
+        .set_expression(
+            "nested".into(),
+            build_operator_tree("(twice, twice)").unwrap(),
+        )
+        .unwrap();
+    let twice = Value::from(vec![Value::from(5), Value::from(5)]);
+    assert_eval_eq!("nested", vec![twice.clone(), twice], &context);
+
+    // The empty context has no named expressions.
+    assert_eval_err!("a", EvalexprError::VariableIdentifierNotFound { .. });
+    assert_eq!(
+        EmptyContext.set_expression("a".into(), build_operator_tree("1").unwrap()),
+        Err(EvalexprError::ContextNotManipulable)
+    );
+}
+
+#[test]
+fn test_integer_literal_out_of_range() {
+    let strict = ParseConfig::default();
+    let lenient = ParseConfig {
+        integer_overflow_policy: IntegerOverflowPolicy::ConvertToFloat,
+        ..ParseConfig::default()
+    };
+    let max = "9223372036854775807";
+    let max_plus_one = "9223372036854775808";
+    let thirty_digits = "123456789012345678901234567890";
+
+    // The largest integer is parsed as integer under both policies.
+    assert_eq!(eval(max), Ok(Value::Int(IntType::MAX)));
+    for config in &[&strict, &lenient] {
+        let parsed = build_operator_tree_with_config(max, config).unwrap();
+        assert_eq!(parsed.tree.eval(), Ok(Value::Int(IntType::MAX)));
+        assert_eq!(parsed.warnings, vec![]);
+    }
+    assert_eq!(eval(&format!("-{}", max)), Ok(Value::Int(-IntType::MAX)));
+
+    // The smallest integer is in range only together with its unary minus.
+    assert_eq!(
+        eval(&format!("-{}", max_plus_one)),
+        Ok(Value::Int(IntType::MIN))
+    );
+    assert_eq!(
+        eval(&format!("- {} + 1", max_plus_one)),
+        Ok(Value::Int(IntType::MIN + 1))
+    );
+    assert_eq!(
+        eval(&format!("(1, -{})", max_plus_one)),
+        Ok(Value::from(vec![Value::Int(1), Value::Int(IntType::MIN)]))
+    );
+    for expression in &[
+        format!("2 - {}", max_plus_one),
+        format!("-({})", max_plus_one),
+        format!("-{} ^ 0", max_plus_one),
+    ] {
+        assert_eq!(
+            eval(expression),
+            Err(EvalexprError::IntegerLiteralOutOfRange(
+                max_plus_one.to_string()
+            ))
+        );
+    }
+    assert_eq!(
+        eval(&format!("--{}", max_plus_one)),
+        Err(EvalexprError::NegationError {
+            argument: Value::Int(IntType::MIN)
+        })
+    );
+
+    // Larger literals are an error by default.
+    for literal in &[max_plus_one, thirty_digits] {
+        let expected = EvalexprError::IntegerLiteralOutOfRange(literal.to_string());
+        assert_eq!(eval(literal), Err(expected.clone()));
+        assert_eq!(build_operator_tree(literal).err(), Some(expected.clone()));
+        assert_eq!(
+            build_operator_tree_with_config(literal, &strict).err(),
+            Some(expected)
+        );
+        assert_eq!(
+            eval(&format!("a = {}", literal)),
+            Err(EvalexprError::IntegerLiteralOutOfRange(literal.to_string()))
+        );
+    }
+
+    // Or converted to float with a warning.
+    for (literal, value) in &[
+        (max_plus_one, 9223372036854775808.0),
+        (thirty_digits, 1.2345678901234568e29),
+    ] {
+        let parsed =
+            build_operator_tree_with_config(&format!("{} + 1", literal), &lenient).unwrap();
+        assert_eq!(parsed.tree.eval(), Ok(Value::Float(value + 1.0)));
+        assert_eq!(
+            parsed.warnings,
+            vec![ParseWarning::IntegerLiteralConvertedToFloat {
+                literal: literal.to_string(),
+                value: *value,
+            }]
+        );
+    }
+    let parsed = build_operator_tree_with_config(
+        &format!("({}, {})", max_plus_one, thirty_digits),
+        &lenient,
+    )
+    .unwrap();
+    assert_eq!(parsed.warnings.len(), 2);
+
+    // Literals that are not all digits keep their meaning.
+    assert_eq!(eval("1e30"), Ok(Value::Float(1e30)));
+    assert_eq!(eval("99999999999999999999.0"), Ok(Value::Float(1e20)));
+    assert_eq!(
+        eval("a99999999999999999999"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "a99999999999999999999".to_string(),
+            span: Some(0..21),
+        })
+    );
+}
+
+#[test]
+fn test_radix_integer_literals() {
+    assert_eq!(eval("0xff"), Ok(Value::Int(255)));
+    assert_eq!(eval("0XFF"), Ok(Value::Int(255)));
+    assert_eq!(eval("0xFf00"), Ok(Value::Int(0xff00)));
+    assert_eq!(eval("0o17"), Ok(Value::Int(15)));
+    assert_eq!(eval("0O755"), Ok(Value::Int(0o755)));
+    assert_eq!(eval("0b1010"), Ok(Value::Int(10)));
+    assert_eq!(eval("0B0"), Ok(Value::Int(0)));
+    assert_eq!(eval("0x0010"), Ok(Value::Int(16)));
+
+    // Negative literals are negated positive literals.
+    assert_eq!(eval("-0x10"), Ok(Value::Int(-16)));
+    assert_eq!(eval("2 - -0b11"), Ok(Value::Int(5)));
+    assert_eq!(eval("-0x7FFFFFFFFFFFFFFF"), Ok(Value::Int(-IntType::MAX)));
+
+    // They mix with other values like any integer.
+    let context = context_map! { "mode" => 10, "flags" => 0x1234 }.unwrap();
+    assert_eq!(
+        eval_with_context("mode == 0b1010 && flags / 0x100 == 0x12", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(eval("0x10 + 1.5"), Ok(Value::Float(17.5)));
+
+    // The largest integer is the boundary.
+    assert_eq!(eval("0x7FFFFFFFFFFFFFFF"), Ok(Value::Int(IntType::MAX)));
+    assert_eq!(
+        eval("0o777777777777777777777"),
+        Ok(Value::Int(IntType::MAX))
+    );
+    for literal in &[
+        "0x8000000000000000",
+        "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+        "0b1000000000000000000000000000000000000000000000000000000000000000",
+    ] {
+        assert_eq!(
+            eval(literal),
+            Err(EvalexprError::IntegerLiteralOutOfRange(literal.to_string()))
+        );
+    }
+
+    // Negated, the smallest integer is in range in any radix.
+    for literal in &[
+        "-0x8000000000000000",
+        "-0X8000_0000_0000_0000",
+        "-0o1000000000000000000000",
+        "-0b1000000000000000000000000000000000000000000000000000000000000000",
+    ] {
+        assert_eq!(eval(literal), Ok(Value::Int(IntType::MIN)));
+    }
+    assert_eq!(
+        eval("1 - 0x8000000000000000"),
+        Err(EvalexprError::IntegerLiteralOutOfRange(
+            "0x8000000000000000".to_string()
+        ))
+    );
+
+    let lenient = ParseConfig {
+        integer_overflow_policy: IntegerOverflowPolicy::ConvertToFloat,
+        ..ParseConfig::default()
+    };
+    let parsed = build_operator_tree_with_config("0x8000000000000000", &lenient).unwrap();
+    assert_eq!(parsed.tree.eval(), Ok(Value::Float(9223372036854775808.0)));
+    assert_eq!(
+        parsed.warnings,
+        vec![ParseWarning::IntegerLiteralConvertedToFloat {
+            literal: "0x8000000000000000".to_string(),
+            value: 9223372036854775808.0,
+        }]
+    );
+
+    // Literals with digits that do not belong to the radix stay identifiers.
+    for literal in &["0x", "0xG", "0b102", "0o8", "0x1.5"] {
+        assert!(eval(literal).is_err(), "{}", literal);
+    }
+    assert_eq!(
+        eval("0b102"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "0b102".to_string(),
+            span: Some(0..5),
+        })
+    );
+
+    // The text of the literal can be preserved.
+    let preserving = ParseConfig {
+        preserve_literal_text: true,
+        ..ParseConfig::default()
+    };
+    let tree = build_operator_tree_with_config("0xFF00 + 0b1", &preserving)
+        .unwrap()
+        .tree;
+    assert_eq!(tree.to_string(), " + 0xFF00 0b1");
+    assert_eq!(tree.eval(), Ok(Value::Int(0xff01)));
+    assert_eq!(
+        build_operator_tree("0xFF00 + 0b1").unwrap().to_string(),
+        " + 65280 1"
+    );
+}
+
+#[test]
+fn test_reserved_words() {
+    let config = ParseConfig::default();
+    let reserved =
+        |identifier: &str, span: std::ops::Range<usize>| ParseWarning::ReservedIdentifier {
+            identifier: identifier.to_string(),
+            span,
+        };
+
+    // Soft-reserved words are still identifiers, but each variable with such an identifier is reported.
+    let parsed =
+        build_operator_tree_with_config("null = 3; in + null * /* as */ x", &config).unwrap();
+    assert_eq!(
+        parsed.warnings,
+        vec![
+            reserved("null", 0..4),
+            reserved("in", 10..12),
+            reserved("null", 15..19)
+        ]
+    );
+    let mut context = context_map! { "in" => 2, "x" => 5 }.unwrap();
+    assert_eq!(
+        parsed.tree.eval_with_context_mut(&mut context),
+        Ok(Value::from(17))
+    );
+    for word in SOFT_RESERVED_WORDS {
+        let parsed = build_operator_tree_with_config(word, &config).unwrap();
+        assert_eq!(parsed.warnings, vec![reserved(word, 0..word.len())]);
+    }
+
+    // Function identifiers, strings and identifiers that only contain a reserved word are not reported.
+    for expression in &[
+        "if(a, 1, 2)",
+        "not (a)",
+        "\"null\" + in_range + if_ + Null",
+        "str::in",
+    ] {
+        let parsed = build_operator_tree_with_config(expression, &config).unwrap();
+        assert_eq!(parsed.warnings, vec![], "{}", expression);
+    }
+
+    // Warnings are ordered by their position in the expression.
+    let lenient = ParseConfig {
+        integer_overflow_policy: IntegerOverflowPolicy::ConvertToFloat,
+        ..ParseConfig::default()
+    };
+    let parsed =
+        build_operator_tree_with_config("or + 9223372036854775808 + as", &lenient).unwrap();
+    assert_eq!(
+        parsed.warnings,
+        vec![
+            reserved("or", 0..2),
+            ParseWarning::IntegerLiteralConvertedToFloat {
+                literal: "9223372036854775808".to_string(),
+                value: 9223372036854775808.0,
+            },
+            reserved("as", 27..29),
+        ]
+    );
+
+    // Hard keywords cannot be assigned to.
+    assert_eq!(
+        HARD_KEYWORDS,
+        &["true", "false", "xor", "bitxor", "implies", "if", "else"]
+    );
+    assert_eq!(
+        build_operator_tree("true = 1"),
+        Err(EvalexprError::AssignmentToKeyword {
+            keyword: "true".to_string(),
+            span: 0..4,
+        })
+    );
+    assert_eq!(
+        eval_with_context_mut("a = 1; false = a", &mut HashMapContext::new()),
+        Err(EvalexprError::AssignmentToKeyword {
+            keyword: "false".to_string(),
+            span: 7..12,
+        })
+    );
+    assert_eq!(eval("true == (1 == 1)"), Ok(Value::from(true)));
+    assert_eq!(
+        eval_with_context_mut("a = true; a", &mut HashMapContext::new()),
+        Ok(Value::from(true))
+    );
+}
+
+#[test]
+fn test_scientific_notation() {
+    assert_eq!(eval("1e9"), Ok(Value::Float(1e9)));
+    assert_eq!(eval("2.5E+7"), Ok(Value::Float(2.5e7)));
+    assert_eq!(eval("3e-4"), Ok(Value::Float(3e-4)));
+    assert_eq!(eval("1.5e-3"), Ok(Value::Float(1.5e-3)));
+    assert_eq!(eval(".5e1"), Ok(Value::Float(5.0)));
+    assert_eq!(eval("5.E-1"), Ok(Value::Float(0.5)));
+
+    // The sign of the exponent does not split the literal, while other signs are operators.
+    assert_eq!(eval("1e-3-1e-3"), Ok(Value::Float(0.0)));
+    assert_eq!(eval("2e+3+-1e+3"), Ok(Value::Float(1000.0)));
+    assert_eq!(eval("-1e-1"), Ok(Value::Float(-0.1)));
+    assert_eq!(eval("(1e-1)"), Ok(Value::Float(0.1)));
+    assert_eq!(
+        build_operator_tree("x*1.5e-3").unwrap().to_string(),
+        " * x 0.0015"
+    );
+
+    // Identifiers that look like exponents stay identifiers.
+    let context = context_map! { "e5" => 5, "e" => 1, "x1e" => 2 }.unwrap();
+    assert_eq!(eval_with_context("e5", &context), Ok(Value::from(5)));
+    assert_eq!(eval_with_context("e5-e", &context), Ok(Value::from(4)));
+    assert_eq!(eval_with_context("x1e-1", &context), Ok(Value::from(1)));
+    assert_eq!(eval("0x1e-1"), Ok(Value::from(29)));
+
+    // An exponent without digits is an error.
+    for literal in &["1e", "2.5E", "1e-", "3e+x", "1e - 3"] {
+        let prefix = literal.split(['-', '+', ' ']).next().unwrap();
+        assert_eq!(
+            eval(literal),
+            Err(EvalexprError::InvalidNumberLiteral(prefix.to_string())),
+            "{}",
+            literal
+        );
+    }
+
+    // Minified literals use negative exponents where they are shorter.
+    let tree = build_operator_tree("0.00000015 + 1").unwrap();
+    assert_eq!(tree.to_minified_string(), "1.5e-7+1");
+    assert_eq!(
+        build_operator_tree(&tree.to_minified_string())
+            .unwrap()
+            .eval(),
+        tree.eval()
+    );
+}
+
+#[test]
+fn test_digit_separators() {
+    assert_eq!(eval("10_000_000"), Ok(Value::from(10_000_000)));
+    assert_eq!(eval("1_000.5_5"), Ok(Value::from(1000.55)));
+    assert_eq!(eval("1_0e1_0"), Ok(Value::from(1e11)));
+    assert_eq!(eval("2_500e-3"), Ok(Value::from(2.5)));
+    assert_eq!(eval("0xFF_FF"), Ok(Value::from(0xFFFF)));
+    assert_eq!(eval("0b1010_1010"), Ok(Value::from(0b1010_1010)));
+    assert_eq!(eval("-1_000 + 1"), Ok(Value::from(-999)));
+    assert_eq!(
+        eval("9_223_372_036_854_775_808"),
+        Err(EvalexprError::IntegerLiteralOutOfRange(
+            "9_223_372_036_854_775_808".to_string()
+        ))
+    );
+
+    // Identifiers with underscores stay identifiers.
+    let context = context_map! { "_foo" => 1, "foo_bar" => 2, "x_1" => 3 }.unwrap();
+    assert_eq!(eval_with_context("_foo", &context), Ok(Value::from(1)));
+    assert_eq!(eval_with_context("foo_bar", &context), Ok(Value::from(2)));
+    assert_eq!(
+        eval_with_context("x_1 + 1_0", &context),
+        Ok(Value::from(13))
+    );
+
+    // Underscores must stand between two digits.
+    for literal in &[
+        "1__0", "1_", "1_.5", "1._5", "0x_FF", "1_e5", "1e_5", "0b1_2",
+    ] {
+        assert_eq!(
+            eval(literal),
+            Err(EvalexprError::InvalidNumberLiteral(literal.to_string())),
+            "{}",
+            literal
+        );
+    }
+
+    // The text of preserved literals keeps the separators.
+    let config = ParseConfig {
+        preserve_literal_text: true,
+        ..ParseConfig::default()
+    };
+    let tree = build_operator_tree_with_config("1_000", &config)
+        .unwrap()
+        .tree;
+    assert_eq!(tree.children()[0].literal_text(), Some("1_000"));
+    assert_eq!(tree.eval(), Ok(Value::from(1000)));
+    assert_eq!(tree.to_minified_string(), "1000");
+}
+
+#[test]
+fn test_line_comments() {
+    let context = context_map! { "a" => 12, "b" => 3, "price" => 100.0 }.unwrap();
+    assert_eq!(
+        eval_with_context("price * 1.19 // add VAT", &context),
+        Ok(Value::from(119.0))
+    );
+    assert_eq!(eval("// nothing but a comment"), Ok(Value::Empty));
+    assert_eq!(eval("1 //"), Ok(Value::from(1)));
+    assert_eq!(eval("2//comment\n+3"), Ok(Value::from(5)));
+
+    // Single slashes still divide.
+    assert_eq!(eval_with_context("a / b", &context), Ok(Value::from(4)));
+    assert_eq!(eval_with_context("a/b", &context), Ok(Value::from(4)));
+    assert_eq!(
+        eval_with_context("a / b // a / 0", &context),
+        Ok(Value::from(4))
+    );
+    assert_eq!(
+        build_operator_tree("a/ /b/"),
+        build_operator_tree("a / / b /")
+    );
+    assert!(eval_with_context("a/ /b/", &context).is_err());
+
+    // Slashes within strings are part of the string.
+    assert_eq!(
+        eval("\"http://example.com\" + \"//\" // comment"),
+        Ok(Value::from("http://example.com//"))
+    );
+
+    // Comments on each line do not change the operator tree.
+    let commented = "
+        // The net price plus VAT.
+        net = price * 1.19; // VAT
+        discount = 5.0; // a fixed discount
+        net - discount // the result
+    ";
+    let uncommented = "net = price * 1.19; discount = 5.0; net - discount";
+    assert_eq!(
+        build_operator_tree(commented).unwrap(),
+        build_operator_tree(uncommented).unwrap()
+    );
+    let mut context = context;
+    assert_eq!(
+        eval_with_context_mut(commented, &mut context),
+        Ok(Value::from(114.0))
+    );
+}
+
+#[test]
+fn test_block_comments() {
+    let context = context_map! { "base" => 10, "surcharge" => 2, "count" => 3 }.unwrap();
+    assert_eq!(
+        eval_with_context("(base /* per unit */ + surcharge) * count", &context),
+        Ok(Value::from(36))
+    );
+    assert_eq!(eval("/**/1/***/+/* ** / */2/**/"), Ok(Value::from(3)));
+    assert_eq!(eval("/* nothing */"), Ok(Value::Empty));
+    assert_eq!(
+        eval("1 + /* spanning\n several // lines\n */ 2"),
+        Ok(Value::from(3))
+    );
+    assert_eq!(eval("8 /* // */ / 2"), Ok(Value::from(4)));
+
+    // Comments separate tokens like whitespace.
+    assert_eq!(
+        build_operator_tree("2/*x*/3"),
+        Err(EvalexprError::MissingOperator { span: 6..7 })
+    );
+    assert_eq!(build_operator_tree("a/*x*/b"), build_operator_tree("a b"));
+    assert_eq!(eval("2/*x*/+3"), Ok(Value::from(5)));
+    assert_eq!(
+        eval("\"/* not a comment */\""),
+        Ok(Value::from("/* not a comment */"))
+    );
+
+    // Block comments are not nested.
+    assert_eq!(
+        build_operator_tree("1 /* a /* b */ + 2 */"),
+        build_operator_tree("1 + 2 */")
+    );
+
+    // Unterminated block comments are an error.
+    assert_eq!(
+        eval("1 /* no end"),
+        Err(EvalexprError::UnterminatedComment(" no end".to_string()))
+    );
+    assert_eq!(
+        eval("1 /*/"),
+        Err(EvalexprError::UnterminatedComment("/".to_string()))
+    );
+    assert_eq!(
+        eval("1 // /* within a line comment\n + 1"),
+        Ok(Value::from(2))
+    );
+}
+
+#[test]
+fn test_tokenize_lossless() {
+    let expressions = [
+        "",
+        "   ",
+        "a+b",
+        "  (base /* per unit */ + surcharge) * count  ",
+        "price * 1.19 // add VAT\n\t// and nothing else",
+        "/**/1/***/+/* ** / */2/**/",
+        "1 + /* spanning\n several // lines\n */ 2\r\n",
+        "x += 1; y &&= a >= b || !c; z=-0x1F_FF",
+        "s = \"escaped \\\" // not a comment\\n\"",
+        "len(\"ünïcödé\")\u{a0}+\u{2003}ß_1",
+        "1_000.5e-3 != 2E+4, (), true",
+    ];
+    for expression in expressions.iter() {
+        let tokens = tokenize_lossless(expression).unwrap();
+        let mut position = 0;
+        for token in &tokens {
+            assert!(!token.text.is_empty(), "{:?}", expression);
+            assert_eq!(token.span.start, position, "{:?}", expression);
+            assert_eq!(&expression[token.span.clone()], token.text);
+            position = token.span.end;
+        }
+        assert_eq!(position, expression.len(), "{:?}", expression);
+        let texts: Vec<_> = tokens.iter().map(|token| token.text).collect();
+        assert_eq!(texts.concat(), *expression);
+
+        // Without the trivia, the tokens are the ones the expression is parsed from.
+        let without_trivia: Vec<_> = tokens
+            .iter()
+            .filter(|token| {
+                !matches!(
+                    token.kind,
+                    TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment
+                )
+            })
+            .map(|token| token.text)
+            .collect();
+        assert_eq!(
+            build_operator_tree(&without_trivia.join(" ")),
+            build_operator_tree(expression)
+        );
+    }
+
+    let kinds_and_texts = |expression| {
+        tokenize_lossless(expression)
+            .unwrap()
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        kinds_and_texts("f(a, \"b\");x*=2.5 /*c*/\n//d\n\u{a0} \ttrue"),
+        vec![
+            (TokenKind::Identifier, "f"),
+            (TokenKind::Parenthesis, "("),
+            (TokenKind::Identifier, "a"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::String, "\"b\""),
+            (TokenKind::Parenthesis, ")"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Identifier, "x"),
+            (TokenKind::Operator, "*="),
+            (TokenKind::Number, "2.5"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::BlockComment, "/*c*/"),
+            (TokenKind::Whitespace, "\n"),
+            (TokenKind::LineComment, "//d"),
+            (TokenKind::Whitespace, "\n\u{a0} \t"),
+            (TokenKind::Boolean, "true"),
+        ]
+    );
+    assert_eq!(
+        kinds_and_texts("a >= -b"),
+        vec![
+            (TokenKind::Identifier, "a"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Operator, ">="),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Operator, "-"),
+            (TokenKind::Identifier, "b"),
+        ]
+    );
+
+    // Expressions that cannot be tokenized fail like when they are parsed.
+    assert_eq!(
+        tokenize_lossless("1 /* no end"),
+        Err(EvalexprError::UnterminatedComment(" no end".to_string()))
+    );
+    assert_eq!(
+        tokenize_lossless("\"open"),
+        Err(EvalexprError::UnterminatedString("open".to_string()))
+    );
+}
+
+#[test]
+fn test_preserve_literal_text() {
+    let preserving = ParseConfig {
+        preserve_literal_text: true,
+        ..ParseConfig::default()
+    };
+    let literals = [
+        "1.50", "0.10", "1.0", "100.000", "007", "0", "2e3", "2.5E10", "1e300", "1.5e0", ".5", "5.",
+    ];
+    for literal in literals.iter() {
+        let expression = format!("x * {} + 1", literal);
+        let tree = build_operator_tree_with_config(&expression, &preserving)
+            .unwrap()
+            .tree;
+        let constant = tree
+            .iter()
+            .find(|node| node.kind() == OperatorKind::Constant && node.literal_text().is_some())
+            .unwrap();
+        assert_eq!(constant.literal_text(), Some(*literal));
+        assert_eq!(tree.to_string(), format!(" + * x {} 1", literal));
+
+        // The evaluation only sees the value.
+        let context = context_map! { "x" => 2 }.unwrap();
+        assert_eq!(
+            tree.eval_with_context(&context),
+            build_operator_tree(&expression)
+                .unwrap()
+                .eval_with_context(&context)
+        );
+    }
+
+    // Without the option, constants are written as their value.
+    let tree = build_operator_tree("1.50 + 2e3 - 007").unwrap();
+    assert_eq!(tree.to_string(), " - + 1.5 2000 7");
+    assert!(tree.iter().all(|node| node.literal_text().is_none()));
+    let tree = build_operator_tree_with_config("1.50 + 2e3 - 007", &preserving)
+        .unwrap()
+        .tree;
+    assert_eq!(tree.to_string(), " - + 1.50 2e3 007");
+    assert_eq!(tree.eval(), Ok(Value::from(1994.5)));
+
+    // Only number literals keep their text, as other literals are always written the same way.
+    let tree = build_operator_tree_with_config("a = \"x\"; true", &preserving)
+        .unwrap()
+        .tree;
+    assert!(tree.iter().all(|node| node.literal_text().is_none()));
+
+    // Number literals that were converted by the integer overflow policy keep their text as well.
+    let lenient = ParseConfig {
+        integer_overflow_policy: IntegerOverflowPolicy::ConvertToFloat,
+        preserve_literal_text: true,
+        ..ParseConfig::default()
+    };
+    let parsed = build_operator_tree_with_config("10000000000000000000", &lenient).unwrap();
+    assert_eq!(parsed.tree.to_string(), " 10000000000000000000");
+    assert_eq!(parsed.tree.eval(), Ok(Value::from(1e19)));
+    assert_eq!(parsed.warnings.len(), 1);
+
+    // The text does not change type checking.
+    let tree = build_operator_tree_with_config("1.50 + 1", &preserving)
+        .unwrap()
+        .tree;
+    assert_eq!(
+        tree.check_types(&Default::default(), &Default::default(), false),
+        Ok(StaticType::Float)
+    );
+    // The debug tree shows the value, as it describes the structure of the tree.
+    assert_eq!(
+        tree.to_debug_tree(),
+        "Root\n  Add\n    Const(Float 1.5)\n    Const(Int 1)"
+    );
+}
+
+#[test]
+fn test_custom_literals() {
+    let color = CustomLiteral::new(
+        "#",
+        |c| c.is_ascii_hexdigit(),
+        |literal| {
+            IntType::from_str_radix(&literal[1..], 16)
+                .map(Value::from)
+                .map_err(|error| EvalexprError::CustomMessage(error.to_string()))
+        },
+    );
+    let version = |prefix: &str| {
+        CustomLiteral::new(
+            prefix,
+            |c| c.is_ascii_digit() || c == '.',
+            |literal| {
+                literal
+                    .trim_start_matches('v')
+                    .split('.')
+                    .map(|part| {
+                        part.parse::<IntType>().map(Value::from).map_err(|_| {
+                            EvalexprError::CustomMessage(format!("bad version {}", literal))
+                        })
+                    })
+                    .collect::<EvalexprResult<TupleType>>()
+                    .map(Value::from)
+            },
+        )
+    };
+    let config = ParseConfig {
+        custom_literals: vec![color.clone(), version("v"), version("")],
+        ..ParseConfig::default()
+    };
+    let eval = |expression: &str| {
+        build_operator_tree_with_config(expression, &config).and_then(|parsed| parsed.tree.eval())
+    };
+
+    assert_eq!(eval("#FF00FF"), Ok(Value::from(0xFF00FF)));
+    assert_eq!(eval("#FF00FF & #00FFFF"), Ok(Value::from(0x0000FF)));
+    assert_eq!(eval("#f0+#0F"), Ok(Value::from(0xFF)));
+    assert_eq!(eval("v1.2.3"), eval("(1, 2, 3)"));
+    assert_eq!(eval("v1.2.3 == 1.2.3"), Ok(Value::from(true)));
+    assert_eq!(
+        eval("(#10, #20)"),
+        Ok(Value::from(vec![Value::from(16), Value::from(32)]))
+    );
+    assert_eq!(eval("\"#FF v1\""), Ok(Value::from("#FF v1")));
+    assert_eq!(eval("#FF // #00"), Ok(Value::from(255)));
+
+    // Number literals keep their meaning, and a custom literal that starts with a digit must span the whole literal.
+    assert_eq!(eval("1.5"), Ok(Value::from(1.5)));
+    assert_eq!(eval("2 + 3"), Ok(Value::from(5)));
+    assert_eq!(eval("1e3"), Ok(Value::from(1000.0)));
+    assert_eq!(eval("1.5e-3"), Ok(Value::from(1.5e-3)));
+    assert_eq!(eval("0xFF"), Ok(Value::from(255)));
+    assert_eq!(
+        eval("1.2.3"),
+        Ok(Value::from(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3)
+        ]))
+    );
+    assert!(matches!(
+        eval("99999999999999999999"),
+        Err(EvalexprError::IntegerLiteralOutOfRange(_))
+    ));
+    assert_eq!(
+        eval("1.2.3a"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "1.2.3a".to_string(),
+            span: Some(0..6),
+        })
+    );
+
+    // A custom literal must not be followed by more characters of an identifier, and takes precedence over identifiers otherwise.
+    assert_eq!(
+        eval("#FF00FFzz"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "#FF00FFzz".to_string(),
+            span: Some(0..9),
+        })
+    );
+    assert_eq!(
+        eval("version"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "version".to_string(),
+            span: Some(0..7),
+        })
+    );
+    assert_eq!(eval("v1"), Ok(Value::from(vec![Value::from(1)])));
+    assert!(eval("#").is_err());
+
+    // The recognizers are tried in order.
+    let shadowing = ParseConfig {
+        custom_literals: vec![
+            CustomLiteral::new("#", |c| c.is_ascii_digit(), |_| Ok(Value::from("first"))),
+            color.clone(),
+        ],
+        ..ParseConfig::default()
+    };
+    let parse = |expression: &str| {
+        build_operator_tree_with_config(expression, &shadowing)
+            .unwrap()
+            .tree
+            .eval()
+    };
+    assert_eq!(parse("#12"), Ok(Value::from("first")));
+    assert_eq!(parse("#1F"), Ok(Value::from(31)));
+
+    // An error of the converter fails the parsing with the span of the literal.
+    let expression = "x + #FFFFFFFFFFFFFFFFFF";
+    let error = build_operator_tree_with_config(expression, &config).unwrap_err();
+    assert_eq!(
+        error,
+        EvalexprError::InvalidCustomLiteral {
+            literal: "#FFFFFFFFFFFFFFFFFF".to_string(),
+            span: 4..23,
+            source: Box::new(EvalexprError::CustomMessage(
+                "number too large to fit in target type".to_string()
+            )),
+        }
+    );
+    assert_eq!(error.span(), Some(4..23));
+    assert_eq!(
+        error.render_with_source(expression),
+        [
+            "error: Invalid custom literal #FFFFFFFFFFFFFFFFFF at offset 4: Error: number too large to fit in target type",
+            " --> 1:5",
+            "  |",
+            "1 | x + #FFFFFFFFFFFFFFFFFF",
+            "  |     ^^^^^^^^^^^^^^^^^^^",
+        ]
+        .join("\n")
+    );
+    assert!(matches!(
+        eval("v1.2..3"),
+        Err(EvalexprError::InvalidCustomLiteral { span, .. }) if span == (0..7)
+    ));
+
+    // The text of custom literals can be preserved, and the tree is written with it.
+    let preserving = ParseConfig {
+        preserve_literal_text: true,
+        ..config.clone()
+    };
+    let tree = build_operator_tree_with_config("#0F + 1", &preserving)
+        .unwrap()
+        .tree;
+    assert_eq!(tree.to_string(), " + #0F 1");
+    assert_eq!(tree.children()[0].children()[0].literal_text(), Some("#0F"));
+    assert_eq!(tree.eval(), Ok(Value::from(16)));
+    let tree = build_operator_tree_with_config("#0F + 1", &config)
+        .unwrap()
+        .tree;
+    assert_eq!(tree.to_string(), " + 15 1");
+
+    // Without recognizers, the literals are parsed as before.
+    assert!(build_operator_tree("#FF00FF").unwrap().eval().is_err());
+    assert_eq!(config.clone(), config);
+    assert_ne!(
+        ParseConfig {
+            custom_literals: vec![version("v")],
+            ..ParseConfig::default()
+        },
+        ParseConfig {
+            custom_literals: vec![version("v")],
+            ..ParseConfig::default()
+        }
+    );
+}
+
+#[test]
+fn test_identifier_interner() {
+    let interner = std::sync::Arc::new(IdentifierInterner::new());
+    let config = ParseConfig {
+        identifier_interner: Some(interner.clone()),
+        ..ParseConfig::default()
     };
+    let expressions: Vec<String> = (0..1000)
+        .map(|index| {
+            format!(
+                "request_count > {} && region == \"eu\" || max(request_count, error_count) > {}",
+                index,
+                index * 2
+            )
+        })
+        .collect();
+    let trees: Vec<Node> = expressions
+        .iter()
+        .map(|expression| {
+            build_operator_tree_with_config(expression, &config)
+                .unwrap()
+                .tree
+        })
+        .collect();
+
+    // Each tree reads four identifiers, which are stored once for all trees.
+    let stats = interner.stats();
+    assert_eq!(stats.identifiers, 4);
     assert_eq!(
-        eval("str::regex_replace(\"foobar\", \".*?(o+)\", \"b$1\")"),
-        Ok(Value::String("boobar".to_owned()))
+        stats.bytes,
+        "request_count".len() + "region".len() + "max".len() + "error_count".len()
     );
+    assert_eq!(stats.requests, 5000);
     assert_eq!(
-        eval("str::regex_replace(\"foobar\", \".*?(i+)\", \"b$1\")"),
-        Ok(Value::String("foobar".to_owned()))
+        stats.requested_bytes,
+        1000 * (2 * "request_count".len() + "region".len() + "max".len() + "error_count".len())
+    );
+    assert!(stats.bytes * 1000 < stats.requested_bytes);
+
+    // The trees share the identifiers of the pool, while trees built without it have their own copies.
+    let first: Vec<&str> = trees[0].iter_identifiers().collect();
+    let last: Vec<&str> = trees[999].iter_identifiers().collect();
+    assert_eq!(first, last);
+    for (first, last) in first.iter().zip(&last) {
+        assert_eq!(first.as_ptr(), last.as_ptr());
+    }
+    let unshared = build_operator_tree(&expressions[0]).unwrap();
+    assert!(unshared
+        .iter_identifiers()
+        .zip(&first)
+        .all(|(unshared, first)| unshared == *first && unshared.as_ptr() != first.as_ptr()));
+
+    // The evaluation does not depend on the pool.
+    let mut context = context_map! {
+        "request_count" => 700,
+        "error_count" => 1200,
+        "region" => "us"
+    }
+    .unwrap();
+    for (expression, tree) in expressions.iter().zip(&trees) {
+        assert_eq!(
+            tree.eval_with_context(&context),
+            eval_with_context(expression, &context),
+            "{:?}",
+            expression
+        );
+        assert_eq!(tree, &build_operator_tree(expression).unwrap());
+    }
+    let compound = build_operator_tree_with_config("request_count += 1; request_count", &config)
+        .unwrap()
+        .tree;
+    assert_eq!(
+        compound.eval_with_context_mut(&mut context),
+        Ok(Value::from(701))
+    );
+    assert_eq!(interner.stats().identifiers, 4);
+
+    assert_eq!(config.clone(), config);
+    assert_ne!(
+        config,
+        ParseConfig {
+            identifier_interner: Some(std::sync::Arc::new(IdentifierInterner::new())),
+            ..ParseConfig::default()
+        }
+    );
+}
+
+#[test]
+fn test_function_call_trace() {
+    use std::cell::RefCell;
+    use std::error::Error;
+    use std::rc::Rc;
+
+    let context = Rc::new(RefCell::new(HashMapContext::new()));
+    context
+        .borrow_mut()
+        .set_function(
+            "normalize".into(),
+            Function::new(Box::new(|argument| {
+                let arguments = expect_tuple(argument)?;
+                if arguments[1].is_string() {
+                    Err(EvalexprError::CustomMessage("bad input".to_string()))
+                } else {
+                    Ok(arguments[0].clone())
+                }
+            })),
+        )
+        .unwrap();
+    context
+        .borrow_mut()
+        .set_value("name", Value::from("x"))
+        .unwrap();
+    // A function that evaluates another expression with the same context.
+    let weak_context = Rc::downgrade(&context);
+    context
+        .borrow_mut()
+        .set_function(
+            "apply".into(),
+            Function::new(Box::new(move |argument| {
+                let context = weak_context.upgrade().unwrap();
+                let context = context.borrow();
+                eval_with_context(&argument.as_string()?, &*context)
+            })),
+        )
+        .unwrap();
+
+    let error =
+        eval_with_context("normalize(1, 2) + normalize(3, \"x\")", &*context.borrow()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "in call to 'normalize' (argument (3, \"x\")): Error: bad input"
+    );
+    assert_eq!(
+        error.source().map(ToString::to_string),
+        Some("Error: bad input".to_string())
+    );
+    assert_eq!(
+        error.user_message(),
+        "This expression could not be evaluated."
+    );
+
+    // Errors of nested evaluations are nested as well.
+    let error = eval_with_context("apply(\"normalize(3, name)\")", &*context.borrow()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "in call to 'apply' (argument \"normalize(3, name)\"): \
+         in call to 'normalize' (argument (3, \"x\")): Error: bad input"
+    );
+    let mut chain = Vec::new();
+    let mut current: Option<&dyn Error> = Some(&error);
+    while let Some(error) = current {
+        chain.push(error.to_string());
+        current = error.source();
+    }
+    assert_eq!(chain.len(), 3);
+    assert_eq!(chain[2], "Error: bad input");
+
+    // Functions that fail with wrong arguments are wrapped as well.
+    assert_eq!(
+        eval_with_context("apply(1)", &*context.borrow()),
+        Err(EvalexprError::InFunctionCall {
+            function: "apply".to_string(),
+            argument: Value::from(1),
+            source: Box::new(EvalexprError::expected_string(Value::from(1))),
+        })
+    );
+
+    // Builtin functions and errors outside of functions are not wrapped.
+    #[cfg(feature = "builtin_math")]
+    assert_eq!(
+        eval("min(true, 1)"),
+        Err(EvalexprError::expected_number(Value::from(true)))
+    );
+    assert_eq!(
+        eval_with_context("unknown(1)", &*context.borrow()),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "unknown".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_explain() {
+    let mut context = context_map! {
+        "tier" => "gold",
+        "spend" => 1250.5,
+        "country" => "NZ",
+        "orders" => 3,
+        "discount" => fn |x: FloatType| x * 0.1,
+    }
+    .unwrap();
+    context
+        .set_expression(
+            "is_big".into(),
+            build_operator_tree("spend > 1000").unwrap(),
+        )
+        .unwrap();
+
+    let rule = build_operator_tree(
+        "(tier == \"gold\" || country == \"DE\") && (discount(spend) > 200 || is_big) && !(orders / 0 > 1 && false)",
+    )
+    .unwrap();
+    let explanation = rule.explain_with_context(&context).unwrap();
+    assert_eq!(
+        explanation.result,
+        Some(Err(EvalexprError::DivisionError {
+            dividend: Value::from(3),
+            divisor: Value::from(0)
+        }))
+    );
+    assert_eq!(
+        explanation.to_text(),
+        "\
+(tier==\"gold\"||country==\"DE\")&&(discount(spend)>200||is_big)&&!(orders/0>1&&false) => error: Error dividing 3 / 0
+  (tier==\"gold\"||country==\"DE\")&&(discount(spend)>200||is_big) => true
+    tier==\"gold\"||country==\"DE\" => true
+      tier==\"gold\" => true
+        tier => \"gold\"
+        \"gold\" => \"gold\"
+      country==\"DE\" => not evaluated
+        country => not evaluated
+        \"DE\" => not evaluated
+    discount(spend)>200||is_big => true
+      discount(spend)>200 => false
+        discount(spend) => 125.05000000000001
+          spend => 1250.5
+        200 => 200
+      is_big => true
+  !(orders/0>1&&false) => error: Error dividing 3 / 0
+    orders/0>1&&false => error: Error dividing 3 / 0
+      orders/0>1 => error: Error dividing 3 / 0
+        orders/0 => error: Error dividing 3 / 0
+          orders => 3
+          0 => 0
+        1 => not evaluated
+      false => not evaluated"
+    );
+
+    // The explanation evaluates like `eval_with_context`.
+    let expressions = [
+        "",
+        "1 + 2 * 3",
+        "(1, tier, (2, 3))",
+        "discount(orders)",
+        "max(orders, 2)",
+        "unknown || true",
+        "orders > 1 ? tier : unknown",
+        "orders ? 1 : 2",
+        "a = 1",
+    ];
+    for expression in &expressions {
+        let tree = build_operator_tree(expression).unwrap();
+        assert_eq!(
+            tree.explain_with_context(&context).unwrap().result,
+            Some(tree.eval_with_context(&context)),
+            "{:?}",
+            expression
+        );
+    }
+
+    // Only the selected branch of a conditional is evaluated.
+    assert_eq!(
+        build_operator_tree("orders > 5 ? unknown : tier")
+            .unwrap()
+            .explain_with_context(&context)
+            .unwrap()
+            .to_text(),
+        "\
+orders>5?unknown:tier => \"gold\"
+  orders>5 => false
+    orders => 3
+    5 => 5
+  unknown => not evaluated
+  tier => \"gold\""
+    );
+}
+
+#[test]
+fn test_compact_tree() {
+    fn context() -> HashMapContext {
+        let mut context = HashMapContext::new();
+        context.set_value("a", Value::from(6)).unwrap();
+        context.set_value("b", Value::from(2.5)).unwrap();
+        context.set_value("s", Value::from("text")).unwrap();
+        context.set_value("flag", Value::from(true)).unwrap();
+        context
+            .set_function(
+                "double".into(),
+                Function::new(Box::new(|argument| match argument {
+                    Value::Int(int) => Ok(Value::Int(int * 2)),
+                    argument => Err(EvalexprError::expected_int(argument.clone())),
+                })),
+            )
+            .unwrap();
+        context
+            .set_expression("area".into(), build_operator_tree("a * b").unwrap())
+            .unwrap();
+        context
+            .set_expression("cycle".into(), build_operator_tree("cycle + 1").unwrap())
+            .unwrap();
+        context
+    }
+
+    let expressions = [
+        "",
+        "()",
+        "1 + 2 * 3 - 4 / 2",
+        "-a ^ 2 % 5",
+        "(1, (2, 3), ())",
+        "a > 3 && b < 3 || !flag",
+        "flag || unknown",
+        "false && unknown",
+        "flag ? a : unknown",
+        "a > 6 ? 1 / 0 : b",
+        "s + \"!\" == \"text!\"",
+        "double(a) + double(double 2)",
+        "double(b)",
+        "area * 2",
+        "cycle",
+        "c = a + 1; d = c * 2; (c, d)",
+        "a = \"changed type\"",
+        "x = 1; x = x + 1; x",
+        "1 / 0",
+        "undefined_variable + 1",
+        "undefined_function(1)",
+        "max(a, b, 10)",
+        "(a + b) * (a - b) / (1 + (2 + (3 + (4 + 5))))",
+    ];
+
+    for expression in &expressions {
+        let node = match build_operator_tree(expression) {
+            Ok(node) => node,
+            Err(error) => {
+                assert_eq!(build_compact_tree(expression), Err(error));
+                continue;
+            },
+        };
+        let compact = node.compact();
+        assert_eq!(build_compact_tree(expression).as_ref(), Ok(&compact));
+        assert_eq!(compact.len(), node.iter().count() + 1);
+
+        assert_eq!(compact.eval(), node.eval(), "{:?}", expression);
+        assert_eq!(
+            compact.eval_with_context(&context()),
+            node.eval_with_context(&context()),
+            "{:?}",
+            expression
+        );
+
+        let mut node_context = context();
+        let mut compact_context = context();
+        assert_eq!(
+            compact.eval_with_context_mut(&mut compact_context),
+            node.eval_with_context_mut(&mut node_context),
+            "{:?}",
+            expression
+        );
+        for identifier in &["a", "c", "d", "x"] {
+            assert_eq!(
+                compact_context.get_value(identifier),
+                node_context.get_value(identifier),
+                "{:?}",
+                expression
+            );
+        }
+
+        // Limits of the evaluation state apply the same way.
+        let config = EvalConfig {
+            max_result_bytes: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            compact.eval_with_context_and_state(&context(), &EvalState::new(config.clone())),
+            node.eval_with_context_and_state(&context(), &EvalState::new(config)),
+            "{:?}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn test_share_common_subexpressions() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // Creates a context whose functions count their calls in `calls`.
+    fn context(calls: &Rc<Cell<usize>>) -> HashMapContext {
+        let mut context = HashMapContext::new();
+        context.set_value("a", Value::from(6)).unwrap();
+        context.set_value("b", Value::from(2.5)).unwrap();
+        let lookup_calls = calls.clone();
+        context
+            .set_function(
+                "lookup".into(),
+                Function::new(Box::new(move |argument| {
+                    lookup_calls.set(lookup_calls.get() + 1);
+                    Ok(Value::Int(argument.as_int()? * 2))
+                })),
+            )
+            .unwrap();
+        let greet_calls = calls.clone();
+        context
+            .set_function(
+                "greet".into(),
+                Function::new(Box::new(move |argument| {
+                    greet_calls.set(greet_calls.get() + 1);
+                    Ok(Value::from(format!("hi {}", argument)))
+                })),
+            )
+            .unwrap();
+        let next_calls = calls.clone();
+        context
+            .set_function(
+                "next".into(),
+                Function::new(Box::new(move |_| {
+                    next_calls.set(next_calls.get() + 1);
+                    Ok(Value::from(next_calls.get() as IntType))
+                }))
+                .impure(),
+            )
+            .unwrap();
+        context
+            .set_expression("area".into(), build_operator_tree("a * 2").unwrap())
+            .unwrap();
+        context
+    }
+
+    // Evaluates the expression with and without sharing, and returns the amounts of function calls of the evaluations with a mutable context.
+    let differential = |expression: &str| -> (usize, usize) {
+        let node = build_operator_tree(expression).unwrap();
+        let mut shared = build_operator_tree(expression).unwrap();
+        shared.share_common_subexpressions();
+        assert_eq!(shared.to_minified_string(), node.to_minified_string());
+        assert_eq!(shared.to_string(), node.to_string());
+
+        assert_eq!(
+            shared.eval_with_context(&context(&Rc::new(Cell::new(0)))),
+            node.eval_with_context(&context(&Rc::new(Cell::new(0)))),
+            "{:?}",
+            expression
+        );
+
+        let node_calls = Rc::new(Cell::new(0));
+        let shared_calls = Rc::new(Cell::new(0));
+        let mut node_context = context(&node_calls);
+        let mut shared_context = context(&shared_calls);
+        assert_eq!(
+            shared.eval_with_context_mut(&mut shared_context),
+            node.eval_with_context_mut(&mut node_context),
+            "{:?}",
+            expression
+        );
+        for identifier in &["a", "x"] {
+            assert_eq!(
+                shared_context.get_value(identifier),
+                node_context.get_value(identifier),
+                "{:?}",
+                expression
+            );
+        }
+
+        for limit in &[None, Some(12), Some(1000)] {
+            let config = EvalConfig {
+                max_result_bytes: *limit,
+                ..Default::default()
+            };
+            let node_state = EvalState::new(config.clone());
+            let shared_state = EvalState::new(config);
+            assert_eq!(
+                shared.eval_with_context_and_state(&context(&Rc::new(Cell::new(0))), &shared_state),
+                node.eval_with_context_and_state(&context(&Rc::new(Cell::new(0))), &node_state),
+                "{:?}",
+                expression
+            );
+            assert_eq!(shared_state.allocated_bytes(), node_state.allocated_bytes());
+        }
+
+        (node_calls.get(), shared_calls.get())
+    };
+
+    // Each distinct call is evaluated once.
+    assert_eq!(differential("lookup(a) + lookup(a) * lookup(a)"), (3, 1));
+    assert_eq!(
+        differential("(lookup(a) + 1, lookup(a) + 1, lookup(a))"),
+        (3, 1)
+    );
+    assert_eq!(differential("lookup(a) + lookup(lookup(a))"), (3, 2));
+    assert_eq!(differential("lookup(a) + lookup(3) + lookup(a)"), (3, 2));
+    assert_eq!(differential("greet(a) + greet(a)"), (2, 1));
+    assert_eq!(
+        differential("lookup(a + 1) * lookup(a + 1) - lookup(1 + a)"),
+        (3, 2)
+    );
+    // An occurrence that is skipped by short-circuiting does not evaluate anything.
+    assert_eq!(differential("lookup(a) > 100 && lookup(a) < 10"), (1, 1));
+    assert_eq!(
+        differential("b > 3 && lookup(a) > 0 || lookup(a) > 0"),
+        (1, 1)
+    );
+    // Errors are the same.
+    assert_eq!(differential("lookup(b) + lookup(b)"), (1, 1));
+    assert_eq!(differential("lookup(unknown) + lookup(unknown)"), (0, 0));
+    // Impure functions, named expressions and assigned variables are not shared.
+    assert_eq!(differential("next() + next() * 10"), (2, 2));
+    assert_eq!(differential("lookup(area) + lookup(area)"), (2, 2));
+    assert_eq!(
+        differential("x = lookup(a); lookup(a) + lookup(x) + lookup(x)"),
+        (4, 3)
+    );
+    assert_eq!(differential("a = lookup(a) + 1; lookup(a) + 1"), (2, 2));
+    assert_eq!(differential("1 + 2"), (0, 0));
+    assert_eq!(differential(""), (0, 0));
+
+    // The shared subexpressions are marked in the tree, and sharing again changes nothing.
+    let mut tree = build_operator_tree("f(x) + f(x) * 2").unwrap();
+    tree.share_common_subexpressions();
+    let shared: Vec<_> = tree
+        .iter()
+        .filter(|node| node.kind() == OperatorKind::Shared)
+        .collect();
+    assert_eq!(shared.len(), 2);
+    assert_eq!(shared[0], shared[1]);
+    assert_eq!(shared[0].children()[0].kind(), OperatorKind::FunctionCall);
+    let debug_tree = tree.to_debug_tree();
+    tree.share_common_subexpressions();
+    assert_eq!(tree.to_debug_tree(), debug_tree);
+    assert_eq!(
+        tree.iter_identifiers().collect::<Vec<_>>(),
+        ["f", "x", "f", "x"]
+    );
+
+    // Values are shared per evaluation.
+    let calls = Rc::new(Cell::new(0));
+    let context = context(&calls);
+    let mut tree = build_operator_tree("lookup(a) + lookup(a)").unwrap();
+    tree.share_common_subexpressions();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(24)));
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(24)));
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_memoized_node() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let calls = Rc::new(Cell::new(0));
+    let mut context = HashMapContext::new();
+    context.set_value("a", Value::from(3)).unwrap();
+    context.set_value("b", Value::from(4)).unwrap();
+    context.set_value("enabled", Value::from(false)).unwrap();
+    let lookup_calls = calls.clone();
+    context
+        .set_function(
+            "lookup".into(),
+            Function::new(Box::new(move |argument| {
+                lookup_calls.set(lookup_calls.get() + 1);
+                Ok(Value::Int(argument.as_int()? * 10))
+            })),
+        )
+        .unwrap();
+    let next_calls = calls.clone();
+    context
+        .set_function(
+            "next".into(),
+            Function::new(Box::new(move |_| {
+                next_calls.set(next_calls.get() + 1);
+                Ok(Value::from(next_calls.get() as IntType))
+            }))
+            .impure(),
+        )
+        .unwrap();
+
+    // Cache hits while the read variables keep their values, even if other variables change.
+    let tree = build_operator_tree("lookup(a) + b").unwrap().memoize();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(34)));
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(34)));
+    assert_eq!(calls.replace(0), 1);
+    context.set_value("enabled", Value::from(true)).unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(34)));
+    assert_eq!(calls.replace(0), 0);
+
+    // A changed variable invalidates the cache, also in an equal context.
+    context.set_value("b", Value::from(5)).unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(35)));
+    assert_eq!(calls.replace(0), 1);
+    let mut other_context = HashMapContext::new();
+    other_context.set_value("a", Value::from(3)).unwrap();
+    other_context.set_value("b", Value::from(5)).unwrap();
+    let other_lookup_calls = calls.clone();
+    other_context
+        .set_function(
+            "lookup".into(),
+            Function::new(Box::new(move |argument| {
+                other_lookup_calls.set(other_lookup_calls.get() + 1);
+                Ok(Value::Int(argument.as_int()? * 10))
+            })),
+        )
+        .unwrap();
+    assert_eq!(tree.eval_with_context(&other_context), Ok(Value::from(35)));
+    assert_eq!(calls.replace(0), 0);
+    other_context.set_value("a", Value::from(1)).unwrap();
+    assert_eq!(tree.eval_with_context(&other_context), Ok(Value::from(15)));
+    assert_eq!(calls.replace(0), 1);
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(35)));
+    assert_eq!(calls.replace(0), 1);
+    tree.clear_cache();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(35)));
+    assert_eq!(calls.replace(0), 1);
+
+    // Only the variables that were read are compared, so variables behind a short circuit do not invalidate the cache.
+    context.set_value("enabled", Value::from(false)).unwrap();
+    let tree = build_operator_tree("enabled && lookup(a) > lookup(b)")
+        .unwrap()
+        .memoize();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(false)));
+    context.set_value("a", Value::from(100)).unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(false)));
+    assert_eq!(calls.replace(0), 0);
+    context.set_value("enabled", Value::from(true)).unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+    assert_eq!(calls.replace(0), 2);
+
+    // Errors are cached like values.
+    let tree = build_operator_tree("lookup(b) + \"text\"")
+        .unwrap()
+        .memoize();
+    assert!(tree.eval_with_context(&context).is_err());
+    assert!(tree.eval_with_context(&context).is_err());
+    assert_eq!(calls.replace(0), 1);
+
+    // Impure expressions are evaluated each time.
+    let tree = build_operator_tree("next() + lookup(a)").unwrap().memoize();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(1001)));
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(1003)));
+    assert_eq!(calls.replace(0), 4);
+    #[cfg(feature = "builtin_datetime")]
+    {
+        let tree = build_operator_tree("now_monotonic() >= 0.0 && lookup(a) > 0")
+            .unwrap()
+            .memoize();
+        for _ in 0..2 {
+            assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+        }
+        assert_eq!(calls.replace(0), 2);
+    }
+    let tree = build_operator_tree("c = lookup(a)").unwrap().memoize();
+    for _ in 0..2 {
+        assert_eq!(
+            tree.eval_with_context(&context),
+            Err(EvalexprError::ContextNotManipulable)
+        );
+    }
+    assert_eq!(calls.replace(0), 2);
+
+    // Named expressions are evaluated each time, as they may change.
+    context
+        .set_expression("area".into(), build_operator_tree("lookup(a)").unwrap())
+        .unwrap();
+    let tree = build_operator_tree("area + 1").unwrap().memoize();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(1001)));
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::from(1001)));
+    assert_eq!(calls.replace(0), 2);
+    assert_eq!(tree.node(), &build_operator_tree("area + 1").unwrap());
+}
+
+#[cfg(feature = "serde_support")]
+#[test]
+fn test_json_conversion() {
+    use std::convert::TryFrom;
+
+    let values = vec![
+        Value::from("text with \"quotes\" and ünïcödé"),
+        Value::from(IntType::MAX),
+        Value::from(IntType::MIN),
+        Value::from(0.1),
+        Value::from(-2.0),
+        Value::from(FloatType::MAX),
+        Value::from(FloatType::MIN_POSITIVE),
+        Value::from(true),
+        Value::Empty,
+        Value::from(vec![]),
+        Value::from(vec![
+            Value::from(1),
+            Value::from(vec![Value::from("nested"), Value::Empty]),
+        ]),
+    ];
+    let expected = vec![
+        serde_json::json!("text with \"quotes\" and ünïcödé"),
+        serde_json::json!(IntType::MAX),
+        serde_json::json!(IntType::MIN),
+        serde_json::json!(0.1),
+        serde_json::json!(-2.0),
+        serde_json::json!(FloatType::MAX),
+        serde_json::json!(FloatType::MIN_POSITIVE),
+        serde_json::json!(true),
+        serde_json::json!(null),
+        serde_json::json!([]),
+        serde_json::json!([1, ["nested", null]]),
+    ];
+
+    for (value, expected) in values.into_iter().zip(expected) {
+        let json = serde_json::Value::try_from(value.clone()).unwrap();
+        assert_eq!(json, expected, "{:?}", value);
+
+        // Numbers survive a round trip through the JSON text exactly, and integers stay integers.
+        let reparsed: serde_json::Value = serde_json::from_str(&json.to_string()).unwrap();
+        assert_eq!(reparsed, json, "{:?}", value);
+        match value {
+            Value::Int(int) => assert_eq!(reparsed.as_i64(), Some(int)),
+            Value::Float(float) => {
+                assert!(reparsed.is_f64());
+                assert_eq!(reparsed.as_f64(), Some(float));
+            },
+            _ => {},
+        }
+    }
+
+    let context = context_map! { "a" => 3 }.unwrap();
+    assert_eq!(
+        eval_to_json("(a * 2, a / 4.0, \"x\" + \"y\", a > 5, ())", &context),
+        Ok(serde_json::json!([6, 0.75, "xy", false, null]))
+    );
+    assert_eq!(
+        eval_to_json("undefined", &context),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "undefined".to_string(),
+            span: Some(0..9),
+        })
+    );
+}
+
+#[cfg(feature = "serde_support")]
+#[test]
+fn test_json_non_finite_floats() {
+    use std::convert::TryFrom;
+
+    // JSON cannot represent NaN or infinity, so the conversion fails instead of producing `null`.
+    assert!(matches!(
+        eval_to_json("0.0 / 0.0", &EmptyContext),
+        Err(EvalexprError::NonFiniteFloatInJson(value)) if value.is_nan()
+    ));
+    assert_eq!(
+        eval_to_json("-1.0 / 0.0", &EmptyContext),
+        Err(EvalexprError::NonFiniteFloatInJson(FloatType::NEG_INFINITY))
+    );
+    // Also within tuples.
+    assert_eq!(
+        serde_json::Value::try_from(Value::from(vec![
+            Value::from(1),
+            Value::from(vec![Value::from(FloatType::INFINITY)])
+        ])),
+        Err(EvalexprError::NonFiniteFloatInJson(FloatType::INFINITY))
+    );
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_async_functions() {
+    use futures::{future, FutureExt};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let fetch_calls = calls.clone();
+    let mut context = HashMapContext::new();
+    context
+        .set_async_function(
+            "fetch".into(),
+            AsyncFunction::new(Box::new(move |argument| {
+                fetch_calls.fetch_add(1, Ordering::SeqCst);
+                let result = argument.as_int().map(|int| Value::from(int * 10));
+                Box::pin(tokio::time::sleep(Duration::from_millis(5)).map(move |_| result))
+            })),
+        )
+        .unwrap();
+    context
+        .set_async_function(
+            "echo".into(),
+            AsyncFunction::new(Box::new(|argument| {
+                Box::pin(future::ready(Ok(argument.clone())))
+            })),
+        )
+        .unwrap();
+    context
+        .set_async_function(
+            "fail".into(),
+            AsyncFunction::new(Box::new(|_| {
+                Box::pin(future::ready(Err(EvalexprError::CustomMessage(
+                    "connection refused".into(),
+                ))))
+            })),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "double".into(),
+            Function::new(Box::new(|argument| Ok(Value::from(argument.as_int()? * 2)))),
+        )
+        .unwrap();
+    context.set_value("x", Value::from(4)).unwrap();
+    context
+        .set_expression("fetched_x".into(), build_operator_tree("fetch(x)").unwrap())
+        .unwrap();
+    context
+        .set_expression("a".into(), build_operator_tree("b").unwrap())
+        .unwrap();
+    context
+        .set_expression("b".into(), build_operator_tree("a").unwrap())
+        .unwrap();
+
+    let eval_async =
+        |expression: &str| runtime.block_on(eval_with_context_async(expression, &context));
+
+    // Async functions, sync functions and operators can be combined freely.
+    assert_eq!(eval_async("fetch(1)"), Ok(Value::from(10)));
+    assert_eq!(eval_async("fetch(1) + fetch(2) * 2"), Ok(Value::from(50)));
+    assert_eq!(eval_async("double(fetch(x)) + 1"), Ok(Value::from(81)));
+    assert_eq!(eval_async("fetch(double(x))"), Ok(Value::from(80)));
+    assert_eq!(eval_async("fetch(fetch(1))"), Ok(Value::from(100)));
+    assert_eq!(
+        eval_async("(fetch(1), x, \"s\")"),
+        Ok(Value::from(vec![
+            Value::from(10),
+            Value::from(4),
+            Value::from("s")
+        ]))
+    );
+    assert_eq!(eval_async("fetched_x / 8"), Ok(Value::from(5)));
+    assert_eq!(eval_async("1 + 2"), Ok(Value::from(3)));
+    assert_eq!(eval_async(""), Ok(Value::Empty));
+    // Higher-order builtin functions evaluate their arguments synchronously.
+    #[cfg(feature = "builtin_tuple")]
+    assert_eq!(
+        eval_async("fetch(1) + map((1, 2), double(it) + x)"),
+        Err(EvalexprError::AdditionError {
+            augend: Value::from(10),
+            addend: Value::from(vec![Value::from(6), Value::from(8)]),
+        })
+    );
+    #[cfg(feature = "builtin_tuple")]
+    assert_eq!(
+        eval_async("map((1, 2), fetch(it))"),
+        Err(EvalexprError::FunctionIdentifierNotFound("fetch".into()))
+    );
+
+    // Short-circuiting operators do not call async functions on the right-hand side.
+    calls.store(0, Ordering::SeqCst);
+    assert_eq!(eval_async("false && fetch(1) > 0"), Ok(Value::from(false)));
+    assert_eq!(
+        eval_async("fetch(1) > 0 || fetch(2) > 0"),
+        Ok(Value::from(true))
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    calls.store(0, Ordering::SeqCst);
+    assert_eq!(
+        eval_async("x > 1 ? fetch(1) : fetch(2)"),
+        Ok(Value::from(10))
+    );
+    assert_eq!(eval_async("x > 9 ? fetch(1) : 0"), Ok(Value::from(0)));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    calls.store(0, Ordering::SeqCst);
+    assert_eq!(eval_async("x ?? fetch(1)"), Ok(Value::from(4)));
+    assert_eq!(eval_async("missing ?? fetch(1)"), Ok(Value::from(10)));
+    assert_eq!(eval_async("(missing) ?? fetch(2)"), Ok(Value::from(20)));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        eval_async("(missing + fetch(1)) ?? fetch(2)"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "missing".to_string(),
+            span: Some(1..8),
+        })
+    );
+
+    // Errors of futures are wrapped like errors of functions.
+    assert_eq!(
+        eval_async("1 + fail(2)"),
+        Err(EvalexprError::InFunctionCall {
+            function: "fail".into(),
+            argument: Value::from(2),
+            source: Box::new(EvalexprError::CustomMessage("connection refused".into())),
+        })
+    );
+    assert_eq!(
+        eval_async("fetch(true)"),
+        Err(EvalexprError::InFunctionCall {
+            function: "fetch".into(),
+            argument: Value::from(true),
+            source: Box::new(EvalexprError::expected_int(Value::from(true))),
+        })
+    );
+    // A failing call stops the evaluation.
+    calls.store(0, Ordering::SeqCst);
+    assert!(eval_async("fail(); fetch(1)").is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    assert_eq!(
+        eval_async("double(\"x\") + fetch(1)"),
+        Err(EvalexprError::InFunctionCall {
+            function: "double".into(),
+            argument: Value::from("x"),
+            source: Box::new(EvalexprError::expected_int(Value::from("x"))),
+        })
+    );
+    assert_eq!(
+        eval_async("unknown(1)"),
+        Err(EvalexprError::FunctionIdentifierNotFound("unknown".into()))
+    );
+    assert_eq!(eval_async("1 +"), eval("1 +"));
+    assert_eq!(
+        eval_async("x + missing"),
+        Err(EvalexprError::VariableIdentifierNotFound {
+            identifier: "missing".into(),
+            span: Some(4..11),
+        })
+    );
+    assert_eq!(
+        eval_async("a"),
+        Err(EvalexprError::ExpressionCycle {
+            chain: vec!["a".into(), "b".into(), "a".into()]
+        })
+    );
+    assert_eq!(eval_async("a"), eval_with_context("a", &context));
+
+    // The synchronous evaluation does not call async functions.
+    assert_eq!(
+        eval_with_context("fetch(1)", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound("fetch".into()))
+    );
+    // Functions take precedence over async functions with the same identifier.
+    context
+        .set_function(
+            "fetch".into(),
+            Function::new(Box::new(|_| Ok(Value::from(0)))),
+        )
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(eval_with_context_async("fetch(1)", &context)),
+        Ok(Value::from(0))
     );
-}
 
-#[test]
-fn test_errors() {
+    // The configuration is applied like in a synchronous evaluation, and the bytes are counted across the awaited calls.
+    let eval_async_with_config = |expression: &str, config: EvalConfig| {
+        runtime.block_on(eval_with_context_async(expression, &context).with_config(config))
+    };
+    let token = CancellationToken::new();
+    token.cancel();
     assert_eq!(
-        eval("-true"),
-        Err(EvalexprError::expected_number(Value::Boolean(true)))
+        eval_async_with_config(
+            "fail(1)",
+            EvalConfig {
+                cancellation_token: Some(token),
+                ..EvalConfig::default()
+            }
+        ),
+        Err(EvalexprError::Cancelled)
     );
+    assert!(matches!(
+        eval_async_with_config(
+            "1 + fail(1)",
+            EvalConfig {
+                max_recursion_depth: 0,
+                ..EvalConfig::default()
+            }
+        ),
+        Err(EvalexprError::RecursionLimitExceeded { .. })
+    ));
+    let max_result_bytes = |limit| EvalConfig {
+        max_result_bytes: Some(limit),
+        ..EvalConfig::default()
+    };
     assert_eq!(
-        eval("1-true"),
-        Err(EvalexprError::expected_number(Value::Boolean(true)))
+        eval_async_with_config("echo(\"abc\") + echo(\"de\")", max_result_bytes(15)),
+        Ok(Value::from("abcde"))
     );
     assert_eq!(
-        eval("true-"),
-        Err(EvalexprError::WrongOperatorArgumentAmount {
-            actual: 1,
-            expected: 2
+        eval_async_with_config("echo(\"abc\") + echo(\"de\")", max_result_bytes(14)),
+        Err(EvalexprError::ValueTooLarge {
+            limit: 14,
+            attempted: 15
         })
     );
-    assert_eq!(eval("!(()true)"), Err(EvalexprError::AppendedToLeafNode));
+    assert!(matches!(
+        eval_async_with_config(
+            "0.0 / 0.0",
+            EvalConfig {
+                nan_policy: NanPolicy::Error,
+                ..EvalConfig::default()
+            }
+        ),
+        Err(EvalexprError::NaNProduced { .. })
+    ));
+    assert!(eval_async_with_config("0.0 / 0.0", EvalConfig::default())
+        .unwrap()
+        .as_float()
+        .unwrap()
+        .is_nan());
 }
 
 #[test]
-fn test_no_panic() {
-    assert!(eval(&format!(
-        "{} + {}",
-        IntType::max_value(),
-        IntType::max_value()
-    ))
-    .is_err());
-    assert!(eval(&format!(
-        "-{} - {}",
-        IntType::max_value(),
-        IntType::max_value()
-    ))
-    .is_err());
-    assert!(eval(&format!("-(-{} - 1)", IntType::max_value())).is_err());
-    assert!(eval(&format!(
-        "{} * {}",
-        IntType::max_value(),
-        IntType::max_value()
-    ))
-    .is_err());
-    assert!(eval(&format!("{} / {}", IntType::max_value(), 0)).is_err());
-    assert!(eval(&format!("{} % {}", IntType::max_value(), 0)).is_err());
-    assert!(eval(&format!(
-        "{} ^ {}",
-        IntType::max_value(),
-        IntType::max_value()
-    ))
-    .is_ok());
-}
+#[cfg(feature = "async")]
+fn test_concurrent_async_evaluations() {
+    use futures::{future, FutureExt};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::oneshot;
 
-#[test]
-fn test_shortcut_functions() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    // `receive` waits until `send` was called, so if the evaluation of `receive` blocked the thread, the evaluations would never finish.
+    let (sender, receiver) = oneshot::channel();
+    let sender = Mutex::new(Some(sender));
+    let receiver = Arc::new(Mutex::new(Some(receiver)));
+    let function_receiver = receiver.clone();
     let mut context = HashMapContext::new();
     context
-        .set_value("string".into(), Value::from("a string"))
+        .set_async_function(
+            "receive".into(),
+            AsyncFunction::new(Box::new(move |_| {
+                let receiver: oneshot::Receiver<Value> =
+                    function_receiver.lock().unwrap().take().unwrap();
+                Box::pin(receiver.map(|value| {
+                    value.map_err(|_| EvalexprError::CustomMessage("sender dropped".into()))
+                }))
+            })),
+        )
+        .unwrap();
+    context
+        .set_async_function(
+            "send".into(),
+            AsyncFunction::new(Box::new(move |argument| {
+                let sender = sender.lock().unwrap().take().unwrap();
+                let result = sender
+                    .send(argument.clone())
+                    .map(|_| Value::Empty)
+                    .map_err(|_| EvalexprError::CustomMessage("receiver dropped".into()));
+                Box::pin(future::ready(result))
+            })),
+        )
         .unwrap();
 
-    // assert_eq!(eval_string("???"));
+    let (received, sent) = runtime.block_on(future::join(
+        eval_with_context_async("receive() + 1", &context),
+        eval_with_context_async("send(41); \"sent\"", &context),
+    ));
+    assert_eq!(received, Ok(Value::from(42)));
+    assert_eq!(sent, Ok(Value::from("sent")));
+
+    // A failed future only fails the evaluation that awaits it.
+    let (_, dropped_receiver) = oneshot::channel();
+    *receiver.lock().unwrap() = Some(dropped_receiver);
+    let (received, other) = runtime.block_on(future::join(
+        eval_with_context_async("receive()", &context),
+        eval_with_context_async("1 + 1", &context),
+    ));
     assert_eq!(
-        eval_string_with_context("string", &context),
-        Ok("a string".to_string())
+        received,
+        Err(EvalexprError::InFunctionCall {
+            function: "receive".into(),
+            argument: Value::Empty,
+            source: Box::new(EvalexprError::CustomMessage("sender dropped".into())),
+        })
     );
-    assert_eq!(eval_float("3.3"), Ok(3.3));
-    assert_eq!(eval_float_with_context("3.3", &context), Ok(3.3));
-    assert_eq!(eval_int("3"), Ok(3));
-    assert_eq!(eval_int_with_context("3", &context), Ok(3));
-    assert_eq!(eval_number("3"), Ok(3.0));
-    assert_eq!(eval_number_with_context("3", &context), Ok(3.0));
-    assert_eq!(eval_boolean("true"), Ok(true));
-    assert_eq!(eval_boolean_with_context("true", &context), Ok(true));
-    assert_eq!(eval_tuple("3,3"), Ok(vec![Value::Int(3), Value::Int(3)]));
+    assert_eq!(other, Ok(Value::from(2)));
+}
+
+#[test]
+#[cfg(feature = "sql")]
+fn test_to_sql() {
+    use evalexpr::sql::{to_sql, SqlDialect, UnsupportedNode};
+
+    let postgres = |expression: &str| {
+        to_sql(
+            &build_operator_tree(expression).unwrap(),
+            &SqlDialect::Postgres,
+        )
+    };
+    let mysql = |expression: &str| {
+        to_sql(
+            &build_operator_tree(expression).unwrap(),
+            &SqlDialect::MySql,
+        )
+    };
+    let clause = |sql: &str, parameters: Vec<Value>| Ok((sql.to_string(), parameters));
+
+    // Comparisons and boolean logic, with constants as bind parameters.
     assert_eq!(
-        eval_tuple_with_context("3,3", &context),
-        Ok(vec![Value::Int(3), Value::Int(3)])
+        postgres("age >= 18 && country == \"NZ\""),
+        clause(
+            "(\"age\" >= $1) AND (\"country\" = $2)",
+            vec![18.into(), "NZ".into()]
+        )
+    );
+    assert_eq!(
+        mysql("age >= 18 && country == \"NZ\""),
+        clause(
+            "(`age` >= ?) AND (`country` = ?)",
+            vec![18.into(), "NZ".into()]
+        )
+    );
+    assert_eq!(
+        postgres("!(a < 1 || b > 2.5) && c <= d"),
+        clause(
+            "(NOT ((\"a\" < $1) OR (\"b\" > $2))) AND (\"c\" <= \"d\")",
+            vec![1.into(), 2.5.into()]
+        )
+    );
+    assert_eq!(postgres("active"), clause("\"active\"", vec![]));
+    assert_eq!(
+        postgres("-a < +(b - 1)"),
+        clause("(-\"a\") < (+(\"b\" - $1))", vec![1.into()])
+    );
+    assert_eq!(
+        postgres("active == true"),
+        clause("\"active\" = $1", vec![true.into()])
     );
-    assert_eq!(eval_empty(""), Ok(EMPTY_VALUE));
-    assert_eq!(eval_empty_with_context("", &context), Ok(EMPTY_VALUE));
 
-    // assert_eq!(build_operator_tree("???").unwrap().eval_string());
+    // Arithmetic is parenthesized to not depend on the operator precedence of the dialect.
     assert_eq!(
-        build_operator_tree("string")
-            .unwrap()
-            .eval_string_with_context(&context),
-        Ok("a string".to_string())
+        postgres("price * (1 + tax) - -discount > 100 % 7"),
+        clause(
+            "((\"price\" * ($1 + \"tax\")) - (-\"discount\")) > ($2 % $3)",
+            vec![1.into(), 100.into(), 7.into()]
+        )
     );
-    assert_eq!(build_operator_tree("3.3").unwrap().eval_float(), Ok(3.3));
     assert_eq!(
-        build_operator_tree("3.3")
-            .unwrap()
-            .eval_float_with_context(&context),
-        Ok(3.3)
+        postgres("a / 2 ^ b"),
+        clause("\"a\" / ($1 ^ \"b\")", vec![2.into()])
     );
-    assert_eq!(build_operator_tree("3").unwrap().eval_int(), Ok(3));
     assert_eq!(
-        build_operator_tree("3")
-            .unwrap()
-            .eval_int_with_context(&context),
-        Ok(3)
+        mysql("a / 2 ^ b"),
+        clause("`a` / (POWER(?, `b`))", vec![2.into()])
     );
-    assert_eq!(build_operator_tree("3").unwrap().eval_number(), Ok(3.0));
     assert_eq!(
-        build_operator_tree("3")
-            .unwrap()
-            .eval_number_with_context(&context),
-        Ok(3.0)
+        postgres("(flags & 4 | mask bitxor 1) > 0"),
+        clause(
+            "((\"flags\" & $1) | (\"mask\" # $2)) > $3",
+            vec![4.into(), 1.into(), 0.into()]
+        )
     );
+    assert_eq!(mysql("mask bitxor 1"), clause("`mask` ^ ?", vec![1.into()]));
     assert_eq!(
-        build_operator_tree("true").unwrap().eval_boolean(),
-        Ok(true)
+        postgres("active xor true"),
+        clause("\"active\" <> $1", vec![true.into()])
     );
     assert_eq!(
-        build_operator_tree("true")
-            .unwrap()
-            .eval_boolean_with_context(&context),
-        Ok(true)
+        mysql("a > 1 implies b"),
+        clause("NOT (`a` > ?) OR `b`", vec![1.into()])
     );
     assert_eq!(
-        build_operator_tree("3,3").unwrap().eval_tuple(),
-        Ok(vec![Value::Int(3), Value::Int(3)])
+        postgres("(vip ? price * 0.9 : price) < 100"),
+        clause(
+            "(CASE WHEN \"vip\" THEN \"price\" * $1 ELSE \"price\" END) < $2",
+            vec![0.9.into(), 100.into()]
+        )
     );
     assert_eq!(
-        build_operator_tree("3,3")
-            .unwrap()
-            .eval_tuple_with_context(&context),
-        Ok(vec![Value::Int(3), Value::Int(3)])
+        postgres("(discount ?? 0) > 5"),
+        clause(
+            "(COALESCE(\"discount\", $1)) > $2",
+            vec![0.into(), 5.into()]
+        )
     );
     assert_eq!(
-        build_operator_tree("")
-            .unwrap()
-            .eval_empty_with_context(&context),
-        Ok(EMPTY_VALUE)
+        postgres("(flags >> 2 & 1) == 1"),
+        clause(
+            "((\"flags\" >> $1) & $2) = $3",
+            vec![2.into(), 1.into(), 1.into()]
+        )
     );
 
+    // `+` with a string constant concatenates.
     assert_eq!(
-        eval_string_with_context_mut("string", &mut context),
-        Ok("a string".to_string())
+        postgres("name + \"!\" == greeting"),
+        clause("(\"name\" || $1) = \"greeting\"", vec!["!".into()])
     );
-    assert_eq!(eval_float_with_context_mut("3.3", &mut context), Ok(3.3));
-    assert_eq!(eval_int_with_context_mut("3", &mut context), Ok(3));
-    assert_eq!(eval_number_with_context_mut("3", &mut context), Ok(3.0));
     assert_eq!(
-        eval_boolean_with_context_mut("true", &mut context),
-        Ok(true)
+        mysql("name + \"!\" == greeting"),
+        clause("(CONCAT(`name`, ?)) = `greeting`", vec!["!".into()])
     );
+
+    // Membership tests.
     assert_eq!(
-        eval_tuple_with_context_mut("3,3", &mut context),
-        Ok(vec![Value::Int(3), Value::Int(3)])
+        postgres("any_of((\"NZ\", \"AU\"), \"==\", country)"),
+        clause("\"country\" IN ($1, $2)", vec!["NZ".into(), "AU".into()])
     );
     assert_eq!(
-        eval_empty_with_context_mut("", &mut context),
-        Ok(EMPTY_VALUE)
+        mysql("all_of((1, 2, x), \"!=\", id) && y"),
+        clause(
+            "(`id` NOT IN (?, ?, `x`)) AND `y`",
+            vec![1.into(), 2.into()]
+        )
     );
+    assert_eq!(postgres("any_of((), \"==\", a)"), clause("FALSE", vec![]));
 
+    // String functions.
     assert_eq!(
-        build_operator_tree("string")
-            .unwrap()
-            .eval_string_with_context_mut(&mut context),
-        Ok("a string".to_string())
+        postgres("len(str::trim(name)) > 3"),
+        clause("LENGTH(TRIM(\"name\")) > $1", vec![3.into()])
     );
-    ;
     assert_eq!(
-        build_operator_tree("3.3")
-            .unwrap()
-            .eval_float_with_context_mut(&mut context),
-        Ok(3.3)
+        mysql("len(str::trim(name)) > 3"),
+        clause("CHAR_LENGTH(TRIM(`name`)) > ?", vec![3.into()])
     );
     assert_eq!(
-        build_operator_tree("3")
-            .unwrap()
-            .eval_int_with_context_mut(&mut context),
-        Ok(3)
+        postgres("str::to_lowercase(a) == str::to_uppercase(b)"),
+        clause("LOWER(\"a\") = UPPER(\"b\")", vec![])
     );
     assert_eq!(
-        build_operator_tree("3")
-            .unwrap()
-            .eval_number_with_context_mut(&mut context),
-        Ok(3.0)
+        postgres("max(a, b, 1) < min(c, 2)"),
+        clause(
+            "GREATEST(\"a\", \"b\", $1) < LEAST(\"c\", $2)",
+            vec![1.into(), 2.into()]
+        )
     );
+    // Wildcards of `LIKE` in constants are escaped.
     assert_eq!(
-        build_operator_tree("true")
-            .unwrap()
-            .eval_boolean_with_context_mut(&mut context),
-        Ok(true)
+        postgres("str::starts_with(code, \"10%_\") || str::ends_with(code, \"x\")"),
+        clause(
+            "(\"code\" LIKE $1) OR (\"code\" LIKE $2)",
+            vec!["10\\%\\_%".into(), "%x".into()]
+        )
     );
     assert_eq!(
-        build_operator_tree("3,3")
-            .unwrap()
-            .eval_tuple_with_context_mut(&mut context),
-        Ok(vec![Value::Int(3), Value::Int(3)])
+        postgres("matches_glob(\"*.tx?\", file)"),
+        clause("\"file\" LIKE $1", vec!["%.tx_".into()])
     );
     assert_eq!(
-        build_operator_tree("")
-            .unwrap()
-            .eval_empty_with_context_mut(&mut context),
-        Ok(EMPTY_VALUE)
+        postgres("matches_glob(\"100\\\\*%\", file)"),
+        clause("\"file\" LIKE $1", vec!["100*\\%".into()])
+    );
+    assert_eq!(
+        postgres("matches_glob_ci(\"*.TXT\", file)"),
+        clause("\"file\" ILIKE $1", vec!["%.TXT".into()])
+    );
+    assert_eq!(
+        mysql("matches_glob_ci(\"*.TXT\", file)"),
+        clause("LOWER(`file`) LIKE ?", vec!["%.txt".into()])
     );
-}
 
-#[test]
-fn test_whitespace() {
-    assert!(eval_boolean("2 < = 3").is_err());
-}
+    // Identifiers are quoted per dot-separated part, and quotes in them are escaped.
+    assert_eq!(
+        postgres("user.age > 1"),
+        clause("\"user\".\"age\" > $1", vec![1.into()])
+    );
+    assert_eq!(
+        mysql("user.age > 1"),
+        clause("`user`.`age` > ?", vec![1.into()])
+    );
 
-#[test]
-fn test_assignment() {
-    let mut context = HashMapContext::new();
+    // Unsupported nodes are reported, so that the expression can be evaluated in-process instead.
     assert_eq!(
-        eval_empty_with_context_mut("int = 3", &mut context),
-        Ok(EMPTY_VALUE)
+        postgres("a > 1 && my_function(a)"),
+        Err(UnsupportedNode {
+            kind: OperatorKind::FunctionCall,
+            reason: "the function \"my_function\" has no SQL equivalent".to_string()
+        })
     );
     assert_eq!(
-        eval_empty_with_context_mut("float = 2.0", &mut context),
-        Ok(EMPTY_VALUE)
+        postgres("a = 1; a > 0").unwrap_err().kind,
+        OperatorKind::Chain
     );
     assert_eq!(
-        eval_empty_with_context_mut("tuple = (1,1)", &mut context),
-        Ok(EMPTY_VALUE)
+        postgres("a = 1").unwrap_err().reason,
+        "the operator `=` has no SQL equivalent"
     );
     assert_eq!(
-        eval_empty_with_context_mut("empty = ()", &mut context),
-        Ok(EMPTY_VALUE)
+        postgres("a = 1; a > 0").unwrap_err().reason,
+        "the operator `;` has no SQL equivalent"
     );
+    assert_eq!(postgres("a = 1").unwrap_err().kind, OperatorKind::Assign);
+    // Operators that lack an operand are reported instead of translated.
     assert_eq!(
-        eval_empty_with_context_mut("boolean = false", &mut context),
-        Ok(EMPTY_VALUE)
+        postgres("-"),
+        Err(UnsupportedNode {
+            kind: OperatorKind::Neg,
+            reason: "the operator has 0 operands, which is not the number it takes".to_string()
+        })
     );
-
-    assert_eq!(eval_int_with_context("int", &context), Ok(3));
-    assert_eq!(eval_float_with_context("float", &context), Ok(2.0));
     assert_eq!(
-        eval_tuple_with_context("tuple", &context),
-        Ok(vec![1.into(), 1.into()])
+        postgres("1 * * 2").unwrap_err().reason,
+        "the operator has 1 operands, which is not the number it takes"
     );
-    assert_eq!(eval_empty_with_context("empty", &context), Ok(EMPTY_VALUE));
-    assert_eq!(eval_boolean_with_context("boolean", &context), Ok(false));
-
     assert_eq!(
-        eval_empty_with_context_mut("b = a = 5", &mut context),
-        Ok(EMPTY_VALUE)
+        postgres("(1, 2) == a").unwrap_err().kind,
+        OperatorKind::Tuple
     );
-    assert_eq!(eval_empty_with_context("b", &context), Ok(EMPTY_VALUE));
-}
-
-#[test]
-fn test_expression_chaining() {
-    let mut context = HashMapContext::new();
+    assert_eq!(postgres("").unwrap_err().kind, OperatorKind::Root);
     assert_eq!(
-        eval_int_with_context_mut("a = 5; a = a + 2; a", &mut context),
-        Ok(7)
+        postgres("str::starts_with(a, b)").unwrap_err().reason,
+        "the second argument of str::starts_with must be a string constant"
     );
-}
-
-#[test]
-fn test_strings() {
-    let mut context = HashMapContext::new();
-    assert_eq!(eval("\"string\""), Ok(Value::from("string")));
     assert_eq!(
-        eval_with_context_mut("a = \"a string\"", &mut context),
-        Ok(Value::Empty)
+        postgres("matches_glob(\"[a-z]*\", a)").unwrap_err().reason,
+        "wildcard patterns with character classes have no LIKE equivalent"
     );
     assert_eq!(
-        eval_boolean_with_context("a == \"a string\"", &context),
-        Ok(true)
+        postgres("any_of((1, 2), \">\", a)").unwrap_err().reason,
+        "any_of is only supported with the comparison \"==\""
+    );
+    assert_eq!(
+        postgres("any_of(values, \"==\", a)").unwrap_err().reason,
+        "the first argument of any_of must be a tuple expression"
+    );
+    assert_eq!(
+        postgres("f(x)").unwrap_err().to_string(),
+        "Cannot translate FunctionCall node to SQL: the function \"f\" has no SQL equivalent"
     );
-    assert_eq!(eval("\"a\" + \"b\""), Ok(Value::from("ab")));
-    assert_eq!(eval("\"a\" > \"b\""), Ok(Value::from(false)));
-    assert_eq!(eval("\"a\" < \"b\""), Ok(Value::from(true)));
 }
 
-#[cfg(feature = "serde")]
 #[test]
-fn test_serde() {
-    let strings = ["3", "4+4", "21^(2*2)--3>5||!true"];
-
-    for string in &strings {
-        let manual_tree = build_operator_tree(string).unwrap();
-        let serde_tree: Node = ron::de::from_str(&format!("\"{}\"", string)).unwrap();
-        assert_eq!(manual_tree.eval(), serde_tree.eval());
+fn test_implicit_multiplication() {
+    let config = ParseConfig {
+        implicit_multiplication: true,
+        ..ParseConfig::default()
+    };
+    let context = context_map! {
+        "x" => 3,
+        "y" => 2,
+        "x2" => 10,
+        "e" => 7,
+        "f" => Function::new(Box::new(|argument| Ok(Value::from(argument.as_int()? + 100))))
     }
-}
+    .unwrap();
+    let eval = |expression: &str| {
+        build_operator_tree_with_config(expression, &config)
+            .and_then(|parsed| parsed.tree.eval_with_context(&context))
+    };
 
-#[test]
-fn test_tuple_definitions() {
-    assert_eq!(eval_empty("()"), Ok(()));
-    assert_eq!(eval_int("(3)"), Ok(3));
+    assert_eq!(eval("2x"), Ok(Value::from(6)));
+    assert_eq!(eval("2.5x"), Ok(Value::from(7.5)));
+    assert_eq!(eval("2x + 3(y - 1)"), Ok(Value::from(9)));
+    assert_eq!(eval("2x^2"), Ok(Value::from(18)));
+    assert_eq!(eval("-2x"), Ok(Value::from(-6)));
+    assert_eq!(eval("2f(1)"), Ok(Value::from(202)));
+    assert_eq!(eval("(x)(y)"), Ok(Value::from(6)));
+    assert_eq!(eval("(x + 1)(y)(2)"), Ok(Value::from(16)));
+    // Whitespace does not matter, like everywhere else.
+    assert_eq!(eval("2 x"), Ok(Value::from(6)));
+    assert_eq!(eval("3 (y)"), Ok(Value::from(6)));
+    assert_eq!(eval("(x) (y)"), Ok(Value::from(6)));
+    // Identifiers stay identifiers, and identifiers followed by a parenthesis stay function calls.
+    assert_eq!(eval("x2"), Ok(Value::from(10)));
+    assert_eq!(eval("2x2"), Ok(Value::from(20)));
+    assert_eq!(eval("f(2)"), Ok(Value::from(102)));
+    // The inserted operator binds like a written one, so this is `f(2) * x`.
+    assert_eq!(eval("f 2x"), Ok(Value::from(306)));
     assert_eq!(
-        eval_tuple("(3, 4)"),
-        Ok(vec![Value::from(3), Value::from(4)])
+        eval("x(2)"),
+        Err(EvalexprError::FunctionIdentifierNotFound("x".to_string()))
     );
+    // Literals that are numbers as a whole are not split.
+    assert_eq!(eval("2e3"), Ok(Value::from(2000.0)));
+    assert_eq!(eval("2e-3"), Ok(Value::from(0.002)));
+    assert_eq!(eval("0x1F"), Ok(Value::from(31)));
+    assert_eq!(eval("2e"), Ok(Value::from(14)));
+    // Only numbers and closing parentheses are followed by an implicit multiplication.
     assert_eq!(
-        eval_tuple("2, (5, 6)"),
-        Ok(vec![
-            Value::from(2),
-            Value::from(vec![Value::from(5), Value::from(6)])
-        ])
+        eval("(x)y"),
+        Err(EvalexprError::MissingOperator { span: 3..4 })
     );
-    assert_eq!(eval_tuple("1, 2"), Ok(vec![Value::from(1), Value::from(2)]));
     assert_eq!(
-        eval_tuple("1, 2, 3, 4"),
-        Ok(vec![
-            Value::from(1),
-            Value::from(2),
-            Value::from(3),
-            Value::from(4)
-        ])
+        eval("2 \"s\""),
+        Err(EvalexprError::MissingOperator { span: 2..5 })
     );
     assert_eq!(
-        eval_tuple("(1, 2, 3), 5, 6, (true, false, 0)"),
-        Ok(vec![
-            Value::from(vec![Value::from(1), Value::from(2), Value::from(3)]),
-            Value::from(5),
-            Value::from(6),
-            Value::from(vec![Value::from(true), Value::from(false), Value::from(0)])
-        ])
+        eval("2_x"),
+        Err(EvalexprError::InvalidNumberLiteral("2_x".to_string()))
     );
+
+    // The inserted operator is written out, so the tree can be parsed again without the option.
+    let parsed = build_operator_tree_with_config("2x + 3(y - 1)", &config).unwrap();
+    assert_eq!(parsed.tree.to_minified_string(), "2*x+3*(y-1)");
     assert_eq!(
-        eval_tuple("1, (2)"),
-        Ok(vec![Value::from(1), Value::from(2)])
+        build_operator_tree(&parsed.tree.to_minified_string()).unwrap(),
+        parsed.tree
     );
+
+    // It is off by default, as a missing operator is more often a typo.
+    assert!(matches!(
+        eval_with_context("2x", &context),
+        Err(EvalexprError::VariableIdentifierNotFound { .. })
+    ));
     assert_eq!(
-        eval_tuple("1, ()"),
-        Ok(vec![Value::from(1), Value::from(())])
+        eval_with_context("3(y - 1)", &context),
+        Err(EvalexprError::MissingOperator { span: 1..2 })
     );
     assert_eq!(
-        eval_tuple("1, ((2))"),
-        Ok(vec![Value::from(1), Value::from(2)])
+        eval_with_context("(x)(y)", &context),
+        Err(EvalexprError::MissingOperator { span: 3..4 })
     );
 }
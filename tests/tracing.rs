@@ -0,0 +1,239 @@
+extern crate evalexpr;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+
+#[cfg(feature = "tracing")]
+mod recorder {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// A span or an event with its name and the values of its fields, written like by `Display`.
+    #[derive(Clone, Debug, Default)]
+    pub struct Recorded {
+        pub name: String,
+        pub target: String,
+        pub fields: BTreeMap<String, String>,
+    }
+
+    impl Recorded {
+        pub fn field(&self, name: &str) -> Option<&str> {
+            self.fields.get(name).map(String::as_str)
+        }
+    }
+
+    impl Visit for Recorded {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    /// A subscriber that records all spans and events.
+    #[derive(Clone, Default)]
+    pub struct Recorder {
+        pub spans: Arc<Mutex<Vec<Recorded>>>,
+        pub events: Arc<Mutex<Vec<Recorded>>>,
+    }
+
+    impl Recorder {
+        pub fn spans(&self) -> Vec<Recorded> {
+            self.spans.lock().unwrap().clone()
+        }
+
+        pub fn events(&self) -> Vec<Recorded> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    fn record(metadata: &Metadata) -> Recorded {
+        Recorded {
+            name: metadata.name().to_string(),
+            target: metadata.target().to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes) -> Id {
+            let mut span = record(attributes.metadata());
+            attributes.record(&mut span);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &Id, values: &Record) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut spans[id.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut recorded = record(event.metadata());
+            event.record(&mut recorded);
+            self.events.lock().unwrap().push(recorded);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+}
+
+#[test]
+#[cfg(feature = "tracing")]
+fn test_tracing_evaluation() {
+    use evalexpr::*;
+    use recorder::Recorder;
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert_eq!(eval("1 + 2"), Ok(Value::from(3)));
+        assert_eq!(eval("1 + 2"), Ok(Value::from(3)));
+        assert_eq!(eval("1 +  2"), Ok(Value::from(3)));
+        assert_eq!(
+            eval_with_context_mut("a = 1.5; a", &mut HashMapContext::new()),
+            Ok(Value::from(1.5))
+        );
+    });
+
+    let spans = recorder.spans();
+    assert_eq!(spans.len(), 4);
+    for span in &spans {
+        assert_eq!(span.name, "eval");
+        assert_eq!(span.target, "evalexpr");
+        assert!(span.field("duration_us").is_some());
+        assert_eq!(span.field("error"), None);
+    }
+    assert_eq!(spans[0].field("result_type"), Some("Int"));
+    assert_eq!(spans[3].field("result_type"), Some("Float"));
+
+    // The hash identifies the expression text.
+    assert!(spans[0].field("expression_hash").is_some());
+    assert_eq!(
+        spans[0].field("expression_hash"),
+        spans[1].field("expression_hash")
+    );
+    assert_ne!(
+        spans[0].field("expression_hash"),
+        spans[2].field("expression_hash")
+    );
+
+    // Each evaluation emits an event with the same fields.
+    let events = recorder.events();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[0].field("message"), Some("finished"));
+    assert_eq!(events[0].field("result_type"), Some("Int"));
+    assert!(events[0].field("duration_us").is_some());
+}
+
+#[test]
+#[cfg(feature = "tracing")]
+fn test_tracing_errors() {
+    use evalexpr::*;
+    use recorder::Recorder;
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert!(eval("1 + true").is_err());
+        assert!(eval("(1").is_err());
+    });
+
+    let spans = recorder.spans();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].field("error"), Some("ExpectedNumberOrString"));
+    assert_eq!(spans[0].field("result_type"), None);
+    // Errors of parsing are reported as well.
+    assert_eq!(spans[1].field("error"), Some("UnmatchedLBrace"));
+
+    let events = recorder.events();
+    assert_eq!(events[0].field("message"), Some("failed"));
+    assert_eq!(events[0].field("error"), Some("ExpectedNumberOrString"));
+}
+
+#[test]
+#[cfg(feature = "tracing")]
+fn test_tracing_nodes() {
+    use evalexpr::*;
+    use recorder::Recorder;
+
+    let tree = build_operator_tree("x * 2").unwrap();
+    let context = context_map! { "x" => 3 }.unwrap();
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert_eq!(tree.eval_with_context(&context), Ok(Value::from(6)));
+        assert_eq!(
+            build_operator_tree("x*2")
+                .unwrap()
+                .eval_with_context_mut(&mut context_map! { "x" => 3 }.unwrap()),
+            Ok(Value::from(6))
+        );
+        assert_eq!(tree.eval_int_with_context(&context), Ok(6));
+    });
+
+    // The hash of a tree does not depend on the whitespace of its expression.
+    let spans = recorder.spans();
+    assert_eq!(spans.len(), 3);
+    assert_eq!(spans[0].name, "eval");
+    assert_eq!(spans[0].field("result_type"), Some("Int"));
+    assert_eq!(
+        spans[0].field("expression_hash"),
+        spans[1].field("expression_hash")
+    );
+    // The typed evaluation methods are instrumented as well.
+    assert_eq!(
+        spans[0].field("expression_hash"),
+        spans[2].field("expression_hash")
+    );
+}
+
+#[test]
+#[cfg(all(feature = "tracing", feature = "builtin_string"))]
+fn test_tracing_function_calls() {
+    use evalexpr::*;
+    use recorder::Recorder;
+
+    let context = context_map! {
+        "double" => Function::new(Box::new(|argument| Ok(Value::from(argument.as_int()? * 2)))),
+        "fail" => Function::new(Box::new(|_| Err(EvalexprError::CustomMessage("failed".to_string()))))
+    }
+    .unwrap();
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert_eq!(
+            eval_with_context("double(2) + len(\"ab\")", &context),
+            Ok(Value::from(6))
+        );
+        assert!(eval_with_context("fail()", &context).is_err());
+    });
+
+    let spans = recorder.spans();
+    let calls: Vec<_> = spans.iter().filter(|span| span.name == "call").collect();
+    assert_eq!(calls.len(), 3);
+    assert_eq!(calls[0].field("function"), Some("double"));
+    assert_eq!(calls[0].field("builtin"), Some("false"));
+    assert_eq!(calls[0].field("result_type"), Some("Int"));
+    assert_eq!(calls[1].field("function"), Some("len"));
+    assert_eq!(calls[1].field("builtin"), Some("true"));
+    assert_eq!(calls[2].field("function"), Some("fail"));
+    assert_eq!(calls[2].field("error"), Some("InFunctionCall"));
+
+    let evaluations: Vec<_> = spans.iter().filter(|span| span.name == "eval").collect();
+    assert_eq!(evaluations.len(), 2);
+    assert_eq!(evaluations[1].field("error"), Some("InFunctionCall"));
+}